@@ -0,0 +1,27 @@
+//! Miller-Rabin against Baillie-PSW over the whole u64 range, and over u128
+//! up to the first strong pseudoprime to the default witnesses
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primality_test_comparison::algorithms::{bpsw, miller_rabin};
+
+// 399165290221 × 798330580441, the smallest composite passing all 12 witnesses
+const PSI_12: u128 = 318665857834031151167461;
+
+fuzz_target!(|n: u128| {
+    let small = n as u64;
+    assert_eq!(
+        miller_rabin::is_prime(small),
+        bpsw::is_prime(small),
+        "{}",
+        small
+    );
+
+    if n < PSI_12 {
+        let expected = bpsw::is_prime_u128(n);
+        assert_eq!(miller_rabin::is_prime_u128(n), expected, "{}", n);
+        assert_eq!(miller_rabin::is_prime_big(&n.into()), expected, "{}", n);
+        assert_eq!(bpsw::is_prime_big(&n.into()), expected, "{}", n);
+    }
+});
@@ -0,0 +1,37 @@
+//! Every registered algorithm against Miller-Rabin, at every input width.
+//! Inputs stay below 2^10 so the arbitrary-precision AKS path keeps the
+//! fuzzer fast; new algorithms are covered as soon as they are registered.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primality_test_comparison::algorithms::config::AlgorithmConfig;
+use primality_test_comparison::algorithms::{Registry, miller_rabin};
+
+fuzz_target!(|n: u16| {
+    let n = (n % (1 << 10)) as u64;
+    let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+    let expected = miller_rabin::is_prime(n);
+
+    for test in registry.iter() {
+        if !test.supported_range().contains(&n) {
+            continue;
+        }
+
+        assert_eq!(test.is_prime(n), expected, "{} on {}", test.name(), n);
+        assert_eq!(
+            test.is_prime_u128(n as u128),
+            expected,
+            "{} (u128) on {}",
+            test.name(),
+            n
+        );
+        assert_eq!(
+            test.is_prime_big(&n.into()),
+            Some(expected),
+            "{} (big) on {}",
+            test.name(),
+            n
+        );
+    }
+});
@@ -0,0 +1,46 @@
+//! Sieves and the `Primes` iterator over arbitrary ranges, against Miller-Rabin
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primality_test_comparison::Primes;
+use primality_test_comparison::algorithms::{miller_rabin, sieve_of_eratosthenes};
+
+fuzz_target!(|input: (u16, u16, u8)| {
+    let (start, len, segment_size) = input;
+    let (start, end) = (start as u64, start as u64 + len as u64);
+
+    let expected: Vec<u64> = (start..=end)
+        .filter(|&n| miller_rabin::is_prime(n))
+        .collect();
+    let in_range =
+        |primes: Vec<u64>| -> Vec<u64> { primes.into_iter().filter(|&p| p >= start).collect() };
+
+    assert_eq!(
+        in_range(sieve_of_eratosthenes::sieve(end)),
+        expected,
+        "sieve [{}, {}]",
+        start,
+        end
+    );
+    assert_eq!(
+        in_range(sieve_of_eratosthenes::sieve_segmented(
+            end,
+            segment_size as usize
+        )),
+        expected,
+        "segmented sieve [{}, {}] with segments of {}",
+        start,
+        end,
+        segment_size
+    );
+    assert_eq!(
+        Primes::starting_at(start)
+            .take_while(|&p| p <= end)
+            .collect::<Vec<_>>(),
+        expected,
+        "Primes [{}, {}]",
+        start,
+        end
+    );
+});
@@ -0,0 +1,35 @@
+//! Every trial division variant and wheel against Miller-Rabin
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primality_test_comparison::algorithms::trial_division::{self, Wheel};
+use primality_test_comparison::algorithms::{
+    miller_rabin, trial_division_newton, trial_division_sqrt,
+};
+
+fuzz_target!(|n: u32| {
+    let n = n as u64;
+    let expected = miller_rabin::is_prime(n);
+
+    assert_eq!(trial_division::is_prime(n), expected, "{}", n);
+    assert_eq!(trial_division_sqrt::is_prime(n), expected, "{}", n);
+    assert_eq!(trial_division_newton::is_prime(n), expected, "{}", n);
+
+    for wheel in [Wheel::Two, Wheel::Six, Wheel::Thirty] {
+        assert_eq!(
+            trial_division::is_prime_with_wheel(n, wheel),
+            expected,
+            "{} ({:?})",
+            n,
+            wheel
+        );
+        assert_eq!(
+            trial_division::is_prime_with_wheel_u128(n as u128, wheel),
+            expected,
+            "{} ({:?}, u128)",
+            n,
+            wheel
+        );
+    }
+});
@@ -0,0 +1,42 @@
+/*
+ * C interface to primality-test-comparison, built with `--features ffi`.
+ * Link against the cdylib (libprimality_test_comparison.so / .dylib / .dll).
+ */
+
+#ifndef PRIMALITY_TEST_COMPARISON_H
+#define PRIMALITY_TEST_COMPARISON_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returned by ptc_is_prime for an id with no algorithm */
+#define PTC_UNKNOWN_ALGORITHM (-1)
+
+/* Returned by ptc_is_prime for an input outside the algorithm's supported range */
+#define PTC_UNSUPPORTED_INPUT (-2)
+
+/* Number of algorithms, valid ids are 0..ptc_algorithm_count() */
+uint32_t ptc_algorithm_count(void);
+
+/* Name of an algorithm (e.g. "miller-rabin"), NULL for an unknown id. Do not free. */
+const char *ptc_algorithm_name(uint32_t algorithm_id);
+
+/* 1 if n is prime, 0 if composite, or a negative PTC_* error code */
+int32_t ptc_is_prime(uint32_t algorithm_id, uint64_t n);
+
+/*
+ * Writes the primes up to n (inclusive) into out, at most capacity of them,
+ * and returns how many primes there are in total. Pass out = NULL to size
+ * the buffer first.
+ */
+size_t ptc_sieve(uint64_t n, uint64_t *out, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PRIMALITY_TEST_COMPARISON_H */
@@ -0,0 +1,517 @@
+//! Which primality test or proof suits a given n
+//!
+//! [`analyze`] collects what decides the choice: the size of n, its small
+//! factors, whether it is a perfect power, how far n - 1 and n + 1 factor
+//! over small primes, and whether n has a special form with a dedicated
+//! test. [`Analysis::recommendations`] turns those facts into advice, best
+//! first, each with its reasoning. Tests this crate implements are checked
+//! against a [`Registry`], so only algorithms it holds (and that support n)
+//! are recommended; proofs it does not implement are named as such.
+//!
+//! The n - 1 and n + 1 proofs need a factored part of at least n^(1/2)
+//! (Pocklington-Lehmer, Morrison) or n^(1/3) (Brillhart-Lehmer-Selfridge),
+//! so the fraction of the bits factored is what is reported.
+
+use std::fmt;
+
+use num_bigint::BigUint;
+
+use super::{Registry, lucas_lehmer, small_primes};
+
+// Largest number the 12 default Miller-Rabin witnesses are proven
+// deterministic for: the smallest strong pseudoprime to all of them,
+// 318665857834031151167461, less one
+const DETERMINISTIC_MR_BOUND: u128 = 318_665_857_834_031_151_167_460;
+
+/// A number divided by every prime below [`small_primes::LIMIT`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialFactorization {
+    /// The small prime factors with their exponents, in increasing order
+    pub factors: Vec<(u64, u32)>,
+    /// What is left once they are divided out
+    pub cofactor: BigUint,
+    /// Share of the bits of the number the small factors account for
+    pub fraction: f64,
+}
+
+/// Divides `m` by the primes below [`small_primes::LIMIT`]. Zero is left
+/// as it is.
+pub fn factor_small(m: &BigUint) -> PartialFactorization {
+    let mut factors = Vec::new();
+    let mut cofactor = m.clone();
+    if cofactor != BigUint::ZERO {
+        for &p in small_primes::all() {
+            let mut exponent = 0;
+            while &cofactor % p == BigUint::ZERO {
+                cofactor /= p;
+                exponent += 1;
+            }
+            if exponent > 0 {
+                factors.push((p, exponent));
+            }
+        }
+    }
+
+    let total = log2(m);
+    let fraction = if total > 0.0 {
+        1.0 - log2(&cofactor) / total
+    } else {
+        1.0
+    };
+    PartialFactorization {
+        factors,
+        cofactor,
+        fraction,
+    }
+}
+
+/// A form of n with a test of its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecialForm {
+    /// 2^p - 1
+    Mersenne { p: u64 },
+    /// 2^(2^m) + 1
+    Fermat { m: u32 },
+    /// k·2^m + 1 with odd k < 2^m
+    Proth { k: BigUint, m: u64 },
+    /// k·2^m - 1 with odd k < 2^m
+    Riesel { k: BigUint, m: u64 },
+}
+
+impl SpecialForm {
+    /// The form of `n`, if it has one. Fermat and Mersenne numbers are
+    /// reported as such rather than as the Proth and Riesel numbers with
+    /// k = 1 they also are.
+    pub fn of(n: &BigUint) -> Option<SpecialForm> {
+        if *n < BigUint::from(3u32) {
+            return None;
+        }
+
+        // n = k·2^m + 1 and n = k·2^m - 1, k odd
+        let split = |m: BigUint| {
+            let shift = m.trailing_zeros().unwrap_or(0);
+            (m >> shift, shift)
+        };
+        let below_power = |k: &BigUint, m: u64| k.bits() <= m;
+
+        let (k, m) = split(n - 1u32);
+        if below_power(&k, m) {
+            return Some(if k == BigUint::from(1u32) && m.is_power_of_two() {
+                SpecialForm::Fermat { m: m.ilog2() }
+            } else {
+                SpecialForm::Proth { k, m }
+            });
+        }
+        let (k, m) = split(n + 1u32);
+        if below_power(&k, m) {
+            return Some(if k == BigUint::from(1u32) {
+                SpecialForm::Mersenne { p: m }
+            } else {
+                SpecialForm::Riesel { k, m }
+            });
+        }
+        None
+    }
+}
+
+impl fmt::Display for SpecialForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecialForm::Mersenne { p } => write!(f, "Mersenne number 2^{} - 1", p),
+            SpecialForm::Fermat { m } => write!(f, "Fermat number F{} = 2^{} + 1", m, 1u64 << m),
+            SpecialForm::Proth { k, m } => write!(f, "Proth number {}·2^{} + 1", k, m),
+            SpecialForm::Riesel { k, m } => write!(f, "Riesel number {}·2^{} - 1", k, m),
+        }
+    }
+}
+
+/// What is known about n before running any test
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub n: BigUint,
+    pub bits: u64,
+    pub digits: usize,
+    /// The smallest prime below [`small_primes::LIMIT`] dividing n, n
+    /// itself if it is such a prime
+    pub smallest_factor: Option<u64>,
+    /// (r, b) with n = r^b for a prime b, looked for only when n has no
+    /// small factor
+    pub perfect_power: Option<(BigUint, u32)>,
+    pub minus_one: PartialFactorization,
+    pub plus_one: PartialFactorization,
+    pub form: Option<SpecialForm>,
+}
+
+/// Inspects `n`; see the [module documentation](self)
+pub fn analyze(n: &BigUint) -> Analysis {
+    let smallest_factor = small_primes::all()
+        .iter()
+        .copied()
+        .find(|&p| n % p == BigUint::ZERO);
+
+    // Without a factor below 2^16 a root r^b = n is at least 2^16, so b is
+    // at most bits / 16; a composite b = cd would also make n an r^c
+    let bits = n.bits();
+    let perfect_power = match smallest_factor {
+        None if bits > 1 => small_primes::up_to(bits / 16)
+            .iter()
+            .map(|&b| b as u32)
+            .find_map(|b| {
+                let root = n.nth_root(b);
+                (root.pow(b) == *n).then_some((root, b))
+            }),
+        _ => None,
+    };
+
+    Analysis {
+        n: n.clone(),
+        bits,
+        digits: n.to_string().len(),
+        smallest_factor,
+        perfect_power,
+        minus_one: factor_small(&if bits == 0 { BigUint::ZERO } else { n - 1u32 }),
+        plus_one: factor_small(&(n + 1u32)),
+        form: SpecialForm::of(n),
+    }
+}
+
+/// How a recommended method can be run
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Availability {
+    /// A registered algorithm, run with `test --algorithms`
+    Registered,
+    /// A command of its own, or a flag of `test`
+    Command,
+    /// Not implemented in this crate
+    External,
+}
+
+/// A method worth running on n, and why
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Recommendation {
+    pub method: &'static str,
+    pub availability: Availability,
+    /// Whether a positive answer proves n prime, rather than only a
+    /// probable prime
+    pub proves: bool,
+    pub reason: String,
+}
+
+impl Analysis {
+    /// Why n is already known not to be prime, None if it may be
+    pub fn composite_reason(&self) -> Option<String> {
+        if self.bits <= 1 {
+            return Some(format!("{} is below 2", self.n));
+        }
+        if let Some(p) = self.smallest_factor.filter(|&p| BigUint::from(p) != self.n) {
+            return Some(format!("{} divides n", p));
+        }
+        if let Some((root, b)) = &self.perfect_power {
+            return Some(format!("n = {}^{}", root, b));
+        }
+        match &self.form {
+            Some(SpecialForm::Mersenne { p }) => u32::try_from(*p)
+                .ok()
+                .and_then(lucas_lehmer::exponent_factor)
+                .map(|a| format!("2^{} - 1 divides n, as {} divides {}", a, a, p)),
+            // 2^m + 1 with an odd factor q of m is divisible by 2^(m/q) + 1
+            Some(SpecialForm::Proth { k, m }) if *k == BigUint::from(1u32) => {
+                let q = (3..=*m).step_by(2).find(|q| m.is_multiple_of(*q))?;
+                Some(format!("2^{} + 1 divides n, as {} divides {}", m / q, q, m))
+            }
+            _ => None,
+        }
+    }
+
+    /// The methods worth running on n, best first, only the ones
+    /// `registry` holds among this crate's algorithms. Empty if n is
+    /// already known not to be prime.
+    pub fn recommendations(&self, registry: &Registry) -> Vec<Recommendation> {
+        if self.composite_reason().is_some() {
+            return Vec::new();
+        }
+
+        let mut general = Vec::new();
+        let registered = |method: &'static str, proves: bool, reason: String| {
+            let test = registry.get(method)?;
+            let supported = match u128::try_from(&self.n) {
+                Ok(n) => test.supported_range_u128().contains(&n),
+                // Only the arbitrary-precision tests are ever picked past u128
+                Err(_) => true,
+            };
+            supported.then_some(Recommendation {
+                method,
+                availability: Availability::Registered,
+                proves,
+                reason,
+            })
+        };
+
+        if self.smallest_factor.is_some() {
+            general.extend(registered(
+                "trial-division",
+                true,
+                "n is a prime below 2^16, found among the primes trial division tries".into(),
+            ));
+        } else if self.bits <= 64 {
+            general.extend(registered(
+                "miller-rabin",
+                true,
+                "n < 2^64, where the 12 default witnesses are proven deterministic, so \
+                 12 modular exponentiations prove it"
+                    .into(),
+            ));
+        } else if u128::try_from(&self.n).is_ok_and(|n| n <= DETERMINISTIC_MR_BOUND) {
+            general.extend(registered(
+                "miller-rabin",
+                true,
+                "n < 3.18·10^23, below the smallest strong pseudoprime to all 12 default \
+                 witnesses, so they still prove it"
+                    .into(),
+            ));
+        } else {
+            general.extend(registered(
+                "bpsw",
+                false,
+                format!(
+                    "n has {} bits, past where any fixed witness set is proven; Baillie-PSW \
+                     costs about 3 Miller-Rabin rounds and has no known counterexample",
+                    self.bits
+                ),
+            ));
+        }
+
+        let mut special = Vec::new();
+        if let Some(form) = &self.form {
+            special.push(self.form_test(form));
+        }
+
+        // Small n are settled by the general tests in microseconds; beyond
+        // them a dedicated test beats the general ones
+        let proven = general.first().is_some_and(|r| r.proves);
+        let mut advice = if proven {
+            general.extend(special);
+            general
+        } else {
+            special.extend(general);
+            special
+        };
+        if !advice.iter().any(|r| r.proves) {
+            advice.push(self.factored_proof(registry));
+        }
+        advice
+    }
+
+    fn form_test(&self, form: &SpecialForm) -> Recommendation {
+        match form {
+            SpecialForm::Mersenne { p } => Recommendation {
+                method: "lucas-lehmer",
+                availability: Availability::Command,
+                proves: true,
+                reason: format!(
+                    "n = 2^{} - 1: the Lucas-Lehmer test takes {} squarings mod n, reduced \
+                     with shifts and adds (run `lucas-lehmer {}`)",
+                    p,
+                    p.saturating_sub(2),
+                    p
+                ),
+            },
+            SpecialForm::Fermat { m } => Recommendation {
+                method: "pepin",
+                availability: Availability::External,
+                proves: true,
+                reason: format!(
+                    "n = F{}: Pépin's test, n is prime if and only if 3^((n - 1)/2) ≡ -1 \
+                     (mod n), one modular exponentiation",
+                    m
+                ),
+            },
+            SpecialForm::Proth { k, m } => Recommendation {
+                method: "proth",
+                availability: Availability::Command,
+                proves: true,
+                reason: format!(
+                    "n = {}·2^{} + 1 with {} < 2^{}: by Proth's theorem n is prime if and \
+                     only if a^((n - 1)/2) ≡ -1 (mod n) for a quadratic non-residue a \
+                     (run `test n --proth`)",
+                    k, m, k, m
+                ),
+            },
+            SpecialForm::Riesel { k, m } => Recommendation {
+                method: "lucas-lehmer-riesel",
+                availability: Availability::External,
+                proves: true,
+                reason: format!(
+                    "n = {}·2^{} - 1 with {} < 2^{}: the Lucas-Lehmer-Riesel test takes {} \
+                     squarings mod n",
+                    k,
+                    m,
+                    k,
+                    m,
+                    m.saturating_sub(2)
+                ),
+            },
+        }
+    }
+
+    // A proof from the factored parts of n - 1 and n + 1, or ECPP when
+    // neither goes far enough. Of these Pocklington-Lehmer is registered up
+    // to u128 and ECPP up to u64.
+    fn factored_proof(&self, registry: &Registry) -> Recommendation {
+        let percent = |f: &PartialFactorization| 100.0 * f.fraction;
+        let (method, reason) = if self.minus_one.fraction >= 0.5 {
+            (
+                "pocklington",
+                format!(
+                    "{:.0}% of n - 1 factors over primes below 2^16, past the half a \
+                     Pocklington-Lehmer n - 1 proof needs",
+                    percent(&self.minus_one)
+                ),
+            )
+        } else if self.plus_one.fraction >= 0.5 {
+            (
+                "morrison",
+                format!(
+                    "{:.0}% of n + 1 factors over primes below 2^16, past the half a Morrison \
+                     n + 1 proof with Lucas sequences needs",
+                    percent(&self.plus_one)
+                ),
+            )
+        } else if self.minus_one.fraction.max(self.plus_one.fraction) >= 1.0 / 3.0 {
+            (
+                "brillhart-lehmer-selfridge",
+                format!(
+                    "{:.0}% of n - 1 and {:.0}% of n + 1 factor over primes below 2^16, \
+                     enough for the third a Brillhart-Lehmer-Selfridge proof needs",
+                    percent(&self.minus_one),
+                    percent(&self.plus_one)
+                ),
+            )
+        } else {
+            (
+                "ecpp",
+                format!(
+                    "only {:.0}% of n - 1 and {:.0}% of n + 1 factor over primes below 2^16, \
+                     too little for an n ± 1 proof; elliptic curve primality proving needs \
+                     no factorization",
+                    percent(&self.minus_one),
+                    percent(&self.plus_one)
+                ),
+            )
+        };
+        let registered = registry.get(method).is_some_and(|test| {
+            u128::try_from(&self.n).is_ok_and(|n| test.supported_range_u128().contains(&n))
+        });
+        Recommendation {
+            method,
+            availability: if registered {
+                Availability::Registered
+            } else {
+                Availability::External
+            },
+            proves: true,
+            reason,
+        }
+    }
+}
+
+// log2(n) from its top 64 bits, -inf for zero
+fn log2(n: &BigUint) -> f64 {
+    let shift = n.bits().saturating_sub(64);
+    let top = (n >> shift).iter_u64_digits().next().unwrap_or(0);
+    (top as f64).log2() + shift as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::config::AlgorithmConfig;
+
+    fn methods(n: &BigUint) -> Vec<&'static str> {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        analyze(n)
+            .recommendations(&registry)
+            .iter()
+            .map(|r| r.method)
+            .collect()
+    }
+
+    #[test]
+    fn test_factor_small() {
+        let factored = factor_small(&BigUint::from(720u32));
+        assert_eq!(factored.factors, [(2, 4), (3, 2), (5, 1)]);
+        assert_eq!(factored.cofactor, BigUint::from(1u32));
+        assert_eq!(factored.fraction, 1.0);
+
+        // 2^10 · 65537
+        let half = factor_small(&BigUint::from(1024u64 * 65_537));
+        assert_eq!(half.factors, [(2, 10)]);
+        assert_eq!(half.cofactor, BigUint::from(65_537u32));
+        assert!((half.fraction - 10.0 / 26.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_special_forms() {
+        let of = |n: u64| SpecialForm::of(&BigUint::from(n));
+        assert_eq!(of(127), Some(SpecialForm::Mersenne { p: 7 }));
+        assert_eq!(of(65_537), Some(SpecialForm::Fermat { m: 4 }));
+        // 13 = 3·2^2 + 1 and 23 = 3·2^3 - 1
+        assert!(matches!(of(13), Some(SpecialForm::Proth { m: 2, .. })));
+        assert!(matches!(of(23), Some(SpecialForm::Riesel { m: 3, .. })));
+        assert_eq!(of(1_000_003), None);
+        assert_eq!(of(2), None);
+    }
+
+    #[test]
+    fn test_composite_reasons() {
+        let reason = |n: BigUint| analyze(&n).composite_reason();
+        assert_eq!(reason(BigUint::from(91u32)).as_deref(), Some("7 divides n"));
+        assert_eq!(reason(BigUint::from(7u32)), None);
+        assert!(reason(BigUint::from(1u32)).is_some());
+
+        let square = BigUint::from(65_537u32).pow(2);
+        assert_eq!(reason(square.clone()).as_deref(), Some("n = 65537^2"));
+        assert!(methods(&square).is_empty());
+
+        // 2^67 - 1 = 193707721 · 761838257287, no factor below 2^16, but
+        // prime exponent: only the tests can tell
+        assert_eq!(reason(lucas_lehmer::mersenne(67)), None);
+    }
+
+    #[test]
+    fn test_recommendations() {
+        assert_eq!(methods(&BigUint::from(65_521u32)), ["trial-division"]);
+        assert_eq!(methods(&BigUint::from(1_000_003u32)), ["miller-rabin"]);
+        // Small Mersenne primes get the general test first
+        assert_eq!(
+            methods(&lucas_lehmer::mersenne(61)),
+            ["miller-rabin", "lucas-lehmer"]
+        );
+        // Past the proven bounds the dedicated test comes first
+        assert_eq!(
+            methods(&lucas_lehmer::mersenne(127)),
+            ["lucas-lehmer", "bpsw"]
+        );
+
+        // 10^40 + 121, a prime of no special form: a probable prime test,
+        // then a proof
+        let p = BigUint::from(10u32).pow(40) + 121u32;
+        let advice = methods(&p);
+        assert_eq!(advice[0], "bpsw");
+        assert_eq!(advice.len(), 2);
+
+        // 2·7^30·29 + 1 has a smooth n - 1 and fits in a u128, so the
+        // registered Pocklington-Lehmer test proves it
+        let p = BigUint::from(7u32).pow(30) * 58u32 + 1u32;
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        let advice = analyze(&p).recommendations(&registry);
+        assert_eq!(advice[1].method, "pocklington");
+        assert_eq!(advice[1].availability, Availability::Registered);
+    }
+}
@@ -1,14 +1,69 @@
-// AKS (Agrawal-Kayal-Saxena) Primality Test
-// A deterministic polynomial-time primality test
-
+//! AKS (Agrawal-Kayal-Saxena) Primality Test
+//! A deterministic polynomial-time primality test
+//!
+//! The congruence rounds of step 5 are independent of each other, so with
+//! `aks.threads` they are spread over several threads, each taking every
+//! T-th value of a. The verdict and the evidence are the same as with one.
+//!
+//! Each round multiplies polynomials of r coefficients modulo X^r - 1. From
+//! `aks.karatsuba-threshold` coefficients up, the product is computed by
+//! Karatsuba's method instead of schoolbook multiplication, which trades
+//! a quarter of the coefficient products for extra additions at each split.
+
+use std::cell::RefCell;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+use crate::error::Result;
+use crate::math::{euler_phi, gcd, multiplicative_order, perfect_power};
+
+/// Returns true if `n` is prime, proven by the AKS algorithm
 pub fn is_prime(n: u64) -> bool {
+    evidence(n).is_none()
+}
+
+/// Smallest polynomial length multiplied by Karatsuba's method by default
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+/// How the congruence rounds are run; none of it changes the outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    /// Threads the rounds are spread over
+    pub threads: usize,
+    /// Smallest polynomial length multiplied by Karatsuba's method, 0 for
+    /// schoolbook multiplication at every length
+    pub karatsuba_threshold: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            threads: 1,
+            karatsuba_threshold: KARATSUBA_THRESHOLD,
+        }
+    }
+}
+
+/// The step at which AKS rejects `n`, or None if it proves `n` prime
+pub fn evidence(n: u64) -> Option<Evidence> {
+    evidence_with(n, Settings::default())
+}
+
+/// [`evidence`] with the congruence rounds run as `settings` say
+pub fn evidence_with(n: u64, settings: Settings) -> Option<Evidence> {
     if n <= 1 {
-        return false;
+        return Some(Evidence::BelowTwo);
     }
 
     // Step 1: Check if n is a perfect power (n = a^b for b > 1)
-    if is_perfect_power(n) {
-        return false;
+    if let Some((base, exponent)) = perfect_power(n) {
+        return Some(Evidence::PerfectPower { base, exponent });
     }
 
     // Step 2: Find the smallest r such that ord_r(n) > log²(n)
@@ -18,66 +73,150 @@ pub fn is_prime(n: u64) -> bool {
     for a in 2..=r.min(n - 1) {
         let g = gcd(a, n);
         if g > 1 && g < n {
-            return false;
+            return Some(Evidence::Divisor(g));
         }
     }
 
     // Step 4: If n ≤ r, we're done
     if n <= r {
-        return true;
+        return None;
     }
 
     // Step 5: Polynomial congruence test
     // For a = 1 to floor(sqrt(φ(r)) * log2(n))
     let limit = ((euler_phi(r) as f64).sqrt() * (n as f64).log2()).floor() as u64;
 
-    for a in 1..=limit {
-        if !check_polynomial_congruence(n, r, a) {
-            return false;
-        }
+    let threshold = settings.karatsuba_threshold;
+    first_failing_round(limit, settings.threads, |a| {
+        check_polynomial_congruence(n, r, a, threshold)
+    })
+    .map(|a| Evidence::PolynomialCongruence { r, a })
+}
+
+// The smallest a in 1..=limit for which `holds` is false. Thread t of
+// `threads` checks a = t + 1, t + 1 + threads, ... and gives up once past a
+// failing a found by any thread, which no larger a can replace.
+fn first_failing_round(
+    limit: u64,
+    threads: usize,
+    holds: impl Fn(u64) -> bool + Sync,
+) -> Option<u64> {
+    if threads <= 1 {
+        return (1..=limit).find(|&a| !holds(a));
     }
 
-    true
+    let first = AtomicU64::new(u64::MAX);
+    thread::scope(|scope| {
+        for t in 0..threads as u64 {
+            let (first, holds) = (&first, &holds);
+            scope.spawn(move || {
+                for a in (t + 1..=limit).step_by(threads) {
+                    if a > first.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if !holds(a) {
+                        first.fetch_min(a, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    Some(first.into_inner()).filter(|&a| a != u64::MAX)
 }
 
-// Check if n is a perfect power
-fn is_perfect_power(n: u64) -> bool {
-    if n == 1 {
-        return true;
-    }
+/// [`is_prime`], describing the outcome of each AKS step to `step`, with the
+/// time spent on the search for r, the gcd checks, and every congruence round
+pub fn trace(n: u64, step: TraceFn<'_>) -> bool {
+    trace_with(n, Settings::default(), step)
+}
 
-    // Check for each possible exponent b from 2 to log2(n)
-    let max_b = (n as f64).log2() as u32;
+/// [`trace`] with the multiplication of `settings`. The rounds always run
+/// one after the other, so that each is timed on its own.
+pub fn trace_with(n: u64, settings: Settings, step: TraceFn<'_>) -> bool {
+    if n <= 1 {
+        step(format_args!("{} is below 2, so it is not prime", n));
+        return false;
+    }
 
-    for b in 2..=max_b {
-        let a = (n as f64).powf(1.0 / b as f64).round() as u64;
+    if let Some((base, exponent)) = perfect_power(n) {
+        step(format_args!(
+            "step 1: {} = {}^{} is a perfect power: composite",
+            n, base, exponent
+        ));
+        return false;
+    }
+    step(format_args!("step 1: {} is not a perfect power", n));
 
-        // Check a and a±1 to account for floating point errors
-        for candidate in [a.saturating_sub(1), a, a.saturating_add(1)] {
-            if candidate > 1 && pow_checked(candidate, b) == Some(n) {
-                return true;
-            }
+    let start = Instant::now();
+    let r = find_smallest_r(n);
+    step(format_args!(
+        "step 2: r = {} is the smallest r with ord_r(n) = {} > log²(n), found in {:.2?}",
+        r,
+        multiplicative_order(n, r),
+        start.elapsed()
+    ));
+
+    let start = Instant::now();
+    for a in 2..=r.min(n - 1) {
+        let g = gcd(a, n);
+        if g > 1 && g < n {
+            step(format_args!(
+                "step 3: gcd({}, {}) = {} after {} gcd checks: composite",
+                a,
+                n,
+                g,
+                a - 1
+            ));
+            return false;
         }
     }
+    step(format_args!(
+        "step 3: gcd(a, n) = 1 for every a ≤ {}, {} gcd checks in {:.2?}",
+        r.min(n - 1),
+        r.min(n - 1).saturating_sub(1),
+        start.elapsed()
+    ));
 
-    false
-}
+    if n <= r {
+        step(format_args!("step 4: n ≤ r: prime"));
+        return true;
+    }
 
-// Compute a^b with overflow checking
-fn pow_checked(mut base: u64, mut exp: u32) -> Option<u64> {
-    let mut result = 1u64;
+    let limit = ((euler_phi(r) as f64).sqrt() * (n as f64).log2()).floor() as u64;
+    step(format_args!(
+        "step 5: checking (X + a)^n ≡ X^n + a (mod X^{} - 1, n) for a = 1..={}, {} rounds",
+        r, limit, limit
+    ));
 
-    while exp > 0 {
-        if exp % 2 == 1 {
-            result = result.checked_mul(base)?;
-        }
-        exp /= 2;
-        if exp > 0 {
-            base = base.checked_mul(base)?;
+    let mut total = Duration::ZERO;
+    for a in 1..=limit {
+        let start = Instant::now();
+        let holds = check_polynomial_congruence(n, r, a, settings.karatsuba_threshold);
+        let elapsed = start.elapsed();
+        total += elapsed;
+
+        if !holds {
+            step(format_args!(
+                "a = {}: the congruence fails in {:.2?}: composite after {} rounds in {:.2?}",
+                a, elapsed, a, total
+            ));
+            return false;
         }
+        step(format_args!(
+            "a = {}: the congruence holds in {:.2?}",
+            a, elapsed
+        ));
     }
 
-    Some(result)
+    step(format_args!(
+        "every congruence holds: prime after {} rounds in {:.2?} ({:.2?} per round)",
+        limit,
+        total,
+        total / limit.max(1) as u32
+    ));
+    true
 }
 
 // Find smallest r such that ord_r(n) > log²(n)
@@ -100,187 +239,415 @@ fn find_smallest_r(n: u64) -> u64 {
     unreachable!()
 }
 
-// Compute the multiplicative order of n modulo r
-fn multiplicative_order(n: u64, r: u64) -> u64 {
-    let n_mod = n % r;
-    let mut current = n_mod;
+// Check polynomial congruence: (X + a)^n ≡ X^n + a (mod X^r - 1, n)
+fn check_polynomial_congruence(n: u64, r: u64, a: u64, karatsuba_threshold: usize) -> bool {
+    // Represent polynomial as coefficients array
+    // We only need to track coefficients modulo n and degree modulo r
+    let ring = PolyRing {
+        r: r as usize,
+        n,
+        karatsuba_threshold,
+    };
+
+    SCRATCH.with_borrow_mut(|scratch| {
+        scratch.resize(&ring);
+
+        // Start with (X + a)
+        scratch.base[0] = a % n;
+        scratch.base[1] = 1;
+
+        // Compute (X + a)^n mod (X^r - 1, n) using binary exponentiation
+        let result = poly_pow_mod(n, &ring, scratch);
+
+        // Check if result equals X^n + a mod (X^r - 1, n)
+        let n_mod_r = (n % r) as usize;
+        let expected_a = a % n;
+
+        result.iter().enumerate().all(|(i, &coeff)| {
+            let expected = if i == 0 {
+                expected_a
+            } else if i == n_mod_r {
+                1
+            } else {
+                0
+            };
+            coeff == expected
+        })
+    })
+}
+
+// The polynomials of an exponentiation, kept per thread so the congruence
+// rounds of a long run reuse them instead of allocating two fresh vectors
+// per multiplication
+#[derive(Default)]
+struct PolyScratch {
+    result: Vec<u64>,
+    base: Vec<u64>,
+    product: Vec<u64>,
+    // The linear product and the temporaries of Karatsuba's method
+    work: Vec<u64>,
+}
 
-    for k in 1..=r {
-        if current == 1 {
-            return k;
+impl PolyScratch {
+    // Zeroes the polynomials as r coefficients, reallocating only to grow
+    fn resize(&mut self, ring: &PolyRing) {
+        for poly in [&mut self.result, &mut self.base, &mut self.product] {
+            poly.clear();
+            poly.resize(ring.r, 0);
         }
-        current = (current * n_mod) % r;
+        self.work.resize(ring.work_len(), 0);
     }
+}
 
-    r
+thread_local! {
+    static SCRATCH: RefCell<PolyScratch> = RefCell::default();
 }
 
-// Compute Euler's totient function φ(n)
-fn euler_phi(n: u64) -> u64 {
-    let mut result = n;
-    let mut n_mut = n;
-    let mut p = 2;
+// Polynomial exponentiation: base^exp mod (X^r - 1, n), with the base
+// in `scratch.base`. The returned result lives in `scratch`.
+fn poly_pow_mod<'s>(mut exp: u64, ring: &PolyRing, scratch: &'s mut PolyScratch) -> &'s [u64] {
+    let PolyScratch {
+        result,
+        base,
+        product,
+        work,
+    } = scratch;
+    result[0] = 1; // Start with polynomial 1
 
-    while p * p <= n_mut {
-        if n_mut % p == 0 {
-            while n_mut % p == 0 {
-                n_mut /= p;
-            }
-            result -= result / p;
+    while exp > 0 {
+        if exp % 2 == 1 {
+            ring.mul(result, base, product, work);
+            mem::swap(result, product);
+        }
+        exp /= 2;
+        if exp > 0 {
+            ring.mul(base, base, product, work);
+            mem::swap(base, product);
         }
-        p += if p == 2 { 1 } else { 2 };
-    }
-
-    if n_mut > 1 {
-        result -= result / n_mut;
     }
 
     result
 }
 
-// Greatest common divisor
-fn gcd(mut a: u64, mut b: u64) -> u64 {
-    while b != 0 {
-        let temp = b;
-        b = a % b;
-        a = temp;
+// Polynomials modulo (X^r - 1, n), with coefficients below n < 2^63
+struct PolyRing {
+    r: usize,
+    n: u64,
+    karatsuba_threshold: usize,
+}
+
+impl PolyRing {
+    fn uses_karatsuba(&self) -> bool {
+        self.karatsuba_threshold > 0 && self.r >= self.karatsuba_threshold.max(2)
+    }
+
+    // Scratch words `mul` needs: the linear product of 2r coefficients and
+    // Karatsuba's temporaries
+    fn work_len(&self) -> usize {
+        if !self.uses_karatsuba() {
+            return 0;
+        }
+        let mut len = 2 * self.r;
+        let mut m = self.r;
+        while m >= self.karatsuba_threshold.max(2) {
+            let high = m - m / 2;
+            len += 4 * high;
+            m = high;
+        }
+        len
+    }
+
+    // Polynomial multiplication, written to `out`
+    fn mul(&self, a: &[u64], b: &[u64], out: &mut [u64], work: &mut [u64]) {
+        let (r, n) = (self.r, self.n);
+
+        if !self.uses_karatsuba() {
+            out.fill(0);
+            for (i, &a_i) in a.iter().enumerate() {
+                for (j, &b_j) in b.iter().enumerate() {
+                    if a_i == 0 || b_j == 0 {
+                        continue;
+                    }
+
+                    let coeff = ((a_i as u128 * b_j as u128) % n as u128) as u64;
+                    let pos = (i + j) % r;
+
+                    out[pos] = (out[pos] + coeff) % n;
+                }
+            }
+            return;
+        }
+
+        // The linear product, whose terms from X^r wrap around to X^0
+        let (linear, temp) = work.split_at_mut(2 * r);
+        self.karatsuba(a, b, linear, temp);
+        for (i, coeff) in out.iter_mut().enumerate() {
+            *coeff = add_mod(linear[i], linear[i + r], n);
+        }
+    }
+
+    // The linear product of `a` and `b` (of equal length m) into `out`,
+    // 2m coefficients of which the last is 0. Halves are split off until
+    // they are shorter than the threshold: with a = a0 + a1 X^h and b alike,
+    // the middle term a0 b1 + a1 b0 is (a0 + a1)(b0 + b1) - a0 b0 - a1 b1.
+    fn karatsuba(&self, a: &[u64], b: &[u64], out: &mut [u64], temp: &mut [u64]) {
+        let (m, n) = (a.len(), self.n);
+
+        if m < self.karatsuba_threshold.max(2) {
+            out.fill(0);
+            for (i, &a_i) in a.iter().enumerate() {
+                if a_i == 0 {
+                    continue;
+                }
+                for (j, &b_j) in b.iter().enumerate() {
+                    let product = ((a_i as u128 * b_j as u128) % n as u128) as u64;
+                    out[i + j] = add_mod(out[i + j], product, n);
+                }
+            }
+            return;
+        }
+
+        // The high half is the longer one when m is odd
+        let low = m / 2;
+        let high = m - low;
+        let (a0, a1) = a.split_at(low);
+        let (b0, b1) = b.split_at(low);
+
+        let (z0, z2) = out.split_at_mut(2 * low);
+        self.karatsuba(a0, b0, z0, temp);
+        self.karatsuba(a1, b1, z2, temp);
+
+        let (sums, temp) = temp.split_at_mut(2 * high);
+        let (middle, temp) = temp.split_at_mut(2 * high);
+        let (sum_a, sum_b) = sums.split_at_mut(high);
+        for i in 0..high {
+            sum_a[i] = add_mod(a1[i], a0.get(i).copied().unwrap_or(0), n);
+            sum_b[i] = add_mod(b1[i], b0.get(i).copied().unwrap_or(0), n);
+        }
+        self.karatsuba(sum_a, sum_b, middle, temp);
+
+        for (i, coeff) in middle.iter_mut().enumerate() {
+            let z0_i = z0.get(i).copied().unwrap_or(0);
+            *coeff = sub_mod(sub_mod(*coeff, z0_i, n), z2[i], n);
+        }
+        for (i, &coeff) in middle.iter().enumerate() {
+            out[low + i] = add_mod(out[low + i], coeff, n);
+        }
     }
-    a
 }
 
-// Check polynomial congruence: (X + a)^n ≡ X^n + a (mod X^r - 1, n)
-fn check_polynomial_congruence(n: u64, r: u64, a: u64) -> bool {
-    // Represent polynomial as coefficients array
-    // We only need to track coefficients modulo n and degree modulo r
+fn add_mod(x: u64, y: u64, n: u64) -> u64 {
+    let sum = x + y;
+    if sum >= n { sum - n } else { sum }
+}
 
-    let r_usize = r as usize;
-    let mut poly = vec![0u64; r_usize];
+fn sub_mod(x: u64, y: u64, n: u64) -> u64 {
+    if x >= y { x - y } else { x + n - y }
+}
 
-    // Start with (X + a)
-    poly[0] = a % n;
-    poly[1] = 1;
+/// Returns true if `n` is prime, proven by the AKS algorithm over
+/// arbitrary-precision integers.
+/// Inputs within the u64 implementation's range take the fixed-width path;
+/// beyond that the polynomial step is correct but extremely slow.
+pub fn is_prime_big(n: &BigUint) -> bool {
+    is_prime_big_with(n, Settings::default())
+}
 
-    // Compute (X + a)^n mod (X^r - 1, n) using binary exponentiation
-    let result = poly_pow_mod(&poly, n, r, n);
+/// [`is_prime_big`] with the congruence rounds run as `settings` say. Beyond
+/// the u64 range the coefficients are BigUints, always multiplied schoolbook.
+pub fn is_prime_big_with(n: &BigUint, settings: Settings) -> bool {
+    match u64::try_from(n) {
+        Ok(n) if n <= u64::MAX >> 1 => evidence_with(n, settings).is_none(),
+        _ => is_prime_big_unchecked(n, settings.threads),
+    }
+}
 
-    // Check if result equals X^n + a mod (X^r - 1, n)
-    let n_mod_r = (n % r) as usize;
-    let expected_a = a % n;
+// The same steps as is_prime with BigUint coefficients. The bit length
+// stands in for log2(n): it is an upper bound, which keeps every step valid.
+fn is_prime_big_unchecked(n: &BigUint, threads: usize) -> bool {
+    if *n <= BigUint::from(1u32) {
+        return false;
+    }
 
-    for i in 0..r_usize {
-        let expected = if i == 0 {
-            expected_a
-        } else if i == n_mod_r {
-            1
-        } else {
-            0
-        };
+    // Step 1: Check if n is a perfect power
+    let bits = n.bits();
+    for b in 2..=bits as u32 {
+        if n.nth_root(b).pow(b) == *n {
+            return false;
+        }
+    }
 
-        if result[i] != expected {
+    // Step 2: Find the smallest r such that ord_r(n) > log²(n)
+    let reduce = |m: u64| (n % m).iter_u64_digits().next().unwrap_or(0);
+    let r = (2..)
+        .find(|&r| {
+            let n_mod_r = reduce(r);
+            gcd(n_mod_r, r) == 1 && multiplicative_order(n_mod_r, r) > bits * bits
+        })
+        .unwrap();
+
+    // Step 3: Check if 1 < gcd(a, n) < n for all a ≤ r
+    for a in 2..=r {
+        let g = gcd(a, reduce(a));
+        if g > 1 && BigUint::from(g) < *n {
             return false;
         }
     }
 
-    true
+    // Step 4: If n ≤ r, we're done
+    if *n <= BigUint::from(r) {
+        return true;
+    }
+
+    // Step 5: Polynomial congruence test
+    let limit = ((euler_phi(r) as f64).sqrt() * bits as f64).floor() as u64;
+    first_failing_round(limit, threads, |a| check_polynomial_congruence_big(n, r, a)).is_none()
 }
 
-// Polynomial exponentiation: poly^exp mod (X^r - 1, n)
-fn poly_pow_mod(poly: &[u64], mut exp: u64, r: u64, n: u64) -> Vec<u64> {
+// (X + a)^n ≡ X^n + a (mod X^r - 1, n) with BigUint coefficients
+fn check_polynomial_congruence_big(n: &BigUint, r: u64, a: u64) -> bool {
     let r_usize = r as usize;
-    let mut result = vec![0u64; r_usize];
-    result[0] = 1; // Start with polynomial 1
+    let zero = BigUint::from(0u32);
 
-    let mut base = poly.to_vec();
+    let mut base = vec![zero.clone(); r_usize];
+    base[0] = BigUint::from(a) % n;
+    base[1] = BigUint::from(1u32);
 
-    while exp > 0 {
-        if exp % 2 == 1 {
-            result = poly_mul_mod(&result, &base, r, n);
-        }
-        exp /= 2;
-        if exp > 0 {
-            base = poly_mul_mod(&base, &base, r, n);
+    let mut result = vec![zero.clone(); r_usize];
+    result[0] = BigUint::from(1u32);
+
+    // Left-to-right binary exponentiation over the bits of n
+    for bit in (0..n.bits()).rev() {
+        result = poly_mul_mod_big(&result, &result, r_usize, n);
+        if n.bit(bit) {
+            result = poly_mul_mod_big(&result, &base, r_usize, n);
         }
     }
 
-    result
+    let n_mod_r = (n % r).iter_u64_digits().next().unwrap_or(0) as usize;
+    result.iter().enumerate().all(|(i, coeff)| {
+        let expected = if i == 0 {
+            BigUint::from(a) % n
+        } else if i == n_mod_r {
+            BigUint::from(1u32)
+        } else {
+            zero.clone()
+        };
+        *coeff == expected
+    })
 }
 
-// Polynomial multiplication modulo (X^r - 1, n)
-fn poly_mul_mod(a: &[u64], b: &[u64], r: u64, n: u64) -> Vec<u64> {
-    let r_usize = r as usize;
-    let mut result = vec![0u64; r_usize];
+// Polynomial multiplication modulo (X^r - 1, n) with BigUint coefficients
+fn poly_mul_mod_big(a: &[BigUint], b: &[BigUint], r: usize, n: &BigUint) -> Vec<BigUint> {
+    let mut result = vec![BigUint::from(0u32); r];
 
-    for i in 0..r_usize {
-        for j in 0..r_usize {
-            if a[i] == 0 || b[j] == 0 {
+    for (i, a_i) in a.iter().enumerate() {
+        if a_i.bits() == 0 {
+            continue;
+        }
+        for (j, b_j) in b.iter().enumerate() {
+            if b_j.bits() == 0 {
                 continue;
             }
-
-            let coeff = ((a[i] as u128 * b[j] as u128) % n as u128) as u64;
-            let pos = (i + j) % r_usize;
-
-            result[pos] = (result[pos] + coeff) % n;
+            result[(i + j) % r] += a_i * b_j;
         }
     }
 
+    for coeff in result.iter_mut() {
+        *coeff %= n;
+    }
+
     result
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub struct Aks {
+    settings: Settings,
+}
 
-    #[test]
-    fn test_perfect_power() {
-        assert!(is_perfect_power(4));
-        assert!(is_perfect_power(8));
-        assert!(is_perfect_power(16));
-        assert!(is_perfect_power(27));
-        assert!(is_perfect_power(64));
+impl Aks {
+    pub fn with_settings(settings: Settings) -> Self {
+        Aks { settings }
+    }
 
-        assert!(!is_perfect_power(10));
-        assert!(!is_perfect_power(15));
-        assert!(!is_perfect_power(20));
-        assert!(!is_perfect_power(33));
-        assert!(!is_perfect_power(124));
+    /// Reads the `aks.threads` knob (0 for one thread per core) and
+    /// `aks.karatsuba-threshold`
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        let threads = match config.get("aks.threads")?.unwrap_or(1) {
+            0 => thread::available_parallelism().map_or(1, |cores| cores.get()),
+            threads => threads,
+        };
+        let karatsuba_threshold = config
+            .get("aks.karatsuba-threshold")?
+            .unwrap_or(KARATSUBA_THRESHOLD);
+
+        Ok(Aks::with_settings(Settings {
+            threads,
+            karatsuba_threshold,
+        }))
     }
+}
 
-    #[test]
-    fn test_pow_checked() {
-        assert!(pow_checked(2, 10) == Some(1024));
-        assert!(pow_checked(3, 5) == Some(243));
-        assert!(pow_checked(10, 16) == Some(10_000_000_000_000_000));
+impl PrimalityTest for Aks {
+    fn name(&self) -> &str {
+        "aks"
+    }
 
-        assert!(pow_checked(10, 20) == None); // Overflow
-        assert!(pow_checked(23, 15) == None); // Overflow
-        assert!(pow_checked(2, 67) == None); // Overflow
+    fn description(&self) -> &str {
+        "Deterministic polynomial-time test via polynomial congruences"
     }
 
-    #[test]
-    fn test_gcd() {
-        assert_eq!(gcd(48, 18), 6);
-        assert_eq!(gcd(101, 10), 1);
-        assert_eq!(gcd(54, 24), 6);
-        assert_eq!(gcd(17, 34), 17);
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        // Coefficient sums are reduced mod n in u64 and must not overflow
+        0..=(u64::MAX >> 1)
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        evidence_with(n, self.settings).is_none()
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence_with(n, self.settings)
+    }
 
-        // https://en.wikipedia.org/wiki/AKS_primality_test#Example_1:_n_=_31_is_prime
-        assert_eq!(gcd(29, 31), 1);
-        assert_eq!(gcd(28, 31), 1);
-        assert_eq!(gcd(2, 31), 1);
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with(n, self.settings, step))
     }
 
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(is_prime_big_with(n, self.settings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     #[test]
-    fn test_euler_phi() {
-        assert_eq!(euler_phi(1), 1);
-        assert_eq!(euler_phi(2), 1);
-        assert_eq!(euler_phi(3), 2);
-        assert_eq!(euler_phi(4), 2);
-        assert_eq!(euler_phi(5), 4);
-        assert_eq!(euler_phi(6), 2);
-        assert_eq!(euler_phi(7), 6);
-        assert_eq!(euler_phi(8), 4);
-        assert_eq!(euler_phi(9), 6);
-        assert_eq!(euler_phi(10), 4);
+    fn test_evidence() {
+        assert_eq!(evidence(1), Some(Evidence::BelowTwo));
+        assert_eq!(
+            evidence(1 << 10),
+            Some(Evidence::PerfectPower {
+                base: 2,
+                exponent: 10
+            })
+        );
+        assert_eq!(evidence(91), Some(Evidence::Divisor(7)));
+        assert_eq!(evidence(97), None);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(trace(31, &mut |step| steps.push(step.to_string())));
+
+        // r = 29, and ⌊√φ(29) · log2(31)⌋ = 26 congruence rounds
+        assert!(steps[1].starts_with("step 2: r = 29 "), "{}", steps[1]);
+        assert!(steps[2].contains(", 28 gcd checks in "), "{}", steps[2]);
+        assert!(steps[3].ends_with(", 26 rounds"), "{}", steps[3]);
+        let rounds = steps.iter().filter(|step| step.starts_with("a = ")).count();
+        assert_eq!(rounds, 26);
+        assert!(steps.last().unwrap().contains("after 26 rounds in "));
     }
 
     #[test]
@@ -294,10 +661,100 @@ mod tests {
 
     #[test]
     fn test_check_polynomial_congruence() {
-        assert!(check_polynomial_congruence(5, 3, 1));
-        assert!(check_polynomial_congruence(7, 4, 2));
-        assert!(check_polynomial_congruence(11, 5, 3));
-        assert!(check_polynomial_congruence(13, 6, 4));
+        for threshold in [0, 2, KARATSUBA_THRESHOLD] {
+            assert!(check_polynomial_congruence(5, 3, 1, threshold));
+            assert!(check_polynomial_congruence(7, 4, 2, threshold));
+            assert!(check_polynomial_congruence(11, 5, 3, threshold));
+            assert!(check_polynomial_congruence(13, 6, 4, threshold));
+
+            // The thread's scratch shrinks back for a smaller r
+            assert!(!check_polynomial_congruence(15, 4, 1, threshold));
+            assert!(check_polynomial_congruence(5, 3, 1, threshold));
+        }
+    }
+
+    fn multiply(ring: &PolyRing, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut out = vec![u64::MAX; ring.r];
+        let mut work = vec![u64::MAX; ring.work_len()];
+        ring.mul(a, b, &mut out, &mut work);
+        out
+    }
+
+    #[test]
+    fn test_poly_mul_mod() {
+        // (1 + 2X)(3 + X^2) = 3 + 6X + X^2 + 2X^3 ≡ 5 + 6X + X^2 (mod X^3 - 1, 7)
+        for karatsuba_threshold in [0, 2] {
+            let ring = PolyRing {
+                r: 3,
+                n: 7,
+                karatsuba_threshold,
+            };
+            assert_eq!(multiply(&ring, &[1, 2, 0], &[3, 0, 1]), [5, 6, 1]);
+        }
+    }
+
+    #[test]
+    fn test_karatsuba_agrees_with_schoolbook() {
+        let mut random = crate::random::RandomSource::new(3);
+        let n = (u64::MAX >> 1) - 24; // Coefficients near the 2^63 bound
+
+        for r in (2..40).chain([63, 64, 65, 200]) {
+            let a: Vec<u64> = (0..r).map(|_| random.in_range(0..=n - 1)).collect();
+            let b: Vec<u64> = (0..r).map(|_| random.in_range(0..=n - 1)).collect();
+            let ring = |karatsuba_threshold| PolyRing {
+                r,
+                n,
+                karatsuba_threshold,
+            };
+
+            let schoolbook = multiply(&ring(0), &a, &b);
+            for threshold in [2, 3, 5, 16, KARATSUBA_THRESHOLD] {
+                assert_eq!(multiply(&ring(threshold), &a, &b), schoolbook, "r = {r}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_agrees_with_u64() {
+        for n in 0..100u64 {
+            assert_eq!(
+                is_prime_big_unchecked(&BigUint::from(n), 1),
+                is_prime(n),
+                "{n}"
+            );
+        }
+
+        // Perfect powers beyond u64
+        let cube = BigUint::from(4294967291u64).pow(3);
+        assert!(!is_prime_big(&cube));
+        assert!(!is_prime_big_unchecked(&BigUint::from(561u64), 4));
+        assert!(is_prime_big_unchecked(&BigUint::from(97u64), 4));
+    }
+
+    #[test]
+    fn test_threads_agree_with_serial() {
+        // Composites that survive steps 1-4 are rejected by the smallest a
+        // whatever the thread count
+        for n in (0..200u64).chain([1029105]) {
+            let serial = evidence(n);
+            for threads in [2, 5] {
+                let settings = Settings {
+                    threads,
+                    ..Settings::default()
+                };
+                assert_eq!(evidence_with(n, settings), serial, "{n}");
+            }
+        }
+
+        assert_eq!(
+            first_failing_round(100, 4, |a| a % 7 != 0 && a % 5 != 0),
+            Some(5)
+        );
+        assert_eq!(first_failing_round(100, 4, |_| true), None);
+        assert_eq!(first_failing_round(0, 4, |_| false), None);
+
+        let config = AlgorithmConfig::from_pairs(&[("aks.threads".into(), "0".into())]).unwrap();
+        assert!(Aks::from_config(&config).unwrap().settings.threads >= 1);
     }
 
     #[test]
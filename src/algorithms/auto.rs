@@ -0,0 +1,406 @@
+//! `auto`: hands each number to the implementation presumed fastest for its
+//! bit length
+//!
+//! | Bits      | Backend                                                  |
+//! |-----------|----------------------------------------------------------|
+//! | up to 16  | Lookup in a bitmap of the primes below 2^16              |
+//! | 17 to 20  | Trial division, by at most 512 odd divisors              |
+//! | 21 to 32  | Miller-Rabin with the bases 2, 7 and 61                  |
+//! | 33 and up | Baillie-PSW, also beyond u64                             |
+//!
+//! A [`Profile`] written by `tune` replaces this table with the algorithms
+//! measured fastest on the machine, up to the largest bit length it covers
+//! (set with `--algo-config auto.profile=PATH`).
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::miller_rabin::MillerRabin;
+use crate::algorithms::trial_division::TrialDivision;
+use crate::algorithms::{
+    Evidence, PrimalityTest, Registry, TraceFn, bpsw, miller_rabin, small_primes, trial_division,
+};
+use crate::error::{Error, Result};
+
+/// Numbers below this are looked up rather than tested
+pub const LOOKUP_LIMIT: u64 = small_primes::LIMIT;
+
+// A backend and the largest bit length it is used for
+struct Route {
+    max_bits: u32,
+    // Name shown for the backend, e.g. "miller-rabin (deterministic32)"
+    label: String,
+    test: Arc<dyn PrimalityTest>,
+}
+
+/// Size-based dispatch, see the [module docs](self)
+pub struct Auto {
+    // By increasing max_bits; the last route takes everything larger
+    routes: Vec<Route>,
+}
+
+impl Auto {
+    /// The built-in dispatch table
+    pub fn new() -> Result<Self> {
+        let deterministic32 =
+            miller_rabin::witness_preset("deterministic32").expect("a built-in preset");
+        // Built here rather than on the first lookup, which is timed
+        small_primes::all();
+
+        Ok(Auto {
+            routes: vec![
+                Route {
+                    max_bits: 16,
+                    label: "lookup".to_string(),
+                    test: Arc::new(PrimeTable),
+                },
+                Route {
+                    max_bits: 20,
+                    label: "trial-division".to_string(),
+                    test: Arc::new(TrialDivision::from_config(&AlgorithmConfig::default())?),
+                },
+                Route {
+                    max_bits: 32,
+                    label: "miller-rabin (deterministic32)".to_string(),
+                    test: Arc::new(MillerRabin::with_witnesses(deterministic32.to_vec())),
+                },
+                Route {
+                    max_bits: 128,
+                    label: "bpsw".to_string(),
+                    test: Arc::new(bpsw::Bpsw),
+                },
+            ],
+        })
+    }
+
+    /// The built-in table, or the profile set with `auto.profile`. Its
+    /// algorithms are looked up in `registry`.
+    pub fn from_config(config: &AlgorithmConfig, registry: &Registry) -> Result<Self> {
+        let auto = Auto::new()?;
+        match config.get::<String>("auto.profile")? {
+            Some(path) => auto.with_profile(&Profile::load(&path)?, registry),
+            None => Ok(auto),
+        }
+    }
+
+    /// Dispatches to the fastest algorithm of each of `profile`'s buckets,
+    /// and by the built-in table above its largest one
+    pub fn with_profile(self, profile: &Profile, registry: &Registry) -> Result<Self> {
+        let mut routes: Vec<Route> = Vec::new();
+        for bucket in &profile.buckets {
+            let Some(fastest) = bucket.fastest() else {
+                continue;
+            };
+            // Neighbouring buckets won by the same algorithm share a route
+            match routes.last_mut() {
+                Some(route) if route.label == fastest => route.max_bits = bucket.max_bits,
+                _ => routes.push(Route {
+                    max_bits: bucket.max_bits,
+                    label: fastest.to_string(),
+                    test: registry.lookup(fastest)?,
+                }),
+            }
+        }
+
+        let profiled = routes.last().map_or(0, |route| route.max_bits);
+        routes.extend(
+            self.routes
+                .into_iter()
+                .filter(|route| route.max_bits > profiled),
+        );
+        Ok(Auto { routes })
+    }
+
+    // The route for a number of `bits` bits
+    fn route(&self, bits: u64) -> &Route {
+        self.routes
+            .iter()
+            .find(|route| bits <= u64::from(route.max_bits))
+            .unwrap_or_else(|| self.routes.last().expect("auto has routes"))
+    }
+
+    fn route_u64(&self, n: u64) -> &Route {
+        self.route(u64::from(u64::BITS - n.leading_zeros()))
+    }
+}
+
+impl PrimalityTest for Auto {
+    fn name(&self) -> &str {
+        "auto"
+    }
+
+    fn description(&self) -> &str {
+        "Picks lookup, trial division, Miller-Rabin, or Baillie-PSW by the size of n"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        self.route_u64(n).test.is_prime(n)
+    }
+
+    fn supported_range_u128(&self) -> RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        let bits = u128::BITS - n.leading_zeros();
+        self.route(u64::from(bits)).test.is_prime_u128(n)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        self.route(n.bits()).test.is_prime_big(n)
+    }
+
+    fn backend(&self, n: &BigUint) -> Option<String> {
+        Some(self.route(n.bits()).label.clone())
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        self.route_u64(n).test.evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let route = self.route_u64(n);
+        step(format_args!(
+            "{} has {} bits: handing it to {}",
+            n,
+            u64::BITS - n.leading_zeros(),
+            route.label
+        ));
+        route.test.trace(n, step)
+    }
+}
+
+/// The fastest algorithm for each bucket of bit lengths on one machine,
+/// measured by `tune`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    /// CPU model name of the machine it was measured on
+    pub cpu_model: Option<String>,
+    /// By increasing bit length
+    pub buckets: Vec<ProfileBucket>,
+}
+
+/// Timings of the numbers from one bit length past the previous bucket's up
+/// to `max_bits`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileBucket {
+    pub max_bits: u32,
+    /// Fastest first; algorithms that were too slow or did not support the
+    /// bucket are left out
+    pub timings: Vec<ProfileTiming>,
+}
+
+/// Mean time one algorithm took per number of a bucket
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileTiming {
+    pub algorithm: String,
+    pub average_nanos: f64,
+}
+
+impl ProfileBucket {
+    pub fn fastest(&self) -> Option<&str> {
+        self.timings.first().map(|timing| timing.algorithm.as_str())
+    }
+}
+
+impl Profile {
+    /// Reads a profile written by [`save`](Self::save)
+    pub fn load(path: &str) -> Result<Profile> {
+        let invalid = |reason: String| Error::InvalidProfile {
+            path: path.to_string(),
+            reason,
+        };
+
+        #[cfg(feature = "serde")]
+        {
+            let json = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => invalid("it does not exist".to_string()),
+                _ => Error::Io(e),
+            })?;
+            let profile: Profile =
+                serde_json::from_str(&json).map_err(|e| invalid(e.to_string()))?;
+            if !profile.buckets.is_sorted_by_key(|bucket| bucket.max_bits) {
+                return Err(invalid("buckets are not by increasing bits".to_string()));
+            }
+            Ok(profile)
+        }
+        #[cfg(not(feature = "serde"))]
+        Err(invalid("reading it needs the serde feature".to_string()))
+    }
+
+    /// Writes the profile as JSON
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+// Looks numbers below LOOKUP_LIMIT up in the bitmap
+struct PrimeTable;
+
+impl PrimalityTest for PrimeTable {
+    fn name(&self) -> &str {
+        "lookup"
+    }
+
+    fn description(&self) -> &str {
+        "Lookup in a bitmap of the primes below 2^16"
+    }
+
+    fn supported_range(&self) -> RangeInclusive<u64> {
+        0..=LOOKUP_LIMIT - 1
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        small_primes::is_prime(n).expect("n is below the lookup limit")
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        trial_division::evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let is_prime = self.is_prime(n);
+        step(format_args!(
+            "{} is {}in the table of primes below {}",
+            n,
+            if is_prime { "" } else { "not " },
+            LOOKUP_LIMIT
+        ));
+        Some(is_prime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        let auto = Auto::new().unwrap();
+
+        for n in (0..1 << 21).chain((1 << 32) - 1000..(1 << 32) + 1000) {
+            assert_eq!(auto.is_prime(n), miller_rabin::is_prime(n), "{}", n);
+        }
+        for n in [u64::MAX - 58, u64::MAX, 3215031751, 3825123056546413051] {
+            assert_eq!(auto.is_prime(n), miller_rabin::is_prime(n), "{}", n);
+        }
+        assert!(auto.is_prime_u128(u128::MAX - 158));
+        assert_eq!(
+            auto.is_prime_big(&((BigUint::from(1u32) << 521) - 1u32)),
+            Some(true)
+        );
+    }
+
+    fn bucket(max_bits: u32, algorithms: &[&str]) -> ProfileBucket {
+        ProfileBucket {
+            max_bits,
+            timings: algorithms
+                .iter()
+                .enumerate()
+                .map(|(i, algorithm)| ProfileTiming {
+                    algorithm: algorithm.to_string(),
+                    average_nanos: 100.0 * (i + 1) as f64,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        let profile = Profile {
+            cpu_model: None,
+            buckets: vec![
+                bucket(8, &["trial-division-sqrt", "miller-rabin"]),
+                bucket(16, &["miller-rabin", "trial-division-sqrt"]),
+                bucket(24, &["miller-rabin"]),
+                bucket(32, &[]),
+                bucket(40, &["bpsw", "miller-rabin"]),
+            ],
+        };
+        let auto = Auto::new()
+            .unwrap()
+            .with_profile(&profile, &registry)
+            .unwrap();
+        let backend = |n: u64| auto.backend(&BigUint::from(n)).unwrap();
+
+        assert_eq!(backend(251), "trial-division-sqrt");
+        assert_eq!(backend(257), "miller-rabin");
+        assert_eq!(backend((1 << 24) - 3), "miller-rabin");
+        assert_eq!(backend((1 << 40) - 87), "bpsw");
+        // The built-in table takes over past the profile
+        assert_eq!(backend(u64::MAX), "bpsw");
+        assert!(auto.is_prime(257) && !auto.is_prime((1 << 24) - 1));
+
+        let unknown = Profile {
+            cpu_model: None,
+            buckets: vec![bucket(8, &["lucas-lehmer"])],
+        };
+        assert!(matches!(
+            Auto::new().unwrap().with_profile(&unknown, &registry),
+            Err(Error::UnknownAlgorithm { .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_profile_round_trip() {
+        let profile = Profile {
+            cpu_model: Some("test CPU".to_string()),
+            buckets: vec![bucket(8, &["bpsw", "aks"]), bucket(16, &["bpsw"])],
+        };
+        let path = std::env::temp_dir().join(format!("auto-profile-{}.json", std::process::id()));
+        profile.save(&path).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(Profile::load(path).unwrap(), profile);
+
+        std::fs::write(path, "{\"cpu_model\":null,\"buckets\":[]").unwrap();
+        assert!(matches!(
+            Profile::load(path),
+            Err(Error::InvalidProfile { .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+        assert!(matches!(
+            Profile::load(path),
+            Err(Error::InvalidProfile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_routes() {
+        let auto = Auto::new().unwrap();
+        let backend = |n: u64| auto.backend(&BigUint::from(n)).unwrap();
+
+        assert_eq!(backend(0), "lookup");
+        assert_eq!(backend(65_521), "lookup");
+        assert_eq!(backend(65_537), "trial-division");
+        assert_eq!(backend(1_048_573), "trial-division");
+        assert_eq!(backend(1_048_583), "miller-rabin (deterministic32)");
+        assert_eq!(backend(4_294_967_291), "miller-rabin (deterministic32)");
+        assert_eq!(backend(4_294_967_311), "bpsw");
+        assert_eq!(auto.backend(&(BigUint::from(1u32) << 600)).unwrap(), "bpsw");
+
+        assert_eq!(auto.evidence(91), Some(Evidence::Divisor(7)));
+        let mut steps = Vec::new();
+        assert_eq!(
+            auto.trace(97, &mut |step| steps.push(step.to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            steps,
+            [
+                "97 has 7 bits: handing it to lookup",
+                "97 is in the table of primes below 65536"
+            ]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Baillie-PSW: a base-2 strong probable prime test followed by a strong
+//! Lucas probable prime test with Selfridge's parameters
+
+use num_bigint::BigUint;
+
+use crate::algorithms::strong_lucas::{
+    is_strong_lucas_probable_prime, is_strong_lucas_probable_prime_big,
+};
+use crate::algorithms::{
+    Evidence, PrimalityTest, TraceFn, miller_rabin, small_primes, strong_lucas,
+};
+
+// Number of primes used to reject small factors before the probable prime
+// tests: 2 to 47
+const SMALL_PRIME_COUNT: usize = 15;
+
+// Every odd composite below this has one of the small primes as a factor
+const SMALL_FACTOR_BOUND: u64 = 53 * 53;
+
+fn factor_base() -> &'static [u64] {
+    small_primes::first(SMALL_PRIME_COUNT)
+}
+
+/// Returns true if `n` is prime.
+/// Deterministic for u64 (no BPSW pseudoprime exists below 2^64).
+pub fn is_prime(n: u64) -> bool {
+    is_prime_u128(n as u128)
+}
+
+/// Returns true if `n` is a BPSW probable prime.
+/// No composite passing the test is known.
+pub fn is_prime_u128(n: u128) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    for &p in factor_base() {
+        let p = p as u128;
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    if n < SMALL_FACTOR_BOUND as u128 {
+        return true;
+    }
+
+    miller_rabin::is_prime_u128_with_witnesses(n, &[2]) && is_strong_lucas_probable_prime(n)
+}
+
+/// The stage at which Baillie-PSW rejects `n`, or None if it accepts `n`
+pub fn evidence(n: u64) -> Option<Evidence> {
+    if n < 2 {
+        return Some(Evidence::BelowTwo);
+    }
+
+    if let Some(&p) = factor_base().iter().find(|&&p| n.is_multiple_of(p)) {
+        return (n != p).then_some(Evidence::Divisor(p));
+    }
+    if n < SMALL_FACTOR_BOUND {
+        return None;
+    }
+
+    if let Some(witness) = miller_rabin::find_witness(n, &[2]) {
+        return Some(Evidence::Witness(witness));
+    }
+    if n.isqrt() * n.isqrt() == n {
+        return Some(Evidence::PerfectPower {
+            base: n.isqrt(),
+            exponent: 2,
+        });
+    }
+
+    (!is_strong_lucas_probable_prime(n as u128)).then_some(Evidence::LucasTest)
+}
+
+/// [`is_prime`], describing each stage to `step`: small prime factors, the
+/// base-2 Miller-Rabin round, Selfridge's parameters and the Lucas test
+pub fn trace(n: u64, step: TraceFn<'_>) -> bool {
+    if n < 2 {
+        step(format_args!("{} is below 2, so it is not prime", n));
+        return false;
+    }
+
+    if let Some(&p) = factor_base().iter().find(|&&p| n.is_multiple_of(p)) {
+        step(format_args!(
+            "{} mod {} = 0: {}",
+            n,
+            p,
+            if n == p { "prime" } else { "composite" }
+        ));
+        return n == p;
+    }
+    step(format_args!("no factor among the primes up to 47"));
+    if n < SMALL_FACTOR_BOUND {
+        step(format_args!("{} < 53², so it is prime", n));
+        return true;
+    }
+
+    step(format_args!("base-2 strong probable prime test:"));
+    if !miller_rabin::trace_with_witnesses(n, &[2], step) {
+        return false;
+    }
+
+    strong_lucas::trace_odd(n, step)
+}
+
+/// Returns true if `n` is a BPSW probable prime, for inputs of any size.
+/// Inputs that fit in a u128 take the fixed-width path.
+pub fn is_prime_big(n: &BigUint) -> bool {
+    if let Ok(n) = u128::try_from(n) {
+        return is_prime_u128(n);
+    }
+
+    if factor_base().iter().any(|&p| (n % p).bits() == 0) {
+        return false;
+    }
+
+    miller_rabin::is_prime_big_with_witnesses(n, &[2]) && is_strong_lucas_probable_prime_big(n)
+}
+
+pub struct Bpsw;
+
+impl PrimalityTest for Bpsw {
+    fn name(&self) -> &str {
+        "bpsw"
+    }
+
+    fn description(&self) -> &str {
+        "Baillie-PSW: base-2 Miller-Rabin plus a strong Lucas test"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128(n)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(is_prime_big(n))
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace(n, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        for n in 0..100_000 {
+            assert_eq!(is_prime(n), miller_rabin::is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_pseudoprimes() {
+        // Strong pseudoprimes to base 2 are caught by the Lucas test
+        for n in [2047, 3277, 4033, 4681, 8321, 3215031751] {
+            assert!(!is_prime(n), "{n}");
+        }
+
+        // Strong Lucas pseudoprimes are caught by the base-2 test
+        for n in [5459, 5777, 10877, 16109, 18971] {
+            assert!(!is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_evidence() {
+        assert_eq!(evidence(0), Some(Evidence::BelowTwo));
+        assert_eq!(evidence(47 * 53), Some(Evidence::Divisor(47)));
+        assert_eq!(evidence(53 * 59), Some(Evidence::Witness(2)));
+        assert_eq!(evidence(5459), Some(Evidence::Witness(2))); // Strong Lucas pseudoprime
+        assert_eq!(evidence(8321), Some(Evidence::LucasTest)); // Strong pseudoprime to base 2
+        assert_eq!(evidence(18446744073709551557), None);
+
+        // Agrees with is_prime
+        for n in 0..10_000 {
+            assert_eq!(evidence(n).is_none(), is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_large_primes() {
+        assert!(is_prime(18446744073709551557)); // Largest prime < 2^64
+        assert!(is_prime_u128(u128::MAX - 158)); // 2^128 - 159
+        assert!(is_prime_u128((1 << 127) - 1));
+        assert!(is_prime_u128(18446744073709551629)); // 2^64 + 13
+    }
+
+    #[test]
+    fn test_big() {
+        let one = BigUint::from(1u32);
+        let m521 = (&one << 521) - 1u32;
+        let m607 = (&one << 607) - 1u32;
+        assert!(is_prime_big(&m521));
+        assert!(is_prime_big(&m607));
+        assert!(!is_prime_big(&(&m521 * &m607)));
+        assert!(!is_prime_big(&(&m521 * &m521))); // Perfect square
+        assert!(!is_prime_big(&((&one << 523) - 1u32)));
+    }
+
+    #[test]
+    fn test_large_composites() {
+        assert!(!is_prime_u128(u128::MAX));
+        assert!(!is_prime_u128(18446744073709551557 * 18446744073709551533));
+        // Strong pseudoprime to every base up to 37
+        assert!(!is_prime_u128(318665857834031151167461));
+    }
+}
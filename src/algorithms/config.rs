@@ -0,0 +1,195 @@
+//! Algorithm-specific configuration
+//! Knobs are passed on the command line as `--algo-config <algorithm>.<knob>=<value>`
+//! (e.g. `miller-rabin.witnesses=2,3,5,7`) and recorded in the result metadata.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// Every supported knob with a short description
+pub const KNOWN_KEYS: &[(&str, &str)] = &[
+    (
+        "aks.threads",
+        "threads the congruence rounds run on, 0 for one per core (default: 1)",
+    ),
+    (
+        "aks.karatsuba-threshold",
+        "smallest polynomial length multiplied by Karatsuba, 0 for never (default: 32)",
+    ),
+    (
+        "auto.profile",
+        "dispatch profile written by `tune`, replacing the built-in table up to its largest bit length",
+    ),
+    (
+        "fermat.bases",
+        "number of bases, the first primes (default: 12, as many as miller-rabin's witnesses)",
+    ),
+    #[cfg(feature = "compact")]
+    (
+        "gaps-lookup.file",
+        "a .gaps file written by `sieve --gaps`, registering the gaps-lookup algorithm",
+    ),
+    #[cfg(feature = "gmp")]
+    (
+        "gmp.reps",
+        "rounds asked of GMP's mpz_probab_prime_p, Baillie-PSW plus reps - 24 Miller-Rabin (default: 30)",
+    ),
+    (
+        "miller-rabin.witnesses",
+        "comma-separated witness bases or a preset: sprp2, deterministic32, deterministic64 (default: the 12 primes up to 37)",
+    ),
+    (
+        "pocklington.bound",
+        "largest prime trial-divided into n - 1 for the proof (default: 65536, every prime below 2^16)",
+    ),
+    (
+        "solovay-strassen.bases",
+        "number of bases, the first primes (default: 12, as many as miller-rabin's witnesses)",
+    ),
+    (
+        "trial-division.wheel",
+        "wheel modulus: 2, 6, or 30 (default: 2)",
+    ),
+    (
+        "sieve-of-eratosthenes.segment-size",
+        "numbers per segment, 0 for a single segment (default: 0)",
+    ),
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AlgorithmConfig {
+    values: BTreeMap<String, String>,
+}
+
+impl AlgorithmConfig {
+    /// Builds a configuration from `key=value` pairs, rejecting unknown keys
+    pub fn from_pairs(pairs: &[(String, String)]) -> Result<Self> {
+        let mut values = BTreeMap::new();
+
+        for (key, value) in pairs {
+            if !KNOWN_KEYS.iter().any(|(known, _)| known == key) {
+                let valid: Vec<&str> = KNOWN_KEYS.iter().map(|(k, _)| *k).collect();
+                return Err(Error::UnknownConfigKey {
+                    key: key.clone(),
+                    valid: valid.join(", "),
+                });
+            }
+            values.insert(key.clone(), value.clone());
+        }
+
+        Ok(AlgorithmConfig { values })
+    }
+
+    /// Parses the value of `key`, returning None if it was not set
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        self.values
+            .get(key)
+            .map(|v| v.trim().parse().map_err(|_| invalid_value(key, v)))
+            .transpose()
+    }
+
+    /// Parses the comma-separated value of `key`, returning None if it was not set
+    pub fn get_list<T: FromStr>(&self, key: &str) -> Result<Option<Vec<T>>> {
+        self.values
+            .get(key)
+            .map(|v| {
+                v.split(',')
+                    .map(|item| item.trim().parse().map_err(|_| invalid_value(key, item)))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Knobs set for the given algorithm, as (key, value) pairs
+    pub fn for_algorithm<'a>(
+        &'a self,
+        algorithm: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.values
+            .iter()
+            .filter(move |(k, _)| {
+                k.strip_prefix(algorithm)
+                    .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+fn invalid_value(key: &str, value: &str) -> Error {
+    Error::InvalidConfigValue {
+        key: key.to_string(),
+        value: value.to_string(),
+        hint: None,
+    }
+}
+
+/// Parses a single `key=value` command line argument
+pub fn parse_key_value(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", s))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pairs: &[(&str, &str)]) -> Result<AlgorithmConfig> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        AlgorithmConfig::from_pairs(&pairs)
+    }
+
+    #[test]
+    fn test_parse_key_value() {
+        assert_eq!(
+            parse_key_value("miller-rabin.witnesses=2,3"),
+            Ok(("miller-rabin.witnesses".to_string(), "2,3".to_string()))
+        );
+        assert!(parse_key_value("miller-rabin.witnesses").is_err());
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        assert!(config(&[("miller-rabin.rounds", "3")]).is_err());
+    }
+
+    #[test]
+    fn test_get() {
+        let config = config(&[
+            ("miller-rabin.witnesses", "2, 3,5"),
+            ("trial-division.wheel", "30"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            config.get_list::<u64>("miller-rabin.witnesses").unwrap(),
+            Some(vec![2, 3, 5])
+        );
+        assert_eq!(config.get::<u64>("trial-division.wheel").unwrap(), Some(30));
+        assert_eq!(
+            config
+                .get::<usize>("sieve-of-eratosthenes.segment-size")
+                .unwrap(),
+            None
+        );
+        assert!(config.get::<u8>("miller-rabin.witnesses").is_err());
+    }
+
+    #[test]
+    fn test_for_algorithm() {
+        let config = config(&[
+            ("miller-rabin.witnesses", "2"),
+            ("trial-division.wheel", "6"),
+        ])
+        .unwrap();
+
+        let keys: Vec<(&str, &str)> = config.for_algorithm("trial-division").collect();
+        assert_eq!(keys, vec![("trial-division.wheel", "6")]);
+        assert_eq!(config.for_algorithm("trial-division-sqrt").count(), 0);
+    }
+}
@@ -0,0 +1,539 @@
+//! Datasets of numbers with known primality, read from files at runtime
+//!
+//! Where [`selfcheck`](super::selfcheck) keeps a few dozen numbers in the
+//! binary, these are large enough to catch the regressions a short table
+//! misses: the first million primes, every strong pseudoprime to base 2 and
+//! every Carmichael number below 10^9. They live in the `data` folder of the
+//! repository, or wherever `$PTC_DATA_DIR` points.
+//!
+//! The pseudoprime files hold one number per line, `#` starting a comment.
+//! The prime table is gzipped bytes, each half the gap from the previous odd
+//! number of the table, starting from 1; 2 is implied.
+//!
+//! Larger public lists, the OEIS b-files in [`DOWNLOADS`], are downloaded
+//! rather than bundled (with the `fetch` feature) into `datasets` under the
+//! [sieve cache](crate::cache) directory. Next to each goes its SHA-256,
+//! recorded at download time since the b-files are extended now and then,
+//! and a list whose file no longer matches it is refused.
+
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use crate::algorithms::PrimalityTest;
+use crate::cache::SieveCache;
+use crate::{Error, Result};
+
+/// The public lists that can be downloaded to check against, each extending
+/// one kind of dataset
+pub const DOWNLOADS: [Download; 4] = [
+    Download {
+        name: "oeis-a000040",
+        dataset: Dataset::Primes,
+        description: "The first primes (OEIS A000040)",
+        url: "https://oeis.org/A000040/b000040.txt",
+    },
+    Download {
+        name: "oeis-a001262",
+        dataset: Dataset::StrongPseudoprimes,
+        description: "Strong pseudoprimes to base 2 (OEIS A001262)",
+        url: "https://oeis.org/A001262/b001262.txt",
+    },
+    Download {
+        name: "oeis-a014233",
+        dataset: Dataset::StrongPseudoprimes,
+        description: "Smallest strong pseudoprimes to the first k prime bases (OEIS A014233)",
+        url: "https://oeis.org/A014233/b014233.txt",
+    },
+    Download {
+        name: "oeis-a002997",
+        dataset: Dataset::Carmichael,
+        description: "Carmichael numbers (OEIS A002997)",
+        url: "https://oeis.org/A002997/b002997.txt",
+    },
+];
+
+// Largest list accepted from a server
+#[cfg(feature = "fetch")]
+const MAX_DOWNLOAD_BYTES: u64 = 64 << 20;
+
+/// A bundled dataset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Dataset {
+    /// The first 10^6 primes, and the composites between them
+    Primes,
+    /// Strong pseudoprimes to base 2 below 10^9, then to every prime base up
+    /// to 31
+    StrongPseudoprimes,
+    /// Carmichael numbers below 10^9
+    Carmichael,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 3] = [
+        Dataset::Primes,
+        Dataset::StrongPseudoprimes,
+        Dataset::Carmichael,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dataset::Primes => "primes",
+            Dataset::StrongPseudoprimes => "strong-pseudoprimes",
+            Dataset::Carmichael => "carmichael",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Dataset::Primes => "primes-1e6.gaps.gz",
+            Dataset::StrongPseudoprimes => "strong-pseudoprimes.txt",
+            Dataset::Carmichael => "carmichael.txt",
+        }
+    }
+
+    /// `$PTC_DATA_DIR`, else the `data` folder of the repository the binary
+    /// was built from
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("PTC_DATA_DIR")
+            .filter(|value| !value.is_empty())
+            .map_or_else(
+                || Path::new(env!("CARGO_MANIFEST_DIR")).join("data"),
+                PathBuf::from,
+            )
+    }
+
+    /// Reads the dataset from its file in `dir`
+    pub fn load(self, dir: &Path) -> Result<KnownNumbers> {
+        let path = dir.join(self.file_name());
+        let invalid = |reason: String| Error::InvalidDataset {
+            path: path.display().to_string(),
+            reason,
+        };
+        // Name the file, which a missing data folder is reported by
+        let unreadable =
+            |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", path.display(), e));
+
+        let numbers = match self {
+            Dataset::Primes => {
+                let mut gaps = Vec::new();
+                GzDecoder::new(File::open(&path).map_err(unreadable)?)
+                    .read_to_end(&mut gaps)
+                    .map_err(|e| match e.kind() {
+                        ErrorKind::InvalidInput | ErrorKind::InvalidData => invalid(e.to_string()),
+                        _ => e.into(),
+                    })?;
+
+                let mut primes = Vec::with_capacity(gaps.len() + 1);
+                primes.push(2);
+                let mut last = 1u64;
+                for gap in gaps {
+                    if gap == 0 {
+                        return Err(invalid(format!("zero gap after {}", last)));
+                    }
+                    last += 2 * gap as u64;
+                    primes.push(last);
+                }
+                primes
+            }
+            Dataset::StrongPseudoprimes | Dataset::Carmichael => {
+                let mut numbers = parse_numbers(&fs::read_to_string(&path).map_err(unreadable)?)
+                    .map_err(invalid)?;
+                numbers.sort_unstable();
+                numbers.dedup();
+                numbers
+            }
+        };
+
+        Ok(KnownNumbers {
+            dataset: self,
+            source: self.name(),
+            numbers,
+        })
+    }
+}
+
+/// A public list of known numbers, downloaded on request
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Download {
+    pub name: &'static str,
+    /// The kind of numbers it lists
+    pub dataset: Dataset,
+    pub description: &'static str,
+    /// Where it is downloaded from, an OEIS b-file: an index and a term per
+    /// line
+    pub url: &'static str,
+}
+
+/// A list just downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetched {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Numbers in the list that fit in a u64
+    pub numbers: usize,
+    pub sha256: String,
+}
+
+impl Download {
+    /// `datasets` under the sieve cache directory, None without one
+    pub fn default_dir() -> Option<PathBuf> {
+        SieveCache::default_dir().map(|dir| dir.join("datasets"))
+    }
+
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.txt", self.name))
+    }
+
+    // The recorded checksum, in the format of `sha256sum`
+    fn checksum_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.txt.sha256", self.name))
+    }
+
+    /// The list downloaded into `dir`, None if it has not been. Fails if
+    /// the file does not match the checksum recorded when it was downloaded.
+    pub fn load(&self, dir: &Path) -> Result<Option<KnownNumbers>> {
+        let path = self.path(dir);
+        let invalid = |reason: String| Error::InvalidDataset {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let recorded = fs::read_to_string(self.checksum_path(dir))
+            .map_err(|e| invalid(format!("no recorded checksum ({})", e)))?;
+        let recorded = recorded.split_whitespace().next().unwrap_or_default();
+        let actual = sha256(&bytes);
+        if actual != recorded {
+            return Err(invalid(format!(
+                "SHA-256 {} does not match the {} recorded at download, download it again",
+                actual, recorded
+            )));
+        }
+
+        let text = String::from_utf8(bytes).map_err(|e| invalid(e.to_string()))?;
+        Ok(Some(KnownNumbers {
+            dataset: self.dataset,
+            source: self.name,
+            numbers: parse_b_file(&text).map_err(invalid)?,
+        }))
+    }
+
+    /// Downloads the list into `dir`, replacing any earlier copy, and
+    /// records its checksum
+    #[cfg(feature = "fetch")]
+    pub fn fetch(&self, dir: &Path) -> Result<Fetched> {
+        let failed = |reason: String| Error::Download {
+            url: self.url.to_string(),
+            reason,
+        };
+
+        let bytes = ureq::get(self.url)
+            .call()
+            .map_err(|e| failed(e.to_string()))?
+            .body_mut()
+            .with_config()
+            .limit(MAX_DOWNLOAD_BYTES)
+            .read_to_vec()
+            .map_err(|e| failed(e.to_string()))?;
+        let numbers = std::str::from_utf8(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(parse_b_file)
+            .map_err(|reason| failed(format!("not a b-file: {}", reason)))?
+            .len();
+
+        // Written aside and renamed into place, so a failed download leaves
+        // the previous copy alone
+        fs::create_dir_all(dir)?;
+        let path = self.path(dir);
+        let partial = dir.join(format!("{}.txt.{}", self.name, std::process::id()));
+        fs::write(&partial, &bytes)?;
+        let sha256 = sha256(&bytes);
+        fs::write(
+            self.checksum_path(dir),
+            format!("{}  {}.txt\n", sha256, self.name),
+        )?;
+        fs::rename(&partial, &path)?;
+
+        Ok(Fetched {
+            path,
+            bytes: bytes.len() as u64,
+            numbers,
+            sha256,
+        })
+    }
+}
+
+/// The numbers of a dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownNumbers {
+    pub dataset: Dataset,
+    /// The bundled dataset or download the numbers come from
+    pub source: &'static str,
+    /// Ascending: primes for [`Dataset::Primes`], composites otherwise
+    pub numbers: Vec<u64>,
+}
+
+impl KnownNumbers {
+    /// Every number the dataset knows the primality of, ascending: for the
+    /// prime table all numbers up to its largest prime, composites included,
+    /// for the others the composites listed
+    pub fn cases(&self) -> Box<dyn Iterator<Item = (u64, bool)> + '_> {
+        match self.dataset {
+            Dataset::Primes => {
+                let mut primes = self.numbers.iter().peekable();
+                let end = self.numbers.last().map_or(0, |&last| last + 1);
+                Box::new((0..end).map(move |n| (n, primes.next_if_eq(&&n).is_some())))
+            }
+            Dataset::StrongPseudoprimes | Dataset::Carmichael => {
+                Box::new(self.numbers.iter().map(|&n| (n, false)))
+            }
+        }
+    }
+
+    /// How many numbers [`cases`](KnownNumbers::cases) yields
+    pub fn case_count(&self) -> u64 {
+        match self.dataset {
+            Dataset::Primes => self.numbers.last().map_or(0, |&last| last + 1),
+            Dataset::StrongPseudoprimes | Dataset::Carmichael => self.numbers.len() as u64,
+        }
+    }
+}
+
+/// How an algorithm fared on a dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validation {
+    /// Numbers tested
+    pub checked: u64,
+    /// Numbers outside the algorithm's supported range
+    pub skipped: u64,
+    /// Numbers the algorithm got wrong
+    pub failures: Vec<u64>,
+    /// The last number tested before the time limit ran out, None if the
+    /// whole dataset was tested
+    pub stopped_at: Option<u64>,
+}
+
+/// Tests every number of `known` in the supported range of `test`, in
+/// ascending order, stopping at the first number past `time_limit`
+pub fn validate(
+    test: &dyn PrimalityTest,
+    known: &KnownNumbers,
+    time_limit: Option<Duration>,
+) -> Validation {
+    let range = test.supported_range();
+    let start = Instant::now();
+    let mut validation = Validation {
+        checked: 0,
+        skipped: 0,
+        failures: Vec::new(),
+        stopped_at: None,
+    };
+
+    for (n, is_prime) in known.cases() {
+        if !range.contains(&n) {
+            validation.skipped += 1;
+            continue;
+        }
+
+        validation.checked += 1;
+        if test.is_prime(n) != is_prime {
+            validation.failures.push(n);
+        }
+
+        if time_limit.is_some_and(|limit| start.elapsed() > limit) {
+            validation.stopped_at = Some(n);
+            break;
+        }
+    }
+
+    validation
+}
+
+fn parse_numbers(text: &str) -> std::result::Result<Vec<u64>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|e| format!("line {}: '{}': {}", i + 1, line, e))
+        })
+        .collect()
+}
+
+/// The names of the pseudoprime lists holding `n`, bundled in `data_dir` or
+/// downloaded into `download_dir`. Lists that are missing or unreadable are
+/// passed over.
+pub fn lists_holding(n: u64, data_dir: &Path, download_dir: Option<&Path>) -> Vec<&'static str> {
+    let bundled = [Dataset::StrongPseudoprimes, Dataset::Carmichael]
+        .into_iter()
+        .filter_map(|dataset| dataset.load(data_dir).ok());
+    let downloaded = download_dir.into_iter().flat_map(|dir| {
+        DOWNLOADS
+            .iter()
+            .filter(|download| download.dataset != Dataset::Primes)
+            .filter_map(move |download| download.load(dir).ok().flatten())
+    });
+
+    bundled
+        .chain(downloaded)
+        .filter(|known| known.numbers.binary_search(&n).is_ok())
+        .map(|known| known.source)
+        .collect()
+}
+
+// The terms of an OEIS b-file, ascending; terms past u64 are left out
+fn parse_b_file(text: &str) -> std::result::Result<Vec<u64>, String> {
+    let mut numbers = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some(term) = line.split_whitespace().nth(1) else {
+            continue;
+        };
+        match term.parse::<u64>() {
+            Ok(n) => numbers.push(n),
+            Err(e) if *e.kind() == std::num::IntErrorKind::PosOverflow => {}
+            Err(e) => return Err(format!("line {}: '{}': {}", i + 1, term, e)),
+        }
+    }
+
+    numbers.sort_unstable();
+    numbers.dedup();
+    Ok(numbers)
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::Registry;
+    use crate::algorithms::config::AlgorithmConfig;
+    use crate::algorithms::miller_rabin;
+
+    fn load(dataset: Dataset) -> KnownNumbers {
+        dataset
+            .load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bundled_datasets() {
+        let primes = load(Dataset::Primes);
+        assert_eq!(primes.numbers.len(), 1_000_000);
+        assert_eq!(primes.numbers[..5], [2, 3, 5, 7, 11]);
+        assert_eq!(primes.numbers.last(), Some(&15_485_863));
+        assert_eq!(primes.case_count(), 15_485_864);
+        let cases: Vec<(u64, bool)> = primes.cases().take(5).collect();
+        assert_eq!(
+            cases,
+            [(0, false), (1, false), (2, true), (3, true), (4, false)]
+        );
+
+        let strong = load(Dataset::StrongPseudoprimes);
+        assert_eq!(strong.numbers[..3], [2047, 3277, 4033]);
+        assert_eq!(strong.numbers.last(), Some(&3_825_123_056_546_413_051));
+        let carmichael = load(Dataset::Carmichael);
+        assert_eq!(carmichael.numbers.len(), 646);
+        assert_eq!(carmichael.numbers[..3], [561, 1105, 1729]);
+
+        for known in [&strong, &carmichael] {
+            assert!(!known.numbers.iter().any(|&n| miller_rabin::is_prime(n)));
+        }
+
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        assert_eq!(lists_holding(561, &data, None), ["carmichael"]);
+        assert_eq!(
+            lists_holding(15841, &data, None),
+            ["strong-pseudoprimes", "carmichael"]
+        );
+        assert!(lists_holding(97, &data, None).is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let strong = load(Dataset::StrongPseudoprimes);
+        let config =
+            AlgorithmConfig::from_pairs(&[("miller-rabin.witnesses".to_string(), "2".to_string())])
+                .unwrap();
+        let registry = Registry::with_builtins(&config).unwrap();
+        let validation = validate(
+            registry.get("miller-rabin").unwrap().as_ref(),
+            &strong,
+            None,
+        );
+        // Base 2 alone lets every one of them through
+        assert_eq!(validation.failures, strong.numbers);
+        assert_eq!(validation.stopped_at, None);
+
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        let division = validate(
+            registry.get("trial-division").unwrap().as_ref(),
+            &strong,
+            None,
+        );
+        assert!(division.failures.is_empty());
+        assert_eq!(division.checked, strong.case_count());
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(
+            parse_numbers("# header\n561\n\n1105 # 5 * 13 * 17\n"),
+            Ok(vec![561, 1105])
+        );
+        assert!(
+            parse_numbers("561\nfive\n")
+                .unwrap_err()
+                .starts_with("line 2")
+        );
+
+        let b_file = "# A014233\n1 2047\n2 1373653\n\n12 318665857834031151167461\n";
+        assert_eq!(parse_b_file(b_file), Ok(vec![2047, 1373653]));
+        assert!(parse_b_file("1 -3\n").is_err());
+    }
+
+    #[test]
+    fn test_download_checksum() {
+        let dir = std::env::temp_dir().join(format!("ptc-datasets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let download = DOWNLOADS[3];
+        assert_eq!(download.load(&dir).unwrap(), None);
+
+        let list = "1 561\n2 1105\n";
+        fs::write(download.path(&dir), list).unwrap();
+        fs::write(
+            download.checksum_path(&dir),
+            format!("{}  oeis-a002997.txt\n", sha256(list.as_bytes())),
+        )
+        .unwrap();
+        let known = download.load(&dir).unwrap().unwrap();
+        assert_eq!(known.numbers, [561, 1105]);
+        assert_eq!(known.source, "oeis-a002997");
+        assert_eq!(known.dataset, Dataset::Carmichael);
+
+        fs::write(download.path(&dir), "1 561\n2 1106\n").unwrap();
+        assert!(matches!(
+            download.load(&dir),
+            Err(Error::InvalidDataset { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
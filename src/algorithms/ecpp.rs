@@ -0,0 +1,520 @@
+//! Elliptic curve primality proving (ECPP)
+//!
+//! Goldwasser-Kilian: if an elliptic curve over Z_n has a point P with
+//! [m]P = O and [m/q]P ≠ O for a prime q > (n^(1/4) + 1)², then n is prime.
+//! This reduces the proof of n to one of q < n, and so on down to a prime
+//! below 2^16, which is looked up. The hard part is a curve whose order m
+//! has such a factor. Atkin and Morain's complex multiplication method finds
+//! the order without counting points: when 4n = u² + |D|v² for a
+//! discriminant D, the curves with the j-invariant of D have order
+//! n + 1 ± u (and four more for D = -3 and -4). This implementation only
+//! uses the nine discriminants of class number 1, whose j-invariants are
+//! integers, and takes q from m by trial division, so some primes find no
+//! curve; those are left to Miller-Rabin, which [`PrimalityTest::backend`]
+//! reports once `is_prime` has run. As in practical ECPP, composites are rejected by Miller-Rabin
+//! before any curve is tried. Inputs are limited to u64.
+//!
+//! [`certify`] returns the chain of curves, a certificate that can be
+//! checked without repeating the search.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use num_bigint::BigUint;
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, miller_rabin, small_primes};
+use crate::math::{jacobi, mod_inverse, mod_pow};
+
+// The discriminants of class number 1 with the j-invariants of their curves
+const DISCRIMINANTS: [(i64, i64); 9] = [
+    (-3, 0),
+    (-4, 1728),
+    (-7, -3375),
+    (-8, 8000),
+    (-11, -32768),
+    (-19, -884736),
+    (-43, -884736000),
+    (-67, -147197952000),
+    (-163, -262537412640768000),
+];
+
+// m is divided by the primes below this, and below the bound q must pass,
+// to leave the factor q
+const TRIAL_BOUND: u64 = 1 << 12;
+
+// Points tried on each curve before moving to the next
+const MAX_POINTS: usize = 8;
+
+/// One link of a certificate: P = `point` on y² = x³ + ax + b over Z_n has
+/// [m]P = O and [m/q]P ≠ O, so n is prime if q is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub n: u64,
+    /// The discriminant the curve has complex multiplication by
+    pub discriminant: i64,
+    pub a: u64,
+    pub b: u64,
+    /// The order of the curve
+    pub m: u128,
+    /// The prime factor of m proven next
+    pub q: u64,
+    pub point: (u64, u64),
+}
+
+fn mul(a: u64, b: u64, n: u64) -> u64 {
+    (a as u128 * b as u128 % n as u128) as u64
+}
+
+fn add(a: u64, b: u64, n: u64) -> u64 {
+    ((a as u128 + b as u128) % n as u128) as u64
+}
+
+fn sub(a: u64, b: u64, n: u64) -> u64 {
+    add(a, n - b, n)
+}
+
+// x mod n
+fn signed(x: i64, n: u64) -> u64 {
+    let r = x.unsigned_abs() % n;
+    if x < 0 { (n - r) % n } else { r }
+}
+
+// A square root of `a` modulo the odd prime `p` by Tonelli-Shanks, None if
+// `a` is a non-residue
+fn sqrt_mod(a: u64, p: u64) -> Option<u64> {
+    if a == 0 {
+        return Some(0);
+    }
+    if mod_pow(a, (p - 1) / 2, p) != 1 {
+        return None;
+    }
+
+    let s = (p - 1).trailing_zeros();
+    let odd = (p - 1) >> s;
+    let z = (2..p).find(|&z| mod_pow(z, (p - 1) / 2, p) == p - 1)?;
+    let (mut m, mut c) = (s, mod_pow(z, odd, p));
+    let (mut t, mut r) = (mod_pow(a, odd, p), mod_pow(a, odd.div_ceil(2), p));
+    while t != 1 {
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul(t2, t2, p);
+            i += 1;
+        }
+        let b = mod_pow(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul(b, b, p);
+        t = mul(t, c, p);
+        r = mul(r, b, p);
+    }
+    Some(r)
+}
+
+// (u, v) with 4n = u² + |d|·v², by the modified Cornacchia algorithm
+fn cornacchia(n: u64, d: i64) -> Option<(u128, u128)> {
+    let mut x = sqrt_mod(signed(d, n), n)? as u128;
+    let (n, abs) = (n as u128, d.unsigned_abs() as u128);
+    if x % 2 != abs % 2 {
+        x = n - x;
+    }
+
+    let (mut r0, mut r1) = (2 * n, x);
+    let limit = (4 * n).isqrt();
+    while r1 > limit {
+        (r0, r1) = (r1, r0 % r1);
+    }
+    let rest = 4 * n - r1 * r1;
+    if !rest.is_multiple_of(abs) {
+        return None;
+    }
+    let v = (rest / abs).isqrt();
+    (v * v == rest / abs).then_some((r1, v))
+}
+
+// The curve orders n + 1 - t for the traces t of discriminant d
+fn orders(n: u64, d: i64) -> Vec<u128> {
+    let Some((u, v)) = cornacchia(n, d) else {
+        return Vec::new();
+    };
+    let mut traces = vec![u as i128];
+    match d {
+        -4 => traces.push(2 * v as i128),
+        -3 => {
+            traces.push((u + 3 * v) as i128 / 2);
+            traces.push((u as i128 - 3 * v as i128) / 2);
+        }
+        _ => {}
+    }
+    traces
+        .iter()
+        .flat_map(|&t| [n as i128 + 1 - t, n as i128 + 1 + t])
+        .map(|m| m as u128)
+        .collect()
+}
+
+// The curves with the j-invariant of d: every twist, as (a, b) of
+// y² = x³ + ax + b. A twist by g must not be a square, and for j = 0 not a
+// cube either, so the powers of g reach every twist.
+fn curves(n: u64, d: i64, j: i64) -> Vec<(u64, u64)> {
+    let non_residue = |cube: bool| {
+        (2..n).find(|&g| {
+            jacobi(g as u128, n as u128) == -1
+                && !(cube && n % 3 == 1 && mod_pow(g, (n - 1) / 3, n) == 1)
+        })
+    };
+    let Some(g) = non_residue(d == -3) else {
+        return Vec::new();
+    };
+    let powers = |count: u64| (0..count).map(move |i| mod_pow(g, i, n));
+
+    match d {
+        -3 => powers(6).map(|b| (0, b)).collect(),
+        -4 => powers(4).map(|a| (a, 0)).collect(),
+        _ => {
+            // y² = x³ + 3cx + 2c with c = j / (1728 - j) has j-invariant j
+            let Some(inverse) = mod_inverse(signed(1728 - j, n), n) else {
+                return Vec::new();
+            };
+            let c = mul(signed(j, n), inverse, n);
+            if c == 0 {
+                return Vec::new();
+            }
+            let (a, b) = (mul(3, c, n), mul(2, c, n));
+            let (g2, g3) = (mul(g, g, n), mod_pow(g, 3, n));
+            vec![(a, b), (mul(a, g2, n), mul(b, g3, n))]
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Point {
+    Infinity,
+    Affine(u64, u64),
+}
+
+// y² = x³ + ax + b over Z_n
+struct Curve {
+    a: u64,
+    n: u64,
+}
+
+impl Curve {
+    // None when a denominator is not invertible, which only happens for
+    // composite n
+    fn add(&self, p: Point, q: Point) -> Option<Point> {
+        let n = self.n;
+        let (x1, y1, x2, y2) = match (p, q) {
+            (Point::Infinity, r) | (r, Point::Infinity) => return Some(r),
+            (Point::Affine(x1, y1), Point::Affine(x2, y2)) => (x1, y1, x2, y2),
+        };
+        let slope = if x1 != x2 {
+            mul(sub(y2, y1, n), mod_inverse(sub(x2, x1, n), n)?, n)
+        } else if add(y1, y2, n) == 0 {
+            return Some(Point::Infinity);
+        } else {
+            let numerator = add(mul(3, mul(x1, x1, n), n), self.a, n);
+            mul(numerator, mod_inverse(add(y1, y1, n), n)?, n)
+        };
+        let x3 = sub(sub(mul(slope, slope, n), x1, n), x2, n);
+        let y3 = sub(mul(slope, sub(x1, x3, n), n), y1, n);
+        Some(Point::Affine(x3, y3))
+    }
+
+    fn mul(&self, p: Point, k: u128) -> Option<Point> {
+        let mut result = Point::Infinity;
+        for bit in (0..128 - k.leading_zeros()).rev() {
+            result = self.add(result, result)?;
+            if k >> bit & 1 == 1 {
+                result = self.add(result, p)?;
+            }
+        }
+        Some(result)
+    }
+}
+
+// The points with x = 0, 1, 2, ... on y² = x³ + ax + b, skipping those of
+// order 2
+fn points(n: u64, a: u64, b: u64) -> impl Iterator<Item = (u64, u64)> {
+    (0..n)
+        .filter_map(move |x| {
+            let rhs = add(mul(mul(x, x, n), x, n), add(mul(a, x, n), b, n), n);
+            (rhs != 0)
+                .then(|| sqrt_mod(rhs, n).map(|y| (x, y)))
+                .flatten()
+        })
+        .take(MAX_POINTS)
+}
+
+// A step proving the probable prime n from a curve of discriminant d and
+// order m = k·q
+fn find_step(n: u64, d: i64, j: i64, m: u128, q: u64) -> Option<Step> {
+    for (a, b) in curves(n, d, j) {
+        let curve = Curve { a, n };
+        for (x, y) in points(n, a, b) {
+            let cofactor_point = curve.mul(Point::Affine(x, y), m / q as u128)?;
+            if cofactor_point == Point::Infinity {
+                continue;
+            }
+            if curve.mul(cofactor_point, q as u128)? == Point::Infinity {
+                return Some(Step {
+                    n,
+                    discriminant: d,
+                    a,
+                    b,
+                    m,
+                    q,
+                    point: (x, y),
+                });
+            }
+            // [m]P ≠ O: the order belongs to another twist
+            break;
+        }
+    }
+    None
+}
+
+// Appends the steps proving the probable prime n to `steps`, false (with
+// `steps` as it was) if no chain of curves reaches 2^16
+fn descend(n: u64, steps: &mut Vec<Step>) -> bool {
+    if n < small_primes::LIMIT {
+        return true;
+    }
+
+    // q > (n^(1/4) + 1)², as floor(n^(1/4)) + 2 > n^(1/4) + 1
+    let root = n.isqrt().isqrt() as u128 + 2;
+    let bound = root * root;
+    // Dividing by primes above the bound could strip q itself
+    let divisors = small_primes::up_to(TRIAL_BOUND.min(bound as u64));
+    for (d, j) in DISCRIMINANTS {
+        if jacobi(signed(d, n) as u128, n as u128) != 1 {
+            continue;
+        }
+        for m in orders(n, d) {
+            let mut q = m;
+            for &p in divisors {
+                while q.is_multiple_of(p as u128) {
+                    q /= p as u128;
+                }
+            }
+            if q <= bound || q >= n as u128 || !miller_rabin::is_prime(q as u64) {
+                continue;
+            }
+
+            let Some(step) = find_step(n, d, j, m, q as u64) else {
+                continue;
+            };
+            steps.push(step);
+            if descend(q as u64, steps) {
+                return true;
+            }
+            steps.pop();
+        }
+    }
+    false
+}
+
+/// The certificate of the prime `n`: the steps down to a prime below
+/// 2^16, the first proving `n`, and none for `n` itself below 2^16. None
+/// if `n` is composite or no chain of curves was found.
+pub fn certify(n: u64) -> Option<Vec<Step>> {
+    let mut steps = Vec::new();
+    (miller_rabin::is_prime(n) && descend(n, &mut steps)).then_some(steps)
+}
+
+#[derive(Debug, Default)]
+pub struct Ecpp {
+    // The last n `is_prime` found no chain of curves for, so `backend` can
+    // report the fallback without repeating the search
+    uncertified: AtomicU64,
+}
+
+impl PrimalityTest for Ecpp {
+    fn name(&self) -> &str {
+        "ecpp"
+    }
+
+    fn description(&self) -> &str {
+        "Elliptic curve primality proof with class number 1 curves (Atkin-Morain)"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        // The curve search assumes a probable prime, so composites stop here
+        if !miller_rabin::is_prime(n) {
+            return false;
+        }
+        // Miller-Rabin is deterministic below 2^64, so n is prime either way.
+        // The search still runs so that the time to prove it is what the
+        // benchmark measures, not just the probable prime test
+        if !descend(n, &mut Vec::new()) {
+            self.uncertified.store(n, Ordering::Relaxed);
+        }
+        true
+    }
+
+    fn backend(&self, n: &BigUint) -> Option<String> {
+        let n = u64::try_from(n).ok()?;
+        (n != 0 && self.uncertified.load(Ordering::Relaxed) == n)
+            .then(|| "miller-rabin".to_string())
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        miller_rabin::evidence_with_witnesses(n, &miller_rabin::DEFAULT_WITNESSES)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        if let Some(evidence) = self.evidence(n) {
+            step(format_args!(
+                "Miller-Rabin before any curve, {}: composite",
+                evidence
+            ));
+            return Some(false);
+        }
+
+        let Some(certificate) = certify(n) else {
+            step(format_args!(
+                "no chain of curves found, falling back to miller-rabin: prime"
+            ));
+            return Some(true);
+        };
+        for link in &certificate {
+            step(format_args!(
+                "{}: D = {}, y² = x³ + {}x + {} has order m = {} · {}",
+                link.n,
+                link.discriminant,
+                link.a,
+                link.b,
+                link.m / link.q as u128,
+                link.q
+            ));
+            step(format_args!(
+                "P = ({}, {}): [m/q]P ≠ O and [m]P = O, so {} is prime if {} is",
+                link.point.0, link.point.1, link.n, link.q
+            ));
+        }
+        let last = certificate.last().map_or(n, |link| link.q);
+        step(format_args!(
+            "{} < 2^16 is in the table of small primes: prime",
+            last
+        ));
+        Some(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks every step of a certificate from scratch
+    fn verify(n: u64, certificate: &[Step]) -> bool {
+        let mut expected = n;
+        for link in certificate {
+            let root = link.n.isqrt().isqrt() as u128 + 2;
+            let curve = Curve {
+                a: link.a,
+                n: link.n,
+            };
+            let (x, y) = link.point;
+            let on_curve = mul(y, y, link.n)
+                == add(
+                    mul(mul(x, x, link.n), x, link.n),
+                    add(mul(link.a, x, link.n), link.b, link.n),
+                    link.n,
+                );
+            let p = Point::Affine(x, y);
+            if link.n != expected
+                || !on_curve
+                || link.q as u128 <= root * root
+                || !link.m.is_multiple_of(link.q as u128)
+                || curve.mul(p, link.m / link.q as u128) == Some(Point::Infinity)
+                || curve.mul(p, link.m) != Some(Point::Infinity)
+            {
+                return false;
+            }
+            expected = link.q;
+        }
+        small_primes::is_prime(expected) == Some(true)
+    }
+
+    #[test]
+    fn test_cornacchia() {
+        for (n, d) in [(1_000_003u64, -7i64), (65_537, -4), (1_000_033, -3)] {
+            if let Some((u, v)) = cornacchia(n, d) {
+                assert_eq!(u * u + d.unsigned_abs() as u128 * v * v, 4 * n as u128);
+            }
+        }
+        // 65537 = 256² + 1², so 4n = 512² + 4 · 1²
+        assert_eq!(cornacchia(65_537, -4), Some((512, 1)));
+        assert_eq!(sqrt_mod(3, 7), None);
+        assert_eq!(sqrt_mod(2, 7).map(|r| r * r % 7), Some(2));
+    }
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        let ecpp = Ecpp::default();
+        for n in (0..20_000).chain(1 << 32..(1 << 32) + 2_000) {
+            assert_eq!(ecpp.is_prime(n), miller_rabin::is_prime(n), "{n}");
+            assert_eq!(ecpp.evidence(n).is_none(), miller_rabin::is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_certificates() {
+        // Most primes get a certificate, each of which checks out
+        let primes: Vec<u64> = (u64::MAX - 20_000..=u64::MAX)
+            .filter(|&n| miller_rabin::is_prime(n))
+            .collect();
+        let ecpp = Ecpp::default();
+        let mut certified = 0;
+        for &n in &primes {
+            // The backend reflects the search of the run before it
+            assert!(ecpp.is_prime(n));
+            if let Some(certificate) = certify(n) {
+                assert!(!certificate.is_empty());
+                assert!(verify(n, &certificate), "{n}");
+                assert_eq!(ecpp.backend(&n.into()), None);
+                certified += 1;
+            } else {
+                assert_eq!(ecpp.backend(&n.into()).as_deref(), Some("miller-rabin"));
+            }
+        }
+        assert!(
+            certified * 10 > primes.len() * 9,
+            "{certified} of {}",
+            primes.len()
+        );
+
+        // No chain of curves is found for this prime, which keeps the
+        // Miller-Rabin verdict
+        let uncertified = 18446744073709532297;
+        assert_eq!(certify(uncertified), None);
+        assert_eq!(Ecpp::default().backend(&uncertified.into()), None);
+        assert!(ecpp.is_prime(uncertified));
+        assert_eq!(ecpp.evidence(uncertified), None);
+        assert_eq!(
+            ecpp.backend(&uncertified.into()).as_deref(),
+            Some("miller-rabin")
+        );
+        assert_eq!(ecpp.backend(&0u64.into()), None);
+
+        // Just past 2^16 the factor q is below 2^12, and survives the trial
+        // division that leaves it
+        let certificate = certify(65_587).unwrap();
+        assert!(verify(65_587, &certificate));
+
+        assert_eq!(certify(65_521), Some(Vec::new()));
+        assert_eq!(certify(3_215_031_751), None);
+    }
+
+    #[test]
+    fn test_trace() {
+        let ecpp = Ecpp::default();
+        let mut steps = Vec::new();
+        assert_eq!(
+            ecpp.trace(65_521, &mut |step| steps.push(step.to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            steps,
+            ["65521 < 2^16 is in the table of small primes: prime"]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Why a primality test rejected a number
+
+use std::fmt;
+
+/// The fact a test used to prove a number composite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evidence {
+    /// 0 and 1 are not prime by definition
+    BelowTwo,
+    /// A divisor d with 1 < d < n
+    Divisor(u64),
+    /// A Miller-Rabin witness: n is not a strong probable prime to this base
+    Witness(u64),
+    /// A Fermat witness: base^(n - 1) ≢ 1 (mod n)
+    FermatWitness(u64),
+    /// An Euler witness: base^((n - 1)/2) ≢ (base/n) (mod n)
+    EulerWitness(u64),
+    /// n = base^exponent with exponent > 1
+    PerfectPower { base: u64, exponent: u32 },
+    /// AKS: (X + a)^n differs from X^n + a modulo (X^r - 1, n)
+    PolynomialCongruence { r: u64, a: u64 },
+    /// Baillie-PSW: n is not a strong Lucas probable prime
+    LucasTest,
+    /// The quadratic Frobenius test failed in Z_n[x]/(x² - bx - c)
+    FrobeniusTest { b: u64, c: u64 },
+    /// Wilson's theorem: (n - 1)! ≡ residue (mod n), which is not -1
+    WilsonResidue(u64),
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evidence::BelowTwo => write!(f, "numbers below 2 are not prime"),
+            Evidence::Divisor(d) => write!(f, "divisible by {}", d),
+            Evidence::Witness(a) => write!(f, "not a strong probable prime to base {}", a),
+            Evidence::FermatWitness(a) => write!(f, "{}^(n - 1) ≢ 1 (mod n)", a),
+            Evidence::EulerWitness(a) => {
+                write!(f, "{a}^((n - 1)/2) ≢ ({a}/n) (mod n)", a = a)
+            }
+            Evidence::PerfectPower { base, exponent } => {
+                write!(f, "perfect power {}^{}", base, exponent)
+            }
+            Evidence::PolynomialCongruence { r, a } => write!(
+                f,
+                "(X + {a})^n ≢ X^n + {a} (mod X^{r} - 1, n)",
+                a = a,
+                r = r
+            ),
+            Evidence::LucasTest => write!(f, "not a strong Lucas probable prime"),
+            Evidence::FrobeniusTest { b, c } => write!(
+                f,
+                "not a Frobenius probable prime with respect to x² - {}x - {}",
+                b, c
+            ),
+            Evidence::WilsonResidue(r) => write!(f, "(n - 1)! ≡ {} (mod n), not -1", r),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Evidence::Divisor(7).to_string(), "divisible by 7");
+        assert_eq!(
+            Evidence::PerfectPower {
+                base: 2,
+                exponent: 10
+            }
+            .to_string(),
+            "perfect power 2^10"
+        );
+        assert_eq!(
+            Evidence::PolynomialCongruence { r: 5, a: 1 }.to_string(),
+            "(X + 1)^n ≢ X^n + 1 (mod X^5 - 1, n)"
+        );
+    }
+}
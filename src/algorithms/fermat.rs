@@ -0,0 +1,255 @@
+//! Fermat probable prime test
+//!
+//! By Fermat's little theorem, a^(n - 1) ≡ 1 (mod n) for a prime n and every
+//! base a it does not divide, so one base breaking the congruence proves n
+//! composite. The converse fails: Carmichael numbers (561, 1105, 1729, ...)
+//! satisfy it for every base coprime to them, and only a base sharing one of
+//! their factors exposes them. Miller-Rabin checks a stronger condition on the
+//! same bases, so the two run with the same default bases show exactly what
+//! the extra square roots of 1 buy.
+
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, small_primes};
+use crate::error::{Error, Result};
+use crate::math::{mod_pow, pow_mod_u128};
+
+/// Bases used when `fermat.bases` is not set: the 12 primes up to 37, the
+/// same as Miller-Rabin's default witnesses
+pub const DEFAULT_BASES: usize = 12;
+
+/// The first base of `bases` for which a^(n - 1) ≢ 1 (mod n), None if `n`
+/// passes them all. Bases that are multiples of `n` are skipped.
+pub fn find_witness(n: u64, bases: &[u64]) -> Option<u64> {
+    bases
+        .iter()
+        .copied()
+        .find(|&a| !a.is_multiple_of(n) && mod_pow(a, n - 1, n) != 1)
+}
+
+/// Returns true if `n` is a Fermat probable prime to every base in `bases`
+pub fn is_prime_with_bases(n: u64, bases: &[u64]) -> bool {
+    evidence_with_bases(n, bases).is_none()
+}
+
+/// [`is_prime_with_bases`] for 128-bit inputs
+pub fn is_prime_u128_with_bases(n: u128, bases: &[u64]) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return is_prime_with_bases(n, bases);
+    }
+    n & 1 == 1
+        && bases.iter().all(|&a| {
+            let a = a as u128;
+            a.is_multiple_of(n) || pow_mod_u128(a, n - 1, n) == 1
+        })
+}
+
+/// [`is_prime_with_bases`] over arbitrary-precision integers. Inputs that
+/// fit in a u128 take the fixed-width path.
+pub fn is_prime_big_with_bases(n: &BigUint, bases: &[u64]) -> bool {
+    if let Ok(n) = u128::try_from(n) {
+        return is_prime_u128_with_bases(n, bases);
+    }
+    if !n.bit(0) {
+        return false;
+    }
+
+    let n_minus_one = n - 1u32;
+    let one = BigUint::from(1u32);
+    bases
+        .iter()
+        .all(|&a| BigUint::from(a).modpow(&n_minus_one, n) == one)
+}
+
+/// Why the Fermat test rejects `n` with the given bases, None if it accepts `n`
+pub fn evidence_with_bases(n: u64, bases: &[u64]) -> Option<Evidence> {
+    match n {
+        0 | 1 => Some(Evidence::BelowTwo),
+        2 | 3 => None,
+        _ if n.is_multiple_of(2) => Some(Evidence::Divisor(2)),
+        _ => find_witness(n, bases).map(Evidence::FermatWitness),
+    }
+}
+
+/// [`is_prime_with_bases`], describing a^(n - 1) mod n for every base to `step`
+pub fn trace_with_bases(n: u64, bases: &[u64], step: TraceFn<'_>) -> bool {
+    if n < 4 || n.is_multiple_of(2) {
+        let is_prime = is_prime_with_bases(n, bases);
+        step(format_args!(
+            "{} is below 4 or even, decided directly: {}",
+            n,
+            if is_prime { "prime" } else { "composite" }
+        ));
+        return is_prime;
+    }
+
+    for &a in bases {
+        if a.is_multiple_of(n) {
+            step(format_args!("a = {}: a multiple of n, skipped", a));
+            continue;
+        }
+
+        let x = mod_pow(a, n - 1, n);
+        step(format_args!("a = {}: {}^{} mod {} = {}", a, a, n - 1, n, x));
+        if x != 1 {
+            step(format_args!("{} is a Fermat witness: composite", a));
+            return false;
+        }
+    }
+
+    step(format_args!("Fermat probable prime to every base"));
+    true
+}
+
+pub struct Fermat {
+    bases: Vec<u64>,
+    description: String,
+}
+
+impl Fermat {
+    pub fn with_bases(bases: Vec<u64>) -> Self {
+        Fermat {
+            description: format!(
+                "Fermat probable prime test to {}, fooled by Carmichael numbers",
+                small_primes::describe_bases(&bases)
+            ),
+            bases,
+        }
+    }
+
+    /// Reads the `fermat.bases` knob, the number of bases, which are the
+    /// first primes (default: [`DEFAULT_BASES`])
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        const KEY: &str = "fermat.bases";
+
+        let count = config.get(KEY)?.unwrap_or(DEFAULT_BASES);
+        let available = small_primes::all().len();
+        if count == 0 || count > available {
+            return Err(Error::InvalidConfigValue {
+                key: KEY.to_string(),
+                value: count.to_string(),
+                hint: Some(format!("expected 1 to {} bases", available)),
+            });
+        }
+
+        Ok(Fermat::with_bases(small_primes::first(count).to_vec()))
+    }
+}
+
+impl PrimalityTest for Fermat {
+    fn name(&self) -> &str {
+        "fermat"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime_with_bases(n, &self.bases)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128_with_bases(n, &self.bases)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(is_prime_big_with_bases(n, &self.bases))
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence_with_bases(n, &self.bases)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_bases(n, &self.bases, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::miller_rabin;
+
+    fn config(bases: &str) -> AlgorithmConfig {
+        AlgorithmConfig::from_pairs(&[("fermat.bases".to_string(), bases.to_string())]).unwrap()
+    }
+
+    #[test]
+    fn test_carmichael() {
+        // 341 = 11 · 31 is a Fermat pseudoprime to base 2 but not to 3, and
+        // the Carmichael number 561 = 3 · 11 · 17 to every base coprime to it
+        let base2 = Fermat::from_config(&config("1")).unwrap();
+        assert!(base2.is_prime(341));
+        assert!(base2.is_prime(561));
+        assert_eq!(
+            evidence_with_bases(341, &[2, 3]),
+            Some(Evidence::FermatWitness(3))
+        );
+
+        // 252601 = 41 · 61 · 101 has no factor among the default bases, so
+        // Fermat accepts it where Miller-Rabin with the same bases does not
+        let fermat = Fermat::from_config(&AlgorithmConfig::default()).unwrap();
+        assert!(!fermat.is_prime(561));
+        assert!(fermat.is_prime(252601));
+        assert!(!miller_rabin::is_prime_with_witnesses(
+            252601,
+            small_primes::first(DEFAULT_BASES)
+        ));
+
+        assert_eq!(
+            fermat.description(),
+            "Fermat probable prime test to the first 12 primes, fooled by Carmichael numbers"
+        );
+        assert_eq!(
+            Fermat::from_config(&config("3")).unwrap().description(),
+            "Fermat probable prime test to the first 3 primes, fooled by Carmichael numbers"
+        );
+        assert!(base2.description().contains("to base 2,"));
+
+        assert!(matches!(
+            Fermat::from_config(&config("0")),
+            Err(Error::InvalidConfigValue { hint: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn test_widths() {
+        let fermat = Fermat::from_config(&AlgorithmConfig::default()).unwrap();
+        let primes = crate::algorithms::sieve_of_eratosthenes::sieve(10_000);
+        for n in 0..=10_000 {
+            let expected = primes.binary_search(&n).is_ok();
+            assert_eq!(fermat.is_prime(n), expected, "{}", n);
+            assert_eq!(fermat.is_prime_u128(n as u128), expected, "{}", n);
+            assert_eq!(fermat.is_prime_big(&n.into()), Some(expected), "{}", n);
+        }
+
+        // 2^127 - 1 is a Mersenne prime, 3 · (2^64 + 13) is not
+        assert!(fermat.is_prime_u128(u128::MAX >> 1));
+        assert!(!fermat.is_prime_u128(3 * 18446744073709551629));
+        let mersenne = |p: u32| (BigUint::from(1u32) << p) - 1u32;
+        assert_eq!(fermat.is_prime_big(&mersenne(521)), Some(true));
+        assert_eq!(fermat.is_prime_big(&mersenne(523)), Some(false));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(!trace_with_bases(341, &[2, 3], &mut |step| {
+            steps.push(step.to_string())
+        }));
+        assert_eq!(
+            steps,
+            [
+                "a = 2: 2^340 mod 341 = 1",
+                "a = 3: 3^340 mod 341 = 56",
+                "3 is a Fermat witness: composite",
+            ]
+        );
+    }
+}
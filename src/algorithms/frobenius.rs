@@ -0,0 +1,392 @@
+//! Grantham's quadratic Frobenius test
+//!
+//! Works in the ring Z_n[x]/(x² - bx - c), with b and c chosen so that
+//! (b² + 4c / n) = -1 and (-c / n) = 1. For a prime n the ring is the field
+//! with n² elements and x^n is the other root b - x, so x^((n + 1)/2) is an
+//! integer, x^(n + 1) ≡ -c, and x^s ≡ 1 or x^(2^j·s) ≡ -1 for some
+//! j ≤ r - 2, where n² - 1 = 2^r·s with s odd. A composite passes with
+//! probability below 1/7710 for random parameters, against 1/4 for a
+//! Miller-Rabin round, at a few times the cost of one. Here the parameters
+//! are the first suitable pair rather than random ones, so the verdicts are
+//! repeatable.
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, small_primes};
+use crate::math::{add_mod_u128, gcd, jacobi, mul_mod_u128};
+
+// Number of primes used to reject small factors: 2 to 47, as in bpsw
+const SMALL_PRIME_COUNT: usize = 15;
+
+// Every odd composite below this has one of the small primes as a factor
+const SMALL_FACTOR_BOUND: u128 = 53 * 53;
+
+// Values of b tried with each c before moving on to the next c
+const MAX_B: u128 = 8;
+
+/// The element u + vx of Z_n[x]/(x² - bx - c)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Element {
+    u: u128,
+    v: u128,
+}
+
+/// Arithmetic modulo n and x² - bx - c
+struct Ring {
+    n: u128,
+    b: u128,
+    c: u128,
+}
+
+impl Ring {
+    fn mul(&self, x: Element, y: Element) -> Element {
+        let n = self.n;
+        let vv = mul_mod_u128(x.v, y.v, n);
+        // x² = bx + c
+        Element {
+            u: add_mod_u128(mul_mod_u128(x.u, y.u, n), mul_mod_u128(self.c, vv, n), n),
+            v: add_mod_u128(
+                add_mod_u128(mul_mod_u128(x.u, y.v, n), mul_mod_u128(x.v, y.u, n), n),
+                mul_mod_u128(self.b, vv, n),
+                n,
+            ),
+        }
+    }
+
+    fn pow(&self, base: Element, exp: u128) -> Element {
+        let mut result = Element { u: 1, v: 0 };
+        for bit in (0..u128::BITS - exp.leading_zeros()).rev() {
+            result = self.mul(result, result);
+            if (exp >> bit) & 1 == 1 {
+                result = self.mul(result, base);
+            }
+        }
+        result
+    }
+}
+
+/// How the test ended for an odd n that got past the small factors
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    ProbablePrime,
+    Divisor(u128),
+    Square,
+    /// The stage of the test that failed, with its parameters
+    Failed(Stage, u128, u128),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    NotInteger,
+    Norm,
+    Strong,
+}
+
+/// The first (b, c) with (-c/n) = 1 and (b² + 4c / n) = -1, trying b from 1
+/// to 8 for each c from 2 on, or a proper divisor of `n` the search ran into.
+/// `n` must be odd and not a perfect square. c = 1 is left out: it often
+/// leads to x² - x - 1, whose Fibonacci pseudoprimes 5777 and 10877 pass.
+fn parameters(n: u128) -> Result<(u128, u128), u128> {
+    // A symbol of 0 means a proper factor when the value is below n
+    let factor = |value: u128| {
+        let d = gcd((value % n) as u64, (n % value) as u64) as u128;
+        (d > 1 && d < n).then_some(d)
+    };
+
+    let mut c = 2;
+    loop {
+        match jacobi(n - c % n, n) {
+            1 => {
+                for b in 1..=MAX_B {
+                    let delta = b * b + 4 * c;
+                    match jacobi(delta % n, n) {
+                        -1 => return Ok((b, c)),
+                        0 => {
+                            if let Some(d) = factor(delta) {
+                                return Err(d);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            0 => {
+                if let Some(d) = factor(c) {
+                    return Err(d);
+                }
+            }
+            _ => {}
+        }
+        c += 1;
+    }
+}
+
+// The quadratic Frobenius test of an odd n ≥ 53² with no factor below 53,
+// reporting each value it computes to `step`
+fn run(n: u128, mut step: impl FnMut(Stage, Element)) -> Outcome {
+    if n.isqrt() * n.isqrt() == n {
+        return Outcome::Square;
+    }
+    let (b, c) = match parameters(n) {
+        Ok(parameters) => parameters,
+        Err(d) => return Outcome::Divisor(d),
+    };
+    let ring = Ring { n, b, c };
+    let x = Element { u: 0, v: 1 };
+    let minus_one = Element { u: n - 1, v: 0 };
+
+    // n + 1 = 2^r1·k and n - 1 = 2^r2·m with k, m odd, so n² - 1 = 2^r·s
+    // with r = r1 + r2 and s = k·m, both exponents below n
+    let (r1, k) = (
+        (n / 2 + 1).trailing_zeros() + 1,
+        (n / 2 + 1) >> (n / 2 + 1).trailing_zeros(),
+    );
+    let (r2, m) = (
+        (n - 1).trailing_zeros(),
+        (n - 1) >> (n - 1).trailing_zeros(),
+    );
+    let r = r1 + r2;
+
+    // x^((n + 1)/2) is an integer, whose square x^(n + 1) is -c
+    let x_k = ring.pow(x, k);
+    let mut half = x_k;
+    for _ in 1..r1 {
+        half = ring.mul(half, half);
+    }
+    step(Stage::NotInteger, half);
+    if half.v != 0 {
+        return Outcome::Failed(Stage::NotInteger, b, c);
+    }
+    let norm = ring.mul(half, half);
+    step(Stage::Norm, norm);
+    if norm != (Element { u: n - c % n, v: 0 }) {
+        return Outcome::Failed(Stage::Norm, b, c);
+    }
+
+    // x^s ≡ 1, or x^(2^j·s) ≡ -1 for some j ≤ r - 2
+    let mut power = ring.pow(x_k, m);
+    step(Stage::Strong, power);
+    if power == (Element { u: 1, v: 0 }) || power == minus_one {
+        return Outcome::ProbablePrime;
+    }
+    for _ in 1..r - 1 {
+        power = ring.mul(power, power);
+        step(Stage::Strong, power);
+        if power == minus_one {
+            return Outcome::ProbablePrime;
+        }
+    }
+    Outcome::Failed(Stage::Strong, b, c)
+}
+
+fn factor_base() -> &'static [u64] {
+    small_primes::first(SMALL_PRIME_COUNT)
+}
+
+/// Returns true if `n` is a Frobenius probable prime
+pub fn is_prime(n: u64) -> bool {
+    is_prime_u128(n as u128)
+}
+
+/// [`is_prime`] for 128-bit inputs
+pub fn is_prime_u128(n: u128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in factor_base() {
+        let p = p as u128;
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    n < SMALL_FACTOR_BOUND || run(n, |_, _| {}) == Outcome::ProbablePrime
+}
+
+/// The stage at which the test rejects `n`, or None if it accepts `n`
+pub fn evidence(n: u64) -> Option<Evidence> {
+    if n < 2 {
+        return Some(Evidence::BelowTwo);
+    }
+    if let Some(&p) = factor_base().iter().find(|&&p| n.is_multiple_of(p)) {
+        return (n != p).then_some(Evidence::Divisor(p));
+    }
+    if (n as u128) < SMALL_FACTOR_BOUND {
+        return None;
+    }
+
+    match run(n as u128, |_, _| {}) {
+        Outcome::ProbablePrime => None,
+        Outcome::Divisor(d) => Some(Evidence::Divisor(d as u64)),
+        Outcome::Square => Some(Evidence::PerfectPower {
+            base: n.isqrt(),
+            exponent: 2,
+        }),
+        Outcome::Failed(_, b, c) => Some(Evidence::FrobeniusTest {
+            b: b as u64,
+            c: c as u64,
+        }),
+    }
+}
+
+/// [`is_prime`], describing the small factors, the parameters and every
+/// power of x the test checks to `step`
+pub fn trace(n: u64, step: TraceFn<'_>) -> bool {
+    if n < 2 {
+        step(format_args!("{} is below 2, so it is not prime", n));
+        return false;
+    }
+    if let Some(&p) = factor_base().iter().find(|&&p| n.is_multiple_of(p)) {
+        step(format_args!(
+            "{} mod {} = 0: {}",
+            n,
+            p,
+            if n == p { "prime" } else { "composite" }
+        ));
+        return n == p;
+    }
+    step(format_args!("no factor among the primes up to 47"));
+    if (n as u128) < SMALL_FACTOR_BOUND {
+        step(format_args!("{} < 53², so it is prime", n));
+        return true;
+    }
+
+    if let Some((b, c)) = (n.isqrt() * n.isqrt() != n)
+        .then(|| parameters(n as u128).ok())
+        .flatten()
+    {
+        step(format_args!(
+            "(-{}/{}) = 1 and ({}/{}) = -1: working mod x² - {}x - {}",
+            c,
+            n,
+            b * b + 4 * c,
+            n,
+            b,
+            c
+        ));
+    }
+
+    let mut squarings = 0;
+    let outcome = run(n as u128, |stage, e| match stage {
+        Stage::NotInteger => step(format_args!("x^((n + 1)/2) = {} + {}x", e.u, e.v)),
+        Stage::Norm => step(format_args!("x^(n + 1) = {} + {}x", e.u, e.v)),
+        Stage::Strong => {
+            step(format_args!("x^(2^{}·s) = {} + {}x", squarings, e.u, e.v));
+            squarings += 1;
+        }
+    });
+    match outcome {
+        Outcome::ProbablePrime => step(format_args!("Frobenius probable prime")),
+        Outcome::Divisor(d) => step(format_args!(
+            "{} shares the factor {} with n: composite",
+            n, d
+        )),
+        Outcome::Square => step(format_args!(
+            "{} = {}², so no parameters exist: composite",
+            n,
+            n.isqrt()
+        )),
+        Outcome::Failed(Stage::NotInteger, _, _) => {
+            step(format_args!("x^((n + 1)/2) is not an integer: composite"))
+        }
+        Outcome::Failed(Stage::Norm, _, c) => {
+            step(format_args!("x^(n + 1) ≢ -{} (mod n): composite", c))
+        }
+        Outcome::Failed(Stage::Strong, _, _) => step(format_args!(
+            "x^s ≢ 1 and no x^(2^j·s) ≡ -1 for j ≤ r - 2: composite"
+        )),
+    }
+    outcome == Outcome::ProbablePrime
+}
+
+pub struct Frobenius;
+
+impl PrimalityTest for Frobenius {
+    fn name(&self) -> &str {
+        "frobenius"
+    }
+
+    fn description(&self) -> &str {
+        "Grantham's quadratic Frobenius test, with the first suitable parameters"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128(n)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace(n, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::miller_rabin;
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        for n in 0..200_000 {
+            assert_eq!(is_prime(n), miller_rabin::is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_pseudoprimes() {
+        // Strong pseudoprimes to base 2, Carmichael numbers, and strong
+        // Lucas pseudoprimes
+        for n in [
+            2047, 3277, 4033, 4681, 8321, 252601, 3215031751, 5459, 5777, 10877,
+        ] {
+            assert!(!is_prime(n), "{n}");
+        }
+        assert!(!is_prime_u128(318665857834031151167461));
+        assert!(!is_prime_u128(u128::MAX));
+        assert!(!is_prime_u128(3 * 18446744073709551629));
+    }
+
+    #[test]
+    fn test_large_primes() {
+        assert!(is_prime(18446744073709551557)); // Largest prime < 2^64
+        assert!(is_prime_u128(u128::MAX - 158)); // 2^128 - 159
+        assert!(is_prime_u128((1 << 127) - 1));
+        assert!(is_prime_u128(18446744073709551629)); // 2^64 + 13
+    }
+
+    #[test]
+    fn test_evidence() {
+        assert_eq!(evidence(1), Some(Evidence::BelowTwo));
+        assert_eq!(evidence(47 * 53), Some(Evidence::Divisor(47)));
+        assert_eq!(
+            evidence(53 * 53),
+            Some(Evidence::PerfectPower {
+                base: 53,
+                exponent: 2
+            })
+        );
+        assert_eq!(evidence(5777), Some(Evidence::FrobeniusTest { b: 2, c: 2 }));
+        for n in 0..10_000 {
+            assert_eq!(evidence(n).is_none(), is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(trace(1_000_003, &mut |step| steps.push(step.to_string())));
+        assert_eq!(steps.last().unwrap(), "Frobenius probable prime");
+
+        steps.clear();
+        assert!(!trace(5777, &mut |step| steps.push(step.to_string())));
+        assert!(steps.last().unwrap().ends_with("composite"));
+    }
+}
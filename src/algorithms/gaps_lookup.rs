@@ -0,0 +1,159 @@
+//! Lookup in a previously computed list of primes
+//!
+//! Answers from a `.gaps` file (see [`crate::benchmark::compact`]) written by
+//! `sieve --gaps` or `benchmark --gaps`, so a sieve run once becomes a test
+//! that costs a binary search. Registered only when `gaps-lookup.file` is set, and
+//! only for the numbers the file's `run.covered-up-to` vouches for: past it, a
+//! missing number may be a prime the run never reached.
+
+use std::ops::RangeInclusive;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{PrimalityTest, TraceFn};
+use crate::benchmark::compact::GapLookup;
+use crate::error::{Error, Result};
+
+pub struct GapsLookup {
+    primes: GapLookup,
+    covered_up_to: u64,
+}
+
+impl GapsLookup {
+    /// Loads the file named by the `gaps-lookup.file` knob, None if it is not set
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Option<Self>> {
+        let Some(path) = config.get::<String>("gaps-lookup.file")? else {
+            return Ok(None);
+        };
+
+        let primes = GapLookup::load(&path)?;
+        let covered_up_to =
+            primes
+                .file()
+                .covered_up_to()
+                .ok_or_else(|| Error::InvalidConfigValue {
+                    key: "gaps-lookup.file".to_string(),
+                    value: path.clone(),
+                    hint: Some(
+                        "the file does not record run.covered-up-to; write it with `sieve --gaps`"
+                            .to_string(),
+                    ),
+                })?;
+
+        Ok(Some(GapsLookup {
+            primes,
+            covered_up_to,
+        }))
+    }
+}
+
+impl PrimalityTest for GapsLookup {
+    fn name(&self) -> &str {
+        "gaps-lookup"
+    }
+
+    fn description(&self) -> &str {
+        "Binary search in the .gaps prime list named by gaps-lookup.file"
+    }
+
+    fn supported_range(&self) -> RangeInclusive<u64> {
+        0..=self.covered_up_to
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        self.primes.contains(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let file = self.primes.file();
+        step(format_args!(
+            "{} holds the {} primes up to {} in {} blocks",
+            file.path().display(),
+            file.len(),
+            self.covered_up_to,
+            file.blocks().len()
+        ));
+
+        let is_prime = match self.primes.block_of(n) {
+            Some(i) => {
+                let block = &file.blocks()[i];
+                step(format_args!(
+                    "block {} spans [{}, {}]: decompressing its {} primes",
+                    i, block.first, block.last, block.count
+                ));
+                let is_prime = self.primes.block(i).binary_search(&n).is_ok();
+                step(format_args!(
+                    "{} is {}in the block: {}",
+                    n,
+                    if is_prime { "" } else { "not " },
+                    if is_prime { "prime" } else { "composite" }
+                ));
+                is_prime
+            }
+            None => {
+                step(format_args!(
+                    "{} falls between blocks, where there are no primes: composite",
+                    n
+                ));
+                false
+            }
+        };
+        Some(is_prime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+    use crate::benchmark::compact::write_gaps;
+    use crate::benchmark::schema::COVERED_KEY;
+
+    fn config(path: &str) -> AlgorithmConfig {
+        AlgorithmConfig::from_pairs(&[("gaps-lookup.file".to_string(), path.to_string())]).unwrap()
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert!(
+            GapsLookup::from_config(&AlgorithmConfig::default())
+                .unwrap()
+                .is_none()
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("ptc-gaps-lookup-{}.gaps", std::process::id()));
+        let primes = sieve(10_000);
+        let covered = [(COVERED_KEY.to_string(), "10000".to_string())];
+        write_gaps(&path, primes.iter().copied(), &covered).unwrap();
+
+        let lookup = GapsLookup::from_config(&config(path.to_str().unwrap()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup.supported_range(), 0..=10_000);
+        for n in 0..=10_000 {
+            assert_eq!(
+                lookup.is_prime(n),
+                primes.binary_search(&n).is_ok(),
+                "{}",
+                n
+            );
+        }
+
+        let mut steps = Vec::new();
+        assert_eq!(
+            lookup.trace(9973, &mut |line| steps.push(line.to_string())),
+            Some(true)
+        );
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[2], "9973 is in the block: prime");
+
+        // Without a recorded coverage, a missing number proves nothing
+        write_gaps(&path, primes.iter().copied(), &[]).unwrap();
+        assert!(matches!(
+            GapsLookup::from_config(&config(path.to_str().unwrap())),
+            Err(Error::InvalidConfigValue { .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
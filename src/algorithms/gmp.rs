@@ -0,0 +1,117 @@
+//! GMP's probable-prime test as a baseline primality test
+//!
+//! `mpz_probab_prime_p`, reached through rug's
+//! [`Integer::is_probably_prime`], is what most software calls to test a
+//! number, so it is the yardstick the crate's own tests are measured against.
+//! It trial-divides, then runs Baillie-PSW and `gmp.reps - 24` further
+//! Miller-Rabin rounds with random bases; below 2^64 Baillie-PSW alone is
+//! deterministic, so GMP answers "definitely prime" there. rug builds GMP
+//! from source, which needs `m4` to build with the `gmp` feature.
+
+use num_bigint::BigUint;
+use rug::Integer;
+use rug::integer::{IsPrime, Order};
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{PrimalityTest, TraceFn};
+use crate::error::Result;
+
+/// Miller-Rabin rounds asked for when `gmp.reps` is not set, as in rug's docs
+pub const DEFAULT_REPS: u32 = 30;
+
+pub struct Gmp {
+    reps: u32,
+}
+
+impl Gmp {
+    /// Reads the `gmp.reps` knob
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        Ok(Gmp {
+            reps: config.get("gmp.reps")?.unwrap_or(DEFAULT_REPS),
+        })
+    }
+
+    fn test(&self, n: Integer) -> IsPrime {
+        n.is_probably_prime(self.reps)
+    }
+}
+
+impl Default for Gmp {
+    fn default() -> Self {
+        Gmp { reps: DEFAULT_REPS }
+    }
+}
+
+impl PrimalityTest for Gmp {
+    fn name(&self) -> &str {
+        "gmp"
+    }
+
+    fn description(&self) -> &str {
+        "GMP's mpz_probab_prime_p through rug, Baillie-PSW plus random Miller-Rabin rounds"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        self.test(Integer::from(n)) != IsPrime::No
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        self.test(Integer::from(n)) != IsPrime::No
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        let n = Integer::from_digits(&n.to_bytes_le(), Order::Lsf);
+        Some(self.test(n) != IsPrime::No)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        step(format_args!(
+            "mpz_probab_prime_p({}, {}): trial division, Baillie-PSW, then random bases",
+            n, self.reps
+        ));
+        let verdict = self.test(Integer::from(n));
+        step(format_args!(
+            "{}",
+            match verdict {
+                IsPrime::Yes => "GMP proves it: prime",
+                IsPrime::Probably => "GMP finds no witness: probably prime",
+                IsPrime::No => "GMP finds a witness: composite",
+            }
+        ));
+        Some(verdict != IsPrime::No)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_agrees_with_sieve() {
+        let gmp = Gmp::default();
+        let primes = sieve(100_000);
+        for n in 0..=100_000 {
+            assert_eq!(gmp.is_prime(n), primes.binary_search(&n).is_ok(), "{}", n);
+        }
+        // 2^64 - 59 is the largest 64-bit prime, 3 · (2^64 + 13) is composite
+        assert!(gmp.is_prime(18446744073709551557));
+        assert!(!gmp.is_prime_u128(3 * 18446744073709551629));
+
+        // 2^521 - 1 is a Mersenne prime, 2^523 - 1 is not
+        let mersenne = |p: u32| (BigUint::from(1u32) << p) - 1u32;
+        assert_eq!(gmp.is_prime_big(&mersenne(521)), Some(true));
+        assert_eq!(gmp.is_prime_big(&mersenne(523)), Some(false));
+
+        let mut steps = Vec::new();
+        assert_eq!(
+            gmp.trace(97, &mut |line| steps.push(line.to_string())),
+            Some(true)
+        );
+        assert_eq!(steps[1], "GMP proves it: prime");
+    }
+}
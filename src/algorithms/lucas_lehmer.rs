@@ -0,0 +1,268 @@
+//! Lucas-Lehmer test for Mersenne numbers M_p = 2^p - 1
+//!
+//! For an odd prime p, M_p is prime if and only if s_(p-2) ≡ 0 (mod M_p),
+//! where s_0 = 4 and s_(i+1) = s_i² - 2. Results are reported the way GIMPS
+//! reports them: the residue s_(p-2) mod M_p by its low 64 bits as 16
+//! hexadecimal digits, the "Res64" an independent double-check of the same
+//! exponent must reproduce. Interim residues at checkpoints along the way
+//! locate where two runs first diverged.
+
+use std::fmt;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+use crate::core_algorithms::trial_division;
+
+/// The low 64 bits of a residue, printed as 16 uppercase hex digits
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Res64(pub u64);
+
+impl Res64 {
+    pub fn of(residue: &BigUint) -> Res64 {
+        Res64(residue.iter_u64_digits().next().unwrap_or(0))
+    }
+}
+
+impl fmt::Display for Res64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016X}", self.0)
+    }
+}
+
+impl FromStr for Res64 {
+    type Err = String;
+
+    /// Up to 16 hex digits in either case, with or without `0x`
+    fn from_str(s: &str) -> Result<Res64, String> {
+        let digits = s.trim();
+        let digits = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+            .unwrap_or(digits);
+        if digits.is_empty() || digits.len() > 16 {
+            return Err(format!("'{}' is not 1 to 16 hex digits", s));
+        }
+        u64::from_str_radix(digits, 16)
+            .map(Res64)
+            .map_err(|_| format!("'{}' is not a hexadecimal residue", s))
+    }
+}
+
+/// 2^p - 1
+pub fn mersenne(p: u32) -> BigUint {
+    (BigUint::from(1u32) << p) - 1u32
+}
+
+/// A divisor 2^a - 1 of M_p for a composite p, from its smallest prime
+/// factor a; None if p is prime or below 2
+pub fn exponent_factor(p: u32) -> Option<u32> {
+    (p >= 2 && !trial_division::is_prime(p.into())).then(|| {
+        (2..p)
+            .find(|a| p.is_multiple_of(*a))
+            .expect("p is composite")
+    })
+}
+
+/// s_(p-2) mod M_p for an odd prime `p`, calling `on_iteration(i, &s_i)`
+/// after each of the p - 2 iterations
+///
+/// # Panics
+///
+/// If `p` is below 3.
+pub fn residue(p: u32, mut on_iteration: impl FnMut(u32, &BigUint)) -> BigUint {
+    assert!(
+        p >= 3,
+        "Lucas-Lehmer needs an odd prime exponent, got {}",
+        p
+    );
+
+    let m = mersenne(p);
+    let mut s = BigUint::from(4u32);
+    for i in 1..=p - 2 {
+        // s² - 2, kept non-negative by adding M_p first
+        s = reduce(&s * &s + &m - 2u32, p, &m);
+        on_iteration(i, &s);
+    }
+    s
+}
+
+/// True if 2^p - 1 is prime
+pub fn is_mersenne_prime(p: u32) -> bool {
+    match p {
+        0 | 1 => false,
+        2 => true,
+        _ if exponent_factor(p).is_some() => false,
+        _ => residue(p, |_, _| {}).bits() == 0,
+    }
+}
+
+/// Lucas-Lehmer as a [`PrimalityTest`] of the one number 2^p - 1, so it can
+/// run beside the general tests on it. Every other input is outside its
+/// supported range.
+pub struct LucasLehmer {
+    p: u32,
+}
+
+impl LucasLehmer {
+    pub fn new(p: u32) -> Self {
+        LucasLehmer { p }
+    }
+}
+
+impl PrimalityTest for LucasLehmer {
+    fn name(&self) -> &str {
+        "lucas-lehmer"
+    }
+
+    fn description(&self) -> &str {
+        "Lucas-Lehmer test of a Mersenne number 2^p - 1"
+    }
+
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        match u64::try_from(mersenne(self.p)) {
+            Ok(m) => m..=m,
+            #[allow(clippy::reversed_empty_ranges)]
+            Err(_) => 1..=0,
+        }
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        debug_assert_eq!(BigUint::from(n), mersenne(self.p));
+        is_mersenne_prime(self.p)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        match u128::try_from(mersenne(self.p)) {
+            Ok(m) => m..=m,
+            #[allow(clippy::reversed_empty_ranges)]
+            Err(_) => 1..=0,
+        }
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        debug_assert_eq!(BigUint::from(n), mersenne(self.p));
+        is_mersenne_prime(self.p)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        (*n == mersenne(self.p)).then(|| is_mersenne_prime(self.p))
+    }
+
+    fn evidence(&self, _n: u64) -> Option<Evidence> {
+        match self.p {
+            0 | 1 => Some(Evidence::BelowTwo),
+            p => exponent_factor(p).map(|a| Evidence::Divisor((1 << a) - 1)),
+        }
+    }
+
+    fn trace(&self, _n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let p = self.p;
+        if p < 3 {
+            let is_prime = is_mersenne_prime(p);
+            step(format_args!(
+                "p = {} is below 3, decided directly: {}",
+                p,
+                if is_prime { "prime" } else { "composite" }
+            ));
+            return Some(is_prime);
+        }
+        if let Some(a) = exponent_factor(p) {
+            step(format_args!(
+                "p = {} is divisible by {}, so 2^{} - 1 divides 2^{} - 1: composite",
+                p, a, a, p
+            ));
+            return Some(false);
+        }
+
+        step(format_args!("s_0 = 4"));
+        let s = residue(p, |i, s| step(format_args!("s_{} = {}", i, s)));
+        let is_prime = s.bits() == 0;
+        step(format_args!(
+            "s_{} {} 0 (mod 2^{} - 1): {}",
+            p - 2,
+            if is_prime { "≡" } else { "≢" },
+            p,
+            if is_prime { "prime" } else { "composite" }
+        ));
+        Some(is_prime)
+    }
+}
+
+// x mod 2^p - 1 without division: 2^p ≡ 1, so the bits above p fold back
+// onto the low ones
+fn reduce(mut x: BigUint, p: u32, m: &BigUint) -> BigUint {
+    while x.bits() > u64::from(p) {
+        x = (&x & m) + (&x >> p);
+    }
+    if &x == m { BigUint::ZERO } else { x }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mersenne_primes() {
+        let exponents: Vec<u32> = (0..130).filter(|&p| is_mersenne_prime(p)).collect();
+        assert_eq!(exponents, [2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127]);
+        assert!(is_mersenne_prime(521));
+    }
+
+    #[test]
+    fn test_residues() {
+        // s_1..s_9 mod 2047: 14, 194, 788, 701, 119, 1877, 240, 282, 1736
+        let mut interim = Vec::new();
+        let last = residue(11, |i, s| interim.push((i, Res64::of(s).0)));
+        assert_eq!(interim.len(), 9);
+        assert_eq!(interim[..3], [(1, 14), (2, 194), (3, 788)]);
+        assert_eq!(Res64::of(&last).to_string(), "00000000000006C8");
+
+        assert_eq!(Res64::of(&residue(127, |_, _| {})), Res64(0));
+        assert_eq!(exponent_factor(11), None);
+        assert_eq!(exponent_factor(15), Some(3));
+    }
+
+    #[test]
+    fn test_as_primality_test() {
+        // M11 = 2047 = 23 · 89, M61 and M127 are prime, M4 = 15 = 3 · 5
+        assert_eq!(
+            LucasLehmer::new(11).is_prime_big(&mersenne(11)),
+            Some(false)
+        );
+        assert_eq!(LucasLehmer::new(61).is_prime_big(&mersenne(61)), Some(true));
+        assert_eq!(
+            LucasLehmer::new(127).is_prime_big(&mersenne(127)),
+            Some(true)
+        );
+        assert_eq!(LucasLehmer::new(127).is_prime_big(&mersenne(61)), None);
+        assert_eq!(LucasLehmer::new(4).evidence(15), Some(Evidence::Divisor(3)));
+        assert_eq!(LucasLehmer::new(200).supported_range_u128().count(), 0);
+
+        let mut steps = Vec::new();
+        assert_eq!(
+            LucasLehmer::new(5).trace(31, &mut |step| steps.push(step.to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            steps,
+            [
+                "s_0 = 4",
+                "s_1 = 14",
+                "s_2 = 8",
+                "s_3 = 0",
+                "s_3 ≡ 0 (mod 2^5 - 1): prime"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_res64() {
+        assert_eq!("00000000000006C8".parse(), Ok(Res64(0x6C8)));
+        assert_eq!("0x6c8".parse(), Ok(Res64(0x6C8)));
+        for input in ["", "0x", "00000000000006C8A", "6G8"] {
+            assert!(input.parse::<Res64>().is_err(), "{:?}", input);
+        }
+    }
+}
@@ -1,97 +1,266 @@
-/// Miller-Rabin primality test
-/// This is a probabilistic primality test, but for u64 values we use
-/// a deterministic set of witnesses that guarantees correctness.
+//! Deterministic Miller-Rabin strong probable prime test
+//!
+//! The fixed-width test lives in the `no_std`
+//! [`core_algorithms::miller_rabin`](crate::core_algorithms::miller_rabin)
+//! module and is re-exported here; this module adds the arbitrary-precision
+//! variant and the registry type.
 
-pub fn is_prime(n: u64) -> bool {
-    // Handle small cases
-    if n <= 1 {
-        return false;
-    }
-    if n <= 3 {
-        return true;
-    }
-    if n % 2 == 0 || n % 3 == 0 {
-        return false;
-    }
-    if n < 9 {
-        return true; // 5 and 7
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+use crate::error::{Error, Result};
+use crate::math::{mod_pow, mod_sqr};
+
+pub use crate::core_algorithms::miller_rabin::{
+    DEFAULT_WITNESSES, WITNESSES_U32, find_witness, is_prime, is_prime_u32, is_prime_u128,
+    is_prime_u128_with_witnesses, is_prime_with_witnesses,
+};
+
+/// Named witness sets for the `miller-rabin.witnesses` knob, with what they
+/// guarantee, from cheapest to the default
+pub const WITNESS_PRESETS: &[(&str, &[u64], &str)] = &[
+    (
+        "sprp2",
+        &[2],
+        "base 2 only, fooled by strong pseudoprimes like 2047",
+    ),
+    (
+        "deterministic32",
+        &[2, 7, 61],
+        "deterministic below 4,759,123,141 (Jaeschke)",
+    ),
+    (
+        "deterministic64",
+        &[2, 325, 9375, 28178, 450775, 9780504, 1795265022],
+        "deterministic below 2^64 with 7 bases (Sinclair)",
+    ),
+    (
+        "default",
+        &DEFAULT_WITNESSES,
+        "the 12 primes up to 37, deterministic below 3.18 × 10^23",
+    ),
+];
+
+/// The witnesses of the preset called `name`
+pub fn witness_preset(name: &str) -> Option<&'static [u64]> {
+    WITNESS_PRESETS
+        .iter()
+        .find(|(preset, _, _)| *preset == name)
+        .map(|&(_, witnesses, _)| witnesses)
+}
+
+/// Why Miller-Rabin rejects `n` with the given witnesses, None if it accepts `n`
+pub fn evidence_with_witnesses(n: u64, witnesses: &[u64]) -> Option<Evidence> {
+    match n {
+        0 | 1 => Some(Evidence::BelowTwo),
+        2 | 3 => None,
+        _ if n.is_multiple_of(2) => Some(Evidence::Divisor(2)),
+        _ if n.is_multiple_of(3) => Some(Evidence::Divisor(3)),
+        5 | 7 => None,
+        _ => find_witness(n, witnesses).map(Evidence::Witness),
     }
+}
 
-    // For u64, these witnesses are sufficient for deterministic results
-    // This set is proven to work correctly for all n < 2^64
-    let witnesses: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+/// [`is_prime_with_witnesses`], describing the decomposition of n - 1 and
+/// every x = a^d mod n and squaring to `step`
+pub fn trace_with_witnesses(n: u64, witnesses: &[u64], step: TraceFn<'_>) -> bool {
+    if n < 9 || n.is_multiple_of(2) || n.is_multiple_of(3) {
+        let is_prime = is_prime_with_witnesses(n, witnesses);
+        step(format_args!(
+            "{} is below 9 or divisible by 2 or 3, decided directly: {}",
+            n,
+            if is_prime { "prime" } else { "composite" }
+        ));
+        return is_prime;
+    }
 
-    // Decompose n-1 = 2^r * d once, shared across all witnesses
     let mut d = n - 1;
     let mut r = 0u32;
     while d & 1 == 0 {
         d >>= 1;
         r += 1;
     }
+    step(format_args!("n - 1 = {} = 2^{} · {}", n - 1, r, d));
 
-    for &witness in &witnesses {
-        if n == witness {
-            return true;
+    for &a in witnesses {
+        if a.is_multiple_of(n) {
+            step(format_args!("a = {}: a multiple of n, skipped", a));
+            continue;
+        }
+
+        let mut x = mod_pow(a, d, n);
+        step(format_args!("a = {}: x = {}^{} mod {} = {}", a, a, d, n, x));
+        if x == 1 || x == n - 1 {
+            step(format_args!("x = ±1, strong probable prime to base {}", a));
+            continue;
+        }
+
+        let mut reached = false;
+        for _ in 1..r {
+            x = mod_sqr(x, n);
+            step(format_args!("x² mod {} = {}", n, x));
+            if x == n - 1 {
+                reached = true;
+                break;
+            }
         }
-        if !miller_rabin_test(n, witness, d, r) {
+        if !reached {
+            step(format_args!(
+                "x never reached n - 1: {} is a witness, composite",
+                a
+            ));
             return false;
         }
+        step(format_args!("x = -1, strong probable prime to base {}", a));
     }
 
+    step(format_args!("strong probable prime to every witness"));
     true
 }
 
-/// Performs modular exponentiation: (base^exp) mod m
-/// Uses u128 intermediates to avoid overflow for u64 inputs.
-#[inline]
-fn mod_pow(base: u64, mut exp: u64, m: u64) -> u64 {
-    if m == 1 {
-        return 0;
+/// Miller-Rabin over arbitrary-precision integers with the default witnesses,
+/// a strong probable prime test for inputs beyond u128
+pub fn is_prime_big(n: &BigUint) -> bool {
+    is_prime_big_with_witnesses(n, &DEFAULT_WITNESSES)
+}
+
+/// Miller-Rabin over arbitrary-precision integers with a custom witness set.
+/// Inputs that fit in a u128 take the fixed-width path.
+pub fn is_prime_big_with_witnesses(n: &BigUint, witnesses: &[u64]) -> bool {
+    if let Ok(n) = u128::try_from(n) {
+        return is_prime_u128_with_witnesses(n, witnesses);
+    }
+    if !n.bit(0) {
+        return false;
     }
 
-    let m128 = m as u128;
-    let mut base128 = (base % m) as u128;
-    let mut result: u128 = 1;
+    let n_minus_one = n - 1u32;
+    let r = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> r;
 
-    while exp > 0 {
-        if exp & 1 == 1 {
-            result = (result * base128) % m128;
+    witnesses.iter().all(|&witness| {
+        let mut x = BigUint::from(witness).modpow(&d, n);
+        if x == BigUint::from(1u32) || x == n_minus_one {
+            return true;
         }
-        exp >>= 1;
-        base128 = (base128 * base128) % m128;
+
+        for _ in 0..r - 1 {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                return true;
+            }
+        }
+
+        false
+    })
+}
+
+pub struct MillerRabin {
+    witnesses: Vec<u64>,
+}
+
+impl MillerRabin {
+    pub fn with_witnesses(witnesses: Vec<u64>) -> Self {
+        MillerRabin { witnesses }
     }
 
-    result as u64
+    /// Reads the `miller-rabin.witnesses` knob, a list of bases or the name
+    /// of one of the [`WITNESS_PRESETS`], defaulting to [`DEFAULT_WITNESSES`]
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        const KEY: &str = "miller-rabin.witnesses";
+
+        let Some(value) = config.get::<String>(KEY)? else {
+            return Ok(MillerRabin {
+                witnesses: DEFAULT_WITNESSES.to_vec(),
+            });
+        };
+        let witnesses = match witness_preset(value.trim()) {
+            Some(witnesses) => witnesses.to_vec(),
+            None => value
+                .split(',')
+                .map(|base| base.trim().parse())
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| {
+                    let presets: Vec<&str> =
+                        WITNESS_PRESETS.iter().map(|(name, _, _)| *name).collect();
+                    Error::InvalidConfigValue {
+                        key: KEY.to_string(),
+                        value: value.clone(),
+                        hint: Some(format!(
+                            "expected comma-separated bases or one of: {}",
+                            presets.join(", ")
+                        )),
+                    }
+                })?,
+        };
+
+        Ok(MillerRabin { witnesses })
+    }
 }
 
-/// Squaring modulo n using u128 to avoid overflow.
-#[inline(always)]
-fn mod_sqr(x: u64, n: u64) -> u64 {
-    let x128 = x as u128;
-    let n128 = n as u128;
-    ((x128 * x128) % n128) as u64
+impl PrimalityTest for MillerRabin {
+    fn name(&self) -> &str {
+        "miller-rabin"
+    }
+
+    fn description(&self) -> &str {
+        "Strong probable prime test, deterministic for u64 with 12 witnesses"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime_with_witnesses(n, &self.witnesses)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128_with_witnesses(n, &self.witnesses)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(is_prime_big_with_witnesses(n, &self.witnesses))
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence_with_witnesses(n, &self.witnesses)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_witnesses(n, &self.witnesses, step))
+    }
 }
+/// Miller-Rabin for 32-bit inputs with 3 witnesses in 64-bit arithmetic, to
+/// measure what the narrower arithmetic buys against [`MillerRabin`]
+pub struct MillerRabinU32;
 
-/// Performs one round of the Miller-Rabin test with a given witness.
-/// Takes pre-computed d and r where n-1 = 2^r * d.
-#[inline]
-fn miller_rabin_test(n: u64, witness: u64, d: u64, r: u32) -> bool {
-    // Compute x = witness^d mod n
-    let mut x = mod_pow(witness, d, n);
+impl PrimalityTest for MillerRabinU32 {
+    fn name(&self) -> &str {
+        "miller-rabin-u32"
+    }
 
-    if x == 1 || x == n - 1 {
-        return true;
+    fn description(&self) -> &str {
+        "Strong probable prime test in 64-bit arithmetic, deterministic for n < 2^32 with 3 witnesses"
     }
 
-    // Square x (r-1) times
-    for _ in 0..r - 1 {
-        x = mod_sqr(x, n);
-        if x == n - 1 {
-            return true;
-        }
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        0..=u64::from(u32::MAX)
+    }
+
+    /// False past [`supported_range`](PrimalityTest::supported_range)
+    fn is_prime(&self, n: u64) -> bool {
+        u32::try_from(n).is_ok_and(is_prime_u32)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence_with_witnesses(n, &WITNESSES_U32.map(u64::from))
     }
 
-    false
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_witnesses(n, &WITNESSES_U32.map(u64::from), step))
+    }
 }
 
 #[cfg(test)]
@@ -99,68 +268,100 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_mod_pow() {
-        assert_eq!(mod_pow(2, 10, 1000), 24); // 2^10 mod 1000 = 1024 mod 1000 = 24
-        assert_eq!(mod_pow(3, 5, 13), 9); // 3^5 mod 13 = 243 mod 13 = 9
-        assert_eq!(mod_pow(7, 3, 11), 2); // 7^3 mod 11 = 343 mod 11 = 2
+    fn test_evidence() {
+        assert_eq!(
+            evidence_with_witnesses(1, &DEFAULT_WITNESSES),
+            Some(Evidence::BelowTwo)
+        );
+        assert_eq!(
+            evidence_with_witnesses(1000, &DEFAULT_WITNESSES),
+            Some(Evidence::Divisor(2))
+        );
+        assert_eq!(
+            evidence_with_witnesses(2047, &[2, 3]),
+            Some(Evidence::Witness(3))
+        );
+        assert_eq!(evidence_with_witnesses(2047, &[2]), None); // Strong pseudoprime
+        assert_eq!(evidence_with_witnesses(7, &DEFAULT_WITNESSES), None);
     }
 
     #[test]
-    fn test_edge_cases() {
-        assert!(!is_prime(0));
-        assert!(!is_prime(1));
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(!trace_with_witnesses(2047, &[2, 3], &mut |step| {
+            steps.push(step.to_string())
+        }));
+        assert_eq!(
+            steps,
+            [
+                "n - 1 = 2046 = 2^1 · 1023",
+                "a = 2: x = 2^1023 mod 2047 = 1",
+                "x = ±1, strong probable prime to base 2",
+                "a = 3: x = 3^1023 mod 2047 = 1565",
+                "x never reached n - 1: 3 is a witness, composite",
+            ]
+        );
     }
 
     #[test]
-    fn test_small_primes() {
-        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
-        for &p in &primes {
-            assert!(is_prime(p));
-        }
-    }
+    fn test_witness_presets() {
+        let config = |value: &str| {
+            AlgorithmConfig::from_pairs(&[(
+                "miller-rabin.witnesses".to_string(),
+                value.to_string(),
+            )])
+            .unwrap()
+        };
 
-    #[test]
-    fn test_small_composites() {
-        let composites = [4, 6, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 22, 24, 25];
-        for &c in &composites {
-            assert!(!is_prime(c));
-        }
-    }
+        let sprp2 = MillerRabin::from_config(&config("sprp2")).unwrap();
+        assert!(sprp2.is_prime(2047)); // Strong pseudoprime to base 2
+        assert_eq!(
+            MillerRabin::from_config(&config("2, 3")).unwrap().witnesses,
+            [2, 3]
+        );
+        assert!(matches!(
+            MillerRabin::from_config(&config("deterministic")),
+            Err(Error::InvalidConfigValue { hint: Some(_), .. })
+        ));
 
-    #[test]
-    fn test_larger_primes() {
-        let primes = [97, 541, 7919, 104729];
-        for &p in &primes {
-            assert!(is_prime(p));
-        }
-    }
+        // 3215031751 is a strong pseudoprime to 2, 3, 5 and 7, but not to 61
+        let deterministic32 = witness_preset("deterministic32").unwrap();
+        assert!(!is_prime_with_witnesses(3215031751, deterministic32));
+        assert!(is_prime_with_witnesses(4_294_967_291, deterministic32));
 
-    #[test]
-    fn test_larger_composites() {
-        let composites = [100, 1000, 10000, 52939758, 1029105];
-        for &c in &composites {
-            assert!(!is_prime(c));
+        // Sinclair's bases agree with the default ones, including on bases
+        // that are multiples of n and on strong pseudoprimes to small bases
+        let deterministic64 = witness_preset("deterministic64").unwrap();
+        let pseudoprimes = [2047, 1373653, 25326001, 3215031751, 3825123056546413051];
+        for n in (0..10_000)
+            .chain(pseudoprimes)
+            .chain(u64::MAX - 10_000..=u64::MAX)
+        {
+            assert_eq!(
+                is_prime_with_witnesses(n, deterministic64),
+                is_prime(n),
+                "{}",
+                n
+            );
         }
     }
 
     #[test]
-    fn test_large_primes() {
-        assert!(is_prime(2147483647)); // 2^31 - 1 (Mersenne prime)
-        assert!(is_prime(4294967291)); // Largest prime < 2^32
-    }
+    fn test_big() {
+        let one = BigUint::from(1u32);
+        assert!(is_prime_big(&((&one << 521) - 1u32))); // Mersenne prime
+        assert!(is_prime_big(&((&one << 607) - 1u32))); // Mersenne prime
+        assert!(!is_prime_big(&((&one << 523) - 1u32)));
+        assert!(!is_prime_big(&(&one << 300)));
 
-    // https://en.wikipedia.org/wiki/Carmichael_number
-    #[test]
-    fn test_carmichael_numbers() {
-        // Carmichael numbers that fool many probabilistic tests
-        let carmichael_numbers = [
-            561,  // 3 × 11 × 17
-            1105, // 5 × 13 × 17
-            1729, // 7 × 13 × 19
-        ];
-
-        for &c in &carmichael_numbers {
-            assert!(!is_prime(c));
-        }
+        // Product of the two Mersenne primes above
+        let product = ((&one << 521) - 1u32) * ((&one << 607) - 1u32);
+        assert!(!is_prime_big(&product));
+
+        // Agrees with the u128 path where it applies
+        assert!(is_prime_big(&BigUint::from(u128::MAX - 158)));
+        assert!(!is_prime_big(&BigUint::from(
+            318665857834031151167461u128 * 41
+        )));
     }
 }
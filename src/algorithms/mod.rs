@@ -1,9 +1,85 @@
+//! Primality test and sieve implementations
+//!
+//! Every primality test implements [`PrimalityTest`] (and keeps a plain
+//! `is_prime(n: u64) -> bool` function) and every sieve exposes
+//! `sieve(n: u64) -> Vec<u64>`, so implementations can be swapped freely and
+//! compared in the same harness.
+
+// Algorithm-specific configuration
+pub mod config;
+
+// Primality test trait and registry
+pub mod registry;
+pub use registry::{PrimalityTest, Registry};
+
+// Result of testing one number
+pub mod result;
+pub use result::TestResult;
+
+// Why a number was found composite
+pub mod evidence;
+pub use evidence::Evidence;
+
+// Chains of bounded tests with a fallback, e.g. trial-division:1000+miller-rabin
+pub mod pipeline;
+
+// Known primes and composites to validate an algorithm against
+pub mod selfcheck;
+
+// Known primes, strong pseudoprimes and Carmichael numbers read from files
+#[cfg(feature = "datasets")]
+pub mod datasets;
+
+// Which test suits a given n, from its size, factors of n ± 1 and form
+pub mod advisor;
+
+// The primes below 2^16, shared by the algorithms that divide by them
+pub mod small_primes;
+
 // Primality Test Algorithms
 pub mod aks;
+pub mod auto;
+pub mod bpsw;
+pub mod ecpp;
+pub mod fermat;
+pub mod frobenius;
 pub mod miller_rabin;
+pub mod pocklington;
+pub mod solovay_strassen;
+pub mod strong_lucas;
 pub mod trial_division;
 pub mod trial_division_newton;
 pub mod trial_division_sqrt;
+pub mod wilson;
+
+// Lookup in a .gaps file of primes computed earlier
+#[cfg(feature = "compact")]
+pub mod gaps_lookup;
+
+// GMP's probable-prime test, a baseline for the tests above
+#[cfg(feature = "gmp")]
+pub mod gmp;
+
+// The primal and num-prime crates, baselines for the tests above
+#[cfg(feature = "num-prime")]
+pub mod num_prime;
+#[cfg(feature = "primal")]
+pub mod primal;
+
+// Tests for numbers of a special form
+pub mod lucas_lehmer;
+pub mod proth;
 
 // Sieve Algorithms
 pub mod sieve_of_eratosthenes;
+
+// The C primesieve library, a baseline for the sieve above
+#[cfg(feature = "primesieve")]
+pub mod primesieve;
+
+/// A sieve with its configuration already applied
+pub type SieveFn = Box<dyn Fn(u64) -> Vec<u64>>;
+
+/// Receives the steps of a traced run, one line each
+/// (see [`PrimalityTest::trace`])
+pub type TraceFn<'a> = &'a mut dyn FnMut(std::fmt::Arguments<'_>);
@@ -0,0 +1,83 @@
+//! The num-prime crate as a baseline primality test
+//!
+//! num-prime (<https://crates.io/crates/num-prime>) tests u64 with a hashed
+//! deterministic Miller-Rabin. Above 2^64, u128 and `BigUint` included, it is
+//! run with its Baillie-PSW configuration, a strong base-2 test and a strong
+//! Lucas test, rather than its default of Miller-Rabin with random bases, so
+//! its verdicts are as repeatable as the crate's own `bpsw`.
+
+use num_bigint::BigUint;
+use num_prime::PrimalityTestConfig;
+use num_prime::nt_funcs;
+
+use crate::algorithms::{PrimalityTest, TraceFn};
+
+pub struct NumPrime;
+
+impl PrimalityTest for NumPrime {
+    fn name(&self) -> &str {
+        "num-prime"
+    }
+
+    fn description(&self) -> &str {
+        "The num-prime crate's hashed Miller-Rabin, Baillie-PSW above 2^64"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        nt_funcs::is_prime64(n)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        nt_funcs::is_prime::<u128>(&n, Some(PrimalityTestConfig::bpsw())).probably()
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(nt_funcs::is_prime::<BigUint>(n, Some(PrimalityTestConfig::bpsw())).probably())
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        step(format_args!(
+            "num-prime is an external crate: below 2^64 it answers with a hashed deterministic Miller-Rabin"
+        ));
+        let is_prime = nt_funcs::is_prime64(n);
+        step(format_args!(
+            "{}",
+            if is_prime { "prime" } else { "composite" }
+        ));
+        Some(is_prime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_agrees_with_sieve() {
+        let primes = sieve(100_000);
+        for n in 0..=100_000 {
+            let is_prime = primes.binary_search(&n).is_ok();
+            assert_eq!(NumPrime.is_prime(n), is_prime, "{}", n);
+            assert_eq!(NumPrime.is_prime_u128(n as u128), is_prime, "{}", n);
+        }
+        // 3 · (2^64 + 13) is composite
+        assert!(!NumPrime.is_prime_u128(3 * 18446744073709551629));
+
+        // 2^521 - 1 is a Mersenne prime, 2^523 - 1 is not
+        let mersenne = |p: u32| (BigUint::from(1u32) << p) - 1u32;
+        assert_eq!(NumPrime.is_prime_big(&mersenne(521)), Some(true));
+        assert_eq!(NumPrime.is_prime_big(&mersenne(523)), Some(false));
+
+        let mut steps = Vec::new();
+        assert_eq!(
+            NumPrime.trace(91, &mut |line| steps.push(line.to_string())),
+            Some(false)
+        );
+        assert_eq!(steps[1], "composite");
+    }
+}
@@ -0,0 +1,198 @@
+//! Pipelines chaining bounded cheap tests with a fallback test
+//!
+//! A spec such as `trial-division:1000+miller-rabin` runs each `name:bound`
+//! stage with [`PrimalityTest::is_prime_bounded`] and hands the number to
+//! the next stage when a stage cannot decide. The last stage has no bound and
+//! always answers, so the whole chain benchmarks like a single algorithm.
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use crate::algorithms::{Evidence, PrimalityTest, Registry, TraceFn};
+use crate::error::{Error, Result};
+
+struct Stage {
+    test: Arc<dyn PrimalityTest>,
+    bound: u64,
+}
+
+/// Registered tests composed into one, see the [module docs](self)
+pub struct Pipeline {
+    spec: String,
+    description: String,
+    stages: Vec<Stage>,
+    fallback: Arc<dyn PrimalityTest>,
+}
+
+impl Pipeline {
+    /// Builds the pipeline described by `spec` from the tests in `registry`
+    pub fn parse(spec: &str, registry: &Registry) -> Result<Pipeline> {
+        let invalid = |reason: &str| Error::InvalidPipeline {
+            spec: spec.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let (stages, fallback) = spec
+            .rsplit_once('+')
+            .ok_or_else(|| invalid("expected stages joined by '+'"))?;
+        if fallback.contains(':') {
+            return Err(invalid("the last stage is the fallback and takes no bound"));
+        }
+
+        let stages = stages
+            .split('+')
+            .map(|stage| {
+                let (name, bound) = stage.split_once(':').ok_or_else(|| {
+                    invalid("every stage but the last needs a bound, as in name:1000")
+                })?;
+                let bound = bound
+                    .parse()
+                    .map_err(|_| invalid(&format!("'{}' is not a valid bound", bound)))?;
+
+                Ok(Stage {
+                    test: registry.lookup(name)?,
+                    bound,
+                })
+            })
+            .collect::<Result<Vec<Stage>>>()?;
+        let fallback = registry.lookup(fallback)?;
+
+        let mut description = String::from("Pipeline:");
+        for stage in &stages {
+            description += &format!(" {} up to {},", stage.test.name(), stage.bound);
+        }
+        description += &format!(" then {}", fallback.name());
+
+        Ok(Pipeline {
+            spec: spec.to_string(),
+            description,
+            stages,
+            fallback,
+        })
+    }
+
+    // The first stage that decides `n`, or the fallback
+    fn deciding_stage(&self, n: u64) -> (&dyn PrimalityTest, bool) {
+        for stage in &self.stages {
+            if let Some(is_prime) = stage.test.is_prime_bounded(n, stage.bound) {
+                return (stage.test.as_ref(), is_prime);
+            }
+        }
+
+        (self.fallback.as_ref(), self.fallback.is_prime(n))
+    }
+}
+
+impl PrimalityTest for Pipeline {
+    fn name(&self) -> &str {
+        &self.spec
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn supported_range(&self) -> RangeInclusive<u64> {
+        // The fallback has to answer whatever the stages leave undecided
+        self.fallback.supported_range()
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        self.deciding_stage(n).1
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        match self.deciding_stage(n) {
+            (test, false) => test.evidence(n),
+            (_, true) => None,
+        }
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        for stage in &self.stages {
+            let name = stage.test.name();
+            if stage.test.is_prime_bounded(n, stage.bound).is_some() {
+                step(format_args!(
+                    "{} decides within its bound {}",
+                    name, stage.bound
+                ));
+                return stage.test.trace(n, step);
+            }
+            step(format_args!(
+                "{} cannot decide within its bound {}, passing on",
+                name, stage.bound
+            ));
+        }
+
+        step(format_args!("falling back to {}", self.fallback.name()));
+        self.fallback.trace(n, step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::config::AlgorithmConfig;
+    use crate::algorithms::miller_rabin;
+
+    fn registry() -> Registry {
+        Registry::with_builtins(&AlgorithmConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let pipeline =
+            Pipeline::parse("trial-division:1000+aks:50+miller-rabin", &registry()).unwrap();
+        assert_eq!(pipeline.name(), "trial-division:1000+aks:50+miller-rabin");
+        assert_eq!(
+            pipeline.description(),
+            "Pipeline: trial-division up to 1000, aks up to 50, then miller-rabin"
+        );
+        assert_eq!(pipeline.supported_range(), 0..=u64::MAX);
+
+        for spec in [
+            "miller-rabin",
+            "trial-division+miller-rabin",
+            "trial-division:ten+miller-rabin",
+            "trial-division:10+miller-rabin:10",
+            "bogus:10+miller-rabin",
+            "trial-division:10+bogus",
+        ] {
+            assert!(Pipeline::parse(spec, &registry()).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        let pipeline = Pipeline::parse("trial-division:10+aks:100+bpsw", &registry()).unwrap();
+        for n in 0..2000 {
+            assert_eq!(pipeline.is_prime(n), miller_rabin::is_prime(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_deciding_stage() {
+        let pipeline = Pipeline::parse("trial-division:100+miller-rabin", &registry()).unwrap();
+
+        // 7 divides 7 · 1009, so trial division answers and explains
+        assert_eq!(pipeline.evidence(7 * 1009), Some(Evidence::Divisor(7)));
+        // 1009 · 1013 has no divisor up to 100: the fallback finds a witness
+        assert!(matches!(
+            pipeline.evidence(1009 * 1013),
+            Some(Evidence::Witness(_))
+        ));
+
+        let mut steps = Vec::new();
+        assert_eq!(
+            pipeline.trace(1009 * 1013, &mut |step| steps.push(step.to_string())),
+            Some(false)
+        );
+        assert_eq!(
+            steps[..2],
+            [
+                "trial-division cannot decide within its bound 100, passing on",
+                "falling back to miller-rabin"
+            ]
+        );
+    }
+}
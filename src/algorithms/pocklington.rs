@@ -0,0 +1,373 @@
+//! Pocklington-Lehmer primality proving
+//!
+//! If n - 1 = F·R with the prime factors of F known and F > √n, then n is
+//! prime if for every prime q dividing F some base a has a^(n - 1) ≡ 1
+//! (mod n) and gcd(a^((n - 1)/q) - 1, n) = 1. Unlike the probable prime
+//! tests, an answer of prime is a proof, and it takes a few modular
+//! exponentiations where AKS needs polynomial arithmetic.
+//!
+//! F comes from trial division of n - 1 by the primes up to
+//! `pocklington.bound`. A cofactor left over joins F when it is known prime:
+//! when trial division stopped below its square root, or when it is proven
+//! prime the same way in turn. If F stays at or below √n, no proof is
+//! possible and the verdict comes from Miller-Rabin below 2^64 and
+//! Baillie-PSW above, which [`PrimalityTest::backend`] reports.
+
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, bpsw, miller_rabin, small_primes};
+use crate::error::{Error, Result};
+use crate::math::{gcd_u128, pow_mod_u128};
+
+/// Largest prime trial-divided into n - 1 when `pocklington.bound` is not
+/// set: every prime of the small prime table
+pub const DEFAULT_BOUND: u64 = small_primes::LIMIT;
+
+// Bases tried for each prime factor q before giving up on a proof
+const MAX_BASES: usize = 20;
+
+/// How a proof of primality ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    Prime,
+    /// a^(n - 1) ≢ 1 (mod n)
+    FermatWitness(u64),
+    /// gcd(a^((n - 1)/q) - 1, n) is a proper divisor
+    Divisor(u128),
+    /// Too little of n - 1 factored, or no base found
+    Undecided,
+}
+
+// The prime factors of n - 1 found by trial division by `primes`, including
+// a cofactor known to be prime, and the unfactored cofactor
+fn factor_n_minus_one(n: u128, primes: &[u64], step: TraceFn<'_>) -> (Vec<u128>, u128) {
+    let mut cofactor = n - 1;
+    let mut factors = Vec::new();
+    let mut complete = false;
+    for &p in primes {
+        let p = p as u128;
+        if p * p > cofactor {
+            complete = true;
+            break;
+        }
+        if cofactor.is_multiple_of(p) {
+            factors.push(p);
+            while cofactor.is_multiple_of(p) {
+                cofactor /= p;
+            }
+        }
+    }
+
+    if cofactor > 1 && !complete {
+        step(format_args!(
+            "proving the cofactor {} prime first",
+            cofactor
+        ));
+        complete = prove(cofactor, primes, step) == Outcome::Prime;
+        step(format_args!(
+            "cofactor {} {}",
+            cofactor,
+            if complete {
+                "proven prime"
+            } else {
+                "not proven prime, left unfactored"
+            }
+        ));
+    }
+    if cofactor > 1 && complete {
+        factors.push(cofactor);
+        cofactor = 1;
+    }
+    (factors, cofactor)
+}
+
+// Proves the odd n > 2 prime or composite, using the primes in `primes` to
+// factor n - 1
+fn prove(n: u128, primes: &[u64], step: TraceFn<'_>) -> Outcome {
+    let (factors, cofactor) = factor_n_minus_one(n, primes, step);
+    let factored = (n - 1) / cofactor;
+    let root = n.isqrt();
+    step(format_args!(
+        "{} - 1 = F·R with the primes {:?} of F, R = {}",
+        n, factors, cofactor
+    ));
+    if factored <= root {
+        step(format_args!(
+            "F = {} ≤ √{} = {}: too little factored for a proof",
+            factored, n, root
+        ));
+        return Outcome::Undecided;
+    }
+    step(format_args!("F = {} > √{} = {}", factored, n, root));
+
+    for &q in &factors {
+        let mut proven = false;
+        for &a in small_primes::first(MAX_BASES) {
+            if a as u128 >= n {
+                break;
+            }
+            if pow_mod_u128(a as u128, n - 1, n) != 1 {
+                step(format_args!(
+                    "a = {}: {}^(n - 1) ≢ 1, a Fermat witness: composite",
+                    a, a
+                ));
+                return Outcome::FermatWitness(a);
+            }
+            // x - 1 mod n, without overflowing for n above 2^127
+            let x = match pow_mod_u128(a as u128, (n - 1) / q, n) {
+                0 => n - 1,
+                x => x - 1,
+            };
+            match gcd_u128(x, n) {
+                1 => {
+                    step(format_args!(
+                        "q = {}: a = {}, gcd({}^((n - 1)/{}) - 1, n) = 1",
+                        q, a, a, q
+                    ));
+                    proven = true;
+                    break;
+                }
+                g if g == n => continue,
+                g => {
+                    step(format_args!(
+                        "q = {}: a = {}, gcd({}^((n - 1)/{}) - 1, n) = {}: composite",
+                        q, a, a, q, g
+                    ));
+                    return Outcome::Divisor(g);
+                }
+            }
+        }
+        if !proven {
+            step(format_args!("q = {}: no base found", q));
+            return Outcome::Undecided;
+        }
+    }
+
+    step(format_args!("{} is prime by Pocklington-Lehmer", n));
+    Outcome::Prime
+}
+
+pub struct Pocklington {
+    primes: &'static [u64],
+}
+
+impl Pocklington {
+    /// The test trial-dividing n - 1 by the primes up to `bound`
+    pub fn with_bound(bound: u64) -> Self {
+        Pocklington {
+            primes: small_primes::up_to(bound),
+        }
+    }
+
+    /// Reads the `pocklington.bound` knob, the largest prime trial-divided
+    /// into n - 1 (default: [`DEFAULT_BOUND`])
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        const KEY: &str = "pocklington.bound";
+
+        let bound = config.get(KEY)?.unwrap_or(DEFAULT_BOUND);
+        if !(2..=small_primes::LIMIT).contains(&bound) {
+            return Err(Error::InvalidConfigValue {
+                key: KEY.to_string(),
+                value: bound.to_string(),
+                hint: Some(format!("expected 2 to {}", small_primes::LIMIT)),
+            });
+        }
+
+        Ok(Pocklington::with_bound(bound))
+    }
+
+    fn outcome(&self, n: u128) -> Outcome {
+        self.outcome_traced(n, &mut |_| {})
+    }
+
+    // The outcome for odd n > 2, which are the only inputs needing a proof
+    fn outcome_traced(&self, n: u128, step: TraceFn<'_>) -> Outcome {
+        prove(n, self.primes, step)
+    }
+}
+
+fn fallback(n: u128) -> bool {
+    match u64::try_from(n) {
+        Ok(n) => miller_rabin::is_prime(n),
+        Err(_) => bpsw::is_prime_u128(n),
+    }
+}
+
+impl PrimalityTest for Pocklington {
+    fn name(&self) -> &str {
+        "pocklington"
+    }
+
+    fn description(&self) -> &str {
+        "Pocklington-Lehmer proof from the factors of n - 1 found by trial division"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        self.is_prime_u128(n as u128)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        match n {
+            0 | 1 => false,
+            2 => true,
+            _ if n.is_multiple_of(2) => false,
+            _ => match self.outcome(n) {
+                Outcome::Prime => true,
+                Outcome::FermatWitness(_) | Outcome::Divisor(_) => false,
+                Outcome::Undecided => fallback(n),
+            },
+        }
+    }
+
+    fn backend(&self, n: &BigUint) -> Option<String> {
+        let n = u128::try_from(n).ok().filter(|&n| n > 2 && n & 1 == 1)?;
+        (self.outcome(n) == Outcome::Undecided).then(|| {
+            if n <= u64::MAX as u128 {
+                "miller-rabin".to_string()
+            } else {
+                "bpsw".to_string()
+            }
+        })
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        match n {
+            0 | 1 => Some(Evidence::BelowTwo),
+            2 => None,
+            _ if n.is_multiple_of(2) => Some(Evidence::Divisor(2)),
+            _ => match self.outcome(n as u128) {
+                Outcome::Prime => None,
+                Outcome::FermatWitness(a) => Some(Evidence::FermatWitness(a)),
+                Outcome::Divisor(g) => Some(Evidence::Divisor(g as u64)),
+                Outcome::Undecided => {
+                    miller_rabin::evidence_with_witnesses(n, &miller_rabin::DEFAULT_WITNESSES)
+                }
+            },
+        }
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        if n < 3 || n.is_multiple_of(2) {
+            let is_prime = self.is_prime(n);
+            step(format_args!(
+                "{} is below 3 or even, decided directly: {}",
+                n,
+                if is_prime { "prime" } else { "composite" }
+            ));
+            return Some(is_prime);
+        }
+
+        match self.outcome_traced(n as u128, step) {
+            Outcome::Prime => Some(true),
+            Outcome::FermatWitness(_) | Outcome::Divisor(_) => Some(false),
+            Outcome::Undecided => {
+                let is_prime = miller_rabin::is_prime(n);
+                step(format_args!(
+                    "no proof, falling back to miller-rabin: {}",
+                    if is_prime { "prime" } else { "composite" }
+                ));
+                Some(is_prime)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bound: &str) -> AlgorithmConfig {
+        AlgorithmConfig::from_pairs(&[("pocklington.bound".to_string(), bound.to_string())])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        let test = Pocklington::from_config(&AlgorithmConfig::default()).unwrap();
+        for n in 0..100_000u64 {
+            let expected = miller_rabin::is_prime(n);
+            assert_eq!(test.is_prime(n), expected, "{n}");
+            assert_eq!(test.evidence(n).is_none(), expected, "{n}");
+            // Below 2^32 the cofactor is always below the square of the bound
+            assert_eq!(test.backend(&n.into()), None, "{n}");
+        }
+
+        // The Carmichael number 561 = 3 · 11 · 17 passes the congruence to
+        // base 2 and only fails it to the base 3 it shares; 2047 = 23 · 89
+        // is a strong pseudoprime to base 2
+        assert_eq!(test.evidence(561), Some(Evidence::FermatWitness(3)));
+        assert!(!test.is_prime(2047));
+    }
+
+    #[test]
+    fn test_widths() {
+        let test = Pocklington::from_config(&AlgorithmConfig::default()).unwrap();
+
+        // 2^64 - 59 and 2^127 - 1 are prime, 3 · (2^64 + 13) is not
+        assert!(test.is_prime(18446744073709551557));
+        assert!(test.is_prime_u128(u128::MAX >> 1));
+        assert!(!test.is_prime_u128(3 * 18446744073709551629));
+        // 2^126 - 2 = 2 · 31 · 601 · 1801 · 269089806001 · 4710883168879506001
+        // keeps a composite cofactor, so 2^127 - 1 is left to Baillie-PSW
+        assert_eq!(
+            test.backend(&(u128::MAX >> 1).into()).as_deref(),
+            Some("bpsw")
+        );
+
+        // Above 2^127, n - 1 = (2^57 + 13) · 2^70 = 3 · 5 · 7² · 196075085817491 · 2^70
+        // factors far enough for a proof, and x - 1 mod n must not overflow
+        let above = (((1u128 << 57) + 13) << 70) + 1;
+        assert_eq!(test.outcome(above), Outcome::Prime);
+        assert!(test.is_prime_u128(above));
+        assert_eq!(test.backend(&above.into()), None);
+        assert!(!test.is_prime_u128(above + 2));
+    }
+
+    #[test]
+    fn test_undecided() {
+        // With only 2 to divide by, 31 - 1 = 2 · 15 leaves F = 2 ≤ √31, and
+        // the cofactor 15 is composite
+        let test = Pocklington::from_config(&config("2")).unwrap();
+        assert_eq!(test.outcome(31), Outcome::Undecided);
+        assert!(test.is_prime(31));
+        assert_eq!(test.backend(&31u32.into()).as_deref(), Some("miller-rabin"));
+
+        // 2^64 + 13 - 1 = 2^2 · 7 · 658812288346769701, whose large factor
+        // is proven prime in turn
+        let test = Pocklington::from_config(&AlgorithmConfig::default()).unwrap();
+        assert!(test.is_prime_u128(18446744073709551629));
+        assert_eq!(test.backend(&18446744073709551629u128.into()), None);
+
+        for bound in ["1", "65537"] {
+            assert!(matches!(
+                Pocklington::from_config(&config(bound)),
+                Err(Error::InvalidConfigValue { hint: Some(_), .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let test = Pocklington::from_config(&AlgorithmConfig::default()).unwrap();
+        let mut steps = Vec::new();
+        assert_eq!(
+            test.trace(97, &mut |step| steps.push(step.to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            steps,
+            [
+                "97 - 1 = F·R with the primes [2, 3] of F, R = 1",
+                "F = 96 > √97 = 9",
+                "q = 2: a = 5, gcd(5^((n - 1)/2) - 1, n) = 1",
+                "q = 3: a = 2, gcd(2^((n - 1)/3) - 1, n) = 1",
+                "97 is prime by Pocklington-Lehmer",
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! The primal crate as a baseline primality test and sieve
+//!
+//! primal (<https://crates.io/crates/primal>) is the most used prime crate in
+//! Rust: a deterministic Miller-Rabin for u64 and a segmented, wheel-based
+//! sieve. Wrapping both runs them in the same harness as the crate's own
+//! implementations, so a report shows where those stand against it.
+
+use crate::algorithms::{PrimalityTest, TraceFn};
+
+pub struct Primal;
+
+impl PrimalityTest for Primal {
+    fn name(&self) -> &str {
+        "primal"
+    }
+
+    fn description(&self) -> &str {
+        "The primal crate's deterministic Miller-Rabin"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        step(format_args!(
+            "primal is an external crate and reports only its verdict"
+        ));
+        let is_prime = is_prime(n);
+        step(format_args!(
+            "{}",
+            if is_prime { "prime" } else { "composite" }
+        ));
+        Some(is_prime)
+    }
+}
+
+pub fn is_prime(n: u64) -> bool {
+    ::primal::is_prime(n)
+}
+
+/// Returns all primes up to `n` with primal's sieve
+///
+/// # Panics
+///
+/// If `n` does not fit in a usize.
+pub fn sieve(n: u64) -> Vec<u64> {
+    let limit = usize::try_from(n).expect("n does not fit in a usize");
+    // The sieve may cover past `limit`, up to the end of its last segment
+    ::primal::Sieve::new(limit)
+        .primes_from(0)
+        .take_while(|&p| p <= limit)
+        .map(|p| p as u64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes;
+
+    #[test]
+    fn test_agrees_with_sieve() {
+        for n in [0, 1, 2, 3, 30, 1000, 1_000_000] {
+            assert_eq!(sieve(n), sieve_of_eratosthenes::sieve(n), "{}", n);
+        }
+
+        let primes = sieve_of_eratosthenes::sieve(100_000);
+        for n in 0..=100_000 {
+            assert_eq!(
+                Primal.is_prime(n),
+                primes.binary_search(&n).is_ok(),
+                "{}",
+                n
+            );
+        }
+        // 2^64 - 59 is the largest 64-bit prime
+        assert!(Primal.is_prime(18446744073709551557));
+    }
+}
@@ -0,0 +1,81 @@
+//! The C primesieve library as a baseline sieve
+//!
+//! primesieve (<https://github.com/kimwalisch/primesieve>) is a segmented
+//! sieve of Eratosthenes with a mod-30 wheel, tuned to the CPU caches and
+//! vectorized, and among the fastest prime generators there is. Wrapping it
+//! runs it in the same harness as the crate's own sieve, so their times are
+//! measured the same way. Links against the shared `libprimesieve`, which
+//! must be installed to build with the `primesieve` feature.
+
+use std::ffi::{CStr, c_char, c_int, c_void};
+
+// primesieve.h: the type of the array primesieve_generate_primes returns
+const UINT64_PRIMES: c_int = 13;
+
+#[link(name = "primesieve")]
+unsafe extern "C" {
+    fn primesieve_generate_primes(
+        start: u64,
+        stop: u64,
+        size: *mut usize,
+        kind: c_int,
+    ) -> *mut c_void;
+    fn primesieve_free(primes: *mut c_void);
+    fn primesieve_get_max_stop() -> u64;
+    fn primesieve_version() -> *const c_char;
+}
+
+/// Returns all primes up to `n`
+///
+/// # Panics
+///
+/// If `n` exceeds [`max_stop`], or primesieve fails to allocate the primes.
+pub fn sieve(n: u64) -> Vec<u64> {
+    assert!(
+        n <= max_stop(),
+        "{} is above primesieve's limit of {}",
+        n,
+        max_stop()
+    );
+
+    let mut size = 0;
+    // SAFETY: `size` outlives the call, which writes the prime count to it
+    let primes = unsafe { primesieve_generate_primes(0, n, &mut size, UINT64_PRIMES) };
+    assert!(!primes.is_null(), "primesieve failed to sieve up to {}", n);
+
+    // SAFETY: with UINT64_PRIMES, a non-null result holds `size` u64 values,
+    // and is freed with primesieve_free once copied
+    unsafe {
+        let copied = std::slice::from_raw_parts(primes as *const u64, size).to_vec();
+        primesieve_free(primes);
+        copied
+    }
+}
+
+/// The largest number primesieve sieves up to, 2^64 - 2^32 · 10
+pub fn max_stop() -> u64 {
+    // SAFETY: takes no arguments and returns a constant
+    unsafe { primesieve_get_max_stop() }
+}
+
+/// Version of the linked library, e.g. "12.4"
+pub fn version() -> String {
+    // SAFETY: primesieve returns a static NUL-terminated string
+    unsafe { CStr::from_ptr(primesieve_version()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes;
+
+    #[test]
+    fn test_agrees_with_sieve() {
+        for n in [0, 1, 2, 3, 30, 1000, 1_000_000] {
+            assert_eq!(sieve(n), sieve_of_eratosthenes::sieve(n), "{}", n);
+        }
+        assert!(!version().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+//! Proth's theorem for Proth numbers N = k·2^m + 1 with k odd and k < 2^m
+//!
+//! N is prime if and only if a^((N - 1)/2) ≡ -1 (mod N) for a base a with
+//! Jacobi symbol (a/N) = -1, so one modular exponentiation proves or
+//! disproves primality, where a general proof such as AKS needs polynomial
+//! arithmetic. The base is the first odd prime with (a/N) = -1. Other
+//! numbers are rejected rather than tested.
+
+use num_bigint::BigUint;
+
+use crate::algorithms::strong_lucas::jacobi_small_big;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, small_primes};
+use crate::error::{Error, Result};
+
+/// (k, m) with n = k·2^m + 1, k odd and k < 2^m, or None if `n` is not a
+/// Proth number
+pub fn decompose(n: &BigUint) -> Option<(BigUint, u64)> {
+    if *n < BigUint::from(3u32) {
+        return None;
+    }
+    let n_minus_one = n - 1u32;
+    let m = n_minus_one.trailing_zeros()?;
+    let k = n_minus_one >> m;
+    (k.bits() <= m).then_some((k, m))
+}
+
+/// How Proth's criterion ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    /// a^((n - 1)/2) ≡ -1 for the base a
+    Prime(u64),
+    /// a^((n - 1)/2) ≢ -1 although (a/n) = -1
+    Witness(u64),
+    /// The base divides n
+    Divisor(u64),
+    Square,
+    /// No prime below 2^16 is a quadratic non-residue
+    NoBase,
+}
+
+// The first odd prime a with (a/n) = -1 decides an odd Proth number n
+fn run(n: &BigUint) -> Outcome {
+    if n.sqrt().pow(2) == *n {
+        return Outcome::Square;
+    }
+
+    let minus_one = n - 1u32;
+    let exponent = &minus_one >> 1;
+    for &a in &small_primes::all()[1..] {
+        match jacobi_small_big(a as i64, n) {
+            -1 => {}
+            0 if *n == BigUint::from(a) => return Outcome::Prime(a),
+            0 => return Outcome::Divisor(a),
+            _ => continue,
+        }
+        return if BigUint::from(a).modpow(&exponent, n) == minus_one {
+            Outcome::Prime(a)
+        } else {
+            Outcome::Witness(a)
+        };
+    }
+    Outcome::NoBase
+}
+
+fn not_proth(n: &BigUint) -> Error {
+    Error::InvalidNumber {
+        input: n.to_string(),
+        reason: "Proth's theorem needs k·2^m + 1 with k odd and k < 2^m".to_string(),
+    }
+}
+
+/// Returns true if the Proth number `n` is prime, or an error if `n` is not
+/// a Proth number
+pub fn is_prime(n: &BigUint) -> Result<bool> {
+    if decompose(n).is_none() {
+        return Err(not_proth(n));
+    }
+    match run(n) {
+        Outcome::Prime(_) => Ok(true),
+        Outcome::NoBase => Err(Error::NoVerdict {
+            number: n.to_string(),
+        }),
+        _ => Ok(false),
+    }
+}
+
+/// Proth's theorem as a [`PrimalityTest`] of one Proth number, so it can
+/// run beside the general tests on it. Every other input is outside its
+/// supported range.
+pub struct Proth {
+    n: BigUint,
+    k: BigUint,
+    m: u64,
+}
+
+impl Proth {
+    /// The test of `n`, or an error if `n` is not a Proth number
+    pub fn new(n: &BigUint) -> Result<Self> {
+        let (k, m) = decompose(n).ok_or_else(|| not_proth(n))?;
+        Ok(Proth { n: n.clone(), k, m })
+    }
+
+    /// k and m with n = k·2^m + 1
+    pub fn form(&self) -> (&BigUint, u64) {
+        (&self.k, self.m)
+    }
+
+    // None if no base was found, which takes a non-residue above 2^16
+    fn verdict(&self) -> Option<bool> {
+        match run(&self.n) {
+            Outcome::Prime(_) => Some(true),
+            Outcome::NoBase => None,
+            _ => Some(false),
+        }
+    }
+}
+
+impl PrimalityTest for Proth {
+    fn name(&self) -> &str {
+        "proth"
+    }
+
+    fn description(&self) -> &str {
+        "Proth's theorem for a number k·2^m + 1 with k odd and k < 2^m"
+    }
+
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        match u64::try_from(&self.n) {
+            Ok(n) => n..=n,
+            #[allow(clippy::reversed_empty_ranges)]
+            Err(_) => 1..=0,
+        }
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        debug_assert_eq!(BigUint::from(n), self.n);
+        self.verdict().unwrap_or(false)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        match u128::try_from(&self.n) {
+            Ok(n) => n..=n,
+            #[allow(clippy::reversed_empty_ranges)]
+            Err(_) => 1..=0,
+        }
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        debug_assert_eq!(BigUint::from(n), self.n);
+        self.verdict().unwrap_or(false)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        (*n == self.n).then(|| self.verdict()).flatten()
+    }
+
+    fn evidence(&self, _n: u64) -> Option<Evidence> {
+        match run(&self.n) {
+            Outcome::Witness(a) => Some(Evidence::EulerWitness(a)),
+            Outcome::Divisor(a) => Some(Evidence::Divisor(a)),
+            Outcome::Square => u64::try_from(self.n.sqrt())
+                .ok()
+                .map(|base| Evidence::PerfectPower { base, exponent: 2 }),
+            Outcome::Prime(_) | Outcome::NoBase => None,
+        }
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        step(format_args!("{} = {}·2^{} + 1", n, self.k, self.m));
+        let outcome = run(&self.n);
+        match outcome {
+            Outcome::Prime(a) if a == n => step(format_args!(
+                "{} is the first odd prime base itself: prime",
+                n
+            )),
+            Outcome::Prime(a) | Outcome::Witness(a) => {
+                let x = BigUint::from(a).modpow(&BigUint::from((n - 1) / 2), &self.n);
+                step(format_args!("({}/{}) = -1, so a = {}", a, n, a));
+                step(format_args!(
+                    "{}^{} mod {} = {}: {}",
+                    a,
+                    (n - 1) / 2,
+                    n,
+                    x,
+                    if matches!(outcome, Outcome::Prime(_)) {
+                        "≡ -1, prime"
+                    } else {
+                        "not -1, composite"
+                    }
+                ));
+            }
+            Outcome::Divisor(a) => step(format_args!(
+                "({}/{}) = 0, so {} divides {}: composite",
+                a, n, a, n
+            )),
+            Outcome::Square => step(format_args!("{} = {}²: composite", n, n.isqrt())),
+            Outcome::NoBase => {
+                step(format_args!("no prime below 2^16 is a non-residue"));
+                return None;
+            }
+        }
+        Some(matches!(outcome, Outcome::Prime(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::miller_rabin;
+
+    #[test]
+    fn test_decompose() {
+        assert_eq!(decompose(&13u32.into()), Some((3u32.into(), 2)));
+        assert_eq!(decompose(&3u32.into()), Some((1u32.into(), 1)));
+        // 7 = 3·2 + 1 has k > 2^m, and even numbers have m = 0
+        for n in [0u32, 1, 2, 7, 10] {
+            assert_eq!(decompose(&n.into()), None, "{n}");
+        }
+        assert!(matches!(
+            is_prime(&7u32.into()),
+            Err(Error::InvalidNumber { .. })
+        ));
+    }
+
+    #[test]
+    fn test_agrees_with_miller_rabin() {
+        let mut proth_numbers = 0;
+        for n in 0..100_000u64 {
+            if decompose(&n.into()).is_some() {
+                proth_numbers += 1;
+                let test = Proth::new(&n.into()).unwrap();
+                assert_eq!(test.is_prime(n), miller_rabin::is_prime(n), "{n}");
+                assert_eq!(test.evidence(n).is_none(), miller_rabin::is_prime(n), "{n}");
+            }
+        }
+        assert!(proth_numbers > 300);
+
+        // 9 = 1·2^3 + 1 is a square, 3 · 2^4 + 1 = 49 too
+        assert_eq!(
+            Proth::new(&49u32.into()).unwrap().evidence(49),
+            Some(Evidence::PerfectPower {
+                base: 7,
+                exponent: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_big() {
+        // 3·2^189 + 1 is prime, 3·2^188 + 1 is not
+        let proth = |m: u32| (BigUint::from(3u32) << m) + 1u32;
+        assert_eq!(is_prime(&proth(189)).ok(), Some(true));
+        assert_eq!(is_prime(&proth(188)).ok(), Some(false));
+        let test = Proth::new(&proth(189)).unwrap();
+        assert_eq!(test.is_prime_big(&proth(189)), Some(true));
+        assert_eq!(test.is_prime_big(&proth(188)), None);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        let test = Proth::new(&97u32.into()).unwrap();
+        assert_eq!(
+            test.trace(97, &mut |step| steps.push(step.to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            steps,
+            [
+                "97 = 3·2^5 + 1",
+                "(5/97) = -1, so a = 5",
+                "5^48 mod 97 = 96: ≡ -1, prime"
+            ]
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Primality test trait and runtime registry
+//! Every primality test implements [`PrimalityTest`] and is looked up by name
+//! through a [`Registry`], so new algorithms (built-in or downstream) show up
+//! in every command without touching the CLI parsing layer.
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::pipeline::Pipeline;
+use crate::algorithms::{Evidence, TraceFn};
+use crate::algorithms::{
+    aks, auto, bpsw, ecpp, fermat, frobenius, miller_rabin, pocklington, solovay_strassen,
+    strong_lucas, trial_division, trial_division_newton, trial_division_sqrt, wilson,
+};
+use crate::error::{Error, Result};
+
+pub trait PrimalityTest: Send + Sync {
+    /// Unique kebab-case name used on the command line (e.g. "miller-rabin")
+    fn name(&self) -> &str;
+
+    /// One-line description of the algorithm
+    fn description(&self) -> &str;
+
+    /// Inputs the implementation handles correctly
+    fn supported_range(&self) -> RangeInclusive<u64> {
+        0..=u64::MAX
+    }
+
+    fn is_prime(&self, n: u64) -> bool;
+
+    /// A capped run of the test for [`Pipeline`](crate::algorithms::pipeline::Pipeline)
+    /// stages: the verdict, or None if the test cannot decide within `bound`.
+    /// The default caps the input, answering only for n ≤ `bound`; trial
+    /// division caps its divisors instead.
+    fn is_prime_bounded(&self, n: u64, bound: u64) -> Option<bool> {
+        (n <= bound && self.supported_range().contains(&n)).then(|| self.is_prime(n))
+    }
+
+    /// Inputs [`is_prime_u128`](Self::is_prime_u128) handles correctly,
+    /// [`supported_range`](Self::supported_range) unless overridden
+    fn supported_range_u128(&self) -> RangeInclusive<u128> {
+        let range = self.supported_range();
+        *range.start() as u128..=*range.end() as u128
+    }
+
+    /// 128-bit variant of [`is_prime`](Self::is_prime).
+    /// The default narrows to u64, so tests that handle larger inputs must
+    /// override both this and [`supported_range_u128`](Self::supported_range_u128).
+    fn is_prime_u128(&self, n: u128) -> bool {
+        let n = u64::try_from(n).expect("n is outside supported_range_u128");
+        self.is_prime(n)
+    }
+
+    /// Arbitrary-precision variant of [`is_prime`](Self::is_prime), or None
+    /// if `n` is outside the inputs the implementation handles.
+    /// The default accepts [`supported_range_u128`](Self::supported_range_u128).
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        let n = u128::try_from(n)
+            .ok()
+            .filter(|n| self.supported_range_u128().contains(n))?;
+        Some(self.is_prime_u128(n))
+    }
+
+    /// The implementation a dispatching test hands `n` to, for showing which
+    /// one answered; None for tests that run on their own
+    fn backend(&self, n: &BigUint) -> Option<String> {
+        let _ = n;
+        None
+    }
+
+    /// Why `n` is composite, or None if it is prime or the test cannot say.
+    /// Runs the test again, so it is kept out of timed code.
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        let _ = n;
+        None
+    }
+
+    /// Runs the test on `n` while describing each internal step to `step`
+    /// (divisors tried, witness computations, ...), for learning how the
+    /// tests differ. Returns the verdict, or None if the test cannot trace.
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let _ = (n, step);
+        None
+    }
+}
+
+/// Short names accepted wherever an algorithm name is, as (alias, name)
+pub const ALIASES: &[(&str, &str)] = &[
+    ("mr", "miller-rabin"),
+    ("td", "trial-division"),
+    ("tdn", "trial-division-newton"),
+    ("tds", "trial-division-sqrt"),
+    ("psw", "bpsw"),
+];
+
+/// Ordered collection of primality tests, looked up by name
+#[derive(Clone, Default)]
+pub struct Registry {
+    tests: Vec<Arc<dyn PrimalityTest>>,
+}
+
+impl Registry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Registry { tests: Vec::new() }
+    }
+
+    /// A registry holding every built-in algorithm, configured from `config`
+    pub fn with_builtins(config: &AlgorithmConfig) -> Result<Self> {
+        let mut registry = Registry::new();
+
+        registry.register(Arc::new(aks::Aks::from_config(config)?));
+        registry.register(Arc::new(auto::Auto::new()?));
+        registry.register(Arc::new(bpsw::Bpsw));
+        registry.register(Arc::new(ecpp::Ecpp::default()));
+        registry.register(Arc::new(fermat::Fermat::from_config(config)?));
+        registry.register(Arc::new(frobenius::Frobenius));
+        registry.register(Arc::new(miller_rabin::MillerRabin::from_config(config)?));
+        registry.register(Arc::new(miller_rabin::MillerRabinU32));
+        registry.register(Arc::new(pocklington::Pocklington::from_config(config)?));
+        registry.register(Arc::new(solovay_strassen::SolovayStrassen::from_config(
+            config,
+        )?));
+        registry.register(Arc::new(strong_lucas::StrongLucas));
+        registry.register(Arc::new(trial_division::TrialDivision::from_config(
+            config,
+        )?));
+        registry.register(Arc::new(trial_division_newton::TrialDivisionNewton));
+        registry.register(Arc::new(trial_division_sqrt::TrialDivisionSqrt));
+        registry.register(Arc::new(trial_division::TrialDivisionU32));
+        registry.register(Arc::new(wilson::Wilson));
+        #[cfg(feature = "gmp")]
+        registry.register(Arc::new(crate::algorithms::gmp::Gmp::from_config(config)?));
+        #[cfg(feature = "primal")]
+        registry.register(Arc::new(crate::algorithms::primal::Primal));
+        #[cfg(feature = "num-prime")]
+        registry.register(Arc::new(crate::algorithms::num_prime::NumPrime));
+        #[cfg(feature = "compact")]
+        if let Some(lookup) = crate::algorithms::gaps_lookup::GapsLookup::from_config(config)? {
+            registry.register(Arc::new(lookup));
+        }
+
+        // Registered again, in place, now that the tests its profile names are
+        registry.register(Arc::new(auto::Auto::from_config(config, &registry)?));
+
+        Ok(registry)
+    }
+
+    /// Adds `test`, replacing any registered test with the same name
+    pub fn register(&mut self, test: Arc<dyn PrimalityTest>) {
+        match self.tests.iter_mut().find(|t| t.name() == test.name()) {
+            Some(existing) => *existing = test,
+            None => self.tests.push(test),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn PrimalityTest>> {
+        self.tests.iter().find(|t| t.name() == name).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn PrimalityTest>> {
+        self.tests.iter()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.tests.iter().map(|t| t.name()).collect()
+    }
+
+    /// Looks up every name in `names`, or returns all tests if `names` is None.
+    /// A name holding `+` is parsed as a [`Pipeline`] spec.
+    pub fn resolve(&self, names: Option<&[String]>) -> Result<Vec<Arc<dyn PrimalityTest>>> {
+        match names {
+            None => Ok(self.tests.clone()),
+            Some(names) => names.iter().map(|name| self.lookup(name)).collect(),
+        }
+    }
+
+    /// Looks up a registered test by name or one of its [`ALIASES`], or
+    /// builds the pipeline `name` describes. An unknown name is rejected with
+    /// the closest registered name as a suggestion.
+    pub fn lookup(&self, name: &str) -> Result<Arc<dyn PrimalityTest>> {
+        if name.contains('+') {
+            return Ok(Arc::new(Pipeline::parse(name, self)?));
+        }
+
+        let name = name.trim();
+        let full_name = ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map_or(name, |&(_, full_name)| full_name);
+
+        self.get(full_name)
+            .or_else(|| {
+                self.tests
+                    .iter()
+                    .find(|t| t.name().eq_ignore_ascii_case(full_name))
+                    .cloned()
+            })
+            .ok_or_else(|| Error::UnknownAlgorithm {
+                name: name.to_string(),
+                suggestion: self.closest_name(name).map(String::from),
+                valid: self.names().join(", "),
+            })
+    }
+
+    // The registered name (or alias) within a few typos of `name`
+    fn closest_name(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        let candidates = self.names().into_iter().chain(
+            ALIASES
+                .iter()
+                .filter(|(_, full_name)| self.get(full_name).is_some())
+                .map(|&(alias, _)| alias),
+        );
+
+        candidates
+            .map(|candidate| (edit_distance(&name, candidate), candidate))
+            // A third of the name may be wrong, so "mr" does not suggest "aks"
+            .filter(|&(distance, candidate)| distance <= candidate.len().max(name.len()) / 3)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+// Edits turning a into b: insertions, deletions, substitutions, and swaps of
+// adjacent characters (the optimal string alignment distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // d[i][j]: distance between the first i characters of a and j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    d[0] = (0..=b.len()).collect();
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = substitution.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // Built-ins registered by the baseline features, on top of the default ones
+    fn baselines() -> usize {
+        [
+            cfg!(feature = "gmp"),
+            cfg!(feature = "primal"),
+            cfg!(feature = "num-prime"),
+        ]
+        .into_iter()
+        .filter(|&enabled| enabled)
+        .count()
+    }
+
+    struct EvenOnly;
+
+    impl PrimalityTest for EvenOnly {
+        fn name(&self) -> &str {
+            "even-only"
+        }
+
+        fn description(&self) -> &str {
+            "Claims every even number is prime"
+        }
+
+        fn is_prime(&self, n: u64) -> bool {
+            n.is_multiple_of(2)
+        }
+    }
+
+    #[test]
+    fn test_builtins() {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        #[allow(unused_mut)]
+        let mut expected = vec![
+            "aks",
+            "auto",
+            "bpsw",
+            "ecpp",
+            "fermat",
+            "frobenius",
+            "miller-rabin",
+            "miller-rabin-u32",
+            "pocklington",
+            "solovay-strassen",
+            "strong-lucas",
+            "trial-division",
+            "trial-division-newton",
+            "trial-division-sqrt",
+            "trial-division-u32",
+            "wilson",
+        ];
+        #[cfg(feature = "gmp")]
+        expected.push("gmp");
+        #[cfg(feature = "primal")]
+        expected.push("primal");
+        #[cfg(feature = "num-prime")]
+        expected.push("num-prime");
+        assert_eq!(registry.names(), expected);
+
+        for test in registry.iter() {
+            assert!(test.is_prime(97), "{}", test.name());
+            assert!(!test.is_prime(91), "{}", test.name());
+        }
+    }
+
+    #[test]
+    fn test_u128_defaults() {
+        // Tests without a 128-bit implementation keep their u64 range
+        assert_eq!(EvenOnly.supported_range_u128(), 0..=u64::MAX as u128);
+        assert!(EvenOnly.is_prime_u128(4));
+
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        let n = 3 * 18446744073709551629; // 3 * (2^64 + 13)
+        for test in registry.iter() {
+            if test.supported_range_u128().contains(&n) {
+                assert!(!test.is_prime_u128(n), "{}", test.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_defaults() {
+        assert_eq!(EvenOnly.is_prime_big(&BigUint::from(4u32)), Some(true));
+        assert_eq!(EvenOnly.is_prime_big(&(BigUint::from(1u32) << 64)), None);
+
+        // 2^521 - 1 is a Mersenne prime
+        let n = (BigUint::from(1u32) << 521) - 1u32;
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        for name in ["bpsw", "miller-rabin"] {
+            assert_eq!(registry.get(name).unwrap().is_prime_big(&n), Some(true));
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        assert_eq!(EvenOnly.trace(4, &mut |_| {}), None);
+
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        for test in registry.iter() {
+            for n in 0..200 {
+                let mut steps = 0;
+                let verdict = test.trace(n, &mut |_| steps += 1);
+                assert_eq!(verdict, Some(test.is_prime(n)), "{} on {}", test.name(), n);
+                assert!(steps > 0, "{} on {}", test.name(), n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_custom() {
+        let mut registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        registry.register(Arc::new(EvenOnly));
+
+        let test = registry.get("even-only").unwrap();
+        assert!(test.is_prime(4));
+        assert_eq!(registry.names().last(), Some(&"even-only"));
+
+        // Registering the same name again replaces the previous test
+        registry.register(Arc::new(EvenOnly));
+        assert_eq!(registry.iter().count(), 17 + baselines());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+
+        assert_eq!(registry.resolve(None).unwrap().len(), 16 + baselines());
+
+        let names = vec!["trial-division".to_string(), "aks".to_string()];
+        let resolved = registry.resolve(Some(&names)).unwrap();
+        assert_eq!(resolved[0].name(), "trial-division");
+        assert_eq!(resolved[1].name(), "aks");
+
+        let unknown = vec!["bogus".to_string()];
+        assert!(registry.resolve(Some(&unknown)).is_err());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+
+        assert_eq!(registry.lookup("mr").unwrap().name(), "miller-rabin");
+        assert_eq!(registry.lookup("TD").unwrap().name(), "trial-division");
+        assert_eq!(registry.lookup("AKS").unwrap().name(), "aks");
+        assert!(registry.lookup("td:100+mr").is_ok());
+        for (_, name) in ALIASES {
+            assert!(registry.get(name).is_some(), "{}", name);
+        }
+
+        let suggestion = |name: &str| match registry.lookup(name) {
+            Err(Error::UnknownAlgorithm { suggestion, .. }) => suggestion,
+            _ => panic!("{} should be unknown", name),
+        };
+        assert_eq!(suggestion("miller-rabbin").as_deref(), Some("miller-rabin"));
+        assert_eq!(
+            suggestion("trial-divison").as_deref(),
+            Some("trial-division")
+        );
+        assert_eq!(suggestion("bspw").as_deref(), Some("bpsw"));
+        assert_eq!(suggestion("lucas-lehmer"), None);
+
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("bspw", "bpsw"), 1);
+    }
+
+    // Every registered algorithm is checked against Miller-Rabin, so new
+    // algorithms are cross-validated as soon as they are added to the registry
+    proptest! {
+        #[test]
+        fn test_builtins_agree_with_miller_rabin(n in 0u64..1 << 10) {
+            let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+            let expected = miller_rabin::is_prime(n);
+
+            for test in registry.iter() {
+                if test.supported_range().contains(&n) {
+                    prop_assert_eq!(test.is_prime(n), expected, "{}", test.name());
+                    // Evidence is optional, but only ever given for composites
+                    if test.evidence(n).is_some() {
+                        prop_assert!(!expected, "{} gave evidence for a prime", test.name());
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_bpsw_agrees_with_miller_rabin(n: u64) {
+            prop_assert_eq!(bpsw::is_prime(n), miller_rabin::is_prime(n));
+        }
+    }
+
+    proptest! {
+        // The arbitrary-precision AKS path is slow in debug builds
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_builtins_agree_across_widths(n in 0u64..1 << 10) {
+            let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+
+            for test in registry.iter() {
+                if test.supported_range().contains(&n) {
+                    let is_prime = test.is_prime(n);
+                    prop_assert_eq!(test.is_prime_u128(n as u128), is_prime, "{}", test.name());
+                    prop_assert_eq!(test.is_prime_big(&n.into()), Some(is_prime), "{}", test.name());
+                }
+            }
+        }
+    }
+
+    // Algorithms that take seconds or more on a prime near the top of u64 in
+    // debug builds, left out of the full-width checks below
+    const SLOW: [&str; 5] = [
+        "aks",
+        "trial-division",
+        "trial-division-newton",
+        "trial-division-sqrt",
+        "wilson",
+    ];
+
+    proptest! {
+        #[test]
+        fn test_fast_builtins_agree_with_miller_rabin(n: u64) {
+            let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+            let expected = miller_rabin::is_prime(n);
+
+            for test in registry.iter().filter(|test| !SLOW.contains(&test.name())) {
+                if test.supported_range().contains(&n) {
+                    prop_assert_eq!(test.is_prime(n), expected, "{}", test.name());
+                }
+            }
+        }
+
+        #[test]
+        fn test_fast_builtins_agree_with_bpsw_u128(n: u128) {
+            let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+            let expected = bpsw::is_prime_u128(n);
+
+            for test in registry.iter().filter(|test| !SLOW.contains(&test.name())) {
+                if test.supported_range_u128().contains(&n) {
+                    prop_assert_eq!(test.is_prime_u128(n), expected, "{}", test.name());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Outcome of testing a single number
+
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+
+use crate::algorithms::PrimalityTest;
+
+/// One algorithm's answer for one number
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestResult {
+    /// Name of the algorithm that ran
+    pub algorithm: String,
+    /// The number tested, serialized as a decimal string
+    #[cfg_attr(feature = "serde", serde(with = "decimal"))]
+    pub number: BigUint,
+    /// The verdict, or None if `number` is outside the algorithm's supported range
+    pub is_prime: Option<bool>,
+    /// Time spent in the algorithm
+    pub elapsed: Duration,
+}
+
+impl TestResult {
+    /// Runs `test` on `number` and times it
+    pub fn run(test: &dyn PrimalityTest, number: &BigUint) -> TestResult {
+        let start_time = Instant::now();
+        let is_prime = test.is_prime_big(number);
+        let elapsed = start_time.elapsed();
+
+        TestResult {
+            algorithm: test.name().to_string(),
+            number: number.clone(),
+            is_prime,
+            elapsed,
+        }
+    }
+
+    /// "prime", "composite", or "skipped"
+    pub fn verdict(&self) -> &'static str {
+        match self.is_prime {
+            Some(true) => "prime",
+            Some(false) => "composite",
+            None => "skipped",
+        }
+    }
+}
+
+// Numbers of any size round-trip through formats without big integers
+#[cfg(feature = "serde")]
+mod decimal {
+    use num_bigint::BigUint;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(n: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(n)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::bpsw::Bpsw;
+    use crate::algorithms::trial_division_sqrt::TrialDivisionSqrt;
+
+    #[test]
+    fn test_run() {
+        let result = TestResult::run(&Bpsw, &BigUint::from(97u32));
+        assert_eq!(result.algorithm, "bpsw");
+        assert_eq!(result.verdict(), "prime");
+
+        let skipped = TestResult::run(&TrialDivisionSqrt, &(BigUint::from(1u32) << 64));
+        assert_eq!(skipped.is_prime, None);
+        assert_eq!(skipped.verdict(), "skipped");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let result = TestResult::run(&Bpsw, &(BigUint::from(1u32) << 127));
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains(r#""number":"170141183460469231731687303715884105728""#));
+
+        let parsed: TestResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+    }
+}
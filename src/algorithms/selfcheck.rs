@@ -0,0 +1,102 @@
+//! Bundled table of numbers with known primality, to catch a broken
+//! algorithm before its results are compared with the others
+
+use crate::algorithms::PrimalityTest;
+
+/// Known primes: small primes, primes next to wheel and Mersenne/Fermat
+/// boundaries. Kept small so AKS proves them all in well under a second.
+pub const KNOWN_PRIMES: &[u64] = &[
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 97, 101, 127, 211, 251, 257, 331,
+    509, 521, 541, 997,
+];
+
+/// Known composites, in groups: 0 and 1; prime squares and a power of two,
+/// missed by an off-by-one bound; Fermat pseudoprimes to base 2; Carmichael
+/// numbers; strong pseudoprimes to base 2, then to every base up to 3, 5, and
+/// 7; and strong Lucas pseudoprimes with Selfridge's parameters
+pub const KNOWN_COMPOSITES: &[u64] = &[
+    0, 1, 4, 9, 25, 49, 121, 169, 289, 361, 529, 961, 1024, 994009, 341, 645, 561, 1105, 1729,
+    2465, 2821, 6601, 8911, 41041, 2047, 3277, 4033, 4681, 8321, 1373653, 25326001, 3215031751,
+    5459, 5777, 10877, 16109, 18971, 22499, 24569,
+];
+
+/// The numbers of the table `test` gets wrong, empty if it passes. Numbers
+/// outside the test's supported range are not checked.
+pub fn selfcheck(test: &dyn PrimalityTest) -> Vec<u64> {
+    let range = test.supported_range();
+
+    let primes = KNOWN_PRIMES.iter().map(|&n| (n, true));
+    let composites = KNOWN_COMPOSITES.iter().map(|&n| (n, false));
+
+    primes
+        .chain(composites)
+        .filter(|(n, _)| range.contains(n))
+        .filter(|&(n, is_prime)| test.is_prime(n) != is_prime)
+        .map(|(n, _)| n)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::Registry;
+    use crate::algorithms::config::AlgorithmConfig;
+    use crate::algorithms::miller_rabin;
+
+    struct Fermat2;
+
+    impl PrimalityTest for Fermat2 {
+        fn name(&self) -> &str {
+            "fermat-2"
+        }
+
+        fn description(&self) -> &str {
+            "Fermat test to base 2"
+        }
+
+        fn is_prime(&self, n: u64) -> bool {
+            n == 2 || (n > 2 && crate::math::mod_pow(2, n - 1, n) == 1)
+        }
+    }
+
+    #[test]
+    fn test_table() {
+        assert!(KNOWN_PRIMES.iter().all(|&n| miller_rabin::is_prime(n)));
+        assert!(!KNOWN_COMPOSITES.iter().any(|&n| miller_rabin::is_prime(n)));
+    }
+
+    #[test]
+    fn test_builtins_pass() {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default()).unwrap();
+        for test in registry.iter() {
+            // The Carmichael number 151 · 751 · 28351 has no factor among
+            // Fermat's bases, so it passes them all, and the strong Lucas
+            // test alone lets its own pseudoprimes through
+            let expected: &[u64] = match test.name() {
+                "fermat" => &[3215031751],
+                "strong-lucas" => &[5459, 5777, 10877, 16109, 18971, 22499, 24569],
+                _ => &[],
+            };
+            assert_eq!(selfcheck(test.as_ref()), expected, "{}", test.name());
+        }
+    }
+
+    #[test]
+    fn test_catches_pseudoprimes() {
+        let failures = selfcheck(&Fermat2);
+        assert!(failures.contains(&341));
+        assert!(failures.contains(&561));
+        assert!(!failures.contains(&97));
+
+        // Base 2 alone lets the strong pseudoprimes through
+        let config =
+            AlgorithmConfig::from_pairs(&[("miller-rabin.witnesses".to_string(), "2".to_string())])
+                .unwrap();
+        let registry = Registry::with_builtins(&config).unwrap();
+        let failures = selfcheck(registry.get("miller-rabin").unwrap().as_ref());
+        assert_eq!(
+            failures,
+            [2047, 3277, 4033, 4681, 8321, 1373653, 25326001, 3215031751]
+        );
+    }
+}
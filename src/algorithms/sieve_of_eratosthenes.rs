@@ -1,45 +1,175 @@
+//! Bit-packed Sieve of Eratosthenes
+
+use crate::algorithms::TraceFn;
+use crate::core_algorithms::miller_rabin;
+use crate::core_algorithms::sieve_of_eratosthenes::{primes, sieve_into, words_for};
+
+// Largest prime a range sieve crosses off with. Past its square, the numbers
+// left standing are confirmed by Miller-Rabin instead of sieving by more primes.
+const RANGE_BASE_LIMIT: u64 = 1 << 20;
+
+/// Returns every prime less than or equal to `n`
 pub fn sieve(n: u64) -> Vec<u64> {
     if n < 2 {
-        println!("There are no prime numbers less than 2.");
+        tracing::debug!(n, "there are no prime numbers less than 2");
         return vec![];
     }
 
-    // Bitwise storage: each u64 holds 64 bits, reducing memory by 8x
-    let size = ((n as usize + 1) + 63) / 64; // ceiling division
-    let mut is_prime = vec![!0u64; size]; // all bits set to 1 (true)
+    let mut bits = vec![0u64; words_for(n)];
+    sieve_into(n, &mut bits);
 
-    // Helper functions for bit manipulation
-    #[inline]
-    fn get_bit(bits: &[u64], idx: usize) -> bool {
-        (bits[idx / 64] & (1u64 << (idx % 64))) != 0
+    primes(&bits, n).collect()
+}
+
+/// Unpacked sieve for small `n`, describing which multiples each prime
+/// crosses off to `step`. Returns the same primes as [`sieve`].
+pub fn trace(n: u64, step: TraceFn<'_>) -> Vec<u64> {
+    let n = n as usize;
+    let mut is_composite = vec![false; n + 1];
+
+    for p in 2..=n.isqrt() {
+        if is_composite[p] {
+            continue;
+        }
+
+        let multiples: Vec<String> = (p * p..=n).step_by(p).map(|m| m.to_string()).collect();
+        for m in (p * p..=n).step_by(p) {
+            is_composite[m] = true;
+        }
+        step(format_args!(
+            "{} is prime: cross off {}",
+            p,
+            multiples.join(", ")
+        ));
     }
+    step(format_args!(
+        "{}² > {}, so every number left is prime",
+        n.isqrt() + 1,
+        n
+    ));
+
+    (2..=n)
+        .filter(|&m| !is_composite[m])
+        .map(|m| m as u64)
+        .collect()
+}
 
-    #[inline]
-    fn clear_bit(bits: &mut [u64], idx: usize) {
-        bits[idx / 64] &= !(1u64 << (idx % 64));
+/// Segmented sieve: only `segment_size` numbers are held in memory at once,
+/// crossed off by the base primes up to sqrt(n).
+/// A segment size of 0 falls back to the single-segment sieve.
+pub fn sieve_segmented(n: u64, segment_size: usize) -> Vec<u64> {
+    if segment_size == 0 {
+        return sieve(n);
+    }
+    if n < 2 {
+        tracing::debug!(n, "there are no prime numbers less than 2");
+        return vec![];
     }
 
-    // Clear bits for 0 and 1
-    clear_bit(&mut is_prime, 0);
-    clear_bit(&mut is_prime, 1);
+    let sqrt = n.isqrt();
+    let base_primes = if sqrt >= 2 { sieve(sqrt) } else { vec![] };
+
+    let mut primes = Vec::new();
+    let mut segment = vec![true; segment_size];
+    let mut low: u64 = 2;
 
-    let sqrt = (n as f64).sqrt() as u64;
-    for i in 2..=sqrt {
-        if get_bit(&is_prime, i as usize) {
-            for j in (i * i..=n).step_by(i as usize) {
-                clear_bit(&mut is_prime, j as usize);
+    while low <= n {
+        let high = (low + segment_size as u64 - 1).min(n);
+        let len = (high - low + 1) as usize;
+        segment[..len].fill(true);
+
+        for &p in &base_primes {
+            // Start at p*p, or the first multiple of p inside the segment
+            let start = (p * p).max(low.div_ceil(p) * p);
+            for j in (start..=high).step_by(p as usize) {
+                segment[(j - low) as usize] = false;
             }
         }
-    }
 
-    let primes: Vec<u64> = (0..=n as usize)
-        .filter(|&index| get_bit(&is_prime, index))
-        .map(|index| index as u64)
-        .collect();
+        primes.extend(
+            segment[..len]
+                .iter()
+                .enumerate()
+                .filter(|(_, is_prime)| **is_prime)
+                .map(|(offset, _)| low + offset as u64),
+        );
+
+        low = high + 1;
+    }
 
     primes
 }
 
+/// The primes in [start, end] one segment of `segment_size` numbers at a
+/// time, in ascending order, so that a caller can hand them on as they are
+/// found. Only the base primes (up to sqrt(end), at most 2^20) and one
+/// segment are held in memory, wherever the range lies in the u64 range.
+pub struct PrimeSegments {
+    base_primes: Vec<u64>,
+    next: Option<u64>,
+    end: u64,
+    segment: Vec<bool>,
+}
+
+impl PrimeSegments {
+    /// # Panics
+    ///
+    /// If `segment_size` is 0.
+    pub fn new(start: u64, end: u64, segment_size: usize) -> PrimeSegments {
+        assert!(segment_size > 0, "a segment must hold at least one number");
+
+        let bound = end.isqrt().min(RANGE_BASE_LIMIT);
+        PrimeSegments {
+            base_primes: sieve(bound),
+            next: Some(start.max(2)).filter(|&low| low <= end),
+            end,
+            segment: vec![true; segment_size],
+        }
+    }
+}
+
+impl Iterator for PrimeSegments {
+    type Item = Vec<u64>;
+
+    /// The primes of the next segment, possibly none
+    fn next(&mut self) -> Option<Vec<u64>> {
+        let low = self.next?;
+        let high = low
+            .saturating_add(self.segment.len() as u64 - 1)
+            .min(self.end);
+        let segment = &mut self.segment[..(high - low + 1) as usize];
+        segment.fill(true);
+
+        for &p in &self.base_primes {
+            // Start at p*p, or the first multiple of p inside the segment
+            let Some(first) = low.div_ceil(p).checked_mul(p) else {
+                continue;
+            };
+            for j in ((p * p).max(first)..=high).step_by(p as usize) {
+                segment[(j - low) as usize] = false;
+            }
+        }
+
+        let confirm_above = RANGE_BASE_LIMIT * RANGE_BASE_LIMIT;
+        let primes = segment
+            .iter()
+            .enumerate()
+            .filter(|(_, is_prime)| **is_prime)
+            .map(|(offset, _)| low + offset as u64)
+            .filter(|&n| n <= confirm_above || miller_rabin::is_prime(n))
+            .collect();
+
+        self.next = high.checked_add(1).filter(|&next| next <= self.end);
+        Some(primes)
+    }
+}
+
+/// Every prime in [start, end], in ascending order, sieved a segment of
+/// `segment_size` numbers at a time (see [`PrimeSegments`])
+pub fn primes_between(start: u64, end: u64, segment_size: usize) -> impl Iterator<Item = u64> {
+    PrimeSegments::new(start, end, segment_size).flatten()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +180,64 @@ mod tests {
         assert_eq!(sieve(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
         assert_eq!(sieve(40), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]);
     }
+
+    #[test]
+    fn test_sieve_segmented() {
+        assert_eq!(sieve_segmented(3, 2), vec![2, 3]);
+        assert_eq!(sieve_segmented(40, 7), sieve(40));
+        assert_eq!(sieve_segmented(100_000, 1000), sieve(100_000));
+        assert_eq!(sieve_segmented(100_000, 1_000_000), sieve(100_000));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        let primes = trace(30, &mut |step| steps.push(step.to_string()));
+
+        assert_eq!(primes, sieve(30));
+        assert_eq!(
+            steps[0],
+            "2 is prime: cross off 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30"
+        );
+        assert_eq!(
+            steps[1],
+            "3 is prime: cross off 9, 12, 15, 18, 21, 24, 27, 30"
+        );
+        assert_eq!(steps[2], "5 is prime: cross off 25, 30");
+        assert_eq!(steps.len(), 4);
+
+        for n in 0..200 {
+            assert_eq!(trace(n, &mut |_| {}), sieve(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_primes_between() {
+        assert_eq!(primes_between(0, 40, 7).collect::<Vec<_>>(), sieve(40));
+        assert_eq!(
+            primes_between(10, 30, 4).collect::<Vec<_>>(),
+            [11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(primes_between(24, 28, 100).next(), None);
+        assert_eq!(primes_between(1, 1, 100).next(), None);
+        assert_eq!(primes_between(30, 10, 100).next(), None);
+
+        let expected: Vec<u64> = sieve(100_000).into_iter().filter(|&p| p >= 5000).collect();
+        assert_eq!(
+            primes_between(5000, 100_000, 1000).collect::<Vec<_>>(),
+            expected
+        );
+
+        // Past 2^40, composites without a factor below 2^20 are left to Miller-Rabin
+        let semiprime = 1_048_583 * 1_048_589;
+        assert!(primes_between(semiprime, semiprime, 10).next().is_none());
+        assert_eq!(
+            primes_between(u64::MAX - 100, u64::MAX, 16).collect::<Vec<_>>(),
+            [
+                18446744073709551521,
+                18446744073709551533,
+                18446744073709551557
+            ]
+        );
+    }
 }
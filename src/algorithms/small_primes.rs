@@ -0,0 +1,93 @@
+//! The primes below 2^16, computed once and shared across threads
+//!
+//! Everything that divides by the first primes (Baillie-PSW's small factor
+//! check, the benchmark prefilter, the table `auto` looks small numbers up
+//! in) takes them from here instead of hardcoding or sieving its own. The
+//! table is built on first use, from the on-disk sieve cache when one is
+//! installed.
+
+use std::sync::OnceLock;
+
+use crate::cache;
+use crate::core_algorithms::sieve_of_eratosthenes::primes;
+
+/// Every number below this is in the table
+pub const LIMIT: u64 = 1 << 16;
+
+struct Table {
+    // One bit per number, in the layout of the sieve
+    bitmap: Vec<u64>,
+    primes: Vec<u64>,
+}
+
+fn table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let bitmap = cache::prime_bitmap(LIMIT - 1);
+        let primes = primes(&bitmap, LIMIT - 1).collect();
+        Table { bitmap, primes }
+    })
+}
+
+/// The primes below [`LIMIT`], in increasing order
+pub fn all() -> &'static [u64] {
+    &table().primes
+}
+
+/// The first `count` primes
+///
+/// # Panics
+///
+/// If `count` exceeds the 6542 primes below [`LIMIT`].
+pub fn first(count: usize) -> &'static [u64] {
+    &all()[..count]
+}
+
+/// The primes up to `bound`, all of the table's for a bound of [`LIMIT`]
+/// or more
+pub fn up_to(bound: u64) -> &'static [u64] {
+    let primes = all();
+    &primes[..primes.partition_point(|&p| p <= bound)]
+}
+
+/// Whether `n` is prime, None from [`LIMIT`] up
+pub fn is_prime(n: u64) -> Option<bool> {
+    (n < LIMIT).then(|| table().bitmap[(n / 64) as usize] & (1 << (n % 64)) != 0)
+}
+
+/// How a probable prime test's `bases` read in its description: "base 2",
+/// "the first 12 primes", or "5 bases" for any other set
+pub(crate) fn describe_bases(bases: &[u64]) -> String {
+    match bases {
+        [a] => format!("base {}", a),
+        _ if all().starts_with(bases) => format!("the first {} primes", bases.len()),
+        _ => format!("{} bases", bases.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_table() {
+        assert_eq!(all(), sieve(LIMIT - 1));
+        assert_eq!(all().len(), 6542);
+        assert_eq!(first(5), [2, 3, 5, 7, 11]);
+        assert_eq!(up_to(30).len(), 10);
+        assert_eq!(up_to(29).last(), Some(&29));
+        assert!(up_to(1).is_empty());
+        assert_eq!(up_to(u64::MAX).len(), 6542);
+
+        assert_eq!(is_prime(65_521), Some(true));
+        assert_eq!(is_prime(65_535), Some(false));
+        assert_eq!(is_prime(LIMIT), None);
+        assert!((0..1000).all(|n| is_prime(n) == Some(all().contains(&n))));
+
+        assert_eq!(describe_bases(&[7]), "base 7");
+        assert_eq!(describe_bases(first(12)), "the first 12 primes");
+        assert_eq!(describe_bases(&[2, 5, 7]), "3 bases");
+    }
+}
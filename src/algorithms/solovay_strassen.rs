@@ -0,0 +1,257 @@
+//! Solovay-Strassen probable prime test
+//!
+//! Euler's criterion: for an odd prime n and a base a it does not divide,
+//! a^((n - 1)/2) ≡ (a/n) (mod n), where (a/n) is the Jacobi symbol. Unlike
+//! Fermat's congruence, no composite satisfies it for every base coprime to
+//! it: at most half of the bases are liars, so k random bases bound the error
+//! by 2^-k. Miller-Rabin's liars are a subset of these, which is why it
+//! replaced this test in practice. Inputs go up to u128, with the
+//! exponentiation done in 128-bit modular arithmetic.
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, small_primes};
+use crate::error::{Error, Result};
+use crate::math::{jacobi, pow_mod_u128};
+
+/// Bases used when `solovay-strassen.bases` is not set: the 12 primes up to
+/// 37, the same as Miller-Rabin's default witnesses
+pub const DEFAULT_BASES: usize = 12;
+
+/// Returns true if the odd `n > 3` satisfies Euler's criterion to base `a`
+pub fn is_euler_probable_prime(n: u128, a: u64) -> bool {
+    let expected = match jacobi(a as u128, n) {
+        0 => return false,
+        1 => 1,
+        _ => n - 1,
+    };
+    pow_mod_u128(a as u128, (n - 1) / 2, n) == expected
+}
+
+/// The first base of `bases` for which Euler's criterion fails, None if `n`
+/// passes them all. Bases that are multiples of `n` are skipped.
+pub fn find_witness(n: u128, bases: &[u64]) -> Option<u64> {
+    bases
+        .iter()
+        .copied()
+        .find(|&a| !(a as u128).is_multiple_of(n) && !is_euler_probable_prime(n, a))
+}
+
+/// Returns true if `n` is an Euler-Jacobi probable prime to every base in `bases`
+pub fn is_prime_with_bases(n: u64, bases: &[u64]) -> bool {
+    is_prime_u128_with_bases(n as u128, bases)
+}
+
+/// [`is_prime_with_bases`] for 128-bit inputs
+pub fn is_prime_u128_with_bases(n: u128, bases: &[u64]) -> bool {
+    match n {
+        0 | 1 => false,
+        2 | 3 => true,
+        _ => n & 1 == 1 && find_witness(n, bases).is_none(),
+    }
+}
+
+/// Why Solovay-Strassen rejects `n` with the given bases, None if it accepts `n`
+pub fn evidence_with_bases(n: u64, bases: &[u64]) -> Option<Evidence> {
+    match n {
+        0 | 1 => Some(Evidence::BelowTwo),
+        2 | 3 => None,
+        _ if n.is_multiple_of(2) => Some(Evidence::Divisor(2)),
+        _ => find_witness(n as u128, bases).map(Evidence::EulerWitness),
+    }
+}
+
+/// [`is_prime_with_bases`], describing the Jacobi symbol and a^((n - 1)/2)
+/// mod n of every base to `step`
+pub fn trace_with_bases(n: u64, bases: &[u64], step: TraceFn<'_>) -> bool {
+    if n < 4 || n.is_multiple_of(2) {
+        let is_prime = is_prime_with_bases(n, bases);
+        step(format_args!(
+            "{} is below 4 or even, decided directly: {}",
+            n,
+            if is_prime { "prime" } else { "composite" }
+        ));
+        return is_prime;
+    }
+
+    for &a in bases {
+        if a.is_multiple_of(n) {
+            step(format_args!("a = {}: a multiple of n, skipped", a));
+            continue;
+        }
+
+        let symbol = jacobi(a as u128, n as u128);
+        if symbol == 0 {
+            step(format_args!(
+                "a = {}: ({}/{}) = 0, a shares a factor with n: composite",
+                a, a, n
+            ));
+            return false;
+        }
+
+        let x = pow_mod_u128(a as u128, (n as u128 - 1) / 2, n as u128);
+        let signed = if x == n as u128 - 1 { -1 } else { x as i64 };
+        step(format_args!(
+            "a = {}: ({}/{}) = {}, {}^{} mod {} ≡ {}",
+            a,
+            a,
+            n,
+            symbol,
+            a,
+            (n - 1) / 2,
+            n,
+            signed
+        ));
+        if signed != symbol as i64 {
+            step(format_args!("{} is an Euler witness: composite", a));
+            return false;
+        }
+    }
+
+    step(format_args!("Euler-Jacobi probable prime to every base"));
+    true
+}
+
+pub struct SolovayStrassen {
+    bases: Vec<u64>,
+    description: String,
+}
+
+impl SolovayStrassen {
+    pub fn with_bases(bases: Vec<u64>) -> Self {
+        SolovayStrassen {
+            description: format!(
+                "Euler-Jacobi probable prime test to {}, using Jacobi symbols",
+                small_primes::describe_bases(&bases)
+            ),
+            bases,
+        }
+    }
+
+    /// Reads the `solovay-strassen.bases` knob, the number of bases, which
+    /// are the first primes (default: [`DEFAULT_BASES`])
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        const KEY: &str = "solovay-strassen.bases";
+
+        let count = config.get(KEY)?.unwrap_or(DEFAULT_BASES);
+        let available = small_primes::all().len();
+        if count == 0 || count > available {
+            return Err(Error::InvalidConfigValue {
+                key: KEY.to_string(),
+                value: count.to_string(),
+                hint: Some(format!("expected 1 to {} bases", available)),
+            });
+        }
+
+        Ok(SolovayStrassen::with_bases(
+            small_primes::first(count).to_vec(),
+        ))
+    }
+}
+
+impl PrimalityTest for SolovayStrassen {
+    fn name(&self) -> &str {
+        "solovay-strassen"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime_with_bases(n, &self.bases)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128_with_bases(n, &self.bases)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence_with_bases(n, &self.bases)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_bases(n, &self.bases, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_carmichael() {
+        // Carmichael numbers fool Fermat for every coprime base, but not
+        // Euler's criterion: 561 = 3 · 11 · 17 fails it to base 5
+        assert!(crate::algorithms::fermat::is_prime_with_bases(561, &[5]));
+        assert_eq!(
+            evidence_with_bases(561, &[5]),
+            Some(Evidence::EulerWitness(5))
+        );
+
+        // Nor does the Carmichael number 252601 = 41 · 61 · 101, which has
+        // no factor among the default bases
+        let test = SolovayStrassen::from_config(&AlgorithmConfig::default()).unwrap();
+        assert!(!test.is_prime(252601));
+        assert!(!test.is_prime(3215031751));
+
+        // 1105 = 5 · 13 · 17 is an Euler-Jacobi pseudoprime to base 2
+        assert!(is_prime_with_bases(1105, &[2]));
+    }
+
+    #[test]
+    fn test_widths() {
+        let test = SolovayStrassen::from_config(&AlgorithmConfig::default()).unwrap();
+        let primes = sieve(10_000);
+        for n in 0..=10_000 {
+            let expected = primes.binary_search(&n).is_ok();
+            assert_eq!(test.is_prime(n), expected, "{}", n);
+            assert_eq!(test.is_prime_u128(n as u128), expected, "{}", n);
+        }
+
+        // 2^64 - 59 and 2^127 - 1 are prime, 3 · (2^64 + 13) is not
+        assert!(test.is_prime(18446744073709551557));
+        assert!(test.is_prime_u128(u128::MAX >> 1));
+        assert!(!test.is_prime_u128(3 * 18446744073709551629));
+
+        assert_eq!(
+            test.description(),
+            "Euler-Jacobi probable prime test to the first 12 primes, using Jacobi symbols"
+        );
+        let config =
+            AlgorithmConfig::from_pairs(&[("solovay-strassen.bases".to_string(), "4".to_string())])
+                .unwrap();
+        assert_eq!(
+            SolovayStrassen::from_config(&config).unwrap().description(),
+            "Euler-Jacobi probable prime test to the first 4 primes, using Jacobi symbols"
+        );
+
+        let config =
+            AlgorithmConfig::from_pairs(&[("solovay-strassen.bases".to_string(), "0".to_string())])
+                .unwrap();
+        assert!(matches!(
+            SolovayStrassen::from_config(&config),
+            Err(Error::InvalidConfigValue { hint: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(!trace_with_bases(561, &[2, 5], &mut |step| {
+            steps.push(step.to_string())
+        }));
+        assert_eq!(
+            steps,
+            [
+                "a = 2: (2/561) = 1, 2^280 mod 561 ≡ 1",
+                "a = 5: (5/561) = 1, 5^280 mod 561 ≡ 67",
+                "5 is an Euler witness: composite",
+            ]
+        );
+    }
+}
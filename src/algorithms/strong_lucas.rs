@@ -0,0 +1,412 @@
+//! Strong Lucas probable prime test with Selfridge's parameters
+//!
+//! Selfridge's method A picks the first D in 5, -7, 9, -11, ... with Jacobi
+//! symbol (D/n) = -1, and sets P = 1, Q = (1 - D) / 4. With n + 1 = 2^s · k
+//! and k odd, a prime n has U_k ≡ 0 or V_(k·2^r) ≡ 0 (mod n) for some
+//! 0 ≤ r < s. This is the second half of Baillie-PSW run on its own: its
+//! pseudoprimes (5459, 5777, 10877, ...) are not the ones of the base-2
+//! strong test, which is why the pair has no known counterexample.
+
+use num_bigint::BigUint;
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+use crate::math::{add_mod_u128, jacobi, mul_mod_u128};
+
+/// Returns true if `n` is a strong Lucas probable prime
+pub fn is_prime(n: u64) -> bool {
+    is_prime_u128(n as u128)
+}
+
+/// [`is_prime`] for 128-bit inputs
+pub fn is_prime_u128(n: u128) -> bool {
+    match n {
+        0 | 1 => false,
+        2 => true,
+        _ => n & 1 == 1 && is_strong_lucas_probable_prime(n),
+    }
+}
+
+/// [`is_prime`] for inputs of any size. Inputs that fit in a u128 take the
+/// fixed-width path.
+pub fn is_prime_big(n: &BigUint) -> bool {
+    if let Ok(n) = u128::try_from(n) {
+        return is_prime_u128(n);
+    }
+    n.bit(0) && is_strong_lucas_probable_prime_big(n)
+}
+
+/// Why the strong Lucas test rejects `n`, None if it accepts `n`
+pub fn evidence(n: u64) -> Option<Evidence> {
+    match n {
+        0 | 1 => Some(Evidence::BelowTwo),
+        2 => None,
+        _ if n.is_multiple_of(2) => Some(Evidence::Divisor(2)),
+        _ if n.isqrt() * n.isqrt() == n => Some(Evidence::PerfectPower {
+            base: n.isqrt(),
+            exponent: 2,
+        }),
+        _ => (!is_strong_lucas_probable_prime(n as u128)).then_some(Evidence::LucasTest),
+    }
+}
+
+/// [`is_prime`], describing Selfridge's parameters and the Lucas test to `step`
+pub fn trace(n: u64, step: TraceFn<'_>) -> bool {
+    if n < 3 || n.is_multiple_of(2) {
+        let is_prime = is_prime(n);
+        step(format_args!(
+            "{} is below 3 or even, decided directly: {}",
+            n,
+            if is_prime { "prime" } else { "composite" }
+        ));
+        return is_prime;
+    }
+    trace_odd(n, step)
+}
+
+/// The strong Lucas test of an odd `n` > 1 described to `step`, shared with
+/// Baillie-PSW's trace
+pub(crate) fn trace_odd(n: u64, step: TraceFn<'_>) -> bool {
+    if n.isqrt() * n.isqrt() == n {
+        step(format_args!(
+            "{} = {}², so no Selfridge parameter exists: composite",
+            n,
+            n.isqrt()
+        ));
+        return false;
+    }
+
+    // Selfridge's method A: the first D in 5, -7, 9, -11, ... with (D/n) = -1
+    let mut d: i64 = 5;
+    loop {
+        let residue = if d < 0 {
+            n - d.unsigned_abs() % n
+        } else {
+            d as u64 % n
+        };
+        match jacobi(residue as u128, n as u128) {
+            -1 => break,
+            0 if d.unsigned_abs() != n => {
+                step(format_args!(
+                    "({}/{}) = 0, so {} shares a factor with {}: composite",
+                    d, n, d, n
+                ));
+                return false;
+            }
+            symbol => step(format_args!("({}/{}) = {}", d, n, symbol)),
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+    step(format_args!(
+        "({}/{}) = -1: Selfridge parameters D = {}, P = 1, Q = {}",
+        d,
+        n,
+        d,
+        (1 - d) / 4
+    ));
+
+    let is_prime = is_strong_lucas_probable_prime(n as u128);
+    step(format_args!(
+        "strong Lucas probable prime test: {}",
+        if is_prime {
+            "passes, prime"
+        } else {
+            "fails, composite"
+        }
+    ));
+    is_prime
+}
+
+/// Strong Lucas probable prime test for an odd `n`, using Selfridge's
+/// method A: the first D in 5, -7, 9, -11, ... with (D/n) = -1, P = 1 and
+/// Q = (1 - D) / 4
+pub fn is_strong_lucas_probable_prime(n: u128) -> bool {
+    // No suitable D exists for perfect squares
+    if n.isqrt() * n.isqrt() == n {
+        return false;
+    }
+
+    // D and Q are kept as residues mod n, since either may be negative
+    let mut abs_d: u128 = 5;
+    let mut negative = false;
+    let d = loop {
+        let d = if negative { n - abs_d % n } else { abs_d % n };
+        match jacobi(d, n) {
+            -1 => break d,
+            0 if abs_d != n => return false,
+            _ => {}
+        }
+        abs_d += 2;
+        negative = !negative;
+    };
+
+    // Q = (1 - D) / 4, computed on the signed value of D
+    let q = if negative {
+        (1 + abs_d) / 4 % n
+    } else {
+        n - ((abs_d - 1) / 4) % n
+    };
+
+    // n + 1 = 2^s * k with k odd, without overflowing when n = u128::MAX - ...
+    let mut k = n / 2 + 1;
+    let mut s = 1;
+    while k.is_multiple_of(2) {
+        k /= 2;
+        s += 1;
+    }
+
+    let half = |x: u128| {
+        if x.is_multiple_of(2) {
+            x / 2
+        } else {
+            x / 2 + n / 2 + 1
+        }
+    };
+    let sub = |a: u128, b: u128| if a >= b { a - b } else { n - (b - a) };
+
+    // Walk the bits of k from the top, tracking U_j, V_j and Q^j
+    let (mut u, mut v, mut q_j) = (1, 1, q);
+    for bit in (0..k.ilog2()).rev() {
+        // Doubling: U_2j = U_j V_j, V_2j = V_j^2 - 2 Q^j
+        u = mul_mod_u128(u, v, n);
+        v = sub(mul_mod_u128(v, v, n), add_mod_u128(q_j, q_j, n));
+        q_j = mul_mod_u128(q_j, q_j, n);
+
+        if (k >> bit) & 1 == 1 {
+            // Increment: U_j+1 = (U_j + V_j) / 2, V_j+1 = (D U_j + V_j) / 2
+            let next_u = half(add_mod_u128(u, v, n));
+            v = half(add_mod_u128(mul_mod_u128(d, u, n), v, n));
+            u = next_u;
+            q_j = mul_mod_u128(q_j, q, n);
+        }
+    }
+
+    if u == 0 || v == 0 {
+        return true;
+    }
+
+    // V_(k * 2^r) for r in 1..s
+    for _ in 1..s {
+        v = sub(mul_mod_u128(v, v, n), add_mod_u128(q_j, q_j, n));
+        if v == 0 {
+            return true;
+        }
+        q_j = mul_mod_u128(q_j, q_j, n);
+    }
+
+    false
+}
+
+// Jacobi symbol (d/n) for a small odd d and a large odd n, reduced through
+// reciprocity to a symbol with a small modulus
+pub(crate) fn jacobi_small_big(d: i64, n: &BigUint) -> i32 {
+    let n_mod_8 = (n % 8u32).to_u32_digits().first().copied().unwrap_or(0);
+    let a = d.unsigned_abs();
+
+    let mut result = 1;
+    // (-1/n) = -1 exactly when n = 3 (mod 4)
+    if d < 0 && n_mod_8 % 4 == 3 {
+        result = -result;
+    }
+    if a % 4 == 3 && n_mod_8 % 4 == 3 {
+        result = -result;
+    }
+
+    let n_mod_a = (n % a).iter_u64_digits().next().unwrap_or(0);
+    result * jacobi(n_mod_a as u128, a as u128)
+}
+
+/// Strong Lucas probable prime test over arbitrary-precision integers, with
+/// the same Selfridge parameters as [`is_strong_lucas_probable_prime`], for
+/// an odd `n` larger than every D tried
+pub fn is_strong_lucas_probable_prime_big(n: &BigUint) -> bool {
+    if n.sqrt().pow(2) == *n {
+        return false;
+    }
+
+    let mut d: i64 = 5;
+    loop {
+        match jacobi_small_big(d, n) {
+            -1 => break,
+            0 => return false,
+            _ => {}
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+
+    // Signed values reduced into [0, n)
+    let residue = |x: i64| {
+        let magnitude = BigUint::from(x.unsigned_abs()) % n;
+        if x < 0 && magnitude.bits() > 0 {
+            n - magnitude
+        } else {
+            magnitude
+        }
+    };
+    let big_d = residue(d);
+    let q = residue((1 - d) / 4);
+
+    let half = |x: BigUint| {
+        if x.bit(0) { (x + n) >> 1 } else { x >> 1 }
+    };
+    let sub = |a: &BigUint, b: &BigUint| (a + n - b) % n;
+
+    // n + 1 = 2^s * k with k odd
+    let n_plus_one = n + 1u32;
+    let s = n_plus_one.trailing_zeros().unwrap_or(0);
+    let k = &n_plus_one >> s;
+
+    let (mut u, mut v, mut q_j) = (BigUint::from(1u32), BigUint::from(1u32), q.clone());
+    for bit in (0..k.bits() - 1).rev() {
+        u = &u * &v % n;
+        v = sub(&(&v * &v % n), &(&q_j * 2u32 % n));
+        q_j = &q_j * &q_j % n;
+
+        if k.bit(bit) {
+            let next_u = half((&u + &v) % n);
+            v = half((&big_d * &u + &v) % n);
+            u = next_u;
+            q_j = &q_j * &q % n;
+        }
+    }
+
+    if u.bits() == 0 || v.bits() == 0 {
+        return true;
+    }
+
+    for _ in 1..s {
+        v = sub(&(&v * &v % n), &(&q_j * 2u32 % n));
+        if v.bits() == 0 {
+            return true;
+        }
+        q_j = &q_j * &q_j % n;
+    }
+
+    false
+}
+
+pub struct StrongLucas;
+
+impl PrimalityTest for StrongLucas {
+    fn name(&self) -> &str {
+        "strong-lucas"
+    }
+
+    fn description(&self) -> &str {
+        "Strong Lucas probable prime test with Selfridge's parameters, without BPSW's base-2 round"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        is_prime_u128(n)
+    }
+
+    fn is_prime_big(&self, n: &BigUint) -> Option<bool> {
+        Some(is_prime_big(n))
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace(n, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::miller_rabin;
+
+    #[test]
+    fn test_pseudoprimes() {
+        // Strong Lucas pseudoprimes pass, though the base-2 test catches them
+        for n in [5459, 5777, 10877, 16109, 18971] {
+            assert!(is_prime(n), "{n}");
+            assert!(!miller_rabin::is_prime_with_witnesses(n, &[2]), "{n}");
+        }
+
+        // Strong pseudoprimes to base 2 do not
+        for n in [2047, 3277, 4033, 4681, 8321, 3215031751] {
+            assert_eq!(evidence(n), Some(Evidence::LucasTest), "{n}");
+        }
+
+        // The composites below 10^5 it accepts, OEIS A217255
+        let accepted: Vec<u64> = (0..100_000)
+            .filter(|&n| is_prime(n) != miller_rabin::is_prime(n))
+            .collect();
+        assert_eq!(
+            accepted,
+            [
+                5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519, 75077, 97439
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evidence() {
+        assert_eq!(evidence(1), Some(Evidence::BelowTwo));
+        assert_eq!(evidence(10), Some(Evidence::Divisor(2)));
+        assert_eq!(
+            evidence(49),
+            Some(Evidence::PerfectPower {
+                base: 7,
+                exponent: 2
+            })
+        );
+        for n in 0..10_000 {
+            assert_eq!(evidence(n).is_none(), is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_widths() {
+        assert!(is_prime(18446744073709551557)); // Largest prime < 2^64
+        assert!(is_prime_u128(u128::MAX - 158)); // 2^128 - 159
+        assert!(!is_prime_u128(u128::MAX));
+
+        let one = BigUint::from(1u32);
+        let m521 = (&one << 521) - 1u32;
+        let m607 = (&one << 607) - 1u32;
+        assert!(is_prime_big(&m521));
+        assert!(!is_prime_big(&(&m521 * &m607)));
+        assert!(!is_prime_big(&(&m521 * &m521))); // Perfect square
+        assert!(!is_prime_big(&((&one << 523) - 1u32)));
+
+        // Both Jacobi symbol implementations agree
+        for d in [5i64, -7, 9, -11, 13, -15, 17] {
+            for n in [u128::MAX - 158, (1 << 127) - 1, 18446744073709551629] {
+                let expected = jacobi(
+                    if d < 0 {
+                        n - d.unsigned_abs() as u128
+                    } else {
+                        d as u128
+                    },
+                    n,
+                );
+                assert_eq!(
+                    jacobi_small_big(d, &BigUint::from(n)),
+                    expected,
+                    "({d}/{n})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(trace(5459, &mut |step| steps.push(step.to_string())));
+        assert_eq!(
+            steps.last().map(String::as_str),
+            Some("strong Lucas probable prime test: passes, prime")
+        );
+    }
+}
@@ -1,70 +1,199 @@
-pub fn is_prime(n: u64) -> bool {
+//! Trial division by odd numbers, bounded by `i * i <= n`
+//!
+//! The functions live in the `no_std`
+//! [`core_algorithms::trial_division`](crate::core_algorithms::trial_division)
+//! module and are re-exported here next to the registry type.
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+use crate::error::{Error, Result};
+
+pub use crate::core_algorithms::trial_division::{
+    Wheel, is_prime, is_prime_u32, is_prime_u128, is_prime_with_wheel, is_prime_with_wheel_bounded,
+    is_prime_with_wheel_u128, smallest_divisor,
+};
+
+/// Why trial division rejects `n`: the smallest divisor it finds.
+/// Shared by every trial division variant, which all scan divisors upwards.
+pub fn evidence(n: u64) -> Option<Evidence> {
+    if n < 2 {
+        return Some(Evidence::BelowTwo);
+    }
+
+    smallest_divisor(n).map(Evidence::Divisor)
+}
+
+/// [`is_prime_with_wheel`], describing each divisor tried to `step`
+pub fn trace_with_wheel(n: u64, wheel: Wheel, step: TraceFn<'_>) -> bool {
     if n <= 1 {
+        step(format_args!("{} is below 2, so it is not prime", n));
         return false;
     }
 
-    if n % 2 == 0 {
+    for &p in wheel.basis() {
+        if n == p {
+            step(format_args!("{} is the basis prime {}: prime", n, p));
+            return true;
+        }
+        if n.is_multiple_of(p) {
+            step(format_args!("{} mod {} = 0: composite", n, p));
+            return false;
+        }
+        step(format_args!("{} mod {} = {}", n, p, n % p));
+    }
+
+    let (mut i, gaps) = wheel.spokes();
+    for gap in gaps.iter().cycle() {
+        if i > n / i {
+            step(format_args!(
+                "{}² > {}, no divisor is left to try: prime",
+                i, n
+            ));
+            return true;
+        }
+        if n.is_multiple_of(i) {
+            step(format_args!("{} mod {} = 0: composite", n, i));
+            return false;
+        }
+        step(format_args!("{} mod {} = {}", n, i, n % i));
+
+        i += gap;
+    }
+
+    unreachable!("the wheel cycles forever")
+}
+
+/// Trial division by 2 and the odd numbers up to a precomputed `bound`,
+/// describing each divisor tried to `step`
+pub fn trace_up_to(n: u64, bound: u64, step: TraceFn<'_>) -> bool {
+    if n <= 1 {
+        step(format_args!("{} is below 2, so it is not prime", n));
+        return false;
+    }
+    if n.is_multiple_of(2) {
+        step(format_args!("{} mod 2 = 0: {}", n, verdict(n == 2)));
         return n == 2;
     }
+    step(format_args!("{} mod 2 = 1", n));
 
     let mut i: u64 = 3;
-
-    while i * i <= n {
-        if n % i == 0 {
+    while i <= bound {
+        if n.is_multiple_of(i) {
+            step(format_args!("{} mod {} = 0: composite", n, i));
             return false;
         }
+        step(format_args!("{} mod {} = {}", n, i, n % i));
 
         i += 2;
     }
 
-    return true;
+    step(format_args!(
+        "no odd divisor up to the bound {}: prime",
+        bound
+    ));
+    true
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn verdict(is_prime: bool) -> &'static str {
+    if is_prime { "prime" } else { "composite" }
+}
+
+pub struct TrialDivision {
+    wheel: Option<Wheel>,
+}
 
-    #[test]
-    fn test_edge_cases() {
-        assert!(!is_prime(0));
-        assert!(!is_prime(1));
+impl TrialDivision {
+    /// Reads the `trial-division.wheel` knob, defaulting to odd divisors only
+    pub fn from_config(config: &AlgorithmConfig) -> Result<Self> {
+        let wheel = match config.get::<u64>("trial-division.wheel")? {
+            Some(modulus) => {
+                Some(
+                    Wheel::from_modulus(modulus).ok_or_else(|| Error::InvalidConfigValue {
+                        key: "trial-division.wheel".to_string(),
+                        value: modulus.to_string(),
+                        hint: Some("use 2, 6, or 30".to_string()),
+                    })?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(TrialDivision { wheel })
     }
+}
 
-    #[test]
-    fn test_small_primes() {
-        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
-        for &p in &primes {
-            assert!(is_prime(p));
-        }
+impl PrimalityTest for TrialDivision {
+    fn name(&self) -> &str {
+        "trial-division"
     }
 
-    #[test]
-    fn test_small_composites() {
-        let composites = [4, 6, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 22, 24, 25];
-        for &c in &composites {
-            assert!(!is_prime(c));
-        }
+    fn description(&self) -> &str {
+        "Trial division by odd numbers while i * i <= n"
     }
 
-    #[test]
-    fn test_larger_primes() {
-        let primes = [97, 541, 7919, 104729];
-        for &p in &primes {
-            assert!(is_prime(p));
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        // i * i overflows once i passes u32::MAX
+        0..=(u32::MAX as u64).pow(2) - 1
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        match self.wheel {
+            Some(wheel) => is_prime_with_wheel(n, wheel),
+            None => is_prime(n),
         }
     }
 
-    #[test]
-    fn test_larger_composites() {
-        let composites = [100, 1000, 10000, 52939758, 1029105];
-        for &c in &composites {
-            assert!(!is_prime(c));
+    fn supported_range_u128(&self) -> std::ops::RangeInclusive<u128> {
+        0..=u128::MAX
+    }
+
+    fn is_prime_u128(&self, n: u128) -> bool {
+        match u64::try_from(n) {
+            Ok(n) if self.supported_range().contains(&n) => self.is_prime(n),
+            _ => is_prime_with_wheel_u128(n, self.wheel.unwrap_or(Wheel::Two)),
         }
     }
 
-    #[test]
-    fn test_large_primes() {
-        assert!(is_prime(2147483647)); // 2^31 - 1 (Mersenne prime)
-        assert!(is_prime(4294967291)); // Largest prime < 2^32
+    fn is_prime_bounded(&self, n: u64, bound: u64) -> Option<bool> {
+        is_prime_with_wheel_bounded(n, self.wheel.unwrap_or(Wheel::Two), bound)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_wheel(n, self.wheel.unwrap_or(Wheel::Two), step))
+    }
+}
+
+/// Trial division of 32-bit inputs in 32-bit arithmetic, to measure what
+/// the narrower division buys against [`TrialDivision`]
+pub struct TrialDivisionU32;
+
+impl PrimalityTest for TrialDivisionU32 {
+    fn name(&self) -> &str {
+        "trial-division-u32"
+    }
+
+    fn description(&self) -> &str {
+        "Trial division by odd numbers in 32-bit arithmetic, for n < 2^32"
+    }
+
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        0..=u64::from(u32::MAX)
+    }
+
+    /// False past [`supported_range`](PrimalityTest::supported_range)
+    fn is_prime(&self, n: u64) -> bool {
+        u32::try_from(n).is_ok_and(is_prime_u32)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace_with_wheel(n, Wheel::Two, step))
     }
 }
@@ -1,54 +1,69 @@
+//! Trial division by odd numbers, bounded by Newton's integer square root
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, trial_division};
+use crate::math::isqrt;
+
+/// Returns true if `n` is prime
 pub fn is_prime(n: u64) -> bool {
     if n <= 1 {
         return false;
     }
 
-    if n % 2 == 0 {
+    if n.is_multiple_of(2) {
         return n == 2;
     }
 
     let mut i: u64 = 3;
-    let sqrt = newton_sqrt_integer(n);
+    let sqrt = isqrt(n);
 
     while i <= sqrt {
-        if n % i == 0 {
+        if n.is_multiple_of(i) {
             return false;
         }
 
         i += 2;
     }
 
-    return true;
+    true
 }
 
-// Inspired by https://www.johndcook.com/blog/2024/01/01/computing-square-root-floor/
-fn newton_sqrt_integer(n: u64) -> u64 {
-    let n_u128: u128 = n as u128;
+pub struct TrialDivisionNewton;
+
+impl PrimalityTest for TrialDivisionNewton {
+    fn name(&self) -> &str {
+        "trial-division-newton"
+    }
 
-    let mut a: u128 = n_u128;
-    let mut b: u128 = (n_u128 + 1) / 2;
+    fn description(&self) -> &str {
+        "Trial division bounded by Newton's integer square root"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
 
-    while b < a {
-        a = b;
-        b = (a * a + n_u128) / (2 * a);
+    fn is_prime_bounded(&self, n: u64, bound: u64) -> Option<bool> {
+        trial_division::is_prime_with_wheel_bounded(n, trial_division::Wheel::Two, bound)
     }
 
-    a as u64
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        trial_division::evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let bound = isqrt(n);
+        step(format_args!(
+            "floor(sqrt({})) = {} (Newton's method)",
+            n, bound
+        ));
+        Some(trial_division::trace_up_to(n, bound, step))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_newton_sqrt() {
-        assert_eq!(newton_sqrt_integer(4), 2);
-        assert_eq!(newton_sqrt_integer(9), 3);
-        assert_eq!(newton_sqrt_integer(16), 4);
-        assert_eq!(newton_sqrt_integer(100), 10);
-        assert_eq!(newton_sqrt_integer(101), 10);
-    }
-
     #[test]
     fn test_edge_cases() {
         assert!(!is_prime(0));
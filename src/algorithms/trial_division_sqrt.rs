@@ -1,9 +1,14 @@
+//! Trial division by odd numbers, bounded by a floating-point `sqrt(n)`
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn, trial_division};
+
+/// Returns true if `n` is prime
 pub fn is_prime(n: u64) -> bool {
     if n <= 1 {
         return false;
     }
 
-    if n % 2 == 0 {
+    if n.is_multiple_of(2) {
         return n == 2;
     }
 
@@ -11,14 +16,47 @@ pub fn is_prime(n: u64) -> bool {
     let sqrt = (n as f64).sqrt() as u64;
 
     while i <= sqrt {
-        if n % i == 0 {
+        if n.is_multiple_of(i) {
             return false;
         }
 
         i += 2;
     }
 
-    return true;
+    true
+}
+
+pub struct TrialDivisionSqrt;
+
+impl PrimalityTest for TrialDivisionSqrt {
+    fn name(&self) -> &str {
+        "trial-division-sqrt"
+    }
+
+    fn description(&self) -> &str {
+        "Trial division bounded by a floating-point sqrt(n)"
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn is_prime_bounded(&self, n: u64, bound: u64) -> Option<bool> {
+        trial_division::is_prime_with_wheel_bounded(n, trial_division::Wheel::Two, bound)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        trial_division::evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        let bound = (n as f64).sqrt() as u64;
+        step(format_args!(
+            "floor(sqrt({})) = {} (floating point)",
+            n, bound
+        ));
+        Some(trial_division::trace_up_to(n, bound, step))
+    }
 }
 
 #[cfg(test)]
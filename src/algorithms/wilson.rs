@@ -0,0 +1,148 @@
+//! Wilson's theorem: n > 1 is prime if and only if (n - 1)! ≡ -1 (mod n)
+//!
+//! A correct test that costs n - 2 modular multiplications, exponentially
+//! more than trial division's √n divisions, kept to show how far apart a
+//! characterization of the primes and a practical test can be. Inputs are
+//! capped at [`MAX_N`], where one call already takes a noticeable fraction
+//! of a second.
+
+use crate::algorithms::{Evidence, PrimalityTest, TraceFn};
+
+/// Largest input accepted. Below 2^24, products of two residues fit in a u64.
+pub const MAX_N: u64 = (1 << 24) - 1;
+
+/// Returns true if `n` is prime
+///
+/// # Panics
+///
+/// If `n` exceeds [`MAX_N`].
+pub fn is_prime(n: u64) -> bool {
+    evidence(n).is_none()
+}
+
+/// (n - 1)! mod n, None for n below 2
+///
+/// # Panics
+///
+/// If `n` exceeds [`MAX_N`].
+pub fn factorial_residue(n: u64) -> Option<u64> {
+    assert!(n <= MAX_N, "{} is above Wilson's test cap of {}", n, MAX_N);
+    (n >= 2).then(|| (2..n).fold(1 % n, |product, k| product * k % n))
+}
+
+/// The residue of (n - 1)! when it is not n - 1, which proves `n` composite
+pub fn evidence(n: u64) -> Option<Evidence> {
+    match factorial_residue(n) {
+        None => Some(Evidence::BelowTwo),
+        Some(residue) if residue == n - 1 => None,
+        Some(residue) => Some(Evidence::WilsonResidue(residue)),
+    }
+}
+
+/// [`is_prime`], describing the product to `step`: where it first vanishes
+/// for a composite and the final residue
+pub fn trace(n: u64, step: TraceFn<'_>) -> bool {
+    if n < 2 {
+        step(format_args!("{} is below 2, so it is not prime", n));
+        return false;
+    }
+    assert!(n <= MAX_N, "{} is above Wilson's test cap of {}", n, MAX_N);
+
+    step(format_args!(
+        "computing ({} - 1)! mod {}: {} multiplications",
+        n,
+        n,
+        n.saturating_sub(2)
+    ));
+
+    let mut product = 1 % n;
+    let mut vanished = false;
+    for k in 2..n {
+        product = product * k % n;
+        if product == 0 && !vanished {
+            step(format_args!(
+                "{}! ≡ 0 (mod {}): the first factorial divisible by {}, so the product stays 0",
+                k, n, n
+            ));
+            vanished = true;
+        }
+    }
+
+    let is_prime = product == n - 1;
+    step(format_args!(
+        "({} - 1)! ≡ {} (mod {}), {} -1: {}",
+        n,
+        product,
+        n,
+        if is_prime { "which is" } else { "not" },
+        if is_prime { "prime" } else { "composite" }
+    ));
+    is_prime
+}
+
+pub struct Wilson;
+
+impl PrimalityTest for Wilson {
+    fn name(&self) -> &str {
+        "wilson"
+    }
+
+    fn description(&self) -> &str {
+        "Wilson's theorem, (n - 1)! ≡ -1 (mod n); correct but O(n), for teaching"
+    }
+
+    fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+        0..=MAX_N
+    }
+
+    fn is_prime(&self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    fn evidence(&self, n: u64) -> Option<Evidence> {
+        evidence(n)
+    }
+
+    fn trace(&self, n: u64, step: TraceFn<'_>) -> Option<bool> {
+        Some(trace(n, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_agrees_with_sieve() {
+        let primes = sieve(2000);
+        for n in 0..=2000 {
+            assert_eq!(is_prime(n), primes.binary_search(&n).is_ok(), "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_evidence() {
+        assert_eq!(evidence(1), Some(Evidence::BelowTwo));
+        // 3! = 6 ≡ 2 (mod 4), the one composite whose product does not vanish
+        assert_eq!(evidence(4), Some(Evidence::WilsonResidue(2)));
+        assert_eq!(evidence(9), Some(Evidence::WilsonResidue(0)));
+        assert_eq!(factorial_residue(13), Some(12));
+        assert!(is_prime(MAX_N - 2)); // 16777213 is prime
+    }
+
+    #[test]
+    #[should_panic(expected = "above Wilson's test cap")]
+    fn test_cap() {
+        is_prime(MAX_N + 1);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut steps = Vec::new();
+        assert!(!trace(12, &mut |line| steps.push(line.to_string())));
+        assert_eq!(steps.len(), 3);
+        assert!(steps[1].starts_with("4! ≡ 0 (mod 12)"));
+        assert_eq!(steps[2], "(12 - 1)! ≡ 0 (mod 12), not -1: composite");
+    }
+}
@@ -0,0 +1,182 @@
+//! Empirical complexity fitting
+//! Fits observed (n, time) measurements to the two model families that
+//! describe the algorithms in this crate: polynomial in n (t = c * n^a, e.g.
+//! trial division's sqrt(n)) and polylogarithmic (t = c * log2(n)^k, e.g.
+//! Miller-Rabin and AKS). Both are linear regressions in log space.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Family {
+    Polynomial,
+    Polylog,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fit {
+    pub family: Family,
+    pub exponent: f64,
+    pub coefficient: f64,
+    pub r_squared: f64,
+}
+
+impl Fit {
+    /// Human readable form of the fitted model, e.g. "n^0.50" or "log^2.93 n"
+    pub fn describe(&self) -> String {
+        match self.family {
+            Family::Polynomial => format!("n^{:.2}", self.exponent),
+            Family::Polylog => format!("log^{:.2} n", self.exponent),
+        }
+    }
+
+    /// Closest textbook complexity class for the fitted exponent
+    pub fn nearest_class(&self) -> String {
+        match self.family {
+            Family::Polynomial => {
+                let classes = [(0.25, "n^(1/4)"), (0.5, "√n"), (1.0, "n")];
+                classes
+                    .iter()
+                    .min_by(|a, b| {
+                        (a.0 - self.exponent)
+                            .abs()
+                            .total_cmp(&(b.0 - self.exponent).abs())
+                    })
+                    .map(|c| c.1.to_string())
+                    .unwrap()
+            }
+            Family::Polylog => {
+                let k = self.exponent.round().max(0.0) as u32;
+                match k {
+                    0 => "1".to_string(),
+                    1 => "log n".to_string(),
+                    _ => format!("log^{} n", k),
+                }
+            }
+        }
+    }
+}
+
+/// Fits `points` (n, seconds) to a single model family.
+/// Returns None if there are fewer than two usable points.
+pub fn fit(family: Family, points: &[(u64, f64)]) -> Option<Fit> {
+    // Transform to log space: ln t = ln c + exponent * x
+    let (xs, ys): (Vec<f64>, Vec<f64>) = points
+        .iter()
+        .filter(|&&(n, t)| n >= 3 && t > 0.0)
+        .map(|&(n, t)| {
+            let x = match family {
+                Family::Polynomial => (n as f64).ln(),
+                Family::Polylog => (n as f64).log2().ln(),
+            };
+            (x, t.ln())
+        })
+        .unzip();
+
+    let (slope, intercept, r_squared) = linear_regression(&xs, &ys)?;
+
+    Some(Fit {
+        family,
+        exponent: slope,
+        coefficient: intercept.exp(),
+        r_squared,
+    })
+}
+
+/// Fits `points` to every model family and returns the one with the best R²
+pub fn best_fit(points: &[(u64, f64)]) -> Option<Fit> {
+    [Family::Polynomial, Family::Polylog]
+        .iter()
+        .filter_map(|&family| fit(family, points))
+        .max_by(|a, b| a.r_squared.total_cmp(&b.r_squared))
+}
+
+// Ordinary least squares, returns (slope, intercept, r²)
+fn linear_regression(xs: &[f64], ys: &[f64]) -> Option<(f64, f64, f64)> {
+    if xs.len() < 2 {
+        return None;
+    }
+
+    let len = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / len;
+    let mean_y = ys.iter().sum::<f64>() / len;
+
+    let mut ss_xy = 0.0;
+    let mut ss_xx = 0.0;
+    let mut ss_yy = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        ss_xy += (x - mean_x) * (y - mean_y);
+        ss_xx += (x - mean_x) * (x - mean_x);
+        ss_yy += (y - mean_y) * (y - mean_y);
+    }
+
+    if ss_xx == 0.0 {
+        return None;
+    }
+
+    let slope = ss_xy / ss_xx;
+    let intercept = mean_y - slope * mean_x;
+    // A perfectly flat series is perfectly explained by any slope-0 model
+    let r_squared = if ss_yy == 0.0 {
+        1.0
+    } else {
+        (ss_xy * ss_xy) / (ss_xx * ss_yy)
+    };
+
+    Some((slope, intercept, r_squared))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn magnitudes() -> Vec<u64> {
+        (1..=12).map(|e| 10u64.pow(e)).collect()
+    }
+
+    #[test]
+    fn test_fit_sqrt() {
+        let points: Vec<(u64, f64)> = magnitudes()
+            .into_iter()
+            .map(|n| (n, 3e-9 * (n as f64).sqrt()))
+            .collect();
+
+        let fit = fit(Family::Polynomial, &points).unwrap();
+        assert!((fit.exponent - 0.5).abs() < 1e-9);
+        assert!((fit.coefficient - 3e-9).abs() < 1e-15);
+        assert!(fit.r_squared > 0.999);
+        assert_eq!(fit.nearest_class(), "√n");
+    }
+
+    #[test]
+    fn test_fit_polylog() {
+        let points: Vec<(u64, f64)> = magnitudes()
+            .into_iter()
+            .map(|n| (n, 1e-8 * (n as f64).log2().powi(3)))
+            .collect();
+
+        let fit = fit(Family::Polylog, &points).unwrap();
+        assert!((fit.exponent - 3.0).abs() < 1e-9);
+        assert_eq!(fit.nearest_class(), "log^3 n");
+    }
+
+    #[test]
+    fn test_best_fit_picks_family() {
+        let sqrt_points: Vec<(u64, f64)> = magnitudes()
+            .into_iter()
+            .map(|n| (n, (n as f64).sqrt()))
+            .collect();
+        assert_eq!(best_fit(&sqrt_points).unwrap().family, Family::Polynomial);
+
+        let log_points: Vec<(u64, f64)> = magnitudes()
+            .into_iter()
+            .map(|n| (n, (n as f64).log2().powi(2)))
+            .collect();
+        assert_eq!(best_fit(&log_points).unwrap().family, Family::Polylog);
+    }
+
+    #[test]
+    fn test_not_enough_points() {
+        assert!(best_fit(&[]).is_none());
+        assert!(best_fit(&[(100, 1.0)]).is_none());
+    }
+}
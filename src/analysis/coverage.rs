@@ -0,0 +1,169 @@
+//! Which integers a benchmark run tested
+//! The candidates are 2 and 3, then 6k - 1 and 6k + 1 for k from 1 up, and
+//! threads lease them in chunks from a shared cursor (see
+//! [`candidates`](crate::benchmark::candidates)). Every other integer is 0,
+//! 1 or a multiple of 2 or 3 and never reaches the algorithm, so no prime is
+//! skipped, but only about a third of the integers below the largest prime
+//! found were tested.
+//!
+//! Runs with every integer as a candidate test the even numbers and small
+//! multiples as well, from 2 up.
+//!
+//! Runs record how far every candidate was tested; for results that do not,
+//! the primes bound it: a thread tested every candidate of its chunks below
+//! the last prime it found, and every chunk below those was leased earlier.
+
+use crate::benchmark::result::PrimeResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CandidateCoverage {
+    /// Largest prime found by each thread (0 for a thread without primes)
+    pub reached: Vec<u64>,
+    /// How far the run recorded testing every candidate, if it did
+    pub recorded: Option<u64>,
+    /// Every integer from 2 was a candidate, not only 2, 3 and 6k ± 1
+    pub every_integer: bool,
+    /// Numbers recorded that are not candidates, so no thread tested them
+    pub misplaced: Vec<u64>,
+}
+
+impl CandidateCoverage {
+    /// Works out the threads' progress from the primes they found and, if
+    /// the run recorded it, how far every candidate was tested. Threads are
+    /// numbered from 0 and counted up to the highest one with a prime.
+    pub fn from_results(
+        results: &[PrimeResult],
+        recorded: Option<u64>,
+        every_integer: bool,
+    ) -> CandidateCoverage {
+        let threads = results.iter().map(|p| p.thread_id + 1).max().unwrap_or(0);
+
+        let mut reached = vec![0; threads];
+        let mut misplaced = Vec::new();
+        for prime in results {
+            // Says nothing about how far its thread got
+            if !(is_candidate(prime.number) || (every_integer && prime.number >= 2)) {
+                misplaced.push(prime.number);
+                continue;
+            }
+
+            let last = &mut reached[prime.thread_id];
+            *last = (*last).max(prime.number);
+        }
+        misplaced.sort_unstable();
+
+        CandidateCoverage {
+            reached,
+            recorded,
+            every_integer,
+            misplaced,
+        }
+    }
+
+    /// Number of integers up to `n` that were candidates
+    pub fn candidates_up_to(&self, n: u64) -> u64 {
+        match self.every_integer {
+            true => n.saturating_sub(1),
+            false => candidates_up_to(n),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.reached.len()
+    }
+
+    /// Every candidate up to here was tested: as recorded, or else up to
+    /// the smallest of the threads' last primes
+    pub fn complete_up_to(&self) -> u64 {
+        self.recorded
+            .unwrap_or_else(|| self.reached.iter().copied().min().unwrap_or(0))
+    }
+
+    /// Some thread tested candidates up to here, in the chunks still in
+    /// progress when the run stopped
+    pub fn reached_up_to(&self) -> u64 {
+        self.reached.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// True if `n` is 2, 3, or of the form 6k ± 1, which some thread tests
+pub fn is_candidate(n: u64) -> bool {
+    n == 2 || n == 3 || n % 6 == 5 || (n % 6 == 1 && n > 1)
+}
+
+/// Number of integers up to `n` that some thread tests
+pub fn candidates_up_to(n: u64) -> u64 {
+    // 2 and 3, then 6k - 1 and 6k + 1 for k from 1
+    u64::from(n >= 2) + u64::from(n >= 3) + n.saturating_add(1) / 6 + n.saturating_sub(1) / 6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(number: u64, thread_id: usize) -> PrimeResult {
+        PrimeResult {
+            number,
+            elapsed: 0,
+            thread_id,
+        }
+    }
+
+    #[test]
+    fn test_is_candidate() {
+        let candidates: Vec<u64> = (0..=25).filter(|&n| is_candidate(n)).collect();
+        assert_eq!(candidates, [2, 3, 5, 7, 11, 13, 17, 19, 23, 25]);
+        // A multiple of 3, and 6k + 1 just below it
+        assert!(!is_candidate(u64::MAX));
+        assert!(is_candidate(u64::MAX - 2));
+
+        // 2, 3, 5, 7, 11, 13, 17, 19, 23 and 25
+        let counts: Vec<u64> = [0, 1, 2, 3, 5, 7, 25].map(candidates_up_to).to_vec();
+        assert_eq!(counts, [0, 0, 1, 2, 3, 4, 10]);
+        assert_eq!(
+            candidates_up_to(10_000),
+            (1..=10_000).filter(|&n| is_candidate(n)).count() as u64
+        );
+    }
+
+    #[test]
+    fn test_from_results() {
+        // Thread 1 stopped at 23, thread 0 at 31; 33 is no candidate
+        let results = [
+            found(2, 0),
+            found(3, 0),
+            found(5, 0),
+            found(7, 0),
+            found(11, 1),
+            found(13, 1),
+            found(17, 0),
+            found(19, 0),
+            found(23, 1),
+            found(29, 0),
+            found(31, 0),
+            found(33, 1),
+        ];
+
+        let coverage = CandidateCoverage::from_results(&results, None, false);
+        assert_eq!(coverage.threads(), 2);
+        assert_eq!(coverage.reached, [31, 23]);
+        assert_eq!(coverage.misplaced, [33]);
+        assert_eq!(coverage.complete_up_to(), 23);
+        assert_eq!(coverage.reached_up_to(), 31);
+
+        // What the run recorded wins over the estimate
+        let coverage = CandidateCoverage::from_results(&results, Some(28), false);
+        assert_eq!(coverage.complete_up_to(), 28);
+        assert_eq!(coverage.candidates_up_to(25), 10);
+
+        // Every integer from 2 is a candidate of such a run
+        let coverage = CandidateCoverage::from_results(&results, Some(34), true);
+        assert!(coverage.misplaced.is_empty());
+        assert_eq!(coverage.candidates_up_to(25), 24);
+
+        let coverage = CandidateCoverage::from_results(&[], None, false);
+        assert_eq!(coverage.complete_up_to(), 0);
+        assert_eq!(coverage.reached_up_to(), 0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Prime density by decade of n, against the prime number theorem
+//! π(x) ≈ x / ln x predicts how many primes each decade should hold. The
+//! estimate runs low by a factor of about 1 + 1 / ln x, so counts slightly
+//! above it are expected; a decade far off it points at candidates that
+//! were skipped or tested twice.
+
+use crate::analysis::throughput::decade;
+
+/// Primes found in the part of a decade that was fully covered
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecadeDensity {
+    /// Covers [10^decade, 10^(decade + 1))
+    pub decade: u32,
+    /// Last number covered, short of the decade's end if the run stopped in it
+    pub end: u64,
+    pub primes: u64,
+    /// Primes expected from x / ln x
+    pub predicted: f64,
+}
+
+impl DecadeDensity {
+    /// Primes found per prime predicted
+    pub fn ratio(&self) -> f64 {
+        self.primes as f64 / self.predicted
+    }
+
+    /// True if the run stopped before the end of the decade
+    pub fn is_partial(&self) -> bool {
+        10u64
+            .checked_pow(self.decade + 1)
+            .is_none_or(|next| self.end < next - 1)
+    }
+}
+
+/// The prime number theorem's estimate x / ln x of π(x), 0 below 2
+pub fn prime_count_estimate(x: u64) -> f64 {
+    if x < 2 {
+        return 0.0;
+    }
+    let x = x as f64;
+    x / x.ln()
+}
+
+/// Counts `primes` (in increasing order, none missing up to `complete_up_to`)
+/// in each decade up to `complete_up_to`, next to the count predicted
+pub fn density_by_decade(primes: &[u64], complete_up_to: u64) -> Vec<DecadeDensity> {
+    if complete_up_to == 0 {
+        return Vec::new();
+    }
+
+    (0..=decade(complete_up_to))
+        .map(|d| {
+            let start = 10u64.pow(d);
+            let end = 10u64
+                .checked_pow(d + 1)
+                .map_or(complete_up_to, |next| (next - 1).min(complete_up_to));
+
+            let below = |n: u64| primes.partition_point(|&p| p < n);
+            let primes = primes.partition_point(|&p| p <= end) - below(start);
+
+            DecadeDensity {
+                decade: d,
+                end,
+                primes: primes as u64,
+                predicted: prime_count_estimate(end) - prime_count_estimate(start - 1),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_density_by_decade() {
+        let primes = sieve(5_000);
+        let density = density_by_decade(&primes, 5_000);
+
+        // π(10) = 4, π(100) = 25, π(1000) = 168, π(5000) = 669
+        let counts: Vec<u64> = density.iter().map(|d| d.primes).collect();
+        assert_eq!(counts, [4, 21, 143, 501]);
+        assert_eq!(density[3].end, 5_000);
+        assert!(density[3].is_partial() && !density[2].is_partial());
+
+        // x / ln x runs low, but not by much this far up
+        assert!(
+            density[2..]
+                .iter()
+                .all(|d| d.ratio() > 1.0 && d.ratio() < 1.2)
+        );
+        assert!((density[0].predicted - 9.0 / 9f64.ln()).abs() < 1e-9);
+
+        assert!(density_by_decade(&[], 0).is_empty());
+        assert_eq!(prime_count_estimate(1), 0.0);
+    }
+}
@@ -0,0 +1,220 @@
+//! Measured false-positive rates of randomized probable prime tests
+//!
+//! With k rounds of random witnesses, Miller-Rabin accepts a composite with
+//! probability at most 4^-k and Solovay-Strassen at most 2^-k, while Fermat
+//! has no bound at all: Carmichael numbers pass every base coprime to them.
+//! Those bounds are for the worst composite. [`measure`] runs the rounds on
+//! a sample of composites and counts how many get through, to set the
+//! observed rate beside the proven one.
+
+use std::fmt;
+
+use crate::algorithms::solovay_strassen::is_euler_probable_prime;
+use crate::core_algorithms::miller_rabin::{self, find_witness};
+use crate::math::mod_pow;
+use crate::random::RandomSource;
+
+/// A probable prime test run one random witness per round
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum ProbabilisticTest {
+    /// a^(n-1) ≡ 1 (mod n)
+    Fermat,
+    /// The strong probable prime test
+    MillerRabin,
+    /// a^((n-1)/2) ≡ (a/n) (mod n), the Euler-Jacobi test
+    SolovayStrassen,
+}
+
+impl ProbabilisticTest {
+    pub const ALL: [ProbabilisticTest; 3] = [
+        ProbabilisticTest::Fermat,
+        ProbabilisticTest::MillerRabin,
+        ProbabilisticTest::SolovayStrassen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProbabilisticTest::Fermat => "fermat",
+            ProbabilisticTest::MillerRabin => "miller-rabin",
+            ProbabilisticTest::SolovayStrassen => "solovay-strassen",
+        }
+    }
+
+    /// Runs one round on the odd `n > 3` with witness `a`, returning true if
+    /// `n` passes as a probable prime
+    pub fn passes(self, n: u64, a: u64) -> bool {
+        debug_assert!(n > 3 && n % 2 == 1);
+        match self {
+            ProbabilisticTest::Fermat => mod_pow(a, n - 1, n) == 1,
+            ProbabilisticTest::MillerRabin => find_witness(n, &[a]).is_none(),
+            ProbabilisticTest::SolovayStrassen => is_euler_probable_prime(n as u128, a),
+        }
+    }
+
+    /// Proven bound on the chance that `rounds` random witnesses all pass a
+    /// composite; None for Fermat, which has none
+    pub fn error_bound(self, rounds: u32) -> Option<f64> {
+        match self {
+            ProbabilisticTest::Fermat => None,
+            ProbabilisticTest::MillerRabin => Some(0.25f64.powi(rounds as i32)),
+            ProbabilisticTest::SolovayStrassen => Some(0.5f64.powi(rounds as i32)),
+        }
+    }
+}
+
+impl fmt::Display for ProbabilisticTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// How many of a sample of composites one test with some number of rounds
+/// accepted
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorRate {
+    pub test: ProbabilisticTest,
+    pub rounds: u32,
+    pub composites: u64,
+    pub false_positives: u64,
+    /// [`ProbabilisticTest::error_bound`] for these rounds
+    pub bound: Option<f64>,
+}
+
+impl ErrorRate {
+    pub fn rate(&self) -> f64 {
+        if self.composites == 0 {
+            return 0.0;
+        }
+        self.false_positives as f64 / self.composites as f64
+    }
+}
+
+/// `count` odd composites drawn from [start, end] (with repeats), or None if
+/// the range holds no odd composite
+pub fn sample_odd_composites(
+    start: u64,
+    end: u64,
+    count: u64,
+    random: &mut RandomSource,
+) -> Option<Vec<u64>> {
+    // 9 is the smallest odd composite, and any six numbers from there on
+    // include an odd multiple of 3, so this finds one if there is any
+    let start = start.max(9);
+    (start..=end)
+        .take(6)
+        .find(|&n| n % 2 == 1 && !miller_rabin::is_prime(n))?;
+
+    let mut composites = Vec::with_capacity(count as usize);
+    while (composites.len() as u64) < count {
+        let n = random.in_range(start..=end);
+        if n % 2 == 1 && !miller_rabin::is_prime(n) {
+            composites.push(n);
+        }
+    }
+    Some(composites)
+}
+
+/// Runs `test` with `rounds` witnesses drawn from [2, n - 2] on each of
+/// `composites` (odd, above 3) and counts those it accepts
+pub fn measure(
+    test: ProbabilisticTest,
+    rounds: u32,
+    composites: &[u64],
+    random: &mut RandomSource,
+) -> ErrorRate {
+    let false_positives = composites
+        .iter()
+        .filter(|&&n| (0..rounds).all(|_| test.passes(n, random.in_range(2..=n - 2))))
+        .count();
+
+    ErrorRate {
+        test,
+        rounds,
+        composites: composites.len() as u64,
+        false_positives: false_positives as u64,
+        bound: test.error_bound(rounds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds() {
+        // Every test passes primes for every base
+        for n in [5, 7, 11, 97, 997] {
+            for a in 2..n - 1 {
+                assert!(ProbabilisticTest::ALL.iter().all(|test| test.passes(n, a)));
+            }
+        }
+
+        // 561, a Carmichael number, is an Euler-Jacobi pseudoprime to base 2
+        // but not a strong one; 2047 is a strong pseudoprime to base 2
+        assert!(ProbabilisticTest::Fermat.passes(561, 2));
+        assert!(ProbabilisticTest::SolovayStrassen.passes(561, 2));
+        assert!(!ProbabilisticTest::MillerRabin.passes(561, 2));
+        assert!(ProbabilisticTest::MillerRabin.passes(2047, 2));
+
+        // Strong implies Euler-Jacobi implies Fermat
+        for n in (9..600).step_by(2).filter(|&n| !miller_rabin::is_prime(n)) {
+            for a in 2..n - 1 {
+                if ProbabilisticTest::MillerRabin.passes(n, a) {
+                    assert!(ProbabilisticTest::SolovayStrassen.passes(n, a), "{n}, {a}");
+                }
+                if ProbabilisticTest::SolovayStrassen.passes(n, a) {
+                    assert!(ProbabilisticTest::Fermat.passes(n, a), "{n}, {a}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut random = RandomSource::new(3);
+        let composites = sample_odd_composites(0, 10_000, 500, &mut random).unwrap();
+        assert_eq!(composites.len(), 500);
+        assert!(
+            composites
+                .iter()
+                .all(|&n| n >= 9 && n % 2 == 1 && !miller_rabin::is_prime(n))
+        );
+        assert_eq!(
+            sample_odd_composites(0, 10_000, 500, &mut RandomSource::new(3)).unwrap(),
+            composites
+        );
+
+        assert_eq!(
+            sample_odd_composites(9, 9, 2, &mut random),
+            Some(vec![9, 9])
+        );
+        assert_eq!(sample_odd_composites(0, 8, 1, &mut random), None);
+        assert_eq!(sample_odd_composites(11, 13, 1, &mut random), None);
+    }
+
+    #[test]
+    fn test_measure() {
+        let mut random = RandomSource::new(5);
+        let composites = sample_odd_composites(9, 1_000, 2000, &mut random).unwrap();
+        for test in ProbabilisticTest::ALL {
+            let one = measure(test, 1, &composites, &mut random);
+            let four = measure(test, 4, &composites, &mut random);
+            assert!(four.false_positives <= one.false_positives);
+            if let Some(bound) = one.bound {
+                assert!(one.rate() <= bound, "{:?}", one);
+            }
+        }
+
+        // Carmichael numbers pass Fermat for most bases
+        let carmichael = [561, 1105, 1729, 2465, 2821, 6601, 8911];
+        let fermat = measure(ProbabilisticTest::Fermat, 1, &carmichael, &mut random);
+        assert_eq!(fermat.bound, None);
+        assert!(fermat.false_positives > 0);
+    }
+}
@@ -0,0 +1,123 @@
+//! Prime gaps: differences between consecutive primes
+//! A gap is maximal if it is larger than every gap before it, the record
+//! gaps tabulated in OEIS A002386 and A005250. Only a sequence with no
+//! missing primes gives meaningful gaps; see
+//! [`PrimeSequence::complete`](crate::analysis::sequence::PrimeSequence::complete).
+
+/// A gap and the prime it starts after
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimeGap {
+    pub prime: u64,
+    pub gap: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GapStats {
+    /// (gap, occurrences) for every gap size seen, by increasing size
+    pub distribution: Vec<(u64, u64)>,
+    /// Record gaps, in the order they occur
+    pub maximal: Vec<PrimeGap>,
+}
+
+impl GapStats {
+    /// Number of gaps, one fewer than the primes
+    pub fn count(&self) -> u64 {
+        self.distribution.iter().map(|&(_, count)| count).sum()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| {
+            self.distribution
+                .iter()
+                .map(|&(gap, n)| gap * n)
+                .sum::<u64>() as f64
+                / count as f64
+        })
+    }
+
+    pub fn largest(&self) -> Option<PrimeGap> {
+        self.maximal.last().copied()
+    }
+
+    /// The `n` most frequent gap sizes, most frequent first (smaller
+    /// gaps first among equals)
+    pub fn most_common(&self, n: usize) -> Vec<(u64, u64)> {
+        let mut common = self.distribution.clone();
+        common.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        common.truncate(n);
+        common
+    }
+}
+
+/// Gap statistics of `primes`, which must be consecutive primes in
+/// increasing order
+pub fn gap_stats(primes: &[u64]) -> GapStats {
+    let mut counts = std::collections::BTreeMap::new();
+    let mut maximal: Vec<PrimeGap> = Vec::new();
+
+    for pair in primes.windows(2) {
+        let gap = pair[1] - pair[0];
+        *counts.entry(gap).or_insert(0) += 1;
+
+        if maximal.last().is_none_or(|record| gap > record.gap) {
+            maximal.push(PrimeGap {
+                prime: pair[0],
+                gap,
+            });
+        }
+    }
+
+    GapStats {
+        distribution: counts.into_iter().collect(),
+        maximal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_gap_stats() {
+        let stats = gap_stats(&sieve(1_000));
+
+        // 168 primes below 1000
+        assert_eq!(stats.count(), 167);
+        assert_eq!(stats.distribution[0], (1, 1));
+        assert_eq!(stats.distribution[1], (2, 35));
+
+        // Maximal gaps below 1000 (OEIS A002386 and A005250)
+        let maximal: Vec<(u64, u64)> = stats.maximal.iter().map(|g| (g.prime, g.gap)).collect();
+        assert_eq!(
+            maximal,
+            [
+                (2, 1),
+                (3, 2),
+                (7, 4),
+                (23, 6),
+                (89, 8),
+                (113, 14),
+                (523, 18),
+                (887, 20)
+            ]
+        );
+        assert_eq!(
+            stats.largest(),
+            Some(PrimeGap {
+                prime: 887,
+                gap: 20
+            })
+        );
+        assert!((stats.mean().unwrap() - (997.0 - 2.0) / 167.0).abs() < 1e-12);
+
+        // Gaps of 6 already outnumber twin primes below 1000
+        assert_eq!(stats.most_common(1)[0].0, 6);
+
+        assert_eq!(gap_stats(&[7]), GapStats::default());
+        assert_eq!(GapStats::default().mean(), None);
+    }
+}
@@ -0,0 +1,348 @@
+//! The same benchmark run on different machines, side by side
+//! Raw primes/sec favours the slower machine, which stopped at smaller and
+//! cheaper candidates, so runs are compared over the range of n both
+//! covered: the primes up to the smaller of their largest primes, and when
+//! each run had found the last of them. The rate is also given per thread,
+//! and per thread and GHz where the clock speed of both machines is known.
+
+use crate::benchmark::result::PrimeResult;
+use crate::benchmark::schema::{ALGORITHM_KEY, DURATION_KEY};
+
+/// Prefix of the environment keys in result metadata
+const ENVIRONMENT_PREFIX: &str = "env.";
+
+/// Where a machine's clock speed was read from, most telling first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ClockSource {
+    /// Mean of the frequencies sampled during the run
+    Monitored,
+    /// The CPU's highest frequency, reported by cpufreq
+    MaxFrequency,
+    /// The base frequency in the CPU model name, e.g. "@ 1.80GHz"
+    ModelName,
+}
+
+impl std::fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ClockSource::Monitored => "monitored",
+            ClockSource::MaxFrequency => "max frequency",
+            ClockSource::ModelName => "model name",
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    pub ghz: f64,
+    pub source: ClockSource,
+}
+
+impl Clock {
+    /// The clock speed recorded in result metadata, from the best source
+    /// available
+    pub fn from_metadata(metadata: &[(String, String)]) -> Option<Clock> {
+        let value = |key: &str| metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+
+        let monitored = value("monitor.samples").and_then(|samples| mean_frequency_khz(samples));
+        let max = value("env.max-frequency-khz").and_then(|khz| khz.parse::<f64>().ok());
+        let model = value("env.cpu-model").and_then(|model| model_name_ghz(model));
+
+        monitored
+            .map(|khz| (khz / 1e6, ClockSource::Monitored))
+            .or(max.map(|khz| (khz / 1e6, ClockSource::MaxFrequency)))
+            .or(model.map(|ghz| (ghz, ClockSource::ModelName)))
+            .filter(|&(ghz, _)| ghz > 0.0)
+            .map(|(ghz, source)| Clock { ghz, source })
+    }
+}
+
+/// One machine's run, as read back from its result file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachineRun {
+    /// Recorded algorithm, None for files written before it was
+    pub algorithm: Option<String>,
+    /// Recorded length of the run, else when its last prime was found
+    pub duration_micros: u64,
+    pub primes: usize,
+    pub largest_prime: Option<u64>,
+    /// Threads that found primes
+    pub threads: usize,
+    pub clock: Option<Clock>,
+    /// The `env.*` metadata, without the prefix
+    pub environment: Vec<(String, String)>,
+}
+
+impl MachineRun {
+    pub fn from_results(primes: &[PrimeResult], metadata: &[(String, String)]) -> MachineRun {
+        let value = |key: &str| metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+
+        MachineRun {
+            algorithm: value(ALGORITHM_KEY).cloned(),
+            duration_micros: value(DURATION_KEY)
+                .and_then(|micros| micros.parse().ok())
+                .unwrap_or_else(|| primes.iter().map(|p| p.elapsed).max().unwrap_or(0)),
+            primes: primes.len(),
+            largest_prime: primes.iter().map(|p| p.number).max(),
+            threads: primes.iter().map(|p| p.thread_id + 1).max().unwrap_or(0),
+            clock: Clock::from_metadata(metadata),
+            environment: metadata
+                .iter()
+                .filter_map(|(key, value)| {
+                    key.strip_prefix(ENVIRONMENT_PREFIX)
+                        .map(|key| (key.to_string(), value.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn primes_per_second(&self) -> f64 {
+        self.primes as f64 / (self.duration_micros.max(1) as f64 / 1e6)
+    }
+}
+
+/// How fast one run covered the range both runs covered
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeThroughput {
+    /// Primes up to the common bound
+    pub primes: usize,
+    /// When the last of them was found
+    pub elapsed_micros: u64,
+    pub primes_per_second: f64,
+    pub per_thread: f64,
+    /// Primes per second per thread and GHz, if the clock speed is known
+    pub per_thread_ghz: Option<f64>,
+}
+
+impl RangeThroughput {
+    fn new(primes: &[PrimeResult], bound: u64, run: &MachineRun) -> RangeThroughput {
+        let (count, elapsed_micros) = primes
+            .iter()
+            .filter(|p| p.number <= bound)
+            .fold((0, 0), |(count, last), p| (count + 1, last.max(p.elapsed)));
+        let primes_per_second = count as f64 / (elapsed_micros.max(1) as f64 / 1e6);
+        let per_thread = primes_per_second / run.threads.max(1) as f64;
+
+        RangeThroughput {
+            primes: count,
+            elapsed_micros,
+            primes_per_second,
+            per_thread,
+            per_thread_ghz: run.clock.map(|clock| per_thread / clock.ghz),
+        }
+    }
+}
+
+/// An environment field the two machines differ on, None where one did not
+/// record it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentDifference {
+    pub key: String,
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+/// Two runs compared over the range of n both covered
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HardwareComparison {
+    pub first: MachineRun,
+    pub second: MachineRun,
+    /// Largest n both runs reached
+    pub bound: u64,
+    pub first_range: RangeThroughput,
+    pub second_range: RangeThroughput,
+    pub differences: Vec<EnvironmentDifference>,
+}
+
+impl HardwareComparison {
+    /// Compares two runs, None unless both found primes
+    pub fn new(
+        first: (&[PrimeResult], &[(String, String)]),
+        second: (&[PrimeResult], &[(String, String)]),
+    ) -> Option<HardwareComparison> {
+        let first_run = MachineRun::from_results(first.0, first.1);
+        let second_run = MachineRun::from_results(second.0, second.1);
+        let bound = first_run.largest_prime?.min(second_run.largest_prime?);
+
+        Some(HardwareComparison {
+            bound,
+            first_range: RangeThroughput::new(first.0, bound, &first_run),
+            second_range: RangeThroughput::new(second.0, bound, &second_run),
+            differences: differences(&first_run.environment, &second_run.environment),
+            first: first_run,
+            second: second_run,
+        })
+    }
+
+    /// How many times faster the second machine covered the common range
+    pub fn speedup(&self) -> f64 {
+        self.second_range.primes_per_second / self.first_range.primes_per_second
+    }
+
+    /// The speedup with each machine's rate divided by its threads
+    pub fn speedup_per_thread(&self) -> f64 {
+        self.second_range.per_thread / self.first_range.per_thread
+    }
+
+    /// The speedup per thread and GHz, the difference the clock speed and
+    /// thread count do not explain, if both clock speeds are known
+    pub fn speedup_per_thread_ghz(&self) -> Option<f64> {
+        Some(self.second_range.per_thread_ghz? / self.first_range.per_thread_ghz?)
+    }
+
+    /// True unless the runs recorded different algorithms
+    pub fn same_algorithm(&self) -> bool {
+        match (&self.first.algorithm, &self.second.algorithm) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+}
+
+// Fields recorded differently, in the order they first appear
+fn differences(
+    first: &[(String, String)],
+    second: &[(String, String)],
+) -> Vec<EnvironmentDifference> {
+    let lookup = |pairs: &[(String, String)], key: &str| {
+        pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    };
+
+    let mut keys: Vec<&str> = Vec::new();
+    for (key, _) in first.iter().chain(second) {
+        if !keys.contains(&key.as_str()) {
+            keys.push(key);
+        }
+    }
+
+    keys.into_iter()
+        .map(|key| EnvironmentDifference {
+            key: key.to_string(),
+            first: lookup(first, key),
+            second: lookup(second, key),
+        })
+        .filter(|difference| difference.first != difference.second)
+        .collect()
+}
+
+// Mean of the frequencies in elapsed:frequency:temperature samples
+fn mean_frequency_khz(samples: &str) -> Option<f64> {
+    let frequencies: Vec<f64> = samples
+        .split(',')
+        .filter_map(|sample| sample.split(':').nth(1)?.parse().ok())
+        .collect();
+    (!frequencies.is_empty()).then(|| frequencies.iter().sum::<f64>() / frequencies.len() as f64)
+}
+
+// "Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz" gives 1.8
+fn model_name_ghz(model: &str) -> Option<f64> {
+    let (_, frequency) = model.rsplit_once('@')?;
+    frequency.trim().strip_suffix("GHz")?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_clock() {
+        let model = pairs(&[("env.cpu-model", "Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz")]);
+        assert_eq!(
+            Clock::from_metadata(&model),
+            Some(Clock {
+                ghz: 1.8,
+                source: ClockSource::ModelName
+            })
+        );
+
+        let mut max = model.clone();
+        max.extend(pairs(&[("env.max-frequency-khz", "4000000")]));
+        assert_eq!(
+            Clock::from_metadata(&max).map(|clock| clock.source),
+            Some(ClockSource::MaxFrequency)
+        );
+
+        max.extend(pairs(&[(
+            "monitor.samples",
+            "500:3000000:,1000:2000000:90000",
+        )]));
+        assert_eq!(
+            Clock::from_metadata(&max),
+            Some(Clock {
+                ghz: 2.5,
+                source: ClockSource::Monitored
+            })
+        );
+
+        assert_eq!(
+            Clock::from_metadata(&pairs(&[("env.cpu-model", "ARM")])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_comparison() {
+        let prime = |number, elapsed, thread_id| PrimeResult {
+            number,
+            elapsed,
+            thread_id,
+        };
+        // The second machine is twice as fast with twice the threads
+        let slow = [prime(2, 100, 0), prime(3, 200, 0), prime(5, 400, 0)];
+        let fast = [
+            prime(2, 50, 0),
+            prime(3, 50, 1),
+            prime(5, 100, 1),
+            prime(7, 150, 0),
+        ];
+        let slow_metadata = pairs(&[
+            (ALGORITHM_KEY, "bpsw"),
+            ("env.cores", "4"),
+            ("env.max-frequency-khz", "2000000"),
+            ("env.os", "linux x86_64"),
+        ]);
+        let fast_metadata = pairs(&[
+            (ALGORITHM_KEY, "bpsw"),
+            (DURATION_KEY, "1000"),
+            ("env.cores", "8"),
+            ("env.os", "linux x86_64"),
+            ("env.max-frequency-khz", "4000000"),
+        ]);
+
+        let comparison =
+            HardwareComparison::new((&slow, &slow_metadata), (&fast, &fast_metadata)).unwrap();
+        assert_eq!(comparison.bound, 5);
+        assert_eq!(comparison.first.duration_micros, 400);
+        assert_eq!(comparison.second.duration_micros, 1000);
+        assert_eq!(comparison.second.threads, 2);
+        assert_eq!(comparison.first_range.primes, 3);
+        assert_eq!(comparison.second_range.elapsed_micros, 100);
+        assert!((comparison.speedup() - 4.0).abs() < 1e-9);
+        assert!((comparison.speedup_per_thread() - 2.0).abs() < 1e-9);
+        assert!((comparison.speedup_per_thread_ghz().unwrap() - 1.0).abs() < 1e-9);
+        assert!(comparison.same_algorithm());
+
+        let keys: Vec<&str> = comparison
+            .differences
+            .iter()
+            .map(|d| d.key.as_str())
+            .collect();
+        assert_eq!(keys, ["cores", "max-frequency-khz"]);
+
+        assert!(HardwareComparison::new((&slow, &slow_metadata), (&[], &fast_metadata)).is_none());
+    }
+}
@@ -0,0 +1,15 @@
+//! Post-processing of measurements collected by benchmarks and sweeps
+
+pub mod complexity;
+pub mod coverage;
+pub mod density;
+pub mod error_rate;
+pub mod gaps;
+pub mod hardware;
+pub mod pseudoprimes;
+pub mod residues;
+pub mod sequence;
+pub mod significance;
+pub mod throughput;
+pub mod timeseries;
+pub mod witnesses;
@@ -0,0 +1,109 @@
+//! Strong pseudoprimes to a set of bases, found against the sieve
+//!
+//! An odd composite n is a strong pseudoprime to base a when a single
+//! Miller-Rabin round with witness a calls it prime. Counts of them up to a
+//! bound, such as the 46 to base 2 below 10^6, are the data witness sets are
+//! chosen from: a set is safe below the smallest number that is a strong
+//! pseudoprime to every base in it. Whether a number is composite comes from
+//! the sieve (through the installed [`cache`](crate::cache)), never from the
+//! test being measured.
+
+use crate::cache::prime_bitmap;
+use crate::core_algorithms::miller_rabin::find_witness;
+
+/// The strong pseudoprimes to every one of `bases` up to `limit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PseudoprimeCensus {
+    pub bases: Vec<u64>,
+    pub limit: u64,
+    /// Odd composites checked, excluding divisors of a base
+    pub odd_composites: u64,
+    /// In increasing order
+    pub pseudoprimes: Vec<u64>,
+}
+
+impl PseudoprimeCensus {
+    /// Fraction of the odd composites that passed every base
+    pub fn rate(&self) -> f64 {
+        if self.odd_composites == 0 {
+            return 0.0;
+        }
+        self.pseudoprimes.len() as f64 / self.odd_composites as f64
+    }
+}
+
+/// Sieves up to `limit` and runs a Miller-Rabin round to each of `bases` on
+/// every odd composite. A number dividing one of the bases is skipped: the
+/// base tells nothing about it.
+///
+/// # Panics
+///
+/// If `bases` is empty.
+pub fn strong_pseudoprime_census(bases: &[u64], limit: u64) -> PseudoprimeCensus {
+    assert!(!bases.is_empty(), "a census needs at least one base");
+
+    let bits = prime_bitmap(limit);
+    let is_prime = |n: u64| bits[(n / 64) as usize] >> (n % 64) & 1 == 1;
+
+    let mut census = PseudoprimeCensus {
+        bases: bases.to_vec(),
+        limit,
+        odd_composites: 0,
+        pseudoprimes: Vec::new(),
+    };
+    // 9 is the smallest odd composite
+    for n in (9..=limit).step_by(2) {
+        if is_prime(n) || bases.iter().any(|base| base.is_multiple_of(n)) {
+            continue;
+        }
+
+        census.odd_composites += 1;
+        if find_witness(n, bases).is_none() {
+            census.pseudoprimes.push(n);
+        }
+    }
+
+    census
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_two() {
+        let census = strong_pseudoprime_census(&[2], 10_000);
+        assert_eq!(census.pseudoprimes, [2047, 3277, 4033, 4681, 8321]);
+        // The 5000 odd numbers up to 10^4, less 1 and the 1228 odd primes
+        assert_eq!(census.odd_composites, 5000 - 1 - 1228);
+        assert!(census.rate() > 0.0 && census.rate() < 0.01);
+
+        // OEIS A001262
+        assert_eq!(
+            strong_pseudoprime_census(&[2], 1_000_000)
+                .pseudoprimes
+                .len(),
+            46
+        );
+    }
+
+    #[test]
+    fn test_several_bases() {
+        assert_eq!(strong_pseudoprime_census(&[3], 200).pseudoprimes, [121]);
+        // The smallest strong pseudoprime to both 2 and 3 (OEIS A014233)
+        assert_eq!(
+            strong_pseudoprime_census(&[2, 3], 1_373_653).pseudoprimes,
+            [1_373_653]
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let census = strong_pseudoprime_census(&[2], 8);
+        assert_eq!((census.odd_composites, census.rate()), (0, 0.0));
+        // 9 divides the base 9 and 15 divides 15: neither is counted
+        let census = strong_pseudoprime_census(&[9, 15], 15);
+        assert_eq!(census.odd_composites, 0);
+    }
+}
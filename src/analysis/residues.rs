@@ -0,0 +1,111 @@
+//! Distribution of primes over residue classes
+//! By Dirichlet's theorem, primes spread evenly over the φ(m) residues mod m
+//! coprime to m; the other classes hold at most the prime dividing m. A
+//! class well off its share means the harness generated candidates unevenly,
+//! e.g. skipping a thread's share of them.
+
+use crate::math::{euler_phi, gcd};
+
+/// Classes further than this many standard errors from their share count as
+/// skewed. Chebyshev's bias and chance stay well within it.
+pub const SKEW_Z_SCORE: f64 = 5.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResidueClass {
+    pub residue: u64,
+    pub primes: u64,
+    /// Only classes coprime to the modulus hold infinitely many primes
+    pub coprime: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResidueDistribution {
+    pub modulus: u64,
+    /// Every class from 0 to modulus - 1
+    pub classes: Vec<ResidueClass>,
+}
+
+impl ResidueDistribution {
+    /// Primes in the classes coprime to the modulus
+    pub fn coprime_primes(&self) -> u64 {
+        self.classes
+            .iter()
+            .filter(|c| c.coprime)
+            .map(|c| c.primes)
+            .sum()
+    }
+
+    /// Share of the coprime primes each coprime class should hold, 1 / φ(m)
+    pub fn expected_share(&self) -> f64 {
+        1.0 / euler_phi(self.modulus) as f64
+    }
+
+    /// Standard errors between a coprime class's count and its share of the
+    /// coprime primes; None for other classes or without primes
+    pub fn z_score(&self, class: &ResidueClass) -> Option<f64> {
+        let n = self.coprime_primes() as f64;
+        let p = self.expected_share();
+        let deviation = (n * p * (1.0 - p)).sqrt();
+        (class.coprime && n > 0.0 && deviation > 0.0)
+            .then(|| (class.primes as f64 - n * p) / deviation)
+    }
+
+    /// True if any coprime class is more than [`SKEW_Z_SCORE`] standard
+    /// errors off its share
+    pub fn is_skewed(&self) -> bool {
+        self.classes
+            .iter()
+            .filter_map(|class| self.z_score(class))
+            .any(|z| z.abs() > SKEW_Z_SCORE)
+    }
+}
+
+/// Counts `primes` in each residue class mod `modulus` (at least 2)
+pub fn residue_distribution(primes: &[u64], modulus: u64) -> ResidueDistribution {
+    assert!(modulus >= 2, "modulus must be at least 2");
+
+    let mut classes: Vec<ResidueClass> = (0..modulus)
+        .map(|residue| ResidueClass {
+            residue,
+            primes: 0,
+            coprime: gcd(residue, modulus) == 1,
+        })
+        .collect();
+    for &p in primes {
+        classes[(p % modulus) as usize].primes += 1;
+    }
+
+    ResidueDistribution { modulus, classes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_last_digit() {
+        let primes = sieve(100_000);
+        let digits = residue_distribution(&primes, 10);
+
+        let counts: Vec<u64> = digits.classes.iter().map(|c| c.primes).collect();
+        // 9592 primes below 10^5: 2 and 5, the rest ending in 1, 3, 7, 9
+        assert_eq!(counts, [0, 2387, 1, 2402, 0, 1, 0, 2411, 0, 2390]);
+        assert_eq!(digits.coprime_primes(), 9590);
+        assert_eq!(digits.expected_share(), 0.25);
+        assert!(!digits.is_skewed());
+        assert_eq!(digits.z_score(&digits.classes[2]), None);
+    }
+
+    #[test]
+    fn test_skewed() {
+        // Primes ≡ 1 mod 4 only, as if half the candidates were skipped
+        let primes: Vec<u64> = sieve(100_000).into_iter().filter(|p| p % 4 != 3).collect();
+
+        let distribution = residue_distribution(&primes, 4);
+        assert!(distribution.is_skewed());
+        assert!(distribution.z_score(&distribution.classes[3]).unwrap() < -SKEW_Z_SCORE);
+    }
+}
@@ -0,0 +1,111 @@
+//! The prime sequence reconstructed from benchmark results
+//! Threads record primes in the order they find them, each working through
+//! the chunks of candidates it leased. Sorted, the primes are the start of
+//! the prime sequence, but only up to the point every thread had reached when
+//! the run stopped: past it, some chunk's candidates were never tested.
+
+use crate::benchmark::result::PrimeResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimeSequence {
+    /// Every prime found, in increasing order
+    pub primes: Vec<u64>,
+    /// Every candidate up to here was tested, so no prime up to here is
+    /// missing (0 if nothing is known to be complete)
+    pub complete_up_to: u64,
+    /// Primes recorded more than once, in increasing order
+    pub duplicates: Vec<u64>,
+}
+
+impl PrimeSequence {
+    /// Sorts the primes found and works out how far they are complete: each
+    /// thread tested all of its candidates below the last prime it found, and
+    /// every chunk below those was leased before, so the sequence is complete
+    /// up to the smallest of those last primes. Runs record a closer bound,
+    /// which callers may put in `complete_up_to` instead.
+    /// Threads are numbered from 0; one without any prime bounds it at 0.
+    pub fn from_results(results: &[PrimeResult]) -> PrimeSequence {
+        let mut primes: Vec<u64> = results.iter().map(|p| p.number).collect();
+        primes.sort_unstable();
+        let duplicates = primes
+            .chunk_by(|a, b| a == b)
+            .filter(|run| run.len() > 1)
+            .map(|run| run[0])
+            .collect();
+        primes.dedup();
+
+        let threads = results.iter().map(|p| p.thread_id + 1).max().unwrap_or(0);
+        let mut last_found = vec![0; threads];
+        for prime in results {
+            let last = &mut last_found[prime.thread_id];
+            *last = (*last).max(prime.number);
+        }
+
+        PrimeSequence {
+            primes,
+            complete_up_to: last_found.into_iter().min().unwrap_or(0),
+            duplicates,
+        }
+    }
+
+    /// The primes up to [`complete_up_to`](Self::complete_up_to), with none missing
+    pub fn complete(&self) -> &[u64] {
+        let end = self.primes.partition_point(|&p| p <= self.complete_up_to);
+        &self.primes[..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(number: u64, thread_id: usize) -> PrimeResult {
+        PrimeResult {
+            number,
+            elapsed: 0,
+            thread_id,
+        }
+    }
+
+    #[test]
+    fn test_from_results() {
+        // Thread 1 got to 29, thread 0 only to 19: 23 may be missing
+        let results = [
+            found(2, 0),
+            found(3, 0),
+            found(5, 0),
+            found(7, 0),
+            found(11, 1),
+            found(13, 1),
+            found(17, 0),
+            found(19, 0),
+            found(29, 1),
+        ];
+
+        let sequence = PrimeSequence::from_results(&results);
+        assert_eq!(sequence.primes, [2, 3, 5, 7, 11, 13, 17, 19, 29]);
+        assert_eq!(sequence.complete_up_to, 19);
+        assert_eq!(sequence.complete(), [2, 3, 5, 7, 11, 13, 17, 19]);
+        assert!(sequence.duplicates.is_empty());
+
+        // Both threads recorded 3, and thread 0 recorded 5 twice
+        let sequence = PrimeSequence::from_results(&[
+            found(2, 0),
+            found(3, 0),
+            found(3, 1),
+            found(5, 0),
+            found(5, 0),
+            found(7, 1),
+        ]);
+        assert_eq!(sequence.primes, [2, 3, 5, 7]);
+        assert_eq!(sequence.duplicates, [3, 5]);
+
+        // A thread without primes leaves nothing known to be complete
+        let sequence = PrimeSequence::from_results(&[found(2, 0), found(5, 2)]);
+        assert_eq!(sequence.complete_up_to, 0);
+        assert!(sequence.complete().is_empty());
+
+        assert!(PrimeSequence::from_results(&[]).primes.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+//! Statistical significance of latency differences
+//! Two-sided tests of whether two samples of per-candidate latencies come
+//! from the same distribution: the rank-based Mann-Whitney U test, robust to
+//! the long tails timing data tends to have, and Welch's t-test on the means.
+
+/// Outcome of a two-sided significance test
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestOutcome {
+    /// U for Mann-Whitney, t for Welch
+    pub statistic: f64,
+    /// Probability of a difference at least this large if the samples
+    /// came from the same distribution
+    pub p_value: f64,
+}
+
+impl TestOutcome {
+    /// True if the difference is significant at level `alpha` (e.g. 0.05)
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// Mann-Whitney U test with the normal approximation and a tie correction.
+/// Returns None if either sample is empty.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> Option<TestOutcome> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let n = n1 + n2;
+
+    // Rank the pooled samples, ties sharing their average rank
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let j = i + pooled[i..]
+            .iter()
+            .take_while(|p| p.0 == pooled[i].0)
+            .count();
+        let average_rank = (i + j + 1) as f64 / 2.0;
+        let in_a = pooled[i..j].iter().filter(|p| p.1).count() as f64;
+        rank_sum_a += average_rank * in_a;
+
+        let ties = (j - i) as f64;
+        tie_term += ties.powi(3) - ties;
+        i = j;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+
+    let p_value = if variance > 0.0 {
+        let z = (u - mean).abs() / variance.sqrt();
+        erfc(z / std::f64::consts::SQRT_2)
+    } else {
+        1.0
+    };
+
+    Some(TestOutcome {
+        statistic: u,
+        p_value: p_value.min(1.0),
+    })
+}
+
+/// Welch's t-test for a difference in means, not assuming equal variances.
+/// Returns None unless both samples hold at least two values.
+pub fn welch_t(a: &[f64], b: &[f64]) -> Option<TestOutcome> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (mean_a, var_a) = mean_variance(a);
+    let (mean_b, var_b) = mean_variance(b);
+    let (se_a, se_b) = (var_a / a.len() as f64, var_b / b.len() as f64);
+    let se = se_a + se_b;
+
+    if se == 0.0 {
+        let p_value = if mean_a == mean_b { 1.0 } else { 0.0 };
+        return Some(TestOutcome {
+            statistic: 0.0,
+            p_value,
+        });
+    }
+
+    let t = (mean_a - mean_b) / se.sqrt();
+    // Welch-Satterthwaite degrees of freedom
+    let df = se.powi(2)
+        / (se_a.powi(2) / (a.len() as f64 - 1.0) + se_b.powi(2) / (b.len() as f64 - 1.0));
+
+    // Two-sided tail of Student's t distribution
+    let p_value = regularized_beta(df / 2.0, 0.5, df / (df + t * t));
+
+    Some(TestOutcome {
+        statistic: t,
+        p_value,
+    })
+}
+
+// Mean and unbiased sample variance
+fn mean_variance(xs: &[f64]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    let variance = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+// Complementary error function, fractional error below 1.2e-7
+// (Chebyshev fit from Numerical Recipes)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+
+// ln Γ(x) for x > 0 (Lanczos approximation, g = 7)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// Regularized incomplete beta function I_x(a, b)
+fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+
+    // The continued fraction converges fastest on this side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Lentz's method for the incomplete beta continued fraction
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-14;
+
+    let clamp = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+
+    for m in 1..=300 {
+        let m = m as f64;
+
+        // Even step
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + numerator * d);
+        c = clamp(1.0 + numerator / c);
+        h *= d * c;
+
+        // Odd step
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + numerator * d);
+        c = clamp(1.0 + numerator / c);
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) -> bool {
+        (actual - expected).abs() < tolerance
+    }
+
+    #[test]
+    fn test_special_functions() {
+        assert!(close(erfc(0.0), 1.0, 1e-7));
+        assert!(close(erfc(1.0), 0.157_299_207, 1e-7));
+        assert!(close(erfc(-1.0), 1.842_700_793, 1e-7));
+        assert!(close(ln_gamma(5.0), 24f64.ln(), 1e-10));
+        assert!(close(
+            ln_gamma(0.5),
+            std::f64::consts::PI.sqrt().ln(),
+            1e-10
+        ));
+        assert!(close(regularized_beta(2.0, 3.0, 0.4), 0.5248, 1e-10));
+    }
+
+    #[test]
+    fn test_mann_whitney() {
+        let low: Vec<f64> = (1..=10).map(f64::from).collect();
+        let high: Vec<f64> = (11..=20).map(f64::from).collect();
+
+        let outcome = mann_whitney(&low, &high).unwrap();
+        assert_eq!(outcome.statistic, 0.0);
+        assert!(outcome.is_significant(0.001));
+
+        // Interleaved samples: U = 45, exactly n1 · n2 / 2 - 5
+        let odd: Vec<f64> = (0..10).map(|i| (2 * i + 1) as f64).collect();
+        let even: Vec<f64> = (0..10).map(|i| (2 * i + 2) as f64).collect();
+        let outcome = mann_whitney(&odd, &even).unwrap();
+        assert_eq!(outcome.statistic, 45.0);
+        assert!(!outcome.is_significant(0.05));
+
+        // All ties carry no information
+        assert_eq!(mann_whitney(&[1.0; 5], &[1.0; 5]).unwrap().p_value, 1.0);
+        assert!(mann_whitney(&[], &[1.0]).is_none());
+    }
+
+    #[test]
+    fn test_welch_t() {
+        // Means 3 and 4, variances 2.5: t = -1 with 8 degrees of freedom
+        let outcome = welch_t(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        assert!(close(outcome.statistic, -1.0, 1e-12));
+        assert!(close(outcome.p_value, 0.346_593, 1e-6));
+
+        assert_eq!(welch_t(&[2.0, 2.0], &[2.0, 2.0]).unwrap().p_value, 1.0);
+        assert_eq!(welch_t(&[1.0, 1.0], &[2.0, 2.0]).unwrap().p_value, 0.0);
+        assert!(welch_t(&[1.0], &[1.0, 2.0]).is_none());
+    }
+}
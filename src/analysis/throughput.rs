@@ -0,0 +1,202 @@
+//! Magnitude-normalized throughput metrics
+//! Raw primes/sec mostly measures how small the candidates were, so these
+//! metrics break a benchmark run down by decade of n and by bit length.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecadeThroughput {
+    pub decade: u32, // Primes in [10^decade, 10^(decade + 1))
+    pub primes: u64,
+    pub primes_per_second: Option<f64>, // None if the decade was crossed instantly
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CandidateTiming {
+    pub candidates: u64,
+    pub total_nanos: u64,
+}
+
+impl CandidateTiming {
+    pub fn average_nanos(&self) -> f64 {
+        self.total_nanos as f64 / self.candidates as f64
+    }
+}
+
+/// Per-candidate timings bucketed by bit length (0..=64)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitLengthTimings {
+    #[cfg_attr(feature = "serde", serde(with = "buckets"))]
+    buckets: [CandidateTiming; 65],
+}
+
+// serde only derives arrays of up to 32 elements, so the buckets go through a Vec
+#[cfg(feature = "serde")]
+mod buckets {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::CandidateTiming;
+
+    pub fn serialize<S: Serializer>(
+        buckets: &[CandidateTiming; 65],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        buckets.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[CandidateTiming; 65], D::Error> {
+        let buckets = Vec::<CandidateTiming>::deserialize(deserializer)?;
+        let len = buckets.len();
+        buckets
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"65 bit-length buckets"))
+    }
+}
+
+impl Default for BitLengthTimings {
+    fn default() -> Self {
+        BitLengthTimings {
+            buckets: [CandidateTiming::default(); 65],
+        }
+    }
+}
+
+impl BitLengthTimings {
+    #[inline]
+    pub fn record(&mut self, n: u64, nanos: u64) {
+        let bucket = &mut self.buckets[bit_length(n) as usize];
+        bucket.candidates += 1;
+        bucket.total_nanos += nanos;
+    }
+
+    pub fn merge(&mut self, other: &BitLengthTimings) {
+        for (mine, theirs) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            mine.candidates += theirs.candidates;
+            mine.total_nanos += theirs.total_nanos;
+        }
+    }
+
+    /// Takes `nanos` per candidate off every bucket's total, stopping at
+    /// zero; used to remove the harness's own share of each timing
+    pub fn subtract_per_candidate(&mut self, nanos: f64) {
+        for bucket in self.buckets.iter_mut() {
+            let overhead = (bucket.candidates as f64 * nanos).round() as u64;
+            bucket.total_nanos = bucket.total_nanos.saturating_sub(overhead);
+        }
+    }
+
+    /// Non-empty buckets as (bit length, timing), smallest first
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &CandidateTiming)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.candidates > 0)
+            .map(|(bits, t)| (bits as u32, t))
+    }
+}
+
+pub fn bit_length(n: u64) -> u32 {
+    u64::BITS - n.leading_zeros()
+}
+
+pub fn decade(n: u64) -> u32 {
+    n.checked_ilog10().unwrap_or(0)
+}
+
+/// Groups primes found as (number, elapsed microseconds) by decade of n and
+/// computes the rate at which each decade was populated
+pub fn decade_throughput(primes: impl IntoIterator<Item = (u64, u64)>) -> Vec<DecadeThroughput> {
+    // (count, first elapsed, last elapsed) per decade
+    let mut spans: Vec<Option<(u64, u64, u64)>> = vec![None; 20];
+
+    for (number, elapsed) in primes {
+        let span = &mut spans[decade(number) as usize];
+        *span = match *span {
+            None => Some((1, elapsed, elapsed)),
+            Some((count, first, last)) => Some((count + 1, first.min(elapsed), last.max(elapsed))),
+        };
+    }
+
+    spans
+        .into_iter()
+        .enumerate()
+        .filter_map(|(decade, span)| {
+            let (count, first, last) = span?;
+            let seconds = (last - first) as f64 / 1_000_000.0;
+            Some(DecadeThroughput {
+                decade: decade as u32,
+                primes: count,
+                primes_per_second: (seconds > 0.0).then(|| count as f64 / seconds),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_length_and_decade() {
+        assert_eq!(bit_length(0), 0);
+        assert_eq!(bit_length(1), 1);
+        assert_eq!(bit_length(255), 8);
+        assert_eq!(bit_length(256), 9);
+        assert_eq!(bit_length(u64::MAX), 64);
+
+        assert_eq!(decade(0), 0);
+        assert_eq!(decade(7), 0);
+        assert_eq!(decade(10), 1);
+        assert_eq!(decade(99), 1);
+        assert_eq!(decade(u64::MAX), 19);
+    }
+
+    #[test]
+    fn test_bit_length_timings() {
+        let mut a = BitLengthTimings::default();
+        a.record(5, 10);
+        a.record(6, 30);
+        let mut b = BitLengthTimings::default();
+        b.record(7, 20);
+        b.record(1000, 100);
+        a.merge(&b);
+
+        let buckets: Vec<(u32, CandidateTiming)> = a.iter().map(|(bits, t)| (bits, *t)).collect();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].0, 3);
+        assert_eq!(buckets[0].1.candidates, 3);
+        assert_eq!(buckets[0].1.average_nanos(), 20.0);
+        assert_eq!(buckets[1].0, 10);
+
+        // 3 candidates of 20ns and one of 100ns, less 25ns each
+        a.subtract_per_candidate(25.0);
+        let totals: Vec<u64> = a.iter().map(|(_, t)| t.total_nanos).collect();
+        assert_eq!(totals, [0, 75]);
+    }
+
+    #[test]
+    fn test_decade_throughput() {
+        let primes = [
+            (2, 0),
+            (3, 0),
+            (5, 0),
+            (7, 0),
+            (11, 0),
+            (13, 500_000),
+            (17, 1_000_000),
+        ];
+        let decades = decade_throughput(primes);
+
+        assert_eq!(decades.len(), 2);
+        assert_eq!(decades[0].decade, 0);
+        assert_eq!(decades[0].primes, 4);
+        assert_eq!(decades[0].primes_per_second, None);
+        assert_eq!(decades[1].decade, 1);
+        assert_eq!(decades[1].primes, 3);
+        assert_eq!(decades[1].primes_per_second, Some(3.0));
+    }
+}
@@ -0,0 +1,182 @@
+//! Runs downsampled to buckets of time, for plotting and comparing
+//! A 12-hour run holds millions of primes, far more points than a chart can
+//! draw. Bucketing keeps one point per slice of the run: a fixed number of
+//! equal slices whatever the run's length, or buckets of a fixed width, such
+//! as a second or a minute, that line up across runs of different lengths.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::benchmark::result::PrimeResult;
+
+/// How finely a run is bucketed over time
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// This many equal slices of the run, which ends with its last prime
+    Slices(usize),
+    /// Buckets of this width from the start of the run
+    Every(Duration),
+}
+
+impl Resolution {
+    // Bucket width in microseconds for a run spanning `span`, at least the
+    // resolution of `elapsed`
+    fn width_micros(&self, span: u64) -> u64 {
+        match *self {
+            Resolution::Slices(slices) => span.div_ceil(slices.max(1) as u64),
+            Resolution::Every(width) => width.as_micros().try_into().unwrap_or(u64::MAX),
+        }
+        .max(1)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Slices(slices) => write!(f, "{} slices", slices),
+            Resolution::Every(width) => write!(f, "every {}", humantime::format_duration(*width)),
+        }
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    /// A number of slices, e.g. `200`, or a bucket width, e.g. `1s` or `1m`
+    fn from_str(s: &str) -> Result<Resolution, String> {
+        let s = s.trim();
+        if let Ok(slices) = s.parse::<usize>() {
+            return match slices {
+                0 => Err("the number of slices must be at least 1".to_string()),
+                _ => Ok(Resolution::Slices(slices)),
+            };
+        }
+
+        match humantime::parse_duration(s) {
+            Ok(width) if width.as_micros() > 0 => Ok(Resolution::Every(width)),
+            Ok(_) => Err("buckets must be at least a microsecond wide".to_string()),
+            Err(_) => Err(format!(
+                "'{}' is neither a number of slices (e.g. 200) nor a bucket width (e.g. 1s, 1m)",
+                s
+            )),
+        }
+    }
+}
+
+/// The primes found in one bucket of time
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeBucket {
+    /// Seconds since the start of the run
+    pub start: f64,
+    /// Width of the bucket in seconds
+    pub seconds: f64,
+    pub primes: u64,
+    /// Primes found up to the end of the bucket
+    pub cumulative: u64,
+    /// Largest prime found in the bucket
+    pub largest_prime: Option<u64>,
+}
+
+impl TimeBucket {
+    pub fn midpoint(&self) -> f64 {
+        self.start + self.seconds / 2.0
+    }
+
+    pub fn primes_per_second(&self) -> f64 {
+        self.primes as f64 / self.seconds
+    }
+}
+
+/// The primes of a run bucketed by when they were found, from the start of
+/// the run to the bucket of its last prime, empty buckets included
+pub fn bucket(primes: &[PrimeResult], resolution: Resolution) -> Vec<TimeBucket> {
+    let Some(span) = primes.iter().map(|p| p.elapsed).max() else {
+        return Vec::new();
+    };
+
+    let width = resolution.width_micros(span);
+    let mut buckets = vec![(0u64, None::<u64>); (span / width + 1) as usize];
+    for prime in primes {
+        let (count, largest) = &mut buckets[(prime.elapsed / width) as usize];
+        *count += 1;
+        *largest = (*largest).max(Some(prime.number));
+    }
+
+    let seconds = width as f64 / 1e6;
+    let mut cumulative = 0;
+    buckets
+        .into_iter()
+        .enumerate()
+        .map(|(i, (primes, largest_prime))| {
+            cumulative += primes;
+            TimeBucket {
+                start: i as f64 * seconds,
+                seconds,
+                primes,
+                cumulative,
+                largest_prime,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prime(number: u64, elapsed: u64) -> PrimeResult {
+        PrimeResult {
+            number,
+            elapsed,
+            thread_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("200".parse(), Ok(Resolution::Slices(200)));
+        assert_eq!("1m".parse(), Ok(Resolution::Every(Duration::from_secs(60))));
+        assert_eq!(
+            " 500ms ".parse(),
+            Ok(Resolution::Every(Duration::from_millis(500)))
+        );
+        assert!("0".parse::<Resolution>().is_err());
+        assert!("0s".parse::<Resolution>().is_err());
+        assert!("often".parse::<Resolution>().is_err());
+        assert_eq!(
+            Resolution::Every(Duration::from_secs(60)).to_string(),
+            "every 1m"
+        );
+    }
+
+    #[test]
+    fn test_bucket() {
+        let primes = [
+            prime(2, 0),
+            prime(5, 1_500_000),
+            prime(3, 1_200_000),
+            prime(7, 3_100_000),
+        ];
+
+        let seconds = bucket(&primes, Resolution::Every(Duration::from_secs(1)));
+        let counts: Vec<u64> = seconds.iter().map(|b| b.primes).collect();
+        assert_eq!(counts, [1, 2, 0, 1]);
+        let cumulative: Vec<u64> = seconds.iter().map(|b| b.cumulative).collect();
+        assert_eq!(cumulative, [1, 3, 3, 4]);
+        let largest: Vec<Option<u64>> = seconds.iter().map(|b| b.largest_prime).collect();
+        assert_eq!(largest, [Some(2), Some(5), None, Some(7)]);
+        assert_eq!(seconds[1].start, 1.0);
+        assert_eq!(seconds[1].midpoint(), 1.5);
+        assert_eq!(seconds[1].primes_per_second(), 2.0);
+
+        // Slices of 1.55s, the last prime opening a third
+        let slices = bucket(&primes, Resolution::Slices(2));
+        let counts: Vec<u64> = slices.iter().map(|b| b.primes).collect();
+        assert_eq!(counts, [3, 0, 1]);
+
+        assert!(bucket(&[], Resolution::Slices(4)).is_empty());
+        assert_eq!(bucket(&[prime(2, 0)], Resolution::Slices(4)).len(), 1);
+    }
+}
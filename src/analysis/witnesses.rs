@@ -0,0 +1,279 @@
+//! Which Miller-Rabin witnesses detect which composites
+//!
+//! Every odd composite in a range is put through one Miller-Rabin round per
+//! witness. A witness that misses n leaves it a strong pseudoprime to that
+//! base; how often two witnesses miss the same numbers shows whether they
+//! are worth using together, and the prefix of a witness list that catches
+//! every composite is a witness set that is deterministic over the range.
+//! Compositeness comes from the deterministic Miller-Rabin, proven for all
+//! of u64, not from the witnesses being measured.
+//!
+//! [`first_counterexample`] checks a claim instead: that a witness set is
+//! deterministic up to a bound, as published for the sets in
+//! [`WITNESS_PRESETS`](crate::algorithms::miller_rabin::WITNESS_PRESETS).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use crate::algorithms::bpsw;
+use crate::core_algorithms::miller_rabin::{self, find_witness, is_prime};
+
+/// Detections by each of a list of witnesses over the odd composites of a
+/// range
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WitnessCoverage {
+    pub witnesses: Vec<u64>,
+    pub start: u64,
+    pub end: u64,
+    pub odd_composites: u64,
+    /// `missed[i][j]`: composites missed by both witness i and witness j,
+    /// so `missed[i][i]` is those witness i misses
+    pub missed: Vec<Vec<u64>>,
+    /// `only[i]`: composites witness i is the only one to detect
+    pub only: Vec<u64>,
+    /// Composites every witness misses, in increasing order
+    pub missed_by_all: Vec<u64>,
+    /// Witnesses from the front of the list needed to detect every
+    /// composite, None if even all of them do not
+    pub prefix_needed: Option<usize>,
+}
+
+impl WitnessCoverage {
+    /// Composites witness `i` detects
+    pub fn detected(&self, i: usize) -> u64 {
+        self.odd_composites - self.missed[i][i]
+    }
+}
+
+/// Runs a round with each of `witnesses` on every odd composite in
+/// [start, end]. A witness that is a multiple of n tells nothing about it
+/// and counts as missing it.
+///
+/// # Panics
+///
+/// If `witnesses` is empty.
+pub fn witness_coverage(witnesses: &[u64], start: u64, end: u64) -> WitnessCoverage {
+    assert!(!witnesses.is_empty(), "coverage needs at least one witness");
+    let count = witnesses.len();
+    let mut coverage = WitnessCoverage {
+        witnesses: witnesses.to_vec(),
+        start,
+        end,
+        odd_composites: 0,
+        missed: vec![vec![0; count]; count],
+        only: vec![0; count],
+        missed_by_all: Vec::new(),
+        prefix_needed: Some(0),
+    };
+
+    // 9 is the smallest odd composite
+    let first = start.max(9) | 1;
+    let mut missing = Vec::with_capacity(count);
+    for n in (first..=end).step_by(2) {
+        if is_prime(n) {
+            continue;
+        }
+        coverage.odd_composites += 1;
+
+        missing.clear();
+        missing.extend((0..count).filter(|&i| find_witness(n, &witnesses[i..=i]).is_none()));
+        for &i in &missing {
+            for &j in &missing {
+                coverage.missed[i][j] += 1;
+            }
+        }
+
+        // The witnesses up to the first that detects n are needed for it
+        let first_detector = (0..count).find(|i| !missing.contains(i));
+        coverage.prefix_needed = match first_detector {
+            Some(i) => coverage.prefix_needed.map(|needed| needed.max(i + 1)),
+            None => None,
+        };
+        match first_detector {
+            None => coverage.missed_by_all.push(n),
+            Some(i) if missing.len() == count - 1 => coverage.only[i] += 1,
+            Some(_) => {}
+        }
+    }
+
+    coverage
+}
+
+/// What decides whether a number a witness set accepts is really prime
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Reference {
+    Bpsw,
+    /// Miller-Rabin with the 12 witnesses proven deterministic for u64
+    MillerRabin,
+}
+
+impl Reference {
+    pub fn name(self) -> &'static str {
+        match self {
+            Reference::Bpsw => "bpsw",
+            Reference::MillerRabin => "miller-rabin",
+        }
+    }
+
+    pub fn is_prime(self, n: u64) -> bool {
+        match self {
+            Reference::Bpsw => bpsw::is_prime(n),
+            Reference::MillerRabin => miller_rabin::is_prime(n),
+        }
+    }
+}
+
+/// Numbers [`first_counterexample`] checks between two progress reports
+pub const BLOCK: u64 = 1 << 24;
+
+/// The smallest odd n ≥ 5 in [start, end] that is a strong probable prime
+/// to every one of `witnesses` but composite according to `reference`, or
+/// None if the set is deterministic over the range. Blocks of [`BLOCK`]
+/// numbers are checked in turn, each split among `threads` threads, and
+/// `progress` is called with the last number of every block that holds no
+/// counterexample.
+pub fn first_counterexample(
+    witnesses: &[u64],
+    start: u64,
+    end: u64,
+    reference: Reference,
+    threads: usize,
+    mut progress: impl FnMut(u64),
+) -> Option<u64> {
+    let fooled = |n: u64| find_witness(n, witnesses).is_none() && !reference.is_prime(n);
+
+    let mut from = start.max(5);
+    while from <= end {
+        let to = from.saturating_add(BLOCK - 1).min(end);
+        if let Some(n) = first_in_block(from | 1, to, threads, &fooled) {
+            return Some(n);
+        }
+        progress(to);
+        match to.checked_add(1) {
+            Some(next) => from = next,
+            None => break,
+        }
+    }
+
+    None
+}
+
+// The smallest odd n in [from, to] (from odd) for which `fooled` holds.
+// Thread t of `threads` checks from + 2t, from + 2t + 2 threads, ... and
+// gives up once past a counterexample found by any thread.
+fn first_in_block(
+    from: u64,
+    to: u64,
+    threads: usize,
+    fooled: &(impl Fn(u64) -> bool + Sync),
+) -> Option<u64> {
+    if threads <= 1 {
+        return (from..=to).step_by(2).find(|&n| fooled(n));
+    }
+
+    let first = AtomicU64::new(u64::MAX);
+    thread::scope(|scope| {
+        for t in 0..threads as u64 {
+            let first = &first;
+            scope.spawn(move || {
+                let Some(own) = from.checked_add(2 * t) else {
+                    return;
+                };
+                for n in (own..=to).step_by(2 * threads) {
+                    if n > first.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if fooled(n) {
+                        first.fetch_min(n, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    Some(first.into_inner()).filter(|&n| n != u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage() {
+        let coverage = witness_coverage(&[2, 3, 5], 0, 10_000);
+        // The strong pseudoprimes to base 2 below 10^4
+        assert_eq!(coverage.missed[0][0], 5);
+        assert_eq!(coverage.detected(0), coverage.odd_composites - 5);
+        // 121 and 703 are strong pseudoprimes to base 3, neither to base 2
+        assert!(coverage.missed[1][1] >= 2);
+        assert_eq!(coverage.missed[0][1], coverage.missed[1][0]);
+        assert_eq!(coverage.missed[0][1], 0);
+        assert!(coverage.missed_by_all.is_empty());
+        assert_eq!(coverage.prefix_needed, Some(2));
+
+        // 3 alone detects exactly the composites 2 misses and 3 does not
+        let pair = witness_coverage(&[2, 3], 0, 10_000);
+        assert_eq!(pair.only[1], pair.missed[0][0] - pair.missed[0][1]);
+        assert_eq!(pair.only[1], 5);
+    }
+
+    #[test]
+    fn test_missed_by_all() {
+        // 1373653, the smallest strong pseudoprime to both 2 and 3
+        let coverage = witness_coverage(&[2, 3], 1_373_600, 1_373_700);
+        assert_eq!(coverage.missed_by_all, [1_373_653]);
+        assert_eq!(coverage.prefix_needed, None);
+
+        let coverage = witness_coverage(&[2], 0, 2046);
+        assert_eq!(coverage.prefix_needed, Some(1));
+        assert_eq!(coverage.only, [coverage.odd_composites]);
+    }
+
+    #[test]
+    fn test_first_counterexample() {
+        for threads in [1, 3] {
+            let check = |witnesses: &[u64], start, end, reference| {
+                let mut reports = Vec::new();
+                let found = first_counterexample(witnesses, start, end, reference, threads, |to| {
+                    reports.push(to)
+                });
+                (found, reports)
+            };
+
+            assert_eq!(
+                check(&[2], 0, 10_000, Reference::Bpsw),
+                (Some(2047), vec![])
+            );
+            assert_eq!(
+                check(&[2, 3], 2048, 10_000_000, Reference::MillerRabin).0,
+                Some(1_373_653)
+            );
+            // Deterministic below 1373653
+            assert_eq!(
+                check(&[2, 3], 0, 1_373_652, Reference::Bpsw),
+                (None, vec![1_373_652])
+            );
+        }
+
+        // Ranges ending at the top of u64 stop there
+        let top = u64::MAX - 100;
+        assert_eq!(
+            first_counterexample(
+                &[2, 3, 5, 7, 11, 13, 17],
+                top,
+                u64::MAX,
+                Reference::Bpsw,
+                2,
+                |_| {}
+            ),
+            None
+        );
+    }
+}
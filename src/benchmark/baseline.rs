@@ -0,0 +1,295 @@
+//! Named baselines of benchmark summary metrics, to detect regressions
+//!
+//! A baseline is a JSON file holding one [`BaselineEntry`] per algorithm.
+//! Later runs are compared against it metric by metric, and a metric that
+//! got worse by more than a threshold counts as a regression.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::result::BenchmarkResult;
+use crate::benchmark::schema::{SCHEMA_VERSION, UNVERSIONED};
+use crate::error::{Error, Result};
+
+/// Summary metrics of one algorithm's benchmark run
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BaselineEntry {
+    pub algorithm: String,
+    /// Length of the run in seconds
+    pub duration_secs: f64,
+    pub primes_found: u64,
+    pub largest_prime: Option<u64>,
+    /// Mean time the algorithm spent on a candidate
+    pub average_nanos: f64,
+}
+
+impl BaselineEntry {
+    pub fn from_result(result: &BenchmarkResult) -> BaselineEntry {
+        let (candidates, nanos) =
+            result
+                .timings
+                .iter()
+                .fold((0, 0), |(candidates, nanos), (_, timing)| {
+                    (candidates + timing.candidates, nanos + timing.total_nanos)
+                });
+
+        BaselineEntry {
+            algorithm: result.algorithm.clone(),
+            duration_secs: result.duration.as_secs_f64(),
+            primes_found: result.primes_found() as u64,
+            largest_prime: result.largest_prime(),
+            average_nanos: if candidates > 0 {
+                nanos as f64 / candidates as f64
+            } else {
+                0.0
+            },
+        }
+    }
+
+    pub fn primes_per_second(&self) -> f64 {
+        self.primes_found as f64 / self.duration_secs
+    }
+
+    /// Changes of each metric from `baseline` to this run. A metric regressed
+    /// if it got worse by more than `threshold_percent`.
+    pub fn compare(&self, baseline: &BaselineEntry, threshold_percent: f64) -> Vec<MetricChange> {
+        vec![
+            MetricChange::new(
+                "primes/s",
+                baseline.primes_per_second(),
+                self.primes_per_second(),
+                true,
+                threshold_percent,
+            ),
+            MetricChange::new(
+                "ns/candidate",
+                baseline.average_nanos,
+                self.average_nanos,
+                false,
+                threshold_percent,
+            ),
+        ]
+    }
+}
+
+/// How one metric moved between a baseline and the current run
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MetricChange {
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    /// Relative improvement in percent, negative if the metric got worse
+    pub improvement_percent: f64,
+    pub regressed: bool,
+}
+
+impl MetricChange {
+    fn new(
+        metric: &'static str,
+        baseline: f64,
+        current: f64,
+        higher_is_better: bool,
+        threshold_percent: f64,
+    ) -> MetricChange {
+        let change = if baseline != 0.0 {
+            100.0 * (current - baseline) / baseline
+        } else {
+            0.0
+        };
+        let improvement_percent = if higher_is_better { change } else { -change };
+
+        MetricChange {
+            metric,
+            baseline,
+            current,
+            improvement_percent,
+            regressed: improvement_percent < -threshold_percent,
+        }
+    }
+}
+
+/// A named set of summary metrics, one entry per algorithm
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Baseline {
+    /// Layout the baseline was written with; see [`crate::benchmark::schema`]
+    #[serde(default = "unversioned")]
+    pub schema_version: u32,
+    pub name: String,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn new(name: impl Into<String>) -> Baseline {
+        Baseline {
+            schema_version: SCHEMA_VERSION,
+            name: name.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Path of the baseline called `name` in `dir`
+    pub fn path(dir: &Path, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !name.starts_with('.');
+        if !valid {
+            return Err(Error::InvalidBaseline {
+                name: name.to_string(),
+                reason: "names may only use letters, digits, '-', '_', and '.'".to_string(),
+            });
+        }
+
+        Ok(dir.join(format!("{}.json", name)))
+    }
+
+    /// Loads the baseline called `name` from `dir`, written by this or an
+    /// earlier build; it is saved back in the current layout
+    pub fn load(dir: &Path, name: &str) -> Result<Baseline> {
+        let path = Baseline::path(dir, name)?;
+        let invalid = |reason: String| Error::InvalidBaseline {
+            name: name.to_string(),
+            reason,
+        };
+
+        let json = fs::read_to_string(&path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => invalid(format!("{} does not exist", path.display())),
+            _ => Error::Io(e),
+        })?;
+        let mut baseline: Baseline =
+            serde_json::from_str(&json).map_err(|e| invalid(e.to_string()))?;
+
+        // Every version so far has the same fields
+        if baseline.schema_version > SCHEMA_VERSION {
+            return Err(invalid(format!(
+                "schema version {} is newer than this build reads (up to {})",
+                baseline.schema_version, SCHEMA_VERSION
+            )));
+        }
+        baseline.schema_version = SCHEMA_VERSION;
+
+        Ok(baseline)
+    }
+
+    /// Loads the baseline called `name` from `dir`, or starts an empty one
+    pub fn load_or_new(dir: &Path, name: &str) -> Result<Baseline> {
+        match Baseline::load(dir, name) {
+            Ok(baseline) => Ok(baseline),
+            Err(Error::InvalidBaseline { .. }) if !Baseline::path(dir, name)?.exists() => {
+                Ok(Baseline::new(name))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the baseline to `dir`, creating it if needed
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        let path = Baseline::path(dir, &self.name)?;
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    pub fn get(&self, algorithm: &str) -> Option<&BaselineEntry> {
+        self.entries.iter().find(|e| e.algorithm == algorithm)
+    }
+
+    /// Adds `entry`, replacing the previous one for the same algorithm
+    pub fn upsert(&mut self, entry: BaselineEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.algorithm == entry.algorithm)
+        {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+}
+
+// Baselines saved before the version was recorded
+fn unversioned() -> u32 {
+    UNVERSIONED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(primes_found: u64, average_nanos: f64) -> BaselineEntry {
+        BaselineEntry {
+            algorithm: "miller-rabin".to_string(),
+            duration_secs: 10.0,
+            primes_found,
+            largest_prime: Some(1009),
+            average_nanos,
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = entry(1000, 50.0);
+
+        // 3% fewer primes is within a 5% threshold, 20% more time is not
+        let changes = entry(970, 60.0).compare(&baseline, 5.0);
+        assert_eq!(changes[0].metric, "primes/s");
+        assert!((changes[0].improvement_percent + 3.0).abs() < 1e-9);
+        assert!(!changes[0].regressed);
+        assert_eq!(changes[1].metric, "ns/candidate");
+        assert!((changes[1].improvement_percent + 20.0).abs() < 1e-9);
+        assert!(changes[1].regressed);
+
+        // Improvements never regress
+        let changes = entry(2000, 25.0).compare(&baseline, 0.0);
+        assert!(changes.iter().all(|c| !c.regressed));
+        assert!((changes[1].improvement_percent - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_upsert() {
+        let mut baseline = Baseline::new("main");
+        baseline.upsert(entry(1000, 50.0));
+        baseline.upsert(entry(1200, 40.0));
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.get("miller-rabin").unwrap().primes_found, 1200);
+        assert!(baseline.get("bpsw").is_none());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let dir = std::env::temp_dir().join(format!("ptc-baseline-{}", std::process::id()));
+        let mut baseline = Baseline::new("before-refactor");
+        baseline.upsert(entry(1000, 50.0));
+
+        let path = baseline.save(&dir).unwrap();
+        assert!(path.ends_with("before-refactor.json"));
+        assert_eq!(Baseline::load(&dir, "before-refactor").unwrap(), baseline);
+
+        assert!(matches!(
+            Baseline::load(&dir, "missing"),
+            Err(Error::InvalidBaseline { .. })
+        ));
+        assert_eq!(
+            Baseline::load_or_new(&dir, "missing").unwrap(),
+            Baseline::new("missing")
+        );
+        assert!(Baseline::path(&dir, "../escape").is_err());
+
+        // Older baselines load in the current layout, newer ones are refused
+        let unversioned = r#"{"name":"old","entries":[]}"#;
+        fs::write(dir.join("old.json"), unversioned).unwrap();
+        assert_eq!(Baseline::load(&dir, "old").unwrap(), Baseline::new("old"));
+
+        let newer = unversioned.replace("{", r#"{"schema_version":99,"#);
+        fs::write(dir.join("old.json"), newer).unwrap();
+        assert!(matches!(
+            Baseline::load(&dir, "old"),
+            Err(Error::InvalidBaseline { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
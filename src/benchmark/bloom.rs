@@ -0,0 +1,305 @@
+//! Bloom filters over prime lists
+//!
+//! A Bloom filter answers whether a number is among the primes it was built
+//! from in a fixed number of bits per prime, however large the primes, at the
+//! price of a false-positive rate chosen up front: a number it rejects is
+//! certainly not among them, a number it accepts only probably is. Each
+//! number sets [`BloomFilter::hashes`] bits chosen by double hashing of two
+//! splitmix64 mixes. The file layout, all integers little-endian:
+//!
+//! ```text
+//! "PTCBLOOM"                      magic and format version
+//! u64 count, (u64 len, UTF-8)     key-value metadata, 2 × count strings
+//! bits: u64, hashes: u64, items: u64
+//! u64 × ⌈bits / 64⌉               the bit array
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// Extension of the files written by [`BloomFilter::save`]
+pub const BLOOM_EXTENSION: &str = "bloom";
+
+/// False-positive rate used when none is given
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+const MAGIC: &[u8; 8] = b"PTCBLOOM";
+
+// Most bits set per number, so a damaged header cannot make lookups crawl
+const MAX_HASHES: u64 = 64;
+
+/// A set of numbers with false positives but no false negatives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    words: Vec<u64>,
+    bits: u64,
+    hashes: u32,
+    items: u64,
+}
+
+impl BloomFilter {
+    /// An empty filter sized for `items` numbers at a false-positive rate of
+    /// `rate`: ⌈-items · ln(rate) / ln²2⌉ bits and round(bits / items · ln 2)
+    /// hashes
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is not strictly between 0 and 1.
+    pub fn with_rate(items: u64, rate: f64) -> BloomFilter {
+        assert!(
+            rate > 0.0 && rate < 1.0,
+            "false-positive rate {} is not between 0 and 1",
+            rate
+        );
+
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(items.max(1) as f64) * rate.ln() / (ln2 * ln2)).ceil() as u64;
+        let bits = bits.max(64);
+        let hashes = (bits as f64 / items.max(1) as f64 * ln2).round() as u64;
+        BloomFilter {
+            words: vec![0; bits.div_ceil(64) as usize],
+            bits,
+            hashes: hashes.clamp(1, MAX_HASHES) as u32,
+            items: 0,
+        }
+    }
+
+    /// A filter holding `primes`, sized for their number
+    pub fn from_primes(primes: &[u64], rate: f64) -> BloomFilter {
+        let mut filter = BloomFilter::with_rate(primes.len() as u64, rate);
+        for &prime in primes {
+            filter.insert(prime);
+        }
+        filter
+    }
+
+    pub fn insert(&mut self, n: u64) {
+        for bit in bit_indices(n, self.hashes, self.bits) {
+            self.words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.items += 1;
+    }
+
+    /// False if `n` was certainly never inserted, true if it probably was
+    pub fn contains(&self, n: u64) -> bool {
+        bit_indices(n, self.hashes, self.bits)
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Numbers inserted
+    pub fn len(&self) -> u64 {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    /// Size of the bit array
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Bits set per number
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// The false-positive rate expected from the numbers inserted so far,
+    /// (1 - e^(-hashes · items / bits))^hashes
+    pub fn false_positive_rate(&self) -> f64 {
+        let k = self.hashes as f64;
+        (1.0 - (-k * self.items as f64 / self.bits as f64).exp()).powf(k)
+    }
+
+    /// Writes the filter and `metadata` to `path`, returning the bytes written
+    pub fn save(&self, path: impl AsRef<Path>, metadata: &[(String, String)]) -> Result<u64> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(metadata.len() as u64).to_le_bytes());
+        for (key, value) in metadata {
+            for s in [key, value] {
+                bytes.extend_from_slice(&(s.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(s.as_bytes());
+            }
+        }
+        for field in [self.bits, self.hashes as u64, self.items] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for word in &self.words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+
+        fs::write(path, &bytes)?;
+        Ok(bytes.len() as u64)
+    }
+
+    /// Reads a filter written by [`save`](Self::save), with its metadata
+    pub fn load(path: impl AsRef<Path>) -> Result<(BloomFilter, Vec<(String, String)>)> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let mut reader = Reader {
+            bytes: &bytes,
+            path,
+        };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid(path, "not a .bloom file".to_string()));
+        }
+
+        let mut metadata = Vec::new();
+        for _ in 0..reader.u64()? {
+            let key = reader.string()?;
+            let value = reader.string()?;
+            metadata.push((key, value));
+        }
+
+        let bits = reader.u64()?;
+        let hashes = reader.u64()?;
+        let items = reader.u64()?;
+        if bits == 0 || !(1..=MAX_HASHES).contains(&hashes) {
+            return Err(invalid(
+                path,
+                format!("{} bits and {} hashes per number", bits, hashes),
+            ));
+        }
+        // Checked before allocating, so a damaged header cannot ask for more
+        let len = bits.div_ceil(64);
+        if reader.bytes.len() as u64 != len * 8 {
+            return Err(invalid(
+                path,
+                format!(
+                    "{} bits need {} bytes, the file has {}",
+                    bits,
+                    len * 8,
+                    reader.bytes.len()
+                ),
+            ));
+        }
+        let words = reader
+            .bytes
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        let filter = BloomFilter {
+            words,
+            bits,
+            hashes: hashes as u32,
+            items,
+        };
+        Ok((filter, metadata))
+    }
+}
+
+// The `hashes` bits of `n` among `bits`: h1 + i · h2 for i below `hashes`
+fn bit_indices(n: u64, hashes: u32, bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = mix(n);
+    let h2 = mix(n ^ 0x9E37_79B9_7F4A_7C15) | 1;
+    (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+// The splitmix64 finalizer, as in RandomSource
+fn mix(n: u64) -> u64 {
+    let mut z = n.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Reads the fields of a .bloom file in order
+struct Reader<'a> {
+    bytes: &'a [u8],
+    path: &'a Path,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid(self.path, "truncated".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u64()?;
+        let len = usize::try_from(len).map_err(|_| invalid(self.path, "truncated".to_string()))?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| invalid(self.path, "metadata is not UTF-8".to_string()))
+    }
+}
+
+fn invalid(path: &Path, reason: String) -> Error {
+    Error::InvalidBloomFile {
+        path: path.display().to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ptc-bloom-{}-{}.bloom", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let primes = sieve(2_000_000);
+        for rate in [0.1, 0.01, 0.001] {
+            let filter = BloomFilter::from_primes(&primes, rate);
+            assert_eq!(filter.len(), primes.len() as u64);
+            assert!(primes.iter().all(|&p| filter.contains(p)));
+
+            // The composites accepted, against the rate asked for
+            let composites: Vec<u64> = (0..2_000_000)
+                .filter(|n| primes.binary_search(n).is_err())
+                .collect();
+            let accepted = composites.iter().filter(|&&n| filter.contains(n)).count();
+            let measured = accepted as f64 / composites.len() as f64;
+            assert!(measured < rate * 1.5, "{} at rate {}", measured, rate);
+            assert!((filter.false_positive_rate() - rate).abs() < rate * 0.2);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip");
+        let filter = BloomFilter::from_primes(&sieve(10_000), 0.01);
+        let metadata = vec![("run.covered-up-to".to_string(), "10000".to_string())];
+        let bytes = filter.save(&path, &metadata).unwrap();
+        assert_eq!(bytes, fs::metadata(&path).unwrap().len());
+        assert_eq!(BloomFilter::load(&path).unwrap(), (filter, metadata));
+
+        // Damaged files are rejected before their bit array is allocated
+        let mut damaged = fs::read(&path).unwrap();
+        damaged.truncate(damaged.len() - 1);
+        fs::write(&path, &damaged).unwrap();
+        assert!(matches!(
+            BloomFilter::load(&path),
+            Err(Error::InvalidBloomFile { .. })
+        ));
+        fs::write(&path, b"PAR1").unwrap();
+        assert!(matches!(
+            BloomFilter::load(&path),
+            Err(Error::InvalidBloomFile { .. })
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "not between 0 and 1")]
+    fn test_invalid_rate() {
+        BloomFilter::with_rate(10, 1.0);
+    }
+}
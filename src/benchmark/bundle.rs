@@ -0,0 +1,314 @@
+//! Shareable archives of benchmark results
+//!
+//! A bundle is a gzipped tarball holding the Parquet files of one or more
+//! runs, their metadata as JSON, the environment of the exporting machine,
+//! and a Markdown report summarizing each run, so a set of results can be
+//! attached to an issue and read without this tool:
+//!
+//! ```text
+//! bundle/
+//! ├── report.md
+//! ├── environment.json
+//! ├── metadata/<run>.json
+//! └── results/<run>.parquet
+//! ```
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::analysis::throughput::decade_throughput;
+use crate::benchmark::environment::Environment;
+use crate::benchmark::parquet::read_from_parquet;
+use crate::benchmark::result::PrimeResult;
+use crate::error::Result;
+use crate::humanize::NumberFormat;
+
+// Reports are shared, so they read the same wherever they are opened
+const NUMBERS: NumberFormat = NumberFormat::ENGLISH;
+
+// Metadata values longer than this (e.g. monitor samples) are cut short in
+// the report; the metadata JSON keeps them whole
+const MAX_REPORT_VALUE_LEN: usize = 80;
+
+/// The contents of one result file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundledRun {
+    /// File stem, e.g. "miller-rabin-30s"
+    pub name: String,
+    pub primes: Vec<PrimeResult>,
+    pub metadata: Vec<(String, String)>,
+}
+
+impl BundledRun {
+    /// Reads a Parquet file written by the benchmark
+    pub fn read(path: &Path) -> Result<BundledRun> {
+        let (primes, metadata) = read_from_parquet(&path.to_string_lossy())?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "results".to_string());
+
+        Ok(BundledRun {
+            name,
+            primes,
+            metadata,
+        })
+    }
+
+    /// Microseconds from the start of the run to the last prime found
+    pub fn span_micros(&self) -> u64 {
+        self.primes.iter().map(|p| p.elapsed).max().unwrap_or(0)
+    }
+}
+
+/// Writes the runs in `files` and the current environment to a gzipped
+/// tarball at `output`. Runs sharing a file stem are numbered apart.
+pub fn export_bundle(files: &[PathBuf], output: &Path) -> Result<()> {
+    let mut runs: Vec<BundledRun> = Vec::with_capacity(files.len());
+    for file in files {
+        let mut run = BundledRun::read(file)?;
+        let taken = |name: &str| runs.iter().any(|r| r.name == name);
+        if taken(&run.name) {
+            run.name = (2..)
+                .map(|i| format!("{}-{}", run.name, i))
+                .find(|name| !taken(name))
+                .expect("unbounded suffixes");
+        }
+        runs.push(run);
+    }
+
+    let environment = Environment::capture();
+
+    // Entries sit under a directory named after the archive
+    let root = output
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .map(|name| {
+            name.trim_end_matches(".tar.gz")
+                .trim_end_matches(".tgz")
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "bundle".to_string());
+
+    let mut archive = tar::Builder::new(GzEncoder::new(
+        File::create(output)?,
+        Compression::default(),
+    ));
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut append = |path: String, data: &[u8]| -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        archive.append_data(&mut header, format!("{}/{}", root, path), data)?;
+        Ok(())
+    };
+
+    append(
+        "report.md".to_string(),
+        render_report(&runs, &environment).as_bytes(),
+    )?;
+    append(
+        "environment.json".to_string(),
+        serde_json::to_string_pretty(&environment)
+            .map_err(std::io::Error::other)?
+            .as_bytes(),
+    )?;
+
+    for (run, file) in runs.iter().zip(files) {
+        let metadata: serde_json::Map<String, serde_json::Value> = run
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().into()))
+            .collect();
+        append(
+            format!("metadata/{}.json", run.name),
+            serde_json::to_string_pretty(&metadata)
+                .map_err(std::io::Error::other)?
+                .as_bytes(),
+        )?;
+        append(format!("results/{}.parquet", run.name), &fs::read(file)?)?;
+    }
+
+    archive.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// Markdown summary of the runs: primes found, throughput by decade of n,
+/// and the recorded metadata of each
+pub fn render_report(runs: &[BundledRun], environment: &Environment) -> String {
+    let mut report = String::new();
+    let mut line = |text: String| {
+        report.push_str(&text);
+        report.push('\n');
+    };
+
+    line("# Benchmark results".to_string());
+    line(String::new());
+    line(format!(
+        "Exported {} by primality-test-comparison {}.",
+        humantime::format_rfc3339_seconds(SystemTime::now()),
+        environment.crate_version
+    ));
+    line(String::new());
+    line(format!(
+        "- CPU: {} (cores: {})",
+        environment.cpu_model.as_deref().unwrap_or("unknown"),
+        environment.cores
+    ));
+    line(format!(
+        "- OS: {}",
+        environment.os_release.as_deref().unwrap_or(&environment.os)
+    ));
+    line(format!("- rustc: {}", environment.rustc_version));
+    line(String::new());
+    // Runs may come from other machines; theirs is in the env.* metadata
+    line("The above describes the exporting machine.".to_string());
+
+    for run in runs {
+        line(String::new());
+        line(format!("## {}", run.name));
+        line(String::new());
+        line(format!(
+            "- Primes found: {}",
+            NUMBERS.integer(run.primes.len() as u64)
+        ));
+        line(format!(
+            "- Largest prime: {}",
+            run.primes
+                .iter()
+                .map(|p| p.number)
+                .max()
+                .map_or("none".to_string(), |n| n.to_string())
+        ));
+        line(format!(
+            "- Last prime found after: {}",
+            NUMBERS.duration(Duration::from_micros(run.span_micros()))
+        ));
+
+        let decades = decade_throughput(run.primes.iter().map(|p| (p.number, p.elapsed)));
+        if !decades.is_empty() {
+            line(String::new());
+            line("| Decade | Primes | Primes/s |".to_string());
+            line("|--------|--------|----------|".to_string());
+            for decade in decades {
+                line(format!(
+                    "| 10^{} | {} | {} |",
+                    decade.decade,
+                    NUMBERS.integer(decade.primes),
+                    decade
+                        .primes_per_second
+                        .map_or("-".to_string(), |rate| NUMBERS.si(rate))
+                ));
+            }
+        }
+
+        if !run.metadata.is_empty() {
+            line(String::new());
+            line("| Metadata | Value |".to_string());
+            line("|----------|-------|".to_string());
+            for (key, value) in &run.metadata {
+                line(format!("| {} | {} |", key, report_value(value)));
+            }
+        }
+    }
+
+    report
+}
+
+// A metadata value fit for a Markdown table cell
+fn report_value(value: &str) -> String {
+    let value = value.replace('|', "\\|");
+    match value.char_indices().nth(MAX_REPORT_VALUE_LEN) {
+        Some((cut, _)) => format!("{}…", &value[..cut]),
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::parquet::write_to_parquet;
+    use flate2::read::GzDecoder;
+
+    fn run() -> BundledRun {
+        BundledRun {
+            name: "trial-division-1s".to_string(),
+            primes: [(101, 10), (103, 20), (1009, 40), (1013, 80)]
+                .into_iter()
+                .map(|(number, elapsed)| PrimeResult {
+                    number,
+                    elapsed,
+                    thread_id: 0,
+                })
+                .collect(),
+            metadata: vec![
+                ("algo-config.rounds".to_string(), "a|b".to_string()),
+                ("monitor.samples".to_string(), "x".repeat(100)),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_report() {
+        let report = render_report(&[run()], &Environment::capture());
+
+        assert!(report.contains("## trial-division-1s"));
+        assert!(report.contains("- Primes found: 4"));
+        assert!(report.contains("- Largest prime: 1013"));
+        assert!(report.contains("| 10^2 | 2 | 200k |"));
+        assert!(report.contains("- Last prime found after: 80.0µs"));
+        assert!(report.contains("| algo-config.rounds | a\\|b |"));
+        assert!(report.contains(&format!("| monitor.samples | {}… |", "x".repeat(80))));
+    }
+
+    #[test]
+    fn test_export_bundle() {
+        let dir = std::env::temp_dir().join(format!("ptc-bundle-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+
+        // Two runs with the same file name, from different folders
+        let run = run();
+        let files = [
+            dir.join("a/trial-division-1s.parquet"),
+            dir.join("b/trial-division-1s.parquet"),
+        ];
+        for file in &files {
+            write_to_parquet(&file.to_string_lossy(), &run.primes, &run.metadata).unwrap();
+        }
+
+        let output = dir.join("shared.tar.gz");
+        export_bundle(&files, &output).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&output).unwrap()));
+        let mut paths: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            [
+                "shared/environment.json",
+                "shared/metadata/trial-division-1s-2.json",
+                "shared/metadata/trial-division-1s.json",
+                "shared/report.md",
+                "shared/results/trial-division-1s-2.parquet",
+                "shared/results/trial-division-1s.parquet",
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
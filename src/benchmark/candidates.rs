@@ -0,0 +1,332 @@
+//! Where the numbers a benchmark tests come from
+//!
+//! A [`CandidateSource`] puts its candidates in an order: by default 2 and
+//! 3, then 6k ± 1 upward (see [`coverage`](crate::analysis::coverage)), or
+//! every integer from 2, so the throughput measured includes how fast each
+//! algorithm turns away the even numbers and small multiples. Threads
+//! take them from a shared [`CandidateCursor`] in leases of [`CHUNK_LEN`]
+//! consecutive positions, so whenever the run stops, everything before the
+//! first position some thread had not reached was tested: the tested
+//! candidates are a prefix of the order but for the chunks still in
+//! progress, at most one per thread.
+//!
+//! A [`Permutation`] instead orders every number of a range pseudo-randomly,
+//! fixed by a seed, so a random-order run is the same run wherever it is
+//! replayed: the order depends on nothing but the range and the seed, not on
+//! the thread count or the platform. Each number's position is computed on
+//! its own, without materializing the range, by cycle-walking a bijection on
+//! the smallest power of two covering it; rounds of key mixing, odd
+//! multiplication and xorshift, with keys drawn from splitmix64, make up the
+//! bijection.
+
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::random::RandomSource;
+
+/// Positions a thread leases at once: enough that taking a lease costs
+/// nothing next to testing it, few enough that the chunks in progress when a
+/// run stops span little of the order
+pub const CHUNK_LEN: u64 = 1024;
+
+// Rounds of the bijection, with the odd multiplier of each
+const MULTIPLIERS: [u64; 4] = [
+    0xBF58_476D_1CE4_E5B9,
+    0x94D0_49BB_1331_11EB,
+    0x9E37_79B9_7F4A_7C15,
+    0xD6E8_FEB8_6659_FD93,
+];
+
+/// The candidates of a benchmark, in the order they are handed out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CandidateSource {
+    /// 2 and 3, then 6k - 1 and 6k + 1 for k from 1 upward
+    #[default]
+    Sequential,
+    /// Every integer from 2 upward, even ones included
+    AllIntegers,
+    /// Every number of a range in a reproducible pseudo-random order
+    Shuffled(Permutation),
+}
+
+impl CandidateSource {
+    /// Number of candidates, None if they run on to the end of u64
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            CandidateSource::Sequential | CandidateSource::AllIntegers => None,
+            CandidateSource::Shuffled(permutation) => Some(permutation.len()),
+        }
+    }
+
+    /// Always false: every source has candidates
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The candidate at `position` of the order, None past its end
+    pub fn nth(&self, position: u64) -> Option<u64> {
+        match self {
+            CandidateSource::Sequential => match position {
+                0 => Some(2),
+                1 => Some(3),
+                _ => {
+                    let k = (position - 2) / 2 + 1;
+                    let six_k = k.checked_mul(6)?;
+                    if position.is_multiple_of(2) {
+                        Some(six_k - 1)
+                    } else {
+                        six_k.checked_add(1)
+                    }
+                }
+            },
+            CandidateSource::AllIntegers => position.checked_add(2),
+            CandidateSource::Shuffled(permutation) => permutation.nth(position),
+        }
+    }
+
+    /// Once the first `tested` candidates were tested, every prime up to
+    /// here was among them; None for a shuffled range, which covers no
+    /// prefix of the integers
+    pub fn covered_up_to(&self, tested: u64) -> Option<u64> {
+        match self {
+            // Everything below the next candidate is 0, 1, a multiple of 2
+            // or 3, or was tested
+            CandidateSource::Sequential | CandidateSource::AllIntegers => {
+                Some(self.nth(tested).map_or(u64::MAX, |next| next - 1))
+            }
+            CandidateSource::Shuffled(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for CandidateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CandidateSource::Sequential => f.write_str("6k ± 1"),
+            CandidateSource::AllIntegers => f.write_str("every integer"),
+            CandidateSource::Shuffled(permutation) => write!(
+                f,
+                "shuffled {}..={} (seed {})",
+                permutation.start,
+                permutation.end(),
+                permutation.seed
+            ),
+        }
+    }
+}
+
+/// Hands the positions of a [`CandidateSource`] out to threads, a chunk at
+/// a time and in order
+#[derive(Debug)]
+pub struct CandidateCursor {
+    source: CandidateSource,
+    next: AtomicU64,
+}
+
+impl CandidateCursor {
+    pub fn new(source: CandidateSource) -> CandidateCursor {
+        CandidateCursor {
+            source,
+            next: AtomicU64::new(0),
+        }
+    }
+
+    pub fn source(&self) -> &CandidateSource {
+        &self.source
+    }
+
+    /// The next [`CHUNK_LEN`] positions no thread has leased yet, fewer at
+    /// the end of the source, None once it is used up
+    pub fn lease(&self) -> Option<Range<u64>> {
+        let start = self.next.fetch_add(CHUNK_LEN, Ordering::Relaxed);
+        let end = start.saturating_add(CHUNK_LEN);
+        match self.source.len() {
+            Some(len) if start >= len => None,
+            Some(len) => Some(start..end.min(len)),
+            None => Some(start..end),
+        }
+    }
+
+    /// Positions leased so far
+    pub fn leased(&self) -> u64 {
+        let next = self.next.load(Ordering::Relaxed);
+        self.source.len().map_or(next, |len| next.min(len))
+    }
+}
+
+/// A pseudo-random order of the numbers of a range, fixed by a seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permutation {
+    start: u64,
+    len: u64,
+    seed: u64,
+    mask: u64,
+    shift: u32,
+    keys: [u64; MULTIPLIERS.len()],
+}
+
+impl Permutation {
+    /// The order of `range` for `seed`; None if the range is empty or all
+    /// of u64, whose length does not fit in one
+    pub fn new(range: RangeInclusive<u64>, seed: u64) -> Option<Permutation> {
+        let (start, end) = range.into_inner();
+        let len = end.checked_sub(start)?.checked_add(1)?;
+
+        let bits = (u64::BITS - (len - 1).leading_zeros()).max(1);
+        let mut random = RandomSource::new(seed);
+        Some(Permutation {
+            start,
+            len,
+            seed,
+            mask: u64::MAX >> (u64::BITS - bits),
+            shift: bits / 2 + 1,
+            keys: std::array::from_fn(|_| random.next_u64()),
+        })
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.start + (self.len - 1)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Always false: a permutation covers at least one number
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The number at position `index` of the order, None past its end
+    pub fn nth(&self, index: u64) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+
+        // Walks the cycle of `index` until it lands back in the range, which
+        // it does within two steps on average since the range is more than
+        // half the power of two
+        let mut x = index;
+        loop {
+            x = self.mix(x);
+            if x < self.len {
+                return Some(self.start + x);
+            }
+        }
+    }
+
+    /// The whole range in order
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).filter_map(|index| self.nth(index))
+    }
+
+    // A bijection on [0, mask]: each step is invertible modulo a power of two
+    fn mix(&self, mut x: u64) -> u64 {
+        for (key, multiplier) in self.keys.iter().zip(MULTIPLIERS) {
+            x = (x ^ key) & self.mask;
+            x = x.wrapping_mul(multiplier) & self.mask;
+            x ^= x >> self.shift;
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permutation() {
+        for (range, seed) in [(0..=0, 1), (5..=6, 2), (10..=1009, 3), (100..=1123, 4)] {
+            let permutation = Permutation::new(range.clone(), seed).unwrap();
+            let mut order: Vec<u64> = permutation.iter().collect();
+            assert_eq!(order.len() as u64, permutation.len());
+            order.sort_unstable();
+            assert_eq!(order, range.collect::<Vec<u64>>());
+        }
+
+        let first: Vec<u64> = Permutation::new(1..=1000, 42).unwrap().iter().collect();
+        let again: Vec<u64> = Permutation::new(1..=1000, 42).unwrap().iter().collect();
+        let other: Vec<u64> = Permutation::new(1..=1000, 43).unwrap().iter().collect();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert!(!first.is_sorted());
+        // The order is part of what a replayed run relies on
+        assert_eq!(first[..4], [911, 826, 570, 12]);
+
+        let huge = Permutation::new(u64::MAX - 9..=u64::MAX, 7).unwrap();
+        assert!(huge.iter().all(|n| n >= u64::MAX - 9));
+        assert_eq!(huge.nth(10), None);
+        assert_eq!(Permutation::new(0..=u64::MAX, 7), None);
+        assert_eq!(Permutation::new(RangeInclusive::new(5, 4), 7), None);
+    }
+
+    #[test]
+    fn test_sequential() {
+        let sequential = CandidateSource::Sequential;
+        let first: Vec<u64> = (0..10).filter_map(|i| sequential.nth(i)).collect();
+        assert_eq!(first, [2, 3, 5, 7, 11, 13, 17, 19, 23, 25]);
+        assert_eq!(sequential.covered_up_to(0), Some(1));
+        assert_eq!(sequential.covered_up_to(4), Some(10));
+        assert_eq!(sequential.nth(u64::MAX), None);
+        assert_eq!(sequential.covered_up_to(u64::MAX), Some(u64::MAX));
+
+        let all = CandidateSource::AllIntegers;
+        let first: Vec<u64> = (0..4).filter_map(|i| all.nth(i)).collect();
+        assert_eq!(first, [2, 3, 4, 5]);
+        assert_eq!(all.covered_up_to(4), Some(5));
+        assert_eq!(all.nth(u64::MAX - 1), None);
+        assert_eq!(all.to_string(), "every integer");
+
+        let shuffled = CandidateSource::Shuffled(Permutation::new(50..=149, 9).unwrap());
+        assert_eq!(shuffled.covered_up_to(100), None);
+        assert_eq!(
+            shuffled.to_string(),
+            "shuffled 50..=149 (seed 9)".to_string()
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let cursor = CandidateCursor::new(CandidateSource::Sequential);
+        assert_eq!(cursor.lease(), Some(0..CHUNK_LEN));
+        assert_eq!(cursor.lease(), Some(CHUNK_LEN..2 * CHUNK_LEN));
+        assert_eq!(cursor.leased(), 2 * CHUNK_LEN);
+
+        // Threads leasing side by side share the range out, each number once
+        let len = 3 * CHUNK_LEN + 100;
+        let cursor = CandidateCursor::new(CandidateSource::Shuffled(
+            Permutation::new(50..=49 + len, 9).unwrap(),
+        ));
+        let mut all: Vec<u64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..3)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut tested = Vec::new();
+                        while let Some(lease) = cursor.lease() {
+                            tested.extend(lease.filter_map(|i| cursor.source().nth(i)));
+                        }
+                        tested
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        all.sort_unstable();
+        assert_eq!(all, (50..=49 + len).collect::<Vec<u64>>());
+        assert_eq!(cursor.leased(), len);
+        assert_eq!(cursor.lease(), None);
+    }
+}
@@ -0,0 +1,614 @@
+//! Compact storage for sorted prime lists
+//!
+//! A `.gaps` file holds only the primes, without the timings and threads a
+//! Parquet result carries: the gaps between consecutive primes as LEB128
+//! varints, split into blocks of [`BLOCK_LEN`] primes that are compressed with
+//! zstd one by one. An index of the blocks at the end of the file records the
+//! first and last prime of each, so a range of primes can be read without
+//! decompressing the rest. The layout, all integers little-endian:
+//!
+//! ```text
+//! "PTCGAPS1"                      magic and format version
+//! varint count, (varint len, UTF-8) key-value metadata, 2 × count strings
+//! zstd frame × blocks             gaps from each block's first prime
+//! (first, last, count, offset, len) × blocks, u64 each
+//! index offset: u64, blocks: u64, "PTCGAPS1"
+//! ```
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::benchmark::schema::COVERED_KEY;
+use crate::error::{Error, Result};
+
+/// Primes per compressed block: the most a range query decompresses beyond
+/// the primes it asked for, twice over
+pub const BLOCK_LEN: usize = 1 << 16;
+
+/// Extension of the files written by [`write_gaps`]
+pub const GAPS_EXTENSION: &str = "gaps";
+
+const MAGIC: &[u8; 8] = b"PTCGAPS1";
+
+// zstd level; higher ones barely shrink varint gaps further
+const COMPRESSION_LEVEL: i32 = 3;
+
+// Bytes of an index entry and of the footer
+const ENTRY_LEN: u64 = 5 * 8;
+const FOOTER_LEN: u64 = 2 * 8 + MAGIC.len() as u64;
+
+/// One compressed block, as recorded in the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapBlock {
+    pub first: u64,
+    pub last: u64,
+    /// Primes in the block, `first` and `last` included
+    pub count: u64,
+    offset: u64,
+    len: u64,
+}
+
+/// What [`write_gaps`] wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapSummary {
+    pub primes: u64,
+    pub blocks: usize,
+    /// Size of the whole file, index and metadata included
+    pub bytes: u64,
+}
+
+/// Writes `primes`, which must be strictly ascending, to `path` with the
+/// `metadata` pairs
+pub fn write_gaps(
+    path: impl AsRef<Path>,
+    primes: impl IntoIterator<Item = u64>,
+    metadata: &[(String, String)],
+) -> Result<GapSummary> {
+    let path = path.as_ref();
+    let mut out = BufWriter::new(File::create(path)?);
+
+    let mut header = MAGIC.to_vec();
+    write_varint(&mut header, metadata.len() as u64);
+    for (key, value) in metadata {
+        for text in [key, value] {
+            write_varint(&mut header, text.len() as u64);
+            header.extend_from_slice(text.as_bytes());
+        }
+    }
+    out.write_all(&header)?;
+
+    let mut offset = header.len() as u64;
+    let mut index: Vec<GapBlock> = Vec::new();
+    let mut gaps = Vec::new();
+    let mut block: Option<GapBlock> = None;
+    for prime in primes {
+        if let Some(current) = &mut block {
+            if prime <= current.last {
+                return Err(invalid(
+                    path,
+                    format!(
+                        "primes must be strictly ascending, got {} after {}",
+                        prime, current.last
+                    ),
+                ));
+            }
+            write_varint(&mut gaps, prime - current.last);
+            current.last = prime;
+            current.count += 1;
+        } else {
+            block = Some(GapBlock {
+                first: prime,
+                last: prime,
+                count: 1,
+                offset,
+                len: 0,
+            });
+        }
+
+        if let Some(mut full) = block.take_if(|block| block.count == BLOCK_LEN as u64) {
+            full.len = write_block(&mut out, &mut gaps)?;
+            offset += full.len;
+            index.push(full);
+        }
+    }
+    if let Some(mut partial) = block {
+        partial.len = write_block(&mut out, &mut gaps)?;
+        offset += partial.len;
+        index.push(partial);
+    }
+
+    for block in &index {
+        for field in [
+            block.first,
+            block.last,
+            block.count,
+            block.offset,
+            block.len,
+        ] {
+            out.write_all(&field.to_le_bytes())?;
+        }
+    }
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&(index.len() as u64).to_le_bytes())?;
+    out.write_all(MAGIC)?;
+    out.flush()?;
+
+    Ok(GapSummary {
+        primes: index.iter().map(|block| block.count).sum(),
+        blocks: index.len(),
+        bytes: offset + index.len() as u64 * ENTRY_LEN + FOOTER_LEN,
+    })
+}
+
+// Compresses the varint gaps of a block, leaving `gaps` empty; returns the
+// bytes written
+fn write_block(out: &mut impl Write, gaps: &mut Vec<u8>) -> Result<u64> {
+    let compressed = zstd::bulk::compress(gaps, COMPRESSION_LEVEL)?;
+    out.write_all(&compressed)?;
+    gaps.clear();
+    Ok(compressed.len() as u64)
+}
+
+/// A file written by [`write_gaps`], of which only the metadata and the
+/// index are read on opening
+#[derive(Debug, Clone)]
+pub struct GapFile {
+    path: PathBuf,
+    size: u64,
+    metadata: Vec<(String, String)>,
+    blocks: Vec<GapBlock>,
+}
+
+impl GapFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<GapFile> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let truncated = || invalid(path, "truncated file".to_string());
+
+        let mut magic = [0u8; 8];
+        file.read_exact(&mut magic).map_err(|_| truncated())?;
+        if &magic != MAGIC {
+            return Err(invalid(path, "not a .gaps file".to_string()));
+        }
+
+        if file_len < MAGIC.len() as u64 + FOOTER_LEN {
+            return Err(truncated());
+        }
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        file.read_exact(&mut footer)?;
+        let index_offset = u64_at(&footer, 0);
+        let block_count = u64_at(&footer, 1);
+        if &footer[16..] != MAGIC
+            || index_offset < MAGIC.len() as u64
+            || block_count
+                .checked_mul(ENTRY_LEN)
+                .and_then(|len| len.checked_add(index_offset))
+                != Some(file_len - FOOTER_LEN)
+        {
+            return Err(invalid(path, "damaged index".to_string()));
+        }
+
+        let mut index = vec![0u8; (block_count * ENTRY_LEN) as usize];
+        file.seek(SeekFrom::Start(index_offset))?;
+        file.read_exact(&mut index)?;
+        let blocks: Vec<GapBlock> = index
+            .chunks_exact(ENTRY_LEN as usize)
+            .map(|entry| GapBlock {
+                first: u64_at(entry, 0),
+                last: u64_at(entry, 1),
+                count: u64_at(entry, 2),
+                offset: u64_at(entry, 3),
+                len: u64_at(entry, 4),
+            })
+            .collect();
+        let in_order = blocks.windows(2).all(|pair| pair[0].last < pair[1].first);
+        let in_bounds = blocks.iter().all(|block| {
+            block.offset >= MAGIC.len() as u64
+                && block
+                    .offset
+                    .checked_add(block.len)
+                    .is_some_and(|end| end <= index_offset)
+        });
+        if !in_order || !in_bounds {
+            return Err(invalid(path, "damaged index".to_string()));
+        }
+
+        // The metadata runs from the magic to the first block
+        let header_end = blocks.first().map_or(index_offset, |block| block.offset);
+        let mut header = vec![0u8; (header_end - MAGIC.len() as u64) as usize];
+        file.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        file.read_exact(&mut header)?;
+        let mut cursor = header.as_slice();
+        let pairs = read_varint(&mut cursor).ok_or_else(truncated)?;
+        let mut text = || -> Option<String> {
+            let len = usize::try_from(read_varint(&mut cursor)?).ok()?;
+            let bytes = cursor.get(..len)?;
+            cursor = &cursor[len..];
+            String::from_utf8(bytes.to_vec()).ok()
+        };
+        let metadata = (0..pairs)
+            .map(|_| Some((text()?, text()?)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid(path, "damaged metadata".to_string()))?;
+
+        Ok(GapFile {
+            path: path.to_path_buf(),
+            size: file_len,
+            metadata,
+            blocks,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    /// The number every prime up to which is in the file, as recorded under
+    /// [`COVERED_KEY`]: the limit of a sieve, or what a run covered
+    pub fn covered_up_to(&self) -> Option<u64> {
+        self.metadata
+            .iter()
+            .find(|(key, _)| key == COVERED_KEY)
+            .and_then(|(_, value)| value.parse().ok())
+    }
+
+    pub fn blocks(&self) -> &[GapBlock] {
+        &self.blocks
+    }
+
+    /// Primes in the file
+    pub fn len(&self) -> u64 {
+        self.blocks.iter().map(|block| block.count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The smallest prime in the file
+    pub fn first(&self) -> Option<u64> {
+        self.blocks.first().map(|block| block.first)
+    }
+
+    /// The largest prime in the file
+    pub fn last(&self) -> Option<u64> {
+        self.blocks.last().map(|block| block.last)
+    }
+
+    /// Every prime in the file, in ascending order
+    pub fn primes(&self) -> Result<Vec<u64>> {
+        self.primes_in(0..=u64::MAX)
+    }
+
+    /// The primes in `range`, decompressing only the blocks that overlap it
+    pub fn primes_in(&self, range: RangeInclusive<u64>) -> Result<Vec<u64>> {
+        let mut primes = Vec::new();
+        for block in self.blocks_in(range) {
+            primes.extend(block?);
+        }
+        Ok(primes)
+    }
+
+    /// The primes in `range` one block at a time, in ascending order, each
+    /// block decompressed only when it is reached
+    pub fn blocks_in(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> impl Iterator<Item = Result<Vec<u64>>> + '_ {
+        let (start, end) = range.into_inner();
+        let from = self.blocks.partition_point(|block| block.last < start);
+        let mut file = None;
+
+        self.blocks[from..]
+            .iter()
+            .take_while(move |block| block.first <= end)
+            .map(move |block| {
+                let file = match &mut file {
+                    Some(file) => file,
+                    None => file.insert(File::open(&self.path)?),
+                };
+                let mut primes = self.read_block(file, block)?;
+                primes.retain(|prime| (start..=end).contains(prime));
+                Ok(primes)
+            })
+    }
+
+    /// The primes in `range` in ascending order, one block decompressed at a
+    /// time
+    pub fn iter(&self, range: RangeInclusive<u64>) -> impl Iterator<Item = Result<u64>> + '_ {
+        self.blocks_in(range).flat_map(|block| match block {
+            Ok(primes) => primes.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        })
+    }
+
+    /// Whether `n` is in the file, decompressing at most one block. For many
+    /// lookups, [`GapLookup`] keeps the file in memory instead.
+    pub fn contains(&self, n: u64) -> Result<bool> {
+        Ok(self.primes_in(n..=n)?.first() == Some(&n))
+    }
+
+    fn read_block(&self, file: &mut File, block: &GapBlock) -> Result<Vec<u64>> {
+        let mut compressed = vec![0u8; block.len as usize];
+        file.seek(SeekFrom::Start(block.offset))?;
+        file.read_exact(&mut compressed)?;
+        self.decode(block, &compressed)
+    }
+
+    // The primes of `block` from its compressed bytes, checked against the index
+    fn decode(&self, block: &GapBlock, compressed: &[u8]) -> Result<Vec<u64>> {
+        let gaps = zstd::stream::decode_all(compressed)?;
+
+        let mut primes = Vec::with_capacity(block.count as usize);
+        primes.push(block.first);
+        let mut cursor = gaps.as_slice();
+        while !cursor.is_empty() {
+            let gap = read_varint(&mut cursor).filter(|&gap| gap > 0);
+            let next = gap.and_then(|gap| primes.last().unwrap().checked_add(gap));
+            primes.push(next.ok_or_else(|| invalid(&self.path, "damaged block".to_string()))?);
+        }
+
+        if primes.len() as u64 != block.count || primes.last() != Some(&block.last) {
+            return Err(invalid(
+                &self.path,
+                format!("block at {} does not match the index", block.first),
+            ));
+        }
+        Ok(primes)
+    }
+}
+
+/// A [`GapFile`] read into memory to answer many lookups. Blocks are
+/// decompressed when a lookup first reaches them, and the most recent
+/// [`CACHED_BLOCKS`] kept, so lookups of nearby numbers decompress nothing.
+#[derive(Debug)]
+pub struct GapLookup {
+    file: GapFile,
+    data: Vec<u8>,
+    // Most recently used last
+    cache: Mutex<Vec<(usize, Arc<Vec<u64>>)>>,
+}
+
+/// Decompressed blocks a [`GapLookup`] keeps
+pub const CACHED_BLOCKS: usize = 8;
+
+impl GapLookup {
+    /// Reads the file at `path` into memory, checking every block once so
+    /// that lookups cannot fail later
+    pub fn load(path: impl AsRef<Path>) -> Result<GapLookup> {
+        let file = GapFile::open(&path)?;
+        let data = fs::read(&path)?;
+        for block in file.blocks() {
+            file.decode(block, compressed(&data, block))?;
+        }
+
+        Ok(GapLookup {
+            file,
+            data,
+            cache: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The index and metadata of the file
+    pub fn file(&self) -> &GapFile {
+        &self.file
+    }
+
+    /// Whether `n` is in the file
+    pub fn contains(&self, n: u64) -> bool {
+        match self.block_of(n) {
+            Some(i) => self.block(i).binary_search(&n).is_ok(),
+            None => false,
+        }
+    }
+
+    /// The index of the block whose primes span `n`, None if `n` lies
+    /// outside every block
+    pub fn block_of(&self, n: u64) -> Option<usize> {
+        let blocks = self.file.blocks();
+        let i = blocks.partition_point(|block| block.last < n);
+        blocks.get(i).filter(|block| block.first <= n).map(|_| i)
+    }
+
+    /// The primes of the `i`-th block
+    pub fn block(&self, i: usize) -> Arc<Vec<u64>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(at) = cache.iter().position(|(cached, _)| *cached == i) {
+            let entry = cache.remove(at);
+            cache.push(entry);
+        } else {
+            let block = &self.file.blocks[i];
+            let primes = self
+                .file
+                .decode(block, compressed(&self.data, block))
+                .expect("every block was checked on loading");
+            if cache.len() == CACHED_BLOCKS {
+                cache.remove(0);
+            }
+            cache.push((i, Arc::new(primes)));
+        }
+        cache.last().unwrap().1.clone()
+    }
+}
+
+// The compressed bytes of `block` in the file contents `data`
+fn compressed<'a>(data: &'a [u8], block: &GapBlock) -> &'a [u8] {
+    &data[block.offset as usize..(block.offset + block.len) as usize]
+}
+
+fn invalid(path: &Path, reason: String) -> Error {
+    Error::InvalidGapFile {
+        path: path.display().to_string(),
+        reason,
+    }
+}
+
+// The `i`-th little-endian u64 of `bytes`
+fn u64_at(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap())
+}
+
+// Seven bits per byte, low bits first, the high bit set on all but the last
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+// None if the bytes run out or the value overflows a u64
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        n |= u64::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ptc-gaps-{}-{}.gaps", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_varint() {
+        for n in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, n);
+            let mut cursor = bytes.as_slice();
+            assert_eq!(read_varint(&mut cursor), Some(n));
+            assert!(cursor.is_empty());
+        }
+        assert_eq!(read_varint(&mut [0x80u8].as_slice()), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip");
+        let primes = sieve(2_000_000);
+        let metadata = vec![("run.algorithm".to_string(), "sieve".to_string())];
+
+        let summary = write_gaps(&path, primes.iter().copied(), &metadata).unwrap();
+        assert_eq!(summary.primes, primes.len() as u64);
+        assert_eq!(summary.blocks, primes.len().div_ceil(BLOCK_LEN));
+        assert_eq!(summary.bytes, fs::metadata(&path).unwrap().len());
+        // Well under the byte per prime of the varints alone
+        assert!(summary.bytes < primes.len() as u64);
+
+        let file = GapFile::open(&path).unwrap();
+        assert_eq!(file.metadata(), metadata);
+        assert_eq!(file.len(), primes.len() as u64);
+        assert_eq!(file.primes().unwrap(), primes);
+        assert_eq!(
+            file.primes_in(1_000_000..=1_000_100).unwrap(),
+            [
+                1_000_003, 1_000_033, 1_000_037, 1_000_039, 1_000_081, 1_000_099
+            ]
+        );
+        assert!(file.contains(999_983).unwrap());
+        assert!(!file.contains(999_985).unwrap());
+        assert!(!file.contains(u64::MAX).unwrap());
+        assert_eq!(file.first(), Some(2));
+        assert_eq!(file.last(), Some(1_999_993));
+        assert_eq!(file.covered_up_to(), None);
+        assert_eq!(
+            file.iter(1_999_900..=u64::MAX)
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+            [1_999_957, 1_999_969, 1_999_979, 1_999_993]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lookup() {
+        let path = temp_path("lookup");
+        let primes = sieve(3_000_000);
+        let metadata = [(COVERED_KEY.to_string(), "3000000".to_string())];
+        write_gaps(&path, primes.iter().copied(), &metadata).unwrap();
+
+        let lookup = GapLookup::load(&path).unwrap();
+        assert_eq!(lookup.file().covered_up_to(), Some(3_000_000));
+        for n in (0..3_000_000).step_by(997) {
+            assert_eq!(lookup.contains(n), primes.binary_search(&n).is_ok(), "{n}");
+        }
+        assert!(lookup.contains(2));
+        assert!(lookup.contains(2_999_999));
+        assert!(!lookup.contains(u64::MAX));
+        assert_eq!(lookup.block_of(2), Some(0));
+        assert_eq!(lookup.block_of(u64::MAX), None);
+        assert!(lookup.cache.lock().unwrap().len() <= CACHED_BLOCKS);
+
+        // A damaged block is found on loading, not by a lookup
+        let mut bytes = fs::read(&path).unwrap();
+        let middle = lookup.file().blocks()[1].offset as usize + 8;
+        bytes[middle] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+        assert!(GapLookup::load(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let path = temp_path("edge-cases");
+
+        write_gaps(&path, [], &[]).unwrap();
+        let file = GapFile::open(&path).unwrap();
+        assert!(file.is_empty());
+        assert!(file.primes().unwrap().is_empty());
+
+        write_gaps(&path, [2, u64::MAX - 58], &[]).unwrap();
+        assert_eq!(
+            GapFile::open(&path).unwrap().primes().unwrap(),
+            [2, u64::MAX - 58]
+        );
+
+        assert!(matches!(
+            write_gaps(&path, [2, 5, 3], &[]),
+            Err(Error::InvalidGapFile { .. })
+        ));
+
+        // Damage is reported, not misread
+        write_gaps(&path, sieve(1000), &[]).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            GapFile::open(&path),
+            Err(Error::InvalidGapFile { .. })
+        ));
+        fs::write(&path, b"PAR1").unwrap();
+        assert!(matches!(
+            GapFile::open(&path),
+            Err(Error::InvalidGapFile { .. })
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+//! Benchmark run configuration
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::algorithms::PrimalityTest;
+use crate::benchmark::candidates::CandidateSource;
+use crate::benchmark::live::LiveResults;
+use crate::benchmark::numa::NumaTopology;
+use crate::benchmark::prefilter::{MAX_PREFILTER_BOUND, Prefilter};
+use crate::benchmark::smt::PhysicalCores;
+use crate::error::{Error, Result};
+
+/// Everything needed to run a benchmark, built with [`BenchmarkConfig::builder`]
+#[derive(Clone)]
+pub struct BenchmarkConfig {
+    pub(crate) algorithm: Arc<dyn PrimalityTest>,
+    pub(crate) duration: Duration,
+    pub(crate) threads: usize,
+    pub(crate) candidates: CandidateSource,
+    pub(crate) metadata: Vec<(String, String)>,
+    pub(crate) cross_check: Option<Arc<dyn PrimalityTest>>,
+    pub(crate) verify_complete: bool,
+    pub(crate) prefilter: Option<Arc<Prefilter>>,
+    pub(crate) monitor: Option<Duration>,
+    pub(crate) status_interval: Option<Duration>,
+    pub(crate) numa: Option<Arc<NumaTopology>>,
+    pub(crate) physical_cores: Option<Arc<PhysicalCores>>,
+    pub(crate) live: Option<Arc<LiveResults>>,
+}
+
+impl BenchmarkConfig {
+    /// Starts building a configuration benchmarking `algorithm`
+    pub fn builder(algorithm: Arc<dyn PrimalityTest>) -> BenchmarkConfigBuilder {
+        BenchmarkConfigBuilder {
+            algorithm,
+            duration: Duration::from_secs(10),
+            threads: None,
+            candidates: CandidateSource::Sequential,
+            metadata: Vec::new(),
+            cross_check: None,
+            verify_complete: false,
+            prefilter: None,
+            monitor: None,
+            status_interval: None,
+            numa: false,
+            physical_cores_only: false,
+            live: None,
+        }
+    }
+
+    pub fn algorithm(&self) -> &Arc<dyn PrimalityTest> {
+        &self.algorithm
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn candidates(&self) -> &CandidateSource {
+        &self.candidates
+    }
+
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    pub fn cross_check(&self) -> Option<&Arc<dyn PrimalityTest>> {
+        self.cross_check.as_ref()
+    }
+
+    pub fn verify_complete(&self) -> bool {
+        self.verify_complete
+    }
+
+    pub fn prefilter(&self) -> Option<&Prefilter> {
+        self.prefilter.as_deref()
+    }
+
+    pub fn monitor(&self) -> Option<Duration> {
+        self.monitor
+    }
+
+    pub fn status_interval(&self) -> Option<Duration> {
+        self.status_interval
+    }
+
+    pub fn numa(&self) -> Option<&NumaTopology> {
+        self.numa.as_deref()
+    }
+
+    pub fn physical_cores(&self) -> Option<&PhysicalCores> {
+        self.physical_cores.as_deref()
+    }
+
+    pub fn live(&self) -> Option<&Arc<LiveResults>> {
+        self.live.as_ref()
+    }
+}
+
+pub struct BenchmarkConfigBuilder {
+    algorithm: Arc<dyn PrimalityTest>,
+    duration: Duration,
+    threads: Option<usize>,
+    candidates: CandidateSource,
+    metadata: Vec<(String, String)>,
+    cross_check: Option<Arc<dyn PrimalityTest>>,
+    verify_complete: bool,
+    prefilter: Option<u64>,
+    monitor: Option<Duration>,
+    status_interval: Option<Duration>,
+    numa: bool,
+    physical_cores_only: bool,
+    live: Option<Arc<LiveResults>>,
+}
+
+impl BenchmarkConfigBuilder {
+    /// How long to search for primes (default: 10s)
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Number of worker threads (default: available parallelism)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Where the numbers tested come from (default: 6k ± 1 upward). A
+    /// shuffled range ends the run early once it is used up, and cannot be
+    /// verified complete.
+    pub fn candidates(mut self, candidates: CandidateSource) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Attaches a key-value pair that is carried into the result and its output files
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Re-verifies every prime found with `reference` once the run is over,
+    /// recording the ones it rejects (default: no cross-check)
+    pub fn cross_check(mut self, reference: Arc<dyn PrimalityTest>) -> Self {
+        self.cross_check = Some(reference);
+        self
+    }
+
+    /// Sieves the range every thread covered once the run is over, recording
+    /// the primes in it that are missing from the results or were found more
+    /// than once (default: off)
+    pub fn verify_complete(mut self, enabled: bool) -> Self {
+        self.verify_complete = enabled;
+        self
+    }
+
+    /// Rejects candidates with a prime factor of at most `bound` before they
+    /// reach the algorithm, timing the prefilter separately (default: none)
+    pub fn prefilter(mut self, bound: u64) -> Self {
+        self.prefilter = Some(bound);
+        self
+    }
+
+    /// Samples CPU frequency and temperature every `interval` during the
+    /// run, to tell thermal throttling apart from the algorithm (default: off)
+    pub fn monitor(mut self, interval: Duration) -> Self {
+        self.monitor = Some(interval);
+        self
+    }
+
+    /// Reports the progress of the run as a `status` event every `interval`:
+    /// elapsed time, primes found, largest candidate tested, and primes per
+    /// second over the interval (default: off)
+    pub fn status_interval(mut self, interval: Duration) -> Self {
+        self.status_interval = Some(interval);
+        self
+    }
+
+    /// Spreads the threads evenly over the NUMA nodes, binding each to its
+    /// node's CPUs before it allocates its buffers (default: off). Building
+    /// fails where the topology cannot be read.
+    pub fn numa(mut self, enabled: bool) -> Self {
+        self.numa = enabled;
+        self
+    }
+
+    /// Pins one thread to each physical core, leaving hyperthread siblings
+    /// idle, and defaults the thread count to the number of physical cores
+    /// (default: off). Building fails where the topology cannot be read, or
+    /// together with NUMA placement.
+    pub fn physical_cores_only(mut self, enabled: bool) -> Self {
+        self.physical_cores_only = enabled;
+        self
+    }
+
+    /// Publishes the primes found to `live` while the run goes on, so they
+    /// can be read before it ends (default: off)
+    pub fn live(mut self, live: Arc<LiveResults>) -> Self {
+        self.live = Some(live);
+        self
+    }
+
+    pub fn build(self) -> Result<BenchmarkConfig> {
+        if self.duration.is_zero() {
+            return Err(Error::InvalidBenchmark(
+                "duration must be greater than zero".to_string(),
+            ));
+        }
+
+        if self.monitor.is_some_and(|interval| interval.is_zero()) {
+            return Err(Error::InvalidBenchmark(
+                "the monitor interval must be greater than zero".to_string(),
+            ));
+        }
+
+        if self
+            .status_interval
+            .is_some_and(|interval| interval.is_zero())
+        {
+            return Err(Error::InvalidBenchmark(
+                "the status interval must be greater than zero".to_string(),
+            ));
+        }
+
+        if let CandidateSource::Shuffled(permutation) = &self.candidates {
+            if self.verify_complete {
+                return Err(Error::InvalidBenchmark(
+                    "a shuffled range cannot be verified complete".to_string(),
+                ));
+            }
+            let supported = self.algorithm.supported_range();
+            if !supported.contains(&permutation.start()) || !supported.contains(&permutation.end())
+            {
+                return Err(Error::InvalidBenchmark(format!(
+                    "the shuffled range {}..={} goes past what '{}' supports ({}..={})",
+                    permutation.start(),
+                    permutation.end(),
+                    self.algorithm.name(),
+                    supported.start(),
+                    supported.end()
+                )));
+            }
+        }
+
+        let numa = match self.numa {
+            true => match NumaTopology::discover() {
+                Some(topology) => Some(Arc::new(topology)),
+                None => {
+                    return Err(Error::InvalidBenchmark(
+                        "NUMA placement needs the topology in /sys/devices/system/node".to_string(),
+                    ));
+                }
+            },
+            false => None,
+        };
+
+        let physical_cores = match self.physical_cores_only {
+            true if numa.is_some() => {
+                return Err(Error::InvalidBenchmark(
+                    "physical-core pinning and NUMA placement cannot be combined".to_string(),
+                ));
+            }
+            true => match PhysicalCores::discover() {
+                Some(cores) => Some(Arc::new(cores)),
+                None => {
+                    return Err(Error::InvalidBenchmark(
+                        "physical-core pinning needs the CPU topology in /sys/devices/system/cpu"
+                            .to_string(),
+                    ));
+                }
+            },
+            false => None,
+        };
+
+        let threads = match (self.threads, &physical_cores) {
+            (Some(0), _) => {
+                return Err(Error::InvalidBenchmark(
+                    "at least one thread is required".to_string(),
+                ));
+            }
+            (Some(threads), _) => threads,
+            (None, Some(cores)) => cores.len(),
+            (None, None) => thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        };
+
+        let prefilter = match self.prefilter {
+            Some(bound) if bound > MAX_PREFILTER_BOUND => {
+                return Err(Error::InvalidBenchmark(format!(
+                    "the prefilter bound must not exceed {}",
+                    MAX_PREFILTER_BOUND
+                )));
+            }
+            Some(bound) => Some(Arc::new(Prefilter::new(bound))),
+            None => None,
+        };
+
+        Ok(BenchmarkConfig {
+            algorithm: self.algorithm,
+            duration: self.duration,
+            threads,
+            candidates: self.candidates,
+            metadata: self.metadata,
+            cross_check: self.cross_check,
+            verify_complete: self.verify_complete,
+            prefilter,
+            monitor: self.monitor,
+            status_interval: self.status_interval,
+            numa,
+            physical_cores,
+            live: self.live,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::trial_division_sqrt::TrialDivisionSqrt;
+    use crate::algorithms::wilson::{self, Wilson};
+    use crate::benchmark::candidates::Permutation;
+
+    #[test]
+    fn test_builder() {
+        let config = BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+            .duration(Duration::from_secs(3))
+            .threads(2)
+            .metadata("key", "value")
+            .prefilter(100)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.algorithm().name(), "trial-division-sqrt");
+        assert_eq!(config.duration(), Duration::from_secs(3));
+        assert_eq!(config.threads(), 2);
+        assert_eq!(
+            config.metadata(),
+            &[("key".to_string(), "value".to_string())]
+        );
+        assert_eq!(config.prefilter().map(Prefilter::bound), Some(100));
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let config = BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+            .build()
+            .unwrap();
+
+        assert_eq!(config.duration(), Duration::from_secs(10));
+        assert!(config.threads() >= 1);
+        assert!(config.cross_check().is_none());
+        assert!(!config.verify_complete());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid() {
+        assert!(
+            BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+                .duration(Duration::ZERO)
+                .build()
+                .is_err()
+        );
+        assert!(
+            BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+                .threads(0)
+                .build()
+                .is_err()
+        );
+        assert!(
+            BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+                .prefilter(MAX_PREFILTER_BOUND + 1)
+                .build()
+                .is_err()
+        );
+        assert!(
+            BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+                .status_interval(Duration::ZERO)
+                .build()
+                .is_err()
+        );
+
+        // Only a shuffled range covers no prefix to verify
+        let verified = |candidates| {
+            BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+                .candidates(candidates)
+                .verify_complete(true)
+                .build()
+        };
+        assert!(verified(CandidateSource::AllIntegers).is_ok());
+        let shuffled = Permutation::new(1..=100, 1).unwrap();
+        assert!(verified(CandidateSource::Shuffled(shuffled)).is_err());
+
+        // Nor may one go past the numbers the algorithm supports
+        let shuffled = |range| {
+            BenchmarkConfig::builder(Arc::new(Wilson))
+                .candidates(CandidateSource::Shuffled(
+                    Permutation::new(range, 1).unwrap(),
+                ))
+                .build()
+        };
+        assert!(shuffled(2..=wilson::MAX_N).is_ok());
+        assert!(shuffled(2..=wilson::MAX_N + 1).is_err());
+    }
+}
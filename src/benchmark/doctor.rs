@@ -0,0 +1,284 @@
+//! Checks for machine conditions known to ruin benchmarks
+//!
+//! Each check reads Linux procfs/sysfs and reports whether the condition
+//! holds, with advice on fixing it. Checks that cannot read what they need
+//! report [`Severity::Unknown`] instead of guessing.
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::benchmark::monitor::numbered_dirs;
+
+/// Share of CPU time spent busy by other processes above which the machine
+/// counts as loaded
+pub const MAX_BACKGROUND_LOAD: f64 = 0.10;
+
+/// How long the CPU load is sampled for
+pub const LOAD_SAMPLE_TIME: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Unknown,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub detail: String,
+    /// What to do about a warning
+    pub advice: Option<&'static str>,
+}
+
+impl Finding {
+    fn new(check: &'static str, severity: Severity, detail: impl Into<String>) -> Finding {
+        Finding {
+            check,
+            severity,
+            detail: detail.into(),
+            advice: None,
+        }
+    }
+
+    fn advice(mut self, advice: &'static str) -> Finding {
+        self.advice = Some(advice);
+        self
+    }
+}
+
+/// Runs every check; takes about [`LOAD_SAMPLE_TIME`] to sample the load
+pub fn diagnose() -> Vec<Finding> {
+    vec![
+        check_build(),
+        check_governor(),
+        check_turbo(),
+        check_load(),
+        check_power(),
+    ]
+}
+
+fn check_build() -> Finding {
+    if cfg!(debug_assertions) {
+        Finding::new(
+            "build",
+            Severity::Warning,
+            "debug build: timings are dominated by unoptimized code",
+        )
+        .advice("build with `cargo build --release`")
+    } else {
+        Finding::new("build", Severity::Ok, "optimized build")
+    }
+}
+
+fn check_governor() -> Finding {
+    let governors: Vec<String> = numbered_dirs(Path::new("/sys/devices/system/cpu"), "cpu")
+        .iter()
+        .filter_map(|dir| fs::read_to_string(dir.join("cpufreq/scaling_governor")).ok())
+        .map(|governor| governor.trim().to_string())
+        .collect();
+
+    if governors.is_empty() {
+        return Finding::new("governor", Severity::Unknown, "cpufreq is not exposed");
+    }
+
+    let slow: Vec<&String> = governors.iter().filter(|g| *g != "performance").collect();
+    match slow.first() {
+        None => Finding::new(
+            "governor",
+            Severity::Ok,
+            "every CPU uses the performance governor",
+        ),
+        Some(governor) => Finding::new(
+            "governor",
+            Severity::Warning,
+            format!(
+                "{} of {} CPUs use the '{}' governor, which lowers the clock when it sees fit",
+                slow.len(),
+                governors.len(),
+                governor
+            ),
+        )
+        .advice("sudo cpupower frequency-set -g performance"),
+    }
+}
+
+fn check_turbo() -> Finding {
+    let read = |path: &str| fs::read_to_string(path).ok().map(|v| v.trim().to_string());
+
+    // intel_pstate reports the opposite of the generic cpufreq switch
+    let turbo = match read("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        Some(no_turbo) => Some((
+            no_turbo == "0",
+            "echo 1 | sudo tee /sys/devices/system/cpu/intel_pstate/no_turbo",
+        )),
+        None => read("/sys/devices/system/cpu/cpufreq/boost").map(|boost| {
+            (
+                boost == "1",
+                "echo 0 | sudo tee /sys/devices/system/cpu/cpufreq/boost",
+            )
+        }),
+    };
+
+    match turbo {
+        None => Finding::new("turbo", Severity::Unknown, "no turbo/boost switch exposed"),
+        Some((false, _)) => Finding::new("turbo", Severity::Ok, "turbo boost is disabled"),
+        Some((true, advice)) => Finding::new(
+            "turbo",
+            Severity::Warning,
+            "turbo boost is enabled: the clock varies with temperature and the number of busy cores",
+        )
+        .advice(advice),
+    }
+}
+
+fn check_load() -> Finding {
+    let read = || {
+        fs::read_to_string("/proc/stat")
+            .ok()
+            .and_then(|stat| parse_cpu_times(&stat))
+    };
+
+    let Some(before) = read() else {
+        return Finding::new("load", Severity::Unknown, "/proc/stat is not readable");
+    };
+    thread::sleep(LOAD_SAMPLE_TIME);
+    let Some(after) = read() else {
+        return Finding::new("load", Severity::Unknown, "/proc/stat is not readable");
+    };
+
+    let Some(busy) = busy_fraction(before, after) else {
+        return Finding::new("load", Severity::Unknown, "no CPU time elapsed");
+    };
+
+    let detail = format!("other processes kept the CPUs {:.1}% busy", 100.0 * busy);
+    if busy > MAX_BACKGROUND_LOAD {
+        Finding::new("load", Severity::Warning, detail)
+            .advice("stop background jobs (builds, browsers, indexers) before a long run")
+    } else {
+        Finding::new("load", Severity::Ok, detail)
+    }
+}
+
+fn check_power() -> Finding {
+    let supplies: Vec<(String, Option<String>, Option<String>)> =
+        fs::read_dir("/sys/class/power_supply")
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| {
+                        let read = |file: &str| {
+                            fs::read_to_string(entry.path().join(file))
+                                .ok()
+                                .map(|v| v.trim().to_string())
+                        };
+                        (
+                            read("type").unwrap_or_default(),
+                            read("online"),
+                            read("status"),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+    match on_battery(&supplies) {
+        Some(true) => Finding::new("power", Severity::Warning, "running on battery power")
+            .advice("plug in AC power: laptops lower their clocks on battery"),
+        Some(false) => Finding::new("power", Severity::Ok, "running on AC power"),
+        None => Finding::new("power", Severity::Ok, "no battery"),
+    }
+}
+
+/// (busy, total) jiffies from the aggregate "cpu" line of /proc/stat; idle
+/// and iowait count as not busy
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let times: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+
+    // user nice system idle iowait irq softirq steal (guest time is in user)
+    let total: u64 = times.iter().take(8).sum();
+    let idle = times.get(3)? + times.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+fn busy_fraction(before: (u64, u64), after: (u64, u64)) -> Option<f64> {
+    let total = after.1.checked_sub(before.1)?;
+    let busy = after.0.checked_sub(before.0)?;
+    (total > 0).then(|| busy as f64 / total as f64)
+}
+
+/// Some(true) if there is a battery and no AC adapter is online, None if
+/// there is no battery. Each supply is (type, online, status).
+fn on_battery(supplies: &[(String, Option<String>, Option<String>)]) -> Option<bool> {
+    let batteries: Vec<_> = supplies.iter().filter(|s| s.0 == "Battery").collect();
+    if batteries.is_empty() {
+        return None;
+    }
+
+    let mains_online = supplies
+        .iter()
+        .any(|s| s.0 == "Mains" && s.1.as_deref() == Some("1"));
+    let discharging = batteries
+        .iter()
+        .any(|s| s.2.as_deref() == Some("Discharging"));
+
+    Some(discharging || !mains_online)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_times() {
+        let before = parse_cpu_times("cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 1 2 3 4\n").unwrap();
+        assert_eq!(before, (150, 1000));
+
+        let after = parse_cpu_times("cpu  150 0 100 1650 100 0 0 0 0 0\n").unwrap();
+        assert_eq!(busy_fraction(before, after), Some(0.1));
+        assert_eq!(busy_fraction(before, before), None);
+
+        assert_eq!(parse_cpu_times("intr 1 2 3\n"), None);
+    }
+
+    #[test]
+    fn test_on_battery() {
+        let supply = |kind: &str, online: Option<&str>, status: Option<&str>| {
+            (
+                kind.to_string(),
+                online.map(str::to_string),
+                status.map(str::to_string),
+            )
+        };
+
+        let battery = supply("Battery", None, Some("Discharging"));
+        let charging = supply("Battery", None, Some("Charging"));
+        let ac = supply("Mains", Some("1"), None);
+        let unplugged = supply("Mains", Some("0"), None);
+
+        assert_eq!(on_battery(&[]), None);
+        assert_eq!(on_battery(std::slice::from_ref(&ac)), None);
+        assert_eq!(on_battery(&[charging.clone(), ac]), Some(false));
+        assert_eq!(on_battery(&[battery, unplugged.clone()]), Some(true));
+        assert_eq!(on_battery(&[charging, unplugged]), Some(true));
+    }
+
+    #[test]
+    fn test_build() {
+        // Tests run unoptimized unless built with --release
+        let finding = check_build();
+        assert_eq!(
+            finding.severity == Severity::Warning,
+            cfg!(debug_assertions)
+        );
+    }
+}
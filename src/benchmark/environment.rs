@@ -0,0 +1,163 @@
+//! Description of the machine a benchmark ran on
+//!
+//! Results from different machines only compare meaningfully alongside the
+//! hardware and toolchain that produced them. The Linux-specific details are
+//! read from procfs and sysfs and left empty elsewhere.
+
+use std::fs;
+
+use crate::benchmark::scheduling;
+use crate::benchmark::smt::PhysicalCores;
+
+/// Hardware, OS, and toolchain of a benchmark run
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Environment {
+    /// CPU model name, e.g. "AMD Ryzen 9 7950X 16-Core Processor"
+    pub cpu_model: Option<String>,
+    /// Logical cores available to the process
+    pub cores: usize,
+    /// Physical cores among them, fewer than `cores` with SMT
+    pub physical_cores: Option<usize>,
+    /// Highest frequency the first CPU can reach, in kHz
+    pub max_frequency_khz: Option<u64>,
+    /// Frequency scaling governor of the first CPU, e.g. "performance"
+    pub governor: Option<String>,
+    /// Target OS and architecture, e.g. "linux x86_64"
+    pub os: String,
+    /// Distribution name, e.g. "Debian GNU/Linux 12 (bookworm)"
+    pub os_release: Option<String>,
+    /// Kernel release, e.g. "6.8.0-45-generic"
+    pub kernel: Option<String>,
+    /// Nice value of the thread that started the run, inherited by the workers
+    pub nice: Option<i32>,
+    /// Scheduling policy of that thread, e.g. "SCHED_RR (priority 10)"
+    pub scheduler: Option<String>,
+    /// Version of the compiler the crate was built with
+    pub rustc_version: String,
+    /// Version of this crate
+    pub crate_version: String,
+}
+
+impl Environment {
+    /// Describes the current machine and build
+    pub fn capture() -> Environment {
+        let read = |path: &str| fs::read_to_string(path).ok();
+
+        Environment {
+            cpu_model: read("/proc/cpuinfo").and_then(|cpuinfo| parse_cpu_model(&cpuinfo)),
+            cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            physical_cores: PhysicalCores::discover().map(|cores| cores.len()),
+            max_frequency_khz: read("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq")
+                .and_then(|khz| khz.trim().parse().ok()),
+            governor: read("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+                .map(|governor| governor.trim().to_string()),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            os_release: read("/etc/os-release").and_then(|release| parse_os_release(&release)),
+            kernel: read("/proc/sys/kernel/osrelease").map(|kernel| kernel.trim().to_string()),
+            nice: scheduling::current_nice(),
+            scheduler: scheduling::current_policy(),
+            rustc_version: env!("PTC_RUSTC_VERSION").to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// The fields as `env.*` metadata pairs, skipping the unknown ones
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let cores = self.cores.to_string();
+        let physical_cores = self.physical_cores.map(|cores| cores.to_string());
+        let max_frequency = self.max_frequency_khz.map(|khz| khz.to_string());
+        let nice = self.nice.map(|nice| nice.to_string());
+        [
+            ("cpu-model", self.cpu_model.as_deref()),
+            ("cores", Some(cores.as_str())),
+            ("physical-cores", physical_cores.as_deref()),
+            ("max-frequency-khz", max_frequency.as_deref()),
+            ("governor", self.governor.as_deref()),
+            ("os", Some(self.os.as_str())),
+            ("os-release", self.os_release.as_deref()),
+            ("kernel", self.kernel.as_deref()),
+            ("nice", nice.as_deref()),
+            ("scheduler", self.scheduler.as_deref()),
+            ("rustc", Some(self.rustc_version.as_str())),
+            ("crate-version", Some(self.crate_version.as_str())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (format!("env.{}", key), value.to_string())))
+        .collect()
+    }
+}
+
+// The first "model name" line of /proc/cpuinfo
+fn parse_cpu_model(cpuinfo: &str) -> Option<String> {
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "model name")
+        .map(|(_, value)| value.trim().to_string())
+}
+
+// PRETTY_NAME from /etc/os-release, unquoted
+fn parse_os_release(release: &str) -> Option<String> {
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|value| value.trim().trim_matches('"').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz\n";
+        assert_eq!(
+            parse_cpu_model(cpuinfo).as_deref(),
+            Some("Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz")
+        );
+        assert_eq!(parse_cpu_model("processor\t: 0\n"), None);
+
+        let release = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 24.04.1 LTS\"\nID=ubuntu\n";
+        assert_eq!(
+            parse_os_release(release).as_deref(),
+            Some("Ubuntu 24.04.1 LTS")
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let environment = Environment {
+            cpu_model: None,
+            cores: 8,
+            physical_cores: None,
+            max_frequency_khz: None,
+            governor: Some("performance".to_string()),
+            os: "linux x86_64".to_string(),
+            os_release: None,
+            kernel: None,
+            nice: Some(5),
+            scheduler: None,
+            rustc_version: "rustc 1.90.0".to_string(),
+            crate_version: "0.1.0".to_string(),
+        };
+
+        let keys: Vec<String> = environment.metadata().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            keys,
+            [
+                "env.cores",
+                "env.governor",
+                "env.os",
+                "env.nice",
+                "env.rustc",
+                "env.crate-version"
+            ]
+        );
+
+        let captured = Environment::capture();
+        assert!(captured.cores >= 1);
+        assert!(captured.rustc_version.starts_with("rustc"));
+        assert_eq!(captured.crate_version, env!("CARGO_PKG_VERSION"));
+    }
+}
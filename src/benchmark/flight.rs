@@ -0,0 +1,427 @@
+//! Arrow Flight endpoint serving benchmark results
+//!
+//! A [`ResultServer`] holds named result sets: completed runs, such as the
+//! Parquet files of earlier runs, and runs in progress, read from their
+//! [`LiveResults`] at the time of each request. Each set is a flight whose
+//! descriptor path and ticket are its name, with the (elapsed, thread,
+//! number) columns of the Parquet files and the run's metadata as schema
+//! metadata. A set still in progress also carries `live` = `running` or
+//! `finished`, so a client can poll it until the run is over.
+//!
+//! `ListFlights` lists the sets, `GetFlightInfo` and `GetSchema` describe
+//! one, and `DoGet` streams its rows; every other call is unimplemented.
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use tokio::sync::oneshot;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{info, warn};
+
+use crate::benchmark::live::LiveResults;
+use crate::benchmark::parquet::{self, Metadata};
+use crate::benchmark::result::PrimeResult;
+use crate::error::Result;
+
+/// Schema metadata key telling whether a set in progress is still running
+pub const LIVE_KEY: &str = "live";
+
+#[derive(Clone)]
+enum Rows {
+    Completed(Arc<[PrimeResult]>),
+    Live(Arc<LiveResults>),
+}
+
+#[derive(Clone)]
+struct ResultSet {
+    name: String,
+    metadata: Metadata,
+    rows: Rows,
+}
+
+impl ResultSet {
+    // The rows as of now
+    fn primes(&self) -> Vec<PrimeResult> {
+        match &self.rows {
+            Rows::Completed(primes) => primes.to_vec(),
+            Rows::Live(live) => live.since(0),
+        }
+    }
+
+    fn schema(&self) -> SchemaRef {
+        let mut metadata: HashMap<String, String> = self.metadata.iter().cloned().collect();
+        if let Rows::Live(live) = &self.rows {
+            let state = if live.is_finished() {
+                "finished"
+            } else {
+                "running"
+            };
+            metadata.insert(LIVE_KEY.to_string(), state.to_string());
+        }
+        let fields = parquet::schema().fields().clone();
+        Arc::new(Schema::new(fields).with_metadata(metadata))
+    }
+
+    fn info(&self) -> std::result::Result<FlightInfo, Status> {
+        let rows = self.primes().len();
+        Ok(FlightInfo::new()
+            .try_with_schema(&self.schema())
+            .map_err(|error| Status::internal(error.to_string()))?
+            .with_descriptor(FlightDescriptor::new_path(vec![self.name.clone()]))
+            .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(self.name.clone())))
+            .with_total_records(rows as i64))
+    }
+}
+
+/// Result sets served over Arrow Flight. Clones share the same sets, so
+/// sets published after [`serve`](ResultServer::serve) are served too.
+#[derive(Clone, Default)]
+pub struct ResultServer {
+    sets: Arc<RwLock<Vec<ResultSet>>>,
+}
+
+impl ResultServer {
+    pub fn new() -> Self {
+        ResultServer::default()
+    }
+
+    /// Serves `primes` as the set `name`, replacing any set of that name
+    pub fn publish(&self, name: impl Into<String>, primes: Vec<PrimeResult>, metadata: Metadata) {
+        self.insert(ResultSet {
+            name: name.into(),
+            metadata,
+            rows: Rows::Completed(primes.into()),
+        });
+    }
+
+    /// Serves the primes `live` holds at each request as the set `name`,
+    /// replacing any set of that name
+    pub fn publish_live(
+        &self,
+        name: impl Into<String>,
+        live: Arc<LiveResults>,
+        metadata: Metadata,
+    ) {
+        self.insert(ResultSet {
+            name: name.into(),
+            metadata,
+            rows: Rows::Live(live),
+        });
+    }
+
+    /// Names of the sets served, in the order they were first published
+    pub fn names(&self) -> Vec<String> {
+        self.read().iter().map(|set| set.name.clone()).collect()
+    }
+
+    /// Answers Flight requests on `address` from a background thread until
+    /// the returned handle is dropped. Port 0 picks a free port, which
+    /// [`ServerHandle::address`] reports.
+    pub fn serve(&self, address: SocketAddr) -> Result<ServerHandle> {
+        // Bound here, so a port in use fails the call rather than the thread
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let service = FlightServiceServer::new(self.clone());
+        let thread = thread::spawn(move || {
+            let served = runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                Server::builder()
+                    .add_service(service)
+                    .serve_with_incoming_shutdown(TcpIncoming::from(listener), async {
+                        let _ = stopped.await;
+                    })
+                    .await
+                    .map_err(std::io::Error::other)
+            });
+            if let Err(error) = served {
+                warn!("⚠️  Flight server stopped: {}", error);
+            }
+        });
+        info!(
+            "🛰️  Serving results over Arrow Flight at grpc://{}",
+            address
+        );
+
+        Ok(ServerHandle {
+            address,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    fn insert(&self, set: ResultSet) {
+        let mut sets = self.sets.write().unwrap_or_else(|e| e.into_inner());
+        match sets.iter_mut().find(|existing| existing.name == set.name) {
+            Some(existing) => *existing = set,
+            None => sets.push(set),
+        }
+    }
+
+    fn read(&self) -> Vec<ResultSet> {
+        self.sets.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn find(&self, name: &[u8]) -> std::result::Result<ResultSet, Status> {
+        let name = String::from_utf8_lossy(name);
+        self.read()
+            .into_iter()
+            .find(|set| set.name == name)
+            .ok_or_else(|| Status::not_found(format!("no result set named '{}'", name)))
+    }
+
+    fn find_descriptor(
+        &self,
+        descriptor: &FlightDescriptor,
+    ) -> std::result::Result<ResultSet, Status> {
+        match descriptor.path.as_slice() {
+            [name] => self.find(name.as_bytes()),
+            _ => Err(Status::invalid_argument(
+                "a descriptor names a result set by a path of one element",
+            )),
+        }
+    }
+}
+
+/// A running [`ResultServer`], stopped when dropped
+pub struct ServerHandle {
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// The address the server listens on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Blocks until the server stops, which it only does on an error
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+type FlightStream<T> = BoxStream<'static, std::result::Result<T, Status>>;
+
+#[tonic::async_trait]
+impl FlightService for ResultServer {
+    type HandshakeStream = FlightStream<HandshakeResponse>;
+    type ListFlightsStream = FlightStream<FlightInfo>;
+    type DoGetStream = FlightStream<FlightData>;
+    type DoPutStream = FlightStream<PutResult>;
+    type DoActionStream = FlightStream<arrow_flight::Result>;
+    type ListActionsStream = FlightStream<ActionType>;
+    type DoExchangeStream = FlightStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> std::result::Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("no authentication is needed"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> std::result::Result<Response<Self::ListFlightsStream>, Status> {
+        let infos: Vec<_> = self.read().iter().map(ResultSet::info).collect();
+        Ok(Response::new(stream::iter(infos).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let set = self.find_descriptor(request.get_ref())?;
+        Ok(Response::new(set.info()?))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("use GetFlightInfo"))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<SchemaResult>, Status> {
+        let set = self.find_descriptor(request.get_ref())?;
+        let schema = set.schema();
+        let result = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(|error: arrow::error::ArrowError| Status::internal(error.to_string()))?;
+        Ok(Response::new(result))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<Self::DoGetStream>, Status> {
+        let set = self.find(&request.get_ref().ticket)?;
+        let batch = parquet::record_batch(&set.primes())
+            .and_then(|batch| Ok(batch.with_schema(set.schema())?))
+            .map_err(|error| Status::internal(error.to_string()))?;
+        let encoded = FlightDataEncoderBuilder::new()
+            .build(stream::iter([Ok(batch)]))
+            .map_err(Status::from);
+        Ok(Response::new(encoded.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("results are read-only"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> std::result::Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("no actions are offered"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> std::result::Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("results are read-only"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::UInt64Array;
+    use arrow_flight::FlightClient;
+    use tonic::transport::Channel;
+
+    fn prime(number: u64, elapsed: u64) -> PrimeResult {
+        PrimeResult {
+            number,
+            elapsed,
+            thread_id: 0,
+        }
+    }
+
+    // The numbers of the set `name`, with the schema metadata sent along
+    async fn fetch(client: &mut FlightClient, name: &str) -> (Vec<u64>, HashMap<String, String>) {
+        let batches: Vec<_> = client
+            .do_get(Ticket::new(name.to_string()))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let metadata = batches[0].schema().metadata().clone();
+        let numbers = batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column_by_name("number").unwrap();
+                let numbers = column.as_any().downcast_ref::<UInt64Array>().unwrap();
+                numbers.values().to_vec()
+            })
+            .collect();
+        (numbers, metadata)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let server = ResultServer::new();
+        server.publish(
+            "done",
+            vec![prime(2, 1), prime(3, 2)],
+            vec![("note".to_string(), "laptop".to_string())],
+        );
+        let live = Arc::new(LiveResults::new());
+        server.publish_live("running", live.clone(), Vec::new());
+        let handle = server.serve("127.0.0.1:0".parse().unwrap()).unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let channel = Channel::from_shared(format!("http://{}", handle.address()))
+                .unwrap()
+                .connect()
+                .await
+                .unwrap();
+            let mut client = FlightClient::new(channel);
+
+            let infos: Vec<FlightInfo> = client
+                .list_flights(Vec::new())
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            let records: Vec<i64> = infos.iter().map(|info| info.total_records).collect();
+            assert_eq!(records, [2, 0]);
+
+            let (numbers, metadata) = fetch(&mut client, "done").await;
+            assert_eq!(numbers, [2, 3]);
+            assert_eq!(metadata["note"], "laptop");
+
+            // A set in progress is read as of each request
+            live.publish([prime(5, 3)]);
+            let (numbers, metadata) = fetch(&mut client, "running").await;
+            assert_eq!(numbers, [5]);
+            assert_eq!(metadata[LIVE_KEY], "running");
+            live.publish([prime(7, 4)]);
+            live.finish();
+            let (numbers, metadata) = fetch(&mut client, "running").await;
+            assert_eq!(numbers, [5, 7]);
+            assert_eq!(metadata[LIVE_KEY], "finished");
+
+            let missing = client.do_get(Ticket::new("nothing".to_string())).await;
+            assert!(missing.is_err());
+        });
+
+        // Publishing again replaces the set, keeping its place
+        server.publish("running", vec![prime(5, 3)], Vec::new());
+        assert_eq!(server.names(), ["done", "running"]);
+    }
+}
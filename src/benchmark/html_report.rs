@@ -0,0 +1,264 @@
+//! Standalone HTML reports with interactive charts
+//!
+//! The page embeds plotly.js, so it opens offline and travels as a single
+//! file. Each result file becomes one trace per chart: the primes found per
+//! second over the run, zoomable to spot stalls, and the distribution of the
+//! time each thread took between consecutive primes it found.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use plotly::common::{Mode, Title};
+use plotly::layout::{Axis, AxisType};
+use plotly::{Layout, Plot, Scatter};
+
+use crate::analysis::timeseries::{self, Resolution};
+use crate::benchmark::parquet::read_from_parquet;
+use crate::benchmark::result::PrimeResult;
+use crate::error::Result;
+use crate::humanize::NumberFormat;
+
+// Reports are shared, so they read the same wherever they are opened
+const NUMBERS: NumberFormat = NumberFormat::ENGLISH;
+
+/// Number of equal slices of a run its throughput is computed over, unless
+/// a [`Resolution`] is given
+pub const THROUGHPUT_BINS: usize = 200;
+
+/// Resolution of the gap distribution, in log-spaced bins per decade
+pub const GAP_BINS_PER_DECADE: u32 = 10;
+
+/// Primes found per second in each bucket of the run at `resolution`, as
+/// (bucket midpoint in seconds, rate)
+pub fn throughput_over_time(primes: &[PrimeResult], resolution: Resolution) -> Vec<(f64, f64)> {
+    timeseries::bucket(primes, resolution)
+        .iter()
+        .map(|bucket| (bucket.midpoint(), bucket.primes_per_second()))
+        .collect()
+}
+
+/// Microseconds each thread took between consecutive primes it found
+pub fn prime_gaps(primes: &[PrimeResult]) -> Vec<u64> {
+    let mut by_thread: Vec<(usize, u64)> =
+        primes.iter().map(|p| (p.thread_id, p.elapsed)).collect();
+    by_thread.sort_unstable();
+
+    by_thread
+        .windows(2)
+        .filter(|pair| pair[0].0 == pair[1].0)
+        .map(|pair| pair[1].1 - pair[0].1)
+        .collect()
+}
+
+/// Share of `gaps` in each log-spaced bin from the shortest gap to the
+/// longest, as (bin midpoint in microseconds, share). Gaps below the
+/// microsecond resolution count as one microsecond.
+pub fn gap_distribution(gaps: &[u64]) -> Vec<(f64, f64)> {
+    let bins_per_decade = f64::from(GAP_BINS_PER_DECADE);
+    let bin = |gap: u64| ((gap.max(1) as f64).log10() * bins_per_decade + 1e-9) as usize;
+
+    let (Some(first), Some(last)) = (
+        gaps.iter().map(|&g| bin(g)).min(),
+        gaps.iter().map(|&g| bin(g)).max(),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut counts = vec![0u64; last - first + 1];
+    for &gap in gaps {
+        counts[bin(gap) - first] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let midpoint = 10f64.powf(((first + i) as f64 + 0.5) / bins_per_decade);
+            (midpoint, count as f64 / gaps.len() as f64)
+        })
+        .collect()
+}
+
+/// Reads the result files and writes their report to `output`, with their
+/// throughput at `resolution`
+pub fn write_html_report(files: &[PathBuf], output: &Path, resolution: Resolution) -> Result<()> {
+    let mut runs = Vec::with_capacity(files.len());
+    for file in files {
+        let (primes, _) = read_from_parquet(&file.to_string_lossy())?;
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file.display().to_string());
+        runs.push((name, primes));
+    }
+
+    fs::write(output, render_html(&runs, resolution))?;
+    Ok(())
+}
+
+/// The report page for runs given as (name, primes found)
+pub fn render_html(runs: &[(String, Vec<PrimeResult>)], resolution: Resolution) -> String {
+    let mut throughput = Plot::new();
+    let mut gaps = Plot::new();
+
+    for (name, primes) in runs {
+        let (x, y): (Vec<f64>, Vec<f64>) =
+            throughput_over_time(primes, resolution).into_iter().unzip();
+        throughput.add_trace(Scatter::new(x, y).mode(Mode::Lines).name(name));
+
+        let (x, y): (Vec<f64>, Vec<f64>) =
+            gap_distribution(&prime_gaps(primes)).into_iter().unzip();
+        gaps.add_trace(Scatter::new(x, y).mode(Mode::LinesMarkers).name(name));
+    }
+
+    throughput.set_layout(
+        Layout::new()
+            .title(Title::with_text(format!(
+                "Throughput over time, {}",
+                resolution
+            )))
+            .x_axis(Axis::new().title("seconds since start"))
+            .y_axis(Axis::new().title("primes found per second"))
+            .height(500),
+    );
+    gaps.set_layout(
+        Layout::new()
+            .title(Title::with_text(
+                "Time between consecutive primes found by a thread",
+            ))
+            .x_axis(Axis::new().title("microseconds").type_(AxisType::Log))
+            .y_axis(Axis::new().title("share of gaps"))
+            .height(500),
+    );
+
+    let rows: String = runs
+        .iter()
+        .map(|(name, primes)| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(name),
+                NUMBERS.integer(primes.len() as u64),
+                primes
+                    .iter()
+                    .map(|p| p.number)
+                    .max()
+                    .map_or("-".to_string(), |n| n.to_string()),
+                NUMBERS.duration(Duration::from_micros(
+                    primes.iter().map(|p| p.elapsed).max().unwrap_or(0)
+                ))
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8" />
+<title>Primality test benchmark report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }}
+td:first-child {{ text-align: left; }}
+</style>
+{scripts}
+</head>
+<body>
+<h1>Primality test benchmark report</h1>
+<p>Generated {generated} by primality-test-comparison {version}. Drag over a chart to zoom, double-click to reset.</p>
+<table>
+<tr><th>Run</th><th>Primes found</th><th>Largest prime</th><th>Last prime after</th></tr>
+{rows}</table>
+{throughput}
+{gaps}
+</body>
+</html>
+"#,
+        scripts = Plot::offline_js_sources(),
+        generated = humantime::format_rfc3339_seconds(SystemTime::now()),
+        version = env!("CARGO_PKG_VERSION"),
+        throughput = throughput.to_inline_html(Some("throughput")),
+        gaps = gaps.to_inline_html(Some("gaps")),
+    )
+}
+
+// Run names come from file names, which may hold anything
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prime(elapsed: u64, thread_id: usize) -> PrimeResult {
+        PrimeResult {
+            number: 7,
+            elapsed,
+            thread_id,
+        }
+    }
+
+    #[test]
+    fn test_throughput_over_time() {
+        let primes = [prime(0, 0), prime(100, 1), prime(150, 0), prime(399, 1)];
+
+        // Slices of 100µs: [0, 100), [100, 200), [200, 300), [300, 400)
+        let throughput = throughput_over_time(&primes, Resolution::Slices(4));
+        let rates: Vec<f64> = throughput.iter().map(|t| t.1).collect();
+        assert_eq!(rates, [10_000.0, 20_000.0, 0.0, 10_000.0]);
+        assert!((throughput[0].0 - 50e-6).abs() < 1e-12);
+
+        assert!(throughput_over_time(&[], Resolution::Slices(4)).is_empty());
+
+        // Fixed buckets of 250µs whatever the span: [0, 250), [250, 500)
+        let throughput =
+            throughput_over_time(&primes, Resolution::Every(Duration::from_micros(250)));
+        let rates: Vec<f64> = throughput.iter().map(|t| t.1).collect();
+        assert_eq!(rates, [12_000.0, 4_000.0]);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let primes = [prime(5, 0), prime(10, 1), prime(15, 0), prime(1010, 1)];
+        assert_eq!(prime_gaps(&primes), [10, 1000]);
+
+        // 10µs and 1000µs are two decades apart: 21 bins, only the ends filled
+        let distribution = gap_distribution(&[10, 1000]);
+        assert_eq!(distribution.len(), 21);
+        assert_eq!(distribution[0].1, 0.5);
+        assert_eq!(distribution[20].1, 0.5);
+        assert!(distribution[1..20].iter().all(|bin| bin.1 == 0.0));
+        assert!(distribution[0].0 > 10.0 && distribution[0].0 < 10f64.powf(1.1));
+
+        assert_eq!(gap_distribution(&[0, 1]), [(10f64.powf(0.05), 1.0)]);
+        assert!(gap_distribution(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(
+            &[
+                (
+                    "miller-rabin-1s".to_string(),
+                    vec![prime(1, 0), prime(9, 0)],
+                ),
+                ("<odd name>".to_string(), Vec::new()),
+            ],
+            Resolution::Slices(THROUGHPUT_BINS),
+        );
+
+        assert!(html.contains("Plotly.newPlot(\"throughput\""));
+        assert!(html.contains("Plotly.newPlot(\"gaps\""));
+        assert!(html.contains("Throughput over time, 200 slices"));
+        assert!(html.contains("<td>miller-rabin-1s</td><td>2</td><td>7</td><td>9.0µs</td>"));
+        assert!(html.contains("<td>&lt;odd name&gt;</td><td>0</td><td>-</td>"));
+        // plotly.js itself is inlined, not fetched from a CDN
+        assert!(!html.contains("<script src="));
+    }
+}
@@ -0,0 +1,91 @@
+//! Primes of a run in progress, readable while the workers still add to them
+//!
+//! A run configured with [`live`](crate::benchmark::BenchmarkConfigBuilder::live)
+//! has each worker publish the primes it found every [`LIVE_BATCH`] primes or
+//! [`LIVE_INTERVAL`], whichever comes first, so readers see the run at most
+//! that far behind without the workers contending on every prime. Primes
+//! appear in the order the workers published them, not sorted by time.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::benchmark::result::PrimeResult;
+
+/// Primes a worker collects before publishing them
+pub const LIVE_BATCH: usize = 4096;
+
+/// Longest a worker holds on to primes before publishing them
+pub const LIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The primes published so far by the workers of one run
+#[derive(Debug, Default)]
+pub struct LiveResults {
+    primes: Mutex<Vec<PrimeResult>>,
+    finished: AtomicBool,
+}
+
+impl LiveResults {
+    pub fn new() -> Self {
+        LiveResults::default()
+    }
+
+    /// Primes published so far
+    pub fn len(&self) -> usize {
+        self.primes.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The primes published from the `from`-th on, empty if there are none
+    /// yet
+    pub fn since(&self, from: usize) -> Vec<PrimeResult> {
+        let primes = self.primes.lock().unwrap_or_else(|e| e.into_inner());
+        primes.get(from..).unwrap_or_default().to_vec()
+    }
+
+    /// True once every worker has stopped and published its last primes
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn publish(&self, primes: impl IntoIterator<Item = PrimeResult>) {
+        self.primes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(primes);
+    }
+
+    pub(crate) fn finish(&self) {
+        self.finished.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since() {
+        let live = LiveResults::new();
+        assert!(live.is_empty());
+        assert!(live.since(5).is_empty());
+
+        let prime = |number| PrimeResult {
+            number,
+            elapsed: number,
+            thread_id: 0,
+        };
+        live.publish([prime(2), prime(3)]);
+        live.publish([prime(5)]);
+        assert_eq!(live.len(), 3);
+        assert_eq!(live.since(1), [prime(3), prime(5)]);
+        assert!(live.since(3).is_empty());
+
+        assert!(!live.is_finished());
+        live.finish();
+        assert!(live.is_finished());
+    }
+}
@@ -0,0 +1,911 @@
+//! Parallel benchmark engine
+//!
+//! Runs a primality test on several threads for a fixed duration, the
+//! threads leasing chunks of 6k ± 1 candidates (or of a shuffled range, see
+//! [`candidates`]) from a shared cursor, and collects every prime found with
+//! the time (since the start of the run) it was found at.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use primality_test_comparison::algorithms::trial_division_sqrt::TrialDivisionSqrt;
+//! use primality_test_comparison::benchmark::{BenchmarkConfig, run};
+//!
+//! let config = BenchmarkConfig::builder(Arc::new(TrialDivisionSqrt))
+//!     .duration(Duration::from_millis(10))
+//!     .threads(2)
+//!     .build()?;
+//!
+//! let result = run(&config)?;
+//! assert_eq!(result.thread_counts.len(), 2);
+//! assert_eq!(result.thread_counts.iter().sum::<usize>(), result.primes_found());
+//! # Ok::<(), primality_test_comparison::Error>(())
+//! ```
+
+#[cfg(feature = "serde")]
+pub mod baseline;
+pub mod bloom;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+pub mod candidates;
+#[cfg(feature = "compact")]
+pub mod compact;
+pub mod config;
+pub mod doctor;
+pub mod environment;
+#[cfg(feature = "flight")]
+pub mod flight;
+#[cfg(feature = "html-report")]
+pub mod html_report;
+pub mod live;
+pub mod monitor;
+pub mod numa;
+pub mod ordering;
+pub mod overhead;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod prefilter;
+pub mod result;
+pub mod scheduling;
+pub mod schema;
+pub mod smt;
+pub mod tuning;
+pub mod wheel;
+
+#[cfg(feature = "serde")]
+pub use baseline::{Baseline, BaselineEntry, MetricChange};
+pub use bloom::BloomFilter;
+#[cfg(feature = "bundle")]
+pub use bundle::{BundledRun, export_bundle};
+pub use candidates::{CandidateCursor, CandidateSource, Permutation};
+#[cfg(feature = "compact")]
+pub use compact::{GapFile, GapSummary, write_gaps};
+pub use config::{BenchmarkConfig, BenchmarkConfigBuilder};
+pub use environment::Environment;
+pub use live::LiveResults;
+pub use monitor::{CpuSample, CpuSensors, IntervalThroughput, MonitorLog, ThrottleEvent};
+pub use numa::{NumaNode, NumaTopology};
+pub use ordering::Order;
+pub use overhead::HarnessOverhead;
+pub use prefilter::{Prefilter, PrefilterStats};
+pub use result::{BenchmarkResult, Completeness, CrossCheck, PrimeResult};
+pub use smt::PhysicalCores;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::{Span, debug, info, info_span, warn};
+
+use crate::algorithms::PrimalityTest;
+use crate::algorithms::sieve_of_eratosthenes::sieve_segmented;
+use crate::analysis::throughput::BitLengthTimings;
+use crate::cache;
+use crate::error::{Error, Result};
+
+// Numbers the completeness check sieves at once, few enough to stay in cache
+const COMPLETENESS_SEGMENT_SIZE: usize = 1 << 18;
+
+struct PrimeResultLocal {
+    number: u64,
+    timestamp: Instant,
+}
+
+impl PrimeResultLocal {
+    fn to_result(&self, start_time: Instant, thread_id: usize) -> PrimeResult {
+        PrimeResult {
+            number: self.number,
+            elapsed: self.timestamp.duration_since(start_time).as_micros() as u64,
+            thread_id,
+        }
+    }
+}
+
+// A worker's share of publishing to the live results: the primes it found
+// past the first `published` are handed over in batches
+struct LivePublisher {
+    live: Arc<LiveResults>,
+    published: usize,
+    last: Instant,
+    start_time: Instant,
+    thread_id: usize,
+}
+
+impl LivePublisher {
+    // Publishes the primes found since the last call that did, once there
+    // are LIVE_BATCH of them or LIVE_INTERVAL has passed, or on `force`
+    fn offer(&mut self, local_primes: &[PrimeResultLocal], force: bool) {
+        let pending = &local_primes[self.published..];
+        let due = match pending.last() {
+            Some(last) => {
+                force
+                    || pending.len() >= live::LIVE_BATCH
+                    || last.timestamp.duration_since(self.last) >= live::LIVE_INTERVAL
+            }
+            None => false,
+        };
+        if due {
+            self.live.publish(
+                pending
+                    .iter()
+                    .map(|p| p.to_result(self.start_time, self.thread_id)),
+            );
+            self.published = local_primes.len();
+            self.last = Instant::now();
+        }
+    }
+}
+
+// What a worker has done so far, published for the status reports. Each
+// worker's counters sit on their own cache line, so updating them never
+// contends with another worker.
+#[derive(Default)]
+#[repr(align(64))]
+struct WorkerProgress {
+    primes: AtomicU64,
+    candidate: AtomicU64,
+}
+
+// What a worker reports when it exits
+struct WorkerExit {
+    // Primes it found
+    count: usize,
+    // NUMA node it was bound to
+    node: Option<usize>,
+    // First position of its lease it had not tested when stopped, None if
+    // it stopped between leases
+    stopped_at: Option<u64>,
+}
+
+/// Runs the benchmark described by `config` and collects its results
+///
+/// Progress is reported as `tracing` events inside a `benchmark` span.
+pub fn run(config: &BenchmarkConfig) -> Result<BenchmarkResult> {
+    let _span = info_span!(
+        "benchmark",
+        algorithm = config.algorithm.name(),
+        threads = config.threads
+    )
+    .entered();
+
+    // Before the workers start, so reading sysfs doesn't compete with them
+    let environment = Environment::capture();
+    let harness_overhead = overhead::measure(overhead::DEFAULT_SAMPLES);
+    debug!(?harness_overhead, "harness overhead measured");
+
+    // Shared state for tracking primes across all threads
+    let running = Arc::new(AtomicBool::new(true));
+    let primes_vector = Arc::new(Mutex::new(Vec::<PrimeResult>::new()));
+    let timings = Arc::new(Mutex::new(BitLengthTimings::default()));
+    let prefilter_stats = Arc::new(Mutex::new(PrefilterStats {
+        bound: config
+            .prefilter
+            .as_ref()
+            .map_or(0, |prefilter| prefilter.bound()),
+        ..Default::default()
+    }));
+
+    // Setup timer thread
+    let running_clone = running.clone();
+    let duration = config.duration;
+    thread::spawn(move || {
+        thread::sleep(duration);
+        debug!(?duration, "duration elapsed, stopping workers");
+        running_clone.store(false, Ordering::SeqCst);
+    });
+
+    // Samples the CPU alongside the workers until they are stopped
+    let monitor = config.monitor.map(|interval| {
+        let running = running.clone();
+        thread::spawn(move || monitor_cpu(interval, &running))
+    });
+
+    // Only tracked when reported, to keep the workers' loop lean otherwise
+    let progress: Option<Arc<[WorkerProgress]>> = config.status_interval.map(|_| {
+        (0..config.threads)
+            .map(|_| WorkerProgress::default())
+            .collect()
+    });
+    let status = config
+        .status_interval
+        .zip(progress.clone())
+        .map(|(interval, progress)| {
+            let running = running.clone();
+            let span = Span::current();
+            thread::spawn(move || {
+                let _enter = span.enter();
+                report_status(interval, &running, &progress);
+            })
+        });
+
+    // Run the primality test in parallel
+    let cursor = Arc::new(CandidateCursor::new(config.candidates.clone()));
+    let started = Instant::now();
+    let handles = is_prime_in_parallel(
+        config,
+        cursor.clone(),
+        running.clone(),
+        primes_vector.clone(),
+        timings.clone(),
+        prefilter_stats.clone(),
+        progress,
+    );
+
+    let exits = handles
+        .into_iter()
+        .enumerate()
+        .map(|(i, handle)| handle.join().map_err(|_| Error::WorkerPanicked(i)))
+        .collect::<Result<Vec<WorkerExit>>>()?;
+    // Shorter than configured when every thread ran out of a shuffled range
+    let elapsed = started.elapsed().min(config.duration);
+
+    // Every position before the first one a thread stopped short of, or
+    // before the first one never leased, was tested
+    let tested = exits
+        .iter()
+        .filter_map(|exit| exit.stopped_at)
+        .fold(cursor.leased(), u64::min);
+    let covered_up_to = config.candidates.covered_up_to(tested);
+    debug!(tested, ?covered_up_to, "candidates tested");
+
+    let thread_counts: Vec<usize> = exits.iter().map(|exit| exit.count).collect();
+    let thread_nodes: Vec<Option<usize>> = exits.iter().map(|exit| exit.node).collect();
+    // Only recorded when placement was asked for
+    let thread_nodes = match config.numa {
+        Some(_) => thread_nodes,
+        None => Vec::new(),
+    };
+
+    if let Some(live) = &config.live {
+        live.finish();
+    }
+
+    // Workers have all exited, so the shared state is no longer contended
+    let mut primes = std::mem::take(&mut *primes_vector.lock().unwrap_or_else(|e| e.into_inner()));
+    // Net of the clock reads around each call, which the overhead reports
+    let mut timings = *timings.lock().unwrap_or_else(|e| e.into_inner());
+    timings.subtract_per_candidate(harness_overhead.timer_nanos);
+    let prefilter = config.prefilter.is_some().then(|| {
+        let mut stats = *prefilter_stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.subtract_per_candidate(harness_overhead.timer_nanos);
+        stats
+    });
+
+    // Order primes by timestamp
+    primes.sort_by_key(|p| p.elapsed);
+    debug!(primes = primes.len(), "benchmark finished");
+
+    let monitor = monitor.and_then(|handle| handle.join().ok());
+    if let Some(handle) = status {
+        let _ = handle.join();
+    }
+    for event in monitor.iter().flat_map(MonitorLog::throttle_events) {
+        warn!(
+            start_micros = event.start,
+            end_micros = event.end,
+            min_frequency_khz = event.min_frequency_khz,
+            max_temperature_millicelsius = event.max_temperature_millicelsius,
+            "CPU throttled"
+        );
+    }
+
+    // Outside the timed run, so the reference never slows the algorithm down
+    let cross_check = config
+        .cross_check
+        .as_ref()
+        .map(|reference| cross_check(&primes, reference.as_ref()));
+    // A shuffled range, which covers no prefix, is refused with the check
+    let completeness = match covered_up_to {
+        Some(covered_up_to) if config.verify_complete => {
+            Some(verify_complete(&primes, covered_up_to))
+        }
+        _ => None,
+    };
+
+    Ok(BenchmarkResult {
+        algorithm: config.algorithm.name().to_string(),
+        duration: elapsed,
+        primes,
+        thread_counts,
+        thread_nodes,
+        covered_up_to,
+        timings,
+        metadata: config.metadata.clone(),
+        cross_check,
+        completeness,
+        prefilter,
+        environment: Some(environment),
+        monitor,
+        harness_overhead: Some(harness_overhead),
+    })
+}
+
+// Samples the CPU sensors every `interval` until `running` is cleared,
+// dropping the last, partial interval
+fn monitor_cpu(interval: Duration, running: &AtomicBool) -> MonitorLog {
+    // Checks for the end of the run without waiting out a long interval
+    const POLL: Duration = Duration::from_millis(10);
+
+    let sensors = CpuSensors::discover();
+    debug!(available = sensors.is_available(), "CPU monitor started");
+
+    let start_time = Instant::now();
+    let mut samples = Vec::new();
+    let mut next_sample = interval;
+
+    while running.load(Ordering::SeqCst) {
+        let elapsed = start_time.elapsed();
+        if elapsed >= next_sample {
+            samples.push(sensors.sample(elapsed.as_micros() as u64));
+            next_sample += interval;
+        } else {
+            thread::sleep(POLL.min(next_sample - elapsed));
+        }
+    }
+
+    MonitorLog { interval, samples }
+}
+
+// Emits a status event every `interval` until `running` is cleared
+fn report_status(interval: Duration, running: &AtomicBool, progress: &[WorkerProgress]) {
+    // Checks for the end of the run without waiting out a long interval
+    const POLL: Duration = Duration::from_millis(10);
+
+    let start_time = Instant::now();
+    let mut next_report = interval;
+    let mut last_primes = 0;
+
+    while running.load(Ordering::SeqCst) {
+        let elapsed = start_time.elapsed();
+        if elapsed < next_report {
+            thread::sleep(POLL.min(next_report - elapsed));
+            continue;
+        }
+
+        let (primes, candidate) = progress_totals(progress);
+        info!(
+            elapsed_secs = (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            primes,
+            candidate,
+            primes_per_second = ((primes - last_primes) as f64 / interval.as_secs_f64()).round(),
+            "status"
+        );
+
+        last_primes = primes;
+        next_report += interval;
+    }
+}
+
+// Primes found by all workers and the largest candidate any of them tested
+fn progress_totals(progress: &[WorkerProgress]) -> (u64, u64) {
+    let primes = progress
+        .iter()
+        .map(|p| p.primes.load(Ordering::Relaxed))
+        .sum();
+    let candidate = progress
+        .iter()
+        .map(|p| p.candidate.load(Ordering::Relaxed))
+        .max()
+        .unwrap_or(0);
+    (primes, candidate)
+}
+
+// Primes that `reference` finds composite; numbers outside its range are trusted
+fn cross_check(primes: &[PrimeResult], reference: &dyn PrimalityTest) -> CrossCheck {
+    let range = reference.supported_range();
+    let mismatches: Vec<u64> = primes
+        .iter()
+        .map(|p| p.number)
+        .filter(|n| range.contains(n) && !reference.is_prime(*n))
+        .collect();
+
+    for number in &mismatches {
+        warn!(number, reference = reference.name(), "cross-check mismatch");
+    }
+
+    CrossCheck {
+        reference: reference.name().to_string(),
+        mismatches,
+    }
+}
+
+// Sieves the range every candidate of which was tested and matches the
+// primes found against it
+fn verify_complete(primes: &[PrimeResult], covered_up_to: u64) -> Completeness {
+    let mut found: Vec<u64> = primes
+        .iter()
+        .map(|p| p.number)
+        .filter(|&n| n <= covered_up_to)
+        .collect();
+    found.sort_unstable();
+
+    let duplicated: Vec<u64> = found
+        .chunk_by(|a, b| a == b)
+        .filter(|run| run.len() > 1)
+        .map(|run| run[0])
+        .collect();
+    found.dedup();
+
+    let missing: Vec<u64> = cache::primes_up_to(covered_up_to)
+        .unwrap_or_else(|| sieve_segmented(covered_up_to, COMPLETENESS_SEGMENT_SIZE))
+        .into_iter()
+        .filter(|p| found.binary_search(p).is_err())
+        .collect();
+
+    for number in &missing {
+        warn!(number, "prime missing from the results");
+    }
+    for number in &duplicated {
+        warn!(number, "prime found more than once");
+    }
+
+    Completeness {
+        covered_up_to,
+        missing,
+        duplicated,
+    }
+}
+
+// Moves a thread's local primes into the shared vector
+fn flush_local_primes(
+    local_primes: &mut Vec<PrimeResultLocal>,
+    primes_vector: &Mutex<Vec<PrimeResult>>,
+    start_time: Instant,
+    thread_id: usize,
+) {
+    let mut shared_primes = primes_vector.lock().unwrap_or_else(|e| e.into_inner());
+    for p in local_primes.drain(..) {
+        shared_primes.push(p.to_result(start_time, thread_id));
+    }
+}
+
+// Each worker returns the number of primes it found and the NUMA node it was
+// bound to, if any
+fn is_prime_in_parallel(
+    config: &BenchmarkConfig,
+    cursor: Arc<CandidateCursor>,
+    running: Arc<AtomicBool>,
+    primes_vector: Arc<Mutex<Vec<PrimeResult>>>,
+    timings: Arc<Mutex<BitLengthTimings>>,
+    prefilter_stats: Arc<Mutex<PrefilterStats>>,
+    progress: Option<Arc<[WorkerProgress]>>,
+) -> Vec<thread::JoinHandle<WorkerExit>> {
+    let parallelism_count = config.threads;
+
+    // Thread handles
+    let mut handles: Vec<thread::JoinHandle<WorkerExit>> = Vec::new();
+
+    // Start time for calculating elapsed time for each prime found
+    let start_time = Instant::now();
+
+    // Spawn threads
+    for i in 0..parallelism_count {
+        // Clone shared state for each thread
+        let running = running.clone();
+        let primes_vector = primes_vector.clone();
+        let timings = timings.clone();
+        let prefilter_stats = prefilter_stats.clone();
+        let primality_test = config.algorithm.clone();
+        let prefilter = config.prefilter.clone();
+        let numa = config.numa.clone();
+        let physical_cores = config.physical_cores.clone();
+        let cursor = cursor.clone();
+        let progress = progress.clone();
+        let mut publisher = config.live.clone().map(|live| LivePublisher {
+            live,
+            published: 0,
+            last: start_time,
+            start_time,
+            thread_id: i,
+        });
+        let span = Span::current();
+
+        // Each thread will have its own local vector to store primes before pushing to shared vector
+        let handle = thread::spawn(move || {
+            let _enter = span.enter();
+            debug!(thread = i, "worker started");
+
+            // Bind before allocating, so the buffers below are node-local
+            let node = numa.as_ref().and_then(|topology| {
+                let node = topology.node_for_thread(i);
+                match numa::bind_current_thread(&node.cpus) {
+                    Ok(()) => Some(node.id),
+                    Err(error) => {
+                        warn!(thread = i, node = node.id, %error, "NUMA binding failed");
+                        None
+                    }
+                }
+            });
+
+            if let Some(cores) = &physical_cores {
+                let cpu = cores.cpu_for_thread(i);
+                if let Err(error) = numa::bind_current_thread(&[cpu]) {
+                    warn!(thread = i, cpu, %error, "pinning to a physical core failed");
+                }
+            }
+
+            // Local vector for this thread to store primes before pushing to shared vector
+            let mut local_primes: Vec<PrimeResultLocal> = Vec::new();
+            // Local per-candidate timings, merged into the shared timings on exit
+            let mut local_timings = BitLengthTimings::default();
+            let mut local_prefilter_stats = PrefilterStats::default();
+
+            // Candidates only rise in the upward orders, so the first past
+            // the algorithm's range ends the run; shuffled ranges were checked
+            let last = *primality_test.supported_range().end();
+            let mut stopped_at = None;
+            'leases: while running.load(Ordering::SeqCst) {
+                let Some(lease) = cursor.lease() else {
+                    break;
+                };
+                for position in lease {
+                    // Check if we should stop
+                    if !running.load(Ordering::SeqCst) {
+                        stopped_at = Some(position);
+                        break 'leases;
+                    }
+                    let Some(candidate) = cursor.source().nth(position) else {
+                        break 'leases;
+                    };
+                    if candidate > last {
+                        stopped_at = Some(position);
+                        break 'leases;
+                    }
+
+                    // Composites with a small factor never reach the algorithm
+                    if let Some(prefilter) = &prefilter {
+                        let prefilter_start = Instant::now();
+                        let rejected = prefilter.rejects(candidate);
+                        local_prefilter_stats
+                            .record(rejected, prefilter_start.elapsed().as_nanos() as u64);
+                        if rejected {
+                            continue;
+                        }
+                    }
+
+                    let is_prime = overhead::timed_is_prime(
+                        primality_test.as_ref(),
+                        candidate,
+                        &mut local_timings,
+                    );
+
+                    // Add candidate to local primes if it is prime
+                    if is_prime {
+                        local_primes.push(PrimeResultLocal {
+                            number: candidate,
+                            timestamp: Instant::now(),
+                        });
+                        if let Some(publisher) = &mut publisher {
+                            publisher.offer(&local_primes, false);
+                        }
+                    }
+
+                    if let Some(progress) = &progress {
+                        let progress = &progress[i];
+                        progress.candidate.store(candidate, Ordering::Relaxed);
+                        progress
+                            .primes
+                            .store(local_primes.len() as u64, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            // Stopped, or out of candidates in a shuffled range
+            let count = local_primes.len();
+            debug!(thread = i, count, "worker stopped");
+            timings
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .merge(&local_timings);
+            prefilter_stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .merge(&local_prefilter_stats);
+
+            // Append local primes to shared primes vector before exiting
+            if let Some(publisher) = &mut publisher {
+                publisher.offer(&local_primes, true);
+            }
+            flush_local_primes(&mut local_primes, &primes_vector, start_time, i);
+            WorkerExit {
+                count,
+                node,
+                stopped_at,
+            }
+        });
+
+        // Store thread handle
+        handles.push(handle);
+    }
+
+    handles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::config::AlgorithmConfig;
+    use crate::algorithms::miller_rabin::{self, MillerRabin};
+
+    // Fermat test to base 2, fooled by 341 = 11 · 31 among others
+    struct Fermat2;
+
+    impl PrimalityTest for Fermat2 {
+        fn name(&self) -> &str {
+            "fermat-2"
+        }
+
+        fn description(&self) -> &str {
+            "Fermat test to base 2"
+        }
+
+        fn is_prime(&self, n: u64) -> bool {
+            n == 2 || (n > 2 && crate::math::mod_pow(2, n - 1, n) == 1)
+        }
+    }
+
+    // Miller-Rabin restricted to the numbers up to its bound
+    struct UpTo(u64);
+
+    impl PrimalityTest for UpTo {
+        fn name(&self) -> &str {
+            "up-to"
+        }
+
+        fn description(&self) -> &str {
+            "Miller-Rabin up to a bound"
+        }
+
+        fn supported_range(&self) -> std::ops::RangeInclusive<u64> {
+            0..=self.0
+        }
+
+        fn is_prime(&self, n: u64) -> bool {
+            assert!(n <= self.0, "{} is past the bound", n);
+            miller_rabin::is_prime(n)
+        }
+    }
+
+    #[test]
+    fn test_supported_range() {
+        // The run ends where the algorithm's range does, well before its time
+        let config = BenchmarkConfig::builder(Arc::new(UpTo(100_000)))
+            .duration(Duration::from_secs(60))
+            .threads(2)
+            .verify_complete(true)
+            .build()
+            .unwrap();
+
+        let result = run(&config).unwrap();
+        assert!(result.duration < Duration::from_secs(60));
+        assert_eq!(result.primes_found(), 9592);
+        assert_eq!(result.largest_prime(), Some(99991));
+        assert!(result.covered_up_to.unwrap() >= 100_000);
+        assert!(result.completeness.unwrap().is_complete());
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let config = BenchmarkConfig::builder(Arc::new(Fermat2))
+            .duration(Duration::from_millis(50))
+            .threads(1)
+            .cross_check(Arc::new(
+                MillerRabin::from_config(&AlgorithmConfig::default()).unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        let result = run(&config).unwrap();
+        let cross_check = result.cross_check.unwrap();
+        assert_eq!(cross_check.reference, "miller-rabin");
+        assert_eq!(cross_check.mismatches.first(), Some(&341));
+        assert!(
+            cross_check
+                .mismatches
+                .iter()
+                .all(|&n| !miller_rabin::is_prime(n))
+        );
+
+        // The first thread also covers 2 and 3
+        assert_eq!(result.primes[0].number, 2);
+        assert_eq!(result.primes[1].number, 3);
+    }
+
+    #[test]
+    fn test_prefilter() {
+        let config = BenchmarkConfig::builder(Arc::new(
+            MillerRabin::from_config(&AlgorithmConfig::default()).unwrap(),
+        ))
+        .duration(Duration::from_millis(50))
+        .threads(1)
+        .prefilter(100)
+        .build()
+        .unwrap();
+
+        let result = run(&config).unwrap();
+        let stats = result.prefilter.unwrap();
+        assert_eq!(stats.bound, 100);
+        assert!(stats.rejected > 0);
+
+        // Only the survivors were timed, and no prime was filtered out
+        let timed: u64 = result.timings.iter().map(|(_, t)| t.candidates).sum();
+        assert_eq!(timed, stats.candidates - stats.rejected);
+        let largest = result.largest_prime().unwrap();
+        let expected = (2..=largest).filter(|&n| miller_rabin::is_prime(n)).count();
+        assert_eq!(result.primes_found(), expected);
+    }
+
+    #[test]
+    fn test_status_interval() {
+        let progress: Vec<WorkerProgress> = [(3, 101), (5, 97)]
+            .into_iter()
+            .map(|(primes, candidate)| WorkerProgress {
+                primes: AtomicU64::new(primes),
+                candidate: AtomicU64::new(candidate),
+            })
+            .collect();
+        assert_eq!(progress_totals(&progress), (8, 101));
+        assert_eq!(progress_totals(&[]), (0, 0));
+
+        // The reporter stops with the workers
+        let config = BenchmarkConfig::builder(Arc::new(Fermat2))
+            .duration(Duration::from_millis(100))
+            .threads(2)
+            .status_interval(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        assert_eq!(config.status_interval(), Some(Duration::from_millis(20)));
+
+        let started = Instant::now();
+        let result = run(&config).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(result.thread_counts.len(), 2);
+        assert!(result.primes_found() > 0);
+    }
+
+    #[test]
+    fn test_live() {
+        let live = Arc::new(LiveResults::new());
+        let config = BenchmarkConfig::builder(Arc::new(Fermat2))
+            .duration(Duration::from_millis(50))
+            .threads(2)
+            .live(live.clone())
+            .build()
+            .unwrap();
+
+        let result = run(&config).unwrap();
+        assert!(live.is_finished());
+
+        // Every prime was published once, in whatever order
+        let mut published = live.since(0);
+        published.sort_by_key(|p| (p.elapsed, p.thread_id, p.number));
+        let mut primes = result.primes.clone();
+        primes.sort_by_key(|p| (p.elapsed, p.thread_id, p.number));
+        assert_eq!(published, primes);
+    }
+
+    #[test]
+    fn test_verify_complete() {
+        let config = BenchmarkConfig::builder(Arc::new(
+            MillerRabin::from_config(&AlgorithmConfig::default()).unwrap(),
+        ))
+        .duration(Duration::from_millis(50))
+        .threads(3)
+        .verify_complete(true)
+        .build()
+        .unwrap();
+
+        let result = run(&config).unwrap();
+        let completeness = result.completeness.unwrap();
+        assert!(completeness.covered_up_to > 1_000);
+        assert_eq!(result.covered_up_to, Some(completeness.covered_up_to));
+        assert!(completeness.is_complete(), "{:?}", completeness);
+
+        // Covered up to 13, so 17 is not expected; 7 was found twice
+        let found = |number, thread_id| PrimeResult {
+            number,
+            elapsed: 0,
+            thread_id,
+        };
+        let primes = [
+            found(2, 0),
+            found(3, 0),
+            found(5, 0),
+            found(7, 0),
+            found(7, 1),
+            found(11, 1),
+            found(13, 1),
+            found(19, 0),
+            found(23, 0),
+        ];
+        let completeness = verify_complete(&primes, 13);
+        assert_eq!(completeness.covered_up_to, 13);
+        assert!(completeness.missing.is_empty());
+        assert_eq!(completeness.duplicated, [7]);
+
+        let completeness = verify_complete(&[found(2, 0), found(3, 0), found(7, 0)], 7);
+        assert_eq!(completeness.missing, [5]);
+        assert!(!completeness.is_complete());
+
+        // Nothing covered, nothing expected
+        assert!(verify_complete(&primes, 1).is_complete());
+    }
+
+    #[test]
+    fn test_monitor() {
+        let config = BenchmarkConfig::builder(Arc::new(
+            MillerRabin::from_config(&AlgorithmConfig::default()).unwrap(),
+        ))
+        .duration(Duration::from_millis(100))
+        .threads(1)
+        .monitor(Duration::from_millis(20))
+        .build()
+        .unwrap();
+
+        let result = run(&config).unwrap();
+        let monitor = result.monitor.unwrap();
+        assert_eq!(monitor.interval, Duration::from_millis(20));
+        assert!(!monitor.samples.is_empty() && monitor.samples.len() <= 5);
+        assert!(
+            monitor
+                .samples
+                .windows(2)
+                .all(|w| w[0].elapsed < w[1].elapsed)
+        );
+
+        let throughput = monitor.throughput(&result.primes);
+        assert_eq!(throughput.len(), monitor.samples.len());
+
+        assert!(
+            BenchmarkConfig::builder(Arc::new(Fermat2))
+                .monitor(Duration::ZERO)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_numa() {
+        let Some(topology) = NumaTopology::discover() else {
+            return;
+        };
+
+        let config = BenchmarkConfig::builder(Arc::new(Fermat2))
+            .duration(Duration::from_millis(20))
+            .threads(3)
+            .numa(true)
+            .build()
+            .unwrap();
+
+        let result = run(&config).unwrap();
+        let expected: Vec<Option<usize>> = (0..3)
+            .map(|t| Some(topology.node_for_thread(t).id))
+            .collect();
+        assert_eq!(result.thread_nodes, expected);
+    }
+
+    #[test]
+    fn test_physical_cores_only() {
+        let Some(cores) = PhysicalCores::discover() else {
+            return;
+        };
+
+        let config = BenchmarkConfig::builder(Arc::new(Fermat2))
+            .duration(Duration::from_millis(20))
+            .physical_cores_only(true)
+            .build()
+            .unwrap();
+        assert_eq!(config.threads(), cores.len());
+        assert_eq!(run(&config).unwrap().thread_counts.len(), cores.len());
+
+        // Both would pin the same threads
+        assert!(
+            BenchmarkConfig::builder(Arc::new(Fermat2))
+                .numa(true)
+                .physical_cores_only(true)
+                .build()
+                .is_err()
+        );
+    }
+}
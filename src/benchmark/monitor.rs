@@ -0,0 +1,353 @@
+//! CPU frequency and temperature sampling during a run
+//!
+//! A sudden drop in throughput halfway through a run is as likely to be
+//! thermal throttling as anything the algorithm does. The monitor samples
+//! the Linux cpufreq, thermal zone, and thermal throttle counters in sysfs,
+//! so such drops can be attributed. On other systems, or in containers that
+//! hide sysfs, the samples carry only their timestamps.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::benchmark::result::PrimeResult;
+
+/// A sample counts as throttled when the frequency falls below this share of
+/// the highest frequency sampled during the run
+pub const THROTTLE_FREQUENCY_RATIO: f64 = 0.85;
+
+/// The sysfs files read for each sample, found once before the run
+#[derive(Debug, Clone, Default)]
+pub struct CpuSensors {
+    frequencies: Vec<PathBuf>,
+    temperatures: Vec<PathBuf>,
+    throttle_counts: Vec<PathBuf>,
+}
+
+impl CpuSensors {
+    /// Finds the sensors of every CPU and thermal zone, if any
+    pub fn discover() -> CpuSensors {
+        let cpus = numbered_dirs(Path::new("/sys/devices/system/cpu"), "cpu");
+        let zones = numbered_dirs(Path::new("/sys/class/thermal"), "thermal_zone");
+
+        let existing = |dirs: &[PathBuf], file: &str| -> Vec<PathBuf> {
+            dirs.iter()
+                .map(|dir| dir.join(file))
+                .filter(|path| path.exists())
+                .collect()
+        };
+
+        CpuSensors {
+            frequencies: existing(&cpus, "cpufreq/scaling_cur_freq"),
+            temperatures: existing(&zones, "temp"),
+            throttle_counts: existing(&cpus, "thermal_throttle/core_throttle_count"),
+        }
+    }
+
+    /// True if any frequency, temperature, or throttle sensor was found
+    pub fn is_available(&self) -> bool {
+        !(self.frequencies.is_empty()
+            && self.temperatures.is_empty()
+            && self.throttle_counts.is_empty())
+    }
+
+    /// Reads every sensor, `elapsed` microseconds into the run
+    pub fn sample(&self, elapsed: u64) -> CpuSample {
+        let read = |paths: &[PathBuf]| -> Vec<i64> {
+            paths
+                .iter()
+                .filter_map(|path| fs::read_to_string(path).ok())
+                .filter_map(|value| value.trim().parse().ok())
+                .collect()
+        };
+
+        let frequencies = read(&self.frequencies);
+        let temperatures = read(&self.temperatures);
+        let throttle_counts = read(&self.throttle_counts);
+
+        CpuSample {
+            elapsed,
+            frequency_khz: (!frequencies.is_empty())
+                .then(|| frequencies.iter().sum::<i64>() as u64 / frequencies.len() as u64),
+            temperature_millicelsius: temperatures.into_iter().max(),
+            throttle_count: (!throttle_counts.is_empty())
+                .then(|| throttle_counts.iter().sum::<i64>() as u64),
+        }
+    }
+}
+
+// Subdirectories of `dir` named `prefix` followed by a number, e.g. cpu0
+pub(crate) fn numbered_dirs(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix))
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// One reading of the CPU sensors
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuSample {
+    /// Microseconds since the start of the run
+    pub elapsed: u64,
+    /// Mean current frequency across CPUs
+    pub frequency_khz: Option<u64>,
+    /// Hottest thermal zone
+    pub temperature_millicelsius: Option<i64>,
+    /// Thermal throttle events counted by the CPUs so far (Intel only)
+    pub throttle_count: Option<u64>,
+}
+
+/// A stretch of consecutive throttled samples
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThrottleEvent {
+    /// Microseconds since the start of the run, from the last sample before
+    /// the throttling to the last throttled sample
+    pub start: u64,
+    pub end: u64,
+    pub min_frequency_khz: Option<u64>,
+    pub max_temperature_millicelsius: Option<i64>,
+}
+
+/// Primes found between two consecutive samples
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntervalThroughput {
+    /// Microseconds since the start of the run
+    pub start: u64,
+    pub end: u64,
+    pub primes: u64,
+    pub primes_per_second: f64,
+    /// True if the sample closing the interval was throttled
+    pub throttled: bool,
+}
+
+/// The samples taken during a run
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorLog {
+    pub interval: Duration,
+    pub samples: Vec<CpuSample>,
+}
+
+impl MonitorLog {
+    pub fn peak_frequency_khz(&self) -> Option<u64> {
+        self.samples.iter().filter_map(|s| s.frequency_khz).max()
+    }
+
+    pub fn min_frequency_khz(&self) -> Option<u64> {
+        self.samples.iter().filter_map(|s| s.frequency_khz).min()
+    }
+
+    pub fn max_temperature_millicelsius(&self) -> Option<i64> {
+        self.samples
+            .iter()
+            .filter_map(|s| s.temperature_millicelsius)
+            .max()
+    }
+
+    /// Whether each sample was throttled: the CPUs counted a new throttle
+    /// event since the previous sample, or the frequency fell below
+    /// [`THROTTLE_FREQUENCY_RATIO`] of the peak
+    pub fn throttled(&self) -> Vec<bool> {
+        let threshold = self
+            .peak_frequency_khz()
+            .map(|peak| peak as f64 * THROTTLE_FREQUENCY_RATIO);
+
+        let mut previous_count = None;
+        self.samples
+            .iter()
+            .map(|sample| {
+                let counted = matches!(
+                    (previous_count, sample.throttle_count),
+                    (Some(previous), Some(current)) if current > previous
+                );
+                previous_count = sample.throttle_count.or(previous_count);
+
+                let slowed = matches!(
+                    (sample.frequency_khz, threshold),
+                    (Some(frequency), Some(threshold)) if (frequency as f64) < threshold
+                );
+
+                counted || slowed
+            })
+            .collect()
+    }
+
+    /// Consecutive throttled samples merged into events
+    pub fn throttle_events(&self) -> Vec<ThrottleEvent> {
+        let mut events: Vec<ThrottleEvent> = Vec::new();
+        let mut in_event = false;
+
+        for (i, (sample, throttled)) in self.samples.iter().zip(self.throttled()).enumerate() {
+            if !throttled {
+                in_event = false;
+                continue;
+            }
+
+            match events.last_mut() {
+                Some(event) if in_event => {
+                    event.end = sample.elapsed;
+                    event.min_frequency_khz =
+                        min_option(event.min_frequency_khz, sample.frequency_khz);
+                    event.max_temperature_millicelsius = event
+                        .max_temperature_millicelsius
+                        .max(sample.temperature_millicelsius);
+                }
+                _ => events.push(ThrottleEvent {
+                    start: i.checked_sub(1).map_or(0, |j| self.samples[j].elapsed),
+                    end: sample.elapsed,
+                    min_frequency_khz: sample.frequency_khz,
+                    max_temperature_millicelsius: sample.temperature_millicelsius,
+                }),
+            }
+            in_event = true;
+        }
+
+        events
+    }
+
+    /// The primes found between consecutive samples (the first interval
+    /// starting at 0), each flagged if its closing sample was throttled.
+    /// `primes` must be ordered by elapsed time.
+    pub fn throughput(&self, primes: &[PrimeResult]) -> Vec<IntervalThroughput> {
+        let mut start = 0;
+        let mut remaining = primes;
+
+        self.samples
+            .iter()
+            .zip(self.throttled())
+            .map(|(sample, throttled)| {
+                let count = remaining
+                    .iter()
+                    .take_while(|p| p.elapsed < sample.elapsed)
+                    .count();
+                remaining = &remaining[count..];
+
+                let seconds = (sample.elapsed - start) as f64 / 1e6;
+                let interval = IntervalThroughput {
+                    start,
+                    end: sample.elapsed,
+                    primes: count as u64,
+                    primes_per_second: if seconds > 0.0 {
+                        count as f64 / seconds
+                    } else {
+                        0.0
+                    },
+                    throttled,
+                };
+                start = sample.elapsed;
+                interval
+            })
+            .collect()
+    }
+}
+
+fn min_option(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(elapsed: u64, frequency_khz: u64, temperature: i64) -> CpuSample {
+        CpuSample {
+            elapsed,
+            frequency_khz: Some(frequency_khz),
+            temperature_millicelsius: Some(temperature),
+            throttle_count: None,
+        }
+    }
+
+    fn log() -> MonitorLog {
+        MonitorLog {
+            interval: Duration::from_secs(1),
+            samples: vec![
+                sample(1_000_000, 4_000_000, 70_000),
+                sample(2_000_000, 3_000_000, 95_000),
+                sample(3_000_000, 2_800_000, 97_000),
+                sample(4_000_000, 3_900_000, 80_000),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_throttle_events() {
+        let log = log();
+        assert_eq!(log.throttled(), [false, true, true, false]);
+        assert_eq!(
+            log.throttle_events(),
+            [ThrottleEvent {
+                start: 1_000_000,
+                end: 3_000_000,
+                min_frequency_khz: Some(2_800_000),
+                max_temperature_millicelsius: Some(97_000),
+            }]
+        );
+
+        // A new count is throttling even at full speed
+        let mut log = log;
+        for (i, count) in [5, 5, 5, 6].into_iter().enumerate() {
+            log.samples[i].throttle_count = Some(count);
+        }
+        assert_eq!(log.throttled(), [false, true, true, true]);
+        assert_eq!(log.throttle_events().len(), 1);
+
+        // Samples without sensors are never throttled
+        let bare = MonitorLog {
+            interval: Duration::from_secs(1),
+            samples: vec![CpuSample {
+                elapsed: 1_000_000,
+                frequency_khz: None,
+                temperature_millicelsius: None,
+                throttle_count: None,
+            }],
+        };
+        assert!(bare.throttle_events().is_empty());
+    }
+
+    #[test]
+    fn test_throughput() {
+        let primes: Vec<PrimeResult> = [10, 500_000, 1_500_000, 3_500_000]
+            .into_iter()
+            .map(|elapsed| PrimeResult {
+                number: 5,
+                elapsed,
+                thread_id: 0,
+            })
+            .collect();
+
+        let throughput = log().throughput(&primes);
+        let counts: Vec<u64> = throughput.iter().map(|t| t.primes).collect();
+        assert_eq!(counts, [2, 1, 0, 1]);
+        assert_eq!(throughput[1].start, 1_000_000);
+        assert!(throughput[1].throttled);
+        assert!((throughput[0].primes_per_second - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sample_timestamps() {
+        // Whatever sysfs exposes here, a sample always carries its timestamp
+        let sensors = CpuSensors::discover();
+        assert_eq!(sensors.sample(42).elapsed, 42);
+        assert!(!CpuSensors::default().is_available());
+    }
+}
@@ -0,0 +1,190 @@
+//! NUMA-aware placement of benchmark threads
+//!
+//! On multi-socket machines a thread scheduled away from the memory it
+//! allocated pays for every access across the interconnect, which shows up
+//! as noise in the timings. With placement enabled, worker threads are
+//! spread round-robin over the NUMA nodes and bound to their node's CPUs
+//! before allocating anything, so Linux's first-touch policy keeps their
+//! buffers in node-local memory.
+
+use std::fs;
+use std::path::Path;
+
+/// A NUMA node and the CPUs it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+}
+
+/// The NUMA nodes with at least one CPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaTopology {
+    pub nodes: Vec<NumaNode>,
+}
+
+impl NumaTopology {
+    /// Reads the topology from sysfs; None where it is unavailable
+    pub fn discover() -> Option<NumaTopology> {
+        let entries = fs::read_dir(Path::new("/sys/devices/system/node")).ok()?;
+
+        let mut nodes: Vec<NumaNode> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let id = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("node")?
+                    .parse()
+                    .ok()?;
+                let cpulist = fs::read_to_string(entry.path().join("cpulist")).ok()?;
+                Some(NumaNode {
+                    id,
+                    cpus: parse_cpu_list(&cpulist)?,
+                })
+            })
+            .filter(|node| !node.cpus.is_empty())
+            .collect();
+        nodes.sort_by_key(|node| node.id);
+
+        (!nodes.is_empty()).then_some(NumaTopology { nodes })
+    }
+
+    /// The node thread `thread` runs on, spreading threads evenly
+    pub fn node_for_thread(&self, thread: usize) -> &NumaNode {
+        &self.nodes[thread % self.nodes.len()]
+    }
+}
+
+/// Parses a kernel CPU list such as "0-3,8,10-11"
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+    if list.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut cpus = Vec::new();
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let (first, last): (usize, usize) = (first.parse().ok()?, last.parse().ok()?);
+                if first > last {
+                    return None;
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+
+    Some(cpus)
+}
+
+/// Restricts the calling thread to `cpus`
+#[cfg(target_os = "linux")]
+pub fn bind_current_thread(cpus: &[usize]) -> std::io::Result<()> {
+    // SAFETY: cpu_set_t is plain data, valid when zeroed, and CPU_SET only
+    // writes within it for CPU numbers below CPU_SETSIZE
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            if cpu < libc::CPU_SETSIZE as usize {
+                libc::CPU_SET(cpu, &mut set);
+            }
+        }
+
+        // pid 0 is the calling thread
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// The CPUs the calling thread may run on
+#[cfg(target_os = "linux")]
+pub fn current_affinity() -> Option<Vec<usize>> {
+    // SAFETY: `set` is a valid cpu_set_t for sched_getaffinity to write, and
+    // CPU_ISSET only reads within it for CPU numbers below CPU_SETSIZE
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect(),
+        )
+    }
+}
+
+/// Restricts the calling thread to `cpus`
+#[cfg(not(target_os = "linux"))]
+pub fn bind_current_thread(_cpus: &[usize]) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "thread affinity is only supported on Linux",
+    ))
+}
+
+/// The CPUs the calling thread may run on
+#[cfg(not(target_os = "linux"))]
+pub fn current_affinity() -> Option<Vec<usize>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a-b"), None);
+    }
+
+    #[test]
+    fn test_node_for_thread() {
+        let topology = NumaTopology {
+            nodes: vec![
+                NumaNode {
+                    id: 0,
+                    cpus: vec![0, 1],
+                },
+                NumaNode {
+                    id: 1,
+                    cpus: vec![2, 3],
+                },
+            ],
+        };
+
+        let nodes: Vec<usize> = (0..5).map(|t| topology.node_for_thread(t).id).collect();
+        assert_eq!(nodes, [0, 1, 0, 1, 0]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_bind_current_thread() {
+        // Binding to the node's own CPUs always succeeds where sysfs is readable
+        if let Some(topology) = NumaTopology::discover() {
+            let cpus = topology.node_for_thread(0).cpus.clone();
+            std::thread::spawn(move || {
+                bind_current_thread(&cpus).unwrap();
+                let affinity = current_affinity().unwrap();
+                assert!(!affinity.is_empty());
+                assert!(affinity.iter().all(|cpu| cpus.contains(cpu)));
+            })
+            .join()
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Cost of the benchmark harness itself
+//!
+//! Every candidate is timed between two clock reads, and part of reading the
+//! clock lands inside the interval. For an algorithm that takes a few
+//! nanoseconds (trial division on tiny numbers) that is most of what gets
+//! measured. Before the workers start, [`measure`] runs candidates through
+//! the same timed call with an algorithm that does nothing; the run's
+//! timings have what it reads subtracted, and the overhead is reported
+//! next to them.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use crate::algorithms::PrimalityTest;
+use crate::analysis::throughput::BitLengthTimings;
+
+/// Candidates [`measure`] times by default
+pub const DEFAULT_SAMPLES: u64 = 100_000;
+
+/// What the harness costs per candidate, measured with an empty algorithm
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HarnessOverhead {
+    /// Time an empty algorithm is timed at, subtracted from every
+    /// candidate's timing
+    pub timer_nanos: f64,
+    /// Time per candidate outside any algorithm: the clock reads and the
+    /// bookkeeping around them
+    pub per_candidate_nanos: f64,
+}
+
+/// Tests `candidate` with `test` between two clock reads and records the
+/// time taken in `timings`. `black_box` keeps the call and its result from
+/// being optimized around the clock reads.
+#[inline]
+pub fn timed_is_prime(
+    test: &dyn PrimalityTest,
+    candidate: u64,
+    timings: &mut BitLengthTimings,
+) -> bool {
+    let start = Instant::now();
+    let is_prime = black_box(test.is_prime(black_box(candidate)));
+    timings.record(candidate, start.elapsed().as_nanos() as u64);
+    is_prime
+}
+
+// Answers without looking at the number, leaving only the cost of the call
+struct Empty;
+
+impl PrimalityTest for Empty {
+    fn name(&self) -> &str {
+        "empty"
+    }
+
+    fn description(&self) -> &str {
+        "Does nothing, to time the harness"
+    }
+
+    fn is_prime(&self, _n: u64) -> bool {
+        false
+    }
+}
+
+/// Times `samples` candidates through [`timed_is_prime`] with an algorithm
+/// that does nothing, after a warm-up of a tenth as many
+pub fn measure(samples: u64) -> HarnessOverhead {
+    let samples = samples.max(1);
+    let candidates = |count| (5..).step_by(6).take(count as usize);
+
+    let mut timings = BitLengthTimings::default();
+    for candidate in candidates(samples / 10) {
+        timed_is_prime(&Empty, candidate, &mut timings);
+    }
+
+    let mut timings = BitLengthTimings::default();
+    let start = Instant::now();
+    for candidate in candidates(samples) {
+        timed_is_prime(&Empty, candidate, &mut timings);
+    }
+    let elapsed = start.elapsed();
+
+    let timed: u64 = timings.iter().map(|(_, timing)| timing.total_nanos).sum();
+    HarnessOverhead {
+        timer_nanos: timed as f64 / samples as f64,
+        per_candidate_nanos: elapsed.as_nanos() as f64 / samples as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let overhead = measure(1000);
+        assert!(overhead.timer_nanos > 0.0);
+        // The timed part is within the whole loop
+        assert!(overhead.timer_nanos <= overhead.per_candidate_nanos);
+    }
+}
@@ -0,0 +1,195 @@
+//! Parquet output for benchmark results
+
+use arrow::array::{Array, ArrayRef, UInt64Array, UInt64Builder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::benchmark::result::PrimeResult;
+use crate::benchmark::schema::{self, SCHEMA_VERSION, SCHEMA_VERSION_KEY};
+use crate::error::{Error, Result};
+
+/// File-level key-value metadata, in the order it was written
+pub type Metadata = Vec<(String, String)>;
+
+/// Writes `primes` as an (elapsed, thread, number) table to `filename`,
+/// storing the schema version and each `metadata` pair as file-level
+/// key-value metadata.
+pub fn write_to_parquet(
+    filename: &str,
+    primes: &[PrimeResult],
+    metadata: &[(String, String)],
+) -> Result<()> {
+    let batch = record_batch(primes)?;
+
+    // Write to parquet file
+    let file = File::create(filename)?;
+    // A version carried over from a file read earlier no longer applies
+    let metadata: Vec<KeyValue> = [(SCHEMA_VERSION_KEY, SCHEMA_VERSION.to_string())]
+        .into_iter()
+        .map(|(k, v)| KeyValue::new(k.to_string(), v))
+        .chain(
+            metadata
+                .iter()
+                .filter(|(k, _)| k != SCHEMA_VERSION_KEY)
+                .map(|(k, v)| KeyValue::new(k.clone(), v.clone())),
+        )
+        .collect();
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(metadata))
+        .build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+/// The (elapsed, thread, number) columns results are stored in
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("elapsed", DataType::UInt64, false),
+        Field::new("thread", DataType::UInt64, false),
+        Field::new("number", DataType::UInt64, false),
+    ]))
+}
+
+/// `primes` as a single batch with the columns of [`schema`]
+pub fn record_batch(primes: &[PrimeResult]) -> Result<RecordBatch> {
+    let mut elapsed_builder = UInt64Builder::new();
+    let mut thread_builder = UInt64Builder::new();
+    let mut number_builder = UInt64Builder::new();
+
+    for prime in primes {
+        elapsed_builder.append_value(prime.elapsed);
+        thread_builder.append_value(prime.thread_id as u64);
+        number_builder.append_value(prime.number);
+    }
+
+    Ok(RecordBatch::try_new(
+        schema(),
+        vec![
+            Arc::new(elapsed_builder.finish()) as ArrayRef,
+            Arc::new(thread_builder.finish()) as ArrayRef,
+            Arc::new(number_builder.finish()) as ArrayRef,
+        ],
+    )?)
+}
+
+/// Reads a file written by [`write_to_parquet`], by this or an earlier
+/// build, back into its primes and key-value metadata
+pub fn read_from_parquet(filename: &str) -> Result<(Vec<PrimeResult>, Metadata)> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(filename)?)?;
+    let metadata: Metadata = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .map(|pairs| {
+            // The arrow writer embeds its own schema alongside ours
+            pairs
+                .iter()
+                .filter(|kv| kv.key != "ARROW:schema")
+                .map(|kv| (kv.key.clone(), kv.value.clone().unwrap_or_default()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Every version so far has the same columns
+    if let Some((_, version)) = metadata.iter().find(|(k, _)| k == SCHEMA_VERSION_KEY)
+        && schema::readable(version).is_none()
+    {
+        return Err(Error::UnsupportedSchema {
+            file: filename.to_string(),
+            version: version.clone(),
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    let mut primes = Vec::new();
+    for batch in builder.build()? {
+        let batch = batch?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|column| column.as_any().downcast_ref::<UInt64Array>())
+                .ok_or_else(|| ArrowError::SchemaError(format!("missing u64 column '{}'", name)))
+        };
+        let (elapsed, thread, number) = (column("elapsed")?, column("thread")?, column("number")?);
+
+        for i in 0..batch.num_rows() {
+            primes.push(PrimeResult {
+                number: number.value(i),
+                elapsed: elapsed.value(i),
+                thread_id: thread.value(i) as usize,
+            });
+        }
+    }
+
+    Ok((primes, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("ptc-parquet-{}.parquet", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        let primes = vec![
+            PrimeResult {
+                number: 2,
+                elapsed: 1,
+                thread_id: 0,
+            },
+            PrimeResult {
+                number: 1009,
+                elapsed: 40,
+                thread_id: 3,
+            },
+        ];
+        let metadata = vec![("note".to_string(), "laptop run".to_string())];
+
+        write_to_parquet(filename, &primes, &metadata).unwrap();
+        let (read, read_metadata) = read_from_parquet(filename).unwrap();
+        assert_eq!(read, primes);
+        assert_eq!(
+            read_metadata,
+            [
+                (SCHEMA_VERSION_KEY.to_string(), SCHEMA_VERSION.to_string()),
+                metadata[0].clone()
+            ]
+        );
+
+        // Files from a newer build are refused, not misread
+        let file = File::create(&path).unwrap();
+        let properties = WriterProperties::builder()
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                SCHEMA_VERSION_KEY.to_string(),
+                "99".to_string(),
+            )]))
+            .build();
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "number",
+            DataType::UInt64,
+            false,
+        )]));
+        ArrowWriter::try_new(file, schema, Some(properties))
+            .unwrap()
+            .close()
+            .unwrap();
+        assert!(matches!(
+            read_from_parquet(filename),
+            Err(Error::UnsupportedSchema { .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
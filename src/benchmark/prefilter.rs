@@ -0,0 +1,131 @@
+//! Small-prime prefilter run on candidates before the algorithm under test
+
+use crate::algorithms::{sieve_of_eratosthenes, small_primes};
+use crate::cache;
+
+/// Largest bound accepted by [`Prefilter::new`], to keep the prime table small
+pub const MAX_PREFILTER_BOUND: u64 = 1_000_000;
+
+/// Rejects candidates with a prime factor of at most `bound`, so only the
+/// survivors reach the algorithm under test. 2 and 3 are left out: the
+/// benchmark only generates 6k ± 1 candidates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefilter {
+    bound: u64,
+    primes: Vec<u64>,
+}
+
+impl Prefilter {
+    /// Prefilter by the primes from 5 up to `bound`, which must not exceed
+    /// [`MAX_PREFILTER_BOUND`]
+    pub fn new(bound: u64) -> Prefilter {
+        assert!(bound <= MAX_PREFILTER_BOUND, "prefilter bound too large");
+
+        let primes = if bound < small_primes::LIMIT {
+            small_primes::up_to(bound).to_vec()
+        } else {
+            cache::primes_up_to(bound).unwrap_or_else(|| sieve_of_eratosthenes::sieve(bound))
+        };
+        let primes = primes.into_iter().filter(|&p| p >= 5).collect();
+
+        Prefilter { bound, primes }
+    }
+
+    pub fn bound(&self) -> u64 {
+        self.bound
+    }
+
+    /// True if `n` has a prime factor p ≤ bound with p < n, i.e. is composite
+    #[inline]
+    pub fn rejects(&self, n: u64) -> bool {
+        self.primes
+            .iter()
+            .take_while(|&&p| p < n)
+            .any(|&p| n.is_multiple_of(p))
+    }
+}
+
+/// What the prefilter did during a run
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefilterStats {
+    /// Largest prime divisor tried
+    pub bound: u64,
+    /// Candidates seen by the prefilter
+    pub candidates: u64,
+    /// Candidates it rejected, never reaching the algorithm under test
+    pub rejected: u64,
+    /// Time spent in the prefilter across all threads
+    pub total_nanos: u64,
+}
+
+impl PrefilterStats {
+    #[inline]
+    pub fn record(&mut self, rejected: bool, nanos: u64) {
+        self.candidates += 1;
+        self.rejected += rejected as u64;
+        self.total_nanos += nanos;
+    }
+
+    pub fn merge(&mut self, other: &PrefilterStats) {
+        self.candidates += other.candidates;
+        self.rejected += other.rejected;
+        self.total_nanos += other.total_nanos;
+    }
+
+    /// Takes `nanos` per candidate off the total, stopping at zero
+    pub fn subtract_per_candidate(&mut self, nanos: f64) {
+        let overhead = (self.candidates as f64 * nanos).round() as u64;
+        self.total_nanos = self.total_nanos.saturating_sub(overhead);
+    }
+
+    /// Share of the candidates rejected, in [0, 1]
+    pub fn rejected_fraction(&self) -> f64 {
+        self.rejected as f64 / self.candidates as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects() {
+        let prefilter = Prefilter::new(30);
+        assert_eq!(prefilter.bound(), 30);
+
+        // Primes pass, including the ones used as divisors
+        for p in [5, 7, 29, 31, 37, 1009] {
+            assert!(!prefilter.rejects(p), "{}", p);
+        }
+
+        assert!(prefilter.rejects(25));
+        assert!(prefilter.rejects(29 * 31));
+        // No factor up to 30, left for the algorithm under test
+        assert!(!prefilter.rejects(31 * 37));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut stats = PrefilterStats {
+            bound: 30,
+            ..Default::default()
+        };
+        stats.record(true, 10);
+        stats.record(false, 20);
+
+        let mut other = PrefilterStats::default();
+        other.record(true, 5);
+        stats.merge(&other);
+
+        assert_eq!(stats.candidates, 3);
+        assert_eq!(stats.rejected, 2);
+        assert_eq!(stats.total_nanos, 35);
+        assert!((stats.rejected_fraction() - 2.0 / 3.0).abs() < 1e-9);
+
+        stats.subtract_per_candidate(10.0);
+        assert_eq!(stats.total_nanos, 5);
+        stats.subtract_per_candidate(10.0);
+        assert_eq!(stats.total_nanos, 0);
+    }
+}
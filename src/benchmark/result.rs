@@ -0,0 +1,484 @@
+//! Structured benchmark results
+
+use std::time::Duration;
+
+use crate::analysis::sequence::PrimeSequence;
+use crate::analysis::throughput::{self, BitLengthTimings, DecadeThroughput};
+use crate::benchmark::bloom::BloomFilter;
+#[cfg(feature = "compact")]
+use crate::benchmark::compact::{GapSummary, write_gaps};
+use crate::benchmark::environment::Environment;
+use crate::benchmark::monitor::MonitorLog;
+use crate::benchmark::overhead::HarnessOverhead;
+#[cfg(feature = "parquet")]
+use crate::benchmark::parquet::write_to_parquet;
+use crate::benchmark::prefilter::PrefilterStats;
+use crate::benchmark::schema::COVERED_KEY;
+use crate::error::Result;
+
+/// A prime found during a benchmark run
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimeResult {
+    pub number: u64,
+    pub elapsed: u64, // Elapsed time in microseconds since benchmark start
+    pub thread_id: usize,
+}
+
+/// Outcome of re-verifying a run's primes with a reference algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossCheck {
+    /// Name of the reference algorithm
+    pub reference: String,
+    /// Primes the reference found composite, in the order they were found
+    pub mismatches: Vec<u64>,
+}
+
+/// Outcome of comparing a run's primes with a sieve of the range it covered
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Completeness {
+    /// Every thread tested its candidates up to here
+    pub covered_up_to: u64,
+    /// Primes up to `covered_up_to` absent from the results
+    pub missing: Vec<u64>,
+    /// Primes up to `covered_up_to` found more than once
+    pub duplicated: Vec<u64>,
+}
+
+impl Completeness {
+    /// True if every prime in the covered range was found exactly once
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.duplicated.is_empty()
+    }
+}
+
+/// Everything collected by a benchmark run
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkResult {
+    /// Name of the algorithm under test
+    pub algorithm: String,
+    /// How long the run went on: the requested duration, or less if it ran
+    /// out of candidates
+    pub duration: Duration,
+    /// Primes found by all threads, ordered by elapsed time
+    pub primes: Vec<PrimeResult>,
+    /// Number of primes found by each thread, indexed by thread id
+    pub thread_counts: Vec<usize>,
+    /// NUMA node each thread was bound to, indexed by thread id; empty unless
+    /// NUMA placement was configured, None where binding failed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub thread_nodes: Vec<Option<usize>>,
+    /// Every candidate up to here was tested, so no prime up to here is
+    /// missing; None for a shuffled range, and for results saved before it
+    /// was recorded
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub covered_up_to: Option<u64>,
+    /// Time spent per candidate, bucketed by bit length
+    pub timings: BitLengthTimings,
+    /// Key-value pairs attached through the configuration
+    pub metadata: Vec<(String, String)>,
+    /// Result of the cross-check, if one was configured
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cross_check: Option<CrossCheck>,
+    /// Result of the completeness check, if one was configured
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub completeness: Option<Completeness>,
+    /// What the prefilter rejected and the time it took, if one was configured.
+    /// `timings` then only covers the candidates that reached the algorithm.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub prefilter: Option<PrefilterStats>,
+    /// Machine and toolchain the run used; captured by every run, but absent
+    /// from results saved before it was recorded
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub environment: Option<Environment>,
+    /// CPU samples taken during the run, if monitoring was configured
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub monitor: Option<MonitorLog>,
+    /// What the harness costs per candidate, already subtracted from
+    /// `timings`; absent from results saved before it was measured
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub harness_overhead: Option<HarnessOverhead>,
+}
+
+impl BenchmarkResult {
+    pub fn primes_found(&self) -> usize {
+        self.primes.len()
+    }
+
+    pub fn largest_prime(&self) -> Option<u64> {
+        self.primes.iter().map(|p| p.number).max()
+    }
+
+    /// Numbers found more than once, in increasing order
+    pub fn duplicates(&self) -> Vec<u64> {
+        PrimeSequence::from_results(&self.primes).duplicates
+    }
+
+    /// Keeps only the first time each number was found, dropping the repeats
+    /// from `primes` and `thread_counts`; returns the number of rows dropped
+    pub fn dedup(&mut self) -> usize {
+        let before = self.primes.len();
+        let mut seen = std::collections::HashSet::new();
+        let thread_counts = &mut self.thread_counts;
+
+        self.primes.retain(|prime| {
+            let first = seen.insert(prime.number);
+            if !first && let Some(count) = thread_counts.get_mut(prime.thread_id) {
+                *count = count.saturating_sub(1);
+            }
+            first
+        });
+
+        before - self.primes.len()
+    }
+
+    /// Primes found and rate per decade of n
+    pub fn decade_throughput(&self) -> Vec<DecadeThroughput> {
+        throughput::decade_throughput(self.primes.iter().map(|p| (p.number, p.elapsed)))
+    }
+
+    /// The metadata, plus `run.covered-up-to` when the run covered a prefix
+    /// of the integers, `cross-check.reference` and `cross-check.mismatches`
+    /// (comma-separated numbers) when a cross-check ran,
+    /// `completeness.covered-up-to`, `completeness.missing` and
+    /// `completeness.duplicated` when completeness was verified, the
+    /// `prefilter.*` counters when a prefilter ran, `numa.thread-nodes` (each
+    /// thread's node, empty where binding failed) under NUMA placement,
+    /// `harness.timer-nanos` and `harness.per-candidate-nanos` when the
+    /// harness overhead was measured, the `env.*` fields of
+    /// the [`Environment`], and the `monitor.*` samples and throttle events
+    /// when the CPU was monitored
+    pub fn output_metadata(&self) -> Vec<(String, String)> {
+        let mut metadata = self.metadata.clone();
+
+        if let Some(covered_up_to) = self.covered_up_to {
+            metadata.push((COVERED_KEY.to_string(), covered_up_to.to_string()));
+        }
+
+        if let Some(cross_check) = &self.cross_check {
+            let mismatches: Vec<String> =
+                cross_check.mismatches.iter().map(u64::to_string).collect();
+            metadata.push((
+                "cross-check.reference".to_string(),
+                cross_check.reference.clone(),
+            ));
+            metadata.push(("cross-check.mismatches".to_string(), mismatches.join(",")));
+        }
+
+        if let Some(completeness) = &self.completeness {
+            let join = |numbers: &[u64]| {
+                let numbers: Vec<String> = numbers.iter().map(u64::to_string).collect();
+                numbers.join(",")
+            };
+            metadata.push((
+                "completeness.covered-up-to".to_string(),
+                completeness.covered_up_to.to_string(),
+            ));
+            metadata.push((
+                "completeness.missing".to_string(),
+                join(&completeness.missing),
+            ));
+            metadata.push((
+                "completeness.duplicated".to_string(),
+                join(&completeness.duplicated),
+            ));
+        }
+
+        if let Some(prefilter) = &self.prefilter {
+            for (key, value) in [
+                ("bound", prefilter.bound),
+                ("candidates", prefilter.candidates),
+                ("rejected", prefilter.rejected),
+                ("total-nanos", prefilter.total_nanos),
+            ] {
+                metadata.push((format!("prefilter.{}", key), value.to_string()));
+            }
+        }
+
+        if !self.thread_nodes.is_empty() {
+            let nodes: Vec<String> = self
+                .thread_nodes
+                .iter()
+                .map(|node| node.map(|n| n.to_string()).unwrap_or_default())
+                .collect();
+            metadata.push(("numa.thread-nodes".to_string(), nodes.join(",")));
+        }
+
+        if let Some(overhead) = &self.harness_overhead {
+            metadata.push((
+                "harness.timer-nanos".to_string(),
+                format!("{:.1}", overhead.timer_nanos),
+            ));
+            metadata.push((
+                "harness.per-candidate-nanos".to_string(),
+                format!("{:.1}", overhead.per_candidate_nanos),
+            ));
+        }
+
+        if let Some(environment) = &self.environment {
+            metadata.extend(environment.metadata());
+        }
+
+        if let Some(monitor) = &self.monitor {
+            metadata.extend(monitor_metadata(monitor));
+        }
+
+        metadata
+    }
+
+    /// Writes the primes to a Parquet file, with [`Self::output_metadata`] as
+    /// key-value metadata
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, filename: &str) -> Result<()> {
+        write_to_parquet(filename, &self.primes, &self.output_metadata())
+    }
+
+    /// Writes the distinct primes found to a compact `.gaps` file, in
+    /// ascending order and without their times and threads, with
+    /// [`Self::output_metadata`]
+    #[cfg(feature = "compact")]
+    pub fn write_gaps(&self, filename: &str) -> Result<GapSummary> {
+        write_gaps(filename, self.distinct_primes(), &self.output_metadata())
+    }
+
+    /// Writes a Bloom filter over the distinct primes found, at a
+    /// false-positive rate of `rate`, with [`Self::output_metadata`].
+    /// Returns the filter and the bytes written.
+    pub fn write_bloom(&self, filename: &str, rate: f64) -> Result<(BloomFilter, u64)> {
+        let filter = BloomFilter::from_primes(&self.distinct_primes(), rate);
+        let bytes = filter.save(filename, &self.output_metadata())?;
+        Ok((filter, bytes))
+    }
+
+    /// The primes found in ascending order, each once
+    pub fn distinct_primes(&self) -> Vec<u64> {
+        let mut numbers: Vec<u64> = self.primes.iter().map(|prime| prime.number).collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        numbers
+    }
+}
+
+// Samples as elapsed:frequency:temperature triples and events as start-end
+// pairs (microseconds, kHz, and m°C), unknown values left empty
+fn monitor_metadata(monitor: &MonitorLog) -> Vec<(String, String)> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    let samples: Vec<String> = monitor
+        .samples
+        .iter()
+        .map(|s| {
+            format!(
+                "{}:{}:{}",
+                s.elapsed,
+                optional(s.frequency_khz.map(|f| f.to_string())),
+                optional(s.temperature_millicelsius.map(|t| t.to_string()))
+            )
+        })
+        .collect();
+    let events: Vec<String> = monitor
+        .throttle_events()
+        .iter()
+        .map(|e| format!("{}-{}", e.start, e.end))
+        .collect();
+
+    vec![
+        (
+            "monitor.interval-micros".to_string(),
+            monitor.interval.as_micros().to_string(),
+        ),
+        ("monitor.samples".to_string(), samples.join(",")),
+        ("monitor.throttle-events".to_string(), events.join(",")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::monitor::CpuSample;
+
+    fn sample() -> BenchmarkResult {
+        let mut timings = BitLengthTimings::default();
+        timings.record(5, 10);
+        timings.record(1000, 100);
+
+        BenchmarkResult {
+            algorithm: "miller-rabin".to_string(),
+            duration: Duration::from_secs(1),
+            primes: vec![
+                PrimeResult {
+                    number: 5,
+                    elapsed: 3,
+                    thread_id: 0,
+                },
+                PrimeResult {
+                    number: 1009,
+                    elapsed: 40,
+                    thread_id: 1,
+                },
+            ],
+            thread_counts: vec![1, 1],
+            thread_nodes: Vec::new(),
+            covered_up_to: None,
+            timings,
+            metadata: vec![("key".to_string(), "value".to_string())],
+            cross_check: None,
+            completeness: None,
+            prefilter: None,
+            environment: None,
+            monitor: None,
+            harness_overhead: None,
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let result = sample();
+        assert_eq!(result.primes_found(), 2);
+        assert_eq!(result.largest_prime(), Some(1009));
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut result = sample();
+        assert!(result.duplicates().is_empty());
+        assert_eq!(result.dedup(), 0);
+
+        // Thread 0 found 1009 again after thread 1, and 5 twice
+        for (number, elapsed) in [(5, 50), (1009, 60), (5, 70)] {
+            result.primes.push(PrimeResult {
+                number,
+                elapsed,
+                thread_id: 0,
+            });
+        }
+        result.thread_counts = vec![4, 1];
+        assert_eq!(result.duplicates(), [5, 1009]);
+
+        assert_eq!(result.dedup(), 3);
+        assert_eq!(result.primes, sample().primes);
+        assert_eq!(result.thread_counts, [1, 1]);
+        assert!(result.duplicates().is_empty());
+    }
+
+    #[test]
+    fn test_output_metadata() {
+        let mut result = sample();
+        assert_eq!(result.output_metadata(), result.metadata);
+
+        result.covered_up_to = Some(1013);
+        assert_eq!(
+            result.output_metadata()[1..],
+            [(COVERED_KEY.to_string(), "1013".to_string())]
+        );
+        result.covered_up_to = None;
+
+        result.cross_check = Some(CrossCheck {
+            reference: "bpsw".to_string(),
+            mismatches: vec![2047, 3277],
+        });
+        assert_eq!(
+            result.output_metadata()[1..],
+            [
+                ("cross-check.reference".to_string(), "bpsw".to_string()),
+                (
+                    "cross-check.mismatches".to_string(),
+                    "2047,3277".to_string()
+                ),
+            ]
+        );
+
+        result.completeness = Some(Completeness {
+            covered_up_to: 100,
+            missing: vec![7, 11],
+            duplicated: Vec::new(),
+        });
+        assert_eq!(
+            result.output_metadata()[3..],
+            [
+                ("completeness.covered-up-to".to_string(), "100".to_string()),
+                ("completeness.missing".to_string(), "7,11".to_string()),
+                ("completeness.duplicated".to_string(), String::new()),
+            ]
+        );
+        result.completeness = None;
+
+        result.prefilter = Some(PrefilterStats {
+            bound: 100,
+            candidates: 10,
+            rejected: 4,
+            total_nanos: 50,
+        });
+        assert_eq!(
+            result.output_metadata()[3..],
+            [
+                ("prefilter.bound".to_string(), "100".to_string()),
+                ("prefilter.candidates".to_string(), "10".to_string()),
+                ("prefilter.rejected".to_string(), "4".to_string()),
+                ("prefilter.total-nanos".to_string(), "50".to_string()),
+            ]
+        );
+
+        let environment = Environment::capture();
+        result.environment = Some(environment.clone());
+        assert_eq!(result.output_metadata()[7..], environment.metadata());
+
+        result.environment = None;
+        result.thread_nodes = vec![Some(0), None, Some(1)];
+        assert_eq!(
+            result.output_metadata()[7..],
+            [("numa.thread-nodes".to_string(), "0,,1".to_string())]
+        );
+
+        result.thread_nodes.clear();
+        result.monitor = Some(MonitorLog {
+            interval: Duration::from_millis(500),
+            samples: vec![
+                CpuSample {
+                    elapsed: 500_000,
+                    frequency_khz: Some(4_000_000),
+                    temperature_millicelsius: None,
+                    throttle_count: None,
+                },
+                CpuSample {
+                    elapsed: 1_000_000,
+                    frequency_khz: Some(2_000_000),
+                    temperature_millicelsius: Some(99_000),
+                    throttle_count: None,
+                },
+            ],
+        });
+        assert_eq!(
+            result.output_metadata()[7..],
+            [
+                ("monitor.interval-micros".to_string(), "500000".to_string()),
+                (
+                    "monitor.samples".to_string(),
+                    "500000:4000000:,1000000:2000000:99000".to_string()
+                ),
+                (
+                    "monitor.throttle-events".to_string(),
+                    "500000-1000000".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let result = sample();
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: BenchmarkResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+
+        // Timings keep every bucket, so a truncated list is rejected
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["timings"]["buckets"].as_array().unwrap().len(), 65);
+        let truncated = json.replacen(r#"{"candidates":0,"total_nanos":0},"#, "", 1);
+        assert!(serde_json::from_str::<BenchmarkResult>(&truncated).is_err());
+    }
+}
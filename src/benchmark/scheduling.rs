@@ -0,0 +1,129 @@
+//! Process priority and scheduling policy
+//!
+//! Background load competing for the CPUs shows up as noise in the timings.
+//! Raising the priority of the thread that starts a benchmark before it
+//! spawns the workers, which inherit it, reduces that interference. Only
+//! Linux is supported; negative nice values and real-time policies need
+//! `CAP_SYS_NICE` (or root).
+
+use std::io;
+
+/// Highest real-time priority accepted by [`set_realtime`]
+pub const MAX_REALTIME_PRIORITY: i32 = 99;
+
+/// Sets the nice value (-20, most favoured, to 19) of the calling thread
+#[cfg(target_os = "linux")]
+pub fn set_nice(nice: i32) -> io::Result<()> {
+    // SAFETY: setpriority only reads its integer arguments; `who` 0 is the
+    // calling thread
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Moves the calling thread to the round-robin real-time policy with
+/// `priority` (1 to [`MAX_REALTIME_PRIORITY`]). Round-robin rather than FIFO,
+/// so the benchmark's own timer thread still gets a turn on busy CPUs.
+#[cfg(target_os = "linux")]
+pub fn set_realtime(priority: i32) -> io::Result<()> {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+
+    // SAFETY: `param` outlives the call, which only reads it
+    match unsafe { libc::sched_setscheduler(0, libc::SCHED_RR, &param) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Nice value of the calling thread
+#[cfg(target_os = "linux")]
+pub fn current_nice() -> Option<i32> {
+    // -1 is both a valid nice value and the error return, so errno tells them apart
+    // SAFETY: errno is thread-local and getpriority only reads its arguments
+    unsafe {
+        *libc::__errno_location() = 0;
+        let nice = libc::getpriority(libc::PRIO_PROCESS, 0);
+        (*libc::__errno_location() == 0).then_some(nice)
+    }
+}
+
+/// Scheduling policy of the calling thread, e.g. "SCHED_OTHER" or
+/// "SCHED_RR (priority 10)"
+#[cfg(target_os = "linux")]
+pub fn current_policy() -> Option<String> {
+    let mut param = libc::sched_param { sched_priority: 0 };
+
+    // SAFETY: `param` is a valid sched_param for sched_getparam to write to
+    let (policy, param) = unsafe {
+        let policy = libc::sched_getscheduler(0);
+        if policy < 0 || libc::sched_getparam(0, &mut param) != 0 {
+            return None;
+        }
+        (policy, param)
+    };
+
+    let name = match policy {
+        libc::SCHED_OTHER => "SCHED_OTHER",
+        libc::SCHED_FIFO => "SCHED_FIFO",
+        libc::SCHED_RR => "SCHED_RR",
+        libc::SCHED_BATCH => "SCHED_BATCH",
+        libc::SCHED_IDLE => "SCHED_IDLE",
+        _ => return Some(format!("policy {}", policy)),
+    };
+
+    Some(match param.sched_priority {
+        0 => name.to_string(),
+        priority => format!("{} (priority {})", name, priority),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_realtime(_priority: i32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_nice() -> Option<i32> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_policy() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "scheduling options are only supported on Linux",
+    )
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nice() {
+        // Lowering the priority is always permitted; in its own thread, so
+        // the other tests keep theirs
+        std::thread::spawn(|| {
+            let before = current_nice().unwrap();
+            let lowered = (before + 1).min(19);
+            set_nice(lowered).unwrap();
+            assert_eq!(current_nice(), Some(lowered));
+            assert_eq!(current_policy().as_deref(), Some("SCHED_OTHER"));
+        })
+        .join()
+        .unwrap();
+    }
+}
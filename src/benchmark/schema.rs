@@ -0,0 +1,51 @@
+//! Versions of the files benchmarks write
+//!
+//! Parquet results (under the `schema-version` metadata key) and baselines
+//! (in their `schema_version` field) record the layout they were written
+//! with, so a later build can tell an old file from a damaged one. Readers
+//! accept every version up to [`SCHEMA_VERSION`]; files written before the
+//! version was recorded count as [`UNVERSIONED`].
+
+/// Layout written by this build. Version 2 only adds the version itself.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Version of the files that do not record one
+pub const UNVERSIONED: u32 = 1;
+
+/// Parquet metadata key holding the version
+pub const SCHEMA_VERSION_KEY: &str = "schema-version";
+
+/// Parquet metadata key naming the algorithm a benchmark ran
+pub const ALGORITHM_KEY: &str = "run.algorithm";
+
+/// Parquet metadata key holding the length of the run in microseconds
+pub const DURATION_KEY: &str = "run.duration-micros";
+
+/// Parquet metadata key describing where the run's candidates came from
+pub const CANDIDATES_KEY: &str = "run.candidates";
+
+/// Parquet metadata key holding the number every prime up to which the run
+/// tested, absent for a shuffled range
+pub const COVERED_KEY: &str = "run.covered-up-to";
+
+/// Parses a recorded version, None unless this build can read it
+pub fn readable(version: &str) -> Option<u32> {
+    version
+        .parse()
+        .ok()
+        .filter(|version| (UNVERSIONED..=SCHEMA_VERSION).contains(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readable() {
+        assert_eq!(readable("1"), Some(1));
+        assert_eq!(readable(&SCHEMA_VERSION.to_string()), Some(SCHEMA_VERSION));
+        assert_eq!(readable(&(SCHEMA_VERSION + 1).to_string()), None);
+        assert_eq!(readable("0"), None);
+        assert_eq!(readable("two"), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Physical core topology, for running one worker per core
+//!
+//! Hyperthread siblings share their core's execution units, so two workers
+//! on one core each run slower than alone, and by how much depends on the
+//! algorithm's instruction mix. Pinning one worker to one logical CPU of
+//! each physical core takes that effect out of throughput comparisons.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::benchmark::numa;
+
+/// The physical cores available to the process, each as its logical CPUs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicalCores {
+    pub cores: Vec<Vec<usize>>,
+}
+
+impl PhysicalCores {
+    /// Reads the SMT siblings from sysfs, keeping the CPUs the process may
+    /// run on; None where the topology is unavailable
+    pub fn discover() -> Option<PhysicalCores> {
+        let entries = fs::read_dir(Path::new("/sys/devices/system/cpu")).ok()?;
+        let allowed = numa::current_affinity();
+
+        let cores: BTreeSet<Vec<usize>> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_prefix("cpu"))
+                    .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .filter_map(|entry| {
+                let siblings =
+                    fs::read_to_string(entry.path().join("topology/thread_siblings_list")).ok()?;
+                numa::parse_cpu_list(&siblings)
+            })
+            .map(|siblings| match &allowed {
+                Some(allowed) => siblings
+                    .into_iter()
+                    .filter(|cpu| allowed.contains(cpu))
+                    .collect(),
+                None => siblings,
+            })
+            .filter(|siblings: &Vec<usize>| !siblings.is_empty())
+            .collect();
+
+        (!cores.is_empty()).then(|| PhysicalCores {
+            cores: cores.into_iter().collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cores.is_empty()
+    }
+
+    /// The logical CPU thread `thread` is pinned to: the first sibling of
+    /// each core in turn
+    pub fn cpu_for_thread(&self, thread: usize) -> usize {
+        self.cores[thread % self.cores.len()][0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_for_thread() {
+        // Two cores with hyperthread siblings numbered the usual Linux way
+        let cores = PhysicalCores {
+            cores: vec![vec![0, 2], vec![1, 3]],
+        };
+        assert_eq!(cores.len(), 2);
+
+        let cpus: Vec<usize> = (0..3).map(|t| cores.cpu_for_thread(t)).collect();
+        assert_eq!(cpus, [0, 1, 0]);
+    }
+
+    #[test]
+    fn test_discover() {
+        // No more physical cores than logical CPUs, and no CPU on two cores
+        if let Some(cores) = PhysicalCores::discover() {
+            let cpus: Vec<usize> = cores.cores.iter().flatten().copied().collect();
+            let unique: BTreeSet<usize> = cpus.iter().copied().collect();
+            assert_eq!(cpus.len(), unique.len());
+            assert!(cores.len() <= cpus.len());
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Micro-benchmarks that find the fastest algorithm for each size of number
+//!
+//! Each bucket of bit lengths is timed on the same run of consecutive odd
+//! numbers with the bucket's largest bit length, a natural mix of primes and
+//! composites, in the [`Order`] asked for. An algorithm that takes longer
+//! than the time limit on one bucket is left out of the larger ones, which
+//! would only be slower.
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::algorithms::PrimalityTest;
+use crate::algorithms::auto::{Profile, ProfileBucket, ProfileTiming};
+use crate::benchmark::{Environment, Order};
+
+/// `count` consecutive odd numbers from the middle of the numbers with
+/// `bits` bits (2 to 64)
+pub fn sample_numbers(bits: u32, count: u64) -> Vec<u64> {
+    let start = (3u64 << (bits - 2)) | 1;
+    (0..count)
+        .map(|i| start + 2 * i)
+        .take_while(|&n| n >> (bits - 1) == 1)
+        .collect()
+}
+
+/// Times every algorithm on each bucket of `bits` (by increasing bit length)
+/// and ranks them, fastest first. Within a bucket the numbers are timed in
+/// `order`, the same for every algorithm.
+pub fn profile(
+    algorithms: &[Arc<dyn PrimalityTest>],
+    bits: &[u32],
+    samples: u64,
+    time_limit: Duration,
+    mut order: Order,
+) -> Profile {
+    let mut too_slow = vec![false; algorithms.len()];
+    let mut buckets = Vec::new();
+
+    for &max_bits in bits {
+        let mut numbers = sample_numbers(max_bits, samples);
+        order.arrange(&mut numbers);
+        info!("⏱️  Timing {}-bit numbers...", max_bits);
+
+        let mut timings = Vec::new();
+        for (algorithm, too_slow) in algorithms.iter().zip(&mut too_slow) {
+            if *too_slow {
+                continue;
+            }
+            if !numbers
+                .iter()
+                .all(|n| algorithm.supported_range().contains(n))
+            {
+                warn!(
+                    "   ⏭️  {} bits is outside the range of '{}'",
+                    max_bits,
+                    algorithm.name()
+                );
+                continue;
+            }
+
+            let (average, finished) = time_numbers(algorithm.as_ref(), &numbers, time_limit);
+            if !finished {
+                warn!(
+                    "   ⏰ '{}' took over {:?}, skipping larger numbers",
+                    algorithm.name(),
+                    time_limit
+                );
+                *too_slow = true;
+            }
+            timings.push(ProfileTiming {
+                algorithm: algorithm.name().to_string(),
+                average_nanos: average.as_nanos() as f64,
+            });
+        }
+
+        timings.sort_by(|a, b| a.average_nanos.total_cmp(&b.average_nanos));
+        buckets.push(ProfileBucket { max_bits, timings });
+    }
+
+    Profile {
+        cpu_model: Environment::capture().cpu_model,
+        buckets,
+    }
+}
+
+// Mean time per number, and whether all of `numbers` were tested within
+// `time_limit`
+fn time_numbers(
+    algorithm: &dyn PrimalityTest,
+    numbers: &[u64],
+    time_limit: Duration,
+) -> (Duration, bool) {
+    let start_time = Instant::now();
+    let mut timed = 0;
+
+    for &n in numbers {
+        black_box(algorithm.is_prime(black_box(n)));
+        timed += 1;
+
+        if start_time.elapsed() > time_limit {
+            break;
+        }
+    }
+
+    let elapsed = start_time.elapsed();
+    (elapsed / timed, timed == numbers.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::bpsw::Bpsw;
+    use crate::algorithms::trial_division_sqrt::TrialDivisionSqrt;
+
+    #[test]
+    fn test_sample_numbers() {
+        assert_eq!(sample_numbers(8, 4), [193, 195, 197, 199]);
+        assert_eq!(sample_numbers(2, 4), [3]);
+        let numbers = sample_numbers(64, 100);
+        assert_eq!(numbers.len(), 100);
+        assert!(numbers.iter().all(|n| n >> 63 == 1 && !n.is_multiple_of(2)));
+    }
+
+    #[test]
+    fn test_profile() {
+        let algorithms: Vec<Arc<dyn PrimalityTest>> =
+            vec![Arc::new(TrialDivisionSqrt), Arc::new(Bpsw)];
+        let order = Order::Shuffled(crate::random::RandomSource::new(5));
+        let profile = profile(&algorithms, &[8, 40], 50, Duration::from_millis(500), order);
+
+        assert_eq!(profile.buckets.len(), 2);
+        assert_eq!(profile.buckets[0].max_bits, 8);
+        assert_eq!(profile.buckets[0].timings.len(), 2);
+        // Trial division by up to 2^20 divisors is far behind at 40 bits
+        assert_eq!(profile.buckets[1].fastest(), Some("bpsw"));
+        assert!(profile.buckets.iter().all(|b| {
+            b.timings
+                .is_sorted_by(|x, y| x.average_nanos <= y.average_nanos)
+        }));
+    }
+}
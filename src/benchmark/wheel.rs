@@ -0,0 +1,131 @@
+//! Raw mod-30 wheel bitmaps, the layout of primesieve's sieve arrays
+//!
+//! Every byte stands for 30 consecutive numbers and holds one bit for each of
+//! the 8 among them coprime to 30: bit `j` of byte `k` is set if
+//! `30k + WHEEL_OFFSETS[j]` is prime. 2, 3 and 5 have no bit and are implied.
+//! The file is the bytes alone, with no header, so tools that read
+//! primesieve-style arrays can map it directly; numbers past the limit it
+//! was written for are left clear in the last byte.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// The residue of each bit of a byte, 30k + offset, least significant bit first
+pub const WHEEL_OFFSETS: [u64; 8] = [7, 11, 13, 17, 19, 23, 29, 31];
+
+/// Numbers each byte stands for
+pub const NUMBERS_PER_BYTE: u64 = 30;
+
+// The bit of each residue mod 30, None for residues sharing a factor with 30.
+// 1 is 31 of the previous byte.
+const BIT_OF_RESIDUE: [Option<u8>; 30] = {
+    let mut bits = [None; 30];
+    let mut j = 0;
+    while j < WHEEL_OFFSETS.len() {
+        bits[(WHEEL_OFFSETS[j] % 30) as usize] = Some(j as u8);
+        j += 1;
+    }
+    bits
+};
+
+/// Bytes of the bitmap of the primes up to `limit`
+pub fn bitmap_len(limit: u64) -> usize {
+    match limit.checked_sub(WHEEL_OFFSETS[0]) {
+        Some(past) => (past / NUMBERS_PER_BYTE + 1) as usize,
+        None => 0,
+    }
+}
+
+/// The bitmap of `primes`, which must all be at most `limit`
+///
+/// # Panics
+///
+/// If a prime exceeds `limit`.
+pub fn wheel_bitmap(primes: &[u64], limit: u64) -> Vec<u8> {
+    let mut bitmap = vec![0u8; bitmap_len(limit)];
+    for &prime in primes {
+        assert!(prime <= limit, "{} is past the limit {}", prime, limit);
+        if let Some((byte, bit)) = position(prime) {
+            bitmap[byte] |= 1 << bit;
+        }
+    }
+    bitmap
+}
+
+/// Writes the bitmap of `primes` up to `limit` to `path`, returning its bytes
+pub fn write_wheel_bitmap(path: impl AsRef<Path>, primes: &[u64], limit: u64) -> Result<u64> {
+    let bitmap = wheel_bitmap(primes, limit);
+    fs::write(path, &bitmap)?;
+    Ok(bitmap.len() as u64)
+}
+
+/// The primes up to `limit` in `bitmap`, 2, 3 and 5 included
+pub fn wheel_primes(bitmap: &[u8], limit: u64) -> Result<Vec<u64>> {
+    if bitmap.len() < bitmap_len(limit) {
+        return Err(Error::InvalidWheelBitmap {
+            reason: format!(
+                "{} bytes, {} are needed for the primes up to {}",
+                bitmap.len(),
+                bitmap_len(limit),
+                limit
+            ),
+        });
+    }
+
+    let mut primes: Vec<u64> = [2, 3, 5].into_iter().filter(|&p| p <= limit).collect();
+    for (k, &byte) in bitmap[..bitmap_len(limit)].iter().enumerate() {
+        let base = k as u64 * NUMBERS_PER_BYTE;
+        for (j, offset) in WHEEL_OFFSETS.iter().enumerate() {
+            if byte & (1 << j) != 0 && base + offset <= limit {
+                primes.push(base + offset);
+            }
+        }
+    }
+    Ok(primes)
+}
+
+// The byte and bit of `n`, None for 2, 3, 5 and the numbers sharing a
+// factor with 30
+fn position(n: u64) -> Option<(usize, u8)> {
+    let bit = BIT_OF_RESIDUE[(n % NUMBERS_PER_BYTE) as usize]?;
+    let byte = n.checked_sub(WHEEL_OFFSETS[0])? / NUMBERS_PER_BYTE;
+    Some((byte as usize, bit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(bitmap_len(6), 0);
+        assert_eq!(bitmap_len(7), 1);
+        assert_eq!(bitmap_len(36), 1);
+        assert_eq!(bitmap_len(37), 2);
+
+        // 7 to 31 are all prime but 7 · 7 = 49 is the first composite bit
+        assert_eq!(wheel_bitmap(&sieve(31), 31), [0xff]);
+        assert_eq!(wheel_bitmap(&sieve(60), 60), [0xff, 0b0110_1111]);
+        assert_eq!(wheel_bitmap(&sieve(61), 61), [0xff, 0b1110_1111]);
+        assert_eq!(position(61), Some((1, 7)));
+        assert_eq!(position(1), None);
+        assert_eq!(position(5), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for limit in [0, 5, 7, 30, 31, 1000, 999_983, 1_000_000] {
+            let primes = sieve(limit);
+            let bitmap = wheel_bitmap(&primes, limit);
+            assert_eq!(bitmap.len(), bitmap_len(limit));
+            assert_eq!(wheel_primes(&bitmap, limit).unwrap(), primes, "{}", limit);
+        }
+        assert!(matches!(
+            wheel_primes(&[0xff], 100),
+            Err(Error::InvalidWheelBitmap { .. })
+        ));
+    }
+}
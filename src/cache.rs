@@ -0,0 +1,274 @@
+//! Sieve bitmaps kept on disk between runs
+//!
+//! Tables built from a sieve (the prefilter's small primes, the shared
+//! [`small_primes`](crate::algorithms::small_primes) table, the primes
+//! `--verify-complete` checks against) would otherwise be sieved again by
+//! every invocation. A [`SieveCache`]
+//! stores the bitmap of [0, limit] as `sieve-<limit>.bin`, in the layout of
+//! [`sieve_into`], and serves any later request up to that limit from it.
+//! Storing a larger bitmap replaces the smaller ones it covers.
+//!
+//! Nothing is cached until a cache is [`install`]ed, which the binary does
+//! unless given `--no-cache`.
+
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tracing::{debug, warn};
+
+use crate::core_algorithms::sieve_of_eratosthenes::{primes, sieve_into, words_for};
+use crate::error::Result;
+
+/// Largest limit a bitmap is cached up to (128 MiB on disk)
+pub const MAX_CACHED_LIMIT: u64 = 1 << 30;
+
+// File header: magic, format version (u32) and limit (u64), little endian
+const MAGIC: &[u8; 8] = b"PTCSIEVE";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_BYTES: usize = 20;
+
+/// A directory of cached sieve bitmaps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SieveCache {
+    dir: PathBuf,
+}
+
+/// One cached bitmap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    /// Largest number the bitmap covers
+    pub limit: u64,
+    pub bytes: u64,
+}
+
+impl SieveCache {
+    pub fn new(dir: impl Into<PathBuf>) -> SieveCache {
+        SieveCache { dir: dir.into() }
+    }
+
+    /// `$PTC_CACHE_DIR`, else `primality-test-comparison` under
+    /// `$XDG_CACHE_HOME` or `~/.cache`
+    pub fn default_dir() -> Option<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+
+        if let Some(dir) = var("PTC_CACHE_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        let base = var("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(base.join("primality-test-comparison"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached bitmaps, by increasing limit
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+            let limit = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("sieve-")?.strip_suffix(".bin"))
+                .and_then(|limit| limit.parse().ok());
+            if let Some(limit) = limit {
+                let bytes = fs::metadata(&path)?.len();
+                entries.push(CacheEntry { path, limit, bytes });
+            }
+        }
+        entries.sort_by_key(|entry| entry.limit);
+        Ok(entries)
+    }
+
+    /// Deletes every cached bitmap, returning what was deleted
+    pub fn clear(&self) -> Result<Vec<CacheEntry>> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            fs::remove_file(&entry.path)?;
+        }
+        Ok(entries)
+    }
+
+    /// Bitmap of [0, n] (n at most [`MAX_CACHED_LIMIT`]), read from the
+    /// smallest cached bitmap that covers it or sieved and stored
+    pub fn bitmap(&self, n: u64) -> Result<Vec<u64>> {
+        assert!(n <= MAX_CACHED_LIMIT, "{} is above the cached limit", n);
+
+        for entry in self.entries()?.iter().filter(|entry| entry.limit >= n) {
+            match read_bitmap(entry, n) {
+                Ok(bits) => {
+                    debug!(n, path = %entry.path.display(), "sieve cache hit");
+                    return Ok(bits);
+                }
+                // Damaged or from another version: sieved and written again
+                Err(e) => warn!(path = %entry.path.display(), %e, "ignoring cached sieve"),
+            }
+        }
+
+        let mut bits = vec![0u64; words_for(n)];
+        sieve_into(n, &mut bits);
+        self.store(n, &bits)?;
+        Ok(bits)
+    }
+
+    // Writes the bitmap of [0, n] and drops the smaller ones it covers. The
+    // file is renamed into place, so a concurrent run never reads it half
+    // written.
+    fn store(&self, n: u64, bits: &[u64]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("sieve-{}.bin", n));
+        let partial = self
+            .dir
+            .join(format!("sieve-{}.bin.{}", n, std::process::id()));
+
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + 8 * bits.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&n.to_le_bytes());
+        for word in bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        File::create(&partial)?.write_all(&bytes)?;
+        fs::rename(&partial, &path)?;
+        debug!(n, path = %path.display(), "sieve cached");
+
+        for entry in self.entries()? {
+            if entry.limit < n {
+                fs::remove_file(&entry.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The first words_for(n) words of the bitmap in `entry`
+fn read_bitmap(entry: &CacheEntry, n: u64) -> io::Result<Vec<u64>> {
+    let invalid = |reason: &str| io::Error::new(ErrorKind::InvalidData, reason.to_string());
+
+    let mut file = File::open(&entry.path)?;
+    let mut header = [0u8; HEADER_BYTES];
+    file.read_exact(&mut header)?;
+    if &header[..8] != MAGIC {
+        return Err(invalid("not a sieve bitmap"));
+    }
+    if header[8..12] != FORMAT_VERSION.to_le_bytes() {
+        return Err(invalid("written in another format version"));
+    }
+    if header[12..] != entry.limit.to_le_bytes()
+        || entry.bytes != (HEADER_BYTES + 8 * words_for(entry.limit)) as u64
+    {
+        return Err(invalid("truncated or mislabelled"));
+    }
+
+    let mut bytes = vec![0u8; 8 * words_for(n)];
+    file.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|word| u64::from_le_bytes(word.try_into().expect("8-byte chunks")))
+        .collect())
+}
+
+static INSTALLED: OnceLock<SieveCache> = OnceLock::new();
+
+/// Makes `cache` the one [`prime_bitmap`] and [`primes_up_to`] use, for the
+/// rest of the process. Returns false if one was installed already.
+pub fn install(cache: SieveCache) -> bool {
+    INSTALLED.set(cache).is_ok()
+}
+
+pub fn installed() -> Option<&'static SieveCache> {
+    INSTALLED.get()
+}
+
+/// Bitmap of [0, n] in the layout of [`sieve_into`], from the installed
+/// cache when there is one and n is small enough, sieved otherwise
+pub fn prime_bitmap(n: u64) -> Vec<u64> {
+    if n <= MAX_CACHED_LIMIT
+        && let Some(cache) = installed()
+    {
+        match cache.bitmap(n) {
+            Ok(bits) => return bits,
+            Err(e) => warn!(%e, "sieve cache unavailable, sieving instead"),
+        }
+    }
+
+    let mut bits = vec![0u64; words_for(n)];
+    sieve_into(n, &mut bits);
+    bits
+}
+
+/// The primes up to `n` from the installed cache, None if there is none or
+/// n is above [`MAX_CACHED_LIMIT`]
+pub fn primes_up_to(n: u64) -> Option<Vec<u64>> {
+    installed().filter(|_| n <= MAX_CACHED_LIMIT)?;
+    Some(primes(&prime_bitmap(n), n).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::sieve_of_eratosthenes::sieve;
+
+    fn temp_cache(name: &str) -> SieveCache {
+        let dir = std::env::temp_dir().join(format!("ptc-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        SieveCache::new(dir)
+    }
+
+    #[test]
+    fn test_bitmap() {
+        let cache = temp_cache("bitmap");
+        assert!(cache.entries().unwrap().is_empty());
+
+        let bits = cache.bitmap(1000).unwrap();
+        assert_eq!(primes(&bits, 1000).collect::<Vec<_>>(), sieve(1000));
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].limit, 1000);
+
+        // Served from the larger bitmap, which is not replaced
+        let bits = cache.bitmap(100).unwrap();
+        assert_eq!(bits.len(), words_for(100));
+        assert_eq!(primes(&bits, 100).count(), 25);
+        assert_eq!(cache.entries().unwrap().len(), 1);
+
+        // A larger one replaces it
+        cache.bitmap(5000).unwrap();
+        let limits: Vec<u64> = cache.entries().unwrap().iter().map(|e| e.limit).collect();
+        assert_eq!(limits, [5000]);
+
+        assert_eq!(cache.clear().unwrap().len(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_damaged() {
+        let cache = temp_cache("damaged");
+        cache.bitmap(1000).unwrap();
+        let path = cache.dir().join("sieve-1000.bin");
+
+        // Truncated: ignored, sieved, and written again
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        let bits = cache.bitmap(1000).unwrap();
+        assert_eq!(primes(&bits, 1000).count(), 168);
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+
+        fs::write(&path, b"not a bitmap").unwrap();
+        assert_eq!(primes(&cache.bitmap(1000).unwrap(), 1000).count(), 168);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
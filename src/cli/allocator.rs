@@ -0,0 +1,180 @@
+//! The allocator every allocation of the binary goes through
+//!
+//! Allocation-heavy algorithms (AKS, the sieves) time differently under
+//! different allocators, so the choice is made explicit and recorded with
+//! each run. The global allocator dispatches to the one named by the
+//! `PTC_ALLOCATOR` environment variable, read once at the first allocation
+//! so that every block is freed by the allocator that handed it out.
+//! `--allocator` sets the variable and runs the binary again under it.
+
+#[cfg(any(feature = "mimalloc", feature = "jemalloc"))]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::process::Command;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use clap::ValueEnum;
+use primality_test_comparison::Result;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// Names the allocator to use, read before `main` runs
+pub const ALLOCATOR_VAR: &str = "PTC_ALLOCATOR";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, EnumIter)]
+pub enum Allocator {
+    System,
+    #[cfg(feature = "mimalloc")]
+    Mimalloc,
+    #[cfg(feature = "jemalloc")]
+    Jemalloc,
+}
+
+impl Allocator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Allocator::System => "system",
+            #[cfg(feature = "mimalloc")]
+            Allocator::Mimalloc => "mimalloc",
+            #[cfg(feature = "jemalloc")]
+            Allocator::Jemalloc => "jemalloc",
+        }
+    }
+
+    fn from_index(index: u8) -> Allocator {
+        match index {
+            #[cfg(feature = "mimalloc")]
+            1 => Allocator::Mimalloc,
+            #[cfg(feature = "jemalloc")]
+            2 => Allocator::Jemalloc,
+            _ => Allocator::System,
+        }
+    }
+
+    fn index(&self) -> u8 {
+        match self {
+            Allocator::System => 0,
+            #[cfg(feature = "mimalloc")]
+            Allocator::Mimalloc => 1,
+            #[cfg(feature = "jemalloc")]
+            Allocator::Jemalloc => 2,
+        }
+    }
+}
+
+// SELECTED holds an Allocator index once the variable has been read
+const UNREAD: u8 = u8::MAX;
+const READING: u8 = u8::MAX - 1;
+static SELECTED: AtomicU8 = AtomicU8::new(UNREAD);
+
+/// The allocator this process runs under
+pub fn active() -> Allocator {
+    Allocator::from_index(selected())
+}
+
+/// Runs the binary again under `allocator` unless it already runs under it.
+/// Returns only if it does, or if running it again failed.
+pub fn switch_to(allocator: Allocator) -> Result<()> {
+    // Already run again once, so never start the binary over and over
+    if active() == allocator || env::var(ALLOCATOR_VAR).is_ok_and(|name| name == allocator.as_str())
+    {
+        return Ok(());
+    }
+
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(env::args_os().skip(1))
+        .env(ALLOCATOR_VAR, allocator.as_str());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Err(command.exec().into())
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status()?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+// Reads the variable at the first allocation. The allocations made to read
+// it go to the system allocator and are freed before the choice is stored,
+// so none of them outlives it.
+fn selected() -> u8 {
+    match SELECTED.load(Ordering::Acquire) {
+        UNREAD => {}
+        selected => return selected,
+    }
+    if SELECTED
+        .compare_exchange(UNREAD, READING, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return Allocator::System.index();
+    }
+
+    let allocator = env::var_os(ALLOCATOR_VAR)
+        .and_then(|name| Allocator::iter().find(|allocator| name == allocator.as_str()))
+        .unwrap_or(Allocator::System);
+    SELECTED.store(allocator.index(), Ordering::Release);
+    allocator.index()
+}
+
+/// Forwards every call to the allocator [`active`] names; installed only
+/// when there is another allocator than the system one to choose
+#[cfg(any(feature = "mimalloc", feature = "jemalloc"))]
+pub struct Dispatch;
+
+// SAFETY: each call is forwarded unchanged to an allocator that upholds the
+// GlobalAlloc contract, and the selection never changes once made, so a block
+// is always freed or resized by the allocator that handed it out
+#[cfg(any(feature = "mimalloc", feature = "jemalloc"))]
+unsafe impl GlobalAlloc for Dispatch {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe {
+            match active() {
+                Allocator::System => System.alloc(layout),
+                #[cfg(feature = "mimalloc")]
+                Allocator::Mimalloc => mimalloc::MiMalloc.alloc(layout),
+                #[cfg(feature = "jemalloc")]
+                Allocator::Jemalloc => tikv_jemallocator::Jemalloc.alloc(layout),
+            }
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        unsafe {
+            match active() {
+                Allocator::System => System.alloc_zeroed(layout),
+                #[cfg(feature = "mimalloc")]
+                Allocator::Mimalloc => mimalloc::MiMalloc.alloc_zeroed(layout),
+                #[cfg(feature = "jemalloc")]
+                Allocator::Jemalloc => tikv_jemallocator::Jemalloc.alloc_zeroed(layout),
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            match active() {
+                Allocator::System => System.dealloc(ptr, layout),
+                #[cfg(feature = "mimalloc")]
+                Allocator::Mimalloc => mimalloc::MiMalloc.dealloc(ptr, layout),
+                #[cfg(feature = "jemalloc")]
+                Allocator::Jemalloc => tikv_jemallocator::Jemalloc.dealloc(ptr, layout),
+            }
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe {
+            match active() {
+                Allocator::System => System.realloc(ptr, layout, new_size),
+                #[cfg(feature = "mimalloc")]
+                Allocator::Mimalloc => mimalloc::MiMalloc.realloc(ptr, layout, new_size),
+                #[cfg(feature = "jemalloc")]
+                Allocator::Jemalloc => tikv_jemallocator::Jemalloc.realloc(ptr, layout, new_size),
+            }
+        }
+    }
+}
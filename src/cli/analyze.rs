@@ -0,0 +1,228 @@
+use num_bigint::BigUint;
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::Registry;
+use primality_test_comparison::algorithms::advisor::{
+    Analysis, Availability, PartialFactorization, Recommendation, analyze,
+};
+#[cfg(feature = "datasets")]
+use primality_test_comparison::algorithms::datasets::{Dataset, Download, lists_holding};
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, Color, RecordWriter, paint};
+use crate::cli::parsing::OutputFormat;
+
+// Numbers longer than this are shown by their first and last digits
+const DIGITS_SHOWN: usize = 40;
+
+// Small factors of n ± 1 listed before the rest are only counted
+const FACTORS_SHOWN: usize = 12;
+
+// A partial factorization in the JSON and CSV formats
+#[derive(Serialize)]
+struct FactorizationRecord<'a> {
+    factors: &'a [(u64, u32)],
+    cofactor: String,
+    fraction: f64,
+}
+
+impl<'a> FactorizationRecord<'a> {
+    fn new(factored: &'a PartialFactorization) -> Self {
+        FactorizationRecord {
+            factors: &factored.factors,
+            cofactor: factored.cofactor.to_string(),
+            fraction: factored.fraction,
+        }
+    }
+}
+
+// The analysis in the JSON and CSV formats
+#[derive(Serialize)]
+struct AnalysisRecord<'a> {
+    number: String,
+    bits: u64,
+    digits: usize,
+    smallest_factor: Option<u64>,
+    perfect_power: Option<String>,
+    minus_one: FactorizationRecord<'a>,
+    plus_one: FactorizationRecord<'a>,
+    form: Option<String>,
+    composite: Option<String>,
+    #[cfg(feature = "datasets")]
+    listed_in: &'a [&'static str],
+    recommendations: &'a [Recommendation],
+    duration_ns: u128,
+}
+
+pub fn handle_cli(number: &BigUint, registry: &Registry, format: OutputFormat) -> Result<()> {
+    info!("🔍 Analyzing a {}-bit number...", number.bits());
+    let start_time = std::time::Instant::now();
+    let analysis = analyze(number);
+    let composite = analysis.composite_reason();
+    let recommendations = analysis.recommendations(registry);
+    #[cfg(feature = "datasets")]
+    let listed_in = listed_in(number);
+    let duration = start_time.elapsed();
+
+    let mut output = RecordWriter::new(format);
+    if !output.is_table() {
+        output.write(&AnalysisRecord {
+            number: number.to_string(),
+            bits: analysis.bits,
+            digits: analysis.digits,
+            smallest_factor: analysis.smallest_factor,
+            perfect_power: analysis
+                .perfect_power
+                .as_ref()
+                .map(|(root, b)| format!("{}^{}", root, b)),
+            minus_one: FactorizationRecord::new(&analysis.minus_one),
+            plus_one: FactorizationRecord::new(&analysis.plus_one),
+            form: analysis.form.as_ref().map(ToString::to_string),
+            composite,
+            #[cfg(feature = "datasets")]
+            listed_in: &listed_in,
+            recommendations: &recommendations,
+            duration_ns: duration.as_nanos(),
+        });
+        return Ok(());
+    }
+
+    print_facts(&analysis);
+    #[cfg(feature = "datasets")]
+    if !listed_in.is_empty() {
+        println!(
+            "   {} listed in {}",
+            paint("Known  ", Color::Bold),
+            listed_in.join(", ")
+        );
+    }
+    match composite {
+        Some(reason) => println!("\n❌ Result: n is not prime, {}; no test needed", reason),
+        None => {
+            println!(
+                "\n✅ Result: {} method(s) recommended, best first",
+                recommendations.len()
+            );
+            print_recommendations(&recommendations);
+        }
+    }
+    println!("⏱️  Time taken: {}", output::duration(duration));
+
+    Ok(())
+}
+
+// The bundled and downloaded pseudoprime lists that hold n
+#[cfg(feature = "datasets")]
+fn listed_in(n: &BigUint) -> Vec<&'static str> {
+    match u64::try_from(n) {
+        Ok(n) => lists_holding(
+            n,
+            &Dataset::default_dir(),
+            Download::default_dir().as_deref(),
+        ),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn print_facts(analysis: &Analysis) {
+    let row = |label: &str, value: String| {
+        println!(
+            "   {} {}",
+            paint(&format!("{:<7}", label), Color::Bold),
+            value
+        )
+    };
+
+    row(
+        "n",
+        format!(
+            "{} ({} bits, {} digits)",
+            shorten(&analysis.n),
+            analysis.bits,
+            analysis.digits
+        ),
+    );
+    row(
+        "Factor",
+        match analysis.smallest_factor {
+            Some(p) if BigUint::from(p) == analysis.n => "n is itself a prime below 2^16".into(),
+            Some(p) => format!("{} is the smallest prime factor", p),
+            None => "none below 2^16".into(),
+        },
+    );
+    if let Some((root, b)) = &analysis.perfect_power {
+        row("Power", format!("n = {}^{}", shorten(root), b));
+    }
+    if analysis.bits > 1 {
+        row("n - 1", factorization(&analysis.minus_one));
+    }
+    row("n + 1", factorization(&analysis.plus_one));
+    if let Some(form) = &analysis.form {
+        row("Form", form.to_string());
+    }
+}
+
+fn print_recommendations(recommendations: &[Recommendation]) {
+    for (i, recommendation) in recommendations.iter().enumerate() {
+        let how = match recommendation.availability {
+            Availability::Registered => "algorithm",
+            Availability::Command => "command",
+            Availability::External => "not implemented here",
+        };
+        let verdict = if recommendation.proves {
+            "proof"
+        } else {
+            "probable prime"
+        };
+        println!(
+            "   {}. {} ({}, {})",
+            i + 1,
+            paint(recommendation.method, Color::Bold),
+            how,
+            verdict
+        );
+        println!("      {}", recommendation.reason);
+    }
+}
+
+// "2^4 · 3 · 5 · C31 (17% factored)", C31 standing for a 31-digit cofactor
+fn factorization(factored: &PartialFactorization) -> String {
+    let mut parts: Vec<String> = factored
+        .factors
+        .iter()
+        .take(FACTORS_SHOWN)
+        .map(|&(p, e)| match e {
+            1 => p.to_string(),
+            _ => format!("{}^{}", p, e),
+        })
+        .collect();
+    let more = factored.factors.len().saturating_sub(FACTORS_SHOWN);
+    if more > 0 {
+        parts.push(format!("({} more)", more));
+    }
+    if factored.cofactor != BigUint::from(1u32) {
+        let digits = factored.cofactor.to_string();
+        parts.push(if digits.len() > 20 {
+            format!("C{}", digits.len())
+        } else {
+            digits
+        });
+    }
+    format!(
+        "{} ({:.0}% factored)",
+        parts.join(" · "),
+        100.0 * factored.fraction
+    )
+}
+
+fn shorten(n: &BigUint) -> String {
+    let digits = n.to_string();
+    if digits.len() <= DIGITS_SHOWN {
+        return digits;
+    }
+    format!(
+        "{}...{}",
+        &digits[..DIGITS_SHOWN / 2],
+        &digits[digits.len() - DIGITS_SHOWN / 2..]
+    )
+}
@@ -1,271 +1,775 @@
 use std::fs;
+#[cfg(feature = "flight")]
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Duration;
-use strum::IntoEnumIterator;
 
-use crate::cli::parsing::PrimeAlgorithm;
-
-struct PrimeResultLocal {
-    number: u64,
-    timestamp: std::time::SystemTime,
-}
-
-struct PrimeResultFinal {
-    number: u64,
-    elapsed: u64, // Elapsed time in microseconds since benchmark start
-    thread_id: usize,
+use primality_test_comparison::algorithms::config::AlgorithmConfig;
+use primality_test_comparison::algorithms::{PrimalityTest, Registry};
+use primality_test_comparison::benchmark::bloom::BLOOM_EXTENSION;
+#[cfg(feature = "compact")]
+use primality_test_comparison::benchmark::compact::GAPS_EXTENSION;
+#[cfg(feature = "flight")]
+use primality_test_comparison::benchmark::flight::ResultServer;
+use primality_test_comparison::benchmark::scheduling;
+use primality_test_comparison::benchmark::schema::{ALGORITHM_KEY, CANDIDATES_KEY, DURATION_KEY};
+use primality_test_comparison::benchmark::{
+    Baseline, BaselineEntry, BenchmarkConfig, BenchmarkConfigBuilder, BenchmarkResult,
+    CandidateSource, Environment, IntervalThroughput, LiveResults, Permutation, run,
+};
+use primality_test_comparison::random::RandomSource;
+use primality_test_comparison::{Error, Result};
+use tracing::{info, warn};
+
+use crate::cli::allocator;
+use crate::cli::output::{self, PrimeWriter};
+use crate::cli::parsing::parse_duration;
+use crate::cli::query::log_bloom;
+use crate::cli::resolve_algorithms;
+
+// Primes listed in a problem report before the rest are only counted
+const LISTED_PRIMES: usize = 10;
+
+// How often --stdout looks for primes the workers published
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Optional checks and stages run alongside the benchmark
+pub struct Options<'a> {
+    /// Drop repeated primes from the results
+    pub dedup: bool,
+    /// Self-check the algorithms before benchmarking any of them
+    pub selfcheck: bool,
+    /// Name of the algorithm that re-verifies every prime found
+    pub cross_check: Option<&'a str>,
+    /// Check the primes found against a sieve of the range covered
+    pub verify_complete: bool,
+    /// Bound of the small-prime prefilter applied to candidates
+    pub prefilter: Option<u64>,
+    /// Name of the baseline to store this run's summary metrics under
+    pub save_baseline: Option<&'a str>,
+    /// Name of the baseline to compare this run's summary metrics against
+    pub compare_baseline: Option<&'a str>,
+    /// Largest slowdown, in percent, not reported as a regression
+    pub regression_threshold: f64,
+    /// Interval between CPU frequency and temperature samples
+    pub monitor: Option<&'a str>,
+    /// Interval between status lines during each run
+    pub status_interval: Option<&'a str>,
+    /// Spread threads over the NUMA nodes
+    pub numa: bool,
+    /// Run one pinned thread per physical core
+    pub no_smt: bool,
+    /// Nice value to run at
+    pub nice: Option<i32>,
+    /// Real-time (round-robin) priority to run at
+    pub realtime: Option<i32>,
+    /// Test every integer from 2 instead of 6k ± 1
+    pub all_candidates: bool,
+    /// Range to test in a seeded pseudo-random order instead of 6k ± 1
+    pub shuffle: Option<RangeInclusive<u64>>,
+    /// Source of the seed the shuffled order is fixed by
+    pub random: &'a RandomSource,
+    /// Also save the primes found as a compact .gaps file
+    #[cfg(feature = "compact")]
+    pub gaps: bool,
+    /// Also save a Bloom filter over the primes found, at this false-positive rate
+    pub bloom: Option<f64>,
+    /// Write the primes to stdout as they are found instead of the final results
+    pub stdout: bool,
+    /// Address to serve the runs at over Arrow Flight while they go on
+    #[cfg(feature = "flight")]
+    pub serve: Option<SocketAddr>,
 }
 
 pub fn handle_cli(
     duration_str: &str,
-    algorithms: &Option<Vec<PrimeAlgorithm>>,
+    algorithms: &Option<Vec<String>>,
     output_path: &PathBuf,
     save: &bool,
-) {
+    options: Options<'_>,
+    registry: &Registry,
+    config: &AlgorithmConfig,
+) -> Result<()> {
+    // Parse duration
+    let duration = parse_duration(duration_str)?;
+    let monitor = options.monitor.map(parse_duration).transpose()?;
+    let status_interval = options.status_interval.map(parse_duration).transpose()?;
+
+    let algorithms = resolve_algorithms(registry, algorithms)?;
+    let candidates = match &options.shuffle {
+        Some(range) => {
+            let permutation =
+                Permutation::new(range.clone(), options.random.seed()).ok_or_else(|| {
+                    Error::InvalidBenchmark(format!(
+                        "cannot shuffle {}..={}: more numbers than fit in a u64",
+                        range.start(),
+                        range.end()
+                    ))
+                })?;
+            info!(
+                "🎲 Testing {} numbers in shuffled order (seed {})",
+                output::count(permutation.len()),
+                permutation.seed()
+            );
+            CandidateSource::Shuffled(permutation)
+        }
+        None if options.all_candidates => {
+            info!("🔢 Testing every integer from 2 upward, even ones included");
+            CandidateSource::AllIntegers
+        }
+        None => CandidateSource::Sequential,
+    };
+    let reference = match options.cross_check {
+        Some(name) => Some(registry.lookup(name)?),
+        None => None,
+    };
+
+    // Baselines live next to the results; a missing one fails before any run
+    let baseline_dir = output_path.join("baselines");
+    let compare_baseline = match options.compare_baseline {
+        Some(name) => Some(Baseline::load(&baseline_dir, name)?),
+        None => None,
+    };
+    let mut save_baseline = match options.save_baseline {
+        Some(name) => Some(Baseline::load_or_new(&baseline_dir, name)?),
+        None => None,
+    };
+
+    // A buggy algorithm would only produce a meaningless comparison
+    if options.selfcheck {
+        crate::cli::selfcheck::check(&algorithms)?;
+    }
+
+    // Workers inherit the priority of this thread, so set it before any run
+    if let Some(nice) = options.nice
+        && let Err(error) = scheduling::set_nice(nice)
+    {
+        warn!("⚠️  Could not set nice value {}: {}", nice, error);
+    }
+    if let Some(priority) = options.realtime
+        && let Err(error) = scheduling::set_realtime(priority)
+    {
+        warn!(
+            "⚠️  Could not switch to SCHED_RR priority {}: {}",
+            priority, error
+        );
+    }
+
+    // Each run records the same in its metadata
+    let environment = Environment::capture();
+    info!(
+        "🖥️  {} (cores: {}, physical: {}, governor: {}), {}, {}, {} allocator",
+        environment.cpu_model.as_deref().unwrap_or("unknown CPU"),
+        environment.cores,
+        environment
+            .physical_cores
+            .map_or("unknown".to_string(), |cores| cores.to_string()),
+        environment.governor.as_deref().unwrap_or("unknown"),
+        environment.os_release.as_deref().unwrap_or(&environment.os),
+        environment.rustc_version,
+        allocator::active().as_str()
+    );
+    if options.nice.is_some() || options.realtime.is_some() {
+        info!(
+            "🚦 Scheduling: {}, nice {}",
+            environment.scheduler.as_deref().unwrap_or("unknown policy"),
+            environment
+                .nice
+                .map_or("unknown".to_string(), |nice| nice.to_string())
+        );
+    }
+
+    // Stopped when the command exits, so only while the runs go on
+    #[cfg(feature = "flight")]
+    let served = match options.serve {
+        Some(address) => {
+            let server = ResultServer::new();
+            let handle = server.serve(address)?;
+            Some((server, handle))
+        }
+        None => None,
+    };
+
     // Run benchmark
-    if algorithms.is_none() {
-        println!("❗️ No algorithm specified. Running all algorithms.");
+    let mut mismatches = 0;
+    let (mut missing, mut duplicated) = (0, 0);
+    let mut regressed = Vec::new();
+    for alg in algorithms {
+        info!(
+            "⏱️  Running '{}' benchmark for {:?}...",
+            alg.name(),
+            duration
+        );
+
+        // Recorded so that result files compared later name their run
+        let mut builder = BenchmarkConfig::builder(alg.clone())
+            .duration(duration)
+            .metadata(ALGORITHM_KEY, alg.name())
+            .metadata(DURATION_KEY, duration.as_micros().to_string())
+            .metadata(CANDIDATES_KEY, candidates.to_string())
+            .metadata("env.allocator", allocator::active().as_str())
+            .candidates(candidates.clone());
+        if let Some(reference) = &reference {
+            info!("   🔁 Cross-checking primes with '{}'", reference.name());
+            builder = builder.cross_check(reference.clone());
+        }
+        if options.verify_complete {
+            builder = builder.verify_complete(true);
+        }
+        if let Some(bound) = options.prefilter {
+            info!(
+                "   🧹 Prefiltering candidates by the primes up to {}",
+                bound
+            );
+            builder = builder.prefilter(bound);
+        }
+        if let Some(interval) = monitor {
+            info!(
+                "   🌡️  Sampling CPU frequency and temperature every {:?}",
+                interval
+            );
+            builder = builder.monitor(interval);
+        }
+        if let Some(interval) = status_interval {
+            builder = builder.status_interval(interval);
+        }
+        if options.numa {
+            builder = builder.numa(true);
+        }
+        if options.no_smt {
+            info!("   🧵 One thread per physical core, hyperthread siblings left idle");
+            builder = builder.physical_cores_only(true);
+        }
 
-        for alg in PrimeAlgorithm::iter() {
-            run_benchmark(duration_str, alg, output_path, save);
+        // Named like the file the run is saved to
+        #[cfg(feature = "flight")]
+        let run_name = format!("{}-{}", alg.name(), duration_str);
+        let mut live = None;
+        #[cfg(feature = "flight")]
+        if let Some((server, _)) = &served {
+            let published = Arc::new(LiveResults::new());
+            server.publish_live(run_name.clone(), published.clone(), Vec::new());
+            live = Some(published);
         }
-    } else {
-        for alg in algorithms.as_ref().unwrap() {
-            run_benchmark(duration_str, *alg, output_path, save);
+        if options.stdout {
+            live.get_or_insert_with(|| Arc::new(LiveResults::new()));
+        }
+        if let Some(live) = &live {
+            builder = builder.live(live.clone());
         }
+
+        // Streamed from a thread of its own while the workers run
+        let done = AtomicBool::new(false);
+        let result = thread::scope(|scope| {
+            let streamer = live
+                .as_deref()
+                .filter(|_| options.stdout)
+                .map(|live| scope.spawn(|| stream_primes(live, &done)));
+            let result = run_benchmark(
+                builder,
+                duration_str,
+                alg.clone(),
+                output_path,
+                save,
+                &options,
+                config,
+            );
+            done.store(true, Ordering::Release);
+            match streamer {
+                Some(streamer) => streamer
+                    .join()
+                    .expect("the prime stream never panics")
+                    .and(result),
+                None => result,
+            }
+        })?;
+        #[cfg(feature = "flight")]
+        if let Some((server, _)) = &served {
+            server.publish(run_name, result.primes.clone(), result.output_metadata());
+        }
+        mismatches += result
+            .cross_check
+            .as_ref()
+            .map_or(0, |cross_check| cross_check.mismatches.len());
+        if let Some(completeness) = &result.completeness {
+            missing += completeness.missing.len();
+            duplicated += completeness.duplicated.len();
+        }
+
+        let entry = BaselineEntry::from_result(&result);
+        if let Some(baseline) = &compare_baseline
+            && print_baseline_comparison(baseline, &entry, options.regression_threshold)
+        {
+            regressed.push(alg.name().to_string());
+        }
+        if let Some(baseline) = &mut save_baseline {
+            baseline.upsert(entry);
+        }
+    }
+
+    if let Some(baseline) = &save_baseline {
+        let path = baseline.save(&baseline_dir)?;
+        info!(
+            "💾 Baseline '{}' written to: {}",
+            baseline.name,
+            path.display()
+        );
+    }
+
+    if mismatches > 0 {
+        return Err(Error::Disagreement { mismatches });
+    }
+
+    if missing > 0 || duplicated > 0 {
+        return Err(Error::Incomplete {
+            missing,
+            duplicated,
+        });
     }
+
+    if !regressed.is_empty() {
+        return Err(Error::Regression {
+            algorithms: regressed.join(", "),
+        });
+    }
+
+    Ok(())
 }
 
 fn run_benchmark(
+    mut builder: BenchmarkConfigBuilder,
     duration_str: &str,
-    algorithm: PrimeAlgorithm,
+    algorithm: Arc<dyn PrimalityTest>,
     output_path: &PathBuf,
     save: &bool,
-) {
-    // Parse duration
-    let duration = parse_duration(duration_str).unwrap_or_else(|e| {
-        eprintln!("⚠️ Error parsing duration '{}': {}", duration_str, e);
-        eprintln!("Valid formats: 5s, 10m, 1h, 30sec, 2min, etc.");
-        std::process::exit(1);
-    });
-
-    println!(
-        "⏱️  Running '{}' benchmark for {:?}...",
-        algorithm.as_str(),
-        duration
-    );
-
-    // Shared state for tracking primes across all threads
-    let running = Arc::new(AtomicBool::new(true));
-    let primes_vector = Arc::new(std::sync::Mutex::new(Vec::<PrimeResultFinal>::new()));
+    options: &Options<'_>,
+    config: &AlgorithmConfig,
+) -> Result<BenchmarkResult> {
+    // Algorithm-specific settings applied to this run, recorded as metadata
+    for (key, value) in config.for_algorithm(algorithm.name()) {
+        info!("   ⚙️  {} = {}", key, value);
+        builder = builder.metadata(format!("algo-config.{}", key), value);
+    }
 
-    // Setup timer thread
-    let running_clone = running.clone();
-    thread::spawn(move || {
-        thread::sleep(duration);
-        println!("\n⏰ Time's up! Stopping all threads...");
-        running_clone.store(false, Ordering::SeqCst);
-    });
+    let benchmark_config = builder.build()?;
 
     // Run the primality test in parallel
-    let handles = is_prime_in_parallel(
-        algorithm.as_algorithm_fn(),
-        running.clone(),
-        primes_vector.clone(),
-    );
+    let mut result = run(&benchmark_config)?;
 
-    for handle in handles {
-        handle.join().unwrap();
+    if result.duration < benchmark_config.duration() {
+        // Rates computed from the file later divide by the time actually run
+        for (key, value) in &mut result.metadata {
+            if key == DURATION_KEY {
+                *value = result.duration.as_micros().to_string();
+            }
+        }
+        info!(
+            "🏁 Every candidate tested after {}, stopping all threads...",
+            output::duration(result.duration)
+        );
+    } else {
+        info!("⏰ Time's up! Stopping all threads...");
+    }
+    for (i, count) in result.thread_counts.iter().enumerate() {
+        match result.thread_nodes.get(i) {
+            Some(Some(node)) => info!(
+                "Thread {} stopping... (count: {}, NUMA node {})",
+                i, count, node
+            ),
+            Some(None) => info!("Thread {} stopping... (count: {}, unbound)", i, count),
+            None => info!("Thread {} stopping... (count: {})", i, count),
+        }
     }
 
-    // Print final results
-    let final_count = primes_vector.lock().unwrap().len();
-    println!("\n📊 Final Results:");
-    println!("   Primes found: {}", final_count);
-    println!(
-        "   Biggest prime found: {:?}",
-        primes_vector
-            .lock()
-            .unwrap()
-            .iter()
-            .map(|p| p.number)
-            .max()
-            .unwrap()
-    );
+    // Repeats mean threads overlapped; reported before the summary counts them
+    let duplicates = result.duplicates();
+    if !duplicates.is_empty() {
+        warn!(
+            "⚠️  {} primes found more than once: {}",
+            duplicates.len(),
+            list_primes(&duplicates)
+        );
+        if options.dedup {
+            info!("✂️  Dropped {} repeated rows", result.dedup());
+        }
+    }
 
-    if !*save {
-        return;
+    // Print final results, unless the primes took their place on stdout
+    if options.stdout {
+        info!(
+            "📊 {} primes found, {} per second",
+            output::count(result.primes_found() as u64),
+            output::si(result.primes_found() as f64 / result.duration.as_secs_f64())
+        );
+    } else {
+        print_results(&result);
     }
 
-    // Order primes by timestamp
-    let mut primes = primes_vector.lock().unwrap();
-    primes.sort_by_key(|p| p.elapsed);
+    if !*save {
+        return Ok(result);
+    }
 
     // Create output directory if it doesn't exist
-    let _ = fs::create_dir(output_path);
+    fs::create_dir_all(output_path)?;
 
     // Generate readable timestamp for filename
     let filename = format!(
         "{}/{}-{}.parquet",
         output_path.display(),
-        algorithm.as_str(),
+        algorithm.name(),
         duration_str
     );
 
     // Write results to Parquet file
-    write_to_parquet(&filename, &primes).unwrap();
-    println!("\n💾 Results written to: {}", filename);
+    result.write_parquet(&filename)?;
+    info!("💾 Results written to: {}", filename);
+
+    #[cfg(feature = "compact")]
+    if options.gaps {
+        let gaps_filename = format!(
+            "{}/{}-{}.{}",
+            output_path.display(),
+            algorithm.name(),
+            duration_str,
+            GAPS_EXTENSION
+        );
+        let summary = result.write_gaps(&gaps_filename)?;
+        info!(
+            "🗜️  Primes written to: {} ({} against {} of Parquet, {:.2} bytes per prime)",
+            gaps_filename,
+            output::bytes(summary.bytes),
+            output::bytes(fs::metadata(&filename)?.len()),
+            summary.bytes as f64 / summary.primes.max(1) as f64
+        );
+    }
+
+    if let Some(rate) = options.bloom {
+        let bloom_filename = format!(
+            "{}/{}-{}.{}",
+            output_path.display(),
+            algorithm.name(),
+            duration_str,
+            BLOOM_EXTENSION
+        );
+        let (filter, bytes) = result.write_bloom(&bloom_filename, rate)?;
+        log_bloom(&bloom_filename, &filter, bytes);
+    }
+
+    Ok(result)
 }
 
-fn write_to_parquet(
-    filename: &str,
-    primes: &[PrimeResultFinal],
-) -> Result<(), Box<dyn std::error::Error>> {
-    use arrow::array::{ArrayRef, UInt64Builder};
-    use arrow::datatypes::{DataType, Field, Schema};
-    use arrow::record_batch::RecordBatch;
-    use parquet::arrow::ArrowWriter;
-    use std::fs::File;
-
-    // Define schema
-    let schema = Arc::new(Schema::new(vec![
-        Field::new("elapsed", DataType::UInt64, false),
-        Field::new("thread", DataType::UInt64, false),
-        Field::new("number", DataType::UInt64, false),
-    ]));
-
-    // Create arrays for each column
-    let mut elapsed_builder = UInt64Builder::new();
-    let mut thread_builder = UInt64Builder::new();
-    let mut number_builder = UInt64Builder::new();
-
-    for prime in primes {
-        let ts = prime.elapsed as u64;
-        elapsed_builder.append_value(ts);
-        thread_builder.append_value(prime.thread_id as u64);
-        number_builder.append_value(prime.number);
-    }
-
-    // Build the record batch
-    let batch = RecordBatch::try_new(
-        schema.clone(),
-        vec![
-            Arc::new(elapsed_builder.finish()) as ArrayRef,
-            Arc::new(thread_builder.finish()) as ArrayRef,
-            Arc::new(number_builder.finish()) as ArrayRef,
-        ],
-    )?;
-
-    // Write to parquet file
-    let file = File::create(filename)?;
-    let mut writer = ArrowWriter::try_new(file, schema, None)?;
-    writer.write(&batch)?;
-    writer.close()?;
+// The summary of a run and the checks made on it, on stdout
+fn print_results(result: &BenchmarkResult) {
+    println!("\n📊 Final Results:");
+    println!(
+        "   Primes found: {}",
+        output::count(result.primes_found() as u64)
+    );
+    println!(
+        "   Primes per second: {}",
+        output::si(result.primes_found() as f64 / result.duration.as_secs_f64())
+    );
+    match result.largest_prime() {
+        Some(largest) => println!("   Biggest prime found: {}", largest),
+        None => println!("   Biggest prime found: none"),
+    }
 
-    Ok(())
+    print_normalized_throughput(result);
+    print_harness_overhead(result);
+    print_prefilter(result);
+    print_monitor(result);
+
+    print_cross_check(result);
+    print_completeness(result);
 }
 
-fn parse_duration(s: &str) -> Result<Duration, String> {
-    humantime::parse_duration(s).map_err(|e| e.to_string())
+// What timing each candidate cost on top of the algorithm, and whether that
+// dwarfs the algorithm itself
+fn print_harness_overhead(result: &BenchmarkResult) {
+    let Some(overhead) = &result.harness_overhead else {
+        return;
+    };
+
+    let (candidates, algorithm_nanos) =
+        result
+            .timings
+            .iter()
+            .fold((0, 0), |(candidates, nanos), (_, timing)| {
+                (candidates + timing.candidates, nanos + timing.total_nanos)
+            });
+    let algorithm_average = algorithm_nanos as f64 / candidates.max(1) as f64;
+
+    println!(
+        "
+🪛 Harness overhead (measured with an empty algorithm):"
+    );
+    println!(
+        "   Clock reads: {:>8.1}ns per candidate, subtracted from the times above",
+        overhead.timer_nanos
+    );
+    println!(
+        "   In all:      {:>8.1}ns per candidate, against {:.1}ns in the algorithm",
+        overhead.per_candidate_nanos, algorithm_average
+    );
+    if algorithm_average < overhead.per_candidate_nanos {
+        warn!(
+            "⚠️  The harness took longer than the algorithm; primes/s mostly measures the harness"
+        );
+    }
 }
 
-fn is_prime_in_parallel(
-    primality_test_func: fn(u64) -> bool,
-    running: Arc<AtomicBool>,
-    primes_vector: Arc<std::sync::Mutex<Vec<PrimeResultFinal>>>,
-) -> Vec<thread::JoinHandle<()>> {
-    // Amount of threads to spawn based on available parallelism
-    let parallelism_count = thread::available_parallelism().unwrap().get();
-    // Thread handles
-    let mut handles: Vec<thread::JoinHandle<()>> = Vec::new();
-
-    // Start time for calculating elapsed time for each prime found
-    let start_time = std::time::SystemTime::now();
-
-    // Spawn threads
-    for i in 0..parallelism_count {
-        // Clone shared state for each thread
-        let running = running.clone();
-        let primes_vector = primes_vector.clone();
-
-        // Each thread will have its own local vector to store primes before pushing to shared vector
-        let handle = thread::spawn(move || {
-            // Local vector for this thread to store primes before pushing to shared vector
-            let mut local_primes: Vec<PrimeResultLocal> = Vec::new();
-
-            // Special handling for the first thread to check small primes
-            if i == 0 {
-                // Check small primes (2 and 3) before entering the main loop
-                for k in 2..3 {
-                    // Check if we should stop
-                    if !running.load(Ordering::SeqCst) {
-                        // Append local primes to shared primes vector before exiting
-                        let mut shared_primes = primes_vector.lock().unwrap();
-                        for p in local_primes.drain(..) {
-                            shared_primes.push(PrimeResultFinal {
-                                number: p.number,
-                                elapsed: p.timestamp.duration_since(start_time).unwrap().as_micros()
-                                    as u64,
-                                thread_id: i,
-                            });
-                        }
-                        return;
-                    }
-
-                    // Check if k is prime and add to local primes if it is
-                    if primality_test_func(k) {
-                        local_primes.push(PrimeResultLocal {
-                            number: k - 1,
-                            timestamp: std::time::SystemTime::now(),
-                        });
-                    }
-                }
-            }
+// Time split between the prefilter and the algorithm, to tell their shares apart
+fn print_prefilter(result: &BenchmarkResult) {
+    let Some(prefilter) = &result.prefilter else {
+        return;
+    };
 
-            for j in 0.. {
-                // Check if we should stop
-                if !running.load(Ordering::SeqCst) {
-                    let count = local_primes.len();
-
-                    // Append local primes to shared primes vector before exiting
-                    let mut shared_primes = primes_vector.lock().unwrap();
-                    for p in local_primes.drain(..) {
-                        shared_primes.push(PrimeResultFinal {
-                            number: p.number,
-                            elapsed: p.timestamp.duration_since(start_time).unwrap().as_micros()
-                                as u64,
-                            thread_id: i,
-                        });
-                    }
-
-                    println!("Thread {} stopping... (count: {})", i, count);
-                    return;
-                }
-
-                // Calculate candidate k based on thread index and iteration
-                let k: u64 = ((i + 1) * 6 + j * parallelism_count * 6)
-                    .try_into()
-                    .unwrap();
-
-                // Check candidates k-1 and k+1 (since all primes > 3 are of the form 6k ± 1)
-                for candidate in [k - 1, k + 1] {
-                    // Check if candidate is prime and add to local primes if it is
-                    if primality_test_func(candidate) {
-                        local_primes.push(PrimeResultLocal {
-                            number: candidate,
-                            timestamp: std::time::SystemTime::now(),
-                        });
-                    }
-                }
-            }
-        });
+    let (tested, algorithm_nanos) =
+        result
+            .timings
+            .iter()
+            .fold((0, 0), |(candidates, nanos), (_, timing)| {
+                (candidates + timing.candidates, nanos + timing.total_nanos)
+            });
+
+    println!("\n🧹 Prefilter (primes up to {}):", prefilter.bound);
+    println!(
+        "   Rejected {} of {} candidates ({:.1}%)",
+        output::count(prefilter.rejected),
+        output::count(prefilter.candidates),
+        100.0 * prefilter.rejected_fraction()
+    );
+    println!(
+        "   Prefilter: {:>12}ns per candidate, {} in total",
+        output::decimal(
+            prefilter.total_nanos as f64 / prefilter.candidates as f64,
+            1
+        ),
+        output::duration(Duration::from_nanos(prefilter.total_nanos))
+    );
+    println!(
+        "   Algorithm: {:>12}ns per survivor,  {} in total",
+        output::decimal(algorithm_nanos as f64 / tested as f64, 1),
+        output::duration(Duration::from_nanos(algorithm_nanos))
+    );
+}
+
+// Frequency and temperature ranges, and the throughput during each throttle
+// event against the rest of the run
+fn print_monitor(result: &BenchmarkResult) {
+    let Some(monitor) = &result.monitor else {
+        return;
+    };
+
+    println!(
+        "\n🌡️  CPU monitor ({} interval, {} samples):",
+        output::duration(monitor.interval),
+        monitor.samples.len()
+    );
+
+    let ghz = |khz: u64| khz as f64 / 1e6;
+    let celsius = |millicelsius: i64| millicelsius as f64 / 1e3;
+
+    if let (Some(peak), Some(min)) = (monitor.peak_frequency_khz(), monitor.min_frequency_khz()) {
+        println!(
+            "   Frequency: {:.2} GHz peak, {:.2} GHz lowest",
+            ghz(peak),
+            ghz(min)
+        );
+    }
+    if let Some(max) = monitor.max_temperature_millicelsius() {
+        println!("   Temperature: {:.1}°C max", celsius(max));
+    }
+    if monitor.peak_frequency_khz().is_none() && monitor.max_temperature_millicelsius().is_none() {
+        println!("   No frequency or temperature sensors found");
+        return;
+    }
+
+    let events = monitor.throttle_events();
+    if events.is_empty() {
+        println!("   ✅ No throttling detected");
+        return;
+    }
+
+    let throughput = monitor.throughput(&result.primes);
+    let rate = |intervals: &[&IntervalThroughput]| {
+        let primes: u64 = intervals.iter().map(|i| i.primes).sum();
+        let micros: u64 = intervals.iter().map(|i| i.end - i.start).sum();
+        primes as f64 / (micros as f64 / 1e6)
+    };
+    let unthrottled: Vec<&IntervalThroughput> =
+        throughput.iter().filter(|i| !i.throttled).collect();
+
+    for event in &events {
+        let during: Vec<&IntervalThroughput> = throughput
+            .iter()
+            .filter(|i| i.start >= event.start && i.end <= event.end)
+            .collect();
+
+        println!(
+            "   ⚠️  Throttled {:.1}s-{:.1}s: {}, {}, {} primes/s vs {} primes/s unthrottled",
+            event.start as f64 / 1e6,
+            event.end as f64 / 1e6,
+            event
+                .min_frequency_khz
+                .map_or("frequency unknown".to_string(), |f| format!(
+                    "{:.2} GHz",
+                    ghz(f)
+                )),
+            event.max_temperature_millicelsius.map_or(
+                "temperature unknown".to_string(),
+                |t| format!("{:.1}°C", celsius(t))
+            ),
+            output::si(rate(&during)),
+            output::si(rate(&unthrottled))
+        );
+    }
+}
+
+fn print_cross_check(result: &BenchmarkResult) {
+    let Some(cross_check) = &result.cross_check else {
+        return;
+    };
+
+    println!("\n🔁 Cross-check with '{}':", cross_check.reference);
+    if cross_check.mismatches.is_empty() {
+        println!(
+            "   ✅ All {} primes confirmed",
+            output::count(result.primes_found() as u64)
+        );
+    } else {
+        println!(
+            "   ❌ {} of {} primes are composite",
+            output::count(cross_check.mismatches.len() as u64),
+            output::count(result.primes_found() as u64)
+        );
+    }
+}
+
+fn print_completeness(result: &BenchmarkResult) {
+    let Some(completeness) = &result.completeness else {
+        return;
+    };
+
+    println!(
+        "\n🧮 Completeness up to {} (sieved):",
+        completeness.covered_up_to
+    );
+    if completeness.is_complete() {
+        println!("   ✅ Every prime found exactly once");
+        return;
+    }
+
+    if !completeness.missing.is_empty() {
+        println!(
+            "   ❌ {} primes missing: {}",
+            completeness.missing.len(),
+            list_primes(&completeness.missing)
+        );
+    }
+    if !completeness.duplicated.is_empty() {
+        println!(
+            "   ❌ {} primes found more than once: {}",
+            completeness.duplicated.len(),
+            list_primes(&completeness.duplicated)
+        );
+    }
+}
+
+// The first few numbers, comma-separated
+fn list_primes(numbers: &[u64]) -> String {
+    let shown: Vec<String> = numbers
+        .iter()
+        .take(LISTED_PRIMES)
+        .map(u64::to_string)
+        .collect();
+    match numbers.len() > LISTED_PRIMES {
+        true => format!("{}, ...", shown.join(", ")),
+        false => shown.join(", "),
+    }
+}
+
+// Returns true if any metric regressed beyond the threshold
+fn print_baseline_comparison(baseline: &Baseline, entry: &BaselineEntry, threshold: f64) -> bool {
+    println!("\n📐 Baseline '{}':", baseline.name);
+
+    let Some(previous) = baseline.get(&entry.algorithm) else {
+        println!("   No entry for '{}', nothing to compare", entry.algorithm);
+        return false;
+    };
+
+    // Longer runs reach larger, slower numbers, so only equal durations compare fairly
+    if previous.duration_secs != entry.duration_secs {
+        warn!(
+            "   ⚠️  Baseline ran for {}, this run for {}",
+            output::duration(Duration::from_secs_f64(previous.duration_secs)),
+            output::duration(Duration::from_secs_f64(entry.duration_secs))
+        );
+    }
 
-        // Store thread handle
-        handles.push(handle);
+    let changes = entry.compare(previous, threshold);
+    for change in &changes {
+        println!(
+            "   {} {:<13} {:>14} -> {:>14}  ({:+.1}%)",
+            if change.regressed { "❌" } else { "✅" },
+            change.metric,
+            output::decimal(change.baseline, 1),
+            output::decimal(change.current, 1),
+            change.improvement_percent
+        );
     }
 
-    handles
+    changes.iter().any(|change| change.regressed)
+}
+
+fn print_normalized_throughput(result: &BenchmarkResult) {
+    println!("\n📏 Throughput by decade of n:");
+    for decade in result.decade_throughput() {
+        let rate = match decade.primes_per_second {
+            Some(rate) => format!("{} primes/s", output::si(rate)),
+            None => "-".to_string(),
+        };
+        println!(
+            "   10^{:<2} {:>12} primes  {:>20}",
+            decade.decade,
+            output::count(decade.primes),
+            rate
+        );
+    }
+
+    println!("\n⏱️  Average time per candidate by bit length:");
+    for (bits, timing) in result.timings.iter() {
+        println!(
+            "   {:>2} bits {:>12}ns  ({} candidates)",
+            bits,
+            output::decimal(timing.average_nanos(), 1),
+            output::count(timing.candidates)
+        );
+    }
+}
+
+// Writes the primes the workers of a run publish to stdout until the run is
+// `done` or the reader closes the pipe
+fn stream_primes(live: &LiveResults, done: &AtomicBool) -> Result<()> {
+    let mut out = PrimeWriter::new();
+    let mut written = 0;
+    loop {
+        // Checked first, so the primes published before it are all written
+        let finished = live.is_finished() || done.load(Ordering::Acquire);
+        let primes = live.since(written);
+        written += primes.len();
+        for prime in &primes {
+            if !out.write(prime.number)? {
+                return Ok(());
+            }
+        }
+        if !out.flush()? || finished {
+            return Ok(());
+        }
+        thread::sleep(STREAM_INTERVAL);
+    }
 }
@@ -0,0 +1,55 @@
+use std::io::{Error as IoError, ErrorKind};
+
+use primality_test_comparison::Result;
+use primality_test_comparison::cache::{CacheEntry, SieveCache};
+
+use crate::cli::output;
+use crate::cli::parsing::CacheAction;
+
+pub fn handle_cli(action: &CacheAction) -> Result<()> {
+    let dir = SieveCache::default_dir().ok_or_else(|| {
+        IoError::new(
+            ErrorKind::NotFound,
+            "no cache directory: set PTC_CACHE_DIR, XDG_CACHE_HOME, or HOME",
+        )
+    })?;
+    let cache = SieveCache::new(dir);
+
+    match action {
+        CacheAction::Info => {
+            let entries = cache.entries()?;
+            println!("🗄️  Sieve cache: {}", cache.dir().display());
+            if entries.is_empty() {
+                println!("   empty");
+                return Ok(());
+            }
+            for entry in &entries {
+                println!(
+                    "   primes up to {:<14} {:>10}",
+                    entry.limit,
+                    output::bytes(entry.bytes)
+                );
+            }
+            println!(
+                "   {:<28} {:>10}",
+                "total",
+                output::bytes(total_bytes(&entries))
+            );
+        }
+        CacheAction::Clear => {
+            let removed = cache.clear()?;
+            println!(
+                "🧹 Removed {} cached bitmaps ({}) from {}",
+                removed.len(),
+                output::bytes(total_bytes(&removed)),
+                cache.dir().display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn total_bytes(entries: &[CacheEntry]) -> u64 {
+    entries.iter().map(|entry| entry.bytes).sum()
+}
@@ -0,0 +1,407 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use primality_test_comparison::algorithms::{PrimalityTest, Registry};
+use primality_test_comparison::analysis::hardware::{
+    HardwareComparison, MachineRun, RangeThroughput,
+};
+use primality_test_comparison::analysis::significance::{self, TestOutcome};
+use primality_test_comparison::benchmark::Order;
+use primality_test_comparison::benchmark::parquet::read_from_parquet;
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::cli::output::{self, Color, RecordWriter, paint};
+use crate::cli::parsing::OutputFormat;
+use crate::cli::sweep::primes_from;
+
+// Each latency is averaged over this many calls, to rise above timer resolution
+const REPEATS: u32 = 16;
+
+/// Which primes are timed, and how the difference is judged
+pub struct Options {
+    /// Time primes from 10^exponent upwards
+    pub exponent: u32,
+    /// Number of primes timed
+    pub samples: u64,
+    /// Significance level of the tests
+    pub alpha: f64,
+    /// Order the primes are timed in
+    pub order: Order,
+}
+
+/// Latencies of two algorithms on the same primes, and whether they differ
+struct Comparison<'a> {
+    a: &'a Arc<dyn PrimalityTest>,
+    b: &'a Arc<dyn PrimalityTest>,
+    latencies_a: Vec<f64>,
+    latencies_b: Vec<f64>,
+    mann_whitney: Option<TestOutcome>,
+    welch: Option<TestOutcome>,
+    alpha: f64,
+}
+
+impl Comparison<'_> {
+    // Names of the faster and slower algorithm and the ratio of their medians,
+    // if significant. The rank test decides: latencies are long-tailed, which
+    // the t-test assumes away
+    fn faster(&self) -> Option<(&str, &str, f64)> {
+        let outcome = self.mann_whitney?;
+        if !outcome.is_significant(self.alpha) {
+            return None;
+        }
+
+        let (median_a, median_b) = (median(&self.latencies_a), median(&self.latencies_b));
+        Some(if median_a < median_b {
+            (self.a.name(), self.b.name(), median_b / median_a)
+        } else {
+            (self.b.name(), self.a.name(), median_a / median_b)
+        })
+    }
+}
+
+// The comparison in the JSON and CSV formats, latencies in nanoseconds
+#[derive(Serialize)]
+struct CompareRecord<'a> {
+    first: &'a str,
+    second: &'a str,
+    exponent: u32,
+    samples: usize,
+    alpha: f64,
+    first_median_ns: f64,
+    first_mean_ns: f64,
+    second_median_ns: f64,
+    second_mean_ns: f64,
+    mann_whitney: Option<TestOutcome>,
+    welch: Option<TestOutcome>,
+    /// The significantly faster algorithm, if any, and by how much
+    faster: Option<&'a str>,
+    speedup: Option<f64>,
+}
+
+// A comparison of result files in the JSON and CSV formats
+#[derive(Serialize)]
+struct HardwareRecord<'a> {
+    first_file: &'a str,
+    second_file: &'a str,
+    #[serde(flatten)]
+    comparison: &'a HardwareComparison,
+    speedup: f64,
+    speedup_per_thread: f64,
+    speedup_per_thread_ghz: Option<f64>,
+}
+
+pub fn handle_cli(
+    first: &str,
+    second: &str,
+    options: Options,
+    format: OutputFormat,
+    registry: &Registry,
+) -> Result<()> {
+    // Two result files compare the machines that wrote them
+    match (Path::new(first).is_file(), Path::new(second).is_file()) {
+        (true, true) => return compare_results(first, second, format),
+        (true, false) | (false, true) => {
+            return Err(Error::InvalidBenchmark(format!(
+                "compare takes two algorithms or two result files, not '{}' and '{}'",
+                first, second
+            )));
+        }
+        (false, false) => {}
+    }
+
+    let Options {
+        exponent,
+        samples,
+        alpha,
+        mut order,
+    } = options;
+    let a = registry.lookup(first)?;
+    let b = registry.lookup(second)?;
+
+    let mut primes = primes_from(10u64.pow(exponent), samples);
+    order.arrange(&mut primes);
+    for alg in [&a, &b] {
+        if !primes.iter().all(|p| alg.supported_range().contains(p)) {
+            return Err(Error::InvalidBenchmark(format!(
+                "'{}' does not support numbers around 10^{}",
+                alg.name(),
+                exponent
+            )));
+        }
+    }
+
+    info!(
+        "⚖️  Timing '{}' and '{}' on {} primes from 10^{}, {}...",
+        a.name(),
+        b.name(),
+        primes.len(),
+        exponent,
+        order
+    );
+
+    // Interleaved per prime, so drift in clock speed affects both alike
+    let (mut latencies_a, mut latencies_b) = (Vec::new(), Vec::new());
+    for &p in &primes {
+        latencies_a.push(latency(&a, p));
+        latencies_b.push(latency(&b, p));
+    }
+
+    let comparison = Comparison {
+        a: &a,
+        b: &b,
+        mann_whitney: significance::mann_whitney(&latencies_a, &latencies_b),
+        welch: significance::welch_t(&latencies_a, &latencies_b),
+        latencies_a,
+        latencies_b,
+        alpha,
+    };
+
+    let mut output = RecordWriter::new(format);
+    if output.is_table() {
+        print_report(&comparison);
+    } else {
+        let nanos = |s: f64| s * 1e9;
+        let faster = comparison.faster();
+        output.write(&CompareRecord {
+            first: a.name(),
+            second: b.name(),
+            exponent,
+            samples: primes.len(),
+            alpha,
+            first_median_ns: nanos(median(&comparison.latencies_a)),
+            first_mean_ns: nanos(mean(&comparison.latencies_a)),
+            second_median_ns: nanos(median(&comparison.latencies_b)),
+            second_mean_ns: nanos(mean(&comparison.latencies_b)),
+            mann_whitney: comparison.mann_whitney,
+            welch: comparison.welch,
+            faster: faster.map(|(faster, _, _)| faster),
+            speedup: faster.map(|(_, _, ratio)| ratio),
+        });
+    }
+
+    Ok(())
+}
+
+// Compares the runs saved in two result files, usually of the same algorithm
+// on different machines
+fn compare_results(first: &str, second: &str, format: OutputFormat) -> Result<()> {
+    let (first_primes, first_metadata) = read_from_parquet(first)?;
+    let (second_primes, second_metadata) = read_from_parquet(second)?;
+    let comparison = HardwareComparison::new(
+        (&first_primes, &first_metadata),
+        (&second_primes, &second_metadata),
+    )
+    .ok_or_else(|| {
+        Error::InvalidBenchmark("both result files must hold at least one prime".to_string())
+    })?;
+
+    let mut output = RecordWriter::new(format);
+    if !output.is_table() {
+        output.write(&HardwareRecord {
+            first_file: first,
+            second_file: second,
+            comparison: &comparison,
+            speedup: comparison.speedup(),
+            speedup_per_thread: comparison.speedup_per_thread(),
+            speedup_per_thread_ghz: comparison.speedup_per_thread_ghz(),
+        });
+        return Ok(());
+    }
+
+    let algorithm = |run: &MachineRun| run.algorithm.clone().unwrap_or("unknown".to_string());
+    if comparison.same_algorithm() {
+        info!(
+            "🖥️  Comparing '{}' results from two machines...",
+            algorithm(&comparison.first)
+        );
+    } else {
+        warn!(
+            "⚠️  The files hold different algorithms, '{}' and '{}': the difference is not only the hardware",
+            algorithm(&comparison.first),
+            algorithm(&comparison.second)
+        );
+    }
+    print_hardware_report(first, second, &comparison);
+
+    Ok(())
+}
+
+fn print_hardware_report(first: &str, second: &str, comparison: &HardwareComparison) {
+    let row = |label: &str, a: String, b: String| {
+        println!(
+            "   {} {:<40} {}",
+            paint(&format!("{:<18}", label), Color::Bold),
+            a,
+            b
+        )
+    };
+    let (a, b) = (&comparison.first, &comparison.second);
+
+    println!("\n📊 Hardware Comparison Report:");
+    println!("   A: {}", first);
+    println!("   B: {}", second);
+    println!();
+    row("", "A".to_string(), "B".to_string());
+    row(
+        "Duration",
+        output::duration(Duration::from_micros(a.duration_micros)),
+        output::duration(Duration::from_micros(b.duration_micros)),
+    );
+    row(
+        "Primes",
+        output::count(a.primes as u64),
+        output::count(b.primes as u64),
+    );
+    row(
+        "Primes/s",
+        output::si(a.primes_per_second()),
+        output::si(b.primes_per_second()),
+    );
+    row("Threads", a.threads.to_string(), b.threads.to_string());
+    let clock = |run: &MachineRun| {
+        run.clock.map_or("unknown".to_string(), |clock| {
+            format!("{:.2} GHz ({})", clock.ghz, clock.source)
+        })
+    };
+    row("Clock", clock(a), clock(b));
+
+    if comparison.differences.is_empty() {
+        println!("\n   The environments are the same");
+    } else {
+        println!("\n   Environment differences:");
+        for difference in &comparison.differences {
+            let value = |value: &Option<String>| value.clone().unwrap_or("-".to_string());
+            row(
+                &difference.key,
+                value(&difference.first),
+                value(&difference.second),
+            );
+        }
+    }
+
+    println!("\n   Up to {}, which both runs reached:", comparison.bound);
+    for (name, range) in [
+        ("A", &comparison.first_range),
+        ("B", &comparison.second_range),
+    ] {
+        println!("      {}  {}", name, describe_range(range));
+    }
+
+    let speedup = comparison.speedup();
+    let (faster, slower, ratio) = if speedup >= 1.0 {
+        ("B", "A", speedup)
+    } else {
+        ("A", "B", 1.0 / speedup)
+    };
+    let normalized = |speedup: f64| {
+        format!(
+            "{:.2}x",
+            if faster == "B" {
+                speedup
+            } else {
+                1.0 / speedup
+            }
+        )
+    };
+    print!(
+        "\n✅ {} covered it {:.2}x faster than {}: {} per thread",
+        faster,
+        ratio,
+        slower,
+        normalized(comparison.speedup_per_thread())
+    );
+    match comparison.speedup_per_thread_ghz() {
+        Some(per_ghz) => println!(", {} per thread and GHz", normalized(per_ghz)),
+        None => println!(" (clock speed unknown, not normalized by it)"),
+    }
+}
+
+// "123,456 primes in 12.30s, 10.0k primes/s, 627 per thread, 134 per thread and GHz"
+fn describe_range(range: &RangeThroughput) -> String {
+    let mut description = format!(
+        "{} primes in {}, {} primes/s, {} per thread",
+        output::count(range.primes as u64),
+        output::duration(Duration::from_micros(range.elapsed_micros)),
+        output::si(range.primes_per_second),
+        output::si(range.per_thread)
+    );
+    if let Some(per_ghz) = range.per_thread_ghz {
+        description.push_str(&format!(", {} per thread and GHz", output::si(per_ghz)));
+    }
+    description
+}
+
+// Average seconds per call of `algorithm` on `n`
+fn latency(algorithm: &Arc<dyn PrimalityTest>, n: u64) -> f64 {
+    let start_time = Instant::now();
+    for _ in 0..REPEATS {
+        std::hint::black_box(algorithm.is_prime(std::hint::black_box(n)));
+    }
+    start_time.elapsed().as_secs_f64() / REPEATS as f64
+}
+
+fn print_report(comparison: &Comparison) {
+    println!("\n📊 Comparison Report:");
+    for (alg, latencies) in [
+        (comparison.a, &comparison.latencies_a),
+        (comparison.b, &comparison.latencies_b),
+    ] {
+        println!(
+            "   {:<22} median {:>12.4?}  mean {:>12.4?}",
+            alg.name(),
+            seconds(median(latencies)),
+            seconds(mean(latencies))
+        );
+    }
+
+    println!();
+    print_outcome("Mann-Whitney U", comparison.mann_whitney, comparison.alpha);
+    print_outcome("Welch's t", comparison.welch, comparison.alpha);
+
+    match comparison.faster() {
+        Some((faster, slower, ratio)) => println!(
+            "\n✅ '{}' is significantly faster than '{}' ({:.2}x by median, α = {})",
+            faster, slower, ratio, comparison.alpha
+        ),
+        None => println!("\n🟰 No significant difference at α = {}", comparison.alpha),
+    }
+}
+
+fn print_outcome(name: &str, outcome: Option<TestOutcome>, alpha: f64) {
+    match outcome {
+        Some(outcome) => println!(
+            "   {:<16} statistic = {:<12.3} p = {:.4}{}",
+            name,
+            outcome.statistic,
+            outcome.p_value,
+            if outcome.is_significant(alpha) {
+                " (significant)"
+            } else {
+                ""
+            }
+        ),
+        None => println!("   {:<16} not enough samples", name),
+    }
+}
+
+fn median(xs: &[f64]) -> f64 {
+    let mut sorted = xs.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+fn seconds(s: f64) -> std::time::Duration {
+    std::time::Duration::from_secs_f64(s)
+}
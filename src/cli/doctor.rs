@@ -0,0 +1,35 @@
+use primality_test_comparison::Result;
+use primality_test_comparison::benchmark::doctor::{self, Severity};
+use tracing::info;
+
+pub fn handle_cli() -> Result<()> {
+    info!("🩻 Checking the machine for benchmarking hygiene...");
+
+    let findings = doctor::diagnose();
+    for finding in &findings {
+        let icon = match finding.severity {
+            Severity::Ok => "✅",
+            Severity::Warning => "⚠️ ",
+            Severity::Unknown => "❔",
+        };
+        println!("   {} {:<9} {}", icon, finding.check, finding.detail);
+        if let Some(advice) = finding.advice {
+            println!("      👉 {}", advice);
+        }
+    }
+
+    let warnings = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Warning)
+        .count();
+    if warnings == 0 {
+        println!("\n🎉 No known problems, ready to benchmark");
+    } else {
+        println!(
+            "\n⚠️  {} problem(s) found: fix them before a long run, or expect noisy results",
+            warnings
+        );
+    }
+
+    Ok(())
+}
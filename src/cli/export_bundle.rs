@@ -0,0 +1,26 @@
+use std::path::{Path, PathBuf};
+
+use primality_test_comparison::Result;
+use primality_test_comparison::benchmark::bundle;
+use tracing::info;
+
+use crate::cli::resolve_result_files;
+
+const DEFAULT_ARCHIVE: &str = "bundle.tar.gz";
+
+pub fn handle_cli(paths: &[PathBuf], output: Option<&Path>) -> Result<()> {
+    let (files, folder) = resolve_result_files(paths)?;
+
+    // Next to the results unless told otherwise
+    let output = output.map_or_else(|| folder.join(DEFAULT_ARCHIVE), Path::to_path_buf);
+
+    info!("📦 Bundling {} result file(s)...", files.len());
+    for file in &files {
+        println!("   {}", file.display());
+    }
+
+    bundle::export_bundle(&files, &output)?;
+    info!("💾 Bundle written to: {}", output.display());
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::datasets::{DOWNLOADS, Download};
+use tracing::{info, warn};
+
+use crate::cli::output;
+
+pub fn handle_cli(names: &Option<Vec<String>>, dir: Option<&Path>, force: bool) -> Result<()> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => Download::default_dir().ok_or_else(|| {
+            IoError::new(
+                ErrorKind::NotFound,
+                "no cache directory: set PTC_CACHE_DIR, XDG_CACHE_HOME, or HOME, or pass --dir",
+            )
+        })?,
+    };
+    let downloads: Vec<&Download> = DOWNLOADS
+        .iter()
+        .filter(|download| {
+            names
+                .as_ref()
+                .is_none_or(|names| names.iter().any(|name| name == download.name))
+        })
+        .collect();
+
+    info!(
+        "🌐 Fetching {} list(s) into {}",
+        downloads.len(),
+        dir.display()
+    );
+    for download in downloads {
+        if !force {
+            match download.load(&dir) {
+                Ok(Some(known)) => {
+                    println!(
+                        "   ✅ {}: already downloaded, {} numbers",
+                        download.name,
+                        output::count(known.numbers.len() as u64)
+                    );
+                    continue;
+                }
+                Ok(None) => {}
+                // Damaged or changed since: downloaded again
+                Err(e) => warn!("⚠️  {}", e),
+            }
+        }
+
+        info!("⬇️  {} from {}", download.description, download.url);
+        let fetched = download.fetch(&dir)?;
+        println!(
+            "   💾 {}: {} numbers, {} bytes",
+            download.name,
+            output::count(fetched.numbers as u64),
+            output::count(fetched.bytes)
+        );
+        println!("      SHA-256 {}", fetched.sha256);
+    }
+
+    Ok(())
+}
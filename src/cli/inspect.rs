@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::benchmark::compact::GapLookup;
+
+use crate::cli::output;
+
+pub fn handle_cli(path: &Path, numbers: &[u64]) -> Result<()> {
+    let lookup = GapLookup::load(path)?;
+    let file = lookup.file();
+
+    println!("🗜️  {} ({})", path.display(), output::bytes(file.size()));
+    match (file.first(), file.last()) {
+        (Some(first), Some(last)) => println!(
+            "   {} primes from {} to {} in {} blocks, {:.2} bytes per prime",
+            output::count(file.len()),
+            first,
+            last,
+            file.blocks().len(),
+            file.size() as f64 / file.len() as f64
+        ),
+        _ => println!("   no primes"),
+    }
+    match file.covered_up_to() {
+        Some(covered) => println!("   complete up to {}", covered),
+        None => println!("   no recorded coverage: absent numbers may still be prime"),
+    }
+
+    if !file.metadata().is_empty() {
+        println!("📋 Metadata:");
+        for (key, value) in file.metadata() {
+            println!("   {} = {}", key, value);
+        }
+    }
+
+    if !numbers.is_empty() {
+        println!("🔍 Lookups:");
+    }
+    for &n in numbers {
+        let covered = file.covered_up_to().is_some_and(|covered| n <= covered);
+        let verdict = match (lookup.contains(n), covered) {
+            (true, _) => "prime",
+            (false, true) => "composite",
+            (false, false) => "not in the file, which does not cover it",
+        };
+        println!("   {}: {}", n, verdict);
+    }
+
+    Ok(())
+}
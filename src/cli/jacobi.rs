@@ -0,0 +1,40 @@
+use primality_test_comparison::algorithms::bpsw;
+use primality_test_comparison::math::{jacobi, legendre};
+use primality_test_comparison::{Error, Result};
+
+pub fn handle_cli(a: u64, n: u64) -> Result<()> {
+    if n.is_multiple_of(2) {
+        return Err(Error::InvalidModulus {
+            modulus: n,
+            reason: "the Jacobi symbol needs an odd positive n".to_string(),
+        });
+    }
+
+    // Only for a prime n does the symbol decide whether a is a square
+    if bpsw::is_prime(n) {
+        let symbol = legendre(a, n);
+        println!(
+            "({}/{}) = {} (Legendre symbol, {} is prime)",
+            a, n, symbol, n
+        );
+        match symbol {
+            0 => println!("   {} divides {}", n, a),
+            1 => println!("   {} is a quadratic residue modulo {}", a, n),
+            _ => println!("   {} is a quadratic non-residue modulo {}", a, n),
+        }
+    } else {
+        let symbol = jacobi(a as u128, n as u128);
+        println!("({}/{}) = {} (Jacobi symbol)", a, n, symbol);
+        match symbol {
+            0 => println!("   {} and {} share a factor", a, n),
+            1 if n > 1 => println!(
+                "   {} is composite, so {} may or may not be a square modulo {}",
+                n, a, n
+            ),
+            -1 => println!("   {} is a quadratic non-residue modulo {}", a, n),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
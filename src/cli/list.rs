@@ -0,0 +1,26 @@
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::Registry;
+use primality_test_comparison::algorithms::registry::ALIASES;
+
+pub fn handle_cli(registry: &Registry) -> Result<()> {
+    println!("🧮 Registered algorithms:\n");
+
+    for alg in registry.iter() {
+        let range = alg.supported_range();
+        println!("   {:<22} {}", alg.name(), alg.description());
+        if range != (0..=u64::MAX) {
+            println!("   {:<22} supports n in {:?}", "", range);
+        }
+
+        let aliases: Vec<&str> = ALIASES
+            .iter()
+            .filter(|(_, name)| *name == alg.name())
+            .map(|&(alias, _)| alias)
+            .collect();
+        if !aliases.is_empty() {
+            println!("   {:<22} alias: {}", "", aliases.join(", "));
+        }
+    }
+
+    Ok(())
+}
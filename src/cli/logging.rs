@@ -0,0 +1,31 @@
+use tracing::level_filters::LevelFilter;
+
+use crate::cli::parsing::LogFormat;
+
+/// Installs the global subscriber that status events are written to (on stderr)
+///
+/// `-q` keeps only warnings and errors, each `-v` raises the level one step
+/// from info (debug, then trace).
+pub fn init(verbose: u8, quiet: bool, format: LogFormat) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::WARN,
+        (false, 0) => LevelFilter::INFO,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr);
+
+    match format {
+        // Plain status lines, with levels and targets only once -v is given
+        LogFormat::Text => builder
+            .without_time()
+            .with_level(verbose > 0)
+            .with_target(verbose > 0)
+            .init(),
+        // One JSON object per event, including the enclosing span's fields
+        LogFormat::Json => builder.json().with_current_span(true).init(),
+    }
+}
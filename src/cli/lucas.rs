@@ -0,0 +1,46 @@
+use primality_test_comparison::algorithms::bpsw;
+use primality_test_comparison::math::{jacobi, lucas_sequences};
+use primality_test_comparison::{Error, Result};
+
+pub fn handle_cli(p: i64, q: i64, k: u64, n: u64, all: bool) -> Result<()> {
+    if n == 0 {
+        return Err(Error::InvalidModulus {
+            modulus: n,
+            reason: "the modulus must be positive".to_string(),
+        });
+    }
+
+    let d = i128::from(p) * i128::from(p) - 4 * i128::from(q);
+    println!("P = {}, Q = {}, D = {}, modulo {}", p, q, d, n);
+
+    if all {
+        let width = (k.to_string().len() + 2).max(3);
+        println!("{:>width$}  {:>20}  {:>20}", "k", "U_k", "V_k");
+        for i in 0..=k {
+            let terms = lucas_sequences(p, q, i, n);
+            println!("{:>width$}  {:>20}  {:>20}", i, terms.u, terms.v);
+        }
+        return Ok(());
+    }
+
+    let terms = lucas_sequences(p, q, k, n);
+    println!("U_{} = {}", k, terms.u);
+    println!("V_{} = {}", k, terms.v);
+    println!("Q^{} = {}", k, terms.q_k);
+
+    // The congruence the Lucas probable prime test checks, when it applies
+    let reduce = |x: i128| x.rem_euclid(i128::from(n)) as u128;
+    if n > 2 && bpsw::is_prime(n) && reduce(d) != 0 && reduce(q.into()) != 0 {
+        let symbol = jacobi(reduce(d), n as u128);
+        println!(
+            "   {} is prime and (D/{}) = {}, so {} divides U_{}",
+            n,
+            n,
+            symbol,
+            n,
+            i128::from(n) - i128::from(symbol)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use primality_test_comparison::algorithms::lucas_lehmer::{self, Res64};
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::OutputFormat;
+
+// One interim residue in the JSON and CSV formats
+#[derive(Serialize)]
+struct InterimRecord {
+    iteration: u32,
+    res64: String,
+}
+
+// The run in the JSON and CSV formats
+#[derive(Serialize)]
+struct LucasLehmerRecord {
+    exponent: u32,
+    is_prime: bool,
+    res64: String,
+    expected: Option<String>,
+    interim: Vec<InterimRecord>,
+    duration_ns: u128,
+}
+
+/// Returns whether 2^p - 1 is prime
+pub fn handle_cli(
+    p: u32,
+    checkpoint: Option<u32>,
+    verify: Option<Res64>,
+    format: OutputFormat,
+) -> Result<bool> {
+    if let Some(a) = lucas_lehmer::exponent_factor(p) {
+        return Err(Error::InvalidNumber {
+            input: p.to_string(),
+            reason: format!(
+                "Lucas-Lehmer needs a prime exponent, and 2^{} - 1 divides 2^{} - 1",
+                a, p
+            ),
+        });
+    }
+
+    let mut output = RecordWriter::new(format);
+    info!(
+        "🔍 Running Lucas-Lehmer on M{} = 2^{} - 1, {} iterations...",
+        p,
+        p,
+        p.saturating_sub(2)
+    );
+
+    let start_time = std::time::Instant::now();
+    let mut interim = Vec::new();
+    // M2 = 3 is prime; the iteration starts at p = 3
+    let res64 = match p {
+        2 => Res64(0),
+        _ => Res64::of(&lucas_lehmer::residue(p, |i, s| {
+            if checkpoint.is_some_and(|every| i.is_multiple_of(every)) {
+                let res64 = Res64::of(s);
+                if output.is_table() {
+                    println!("M{} interim LL residue {} at iteration {}", p, res64, i);
+                }
+                interim.push(InterimRecord {
+                    iteration: i,
+                    res64: res64.to_string(),
+                });
+            }
+        })),
+    };
+    let duration = start_time.elapsed();
+    let is_prime = res64 == Res64(0);
+
+    if !output.is_table() {
+        output.write(&LucasLehmerRecord {
+            exponent: p,
+            is_prime,
+            res64: res64.to_string(),
+            expected: verify.map(|expected| expected.to_string()),
+            interim,
+            duration_ns: duration.as_nanos(),
+        });
+    } else {
+        if is_prime {
+            println!("M{} is prime!", p);
+        } else {
+            println!("M{} is not prime. LL Res64: {}.", p, res64);
+        }
+        println!("⏱️  Time taken: {}", output::duration(duration));
+    }
+
+    match verify {
+        Some(expected) if expected != res64 => Err(Error::ResidueMismatch {
+            exponent: p,
+            expected: expected.to_string(),
+            computed: res64.to_string(),
+        }),
+        Some(expected) => {
+            info!("✅ Res64 matches the expected {}", expected);
+            Ok(is_prime)
+        }
+        None => Ok(is_prime),
+    }
+}
@@ -1,6 +1,140 @@
+use std::fs;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::{PrimalityTest, Registry, TraceFn};
+use tracing::info;
+
+pub mod allocator;
+pub mod logging;
+pub mod output;
 pub mod parsing;
 
 // Commands
+pub mod analyze;
 pub mod benchmark;
+pub mod cache;
+pub mod compare;
+pub mod doctor;
+pub mod error_rate;
+pub mod export_bundle;
+#[cfg(feature = "fetch")]
+pub mod fetch_datasets;
+#[cfg(feature = "compact")]
+pub mod inspect;
+pub mod jacobi;
+pub mod list;
+pub mod lucas;
+pub mod lucas_lehmer;
+pub mod modmath;
+pub mod primes;
+pub mod pseudoprimes;
+pub mod query;
+pub mod report;
+pub mod selfcheck;
+#[cfg(feature = "flight")]
+pub mod serve;
 pub mod sieve;
+pub mod stats;
+pub mod sweep;
 pub mod test;
+pub mod tune;
+#[cfg(feature = "datasets")]
+pub mod validate;
+pub mod verify;
+pub mod verify_witness_set;
+pub mod witnesses;
+
+// Where saved results are looked for when no path is given
+const DEFAULT_RESULTS_FOLDER: &str = "./out";
+
+// Steps printed by --trace before the rest are only counted
+const MAX_TRACE_STEPS: usize = 200;
+
+/// Runs `traced`, printing the steps it reports as a numbered list. Past
+/// `MAX_TRACE_STEPS` only the last step, usually the verdict, is printed.
+pub fn print_trace<T>(traced: impl FnOnce(TraceFn<'_>) -> T) -> T {
+    println!("📝 Steps:");
+
+    let mut count = 0;
+    let mut last = String::new();
+    let result = traced(&mut |step| {
+        count += 1;
+        if count <= MAX_TRACE_STEPS {
+            println!("   {}. {}", count, step);
+        } else {
+            last = step.to_string();
+        }
+    });
+
+    if count > MAX_TRACE_STEPS {
+        if count > MAX_TRACE_STEPS + 1 {
+            println!("   ... {} more steps", count - MAX_TRACE_STEPS - 1);
+        }
+        println!("   {}. {}", count, last);
+    }
+
+    result
+}
+
+/// Looks up the algorithms selected on the command line, or all of them if none were given
+pub fn resolve_algorithms(
+    registry: &Registry,
+    algorithms: &Option<Vec<String>>,
+) -> Result<Vec<Arc<dyn PrimalityTest>>> {
+    if algorithms.is_none() {
+        info!("❗️ No algorithm specified. Running all algorithms.");
+    }
+
+    registry.resolve(algorithms.as_deref())
+}
+
+/// The result files named on the command line, a folder standing for the
+/// .parquet files directly inside it (default: ./out). Also returns the
+/// folder of the first path, where files derived from the results go.
+pub fn resolve_result_files(paths: &[PathBuf]) -> Result<(Vec<PathBuf>, PathBuf)> {
+    let paths = if paths.is_empty() {
+        vec![PathBuf::from(DEFAULT_RESULTS_FOLDER)]
+    } else {
+        paths.to_vec()
+    };
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file() && path.extension().is_some_and(|ext| ext == "parquet")
+                })
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    if files.is_empty() {
+        return Err(IoError::new(
+            ErrorKind::NotFound,
+            format!("no .parquet files found in {}", paths[0].display()),
+        )
+        .into());
+    }
+
+    let folder = if paths[0].is_dir() {
+        paths[0].clone()
+    } else {
+        paths[0]
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    };
+
+    Ok((files, folder))
+}
@@ -0,0 +1,63 @@
+use primality_test_comparison::math::{euler_phi, gcd, mod_inverse, mod_pow, order_mod};
+use primality_test_comparison::{Error, Result};
+
+use crate::cli::parsing::ModmathOperation;
+
+pub fn handle_cli(operation: &ModmathOperation) -> Result<()> {
+    match *operation {
+        ModmathOperation::Pow {
+            base,
+            exponent,
+            modulus,
+        } => {
+            check_modulus(modulus)?;
+            println!(
+                "{}^{} mod {} = {}",
+                base,
+                exponent,
+                modulus,
+                mod_pow(base, exponent, modulus)
+            );
+        }
+        ModmathOperation::Inverse { a, modulus } => {
+            check_modulus(modulus)?;
+            let inverse = mod_inverse(a, modulus).ok_or_else(|| not_invertible(a, modulus))?;
+            println!("{}⁻¹ mod {} = {}", a, modulus, inverse);
+        }
+        ModmathOperation::Order { a, modulus } => {
+            check_modulus(modulus)?;
+            let order = order_mod(a, modulus).ok_or_else(|| not_invertible(a, modulus))?;
+            println!("ord_{}({}) = {}", modulus, a, order);
+
+            let phi = euler_phi(modulus);
+            if order == phi {
+                println!("   {} is a primitive root modulo {}", a, modulus);
+            } else {
+                println!(
+                    "   {} is a proper divisor of φ({}) = {}, so {} is not a primitive root",
+                    order, modulus, phi, a
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_modulus(modulus: u64) -> Result<()> {
+    if modulus == 0 {
+        return Err(Error::InvalidModulus {
+            modulus,
+            reason: "the modulus must be positive".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn not_invertible(a: u64, modulus: u64) -> Error {
+    Error::InvalidModulus {
+        modulus,
+        reason: format!("{} is not invertible (gcd = {})", a, gcd(a, modulus)),
+    }
+}
@@ -0,0 +1,222 @@
+//! Machine-readable results shared by the commands
+//!
+//! With `--format json` or `--format csv`, a command hands its results to a
+//! [`RecordWriter`] instead of printing text. JSON writes one object per line;
+//! CSV writes a header taken from the first record, then one row per record.
+//! Nested objects become dotted columns in CSV, and lists a JSON cell.
+//! [`PrimeWriter`] streams bare numbers instead, one per line, for pipelines.
+//!
+//! [`paint`] colors table output when it goes to a terminal, and [`count`],
+//! [`decimal`], [`si`], [`duration`] and [`bytes`] write its numbers for people to read,
+//! grouped the way the locale groups digits. Records keep plain numbers.
+
+use std::io::{BufWriter, ErrorKind, Stdout, Write};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::humanize::NumberFormat;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cli::parsing::OutputFormat;
+
+/// Writes records to stdout in the selected format
+pub struct RecordWriter {
+    format: OutputFormat,
+    // CSV columns, fixed by the first record
+    columns: Option<Vec<String>>,
+}
+
+impl RecordWriter {
+    pub fn new(format: OutputFormat) -> Self {
+        RecordWriter {
+            format,
+            columns: None,
+        }
+    }
+
+    /// True if the command prints its own text rather than records
+    pub fn is_table(&self) -> bool {
+        self.format == OutputFormat::Table
+    }
+
+    /// Prints `record` as JSON or as a CSV row; nothing in table format
+    pub fn write<T: Serialize>(&mut self, record: &T) {
+        let value = serde_json::to_value(record).expect("records always serialize");
+
+        match self.format {
+            OutputFormat::Table => {}
+            OutputFormat::Json => println!("{}", value),
+            OutputFormat::Csv => {
+                let mut cells = Vec::new();
+                flatten("", value, &mut cells);
+
+                let columns = self.columns.get_or_insert_with(|| {
+                    let header: Vec<String> = cells.iter().map(|(key, _)| key.clone()).collect();
+                    println!("{}", csv_row(&header));
+                    header
+                });
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        cells
+                            .iter()
+                            .find(|(key, _)| key == column)
+                            .map(|(_, cell)| cell.clone())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                println!("{}", csv_row(&row));
+            }
+        }
+    }
+}
+
+/// Writes primes to stdout one per line, buffered. A reader that closes the
+/// pipe early (e.g. `| head`) ends the stream rather than failing it: the
+/// writes after it are dropped and return false.
+pub struct PrimeWriter {
+    out: BufWriter<Stdout>,
+    closed: bool,
+}
+
+impl PrimeWriter {
+    pub fn new() -> Self {
+        PrimeWriter {
+            out: BufWriter::new(std::io::stdout()),
+            closed: false,
+        }
+    }
+
+    /// Writes `prime` on a line of its own; false once the reader is gone
+    pub fn write(&mut self, prime: u64) -> Result<bool> {
+        if self.closed {
+            return Ok(false);
+        }
+        let written = writeln!(self.out, "{}", prime);
+        self.check(written)
+    }
+
+    /// Hands the primes written so far to the reader; false once it is gone
+    pub fn flush(&mut self) -> Result<bool> {
+        if self.closed {
+            return Ok(false);
+        }
+        let flushed = self.out.flush();
+        self.check(flushed)
+    }
+
+    fn check(&mut self, outcome: std::io::Result<()>) -> Result<bool> {
+        match outcome {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// (column, cell) pairs of `value`, object keys joined with dots
+fn flatten(prefix: &str, value: Value, cells: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                let column = match prefix {
+                    "" => key,
+                    _ => format!("{}.{}", prefix, key),
+                };
+                flatten(&column, field, cells);
+            }
+        }
+        Value::Null => cells.push((prefix.to_string(), String::new())),
+        Value::String(text) => cells.push((prefix.to_string(), text)),
+        other => cells.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+// Cells quoted where they hold a separator, quote, or line break (RFC 4180)
+fn csv_row(cells: &[String]) -> String {
+    let quoted: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect();
+    quoted.join(",")
+}
+
+/// Colors for text printed to a terminal
+#[derive(Debug, Clone, Copy)]
+pub enum Color {
+    Red,
+    Green,
+    Bold,
+}
+
+/// `text` wrapped in ANSI codes for `color`, unchanged when stdout is not a
+/// terminal or `NO_COLOR` is set (https://no-color.org)
+pub fn paint(text: &str, color: Color) -> String {
+    use std::io::IsTerminal;
+
+    let colored = std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    if !colored {
+        return text.to_string();
+    }
+
+    let code = match color {
+        Color::Red => "31",
+        Color::Green => "32",
+        Color::Bold => "1",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+// Read from the locale once, on first use
+fn numbers() -> &'static NumberFormat {
+    static NUMBERS: OnceLock<NumberFormat> = OnceLock::new();
+    NUMBERS.get_or_init(NumberFormat::from_env)
+}
+
+/// `n` with thousands separators, e.g. `1,234,567`
+pub fn count(n: u64) -> String {
+    numbers().integer(n)
+}
+
+/// `x` with `precision` decimals and thousands separators
+pub fn decimal(x: f64, precision: usize) -> String {
+    numbers().decimal(x, precision)
+}
+
+/// `x` to three significant digits with an SI suffix, e.g. `1.23M`
+pub fn si(x: f64) -> String {
+    numbers().si(x)
+}
+
+/// `d` in the units that matter at its scale, e.g. `467.0ms` or `2m 6s`
+pub fn duration(d: Duration) -> String {
+    numbers().duration(d)
+}
+
+/// `bytes` in the largest unit that keeps the value at least 1, e.g. `1.2 MiB`
+pub fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
@@ -1,8 +1,38 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use num_bigint::BigUint;
+#[cfg(feature = "flight")]
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::time::Duration;
 use strum_macros::EnumIter;
 
-use crate::algorithms::*;
+use primality_test_comparison::algorithms::config::{AlgorithmConfig, parse_key_value};
+#[cfg(feature = "fetch")]
+use primality_test_comparison::algorithms::datasets::DOWNLOADS;
+#[cfg(feature = "datasets")]
+use primality_test_comparison::algorithms::datasets::Dataset;
+use primality_test_comparison::algorithms::lucas_lehmer::Res64;
+#[cfg(feature = "primal")]
+use primality_test_comparison::algorithms::primal;
+#[cfg(feature = "primesieve")]
+use primality_test_comparison::algorithms::primesieve;
+use primality_test_comparison::algorithms::{
+    SieveFn, TraceFn, miller_rabin, sieve_of_eratosthenes,
+};
+use primality_test_comparison::analysis::error_rate::ProbabilisticTest;
+use primality_test_comparison::analysis::timeseries::Resolution;
+use primality_test_comparison::analysis::witnesses::Reference;
+use primality_test_comparison::benchmark::Order;
+use primality_test_comparison::benchmark::bloom::DEFAULT_FALSE_POSITIVE_RATE;
+use primality_test_comparison::random::RandomSource;
+use primality_test_comparison::{Error, Result, input};
+
+use crate::cli::allocator::Allocator;
+
+// Where results are served over Arrow Flight unless told otherwise
+#[cfg(feature = "flight")]
+const DEFAULT_FLIGHT_ADDRESS: &str = "127.0.0.1:50051";
 
 #[derive(Parser)]
 #[command(name = "Primality Test Comparison")]
@@ -11,8 +41,46 @@ use crate::algorithms::*;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Algorithm-specific setting, repeatable (e.g., miller-rabin.witnesses=2,3,5,7)
+    #[arg(long = "algo-config", value_name = "KEY=VALUE", global = true, value_parser = parse_key_value)]
+    pub algo_config: Vec<(String, String)>,
+
+    /// Miller-Rabin witness bases (e.g., 2,3,5,7) or a preset: sprp2, deterministic32, deterministic64; short for --algo-config miller-rabin.witnesses=...
+    #[arg(long, value_name = "BASES|PRESET", global = true)]
+    pub witnesses: Option<String>,
+
+    /// Show more status output (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only show warnings and errors (`test` prints no results, only its exit code)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Format of the status output written to stderr (default: text)
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    pub log_format: LogFormat,
+
+    /// Format of the results of `test`, `sieve`, `stats`, and `compare` (default: table)
+    #[arg(long, value_enum, default_value = "table", global = true)]
+    pub format: OutputFormat,
+
+    /// Seed for every randomized step (e.g. verify --fuzz-check), to replay a run (default: derived from the clock)
+    #[arg(long, global = true, value_parser = input::parse_u64)]
+    pub seed: Option<u64>,
+
+    /// Sieve prime tables from scratch instead of reusing the on-disk cache (see `cache info`)
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Memory allocator to run under, recorded with benchmark runs; mimalloc and jemalloc need their features (default: system)
+    #[arg(long, value_enum, global = true)]
+    pub allocator: Option<Allocator>,
 }
 
+// Parsed once per run, so the size of the benchmark's flags does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// does testing things
@@ -21,8 +89,8 @@ pub enum Commands {
         duration: String,
 
         /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
-        #[arg(value_enum, value_delimiter = ',')]
-        algorithms: Option<Vec<PrimeAlgorithm>>,
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
 
         /// Sets a custom output folder for the results (default: ./out)
         #[arg(short, long, value_name = "FOLDER", default_value = "./out")]
@@ -31,52 +99,636 @@ pub enum Commands {
         /// Save results to a file (default: false)
         #[arg(short, long, default_value = "false")]
         save: bool,
+
+        /// Drop primes found more than once, keeping the first, before reporting and saving
+        #[arg(long)]
+        dedup: bool,
+
+        /// Self-check the algorithms first and refuse to benchmark if any fails
+        #[arg(long)]
+        selfcheck: bool,
+
+        /// Re-verify every prime found with this algorithm (e.g., miller-rabin)
+        #[arg(long, value_name = "ALGORITHM")]
+        cross_check: Option<String>,
+
+        /// Sieve the range every thread covered afterwards and check each prime in it was found exactly once
+        #[arg(long)]
+        verify_complete: bool,
+
+        /// Reject candidates with a prime factor up to BOUND before the algorithm sees them
+        #[arg(long, value_name = "BOUND", value_parser = input::parse_u64)]
+        prefilter: Option<u64>,
+
+        /// Store the run's summary metrics as baseline NAME (under OUTPUT_PATH/baselines)
+        #[arg(long, value_name = "NAME")]
+        save_baseline: Option<String>,
+
+        /// Compare the run's summary metrics with baseline NAME and fail on a regression
+        #[arg(long, value_name = "NAME")]
+        compare_baseline: Option<String>,
+
+        /// Sample CPU frequency and temperature every INTERVAL to spot throttling (default: 1s)
+        #[arg(long, value_name = "INTERVAL", num_args = 0..=1, default_missing_value = "1s")]
+        monitor: Option<String>,
+
+        /// Log a status line (elapsed, primes found, current candidate, primes/sec) every INTERVAL
+        #[arg(long, value_name = "INTERVAL")]
+        status_interval: Option<String>,
+
+        /// Spread threads evenly over the NUMA nodes, bound to their node's CPUs
+        #[arg(long)]
+        numa: bool,
+
+        /// Run one thread per physical core, pinned, leaving hyperthread siblings idle
+        #[arg(long, conflicts_with = "numa")]
+        no_smt: bool,
+
+        /// Run at nice value NICE (-20 to 19; negative values need CAP_SYS_NICE)
+        #[arg(long, value_name = "NICE", allow_negative_numbers = true, value_parser = clap::value_parser!(i32).range(-20..=19))]
+        nice: Option<i32>,
+
+        /// Run under the SCHED_RR real-time policy at PRIORITY (1 to 99, default: 1; needs CAP_SYS_NICE)
+        #[arg(long, value_name = "PRIORITY", num_args = 0..=1, default_missing_value = "1", conflicts_with = "nice", value_parser = clap::value_parser!(i32).range(1..=99))]
+        realtime: Option<i32>,
+
+        /// Test every integer from 2 upward, even ones included, instead of only 2, 3 and 6k ± 1
+        #[arg(long, conflicts_with = "shuffle")]
+        all_candidates: bool,
+
+        /// Test every number of RANGE (START..=END or START..END) in an order fixed by --seed instead of 6k ± 1 upward
+        #[arg(long, value_name = "RANGE", value_parser = parse_range, conflicts_with = "verify_complete")]
+        shuffle: Option<RangeInclusive<u64>>,
+
+        /// With --save, also write the distinct primes found to a compact .gaps file next to the Parquet file (sorted, without times or threads)
+        #[cfg(feature = "compact")]
+        #[arg(long, requires = "save")]
+        gaps: bool,
+
+        /// With --save, also write a Bloom filter over the distinct primes found next to the Parquet file
+        #[arg(long, requires = "save")]
+        bloom: bool,
+
+        /// False-positive rate the Bloom filter is sized for, between 0 and 1 (default: 0.01)
+        #[arg(long, value_name = "RATE", default_value_t = DEFAULT_FALSE_POSITIVE_RATE, value_parser = parse_rate, requires = "bloom")]
+        bloom_fp_rate: f64,
+
+        /// Write the primes to stdout, one per line, as the threads find them, instead of the final results (unordered across threads)
+        #[arg(long)]
+        stdout: bool,
+
+        /// Slowdown in percent tolerated before --compare-baseline reports a regression (default: 5)
+        #[arg(
+            long,
+            value_name = "PERCENT",
+            default_value_t = 5.0,
+            requires = "compare_baseline"
+        )]
+        regression_threshold: f64,
+
+        /// Serve the runs over Arrow Flight at ADDRESS while they go on, each with the primes found so far (default: 127.0.0.1:50051)
+        #[cfg(feature = "flight")]
+        #[arg(long, value_name = "ADDRESS", num_args = 0..=1, default_missing_value = DEFAULT_FLIGHT_ADDRESS)]
+        serve: Option<SocketAddr>,
     },
     Test {
-        /// Number to test for primality
-        number: u64,
+        /// Number to test for primality, or an expression such as 2^61-1 (any size; most algorithms stop at 2^64 or 2^128)
+        #[arg(value_parser = input::evaluate)]
+        number: BigUint,
 
         /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
-        #[arg(value_enum, value_delimiter = ',')]
-        algorithms: Option<Vec<PrimeAlgorithm>>,
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+
+        /// Print one JSON object per algorithm instead of text (same as --format json)
+        #[arg(long)]
+        json: bool,
+
+        /// Also print each algorithm's internal steps (divisors tried, witness computations, ...; table format only)
+        #[arg(long, conflicts_with = "json")]
+        trace: bool,
+
+        /// Run the algorithms at the same time, one thread each, and rank them by finishing order
+        #[arg(long, conflicts_with = "trace")]
+        race: bool,
+
+        /// Give up on an algorithm that has not finished after this long (e.g., 30s, 5m)
+        #[arg(long, value_name = "DURATION")]
+        timeout: Option<String>,
+
+        /// Take the number as an exponent p and test the Mersenne number 2^p - 1, with Lucas-Lehmer first
+        #[arg(long)]
+        mersenne: bool,
+
+        /// Also prove or disprove the number with Proth's theorem, first; it must be k·2^m + 1 with k odd and k < 2^m
+        #[arg(long, conflicts_with = "mersenne")]
+        proth: bool,
     },
     Sieve {
         /// Number to generate primes up to
+        #[arg(value_parser = input::parse_u64)]
         number: u64,
 
         /// Algorithm to use (comma-separated, e.g., sieve-of-eratosthenes)
         #[arg(value_enum, value_delimiter = ',')]
         algorithms: Option<Vec<SieveAlgorithm>>,
+
+        /// Also print the multiples each prime crosses off (small numbers only; table format only)
+        #[arg(long)]
+        trace: bool,
+
+        /// Write the primes to stdout, one per line, instead of the result
+        #[arg(long, conflicts_with = "trace")]
+        stdout: bool,
+
+        /// Also write the primes to FILE in the compact .gaps format
+        #[cfg(feature = "compact")]
+        #[arg(long, value_name = "FILE")]
+        gaps: Option<PathBuf>,
+
+        /// Also write a Bloom filter over the primes to FILE
+        #[arg(long, value_name = "FILE")]
+        bloom: Option<PathBuf>,
+
+        /// False-positive rate the Bloom filter is sized for, between 0 and 1 (default: 0.01)
+        #[arg(long, value_name = "RATE", default_value_t = DEFAULT_FALSE_POSITIVE_RATE, value_parser = parse_rate, requires = "bloom")]
+        bloom_fp_rate: f64,
+
+        /// Also write the primes to FILE as a raw mod-30 wheel bitmap in primesieve's layout (30 numbers per byte)
+        #[arg(long, value_name = "FILE")]
+        wheel_bitmap: Option<PathBuf>,
+    },
+    /// Stream the primes in a range to stdout, one per line, as a segmented sieve finds them
+    Primes {
+        /// First number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        start: u64,
+
+        /// Last number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        end: u64,
+
+        /// Read the primes from a .gaps file (written by `sieve --gaps` or `benchmark --gaps`) instead of sieving
+        #[cfg(feature = "compact")]
+        #[arg(long, value_name = "FILE")]
+        from: Option<PathBuf>,
+    },
+    /// Describe a .gaps prime file and look numbers up in it
+    #[cfg(feature = "compact")]
+    Inspect {
+        /// The .gaps file (written by `sieve --gaps` or `benchmark --gaps`)
+        file: PathBuf,
+
+        /// Numbers to look up in the file
+        #[arg(value_parser = input::parse_u64)]
+        numbers: Vec<u64>,
+    },
+    /// Look numbers up in a Bloom filter written by `sieve --bloom` or `benchmark --bloom`
+    Query {
+        /// The .bloom file
+        file: PathBuf,
+
+        /// Numbers to look up
+        #[arg(required = true, value_parser = input::parse_u64)]
+        numbers: Vec<u64>,
+    },
+    /// Time algorithms across magnitudes of n and fit their complexity
+    Sweep {
+        /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+
+        /// Largest power of ten to sweep up to (default: 12, i.e. 10^1..10^12)
+        #[arg(short = 'e', long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(1..=19))]
+        max_exponent: u32,
+
+        /// Number of primes timed at each magnitude (default: 100)
+        #[arg(short = 'n', long, default_value_t = 100, value_parser = parse_count)]
+        samples: u64,
+
+        /// Stop sweeping an algorithm once one magnitude takes longer than this (default: 10s)
+        #[arg(short, long, default_value = "10s")]
+        time_limit: String,
+
+        /// Order the numbers are timed in; shuffled draws from --seed (default: ascending)
+        #[arg(long, value_enum, default_value_t = CandidateOrder::Ascending)]
+        order: CandidateOrder,
+    },
+    /// Find the fastest algorithm for each size of number and write a dispatch profile for `auto`
+    Tune {
+        /// Algorithms to time (comma-separated; default: all but auto)
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+
+        /// Bit lengths ending each bucket (comma-separated, 2 to 64; default: every 8 bits)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_value = "8,16,24,32,40,48,56,64",
+            value_parser = clap::value_parser!(u32).range(2..=64)
+        )]
+        bits: Vec<u32>,
+
+        /// Numbers timed in each bucket (default: 200)
+        #[arg(short = 'n', long, default_value_t = 200, value_parser = input::parse_u64)]
+        samples: u64,
+
+        /// Leave an algorithm out of larger buckets once one takes longer than this (default: 1s)
+        #[arg(short, long, default_value = "1s")]
+        time_limit: String,
+
+        /// Where to write the profile
+        #[arg(short, long, value_name = "FILE", default_value = "auto-profile.json")]
+        output: PathBuf,
+
+        /// Order the numbers are timed in; shuffled draws from --seed (default: ascending)
+        #[arg(long, value_enum, default_value_t = CandidateOrder::Ascending)]
+        order: CandidateOrder,
+    },
+    /// Time two algorithms on the same primes and test whether the difference is significant, or compare result files from two machines
+    Compare {
+        /// First algorithm (or pipeline) to time, or a result file to compare machines with
+        first: String,
+
+        /// Second algorithm (or pipeline) to time, or the result file to compare with the first
+        second: String,
+
+        /// Time primes from 10^EXPONENT upwards (default: 9)
+        #[arg(short = 'e', long, default_value_t = 9, value_parser = clap::value_parser!(u32).range(1..=19))]
+        exponent: u32,
+
+        /// Number of primes timed, one latency sample each (default: 200)
+        #[arg(short = 'n', long, default_value_t = 200, value_parser = clap::value_parser!(u64).range(2..))]
+        samples: u64,
+
+        /// Significance level for the tests (default: 0.05)
+        #[arg(long, default_value_t = 0.05)]
+        alpha: f64,
+
+        /// Order the numbers are timed in; shuffled draws from --seed (default: ascending)
+        #[arg(long, value_enum, default_value_t = CandidateOrder::Ascending)]
+        order: CandidateOrder,
+    },
+    /// Check the machine for conditions known to ruin benchmarks
+    Doctor,
+    /// Package Parquet results, their metadata, and a report into a .tar.gz to share
+    ExportBundle {
+        /// Result files or folders holding them (default: ./out)
+        paths: Vec<PathBuf>,
+
+        /// Archive to write (default: bundle.tar.gz in the first folder given)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Analyze the primes in Parquet results: coverage, density, gaps, and residues
+    Stats {
+        /// Result files or folders holding them (default: ./out)
+        paths: Vec<PathBuf>,
+
+        /// Tabulate the primes by residue mod each M (comma-separated, default: 10, i.e. last digit)
+        #[arg(long, value_name = "M", num_args = 0..=1, value_delimiter = ',', default_missing_value = "10", value_parser = clap::value_parser!(u64).range(2..=1000))]
+        residues: Option<Vec<u64>>,
+
+        /// Tabulate the primes found per bucket of time: SLICES equal slices or buckets of WIDTH, e.g. 1m
+        #[arg(long, value_name = "SLICES|WIDTH")]
+        over_time: Option<Resolution>,
+    },
+    /// Chart Parquet results in a standalone HTML page with interactive plots
+    Report {
+        /// Result files or folders holding them (default: ./out)
+        paths: Vec<PathBuf>,
+
+        /// Page to write (default: report.html in the first folder given)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Chart throughput over SLICES equal slices of each run or buckets of WIDTH, e.g. 1s or 1m (default: 200)
+        #[arg(long, value_name = "SLICES|WIDTH", default_value = "200")]
+        bucket: Resolution,
+    },
+    /// Serve Parquet results over Arrow Flight, one flight per file, until interrupted
+    #[cfg(feature = "flight")]
+    Serve {
+        /// Result files or folders holding them (default: ./out)
+        paths: Vec<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = DEFAULT_FLIGHT_ADDRESS)]
+        address: SocketAddr,
     },
+    /// Check that algorithms agree with Miller-Rabin on every number in a range
+    Verify {
+        /// First number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        start: u64,
+
+        /// Last number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        end: u64,
+
+        /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+
+        /// Check this many random numbers from the range instead of every number
+        #[arg(long, value_name = "COUNT", value_parser = input::parse_u64)]
+        fuzz_check: Option<u64>,
+
+        /// Where to write every mismatch as JSON lines, if there are any
+        #[arg(long, value_name = "FILE", default_value = "verify-mismatches.jsonl")]
+        mismatch_file: PathBuf,
+    },
+    /// Validate algorithms against a table of known primes, composites, and pseudoprimes
+    Selfcheck {
+        /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+    },
+    /// Validate algorithms against bundled datasets: the first 10^6 primes, strong pseudoprimes, and Carmichael numbers
+    #[cfg(feature = "datasets")]
+    Validate {
+        /// Algorithm to use (comma-separated, e.g., trial-division,miller-rabin)
+        #[arg(value_delimiter = ',')]
+        algorithms: Option<Vec<String>>,
+
+        /// Datasets to check against (comma-separated; default: all)
+        #[arg(short, long, value_enum, value_delimiter = ',')]
+        datasets: Option<Vec<DatasetName>>,
+
+        /// Folder the datasets are read from (default: $PTC_DATA_DIR, else the data folder of the repository)
+        #[arg(long, value_name = "DIR")]
+        data_dir: Option<PathBuf>,
+
+        /// Move on from a dataset once an algorithm has spent this long on it, reporting how far it got (default: 10s)
+        #[arg(short, long, default_value = "10s")]
+        time_limit: String,
+    },
+    /// Download public prime and pseudoprime lists (OEIS b-files) into the cache for `validate` and `analyze`
+    #[cfg(feature = "fetch")]
+    FetchDatasets {
+        /// Lists to download (comma-separated; default: all)
+        #[arg(value_delimiter = ',', value_parser = clap::builder::PossibleValuesParser::new(DOWNLOADS.map(|download| download.name)))]
+        names: Option<Vec<String>>,
+
+        /// Folder the lists are written to (default: datasets under the cache directory)
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        /// Download lists again even if they are already cached
+        #[arg(long)]
+        force: bool,
+    },
+    /// Measure how often randomized tests with k random witnesses accept a composite
+    ErrorRate {
+        /// First number composites are drawn from (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        start: u64,
+
+        /// Last number composites are drawn from (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        end: u64,
+
+        /// Tests to measure (comma-separated; default: all)
+        #[arg(value_enum, value_delimiter = ',')]
+        tests: Option<Vec<ProbabilisticAlgorithm>>,
+
+        /// Random witnesses per composite, each count measured separately (comma-separated)
+        #[arg(short, long, value_delimiter = ',', default_value = "1,2,3,4", value_parser = clap::value_parser!(u32).range(1..=64))]
+        rounds: Vec<u32>,
+
+        /// Composites drawn from the range, from --seed (default: 100000)
+        #[arg(short = 'n', long, default_value_t = 100_000, value_parser = input::parse_u64)]
+        samples: u64,
+    },
+    /// Count the strong pseudoprimes to a set of bases up to a bound, with the sieve as ground truth
+    Pseudoprimes {
+        /// Largest number checked (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        limit: u64,
+
+        /// Miller-Rabin bases a number must pass all of (comma-separated, default: 2)
+        #[arg(short, long, value_delimiter = ',', default_value = "2", value_parser = input::parse_u64)]
+        bases: Vec<u64>,
+
+        /// Also print every pseudoprime found, not just how many
+        #[arg(long)]
+        list: bool,
+    },
+    /// Tabulate which Miller-Rabin witnesses detect the odd composites in a range
+    Witnesses {
+        /// First number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        start: u64,
+
+        /// Last number of the range (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        end: u64,
+
+        /// Witnesses to compare, in the order a set would try them (comma-separated, default: the primes up to 37)
+        #[arg(short, long, value_delimiter = ',', default_value = "2,3,5,7,11,13,17,19,23,29,31,37", value_parser = input::parse_u64)]
+        bases: Vec<u64>,
+    },
+    /// Check that a Miller-Rabin witness set is deterministic for every odd number up to a bound
+    VerifyWitnessSet {
+        /// Witnesses (comma-separated, e.g., 2,7,61) or a preset: sprp2, deterministic32, deterministic64, default
+        #[arg(value_name = "WITNESSES", value_parser = parse_witness_set)]
+        witness_set: WitnessSet,
+
+        /// Largest number checked (inclusive)
+        #[arg(value_parser = input::parse_u64)]
+        bound: u64,
+
+        /// First number checked, to resume an interrupted run (default: 0)
+        #[arg(long, default_value_t = 0, value_parser = input::parse_u64)]
+        from: u64,
+
+        /// What decides whether an accepted number is prime (default: bpsw)
+        #[arg(long, value_enum, default_value_t = WitnessReference::Bpsw)]
+        reference: WitnessReference,
+
+        /// Threads checking each block (default: 0, one per core)
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    /// Run the Lucas-Lehmer test on 2^p - 1, printing GIMPS-style residues to double-check
+    LucasLehmer {
+        /// Exponent p of the Mersenne number 2^p - 1, a prime
+        #[arg(value_parser = clap::value_parser!(u32).range(2..))]
+        p: u32,
+
+        /// Also print the interim residue every N iterations
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        checkpoint: Option<u32>,
+
+        /// Res64 of a previous run to check the final residue against (16 hex digits)
+        #[arg(long, value_name = "RES64")]
+        verify: Option<Res64>,
+    },
+    /// Inspect a number and recommend the primality test or proof that suits it, with the reasoning
+    Analyze {
+        /// Number to inspect, or an expression such as 2^127-1 (any size)
+        #[arg(value_parser = input::evaluate)]
+        number: BigUint,
+    },
+    /// Compute the Jacobi symbol (a/n), which is the Legendre symbol when n is prime
+    Jacobi {
+        /// Numerator of the symbol
+        #[arg(value_parser = input::parse_u64)]
+        a: u64,
+
+        /// Odd positive modulus
+        #[arg(value_parser = input::parse_u64)]
+        n: u64,
+    },
+    /// Compute the Lucas sequence terms U_k(P, Q) and V_k(P, Q) modulo n
+    Lucas {
+        /// Parameter P, which may be negative
+        #[arg(allow_negative_numbers = true, value_parser = input::parse_i64)]
+        p: i64,
+
+        /// Parameter Q, which may be negative
+        #[arg(allow_negative_numbers = true, value_parser = input::parse_i64)]
+        q: i64,
+
+        /// Index of the term
+        #[arg(value_parser = input::parse_u64)]
+        k: u64,
+
+        /// Positive modulus
+        #[arg(value_parser = input::parse_u64)]
+        n: u64,
+
+        /// List every term from 0 up to k instead of term k alone
+        #[arg(long)]
+        all: bool,
+    },
+    /// Modular exponentiation, inverses, and multiplicative orders
+    Modmath {
+        #[command(subcommand)]
+        operation: ModmathOperation,
+    },
+    /// Show or clear the sieve bitmaps cached on disk between runs
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// List the registered primality testing algorithms
+    List,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, EnumIter)]
-pub enum PrimeAlgorithm {
-    Aks,
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// Show the cache directory and the bitmaps in it
+    Info,
+    /// Delete every cached bitmap
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum ModmathOperation {
+    /// Compute base^exponent mod modulus
+    Pow {
+        #[arg(value_parser = input::parse_u64)]
+        base: u64,
+        #[arg(value_parser = input::parse_u64)]
+        exponent: u64,
+        #[arg(value_parser = input::parse_u64)]
+        modulus: u64,
+    },
+    /// Compute the x with a·x ≡ 1 (mod modulus)
+    Inverse {
+        #[arg(value_parser = input::parse_u64)]
+        a: u64,
+        #[arg(value_parser = input::parse_u64)]
+        modulus: u64,
+    },
+    /// Compute the smallest k ≥ 1 with a^k ≡ 1 (mod modulus)
+    Order {
+        #[arg(value_parser = input::parse_u64)]
+        a: u64,
+        #[arg(value_parser = input::parse_u64)]
+        modulus: u64,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum CandidateOrder {
+    Ascending,
+    Descending,
+    Shuffled,
+}
+
+impl CandidateOrder {
+    /// The order, shuffling with numbers drawn from `random`
+    pub fn with_random(self, random: &RandomSource) -> Order {
+        match self {
+            CandidateOrder::Ascending => Order::Ascending,
+            CandidateOrder::Descending => Order::Descending,
+            CandidateOrder::Shuffled => Order::Shuffled(random.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ProbabilisticAlgorithm {
+    Fermat,
     MillerRabin,
-    TrialDivision,
-    TrialDivisionNewton,
-    TrialDivisionSqrt,
+    SolovayStrassen,
 }
 
-impl PrimeAlgorithm {
-    pub fn as_str(&self) -> &'static str {
+impl ProbabilisticAlgorithm {
+    pub fn test(self) -> ProbabilisticTest {
+        match self {
+            ProbabilisticAlgorithm::Fermat => ProbabilisticTest::Fermat,
+            ProbabilisticAlgorithm::MillerRabin => ProbabilisticTest::MillerRabin,
+            ProbabilisticAlgorithm::SolovayStrassen => ProbabilisticTest::SolovayStrassen,
+        }
+    }
+}
+
+#[cfg(feature = "datasets")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DatasetName {
+    Primes,
+    StrongPseudoprimes,
+    Carmichael,
+}
+
+#[cfg(feature = "datasets")]
+impl DatasetName {
+    pub fn dataset(self) -> Dataset {
         match self {
-            PrimeAlgorithm::Aks => "aks",
-            PrimeAlgorithm::MillerRabin => "miller-rabin",
-            PrimeAlgorithm::TrialDivision => "trial-division",
-            PrimeAlgorithm::TrialDivisionNewton => "trial-division-newton",
-            PrimeAlgorithm::TrialDivisionSqrt => "trial-division-sqrt",
+            DatasetName::Primes => Dataset::Primes,
+            DatasetName::StrongPseudoprimes => Dataset::StrongPseudoprimes,
+            DatasetName::Carmichael => Dataset::Carmichael,
         }
     }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum WitnessReference {
+    Bpsw,
+    MillerRabin,
+}
 
-    pub fn as_algorithm_fn(&self) -> fn(u64) -> bool {
+impl WitnessReference {
+    pub fn reference(self) -> Reference {
         match self {
-            PrimeAlgorithm::Aks => aks::is_prime,
-            PrimeAlgorithm::MillerRabin => miller_rabin::is_prime,
-            PrimeAlgorithm::TrialDivision => trial_division::is_prime,
-            PrimeAlgorithm::TrialDivisionNewton => trial_division_newton::is_prime,
-            PrimeAlgorithm::TrialDivisionSqrt => trial_division_sqrt::is_prime,
+            WitnessReference::Bpsw => Reference::Bpsw,
+            WitnessReference::MillerRabin => Reference::MillerRabin,
         }
     }
 }
@@ -84,18 +736,129 @@ impl PrimeAlgorithm {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, EnumIter)]
 pub enum SieveAlgorithm {
     SieveOfEratosthenes,
+    #[cfg(feature = "primesieve")]
+    Primesieve,
+    #[cfg(feature = "primal")]
+    Primal,
 }
 
 impl SieveAlgorithm {
     pub fn as_str(&self) -> &'static str {
         match self {
             SieveAlgorithm::SieveOfEratosthenes => "sieve-of-eratosthenes",
+            #[cfg(feature = "primesieve")]
+            SieveAlgorithm::Primesieve => "primesieve",
+            #[cfg(feature = "primal")]
+            SieveAlgorithm::Primal => "primal",
         }
     }
 
-    pub fn as_algorithm_fn(&self) -> fn(u64) -> Vec<u64> {
+    pub fn as_algorithm_fn(&self, config: &AlgorithmConfig) -> Result<SieveFn> {
+        let algorithm_fn: SieveFn = match self {
+            SieveAlgorithm::SieveOfEratosthenes => {
+                match config.get::<usize>("sieve-of-eratosthenes.segment-size")? {
+                    Some(segment_size) => {
+                        Box::new(move |n| sieve_of_eratosthenes::sieve_segmented(n, segment_size))
+                    }
+                    None => Box::new(sieve_of_eratosthenes::sieve),
+                }
+            }
+            #[cfg(feature = "primesieve")]
+            SieveAlgorithm::Primesieve => Box::new(primesieve::sieve),
+            #[cfg(feature = "primal")]
+            SieveAlgorithm::Primal => Box::new(primal::sieve),
+        };
+
+        Ok(algorithm_fn)
+    }
+
+    /// Runs the sieve up to `n` while describing its steps to `step`
+    pub fn trace(&self, n: u64, step: TraceFn<'_>) -> Vec<u64> {
         match self {
-            SieveAlgorithm::SieveOfEratosthenes => sieve_of_eratosthenes::sieve,
+            SieveAlgorithm::SieveOfEratosthenes => sieve_of_eratosthenes::trace(n, step),
+            // An external library, run as a black box
+            #[cfg(feature = "primesieve")]
+            SieveAlgorithm::Primesieve => {
+                step(format_args!(
+                    "primesieve {} is an external library and reports no steps",
+                    primesieve::version()
+                ));
+                primesieve::sieve(n)
+            }
+            #[cfg(feature = "primal")]
+            SieveAlgorithm::Primal => {
+                step(format_args!(
+                    "primal is an external crate and reports no steps"
+                ));
+                primal::sieve(n)
+            }
         }
     }
 }
+
+/// Miller-Rabin witnesses given as one argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessSet(pub Vec<u64>);
+
+/// A witness preset name or comma-separated bases
+pub fn parse_witness_set(s: &str) -> Result<WitnessSet> {
+    match miller_rabin::witness_preset(s.trim()) {
+        Some(witnesses) => Ok(WitnessSet(witnesses.to_vec())),
+        None => s
+            .split(',')
+            .map(input::parse_u64)
+            .collect::<Result<_>>()
+            .map(WitnessSet),
+    }
+}
+
+/// `START..=END`, or `START..END` without END, each a number or expression
+pub fn parse_range(s: &str) -> Result<RangeInclusive<u64>> {
+    let invalid = |reason: &str| Error::InvalidNumber {
+        input: s.to_string(),
+        reason: reason.to_string(),
+    };
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| invalid("expected START..=END or START..END"))?;
+    let start = input::parse_u64(start)?;
+    let end = match end.strip_prefix('=') {
+        Some(end) => input::parse_u64(end)?,
+        None => input::parse_u64(end)?
+            .checked_sub(1)
+            .ok_or_else(|| invalid("empty range"))?,
+    };
+    if end < start {
+        return Err(invalid("empty range"));
+    }
+    Ok(start..=end)
+}
+
+/// A number or expression of at least 1
+pub fn parse_count(s: &str) -> Result<u64> {
+    Some(input::parse_u64(s)?)
+        .filter(|&count| count > 0)
+        .ok_or_else(|| Error::InvalidNumber {
+            input: s.to_string(),
+            reason: "expected at least 1".to_string(),
+        })
+}
+
+/// A probability strictly between 0 and 1
+pub fn parse_rate(s: &str) -> Result<f64> {
+    s.trim()
+        .parse()
+        .ok()
+        .filter(|rate| *rate > 0.0 && *rate < 1.0)
+        .ok_or_else(|| Error::InvalidNumber {
+            input: s.to_string(),
+            reason: "expected a rate strictly between 0 and 1".to_string(),
+        })
+}
+
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    humantime::parse_duration(s).map_err(|e| Error::InvalidDuration {
+        input: s.to_string(),
+        reason: e.to_string(),
+    })
+}
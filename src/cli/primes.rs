@@ -0,0 +1,76 @@
+#[cfg(feature = "compact")]
+use std::path::Path;
+
+use primality_test_comparison::algorithms::sieve_of_eratosthenes::PrimeSegments;
+#[cfg(feature = "compact")]
+use primality_test_comparison::benchmark::compact::GapFile;
+use primality_test_comparison::{Error, Result};
+use tracing::{debug, info};
+
+use crate::cli::output::{self, PrimeWriter};
+
+// Numbers sieved at once; each segment's primes are written as soon as it is done
+const SEGMENT_SIZE: usize = 1 << 18;
+
+pub fn handle_cli(
+    start: u64,
+    end: u64,
+    #[cfg(feature = "compact")] from: Option<&Path>,
+) -> Result<()> {
+    if start > end {
+        return Err(Error::InvalidRange { start, end });
+    }
+
+    // Only the blocks of the file that overlap the range are decompressed
+    #[cfg(feature = "compact")]
+    if let Some(path) = from {
+        let file = GapFile::open(path)?;
+        info!(
+            "🗜️  Streaming the primes in [{}, {}] from {} ({} primes in {} blocks)...",
+            start,
+            end,
+            path.display(),
+            output::count(file.len()),
+            file.blocks().len()
+        );
+        return stream(start, end, file.blocks_in(start..=end));
+    }
+
+    info!("🔢 Streaming the primes in [{}, {}]...", start, end);
+    stream(
+        start,
+        end,
+        PrimeSegments::new(start, end, SEGMENT_SIZE).map(Ok),
+    )
+}
+
+// Writes the primes of each segment, handing them to the reader after each
+fn stream(start: u64, end: u64, segments: impl Iterator<Item = Result<Vec<u64>>>) -> Result<()> {
+    let start_time = std::time::Instant::now();
+    let mut out = PrimeWriter::new();
+    let mut count = 0;
+    for primes in segments {
+        let primes = primes?;
+        for &prime in &primes {
+            if !out.write(prime)? {
+                debug!(prime, "stdout closed, stopping");
+                return Ok(());
+            }
+        }
+        count += primes.len() as u64;
+        if !out.flush()? {
+            debug!("stdout closed, stopping");
+            return Ok(());
+        }
+    }
+
+    info!(
+        "✅ {} primes in [{}, {}], in {}",
+        output::count(count),
+        start,
+        end,
+        output::duration(start_time.elapsed())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use primality_test_comparison::Result;
+use primality_test_comparison::analysis::pseudoprimes::strong_pseudoprime_census;
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::OutputFormat;
+
+// The census in the JSON and CSV formats
+#[derive(Serialize)]
+struct CensusRecord {
+    bases: Vec<u64>,
+    limit: u64,
+    odd_composites: u64,
+    pseudoprimes: usize,
+    rate: f64,
+    smallest: Option<u64>,
+    duration_ns: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    list: Option<Vec<u64>>,
+}
+
+pub fn handle_cli(limit: u64, bases: &[u64], list: bool, format: OutputFormat) -> Result<()> {
+    let mut output = RecordWriter::new(format);
+    let named = bases
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let named = match bases.len() {
+        1 => format!("base {}", named),
+        _ => format!("every base of {}", named),
+    };
+    info!(
+        "🔍 Finding the strong pseudoprimes to {} up to {}...",
+        named, limit
+    );
+
+    let start_time = std::time::Instant::now();
+    let census = strong_pseudoprime_census(bases, limit);
+    let duration = start_time.elapsed();
+
+    if !output.is_table() {
+        output.write(&CensusRecord {
+            bases: census.bases.clone(),
+            limit,
+            odd_composites: census.odd_composites,
+            pseudoprimes: census.pseudoprimes.len(),
+            rate: census.rate(),
+            smallest: census.pseudoprimes.first().copied(),
+            duration_ns: duration.as_nanos(),
+            list: list.then(|| census.pseudoprimes.clone()),
+        });
+        return Ok(());
+    }
+
+    println!(
+        "\n✅ Result: {} strong pseudoprimes to {} among the {} odd composites up to {}",
+        output::count(census.pseudoprimes.len() as u64),
+        named,
+        output::count(census.odd_composites),
+        limit
+    );
+    match census.pseudoprimes.first() {
+        Some(smallest) => println!(
+            "   1 in {} odd composites passes, the smallest being {}",
+            output::decimal(1.0 / census.rate(), 0),
+            smallest
+        ),
+        None => println!(
+            "   The bases prove every odd composite up to {} composite",
+            limit
+        ),
+    }
+    println!("⏱️  Time taken: {}", output::duration(duration));
+
+    if list {
+        for pseudoprime in &census.pseudoprimes {
+            println!("{}", pseudoprime);
+        }
+    }
+
+    Ok(())
+}
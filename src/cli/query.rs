@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::benchmark::BloomFilter;
+use primality_test_comparison::benchmark::schema::COVERED_KEY;
+use tracing::info;
+
+use crate::cli::output;
+
+pub fn handle_cli(path: &Path, numbers: &[u64]) -> Result<()> {
+    let (filter, metadata) = BloomFilter::load(path)?;
+    let covered_up_to: Option<u64> = metadata
+        .iter()
+        .find(|(key, _)| key == COVERED_KEY)
+        .and_then(|(_, value)| value.parse().ok());
+
+    println!(
+        "🌸 {}: {} primes in {} bits, {} hashes each, {:.4}% false positives expected",
+        path.display(),
+        output::count(filter.len()),
+        output::count(filter.bits()),
+        filter.hashes(),
+        filter.false_positive_rate() * 100.0
+    );
+
+    // A number the filter rejects is certainly not among its primes, which
+    // makes it composite only where every prime was inserted
+    for &n in numbers {
+        let verdict = if filter.contains(n) {
+            "probably prime"
+        } else if covered_up_to.is_some_and(|covered| n <= covered) {
+            "composite"
+        } else {
+            "not among the primes found"
+        };
+        println!("   {}: {}", n, verdict);
+    }
+
+    Ok(())
+}
+
+/// Reports a Bloom filter written to `path` in `bytes` bytes
+pub fn log_bloom(path: &str, filter: &BloomFilter, bytes: u64) {
+    info!(
+        "🌸 Bloom filter written to: {} ({}, {:.2} bits per prime, {:.4}% false positives expected)",
+        path,
+        output::bytes(bytes),
+        filter.bits() as f64 / filter.len().max(1) as f64,
+        filter.false_positive_rate() * 100.0
+    );
+}
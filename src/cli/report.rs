@@ -0,0 +1,31 @@
+use std::path::{Path, PathBuf};
+
+use primality_test_comparison::Result;
+use primality_test_comparison::analysis::timeseries::Resolution;
+use primality_test_comparison::benchmark::html_report;
+use tracing::info;
+
+use crate::cli::resolve_result_files;
+
+const DEFAULT_REPORT: &str = "report.html";
+
+pub fn handle_cli(paths: &[PathBuf], output: Option<&Path>, bucket: Resolution) -> Result<()> {
+    let (files, folder) = resolve_result_files(paths)?;
+
+    // Next to the results unless told otherwise
+    let output = output.map_or_else(|| folder.join(DEFAULT_REPORT), Path::to_path_buf);
+
+    info!(
+        "📊 Charting {} result file(s), throughput {}...",
+        files.len(),
+        bucket
+    );
+    for file in &files {
+        println!("   {}", file.display());
+    }
+
+    html_report::write_html_report(&files, &output, bucket)?;
+    info!("💾 Report written to: {}", output.display());
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use primality_test_comparison::algorithms::selfcheck::{KNOWN_COMPOSITES, KNOWN_PRIMES, selfcheck};
+use primality_test_comparison::algorithms::{PrimalityTest, Registry};
+use primality_test_comparison::{Error, Result};
+use tracing::info;
+
+use crate::cli::resolve_algorithms;
+
+pub fn handle_cli(algorithms: &Option<Vec<String>>, registry: &Registry) -> Result<()> {
+    check(&resolve_algorithms(registry, algorithms)?)
+}
+
+/// Validates `algorithms` against the known primes and composites, failing
+/// with the names of those that get any of them wrong
+pub fn check(algorithms: &[Arc<dyn PrimalityTest>]) -> Result<()> {
+    info!(
+        "🩺 Self-checking against {} known primes and {} known composites...",
+        KNOWN_PRIMES.len(),
+        KNOWN_COMPOSITES.len()
+    );
+
+    let mut failed = Vec::new();
+    for alg in algorithms {
+        let failures = selfcheck(alg.as_ref());
+
+        if failures.is_empty() {
+            println!("   ✅ {}", alg.name());
+        } else {
+            let numbers: Vec<String> = failures.iter().map(u64::to_string).collect();
+            println!("   ❌ {}: wrong on {}", alg.name(), numbers.join(", "));
+            failed.push(alg.name().to_string());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(Error::SelfCheckFailed {
+            algorithms: failed.join(", "),
+        });
+    }
+
+    Ok(())
+}
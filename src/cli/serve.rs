@@ -0,0 +1,36 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::benchmark::flight::ResultServer;
+use primality_test_comparison::benchmark::parquet::read_from_parquet;
+use tracing::info;
+
+use crate::cli::resolve_result_files;
+
+/// Serves the result files over Arrow Flight until interrupted, each as a
+/// set named after its file
+pub fn handle_cli(paths: &[PathBuf], address: SocketAddr) -> Result<()> {
+    let (files, _) = resolve_result_files(paths)?;
+    let server = ResultServer::new();
+
+    for file in &files {
+        let (primes, metadata) = read_from_parquet(&file.to_string_lossy())?;
+        let name = file
+            .file_stem()
+            .map_or_else(|| file.to_string_lossy(), |stem| stem.to_string_lossy());
+        info!(
+            "📂 {} ({} primes) as '{}'",
+            file.display(),
+            primes.len(),
+            name
+        );
+        server.publish(name, primes, metadata);
+    }
+
+    let handle = server.serve(address)?;
+    info!("⏳ Press Ctrl-C to stop");
+    handle.wait();
+
+    Ok(())
+}
@@ -1,33 +1,175 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
 use strum::IntoEnumIterator;
 
-use crate::cli::parsing::SieveAlgorithm;
+use crate::cli::parsing::{OutputFormat, SieveAlgorithm};
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::config::AlgorithmConfig;
+use primality_test_comparison::benchmark::BloomFilter;
+#[cfg(feature = "compact")]
+use primality_test_comparison::benchmark::compact::write_gaps;
+use primality_test_comparison::benchmark::schema::{ALGORITHM_KEY, COVERED_KEY};
+use primality_test_comparison::benchmark::wheel::write_wheel_bitmap;
+use tracing::{info, warn};
+
+use crate::cli::output::{self, PrimeWriter, RecordWriter};
+use crate::cli::print_trace;
+use crate::cli::query::log_bloom;
+
+// Largest number `sieve --trace` lists the crossed-off multiples for
+const MAX_TRACED_NUMBER: u64 = 1000;
+
+/// What to do with the primes besides counting them
+pub struct Options {
+    /// List the multiples each prime crosses off
+    pub trace: bool,
+    /// Write the primes to stdout in place of the result
+    pub stdout: bool,
+    /// File to write the primes to in the compact .gaps format
+    #[cfg(feature = "compact")]
+    pub gaps: Option<PathBuf>,
+    /// File to write a Bloom filter over the primes to
+    pub bloom: Option<PathBuf>,
+    /// False-positive rate the Bloom filter is sized for
+    pub bloom_fp_rate: f64,
+    /// File to write the primes to as a mod-30 wheel bitmap
+    pub wheel_bitmap: Option<PathBuf>,
+}
+
+// One algorithm's sieve in the JSON and CSV formats
+#[derive(Serialize)]
+struct SieveRecord {
+    algorithm: &'static str,
+    limit: u64,
+    primes: usize,
+    duration_ns: u128,
+}
+
+pub fn handle_cli(
+    number: u64,
+    algorithms: &Option<Vec<SieveAlgorithm>>,
+    options: Options,
+    format: OutputFormat,
+    config: &AlgorithmConfig,
+) -> Result<()> {
+    let mut output = RecordWriter::new(format);
 
-pub fn handle_cli(number: u64, algorithms: &Option<Vec<SieveAlgorithm>>) {
     // Run benchmark
     if algorithms.is_none() {
-        println!("❗️ No algorithm specified. Running all algorithms.");
+        info!("❗️ No algorithm specified. Running all algorithms.");
 
         for alg in SieveAlgorithm::iter() {
-            run_sieve(number, alg);
+            run_sieve(number, alg, &options, &mut output, config)?;
         }
     } else {
         for alg in algorithms.as_ref().unwrap() {
-            run_sieve(number, *alg);
+            run_sieve(number, *alg, &options, &mut output, config)?;
         }
     }
+
+    Ok(())
 }
 
-fn run_sieve(number: u64, algorithm: SieveAlgorithm) {
-    println!(
+fn run_sieve(
+    number: u64,
+    algorithm: SieveAlgorithm,
+    options: &Options,
+    output: &mut RecordWriter,
+    config: &AlgorithmConfig,
+) -> Result<()> {
+    info!(
         "🔍 Testing sieve algorithm '{}' for numbers up to {}...",
         algorithm.as_str(),
         number
     );
 
+    let algorithm_fn = algorithm.as_algorithm_fn(config)?;
+
     let start_time = std::time::Instant::now();
-    let primes = algorithm.as_algorithm_fn()(number);
+    let primes = algorithm_fn(number);
     let duration = start_time.elapsed();
 
-    println!("\n✅ Result: [0, {}] has {} primes", number, primes.len());
-    println!("⏱️  Time taken: {:.4?}", duration);
+    // Every prime up to the limit is in the file
+    #[cfg(feature = "compact")]
+    if let Some(path) = &options.gaps {
+        let metadata = [
+            (ALGORITHM_KEY.to_string(), algorithm.as_str().to_string()),
+            (COVERED_KEY.to_string(), number.to_string()),
+        ];
+        let summary = write_gaps(path, primes.iter().copied(), &metadata)?;
+        info!(
+            "🗜️  Primes written to: {} ({}, {:.2} bytes per prime)",
+            path.display(),
+            output::bytes(summary.bytes),
+            summary.bytes as f64 / summary.primes.max(1) as f64
+        );
+    }
+
+    if let Some(path) = &options.bloom {
+        let filter = BloomFilter::from_primes(&primes, options.bloom_fp_rate);
+        let metadata = [
+            (ALGORITHM_KEY.to_string(), algorithm.as_str().to_string()),
+            (COVERED_KEY.to_string(), number.to_string()),
+        ];
+        let bytes = filter.save(path, &metadata)?;
+        log_bloom(&path.display().to_string(), &filter, bytes);
+    }
+
+    if let Some(path) = &options.wheel_bitmap {
+        let bytes = write_wheel_bitmap(path, &primes, number)?;
+        info!(
+            "🎡 Wheel bitmap written to: {} ({}, 30 numbers per byte)",
+            path.display(),
+            output::bytes(bytes)
+        );
+    }
+
+    // The primes themselves take the place of the result
+    if options.stdout {
+        let mut out = PrimeWriter::new();
+        for &prime in &primes {
+            if !out.write(prime)? {
+                break;
+            }
+        }
+        out.flush()?;
+        info!(
+            "✅ [0, {}] has {} primes, sieved in {}",
+            number,
+            output::count(primes.len() as u64),
+            output::duration(duration)
+        );
+        return Ok(());
+    }
+
+    if !output.is_table() {
+        output.write(&SieveRecord {
+            algorithm: algorithm.as_str(),
+            limit: number,
+            primes: primes.len(),
+            duration_ns: duration.as_nanos(),
+        });
+        return Ok(());
+    }
+
+    println!(
+        "\n✅ Result: [0, {}] has {} primes",
+        number,
+        output::count(primes.len() as u64)
+    );
+    println!("⏱️  Time taken: {}", output::duration(duration));
+
+    if options.trace {
+        if number <= MAX_TRACED_NUMBER {
+            print_trace(|step| algorithm.trace(number, step));
+        } else {
+            warn!(
+                "⏭️  Not traced: --trace is limited to numbers up to {}",
+                MAX_TRACED_NUMBER
+            );
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,345 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::analysis::coverage::CandidateCoverage;
+use primality_test_comparison::analysis::density::{self, DecadeDensity};
+use primality_test_comparison::analysis::gaps::{self, GapStats};
+use primality_test_comparison::analysis::residues::{self, ResidueDistribution, SKEW_Z_SCORE};
+use primality_test_comparison::analysis::sequence::PrimeSequence;
+use primality_test_comparison::analysis::timeseries::{self, Resolution, TimeBucket};
+use primality_test_comparison::benchmark::CandidateSource;
+use primality_test_comparison::benchmark::parquet::read_from_parquet;
+use primality_test_comparison::benchmark::schema::{CANDIDATES_KEY, COVERED_KEY};
+use serde::Serialize;
+
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::OutputFormat;
+use crate::cli::resolve_result_files;
+
+// Gap sizes listed by how often they occur
+const COMMON_GAPS: usize = 10;
+
+// Misplaced primes listed before the rest are only counted
+const MISPLACED_SHOWN: usize = 10;
+
+// One file's analysis in the JSON and CSV formats
+#[derive(Serialize)]
+struct StatsRecord {
+    file: String,
+    primes: usize,
+    complete_up_to: u64,
+    complete_primes: usize,
+    duplicates: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidates: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage: Option<CandidateCoverage>,
+    density: Vec<DecadeDensity>,
+    mean_gap: Option<f64>,
+    gaps: GapStats,
+    residues: Vec<ResidueDistribution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    over_time: Option<Vec<TimeBucket>>,
+}
+
+pub fn handle_cli(
+    paths: &[PathBuf],
+    moduli: Option<&[u64]>,
+    over_time: Option<Resolution>,
+    format: OutputFormat,
+) -> Result<()> {
+    let (files, _) = resolve_result_files(paths)?;
+    let mut output = RecordWriter::new(format);
+
+    for (i, file) in files.iter().enumerate() {
+        let (results, metadata) = read_from_parquet(&file.to_string_lossy())?;
+        let mut sequence = PrimeSequence::from_results(&results);
+        let value = |key: &str| {
+            metadata
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        // A shuffled run tests its range in no order, so no prefix of it is
+        // known to be complete and the 6k ± 1 shares do not apply
+        let candidates = value(CANDIDATES_KEY);
+        let shuffled = candidates
+            .as_deref()
+            .is_some_and(|source| source.starts_with("shuffled"));
+        let coverage = if shuffled {
+            sequence.complete_up_to = 0;
+            None
+        } else {
+            // Exact where the run recorded it, past the threads' last primes
+            let recorded = value(COVERED_KEY).and_then(|covered| covered.parse().ok());
+            if let Some(covered) = recorded {
+                sequence.complete_up_to = covered;
+            }
+            let every_integer =
+                candidates.as_deref() == Some(&CandidateSource::AllIntegers.to_string());
+            Some(CandidateCoverage::from_results(
+                &results,
+                recorded,
+                every_integer,
+            ))
+        };
+        let density = density::density_by_decade(sequence.complete(), sequence.complete_up_to);
+        let gaps = gaps::gap_stats(sequence.complete());
+        let residues: Vec<ResidueDistribution> = moduli
+            .unwrap_or_default()
+            .iter()
+            .map(|&modulus| residues::residue_distribution(sequence.complete(), modulus))
+            .collect();
+        let buckets = over_time.map(|resolution| timeseries::bucket(&results, resolution));
+
+        if !output.is_table() {
+            output.write(&StatsRecord {
+                file: file.display().to_string(),
+                primes: sequence.primes.len(),
+                complete_up_to: sequence.complete_up_to,
+                complete_primes: sequence.complete().len(),
+                duplicates: sequence.duplicates,
+                candidates,
+                coverage,
+                density,
+                mean_gap: gaps.mean(),
+                gaps,
+                residues,
+                over_time: buckets,
+            });
+            continue;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("📂 {}", file.display());
+        println!(
+            "   {} primes found, complete up to {} ({} primes)",
+            output::count(sequence.primes.len() as u64),
+            sequence.complete_up_to,
+            output::count(sequence.complete().len() as u64)
+        );
+        if !sequence.duplicates.is_empty() {
+            println!(
+                "   ⚠️  {} primes recorded more than once, counted once below",
+                output::count(sequence.duplicates.len() as u64)
+            );
+        }
+
+        match &coverage {
+            Some(coverage) => print_coverage(coverage),
+            None => println!(
+                "\n🎲 Candidates: {}, so nothing is known to be complete",
+                candidates.as_deref().unwrap_or("shuffled")
+            ),
+        }
+        print_density(&density);
+        print_gaps(&gaps, sequence.complete_up_to);
+        for distribution in &residues {
+            print_residues(distribution);
+        }
+        if let (Some(resolution), Some(buckets)) = (over_time, &buckets) {
+            print_over_time(resolution, buckets);
+        }
+    }
+
+    Ok(())
+}
+
+// The run downsampled to buckets of time, one line each
+fn print_over_time(resolution: Resolution, buckets: &[TimeBucket]) {
+    println!("\n⏱️  Primes found over time ({}):", resolution);
+    println!(
+        "   {:>12} {:>12} {:>14} {:>12}  largest prime",
+        "start", "primes", "primes/s", "total"
+    );
+    for bucket in buckets {
+        println!(
+            "   {:>12} {:>12} {:>14} {:>12}  {}",
+            output::duration(Duration::from_secs_f64(bucket.start)),
+            output::count(bucket.primes),
+            output::decimal(bucket.primes_per_second(), 1),
+            output::count(bucket.cumulative),
+            bucket
+                .largest_prime
+                .map_or("-".to_string(), |n| n.to_string())
+        );
+    }
+}
+
+// What "primes found" counts: the integers the threads tested, and how far
+fn print_coverage(coverage: &CandidateCoverage) {
+    let complete = coverage.complete_up_to();
+    println!("\n🎯 Candidate coverage:");
+    let candidates = match coverage.every_integer {
+        true => "every integer from 2",
+        false => "2, 3 and every 6k ± 1",
+    };
+    println!(
+        "   Candidates: {} upward, leased in chunks by the threads ({})",
+        candidates,
+        coverage.threads()
+    );
+    println!(
+        "   Up to {}: {}, {} of {} integers ({:.1}%), {}",
+        complete,
+        candidates,
+        output::count(coverage.candidates_up_to(complete)),
+        output::count(complete),
+        match complete {
+            0 => 0.0,
+            n => 100.0 * coverage.candidates_up_to(n) as f64 / n as f64,
+        },
+        match coverage.recorded {
+            Some(_) => "as recorded by the run",
+            None => "estimated from each thread's last prime",
+        }
+    );
+
+    let reached = coverage.reached_up_to();
+    if reached > complete {
+        println!(
+            "   Up to {}: in part, by the chunks in progress when the run stopped",
+            reached
+        );
+    }
+    match coverage.every_integer {
+        true => println!("   Never tested: 0 and 1, neither of them prime"),
+        false => {
+            println!("   Never tested: 0, 1 and multiples of 2 or 3 past 3, none of them prime")
+        }
+    }
+
+    if !coverage.misplaced.is_empty() {
+        let shown: Vec<String> = coverage
+            .misplaced
+            .iter()
+            .take(MISPLACED_SHOWN)
+            .map(|n| n.to_string())
+            .collect();
+        println!(
+            "   ⚠️  {} numbers recorded that are not candidates: {}{}",
+            output::count(coverage.misplaced.len() as u64),
+            shown.join(", "),
+            if coverage.misplaced.len() > MISPLACED_SHOWN {
+                ", ..."
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+// Primes per decade against x / ln x, which runs low by about 1 / ln x: a
+// decade far off the others points at skewed candidate coverage
+fn print_density(density: &[DecadeDensity]) {
+    if density.is_empty() {
+        return;
+    }
+
+    println!("\n📈 Prime density by decade of n (vs x / ln x):");
+    for decade in density {
+        println!(
+            "   10^{:<2} {:>12} primes {:>14} predicted  ratio {:.3}{}",
+            decade.decade,
+            output::count(decade.primes),
+            output::decimal(decade.predicted, 1),
+            decade.ratio(),
+            if decade.is_partial() {
+                format!("  (up to {})", decade.end)
+            } else {
+                String::new()
+            }
+        );
+    }
+}
+
+fn print_gaps(stats: &GapStats, bound: u64) {
+    let Some(mean) = stats.mean() else {
+        println!("\n🕳️  Too few primes for gaps");
+        return;
+    };
+
+    println!("\n🕳️  Prime gaps up to {}:", bound);
+    // The prime number theorem puts the average gap near ln n
+    println!(
+        "   {} gaps, mean {:.2} (ln {} = {:.2})",
+        output::count(stats.count()),
+        mean,
+        bound,
+        (bound as f64).ln()
+    );
+
+    let common: Vec<String> = stats
+        .most_common(COMMON_GAPS)
+        .iter()
+        .map(|&(gap, count)| {
+            format!(
+                "{} ({:.1}%)",
+                gap,
+                100.0 * count as f64 / stats.count() as f64
+            )
+        })
+        .collect();
+    println!("   Most common: {}", common.join(", "));
+
+    println!("   Maximal gaps:");
+    for record in &stats.maximal {
+        println!("   {:>8} after {}", record.gap, record.prime);
+    }
+}
+
+// Coprime classes against their even share, then the primes dividing m
+fn print_residues(distribution: &ResidueDistribution) {
+    let total = distribution.coprime_primes();
+    println!(
+        "\n🔢 Primes mod {} (expected {:.2}% in each class coprime to {}):",
+        distribution.modulus,
+        100.0 * distribution.expected_share(),
+        distribution.modulus
+    );
+
+    for class in distribution.classes.iter().filter(|c| c.coprime) {
+        let share = match total {
+            0 => 0.0,
+            total => 100.0 * class.primes as f64 / total as f64,
+        };
+        let z = distribution.z_score(class);
+        println!(
+            "   {:>4} {:>12} primes {:>7.2}%  z {:>6}{}",
+            class.residue,
+            output::count(class.primes),
+            share,
+            z.map_or("-".to_string(), |z| format!("{:+.1}", z)),
+            if z.is_some_and(|z| z.abs() > SKEW_Z_SCORE) {
+                "  ⚠️"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let dividing: Vec<String> = distribution
+        .classes
+        .iter()
+        .filter(|c| !c.coprime && c.primes > 0)
+        .map(|c| c.residue.to_string())
+        .collect();
+    if !dividing.is_empty() {
+        println!(
+            "   plus {}, dividing {}",
+            dividing.join(", "),
+            distribution.modulus
+        );
+    }
+
+    if distribution.is_skewed() {
+        println!(
+            "   ⚠️  Classes more than {} standard errors off: candidates were generated unevenly",
+            SKEW_Z_SCORE
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::{PrimalityTest, Registry, miller_rabin};
+use primality_test_comparison::analysis::complexity;
+use primality_test_comparison::benchmark::Order;
+use tracing::{info, warn};
+
+use crate::cli::parsing::parse_duration;
+use crate::cli::resolve_algorithms;
+
+struct SweepResult {
+    algorithm: Arc<dyn PrimalityTest>,
+    // (magnitude, average seconds per prime)
+    points: Vec<(u64, f64)>,
+}
+
+pub fn handle_cli(
+    algorithms: &Option<Vec<String>>,
+    max_exponent: u32,
+    samples: u64,
+    time_limit_str: &str,
+    mut order: Order,
+    registry: &Registry,
+) -> Result<()> {
+    let time_limit = parse_duration(time_limit_str)?;
+    let selected = resolve_algorithms(registry, algorithms)?;
+
+    // The same primes, in the same order, are timed for every algorithm so
+    // results are comparable
+    info!("🔀 Order: {}", order);
+    let magnitudes: Vec<(u32, Vec<u64>)> = (1..=max_exponent)
+        .map(|e| {
+            let mut primes = primes_from(10u64.pow(e), samples);
+            order.arrange(&mut primes);
+            (e, primes)
+        })
+        .collect();
+
+    let results: Vec<SweepResult> = selected
+        .into_iter()
+        .map(|alg| run_sweep(alg, &magnitudes, time_limit))
+        .collect();
+
+    print_report(&results);
+
+    Ok(())
+}
+
+fn run_sweep(
+    algorithm: Arc<dyn PrimalityTest>,
+    magnitudes: &[(u32, Vec<u64>)],
+    time_limit: Duration,
+) -> SweepResult {
+    info!(
+        "📈 Sweeping '{}' across 10^1..10^{}...",
+        algorithm.name(),
+        magnitudes.len()
+    );
+
+    let mut points = Vec::new();
+
+    for (exponent, primes) in magnitudes {
+        if !primes
+            .iter()
+            .all(|p| algorithm.supported_range().contains(p))
+        {
+            warn!(
+                "   ⏭️  10^{} is outside the supported range, stopping",
+                exponent
+            );
+            break;
+        }
+
+        let start_time = Instant::now();
+        let mut timed = 0;
+
+        for &p in primes {
+            algorithm.is_prime(p);
+            timed += 1;
+
+            if start_time.elapsed() > time_limit {
+                break;
+            }
+        }
+
+        let elapsed = start_time.elapsed();
+        let average = elapsed / timed;
+        info!("   10^{:<2} {:>12.4?} per prime", exponent, average);
+        points.push((10u64.pow(*exponent), average.as_secs_f64()));
+
+        // Larger magnitudes only get slower, so stop here
+        if elapsed > time_limit {
+            info!(
+                "   ⏰ Time limit of {:?} reached, skipping larger magnitudes",
+                time_limit
+            );
+            break;
+        }
+    }
+
+    SweepResult { algorithm, points }
+}
+
+fn print_report(results: &[SweepResult]) {
+    println!("\n📊 Complexity Report:");
+
+    for result in results {
+        match complexity::best_fit(&result.points) {
+            Some(fit) => println!(
+                "   {:<22} ~ {:<12} (fit: {}, R² = {:.3})",
+                result.algorithm.name(),
+                fit.nearest_class(),
+                fit.describe(),
+                fit.r_squared
+            ),
+            None => println!(
+                "   {:<22} not enough magnitudes to fit",
+                result.algorithm.name()
+            ),
+        }
+    }
+}
+
+/// First `count` primes greater than or equal to `start`
+pub fn primes_from(start: u64, count: u64) -> Vec<u64> {
+    (start..)
+        .filter(|&n| miller_rabin::is_prime(n))
+        .take(count as usize)
+        .collect()
+}
@@ -1,34 +1,429 @@
-use strum::IntoEnumIterator;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::cli::parsing::PrimeAlgorithm;
+use num_bigint::BigUint;
+use primality_test_comparison::algorithms::lucas_lehmer::{self, LucasLehmer};
+use primality_test_comparison::algorithms::proth::Proth;
+use primality_test_comparison::algorithms::{Evidence, PrimalityTest, Registry, TestResult};
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::{info, warn};
 
-pub fn handle_cli(number: u64, algorithms: &Option<Vec<PrimeAlgorithm>>) {
-    // Run benchmark
-    if algorithms.is_none() {
-        println!("❗️ No algorithm specified. Running all algorithms.");
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::{OutputFormat, parse_duration};
+use crate::cli::{print_trace, resolve_algorithms};
 
-        for alg in PrimeAlgorithm::iter() {
-            run_test(number, alg);
-        }
+/// How the algorithms are run
+pub struct Options<'a> {
+    /// Print each algorithm's internal steps after its verdict
+    pub trace: bool,
+    /// Run the algorithms at the same time and rank them by finishing order
+    pub race: bool,
+    /// Give up on an algorithm still running after this long
+    pub timeout: Option<&'a str>,
+    /// Take the number as the exponent p of 2^p - 1 and run Lucas-Lehmer too
+    pub mersenne: bool,
+    /// Run Proth's theorem too, which the number must be of the form for
+    pub proth: bool,
+}
+
+/// Returns whether `number` is prime, once every algorithm that supports it agrees
+pub fn handle_cli(
+    number: &BigUint,
+    algorithms: &Option<Vec<String>>,
+    format: OutputFormat,
+    quiet: bool,
+    options: Options,
+    registry: &Registry,
+) -> Result<bool> {
+    let mut output = RecordWriter::new(format);
+    let mut algorithms = resolve_algorithms(registry, algorithms)?;
+    let timeout = options.timeout.map(parse_duration).transpose()?;
+
+    let mersenne;
+    let number = if options.mersenne {
+        let p = u32::try_from(number).map_err(|_| Error::InvalidNumber {
+            input: number.to_string(),
+            reason: "a Mersenne exponent must fit in a u32".to_string(),
+        })?;
+        info!("🔢 Testing the Mersenne number M{} = 2^{} - 1", p, p);
+        algorithms.insert(0, Arc::new(LucasLehmer::new(p)));
+        mersenne = lucas_lehmer::mersenne(p);
+        &mersenne
+    } else {
+        number
+    };
+
+    if options.proth {
+        let proth = Proth::new(number)?;
+        let (k, m) = proth.form();
+        info!("🔢 {} = {}·2^{} + 1 is a Proth number", number, k, m);
+        algorithms.insert(0, Arc::new(proth));
+    }
+
+    let outcomes = if options.race {
+        run_race(number, &algorithms, &mut output, timeout, quiet)
     } else {
-        for alg in algorithms.as_ref().unwrap() {
-            run_test(number, *alg);
+        algorithms
+            .into_iter()
+            .map(|alg| run_test(number, alg, &mut output, options.trace, timeout, quiet))
+            .collect()
+    };
+
+    let verdicts: Vec<bool> = outcomes
+        .iter()
+        .filter_map(|outcome| match outcome {
+            Outcome::Verdict(is_prime) => Some(*is_prime),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(algorithm) = outcomes.iter().find_map(|outcome| match outcome {
+        Outcome::Panicked(algorithm) => Some(algorithm),
+        _ => None,
+    }) {
+        return Err(Error::AlgorithmPanicked {
+            algorithm: algorithm.clone(),
+            number: number.to_string(),
+        });
+    }
+
+    match verdicts.first() {
+        None if outcomes.contains(&Outcome::TimedOut) => Err(Error::TimedOut {
+            number: number.to_string(),
+            limit: options.timeout.unwrap_or_default().to_string(),
+        }),
+        None => Err(Error::NoVerdict {
+            number: number.to_string(),
+        }),
+        Some(&is_prime) if verdicts.iter().any(|&verdict| verdict != is_prime) => {
+            Err(Error::ConflictingVerdicts {
+                number: number.to_string(),
+            })
+        }
+        Some(&is_prime) => Ok(is_prime),
+    }
+}
+
+// What came of running one algorithm
+#[derive(PartialEq)]
+enum Outcome {
+    Verdict(bool),
+    Skipped,
+    TimedOut,
+    // The name of an algorithm that panicked instead of returning
+    Panicked(String),
+}
+
+// One algorithm's verdict in the JSON and CSV formats
+#[derive(Serialize)]
+struct TestRecord<'a> {
+    number: String,
+    algorithm: &'a str,
+    is_prime: Option<bool>,
+    duration_ns: u128,
+    evidence: Option<String>,
+    // The implementation a meta-algorithm such as `auto` handed the number to
+    backend: Option<String>,
+    timed_out: bool,
+}
+
+impl<'a> TestRecord<'a> {
+    fn new(result: &'a TestResult, evidence: Option<Evidence>, backend: Option<String>) -> Self {
+        TestRecord {
+            number: result.number.to_string(),
+            algorithm: &result.algorithm,
+            is_prime: result.is_prime,
+            duration_ns: result.elapsed.as_nanos(),
+            evidence: evidence.map(|evidence| evidence.to_string()),
+            backend,
+            timed_out: false,
         }
     }
+
+    // An algorithm given up on after `limit`
+    fn timed_out(number: &BigUint, algorithm: &'a str, limit: Duration) -> Self {
+        TestRecord {
+            number: number.to_string(),
+            algorithm,
+            is_prime: None,
+            duration_ns: limit.as_nanos(),
+            evidence: None,
+            backend: None,
+            timed_out: true,
+        }
+    }
+}
+
+// An algorithm and its result, or None if it panicked
+type Finished = (Arc<dyn PrimalityTest>, Option<TestResult>);
+
+// Runs `algorithm` on a thread of its own, which reports to `sender` when it
+// finishes or panics. A thread that is given up on cannot be stopped; it
+// keeps running until it finishes or the command exits.
+fn spawn_test(
+    algorithm: &Arc<dyn PrimalityTest>,
+    number: &BigUint,
+    sender: mpsc::Sender<Finished>,
+) {
+    let algorithm = Arc::clone(algorithm);
+    let number = number.clone();
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            TestResult::run(algorithm.as_ref(), &number)
+        }))
+        .ok();
+        // Nobody listens once the algorithm timed out
+        let _ = sender.send((algorithm, result));
+    });
 }
 
-fn run_test(number: u64, algorithm: PrimeAlgorithm) {
-    println!(
+fn run_test(
+    number: &BigUint,
+    algorithm: Arc<dyn PrimalityTest>,
+    output: &mut RecordWriter,
+    trace: bool,
+    timeout: Option<Duration>,
+    quiet: bool,
+) -> Outcome {
+    info!(
         "🔍 Testing if {} is prime using '{}'...",
         number,
-        algorithm.as_str()
+        algorithm.name()
     );
 
-    let start_time = std::time::Instant::now();
-    let is_prime = algorithm.as_algorithm_fn()(number);
-    let duration = start_time.elapsed();
-    let result_str = if is_prime { "prime" } else { "composite" };
+    let result = match timeout {
+        None => match panic::catch_unwind(AssertUnwindSafe(|| {
+            TestResult::run(algorithm.as_ref(), number)
+        })) {
+            Ok(result) => result,
+            Err(_) => return panicked(algorithm.as_ref(), output, quiet),
+        },
+        Some(limit) => {
+            let (sender, receiver) = mpsc::channel();
+            spawn_test(&algorithm, number, sender);
+            match receiver.recv_timeout(limit) {
+                Ok((_, Some(result))) => result,
+                Ok((_, None)) | Err(RecvTimeoutError::Disconnected) => {
+                    return panicked(algorithm.as_ref(), output, quiet);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!("⌛ '{}' timed out after {:?}", algorithm.name(), limit);
+                    if output.is_table() && !quiet {
+                        println!("\n⌛ Result: timed out after {:?}", limit);
+                    } else if !quiet {
+                        output.write(&TestRecord::timed_out(number, algorithm.name(), limit));
+                    }
+                    return Outcome::TimedOut;
+                }
+            }
+        }
+    };
+
+    // Asked for after the timed run, and only for numbers the u64 path handles
+    let evidence = match u64::try_from(number) {
+        Ok(n) if result.is_prime == Some(false) => algorithm.evidence(n),
+        _ => None,
+    };
+
+    let backend = algorithm.backend(number);
+
+    if !output.is_table() && !quiet {
+        output.write(&TestRecord::new(&result, evidence, backend.clone()));
+    }
+
+    let Some(is_prime) = result.is_prime else {
+        warn!(
+            "⏭️  Skipped: {} is outside the supported range {:?}",
+            number,
+            algorithm.supported_range_u128()
+        );
+        return Outcome::Skipped;
+    };
 
-    println!("\n✅ Result: {} is {}", number, result_str);
-    println!("⏱️  Time taken: {:.4?}", duration);
+    if output.is_table() && !quiet {
+        println!("\n✅ Result: {} is {}", number, result.verdict());
+        if let Some(evidence) = evidence {
+            println!("🧾 Evidence: {}", evidence);
+        }
+        if let Some(backend) = backend {
+            println!("🧭 Backend: {}", backend);
+        }
+        println!("⏱️  Time taken: {}", output::duration(result.elapsed));
+        if trace {
+            print_steps(number, algorithm.as_ref());
+        }
+    }
+
+    Outcome::Verdict(is_prime)
+}
+
+// Reports an algorithm that panicked, which fails the command once every
+// algorithm has run
+fn panicked(algorithm: &dyn PrimalityTest, output: &RecordWriter, quiet: bool) -> Outcome {
+    warn!("💥 '{}' panicked", algorithm.name());
+    if output.is_table() && !quiet {
+        println!("\n💥 Result: panicked");
+    }
+    Outcome::Panicked(algorithm.name().to_string())
+}
+
+// Runs every algorithm on `number` at once, one thread each. Each one is
+// printed as it finishes, so a slow algorithm does not hold up the table.
+fn run_race(
+    number: &BigUint,
+    algorithms: &[Arc<dyn PrimalityTest>],
+    output: &mut RecordWriter,
+    timeout: Option<Duration>,
+    quiet: bool,
+) -> Vec<Outcome> {
+    info!("🏁 Racing {} algorithms on {}...", algorithms.len(), number);
+
+    let name_width = algorithms
+        .iter()
+        .map(|alg| alg.name().len())
+        .max()
+        .unwrap_or(0)
+        .max("Algorithm".len());
+    // #, algorithm, verdict, time, finished at, behind, backend
+    let row = |cells: [&str; 7]| {
+        let line = format!(
+            "   {:>2}  {:<w$}  {:<9}  {:>12}  {:>12}  {:>12}  {}",
+            cells[0],
+            cells[1],
+            cells[2],
+            cells[3],
+            cells[4],
+            cells[5],
+            cells[6],
+            w = name_width
+        );
+        println!("{}", line.trim_end());
+    };
+    if output.is_table() && !quiet {
+        println!("\n🏁 Finishing order:");
+        row([
+            "#",
+            "Algorithm",
+            "Verdict",
+            "Time",
+            "Finished at",
+            "Behind",
+            "Backend",
+        ]);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    for algorithm in algorithms {
+        spawn_test(algorithm, number, sender.clone());
+    }
+    drop(sender);
+
+    let mut outcomes = Vec::new();
+    let mut finished = Vec::new();
+    let mut winner = None;
+    loop {
+        let next = match timeout {
+            None => receiver.recv().ok(),
+            Some(limit) => receiver
+                .recv_timeout(limit.saturating_sub(start_time.elapsed()))
+                .ok(),
+        };
+        let Some((algorithm, result)) = next else {
+            break;
+        };
+        // From the start of the race, so it includes waiting for a core
+        let finished_at = start_time.elapsed();
+        finished.push(Arc::clone(&algorithm));
+
+        let Some(result) = result else {
+            warn!("💥 '{}' panicked", algorithm.name());
+            outcomes.push(Outcome::Panicked(algorithm.name().to_string()));
+            if output.is_table() && !quiet {
+                row(["-", algorithm.name(), "panicked", "", "", "", ""]);
+            }
+            continue;
+        };
+
+        let Some(is_prime) = result.is_prime else {
+            warn!(
+                "⏭️  Skipped '{}': {} is outside the supported range {:?}",
+                algorithm.name(),
+                number,
+                algorithm.supported_range_u128()
+            );
+            outcomes.push(Outcome::Skipped);
+            continue;
+        };
+        outcomes.push(Outcome::Verdict(is_prime));
+        let winner = *winner.get_or_insert(finished_at);
+        let place = outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, Outcome::Verdict(_)))
+            .count();
+
+        if quiet {
+            continue;
+        }
+        if output.is_table() {
+            let behind = match place {
+                1 => String::new(),
+                _ => format!("+{:.4?}", finished_at - winner),
+            };
+            row([
+                &place.to_string(),
+                algorithm.name(),
+                result.verdict(),
+                &format!("{:.4?}", result.elapsed),
+                &format!("{:.4?}", finished_at),
+                &behind,
+                &algorithm.backend(number).unwrap_or_default(),
+            ]);
+        } else {
+            let evidence = match u64::try_from(number) {
+                Ok(n) if !is_prime => algorithm.evidence(n),
+                _ => None,
+            };
+            let backend = algorithm.backend(number);
+            output.write(&TestRecord::new(&result, evidence, backend));
+        }
+    }
+
+    // Whatever has not finished by now ran out of time
+    for algorithm in algorithms {
+        if finished.iter().any(|done| Arc::ptr_eq(done, algorithm)) {
+            continue;
+        }
+        // Without a time limit every worker reports back, panicked or not
+        let limit = timeout.expect("every algorithm finishes without a time limit");
+        warn!("⌛ '{}' timed out after {:?}", algorithm.name(), limit);
+        outcomes.push(Outcome::TimedOut);
+
+        if quiet {
+            continue;
+        }
+        if output.is_table() {
+            row(["-", algorithm.name(), "timed out", "", "", "", ""]);
+        } else {
+            output.write(&TestRecord::timed_out(number, algorithm.name(), limit));
+        }
+    }
+
+    outcomes
+}
+
+// Traced again after the timed run, so tracing never skews the timing
+fn print_steps(number: &BigUint, algorithm: &dyn PrimalityTest) {
+    let Ok(n) = u64::try_from(number) else {
+        println!("📝 Steps: only traced for numbers below 2^64");
+        return;
+    };
+
+    if print_trace(|step| algorithm.trace(n, step)).is_none() {
+        println!("   '{}' does not trace its steps", algorithm.name());
+    }
 }
@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::time::Duration;
+
+use primality_test_comparison::Result;
+use primality_test_comparison::algorithms::Registry;
+use primality_test_comparison::algorithms::auto::Profile;
+use primality_test_comparison::benchmark::{Order, tuning};
+use tracing::info;
+
+use crate::cli::parsing::parse_duration;
+use crate::cli::resolve_algorithms;
+
+pub fn handle_cli(
+    algorithms: &Option<Vec<String>>,
+    bits: &[u32],
+    samples: u64,
+    time_limit_str: &str,
+    output: &Path,
+    order: Order,
+    registry: &Registry,
+) -> Result<()> {
+    let time_limit = parse_duration(time_limit_str)?;
+    let mut selected = resolve_algorithms(registry, algorithms)?;
+    // It would only be timed through the algorithms it dispatches to
+    if algorithms.is_none() {
+        selected.retain(|alg| alg.name() != "auto");
+    }
+
+    let mut bits = bits.to_vec();
+    bits.sort_unstable();
+    bits.dedup();
+
+    info!(
+        "🎛️  Tuning {} algorithms on {} buckets, {}...",
+        selected.len(),
+        bits.len(),
+        order
+    );
+    let profile = tuning::profile(&selected, &bits, samples, time_limit, order);
+
+    print_profile(&profile);
+
+    profile.save(output)?;
+    println!("\n💾 Profile written to {}", output.display());
+    println!(
+        "   Use it with --algo-config auto.profile={}",
+        output.display()
+    );
+
+    Ok(())
+}
+
+fn print_profile(profile: &Profile) {
+    println!("\n🎛️  Fastest per bucket:");
+    println!(
+        "   {:>4}  {:<22} {:>12}  {:<22} {:>12}",
+        "Bits", "Fastest", "Time", "Runner-up", "Time"
+    );
+
+    for bucket in &profile.buckets {
+        let cell = |rank: usize| {
+            bucket.timings.get(rank).map_or(("-", String::new()), |t| {
+                let time = Duration::from_secs_f64(t.average_nanos / 1e9);
+                (t.algorithm.as_str(), format!("{:.1?}", time))
+            })
+        };
+        let (fastest, fastest_time) = cell(0);
+        let (runner_up, runner_up_time) = cell(1);
+        let line = format!(
+            "   {:>4}  {:<22} {:>12}  {:<22} {:>12}",
+            bucket.max_bits, fastest, fastest_time, runner_up, runner_up_time
+        );
+        println!("{}", line.trim_end());
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::Path;
+use std::time::Instant;
+
+use primality_test_comparison::algorithms::Registry;
+use primality_test_comparison::algorithms::datasets::{
+    DOWNLOADS, Dataset, Download, KnownNumbers, validate,
+};
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::{DatasetName, OutputFormat, parse_duration};
+use crate::cli::resolve_algorithms;
+
+// Wrong numbers printed per dataset before the rest are only counted
+const MAX_REPORTED_FAILURES: usize = 10;
+
+// One algorithm on one dataset in the JSON and CSV formats
+#[derive(Serialize)]
+struct ValidationRecord {
+    algorithm: String,
+    dataset: Dataset,
+    source: &'static str,
+    numbers: u64,
+    checked: u64,
+    skipped: u64,
+    failures: Vec<u64>,
+    stopped_at: Option<u64>,
+    duration_ns: u128,
+}
+
+pub fn handle_cli(
+    algorithms: &Option<Vec<String>>,
+    datasets: Option<&[DatasetName]>,
+    data_dir: Option<&Path>,
+    time_limit_str: &str,
+    format: OutputFormat,
+    registry: &Registry,
+) -> Result<()> {
+    let time_limit = parse_duration(time_limit_str)?;
+    let algorithms = resolve_algorithms(registry, algorithms)?;
+    let datasets: Vec<Dataset> = match datasets {
+        Some(names) => names.iter().map(|name| name.dataset()).collect(),
+        None => Dataset::ALL.to_vec(),
+    };
+
+    let dir = data_dir.map_or_else(Dataset::default_dir, Path::to_path_buf);
+    let mut tables: Vec<KnownNumbers> = Vec::new();
+    for &dataset in &datasets {
+        let known = dataset.load(&dir)?;
+        info!(
+            "📚 Loaded '{}' from {}: {} numbers",
+            dataset.name(),
+            dir.join(dataset.file_name()).display(),
+            output::count(known.case_count())
+        );
+        tables.push(known);
+    }
+
+    // Lists downloaded with fetch-datasets extend the datasets they belong to
+    if let Some(download_dir) = Download::default_dir() {
+        for download in DOWNLOADS.iter().filter(|d| datasets.contains(&d.dataset)) {
+            if let Some(known) = download.load(&download_dir)? {
+                info!(
+                    "📚 Loaded '{}' from {}: {} numbers",
+                    download.name,
+                    download.path(&download_dir).display(),
+                    output::count(known.case_count())
+                );
+                tables.push(known);
+            }
+        }
+    }
+
+    let mut output = RecordWriter::new(format);
+    let mut failed = Vec::new();
+    for alg in &algorithms {
+        info!("🔎 Validating '{}'...", alg.name());
+
+        for known in &tables {
+            let start_time = Instant::now();
+            let validation = validate(alg.as_ref(), known, Some(time_limit));
+            let duration = start_time.elapsed();
+
+            if !validation.failures.is_empty() && !failed.contains(&alg.name()) {
+                failed.push(alg.name());
+            }
+
+            if !output.is_table() {
+                output.write(&ValidationRecord {
+                    algorithm: alg.name().to_string(),
+                    dataset: known.dataset,
+                    source: known.source,
+                    numbers: known.case_count(),
+                    checked: validation.checked,
+                    skipped: validation.skipped,
+                    failures: validation.failures,
+                    stopped_at: validation.stopped_at,
+                    duration_ns: duration.as_nanos(),
+                });
+                continue;
+            }
+
+            let name = known.source;
+            let summary = if validation.failures.is_empty() {
+                format!(
+                    "   ✅ {}: {} numbers correct",
+                    name,
+                    output::count(validation.checked)
+                )
+            } else {
+                let shown: Vec<String> = validation
+                    .failures
+                    .iter()
+                    .take(MAX_REPORTED_FAILURES)
+                    .map(u64::to_string)
+                    .collect();
+                let more = validation.failures.len() - shown.len();
+                format!(
+                    "   ❌ {}: wrong on {} of {} numbers: {}{}",
+                    name,
+                    output::count(validation.failures.len() as u64),
+                    output::count(validation.checked),
+                    shown.join(", "),
+                    if more > 0 {
+                        format!(" and {} more", output::count(more as u64))
+                    } else {
+                        String::new()
+                    }
+                )
+            };
+            println!("{} ({})", summary, output::duration(duration));
+
+            if let Some(last) = validation.stopped_at {
+                println!(
+                    "      ⏱️  Stopped at {} after {}, {} numbers left unchecked",
+                    last,
+                    time_limit_str,
+                    output::count(known.case_count() - validation.checked - validation.skipped)
+                );
+            }
+            if validation.skipped > 0 {
+                println!(
+                    "      ⏭️  {} numbers outside the supported range {:?}",
+                    output::count(validation.skipped),
+                    alg.supported_range()
+                );
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(Error::ValidationFailed {
+            algorithms: failed.join(", "),
+        });
+    }
+
+    Ok(())
+}
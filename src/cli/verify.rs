@@ -0,0 +1,227 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use primality_test_comparison::algorithms::{Evidence, PrimalityTest, Registry, miller_rabin};
+use primality_test_comparison::random::RandomSource;
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::cli::output::{self, Color, paint};
+use crate::cli::resolve_algorithms;
+
+// Mismatches printed per algorithm before the rest are only counted
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+// Column header of the reference verdicts
+const REFERENCE: &str = "reference (Miller-Rabin)";
+
+/// A number an algorithm and the reference disagree on, one JSON line of the
+/// mismatch file
+#[derive(Serialize)]
+struct Mismatch {
+    number: u64,
+    algorithm: String,
+    verdict: &'static str,
+    evidence: Option<String>,
+    reference_verdict: &'static str,
+    reference_evidence: Option<String>,
+}
+
+impl Mismatch {
+    fn new(n: u64, algorithm: &dyn PrimalityTest, is_prime: bool) -> Mismatch {
+        // Evidence only backs a composite verdict
+        let reason = |evidence: Option<Evidence>, is_prime: bool| {
+            evidence.filter(|_| !is_prime).map(|e| e.to_string())
+        };
+
+        Mismatch {
+            number: n,
+            algorithm: algorithm.name().to_string(),
+            verdict: verdict(is_prime),
+            evidence: reason(algorithm.evidence(n), is_prime),
+            reference_verdict: verdict(!is_prime),
+            reference_evidence: reason(
+                miller_rabin::evidence_with_witnesses(n, &miller_rabin::DEFAULT_WITNESSES),
+                !is_prime,
+            ),
+        }
+    }
+}
+
+// Random inputs for --fuzz-check; every algorithm sees the same sequence
+#[derive(Clone)]
+struct FuzzCheck {
+    count: u64,
+    random: RandomSource,
+}
+
+pub fn handle_cli(
+    start: u64,
+    end: u64,
+    algorithms: &Option<Vec<String>>,
+    fuzz_check: Option<u64>,
+    random: &RandomSource,
+    mismatch_file: &Path,
+    registry: &Registry,
+) -> Result<()> {
+    if start > end {
+        return Err(Error::InvalidRange { start, end });
+    }
+
+    let fuzz_check = fuzz_check.map(|count| {
+        info!(
+            "🎲 Fuzz-checking {} random numbers (seed {})",
+            count,
+            random.seed()
+        );
+        FuzzCheck {
+            count,
+            random: random.clone(),
+        }
+    });
+
+    let mut mismatches = Vec::new();
+    for alg in resolve_algorithms(registry, algorithms)? {
+        mismatches.extend(run_verify(start, end, fuzz_check.clone(), alg));
+    }
+
+    if !mismatches.is_empty() {
+        write_mismatches(mismatch_file, &mismatches)?;
+        info!("💾 Mismatches written to: {}", mismatch_file.display());
+        return Err(Error::Disagreement {
+            mismatches: mismatches.len(),
+        });
+    }
+
+    Ok(())
+}
+
+fn run_verify(
+    start: u64,
+    end: u64,
+    fuzz_check: Option<FuzzCheck>,
+    algorithm: Arc<dyn PrimalityTest>,
+) -> Vec<Mismatch> {
+    info!(
+        "🔎 Verifying '{}' against Miller-Rabin on [{}, {}]...",
+        algorithm.name(),
+        start,
+        end
+    );
+
+    // Only check the part of the range the algorithm supports
+    let supported = algorithm.supported_range();
+    let from = start.max(*supported.start());
+    let to = end.min(*supported.end());
+    if from > to {
+        warn!(
+            "⏭️  Skipped: range is outside the supported range {:?}",
+            supported
+        );
+        return Vec::new();
+    }
+
+    let numbers: Box<dyn Iterator<Item = u64>> = match fuzz_check {
+        Some(FuzzCheck { count, mut random }) => {
+            Box::new((0..count).map(move |_| random.in_range(from..=to)))
+        }
+        None => Box::new(from..=to),
+    };
+
+    let start_time = std::time::Instant::now();
+    let mut mismatches = Vec::new();
+    let mut checked: u64 = 0;
+
+    for n in numbers {
+        checked += 1;
+        let actual = algorithm.is_prime(n);
+
+        if actual != miller_rabin::is_prime(n) {
+            mismatches.push(Mismatch::new(n, algorithm.as_ref(), actual));
+        }
+    }
+
+    let duration = start_time.elapsed();
+    if mismatches.is_empty() {
+        println!("✅ All {} numbers agree", output::count(checked));
+    } else {
+        print_diff(&mismatches);
+        println!(
+            "❌ {} of {} numbers disagree",
+            output::count(mismatches.len() as u64),
+            output::count(checked)
+        );
+    }
+    println!("⏱️  Time taken: {}", output::duration(duration));
+
+    mismatches
+}
+
+// The first mismatches as a table, the algorithm's verdicts in red next to
+// the reference's in green
+fn print_diff(mismatches: &[Mismatch]) {
+    let cell = |verdict: &str, evidence: &Option<String>| match evidence {
+        Some(evidence) => format!("{} ({})", verdict, evidence),
+        None => verdict.to_string(),
+    };
+    let rows: Vec<(String, String, String)> = mismatches
+        .iter()
+        .take(MAX_REPORTED_MISMATCHES)
+        .map(|m| {
+            (
+                m.number.to_string(),
+                cell(m.verdict, &m.evidence),
+                cell(m.reference_verdict, &m.reference_evidence),
+            )
+        })
+        .collect();
+
+    let algorithm = &mismatches[0].algorithm;
+    let number_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(6);
+    let verdict_width = rows
+        .iter()
+        .map(|r| r.1.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(algorithm.chars().count());
+
+    let header = format!(
+        "{:<nw$} │ {:<vw$} │ {}",
+        "Number",
+        algorithm,
+        REFERENCE,
+        nw = number_width,
+        vw = verdict_width
+    );
+    println!("   {}", paint(&header, Color::Bold));
+    for (number, actual, expected) in &rows {
+        println!(
+            "   {:<nw$} │ {} │ {}",
+            number,
+            paint(&format!("{:<vw$}", actual, vw = verdict_width), Color::Red),
+            paint(expected, Color::Green),
+            nw = number_width
+        );
+    }
+    if mismatches.len() > rows.len() {
+        println!("   ... {} more", mismatches.len() - rows.len());
+    }
+}
+
+// One JSON object per line, every mismatch of every algorithm
+fn write_mismatches(path: &Path, mismatches: &[Mismatch]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for mismatch in mismatches {
+        let line = serde_json::to_string(mismatch).expect("mismatches always serialize");
+        writeln!(file, "{}", line)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn verdict(is_prime: bool) -> &'static str {
+    if is_prime { "prime" } else { "composite" }
+}
@@ -0,0 +1,101 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use primality_test_comparison::analysis::witnesses::{Reference, first_counterexample};
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, RecordWriter};
+use crate::cli::parsing::OutputFormat;
+
+// Time between two progress lines
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+// The check in the JSON and CSV formats
+#[derive(Serialize)]
+struct WitnessSetRecord<'a> {
+    witnesses: &'a [u64],
+    from: u64,
+    bound: u64,
+    reference: Reference,
+    counterexample: Option<u64>,
+    duration_ns: u128,
+}
+
+pub fn handle_cli(
+    witnesses: &[u64],
+    from: u64,
+    bound: u64,
+    reference: Reference,
+    threads: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    if from > bound {
+        return Err(Error::InvalidRange {
+            start: from,
+            end: bound,
+        });
+    }
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |cores| cores.get()),
+        threads => threads,
+    };
+
+    let named = witnesses
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    info!(
+        "🔍 Checking witnesses {{{}}} on every odd number in [{}, {}] against {}, {} thread(s)...",
+        named,
+        from,
+        bound,
+        reference.name(),
+        threads
+    );
+
+    let start_time = Instant::now();
+    let mut last_report = start_time;
+    let counterexample = first_counterexample(witnesses, from, bound, reference, threads, |to| {
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            let checked = (to - from) as f64 + 1.0;
+            info!(
+                "⏳ Checked up to {} ({:.1}%, {:.2e} numbers/s); resume with --from {}",
+                to,
+                100.0 * checked / ((bound - from) as f64 + 1.0),
+                checked / start_time.elapsed().as_secs_f64(),
+                to.saturating_add(1)
+            );
+        }
+    });
+    let duration = start_time.elapsed();
+
+    let mut output = RecordWriter::new(format);
+    if !output.is_table() {
+        output.write(&WitnessSetRecord {
+            witnesses,
+            from,
+            bound,
+            reference,
+            counterexample,
+            duration_ns: duration.as_nanos(),
+        });
+    } else if counterexample.is_none() {
+        println!(
+            "\n✅ Result: {{{}}} is deterministic for every odd number in [{}, {}]",
+            named, from, bound
+        );
+        println!("⏱️  Time taken: {}", output::duration(duration));
+    }
+
+    match counterexample {
+        Some(number) => Err(Error::Counterexample {
+            number,
+            witnesses: format!("{{{}}}", named),
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,161 @@
+use primality_test_comparison::analysis::witnesses::{WitnessCoverage, witness_coverage};
+use primality_test_comparison::{Error, Result};
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::output::{self, Color, RecordWriter, paint};
+use crate::cli::parsing::OutputFormat;
+
+// Composites fooling every witness listed before the rest are only counted
+const MISSED_SHOWN: usize = 10;
+
+// The whole coverage in the JSON and CSV formats
+#[derive(Serialize)]
+struct CoverageRecord<'a> {
+    #[serde(flatten)]
+    coverage: &'a WitnessCoverage,
+    duration_ns: u128,
+}
+
+pub fn handle_cli(start: u64, end: u64, bases: &[u64], format: OutputFormat) -> Result<()> {
+    if start > end {
+        return Err(Error::InvalidRange { start, end });
+    }
+
+    info!(
+        "🔍 Running {} Miller-Rabin witnesses on the odd composites in [{}, {}]...",
+        bases.len(),
+        start,
+        end
+    );
+    let start_time = std::time::Instant::now();
+    let coverage = witness_coverage(bases, start, end);
+    let duration = start_time.elapsed();
+
+    let mut output = RecordWriter::new(format);
+    if !output.is_table() {
+        output.write(&CoverageRecord {
+            coverage: &coverage,
+            duration_ns: duration.as_nanos(),
+        });
+        return Ok(());
+    }
+
+    println!(
+        "\n✅ Result: {} witnesses over the {} odd composites in [{}, {}]",
+        bases.len(),
+        output::count(coverage.odd_composites),
+        start,
+        end
+    );
+    println!("⏱️  Time taken: {}", output::duration(duration));
+    print_detections(&coverage);
+    print_matrix(&coverage);
+    print_verdict(&coverage);
+
+    Ok(())
+}
+
+// One row per witness: what it detects, what it misses, and what no other
+// witness in the list would have detected
+fn print_detections(coverage: &WitnessCoverage) {
+    let width = coverage.odd_composites.to_string().len().max(8);
+    println!(
+        "\n   {}",
+        paint(
+            &format!(
+                "{:>7} │ {:>w$} │ {:>w$} │ {}",
+                "Witness",
+                "Detected",
+                "Missed",
+                "Only detector of",
+                w = width
+            ),
+            Color::Bold
+        )
+    );
+    for (i, witness) in coverage.witnesses.iter().enumerate() {
+        println!(
+            "   {:>7} │ {:>w$} │ {:>w$} │ {}",
+            witness,
+            coverage.detected(i),
+            coverage.missed[i][i],
+            coverage.only[i],
+            w = width
+        );
+    }
+}
+
+// Composites missed by both the row and the column witness; the diagonal is
+// each witness's own misses
+fn print_matrix(coverage: &WitnessCoverage) {
+    let width = coverage
+        .witnesses
+        .iter()
+        .map(|w| w.to_string().len())
+        .chain(
+            coverage
+                .missed
+                .iter()
+                .flatten()
+                .map(|m| m.to_string().len()),
+        )
+        .max()
+        .unwrap_or(1);
+
+    println!("\n   Missed by both:");
+    let header: String = coverage
+        .witnesses
+        .iter()
+        .map(|w| format!(" {:>w$}", w, w = width))
+        .collect();
+    println!("   {:>w$} {}", "", paint(&header, Color::Bold), w = width);
+    for (witness, row) in coverage.witnesses.iter().zip(&coverage.missed) {
+        let cells: String = row
+            .iter()
+            .map(|m| format!(" {:>w$}", m, w = width))
+            .collect();
+        println!(
+            "   {} {}",
+            paint(&format!("{:>w$}", witness, w = width), Color::Bold),
+            cells
+        );
+    }
+}
+
+fn print_verdict(coverage: &WitnessCoverage) {
+    println!();
+    match coverage.prefix_needed {
+        Some(0) => println!("   The range holds no odd composite"),
+        Some(needed) => {
+            let prefix: Vec<String> = coverage.witnesses[..needed]
+                .iter()
+                .map(u64::to_string)
+                .collect();
+            println!(
+                "   ✅ The first {} witness(es) ({}) detect every composite in the range",
+                needed,
+                prefix.join(", ")
+            );
+        }
+        None => {
+            let shown: Vec<String> = coverage
+                .missed_by_all
+                .iter()
+                .take(MISSED_SHOWN)
+                .map(u64::to_string)
+                .collect();
+            let more = coverage.missed_by_all.len() - shown.len();
+            println!(
+                "   ❌ {} composite(s) fool every witness: {}{}",
+                coverage.missed_by_all.len(),
+                shown.join(", "),
+                if more > 0 {
+                    format!(" ... and {} more", more)
+                } else {
+                    String::new()
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Deterministic Miller-Rabin strong probable prime test
+
+use crate::math::{mod_pow, mod_sqr, mul_mod_u128, pow_mod_u128};
+
+/// Witnesses proven to give deterministic results for all n < 2^64
+pub const DEFAULT_WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Miller-Rabin primality test
+/// This is a probabilistic primality test, but for u64 values we use
+/// a deterministic set of witnesses that guarantees correctness.
+pub fn is_prime(n: u64) -> bool {
+    is_prime_with_witnesses(n, &DEFAULT_WITNESSES)
+}
+
+/// Miller-Rabin primality test with a custom witness set.
+/// Smaller sets are faster but may accept strong pseudoprimes.
+pub fn is_prime_with_witnesses(n: u64, witnesses: &[u64]) -> bool {
+    // Handle small cases
+    if n <= 1 {
+        return false;
+    }
+    if n <= 3 {
+        return true;
+    }
+    if n.is_multiple_of(2) || n.is_multiple_of(3) {
+        return false;
+    }
+    if n < 9 {
+        return true; // 5 and 7
+    }
+
+    find_witness(n, witnesses).is_none()
+}
+
+/// The first of `witnesses` proving the odd number `n > 3` composite, or
+/// None if `n` is a strong probable prime to all of them
+pub fn find_witness(n: u64, witnesses: &[u64]) -> Option<u64> {
+    debug_assert!(n > 3 && n % 2 == 1);
+
+    // Decompose n-1 = 2^r * d once, shared across all witnesses
+    let mut d = n - 1;
+    let mut r = 0u32;
+    while d & 1 == 0 {
+        d >>= 1;
+        r += 1;
+    }
+
+    witnesses
+        .iter()
+        .copied()
+        // Witnesses that are multiples of n carry no information
+        .filter(|witness| !witness.is_multiple_of(n))
+        .find(|&witness| !miller_rabin_test(n, witness, d, r))
+}
+
+/// Performs one round of the Miller-Rabin test with a given witness.
+/// Takes pre-computed d and r where n-1 = 2^r * d.
+#[inline]
+fn miller_rabin_test(n: u64, witness: u64, d: u64, r: u32) -> bool {
+    // Compute x = witness^d mod n
+    let mut x = mod_pow(witness, d, n);
+
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+
+    // Square x (r-1) times
+    for _ in 0..r - 1 {
+        x = mod_sqr(x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Witnesses deterministic below 4,759,123,141 (Jaeschke), so for every u32
+pub const WITNESSES_U32: [u32; 3] = [2, 7, 61];
+
+/// Miller-Rabin for 32-bit inputs with [`WITNESSES_U32`]. Products of two
+/// residues fit in a u64, so the arithmetic stays 64 bits wide where
+/// [`is_prime`] multiplies in u128.
+pub fn is_prime_u32(n: u32) -> bool {
+    if n <= 1 {
+        return false;
+    }
+    if n <= 3 {
+        return true;
+    }
+    if n.is_multiple_of(2) || n.is_multiple_of(3) {
+        return false;
+    }
+    if n < 9 {
+        return true; // 5 and 7
+    }
+
+    let r = (n - 1).trailing_zeros();
+    let d = (n - 1) >> r;
+
+    WITNESSES_U32
+        .iter()
+        .copied()
+        .filter(|witness| !witness.is_multiple_of(n))
+        .all(|witness| miller_rabin_test_u32(n, witness, d, r))
+}
+
+// One Miller-Rabin round over u32, where n-1 = 2^r * d
+fn miller_rabin_test_u32(n: u32, witness: u32, d: u32, r: u32) -> bool {
+    let m = u64::from(n);
+    let minus_one = m - 1;
+
+    // x = witness^d mod n
+    let mut base = u64::from(witness) % m;
+    let mut exp = d;
+    let mut x = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            x = x * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+
+    if x == 1 || x == minus_one {
+        return true;
+    }
+
+    for _ in 0..r - 1 {
+        x = x * x % m;
+        if x == minus_one {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Miller-Rabin over u128 with the default witnesses.
+/// Deterministic below 3.18 * 10^23 (the smallest strong pseudoprime to all
+/// 12 witnesses); above that it is a strong probable prime test.
+pub fn is_prime_u128(n: u128) -> bool {
+    is_prime_u128_with_witnesses(n, &DEFAULT_WITNESSES)
+}
+
+/// Miller-Rabin over u128 with a custom witness set.
+/// Inputs that fit in a u64 take the faster 64-bit path.
+pub fn is_prime_u128_with_witnesses(n: u128, witnesses: &[u64]) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return is_prime_with_witnesses(n, witnesses);
+    }
+    if n.is_multiple_of(2) || n.is_multiple_of(3) {
+        return false;
+    }
+
+    let mut d = n - 1;
+    let mut r = 0u32;
+    while d & 1 == 0 {
+        d >>= 1;
+        r += 1;
+    }
+
+    witnesses
+        .iter()
+        .map(|&witness| witness as u128)
+        .filter(|witness| !witness.is_multiple_of(n))
+        .all(|witness| miller_rabin_test_u128(n, witness, d, r))
+}
+
+// One Miller-Rabin round over u128, where n-1 = 2^r * d
+fn miller_rabin_test_u128(n: u128, witness: u128, d: u128, r: u32) -> bool {
+    let mut x = pow_mod_u128(witness, d, n);
+
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+
+    for _ in 0..r - 1 {
+        x = mul_mod_u128(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u128() {
+        assert!(is_prime_u128(u128::MAX - 158)); // 2^128 - 159
+        assert!(is_prime_u128((1 << 127) - 1)); // Mersenne prime
+        assert!(is_prime_u128(18446744073709551629)); // 2^64 + 13
+        assert!(!is_prime_u128(18446744073709551557 * 18446744073709551533));
+        assert!(!is_prime_u128(1 << 100));
+
+        // Agrees with the u64 path below 2^64
+        for n in 0..10_000u64 {
+            assert_eq!(is_prime_u128(n as u128), is_prime(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_u128_strong_pseudoprime() {
+        // 399165290221 × 798330580441 passes every default witness
+        assert!(is_prime_u128(318665857834031151167461));
+        assert!(!is_prime_u128_with_witnesses(
+            318665857834031151167461,
+            &[41]
+        ));
+    }
+
+    #[test]
+    fn test_u32() {
+        // Agrees with the u64 path, including on strong pseudoprimes to 2
+        // and to 2, 3, 5 and 7, and at the top of the range
+        let pseudoprimes = [2047, 1373653, 25326001, 3215031751];
+        for n in (0..10_000)
+            .chain(pseudoprimes)
+            .chain(u32::MAX - 1000..=u32::MAX)
+        {
+            assert_eq!(is_prime_u32(n), is_prime(u64::from(n)), "{n}");
+        }
+        assert!(is_prime_u32(4294967291)); // Largest u32 prime
+    }
+
+    #[test]
+    fn test_find_witness() {
+        assert_eq!(find_witness(2047, &[2, 3]), Some(3));
+        assert_eq!(find_witness(561, &DEFAULT_WITNESSES), Some(2));
+        assert_eq!(find_witness(104729, &DEFAULT_WITNESSES), None);
+    }
+
+    #[test]
+    fn test_custom_witnesses() {
+        // 2047 = 23 × 89 is the smallest strong pseudoprime to base 2
+        assert!(is_prime_with_witnesses(2047, &[2]));
+        assert!(!is_prime_with_witnesses(2047, &[2, 3]));
+        assert!(!is_prime(2047));
+
+        // Witnesses equal to n are skipped rather than rejecting n
+        assert!(is_prime_with_witnesses(37, &[37]));
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert!(!is_prime(0));
+        assert!(!is_prime(1));
+    }
+
+    #[test]
+    fn test_small_primes() {
+        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+        for &p in &primes {
+            assert!(is_prime(p));
+        }
+    }
+
+    #[test]
+    fn test_small_composites() {
+        let composites = [4, 6, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 22, 24, 25];
+        for &c in &composites {
+            assert!(!is_prime(c));
+        }
+    }
+
+    #[test]
+    fn test_larger_primes() {
+        let primes = [97, 541, 7919, 104729];
+        for &p in &primes {
+            assert!(is_prime(p));
+        }
+    }
+
+    #[test]
+    fn test_larger_composites() {
+        let composites = [100, 1000, 10000, 52939758, 1029105];
+        for &c in &composites {
+            assert!(!is_prime(c));
+        }
+    }
+
+    #[test]
+    fn test_large_primes() {
+        assert!(is_prime(2147483647)); // 2^31 - 1 (Mersenne prime)
+        assert!(is_prime(4294967291)); // Largest prime < 2^32
+    }
+
+    // https://en.wikipedia.org/wiki/Carmichael_number
+    #[test]
+    fn test_carmichael_numbers() {
+        // Carmichael numbers that fool many probabilistic tests
+        let carmichael_numbers = [
+            561,  // 3 × 11 × 17
+            1105, // 5 × 13 × 17
+            1729, // 7 × 13 × 19
+        ];
+
+        for &c in &carmichael_numbers {
+            assert!(!is_prime(c));
+        }
+    }
+}
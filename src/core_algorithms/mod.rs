@@ -0,0 +1,11 @@
+//! `no_std` core of the primality tests and sieve
+//!
+//! Pure integer code that neither allocates nor uses `std`, so it runs on
+//! microcontrollers when the crate is built with `default-features = false`
+//! (which leaves this the only module). The `algorithms` module re-exports
+//! these functions and adds the registry types and allocating APIs.
+
+pub mod miller_rabin;
+pub mod primes;
+pub mod sieve_of_eratosthenes;
+pub mod trial_division;
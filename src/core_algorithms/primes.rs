@@ -0,0 +1,118 @@
+//! Iterator over the primes in increasing order
+
+use core::iter::FusedIterator;
+
+use crate::core_algorithms::miller_rabin;
+
+/// The primes in increasing order, up to the largest prime below 2^64.
+///
+/// Candidates are 2, 3, and the numbers of the form 6k ± 1, each checked
+/// with deterministic Miller-Rabin, so the iterator needs no memory and can
+/// start anywhere.
+///
+/// ```
+/// use primality_test_comparison::Primes;
+///
+/// assert_eq!(Primes::new().take(5).collect::<Vec<_>>(), [2, 3, 5, 7, 11]);
+/// assert_eq!(Primes::new().nth(999), Some(7919));
+/// assert_eq!(Primes::new().take_while(|&p| p < 1000).count(), 168);
+/// assert_eq!(Primes::starting_at(1_000_000).next(), Some(1_000_003));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Primes {
+    // Next candidate to test, None once past u64::MAX
+    candidate: Option<u64>,
+}
+
+impl Primes {
+    /// Every prime, starting from 2
+    pub fn new() -> Self {
+        Primes { candidate: Some(2) }
+    }
+
+    /// The primes greater than or equal to `n`
+    pub fn starting_at(n: u64) -> Self {
+        let candidate = match n {
+            0..=2 => Some(2),
+            3 => Some(3),
+            4 | 5 => Some(5),
+            // Round up to the next 6k ± 1
+            _ => match n % 6 {
+                0 => n.checked_add(1),
+                1 | 5 => Some(n),
+                r => n.checked_add(5 - r),
+            },
+        };
+
+        Primes { candidate }
+    }
+}
+
+impl Default for Primes {
+    fn default() -> Self {
+        Primes::new()
+    }
+}
+
+// Next number worth testing after `n` (2, 3, then 6k ± 1)
+fn next_candidate(n: u64) -> Option<u64> {
+    match n {
+        2 => Some(3),
+        3 => Some(5),
+        _ if n % 6 == 5 => n.checked_add(2),
+        _ => n.checked_add(4),
+    }
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            let candidate = self.candidate?;
+            self.candidate = next_candidate(candidate);
+
+            if miller_rabin::is_prime(candidate) {
+                return Some(candidate);
+            }
+        }
+    }
+}
+
+impl FusedIterator for Primes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_algorithms::sieve_of_eratosthenes::{primes, sieve_into, words_for};
+
+    #[test]
+    fn test_matches_sieve() {
+        let mut bits = [0u64; words_for(100_000)];
+        sieve_into(100_000, &mut bits);
+
+        assert!(
+            Primes::new()
+                .take_while(|&p| p <= 100_000)
+                .eq(primes(&bits, 100_000))
+        );
+    }
+
+    #[test]
+    fn test_starting_at() {
+        for n in 0..1000 {
+            let expected = Primes::new().find(|&p| p >= n);
+            assert_eq!(Primes::starting_at(n).next(), expected, "{n}");
+        }
+    }
+
+    #[test]
+    fn test_end_of_range() {
+        let mut primes = Primes::starting_at(18446744073709551534);
+        assert_eq!(primes.next(), Some(18446744073709551557)); // Largest prime < 2^64
+        assert_eq!(primes.next(), None);
+        assert_eq!(primes.next(), None);
+
+        assert_eq!(Primes::starting_at(u64::MAX).next(), None);
+    }
+}
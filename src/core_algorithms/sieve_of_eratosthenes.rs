@@ -0,0 +1,79 @@
+//! Bit-packed Sieve of Eratosthenes over a caller-provided buffer
+
+/// Number of u64 words needed to sieve [0, n]
+pub const fn words_for(n: u64) -> usize {
+    (n as usize + 1).div_ceil(64)
+}
+
+#[inline]
+fn get_bit(bits: &[u64], idx: usize) -> bool {
+    (bits[idx / 64] & (1u64 << (idx % 64))) != 0
+}
+
+#[inline]
+fn clear_bit(bits: &mut [u64], idx: usize) {
+    bits[idx / 64] &= !(1u64 << (idx % 64));
+}
+
+/// Sieves [0, n] into `bits`, leaving bit i set exactly when i is prime.
+/// Each u64 holds 64 numbers, reducing memory by 8x over a bool per number.
+///
+/// # Panics
+///
+/// If `bits` holds fewer than [`words_for(n)`](words_for) words.
+pub fn sieve_into(n: u64, bits: &mut [u64]) {
+    let size = words_for(n);
+    assert!(
+        bits.len() >= size,
+        "sieving up to {} needs {} words, got {}",
+        n,
+        size,
+        bits.len()
+    );
+
+    let bits = &mut bits[..size];
+    bits.fill(!0u64); // all bits set to 1 (true)
+
+    // Clear bits for 0 and 1
+    clear_bit(bits, 0);
+    clear_bit(bits, 1);
+
+    let sqrt = n.isqrt();
+    for i in 2..=sqrt {
+        if get_bit(bits, i as usize) {
+            for j in (i * i..=n).step_by(i as usize) {
+                clear_bit(bits, j as usize);
+            }
+        }
+    }
+}
+
+/// Iterates the primes up to `n` in a buffer filled by [`sieve_into`]
+pub fn primes(bits: &[u64], n: u64) -> impl Iterator<Item = u64> + '_ {
+    (0..=n).filter(move |&i| get_bit(bits, i as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sieve_into() {
+        let mut bits = [0u64; 2];
+        sieve_into(100, &mut bits);
+        assert_eq!(primes(&bits, 100).count(), 25);
+        assert_eq!(primes(&bits, 100).last(), Some(97));
+
+        // Only the primes up to the requested bound are reported
+        assert_eq!(primes(&bits, 10).count(), 4);
+
+        sieve_into(1, &mut bits);
+        assert_eq!(primes(&bits, 1).next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_short_buffer() {
+        sieve_into(64, &mut [0u64; 1]);
+    }
+}
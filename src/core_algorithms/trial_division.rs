@@ -0,0 +1,331 @@
+//! Trial division by odd numbers, bounded by `i * i <= n`
+
+/// Returns true if `n` is prime
+pub fn is_prime(n: u64) -> bool {
+    if n <= 1 {
+        return false;
+    }
+
+    if n.is_multiple_of(2) {
+        return n == 2;
+    }
+
+    let mut i: u64 = 3;
+
+    while i * i <= n {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+
+        i += 2;
+    }
+
+    true
+}
+
+/// Trial division of a 32-bit input, dividing in 32 bits, which is cheaper
+/// than 64-bit division on most CPUs
+pub fn is_prime_u32(n: u32) -> bool {
+    if n <= 1 {
+        return false;
+    }
+
+    if n.is_multiple_of(2) {
+        return n == 2;
+    }
+
+    // i <= n / i rather than i * i <= n, which would overflow near u32::MAX
+    let mut i: u32 = 3;
+    while i <= n / i {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+
+        i += 2;
+    }
+
+    true
+}
+
+/// Smallest divisor d of `n` with 1 < d < n, or None if `n` is prime or below 2
+pub fn smallest_divisor(n: u64) -> Option<u64> {
+    if n.is_multiple_of(2) {
+        return (n > 2).then_some(2);
+    }
+
+    let mut i: u64 = 3;
+    while i <= n / i {
+        if n.is_multiple_of(i) {
+            return Some(i);
+        }
+        i += 2;
+    }
+
+    None
+}
+
+/// Wheel used to skip candidate divisors sharing a factor with its modulus
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Wheel {
+    Two,
+    Six,
+    Thirty,
+}
+
+impl Wheel {
+    pub fn from_modulus(modulus: u64) -> Option<Wheel> {
+        match modulus {
+            2 => Some(Wheel::Two),
+            6 => Some(Wheel::Six),
+            30 => Some(Wheel::Thirty),
+            _ => None,
+        }
+    }
+
+    /// Primes dividing the modulus, tested directly
+    pub fn basis(&self) -> &'static [u64] {
+        match self {
+            Wheel::Two => &[2],
+            Wheel::Six => &[2, 3],
+            Wheel::Thirty => &[2, 3, 5],
+        }
+    }
+
+    /// First candidate divisor after the basis and the gaps between candidates
+    pub fn spokes(&self) -> (u64, &'static [u64]) {
+        match self {
+            Wheel::Two => (3, &[2]),
+            Wheel::Six => (5, &[2, 4]),
+            Wheel::Thirty => (7, &[4, 2, 4, 2, 4, 6, 2, 6]),
+        }
+    }
+}
+
+/// Trial division skipping divisors that share a factor with the wheel modulus
+pub fn is_prime_with_wheel(n: u64, wheel: Wheel) -> bool {
+    if n <= 1 {
+        return false;
+    }
+
+    for &p in wheel.basis() {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let (mut i, gaps) = wheel.spokes();
+
+    for gap in gaps.iter().cycle() {
+        if i > n / i {
+            break;
+        }
+        if n.is_multiple_of(i) {
+            return false;
+        }
+
+        i += gap;
+    }
+
+    true
+}
+
+/// [`is_prime_with_wheel`] trying divisors up to `bound` only: the verdict
+/// if a divisor or √n is reached within the bound, otherwise None
+pub fn is_prime_with_wheel_bounded(n: u64, wheel: Wheel, bound: u64) -> Option<bool> {
+    if n <= 1 {
+        return Some(false);
+    }
+
+    for &p in wheel.basis() {
+        if n.is_multiple_of(p) {
+            return Some(n == p);
+        }
+    }
+
+    let (mut i, gaps) = wheel.spokes();
+
+    for gap in gaps.iter().cycle() {
+        if i > n / i {
+            return Some(true);
+        }
+        if i > bound {
+            return None;
+        }
+        if n.is_multiple_of(i) {
+            return Some(false);
+        }
+
+        i += gap;
+    }
+
+    unreachable!("the wheel cycles forever")
+}
+
+/// Trial division over u128, bounded by `i <= n / i` so it cannot overflow.
+/// Impractical far beyond 2^64, but correct for every input.
+pub fn is_prime_u128(n: u128) -> bool {
+    is_prime_with_wheel_u128(n, Wheel::Two)
+}
+
+/// [`is_prime_with_wheel`] over u128
+pub fn is_prime_with_wheel_u128(n: u128, wheel: Wheel) -> bool {
+    if n <= 1 {
+        return false;
+    }
+
+    for &p in wheel.basis() {
+        if n.is_multiple_of(p as u128) {
+            return n == p as u128;
+        }
+    }
+
+    let (start, gaps) = wheel.spokes();
+    let mut i = start as u128;
+
+    for &gap in gaps.iter().cycle() {
+        if i > n / i {
+            break;
+        }
+        if n.is_multiple_of(i) {
+            return false;
+        }
+
+        i += gap as u128;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u32() {
+        for n in (0..10_000).chain(u32::MAX - 1000..=u32::MAX) {
+            assert_eq!(is_prime_u32(n), is_prime(u64::from(n)), "{n}");
+        }
+        assert!(is_prime_u32(4294967291));
+        assert!(!is_prime_u32(65521 * 65519));
+    }
+
+    #[test]
+    fn test_smallest_divisor() {
+        assert_eq!(smallest_divisor(0), None);
+        assert_eq!(smallest_divisor(1), None);
+        assert_eq!(smallest_divisor(2), None);
+        assert_eq!(smallest_divisor(4), Some(2));
+        assert_eq!(smallest_divisor(91), Some(7));
+        assert_eq!(smallest_divisor(104729), None);
+        assert_eq!(smallest_divisor(1000003 * 999983), Some(999983));
+    }
+
+    #[test]
+    fn test_wheels_agree() {
+        for wheel in [Wheel::Two, Wheel::Six, Wheel::Thirty] {
+            for n in 0..10_000 {
+                assert_eq!(is_prime_with_wheel(n, wheel), is_prime(n), "{n}");
+            }
+            assert!(is_prime_with_wheel(4294967291, wheel));
+            assert!(!is_prime_with_wheel(1029105, wheel));
+        }
+    }
+
+    // The largest prime below (2^32 - 1)², the top of TrialDivision's range,
+    // takes i past 2^32, where i * i would overflow. No shortcut gets i there:
+    // each wheel runs about 2^32 divisions, minutes in a debug build, so these
+    // are ignored by default and run by CI's release build.
+    const RANGE_TOP_PRIME: u64 = 18446744065119617011;
+
+    #[test]
+    #[ignore = "about 2^32 divisions, run with --release"]
+    fn test_wheel_two_at_range_top() {
+        assert!(is_prime_with_wheel(RANGE_TOP_PRIME, Wheel::Two));
+    }
+
+    #[test]
+    #[ignore = "about 2^32 divisions, run with --release"]
+    fn test_wheel_six_at_range_top() {
+        assert!(is_prime_with_wheel(RANGE_TOP_PRIME, Wheel::Six));
+    }
+
+    #[test]
+    #[ignore = "about 2^32 divisions, run with --release"]
+    fn test_wheel_thirty_at_range_top() {
+        assert!(is_prime_with_wheel(RANGE_TOP_PRIME, Wheel::Thirty));
+    }
+
+    #[test]
+    fn test_bounded() {
+        for wheel in [Wheel::Two, Wheel::Six, Wheel::Thirty] {
+            assert_eq!(is_prime_with_wheel_bounded(1, wheel, 10), Some(false));
+            assert_eq!(is_prime_with_wheel_bounded(5, wheel, 10), Some(true));
+            assert_eq!(is_prime_with_wheel_bounded(91, wheel, 10), Some(false)); // 7 · 13
+            assert_eq!(is_prime_with_wheel_bounded(97, wheel, 10), Some(true)); // 11² > 97
+            assert_eq!(is_prime_with_wheel_bounded(127, wheel, 10), None); // 11² < 127
+            assert_eq!(is_prime_with_wheel_bounded(11 * 13, wheel, 10), None);
+            assert_eq!(is_prime_with_wheel_bounded(11 * 13, wheel, 11), Some(false));
+            assert_eq!(
+                is_prime_with_wheel_bounded(4294967291, wheel, u64::MAX),
+                Some(true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_u128() {
+        for n in 0..10_000u64 {
+            assert_eq!(is_prime_u128(n as u128), is_prime(n), "{n}");
+        }
+
+        // Beyond u64, with and without the largest wheel
+        let composite = 2305843009213693951u128 * 1000003; // (2^61 - 1) * 1000003
+        assert!(!is_prime_u128(composite));
+        assert!(!is_prime_with_wheel_u128(composite, Wheel::Thirty));
+        assert!(!is_prime_u128(u128::MAX));
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert!(!is_prime(0));
+        assert!(!is_prime(1));
+    }
+
+    #[test]
+    fn test_small_primes() {
+        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+        for &p in &primes {
+            assert!(is_prime(p));
+        }
+    }
+
+    #[test]
+    fn test_small_composites() {
+        let composites = [4, 6, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 22, 24, 25];
+        for &c in &composites {
+            assert!(!is_prime(c));
+        }
+    }
+
+    #[test]
+    fn test_larger_primes() {
+        let primes = [97, 541, 7919, 104729];
+        for &p in &primes {
+            assert!(is_prime(p));
+        }
+    }
+
+    #[test]
+    fn test_larger_composites() {
+        let composites = [100, 1000, 10000, 52939758, 1029105];
+        for &c in &composites {
+            assert!(!is_prime(c));
+        }
+    }
+
+    #[test]
+    fn test_large_primes() {
+        assert!(is_prime(2147483647)); // 2^31 - 1 (Mersenne prime)
+        assert!(is_prime(4294967291)); // Largest prime < 2^32
+    }
+}
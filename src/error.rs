@@ -0,0 +1,126 @@
+//! Crate-wide error type
+
+/// Everything that can go wrong while configuring or running a comparison
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid duration '{input}': {reason} (valid formats: 5s, 10m, 1h, 30sec, 2min, etc.)")]
+    InvalidDuration { input: String, reason: String },
+
+    #[error("unknown algorithm '{name}'{} (valid algorithms: {valid})", suggestion.as_ref().map(|s| format!(", did you mean '{}'?", s)).unwrap_or_default())]
+    UnknownAlgorithm {
+        name: String,
+        suggestion: Option<String>,
+        valid: String,
+    },
+
+    #[error("invalid pipeline '{spec}': {reason}")]
+    InvalidPipeline { spec: String, reason: String },
+
+    #[error("unknown algorithm config key '{key}' (valid keys: {valid})")]
+    UnknownConfigKey { key: String, valid: String },
+
+    #[error("invalid value '{value}' for '{key}'{}", hint.as_ref().map(|h| format!(" ({})", h)).unwrap_or_default())]
+    InvalidConfigValue {
+        key: String,
+        value: String,
+        hint: Option<String>,
+    },
+
+    #[error("invalid benchmark configuration: {0}")]
+    InvalidBenchmark(String),
+
+    #[error("invalid number '{input}': {reason}")]
+    InvalidNumber { input: String, reason: String },
+
+    #[error("invalid range: start ({start}) must not exceed end ({end})")]
+    InvalidRange { start: u64, end: u64 },
+
+    #[error("[{start}, {end}] contains no odd composite to sample")]
+    NoComposites { start: u64, end: u64 },
+
+    #[error("invalid modulus {modulus}: {reason}")]
+    InvalidModulus { modulus: u64, reason: String },
+
+    #[error("invalid baseline '{name}': {reason}")]
+    InvalidBaseline { name: String, reason: String },
+
+    #[error("invalid dispatch profile {path}: {reason}")]
+    InvalidProfile { path: String, reason: String },
+
+    #[error(
+        "{file} has schema version '{version}', which this build cannot read (it reads up to {supported})"
+    )]
+    UnsupportedSchema {
+        file: String,
+        version: String,
+        supported: u32,
+    },
+
+    #[error("performance regressed against the baseline for: {algorithms}")]
+    Regression { algorithms: String },
+
+    #[error("benchmark worker thread {0} panicked")]
+    WorkerPanicked(usize),
+
+    #[error("{mismatches} disagreement(s) found")]
+    Disagreement { mismatches: usize },
+
+    #[error("{missing} prime(s) missing and {duplicated} found more than once")]
+    Incomplete { missing: usize, duplicated: usize },
+
+    #[error("{number} is a strong probable prime to every witness in {witnesses} but composite")]
+    Counterexample { number: u64, witnesses: String },
+
+    #[error("M{exponent} residue {computed} does not match the expected {expected}")]
+    ResidueMismatch {
+        exponent: u32,
+        expected: String,
+        computed: String,
+    },
+
+    #[error("self-check failed for: {algorithms}")]
+    SelfCheckFailed { algorithms: String },
+
+    #[error("validation against known numbers failed for: {algorithms}")]
+    ValidationFailed { algorithms: String },
+
+    #[error("invalid dataset {path}: {reason}")]
+    InvalidDataset { path: String, reason: String },
+
+    #[error("invalid .gaps file {path}: {reason}")]
+    InvalidGapFile { path: String, reason: String },
+
+    #[error("invalid .bloom file {path}: {reason}")]
+    InvalidBloomFile { path: String, reason: String },
+
+    #[error("invalid wheel bitmap: {reason}")]
+    InvalidWheelBitmap { reason: String },
+
+    #[error("download of {url} failed: {reason}")]
+    Download { url: String, reason: String },
+
+    #[error("{number} is outside the supported range of every selected algorithm")]
+    NoVerdict { number: String },
+
+    #[error("no algorithm reached a verdict on {number} within {limit}")]
+    TimedOut { number: String, limit: String },
+
+    #[error("algorithms disagree on whether {number} is prime")]
+    ConflictingVerdicts { number: String },
+
+    #[error("'{algorithm}' panicked while testing {number}")]
+    AlgorithmPanicked { algorithm: String, number: String },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,128 @@
+//! C interface, enabled by the `ffi` feature
+//!
+//! Algorithms are addressed by their index in the built-in registry (see
+//! `ptc_algorithm_name`), so C and C++ harnesses can call the same
+//! implementations the CLI benchmarks. The matching header is
+//! `include/primality_test_comparison.h`.
+
+use std::ffi::{CString, c_char};
+use std::sync::OnceLock;
+
+use crate::algorithms::config::AlgorithmConfig;
+use crate::algorithms::{Registry, sieve_of_eratosthenes};
+
+/// Returned by `ptc_is_prime` for an id with no algorithm
+pub const PTC_UNKNOWN_ALGORITHM: i32 = -1;
+
+/// Returned by `ptc_is_prime` for an input outside the algorithm's supported range
+pub const PTC_UNSUPPORTED_INPUT: i32 = -2;
+
+// Built-in registry with default settings, plus NUL-terminated names
+fn builtins() -> &'static (Registry, Vec<CString>) {
+    static BUILTINS: OnceLock<(Registry, Vec<CString>)> = OnceLock::new();
+
+    BUILTINS.get_or_init(|| {
+        let registry = Registry::with_builtins(&AlgorithmConfig::default())
+            .expect("the default configuration is valid");
+        let names = registry
+            .names()
+            .into_iter()
+            .map(|name| CString::new(name).expect("algorithm names contain no NUL"))
+            .collect();
+
+        (registry, names)
+    })
+}
+
+/// Number of algorithms, valid ids are `0..ptc_algorithm_count()`
+#[unsafe(no_mangle)]
+pub extern "C" fn ptc_algorithm_count() -> u32 {
+    builtins().1.len() as u32
+}
+
+/// Name of the algorithm with id `algorithm_id` (e.g. "miller-rabin"), or
+/// null for an unknown id. The string is static and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn ptc_algorithm_name(algorithm_id: u32) -> *const c_char {
+    match builtins().1.get(algorithm_id as usize) {
+        Some(name) => name.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
+/// Tests `n` with the algorithm `algorithm_id`: 1 if prime, 0 if composite,
+/// or a negative `PTC_*` error code
+#[unsafe(no_mangle)]
+pub extern "C" fn ptc_is_prime(algorithm_id: u32, n: u64) -> i32 {
+    let Some(test) = builtins().0.iter().nth(algorithm_id as usize) else {
+        return PTC_UNKNOWN_ALGORITHM;
+    };
+
+    if !test.supported_range().contains(&n) {
+        return PTC_UNSUPPORTED_INPUT;
+    }
+
+    test.is_prime(n) as i32
+}
+
+/// Writes the primes up to `n` (inclusive) into `out`, at most `capacity`
+/// of them, and returns how many primes there are in total. Call with a
+/// null `out` to size the buffer first.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes of `capacity` u64 values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ptc_sieve(n: u64, out: *mut u64, capacity: usize) -> usize {
+    let primes = sieve_of_eratosthenes::sieve(n);
+
+    if !out.is_null() {
+        let count = primes.len().min(capacity);
+        // SAFETY: the caller guarantees `out` holds `capacity` >= `count` values
+        unsafe { std::ptr::copy_nonoverlapping(primes.as_ptr(), out, count) };
+    }
+
+    primes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    #[test]
+    fn test_algorithms() {
+        let count = ptc_algorithm_count();
+        assert!(count > 0);
+
+        for id in 0..count {
+            let name = unsafe { CStr::from_ptr(ptc_algorithm_name(id)) };
+            assert!(!name.is_empty());
+            assert_eq!(ptc_is_prime(id, 97), 1, "{:?}", name);
+            assert_eq!(ptc_is_prime(id, 91), 0, "{:?}", name);
+        }
+
+        assert!(ptc_algorithm_name(count).is_null());
+        assert_eq!(ptc_is_prime(count, 97), PTC_UNKNOWN_ALGORITHM);
+    }
+
+    #[test]
+    fn test_unsupported_input() {
+        let aks = (0..ptc_algorithm_count())
+            .find(|&id| unsafe { CStr::from_ptr(ptc_algorithm_name(id)) } == c"aks")
+            .unwrap();
+
+        assert_eq!(ptc_is_prime(aks, u64::MAX), PTC_UNSUPPORTED_INPUT);
+    }
+
+    #[test]
+    fn test_sieve() {
+        assert_eq!(unsafe { ptc_sieve(30, std::ptr::null_mut(), 0) }, 10);
+
+        // A short buffer is filled as far as it goes
+        let mut out = [0u64; 4];
+        assert_eq!(unsafe { ptc_sieve(30, out.as_mut_ptr(), out.len()) }, 10);
+        assert_eq!(out, [2, 3, 5, 7]);
+    }
+}
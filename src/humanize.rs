@@ -0,0 +1,233 @@
+//! Numbers and durations written for people to read
+//!
+//! Counts get thousands separators (`1,234,567`), large quantities an SI
+//! suffix (`1.23M`), and durations the units that matter at their scale
+//! (`467.0ms`, `3.52s`, `12h 3m 4s`). The separators follow a
+//! [`NumberFormat`], which [`NumberFormat::from_env`] picks from the locale
+//! the way `LC_NUMERIC` would; the C and POSIX locales keep plain digits.
+
+use std::time::Duration;
+
+/// SI prefixes from 10^3 up
+const SI_PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// How digits are grouped and fractions marked
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Separator between groups of three digits, None for plain digits
+    pub group: Option<char>,
+    /// Decimal mark
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    /// Plain digits and a decimal point, as in the C locale
+    pub const PLAIN: NumberFormat = NumberFormat {
+        group: None,
+        decimal: '.',
+    };
+
+    /// Commas between groups and a decimal point, as in English
+    pub const ENGLISH: NumberFormat = NumberFormat {
+        group: Some(','),
+        decimal: '.',
+    };
+
+    /// The format of a locale name such as `de_DE.UTF-8`, by its language
+    /// and region; languages not listed group like English
+    pub fn from_locale(locale: &str) -> NumberFormat {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        if name.is_empty() || name == "C" || name == "POSIX" {
+            return NumberFormat::PLAIN;
+        }
+
+        let (language, region) = name.split_once(['_', '-']).unwrap_or((name, ""));
+        let (group, decimal) = match (language, region) {
+            ("de" | "it" | "fr", "CH") => ('\'', '.'),
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "sl" | "hr"
+                | "sr",
+                _,
+            ) => ('.', ','),
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+                | "bg" | "lt" | "lv" | "et",
+                _,
+            ) => ('\u{202F}', ','),
+            _ => (',', '.'),
+        };
+        NumberFormat {
+            group: Some(group),
+            decimal,
+        }
+    }
+
+    /// The format of the locale in `LC_ALL`, `LC_NUMERIC` or `LANG`, the
+    /// first one set, or [`NumberFormat::PLAIN`]
+    pub fn from_env() -> NumberFormat {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|variable| std::env::var(variable).ok())
+            .find(|locale| !locale.is_empty())
+            .map_or(NumberFormat::PLAIN, |locale| {
+                NumberFormat::from_locale(&locale)
+            })
+    }
+
+    /// `n` with its digits grouped, e.g. `1,234,567`
+    pub fn integer(&self, n: u64) -> String {
+        self.group_digits(&n.to_string())
+    }
+
+    /// `x` with `precision` decimals and its integer digits grouped, e.g.
+    /// `236,980.5`
+    pub fn decimal(&self, x: f64, precision: usize) -> String {
+        let text = format!("{:.*}", precision, x.abs());
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let sign = if x.is_sign_negative() && text.bytes().any(|b| b.is_ascii_digit() && b != b'0')
+        {
+            "-"
+        } else {
+            ""
+        };
+
+        let mut result = format!("{}{}", sign, self.group_digits(whole));
+        if !fraction.is_empty() {
+            result.push(self.decimal);
+            result.push_str(fraction);
+        }
+        result
+    }
+
+    /// `x` to three significant digits with an SI suffix, e.g. `1.23M` or
+    /// `987`; whole numbers below 1000 keep their digits
+    pub fn si(&self, x: f64) -> String {
+        let mut scaled = x.abs();
+        let mut prefix = "";
+        for next in SI_PREFIXES {
+            // 999.95k rounds to 1000.0k, so it moves up already
+            if scaled < 999.95 {
+                break;
+            }
+            scaled /= 1000.0;
+            prefix = next;
+        }
+
+        let scaled = scaled.copysign(x);
+        if prefix.is_empty() && scaled.fract() == 0.0 {
+            return format!("{}", scaled);
+        }
+        let precision = match scaled.abs() {
+            v if v >= 99.95 => 0,
+            v if v >= 9.995 => 1,
+            _ => 2,
+        };
+        format!("{}{}", self.decimal(scaled, precision), prefix)
+    }
+
+    /// `duration` in the units that matter at its scale: the nearest tenth
+    /// of a unit below a second, hundredths of a second below a minute, and
+    /// whole seconds in hours and minutes beyond
+    pub fn duration(&self, duration: Duration) -> String {
+        let text = if duration.is_zero() {
+            return "0s".to_string();
+        } else if duration < Duration::from_secs(1) {
+            format!("{:.1?}", duration)
+        } else if duration < Duration::from_secs(60) {
+            format!("{:.2}s", duration.as_secs_f64())
+        } else {
+            let seconds = Duration::from_secs(duration.as_secs_f64().round() as u64);
+            return humantime::format_duration(seconds).to_string();
+        };
+        text.replace('.', &self.decimal.to_string())
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let Some(group) = self.group else {
+            return digits.to_string();
+        };
+
+        let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(group);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat::PLAIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(
+            NumberFormat::from_locale("en_US.UTF-8"),
+            NumberFormat::ENGLISH
+        );
+        assert_eq!(NumberFormat::from_locale("C.UTF-8"), NumberFormat::PLAIN);
+        assert_eq!(NumberFormat::from_locale("POSIX"), NumberFormat::PLAIN);
+        assert_eq!(NumberFormat::from_locale(""), NumberFormat::PLAIN);
+        assert_eq!(
+            NumberFormat::from_locale("de_DE.UTF-8@euro"),
+            NumberFormat {
+                group: Some('.'),
+                decimal: ','
+            }
+        );
+        assert_eq!(NumberFormat::from_locale("de_CH").group, Some('\''));
+        assert_eq!(NumberFormat::from_locale("fr-FR").group, Some('\u{202F}'));
+    }
+
+    #[test]
+    fn test_numbers() {
+        let english = NumberFormat::ENGLISH;
+        assert_eq!(english.integer(0), "0");
+        assert_eq!(english.integer(999), "999");
+        assert_eq!(english.integer(1000), "1,000");
+        assert_eq!(english.integer(1_234_567), "1,234,567");
+        assert_eq!(NumberFormat::PLAIN.integer(1_234_567), "1234567");
+
+        assert_eq!(english.decimal(236_980.54, 1), "236,980.5");
+        assert_eq!(english.decimal(-1234.5, 0), "-1,234");
+        assert_eq!(english.decimal(-0.01, 1), "0.0");
+        let german = NumberFormat::from_locale("de_DE");
+        assert_eq!(german.decimal(1_234_567.891, 2), "1.234.567,89");
+
+        assert_eq!(english.si(987.0), "987");
+        assert_eq!(english.si(12.5), "12.5");
+        assert_eq!(english.si(1234.0), "1.23k");
+        assert_eq!(english.si(1_234_567.0), "1.23M");
+        assert_eq!(english.si(45_600_000.0), "45.6M");
+        assert_eq!(english.si(999_999.0), "1.00M");
+        assert_eq!(english.si(2.5e18), "2.50E");
+        assert_eq!(german.si(1_500_000.0), "1,50M");
+    }
+
+    #[test]
+    fn test_duration() {
+        let english = NumberFormat::ENGLISH;
+        assert_eq!(english.duration(Duration::ZERO), "0s");
+        assert_eq!(english.duration(Duration::from_nanos(2_512)), "2.5µs");
+        assert_eq!(english.duration(Duration::from_micros(467_023)), "467.0ms");
+        assert_eq!(english.duration(Duration::from_millis(3_517)), "3.52s");
+        assert_eq!(english.duration(Duration::from_millis(125_600)), "2m 6s");
+        assert_eq!(
+            english.duration(Duration::from_secs(12 * 3600 + 184)),
+            "12h 3m 4s"
+        );
+        assert_eq!(
+            NumberFormat::from_locale("fr_FR").duration(Duration::from_millis(3_517)),
+            "3,52s"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Numbers typed on the command line
+//!
+//! Literals may be hexadecimal (`0x1FFFFFFFFFFFFFFF`), octal (`0o17`) or
+//! binary (`0b1011`) as well as decimal, with `_` between digits
+//! (`1_000_003`), the way other tools print large numbers. A number can also
+//! be written as an arithmetic expression such as `2^61-1`, `10^18+9` or
+//! `3*5*7+2`, since long literals are easy to mistype. Expressions use `+`,
+//! `-`, `*`, `^` (right associative, binding tightest) and parentheses over
+//! non-negative integers.
+
+use num_bigint::BigUint;
+
+use crate::error::{Error, Result};
+
+/// Results and intermediate values larger than this many bits are rejected,
+/// so a typo like `10^10^10` fails instead of exhausting memory
+pub const MAX_BITS: u64 = 1 << 16;
+
+/// [`evaluate`] for a number that must fit in a u64
+pub fn parse_u64(input: &str) -> Result<u64> {
+    u64::try_from(evaluate(input)?).map_err(|_| Error::InvalidNumber {
+        input: input.to_string(),
+        reason: "larger than 2^64 - 1".to_string(),
+    })
+}
+
+/// [`parse_u64`] for a number that may be negative: a leading `-` negates
+/// the whole expression, so `-2^62` is -(2^62)
+pub fn parse_i64(input: &str) -> Result<i64> {
+    let (negative, magnitude) = match input.trim_start().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+    let value = i128::from(parse_u64(magnitude)?);
+    i64::try_from(if negative { -value } else { value }).map_err(|_| Error::InvalidNumber {
+        input: input.to_string(),
+        reason: "outside -2^63..2^63 - 1".to_string(),
+    })
+}
+
+/// Evaluates `input`, a literal or an arithmetic expression
+pub fn evaluate(input: &str) -> Result<BigUint> {
+    let invalid = |reason: String| Error::InvalidNumber {
+        input: input.to_string(),
+        reason,
+    };
+
+    let mut parser = Parser {
+        tokens: tokenize(input).map_err(invalid)?,
+        position: 0,
+    };
+    let value = parser.expression().map_err(invalid)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(invalid(format!("unexpected '{}'", token))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigUint),
+    Operator(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Operator(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' => {
+                let mut literal = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_alphanumeric() || **d == '_') {
+                    literal.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Number(parse_literal(&literal)?));
+            }
+            '+' | '-' | '*' | '^' | '(' | ')' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            _ => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+
+    if tokens.is_empty() {
+        return Err("expected a number".to_string());
+    }
+    Ok(tokens)
+}
+
+// A decimal, 0x hexadecimal, 0o octal or 0b binary literal, with `_` allowed
+// anywhere but at the end
+fn parse_literal(literal: &str) -> std::result::Result<BigUint, String> {
+    let prefix = literal.get(..2).map(str::to_ascii_lowercase);
+    let (radix, digits) = match prefix.as_deref() {
+        Some("0x") => (16, &literal[2..]),
+        Some("0o") => (8, &literal[2..]),
+        Some("0b") => (2, &literal[2..]),
+        _ => (10, literal),
+    };
+
+    let digits_only = digits.replace('_', "");
+    if digits_only.is_empty() {
+        return Err(format!("'{}' has no digits", literal));
+    }
+    if digits.ends_with('_') {
+        return Err(format!("'{}' ends with '_'", literal));
+    }
+    BigUint::parse_bytes(digits_only.as_bytes(), radix)
+        .ok_or_else(|| format!("'{}' is not a base-{} number", literal, radix))
+}
+
+// Recursive descent over
+//   expression := term (('+' | '-') term)*
+//   term       := power ('*' power)*
+//   power      := atom ('^' power)?
+//   atom       := number | '(' expression ')'
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+type Parsed = std::result::Result<BigUint, String>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    // Consumes the next token if it is `op`
+    fn eat(&mut self, op: char) -> bool {
+        let found = self.peek() == Some(&Token::Operator(op));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expression(&mut self) -> Parsed {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value = within_bounds(value + self.term()?)?;
+            } else if self.eat('-') {
+                let rhs = self.term()?;
+                if rhs > value {
+                    return Err(format!("{} - {} is negative", value, rhs));
+                }
+                value -= rhs;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Parsed {
+        let mut value = self.power()?;
+        while self.eat('*') {
+            let rhs = self.power()?;
+            value = within_bounds(value * rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn power(&mut self) -> Parsed {
+        let base = self.atom()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+
+        let exponent = self.power()?;
+        // 0^e and 1^e never grow
+        if base.bits() <= 1 {
+            return Ok(if exponent.bits() == 0 {
+                BigUint::from(1u32)
+            } else {
+                base
+            });
+        }
+        // base^e has at least (bits - 1) * e + 1 bits, so this stops the
+        // huge ones before they are computed
+        match u32::try_from(&exponent) {
+            Ok(e) if (base.bits() - 1).saturating_mul(u64::from(e)) < MAX_BITS => {
+                within_bounds(base.pow(e))
+            }
+            _ => Err(too_large()),
+        }
+    }
+
+    fn atom(&mut self) -> Parsed {
+        match self.peek().cloned() {
+            Some(Token::Number(n)) => {
+                self.position += 1;
+                Ok(n)
+            }
+            Some(Token::Operator('(')) => {
+                self.position += 1;
+                let value = self.expression()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(value)
+            }
+            Some(token) => Err(format!("expected a number, found '{}'", token)),
+            None => Err("expected a number at the end".to_string()),
+        }
+    }
+}
+
+fn within_bounds(value: BigUint) -> Parsed {
+    if value.bits() > MAX_BITS {
+        return Err(too_large());
+    }
+    Ok(value)
+}
+
+fn too_large() -> String {
+    format!("larger than {} bits", MAX_BITS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(input: &str) -> String {
+        evaluate(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(value("982451653"), "982451653");
+        assert_eq!(value("2^61-1"), "2305843009213693951");
+        assert_eq!(value("10^18+9"), "1000000000000000009");
+        assert_eq!(value("3*5*7+2"), "107");
+        assert_eq!(value(" 2 ^ (1 + 2) * 3 "), "24");
+        // Right associative: 2^(3^2), not (2^3)^2
+        assert_eq!(value("2^3^2"), "512");
+        assert_eq!(value("2^521-1").len(), 157);
+        assert_eq!(value("0^0"), "1");
+        assert_eq!(value("1^99999999999999999999"), "1");
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(value("0x1FFFFFFFFFFFFFFF"), "2305843009213693951");
+        assert_eq!(value("0XfF"), "255");
+        assert_eq!(value("0b1011"), "11");
+        assert_eq!(value("0o17"), "15");
+        assert_eq!(value("1_000_003"), "1000003");
+        assert_eq!(value("0x_ff"), "255");
+        assert_eq!(value("0x10^2 + 0b1"), "257");
+
+        for input in ["0x", "0x_", "0b102", "1_", "_1", "0xg", "12ab"] {
+            assert!(evaluate(input).is_err(), "{:?} should be rejected", input);
+        }
+
+        assert_eq!(parse_u64("0xFFFF_FFFF_FFFF_FFFF").unwrap(), u64::MAX);
+        assert_eq!(parse_u64("10^6").unwrap(), 1_000_000);
+        assert!(matches!(
+            parse_u64("2^64"),
+            Err(Error::InvalidNumber { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_i64() {
+        assert_eq!(parse_i64("-1").unwrap(), -1);
+        // The sign applies to the whole expression
+        assert_eq!(parse_i64("- 2^3 + 1").unwrap(), -9);
+        assert_eq!(parse_i64("-2^63").unwrap(), i64::MIN);
+        assert_eq!(parse_i64("2^63-1").unwrap(), i64::MAX);
+        for input in ["2^63", "-(2^63+1)", "-2^64", "--1", "-"] {
+            assert!(parse_i64(input).is_err(), "{:?} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for input in [
+            "", "2^", "2+*3", "(2+3", "2+3)", "2-3", "1e9", "-5", "10^10^10",
+        ] {
+            assert!(
+                matches!(evaluate(input), Err(Error::InvalidNumber { .. })),
+                "{:?} should be rejected",
+                input
+            );
+        }
+        assert!(evaluate(&format!("2^{}", MAX_BITS - 1)).is_ok());
+        assert!(evaluate(&format!("2^{}", MAX_BITS)).is_err());
+        assert!(evaluate(&format!("2^{}*2", MAX_BITS - 1)).is_err());
+        assert!(evaluate("3^42000").is_err());
+    }
+}
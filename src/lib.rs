@@ -0,0 +1,48 @@
+//! # Primality Test Comparison
+//!
+//! Implementations of classical and modern primality tests and sieves, plus
+//! the parallel benchmark engine used by the `primality-test-comparison`
+//! binary to compare them.
+//!
+//! ```
+//! use primality_test_comparison::algorithms::{aks, miller_rabin, sieve_of_eratosthenes};
+//!
+//! assert!(miller_rabin::is_prime(982_451_653));
+//! assert!(!aks::is_prime(561));
+//! assert_eq!(sieve_of_eratosthenes::sieve(30).len(), 10);
+//! ```
+//!
+//! [`Primes`] streams the primes in increasing order.
+//!
+//! Without the default `std` feature the crate is `#![no_std]` and only
+//! [`core_algorithms`], [`math`] and [`Primes`] are available.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+pub mod core_algorithms;
+pub mod math;
+pub use core_algorithms::primes::Primes;
+
+#[cfg(feature = "std")]
+pub mod algorithms;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod benchmark;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod humanize;
+#[cfg(feature = "std")]
+pub mod input;
+#[cfg(feature = "std")]
+pub mod random;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use error::{Error, Result};
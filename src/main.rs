@@ -1,11 +1,52 @@
-mod algorithms;
 mod cli;
 
 use clap::Parser;
 use cli::parsing::*;
+use primality_test_comparison::algorithms::Registry;
+use primality_test_comparison::algorithms::config::AlgorithmConfig;
+use primality_test_comparison::cache::{self, SieveCache};
+use primality_test_comparison::random::RandomSource;
+use primality_test_comparison::{Error, Result};
+
+// Every allocation goes through the allocator chosen with --allocator
+#[cfg(any(feature = "mimalloc", feature = "jemalloc"))]
+#[global_allocator]
+static GLOBAL: cli::allocator::Dispatch = cli::allocator::Dispatch;
 
 fn main() {
     let cli_parsed = Cli::parse();
+    cli::logging::init(cli_parsed.verbose, cli_parsed.quiet, cli_parsed.log_format);
+
+    match run(&cli_parsed) {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            tracing::error!("⚠️ Error: {}", e);
+            std::process::exit(exit_code(&e));
+        }
+    }
+}
+
+// Exit code of a successful run: 0, or 1 when `test` finds a composite
+fn run(cli_parsed: &Cli) -> Result<i32> {
+    if let Some(allocator) = cli_parsed.allocator {
+        cli::allocator::switch_to(allocator)?;
+    }
+
+    // `cache` itself would fill the cache it inspects by building the registry
+    let use_cache = !cli_parsed.no_cache && !matches!(cli_parsed.command, Commands::Cache { .. });
+    if use_cache && let Some(dir) = SieveCache::default_dir() {
+        cache::install(SieveCache::new(dir));
+    }
+
+    let mut pairs = cli_parsed.algo_config.clone();
+    if let Some(witnesses) = &cli_parsed.witnesses {
+        pairs.push(("miller-rabin.witnesses".to_string(), witnesses.clone()));
+    }
+    let config = AlgorithmConfig::from_pairs(&pairs)?;
+    let registry = Registry::with_builtins(&config)?;
+    let random = cli_parsed
+        .seed
+        .map_or_else(RandomSource::from_clock, RandomSource::new);
 
     match &cli_parsed.command {
         Commands::Benchmark {
@@ -13,14 +54,324 @@ fn main() {
             algorithms,
             output_path,
             save,
+            dedup,
+            selfcheck,
+            cross_check,
+            verify_complete,
+            prefilter,
+            save_baseline,
+            compare_baseline,
+            regression_threshold,
+            monitor,
+            status_interval,
+            numa,
+            no_smt,
+            nice,
+            realtime,
+            all_candidates,
+            shuffle,
+            #[cfg(feature = "compact")]
+            gaps,
+            bloom,
+            bloom_fp_rate,
+            stdout,
+            #[cfg(feature = "flight")]
+            serve,
+        } => cli::benchmark::handle_cli(
+            duration,
+            algorithms,
+            output_path,
+            save,
+            cli::benchmark::Options {
+                dedup: *dedup,
+                selfcheck: *selfcheck,
+                cross_check: cross_check.as_deref(),
+                verify_complete: *verify_complete,
+                prefilter: *prefilter,
+                save_baseline: save_baseline.as_deref(),
+                compare_baseline: compare_baseline.as_deref(),
+                regression_threshold: *regression_threshold,
+                monitor: monitor.as_deref(),
+                status_interval: status_interval.as_deref(),
+                numa: *numa,
+                no_smt: *no_smt,
+                nice: *nice,
+                realtime: *realtime,
+                all_candidates: *all_candidates,
+                shuffle: shuffle.clone(),
+                random: &random,
+                #[cfg(feature = "compact")]
+                gaps: *gaps,
+                bloom: bloom.then_some(*bloom_fp_rate),
+                stdout: *stdout,
+                #[cfg(feature = "flight")]
+                serve: *serve,
+            },
+            &registry,
+            &config,
+        )?,
+        Commands::Test {
+            number,
+            algorithms,
+            json,
+            trace,
+            race,
+            timeout,
+            mersenne,
+            proth,
         } => {
-            cli::benchmark::handle_cli(duration, algorithms, output_path, save);
+            let is_prime = cli::test::handle_cli(
+                number,
+                algorithms,
+                if *json {
+                    OutputFormat::Json
+                } else {
+                    cli_parsed.format
+                },
+                cli_parsed.quiet,
+                cli::test::Options {
+                    trace: *trace,
+                    race: *race,
+                    timeout: timeout.as_deref(),
+                    mersenne: *mersenne,
+                    proth: *proth,
+                },
+                &registry,
+            )?;
+            if !is_prime {
+                return Ok(1);
+            }
         }
-        Commands::Test { number, algorithms } => {
-            cli::test::handle_cli(*number, algorithms);
+        Commands::Sieve {
+            number,
+            algorithms,
+            trace,
+            stdout,
+            #[cfg(feature = "compact")]
+            gaps,
+            bloom,
+            bloom_fp_rate,
+            wheel_bitmap,
+        } => cli::sieve::handle_cli(
+            *number,
+            algorithms,
+            cli::sieve::Options {
+                trace: *trace,
+                stdout: *stdout,
+                #[cfg(feature = "compact")]
+                gaps: gaps.clone(),
+                bloom: bloom.clone(),
+                bloom_fp_rate: *bloom_fp_rate,
+                wheel_bitmap: wheel_bitmap.clone(),
+            },
+            cli_parsed.format,
+            &config,
+        )?,
+        Commands::Primes {
+            start,
+            end,
+            #[cfg(feature = "compact")]
+            from,
+        } => cli::primes::handle_cli(
+            *start,
+            *end,
+            #[cfg(feature = "compact")]
+            from.as_deref(),
+        )?,
+        Commands::Query { file, numbers } => cli::query::handle_cli(file, numbers)?,
+        #[cfg(feature = "compact")]
+        Commands::Inspect { file, numbers } => cli::inspect::handle_cli(file, numbers)?,
+        Commands::Sweep {
+            algorithms,
+            max_exponent,
+            samples,
+            time_limit,
+            order,
+        } => cli::sweep::handle_cli(
+            algorithms,
+            *max_exponent,
+            *samples,
+            time_limit,
+            order.with_random(&random),
+            &registry,
+        )?,
+        Commands::Tune {
+            algorithms,
+            bits,
+            samples,
+            time_limit,
+            output,
+            order,
+        } => cli::tune::handle_cli(
+            algorithms,
+            bits,
+            *samples,
+            time_limit,
+            output,
+            order.with_random(&random),
+            &registry,
+        )?,
+        Commands::Compare {
+            first,
+            second,
+            exponent,
+            samples,
+            alpha,
+            order,
+        } => cli::compare::handle_cli(
+            first,
+            second,
+            cli::compare::Options {
+                exponent: *exponent,
+                samples: *samples,
+                alpha: *alpha,
+                order: order.with_random(&random),
+            },
+            cli_parsed.format,
+            &registry,
+        )?,
+        Commands::Doctor => cli::doctor::handle_cli()?,
+        Commands::ExportBundle { paths, output } => {
+            cli::export_bundle::handle_cli(paths, output.as_deref())?
+        }
+        Commands::Stats {
+            paths,
+            residues,
+            over_time,
+        } => cli::stats::handle_cli(paths, residues.as_deref(), *over_time, cli_parsed.format)?,
+        Commands::Report {
+            paths,
+            output,
+            bucket,
+        } => cli::report::handle_cli(paths, output.as_deref(), *bucket)?,
+        #[cfg(feature = "flight")]
+        Commands::Serve { paths, address } => cli::serve::handle_cli(paths, *address)?,
+        Commands::Verify {
+            start,
+            end,
+            algorithms,
+            fuzz_check,
+            mismatch_file,
+        } => cli::verify::handle_cli(
+            *start,
+            *end,
+            algorithms,
+            *fuzz_check,
+            &random,
+            mismatch_file,
+            &registry,
+        )?,
+        Commands::Selfcheck { algorithms } => cli::selfcheck::handle_cli(algorithms, &registry)?,
+        #[cfg(feature = "datasets")]
+        Commands::Validate {
+            algorithms,
+            datasets,
+            data_dir,
+            time_limit,
+        } => cli::validate::handle_cli(
+            algorithms,
+            datasets.as_deref(),
+            data_dir.as_deref(),
+            time_limit,
+            cli_parsed.format,
+            &registry,
+        )?,
+        #[cfg(feature = "fetch")]
+        Commands::FetchDatasets { names, dir, force } => {
+            cli::fetch_datasets::handle_cli(names, dir.as_deref(), *force)?
         }
-        Commands::Sieve { number, algorithms } => {
-            cli::sieve::handle_cli(*number, algorithms);
+        Commands::ErrorRate {
+            start,
+            end,
+            tests,
+            rounds,
+            samples,
+        } => cli::error_rate::handle_cli(
+            *start,
+            *end,
+            tests.as_deref(),
+            rounds,
+            *samples,
+            &random,
+            cli_parsed.format,
+        )?,
+        Commands::Pseudoprimes { limit, bases, list } => {
+            cli::pseudoprimes::handle_cli(*limit, bases, *list, cli_parsed.format)?
         }
+        Commands::Witnesses { start, end, bases } => {
+            cli::witnesses::handle_cli(*start, *end, bases, cli_parsed.format)?
+        }
+        Commands::VerifyWitnessSet {
+            witness_set,
+            bound,
+            from,
+            reference,
+            threads,
+        } => cli::verify_witness_set::handle_cli(
+            &witness_set.0,
+            *from,
+            *bound,
+            reference.reference(),
+            *threads,
+            cli_parsed.format,
+        )?,
+        Commands::LucasLehmer {
+            p,
+            checkpoint,
+            verify,
+        } => {
+            if !cli::lucas_lehmer::handle_cli(*p, *checkpoint, *verify, cli_parsed.format)? {
+                return Ok(1);
+            }
+        }
+        Commands::Analyze { number } => {
+            cli::analyze::handle_cli(number, &registry, cli_parsed.format)?
+        }
+        Commands::Jacobi { a, n } => cli::jacobi::handle_cli(*a, *n)?,
+        Commands::Lucas { p, q, k, n, all } => cli::lucas::handle_cli(*p, *q, *k, *n, *all)?,
+        Commands::Modmath { operation } => cli::modmath::handle_cli(operation)?,
+        Commands::Cache { action } => cli::cache::handle_cli(action)?,
+        Commands::List => cli::list::handle_cli(&registry)?,
+    }
+
+    Ok(0)
+}
+
+// 1: the command ran but found a problem, 2: bad input, 3: I/O or output failure,
+// 4: internal failure, 5: `test` verdicts disagree, 6: `test` timed out without a verdict
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Disagreement { .. }
+        | Error::Incomplete { .. }
+        | Error::SelfCheckFailed { .. }
+        | Error::ValidationFailed { .. }
+        | Error::Regression { .. }
+        | Error::Counterexample { .. }
+        | Error::ResidueMismatch { .. } => 1,
+        Error::InvalidDuration { .. }
+        | Error::UnknownAlgorithm { .. }
+        | Error::InvalidPipeline { .. }
+        | Error::UnknownConfigKey { .. }
+        | Error::InvalidConfigValue { .. }
+        | Error::InvalidBenchmark(_)
+        | Error::InvalidNumber { .. }
+        | Error::InvalidRange { .. }
+        | Error::NoComposites { .. }
+        | Error::InvalidModulus { .. }
+        | Error::InvalidBaseline { .. }
+        | Error::InvalidProfile { .. }
+        | Error::InvalidDataset { .. }
+        | Error::UnsupportedSchema { .. }
+        | Error::NoVerdict { .. } => 2,
+        Error::Io(_)
+        | Error::Download { .. }
+        | Error::InvalidGapFile { .. }
+        | Error::InvalidBloomFile { .. }
+        | Error::InvalidWheelBitmap { .. }
+        | Error::Arrow(_)
+        | Error::Parquet(_) => 3,
+        Error::WorkerPanicked(_) | Error::AlgorithmPanicked { .. } => 4,
+        Error::ConflictingVerdicts { .. } => 5,
+        Error::TimedOut { .. } => 6,
     }
 }
@@ -0,0 +1,102 @@
+//! Lucas sequences U_k(P, Q) and V_k(P, Q) modulo n
+//!
+//! U_0 = 0, U_1 = 1 and V_0 = 2, V_1 = P, both following
+//! X_(k+1) = P X_k - Q X_(k-1). They are the building blocks of the Lucas
+//! probable prime tests. Terms are computed in O(log k) steps from the pair
+//! (U_j, U_(j+1)), which needs no division by 2 or by D = P² - 4Q, so any
+//! modulus works, even or not coprime to D.
+
+use crate::math::mod_pow;
+
+/// U_k, V_k and Q^k modulo n, for one index k
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LucasTerms {
+    pub u: u64,
+    pub v: u64,
+    pub q_k: u64,
+}
+
+/// U_k(P, Q), V_k(P, Q) and Q^k modulo `n`
+///
+/// # Panics
+///
+/// If `n` is 0.
+pub fn lucas_sequences(p: i64, q: i64, k: u64, n: u64) -> LucasTerms {
+    assert!(n > 0, "the modulus must be positive");
+
+    let m = n as u128;
+    let reduce = |x: i64| (x as i128).rem_euclid(n as i128) as u128;
+    let (p, q) = (reduce(p), reduce(q));
+    let mul = |a: u128, b: u128| a * b % m;
+    let sub = |a: u128, b: u128| (a + m - b) % m;
+
+    // (U_j, U_(j+1)), walking j up to k along its bits from the top
+    let (mut u, mut u_next) = (0, 1 % m);
+    for bit in (0..u64::BITS - k.leading_zeros()).rev() {
+        // U_2j = U_j (2 U_(j+1) - P U_j), U_(2j+1) = U_(j+1)² - Q U_j²
+        (u, u_next) = (
+            mul(u, sub(2 * u_next % m, mul(p, u))),
+            sub(mul(u_next, u_next), mul(q, mul(u, u))),
+        );
+
+        if (k >> bit) & 1 == 1 {
+            (u, u_next) = (u_next, sub(mul(p, u_next), mul(q, u)));
+        }
+    }
+
+    LucasTerms {
+        u: u as u64,
+        // V_k = 2 U_(k+1) - P U_k
+        v: sub(2 * u_next % m, mul(p, u)) as u64,
+        q_k: mod_pow(q as u64, k, n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The first `count` terms of U and V by the recurrence, over the integers
+    fn by_recurrence(p: i64, q: i64, count: usize) -> impl Iterator<Item = (i128, i128)> {
+        let (p, q) = (p as i128, q as i128);
+        let (mut u, mut u_next, mut v, mut v_next) = (0, 1, 2, p);
+        (0..count).map(move |_| {
+            let term = (u, v);
+            (u, u_next) = (u_next, p * u_next - q * u);
+            (v, v_next) = (v_next, p * v_next - q * v);
+            term
+        })
+    }
+
+    #[test]
+    fn test_fibonacci_and_lucas_numbers() {
+        let terms = lucas_sequences(1, -1, 10, u64::MAX);
+        assert_eq!((terms.u, terms.v, terms.q_k), (55, 123, 1));
+        assert_eq!(lucas_sequences(1, -1, 0, 1000).v, 2);
+        // F_90 fits in a u64 and is not reduced
+        assert_eq!(lucas_sequences(1, -1, 90, u64::MAX).u, 2880067194370816120);
+    }
+
+    #[test]
+    fn test_agrees_with_recurrence() {
+        for (p, q) in [(1, -1), (2, -1), (3, 2), (-3, 5), (4, 4), (0, 7)] {
+            for n in [1, 2, 12, 97, 1 << 40] {
+                for (k, (u, v)) in by_recurrence(p, q, 40).enumerate() {
+                    let terms = lucas_sequences(p, q, k as u64, n);
+                    let reduce = |x: i128| x.rem_euclid(n as i128) as u64;
+                    assert_eq!(terms.u, reduce(u), "U_{k}({p}, {q}) mod {n}");
+                    assert_eq!(terms.v, reduce(v), "V_{k}({p}, {q}) mod {n}");
+                    assert_eq!(terms.q_k, reduce((q as i128).pow(k as u32)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_prime_modulus() {
+        // For a prime p with (D/p) = -1, p divides U_(p+1): here D = 5
+        for p in [7, 13, 17, 1_000_000_007] {
+            assert_eq!(lucas_sequences(1, -1, p + 1, p).u, 0);
+        }
+    }
+}
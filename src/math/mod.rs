@@ -0,0 +1,17 @@
+//! Number-theoretic helpers shared by the primality tests
+//!
+//! Like [`core_algorithms`](crate::core_algorithms) this module is `no_std`
+//! and allocation-free, so it is available with or without the `std`
+//! feature.
+
+pub mod lucas;
+pub mod modular;
+pub mod number_theory;
+pub mod roots;
+
+pub use lucas::{LucasTerms, lucas_sequences};
+pub use modular::{add_mod_u128, mod_inverse, mod_pow, mod_sqr, mul_mod_u128, pow_mod_u128};
+pub use number_theory::{
+    euler_phi, gcd, gcd_u128, jacobi, legendre, multiplicative_order, order_mod,
+};
+pub use roots::{is_perfect_power, isqrt, perfect_power, pow_checked};
@@ -0,0 +1,151 @@
+//! Modular arithmetic over u64 and u128
+
+/// Performs modular exponentiation: (base^exp) mod m
+/// Uses u128 intermediates to avoid overflow for u64 inputs.
+#[inline]
+pub fn mod_pow(base: u64, mut exp: u64, m: u64) -> u64 {
+    if m == 1 {
+        return 0;
+    }
+
+    let m128 = m as u128;
+    let mut base128 = (base % m) as u128;
+    let mut result: u128 = 1;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = (result * base128) % m128;
+        }
+        exp >>= 1;
+        base128 = (base128 * base128) % m128;
+    }
+
+    result as u64
+}
+
+/// Modular inverse: the x in [0, m) with a·x ≡ 1 (mod m), or None when
+/// gcd(a, m) ≠ 1 or m = 0. Uses the extended Euclidean algorithm.
+pub fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+
+    // Invariant: r ≡ t·a (mod m) for both (r, t) pairs
+    let (mut r0, mut r1) = (m as i128, (a % m) as i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+
+    if r0 != 1 {
+        return (m == 1).then_some(0);
+    }
+
+    Some(t0.rem_euclid(m as i128) as u64)
+}
+
+/// Squaring modulo n using u128 to avoid overflow.
+#[inline(always)]
+pub fn mod_sqr(x: u64, n: u64) -> u64 {
+    let x128 = x as u128;
+    let n128 = n as u128;
+    ((x128 * x128) % n128) as u64
+}
+
+/// (a + b) mod m for a, b < m, without overflowing u128
+#[inline]
+pub fn add_mod_u128(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+/// (a * b) mod m for any u128 modulus.
+/// Moduli below 2^64 multiply directly; larger ones fall back to
+/// double-and-add since the full product needs 256 bits.
+pub fn mul_mod_u128(a: u128, b: u128, m: u128) -> u128 {
+    let (mut a, mut b) = (a % m, b % m);
+    if m <= u64::MAX as u128 {
+        return (a * b) % m;
+    }
+
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod_u128(result, a, m);
+        }
+        a = add_mod_u128(a, a, m);
+        b >>= 1;
+    }
+
+    result
+}
+
+/// (base^exp) mod m for any u128 modulus
+pub fn pow_mod_u128(base: u128, mut exp: u128, m: u128) -> u128 {
+    if m == 1 {
+        return 0;
+    }
+
+    let mut base = base % m;
+    let mut result = 1;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod_u128(result, base, m);
+        }
+        exp >>= 1;
+        base = mul_mod_u128(base, base, m);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(2, 10, 1000), 24); // 2^10 mod 1000 = 1024 mod 1000 = 24
+        assert_eq!(mod_pow(3, 5, 13), 9); // 3^5 mod 13 = 243 mod 13 = 9
+        assert_eq!(mod_pow(7, 3, 11), 2); // 7^3 mod 11 = 343 mod 11 = 2
+        assert_eq!(mod_pow(5, 0, 1), 0);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4)); // 3 · 4 = 12 ≡ 1
+        assert_eq!(mod_inverse(10, 17), Some(12));
+        assert_eq!(mod_inverse(6, 9), None); // gcd = 3
+        assert_eq!(mod_inverse(5, 1), Some(0));
+        assert_eq!(mod_inverse(5, 0), None);
+
+        let m = 18446744073709551557; // The largest u64 prime
+        let inverse = mod_inverse(u64::MAX, m).unwrap();
+        assert_eq!((u64::MAX as u128 * inverse as u128) % m as u128, 1);
+    }
+
+    #[test]
+    fn test_mod_sqr() {
+        assert_eq!(mod_sqr(12, 7), 4);
+        assert_eq!(mod_sqr(u64::MAX - 1, u64::MAX), 1); // (-1)^2
+    }
+
+    #[test]
+    fn test_mul_mod_u128() {
+        let m = u128::MAX - 158; // 2^128 - 159, the largest u128 prime
+        assert_eq!(mul_mod_u128(m - 1, m - 1, m), 1); // (-1)^2
+        assert_eq!(mul_mod_u128(1 << 100, 1 << 28, m), 159); // 2^128
+        assert_eq!(pow_mod_u128(2, m - 1, m), 1); // Fermat
+    }
+
+    #[test]
+    fn test_add_mod_u128() {
+        let m = u128::MAX - 158;
+        assert_eq!(add_mod_u128(m - 1, m - 1, m), m - 2);
+        assert_eq!(add_mod_u128(3, 4, 10), 7);
+        assert_eq!(add_mod_u128(6, 4, 10), 0);
+    }
+}
@@ -0,0 +1,221 @@
+//! Divisibility, totients, multiplicative orders and the Jacobi and Legendre
+//! symbols
+
+use crate::math::mod_pow;
+
+/// Greatest common divisor
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let temp = b;
+        b = a % b;
+        a = temp;
+    }
+    a
+}
+
+/// [`gcd`] for 128-bit inputs
+pub fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let temp = b;
+        b = a % b;
+        a = temp;
+    }
+    a
+}
+
+/// Euler's totient function φ(n)
+pub fn euler_phi(n: u64) -> u64 {
+    let mut result = n;
+    let mut n_mut = n;
+    let mut p = 2;
+
+    while p <= n_mut / p {
+        if n_mut.is_multiple_of(p) {
+            while n_mut.is_multiple_of(p) {
+                n_mut /= p;
+            }
+            result -= result / p;
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+
+    if n_mut > 1 {
+        result -= result / n_mut;
+    }
+
+    result
+}
+
+/// Multiplicative order of n modulo r, the smallest k with n^k ≡ 1 (mod r).
+/// Returns r when n is not invertible modulo r. Intended for small moduli:
+/// r² must fit in a u64.
+pub fn multiplicative_order(n: u64, r: u64) -> u64 {
+    let n_mod = n % r;
+    let mut current = n_mod;
+
+    for k in 1..=r {
+        if current == 1 {
+            return k;
+        }
+        current = (current * n_mod) % r;
+    }
+
+    r
+}
+
+/// Multiplicative order of a modulo any m ≥ 1, or None when a is not
+/// invertible modulo m. Divides φ(m) down by its prime factors, so unlike
+/// [`multiplicative_order`] it handles every u64 modulus, in O(√m) time.
+pub fn order_mod(a: u64, m: u64) -> Option<u64> {
+    if m == 0 || gcd(a, m) != 1 {
+        return None;
+    }
+
+    let phi = euler_phi(m);
+    let mut order = phi;
+
+    // Strips each prime factor p of φ(m) while a^(order / p) ≡ 1 still holds
+    let mut reduce = |p: u64| {
+        while order.is_multiple_of(p) && mod_pow(a, order / p, m) == 1 % m {
+            order /= p;
+        }
+    };
+
+    let mut rest = phi;
+    let mut p = 2;
+    while p <= rest / p {
+        if rest.is_multiple_of(p) {
+            while rest.is_multiple_of(p) {
+                rest /= p;
+            }
+            reduce(p);
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if rest > 1 {
+        reduce(rest);
+    }
+
+    Some(order)
+}
+
+/// Jacobi symbol (a/n) for odd n
+pub fn jacobi(a: u128, n: u128) -> i32 {
+    let (mut a, mut n) = (a % n, n);
+    let mut result = 1;
+
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+
+        core::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+
+    if n == 1 { result } else { 0 }
+}
+
+/// Legendre symbol (a/p) for an odd prime p, by Euler's criterion
+/// a^((p - 1) / 2) mod p: 1 if a is a nonzero square modulo p, -1 if it is
+/// not, and 0 if p divides a. For composite p the result is meaningless;
+/// use [`jacobi`] instead.
+pub fn legendre(a: u64, p: u64) -> i32 {
+    match mod_pow(a, (p - 1) / 2, p) {
+        0 => 0,
+        1 => 1,
+        _ => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(48, 18), 6);
+        assert_eq!(gcd(101, 10), 1);
+        assert_eq!(gcd(54, 24), 6);
+        assert_eq!(gcd(17, 34), 17);
+
+        // https://en.wikipedia.org/wiki/AKS_primality_test#Example_1:_n_=_31_is_prime
+        assert_eq!(gcd(29, 31), 1);
+        assert_eq!(gcd(28, 31), 1);
+        assert_eq!(gcd(2, 31), 1);
+    }
+
+    #[test]
+    fn test_euler_phi() {
+        assert_eq!(euler_phi(1), 1);
+        assert_eq!(euler_phi(2), 1);
+        assert_eq!(euler_phi(3), 2);
+        assert_eq!(euler_phi(4), 2);
+        assert_eq!(euler_phi(5), 4);
+        assert_eq!(euler_phi(6), 2);
+        assert_eq!(euler_phi(7), 6);
+        assert_eq!(euler_phi(8), 4);
+        assert_eq!(euler_phi(9), 6);
+        assert_eq!(euler_phi(10), 4);
+    }
+
+    #[test]
+    fn test_multiplicative_order() {
+        assert_eq!(multiplicative_order(2, 7), 3); // 2^3 = 8 ≡ 1
+        assert_eq!(multiplicative_order(3, 7), 6); // 3 is a primitive root
+        assert_eq!(multiplicative_order(10, 3), 1);
+        assert_eq!(multiplicative_order(2, 4), 4); // Not invertible
+    }
+
+    #[test]
+    fn test_order_mod() {
+        for r in 2..200 {
+            for n in 0..r {
+                let expected = (gcd(n, r) == 1).then(|| multiplicative_order(n, r));
+                assert_eq!(order_mod(n, r), expected, "ord_{r}({n})");
+            }
+        }
+
+        assert_eq!(order_mod(5, 1), Some(1));
+        assert_eq!(order_mod(5, 0), None);
+
+        // 3 is a primitive root of the Fermat prime 65537, and 2 has order 32
+        assert_eq!(order_mod(3, 65537), Some(65536));
+        assert_eq!(order_mod(2, 65537), Some(32));
+        // 274177 divides 2^64 + 1, so 2^64 ≡ -1 and 2 has order 128
+        assert_eq!(order_mod(2, 274177), Some(128));
+
+        // Beyond the reach of multiplicative_order: -1 has order 2
+        let p = 4294967291;
+        assert_eq!(order_mod(p - 1, p), Some(2));
+    }
+
+    #[test]
+    fn test_jacobi() {
+        assert_eq!(jacobi(1001, 9907), -1);
+        assert_eq!(jacobi(19, 45), 1);
+        assert_eq!(jacobi(8, 21), -1);
+        assert_eq!(jacobi(5, 21), 1);
+        assert_eq!(jacobi(3, 15), 0);
+    }
+
+    #[test]
+    fn test_legendre() {
+        // The squares modulo 7 are 1, 2 and 4
+        let symbols: [i32; 7] = core::array::from_fn(|a| legendre(a as u64, 7));
+        assert_eq!(symbols, [0, 1, 1, -1, 1, -1, -1]);
+
+        // Euler's criterion agrees with the Jacobi symbol for primes
+        for p in [3, 5, 11, 9907, 18446744073709551557] {
+            for a in [0, 1, 2, 3, 1001, p - 1, p + 2, u64::MAX] {
+                assert_eq!(legendre(a, p), jacobi(a as u128, p as u128), "({a}/{p})");
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Integer roots and perfect powers
+
+// Inspired by https://www.johndcook.com/blog/2024/01/01/computing-square-root-floor/
+/// Floor of the square root of `n`, by Newton's method
+pub fn isqrt(n: u64) -> u64 {
+    let n_u128: u128 = n as u128;
+
+    let mut a: u128 = n_u128;
+    let mut b: u128 = n_u128.div_ceil(2);
+
+    while b < a {
+        a = b;
+        b = (a * a + n_u128) / (2 * a);
+    }
+
+    a as u64
+}
+
+/// Compute base^exp, or None on overflow
+pub fn pow_checked(mut base: u64, mut exp: u32) -> Option<u64> {
+    let mut result = 1u64;
+
+    while exp > 0 {
+        if exp % 2 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exp /= 2;
+        if exp > 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+
+    Some(result)
+}
+
+/// Returns true if n = a^b for some a > 1 and b > 1 (and for n = 1)
+pub fn is_perfect_power(n: u64) -> bool {
+    n == 1 || perfect_power(n).is_some()
+}
+
+/// (a, b) with n = a^b, a > 1 and the largest such b > 1, or None if `n`
+/// is not a perfect power
+pub fn perfect_power(n: u64) -> Option<(u64, u32)> {
+    if n < 4 {
+        return None;
+    }
+
+    // Check for each possible exponent b from log2(n) down to 2
+    (2..=n.ilog2()).rev().find_map(|b| {
+        let a = floor_root(n, b);
+        (pow_checked(a, b) == Some(n)).then_some((a, b))
+    })
+}
+
+// Floor of the b-th root of n >= 1, by binary search between powers of two
+fn floor_root(n: u64, b: u32) -> u64 {
+    let mut low = 1u64 << (n.ilog2() / b);
+    let mut high = low * 2;
+
+    // Invariant: low^b <= n < high^b
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        match pow_checked(mid, b) {
+            Some(power) if power <= n => low = mid,
+            _ => high = mid,
+        }
+    }
+
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(4), 2);
+        assert_eq!(isqrt(9), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(100), 10);
+        assert_eq!(isqrt(101), 10);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+    }
+
+    #[test]
+    fn test_pow_checked() {
+        assert!(pow_checked(2, 10) == Some(1024));
+        assert!(pow_checked(3, 5) == Some(243));
+        assert!(pow_checked(10, 16) == Some(10_000_000_000_000_000));
+
+        assert!(pow_checked(10, 20).is_none()); // Overflow
+        assert!(pow_checked(23, 15).is_none()); // Overflow
+        assert!(pow_checked(2, 67).is_none()); // Overflow
+    }
+
+    #[test]
+    fn test_perfect_power() {
+        assert!(is_perfect_power(4));
+        assert!(is_perfect_power(8));
+        assert!(is_perfect_power(16));
+        assert!(is_perfect_power(27));
+        assert!(is_perfect_power(64));
+        assert!(is_perfect_power(4294967291 * 4294967291));
+        assert!(is_perfect_power(1 << 63));
+        assert!(is_perfect_power(3u64.pow(40)));
+
+        assert!(!is_perfect_power(10));
+        assert!(!is_perfect_power(15));
+        assert!(!is_perfect_power(20));
+        assert!(!is_perfect_power(33));
+        assert!(!is_perfect_power(124));
+        assert!(!is_perfect_power(u64::MAX));
+    }
+
+    #[test]
+    fn test_perfect_power_decomposition() {
+        assert_eq!(perfect_power(64), Some((2, 6)));
+        assert_eq!(perfect_power(36), Some((6, 2)));
+        assert_eq!(perfect_power(3u64.pow(40)), Some((3, 40)));
+        assert_eq!(perfect_power(1), None);
+        assert_eq!(perfect_power(35), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Seedable random numbers for every randomized step
+//!
+//! Randomized steps (so far the inputs of `verify --fuzz-check`) draw from a
+//! [`RandomSource`] handed to them instead of seeding their own, so a whole
+//! run replays exactly from the one seed it reports (`--seed`). The stream
+//! is splitmix64: fast and well mixed, which is all sampling inputs needs,
+//! but not suitable for cryptography.
+
+use std::ops::RangeInclusive;
+
+/// A reproducible stream of pseudo-random numbers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomSource {
+    seed: u64,
+    state: u64,
+}
+
+impl RandomSource {
+    pub fn new(seed: u64) -> RandomSource {
+        RandomSource { seed, state: seed }
+    }
+
+    /// A source seeded from the system clock, for runs that were not given
+    /// a seed; [`seed`](Self::seed) tells how to replay them
+    pub fn from_clock() -> RandomSource {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        RandomSource::new(seed)
+    }
+
+    /// The seed the stream started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty. The modulo reduction
+    /// slightly favors the low end of ranges that do not divide 2^64.
+    pub fn in_range(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (from, to) = range.into_inner();
+        assert!(from <= to, "empty range {}..={}", from, to);
+
+        let z = self.next_u64();
+        match (to - from).checked_add(1) {
+            Some(span) => from + z % span,
+            None => z, // The whole u64 range
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let first: Vec<u64> = {
+            let mut random = RandomSource::new(42);
+            (0..5).map(|_| random.next_u64()).collect()
+        };
+        let mut random = RandomSource::new(42);
+        let second: Vec<u64> = (0..5).map(|_| random.next_u64()).collect();
+        assert_eq!(first, second);
+        assert_eq!(random.seed(), 42);
+
+        // The published splitmix64 output for seed 0
+        assert_eq!(RandomSource::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_ne!(RandomSource::new(1).next_u64(), first[0]);
+    }
+
+    #[test]
+    fn test_in_range() {
+        let mut random = RandomSource::new(7);
+        assert!((0..1000).all(|_| (10..=20).contains(&random.in_range(10..=20))));
+        assert_eq!(random.in_range(5..=5), 5);
+        random.in_range(0..=u64::MAX);
+    }
+}
@@ -0,0 +1,76 @@
+//! WebAssembly bindings, enabled by the `wasm` feature
+//!
+//! Build with
+//! `cargo build --lib --release --target wasm32-unknown-unknown --no-default-features --features wasm`
+//! and generate the JavaScript glue with `wasm-bindgen`. Numbers are passed
+//! as decimal strings so inputs beyond 2^53 survive the trip from JavaScript.
+
+use num_bigint::BigUint;
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::Registry;
+use crate::algorithms::config::AlgorithmConfig;
+use crate::error::Result;
+
+/// Tests `n` for primality with the named algorithm (e.g. "miller-rabin")
+#[wasm_bindgen]
+pub fn is_prime(algorithm: &str, n: &str) -> std::result::Result<bool, JsError> {
+    let n: BigUint = n
+        .trim()
+        .parse()
+        .map_err(|_| JsError::new(&format!("'{}' is not a non-negative integer", n)))?;
+
+    match test_with(&builtins()?, algorithm, &n)? {
+        Some(is_prime) => Ok(is_prime),
+        None => Err(JsError::new(&format!(
+            "{} is outside the range supported by '{}'",
+            n, algorithm
+        ))),
+    }
+}
+
+/// Names of the available algorithms, in registry order
+#[wasm_bindgen]
+pub fn algorithms() -> std::result::Result<Vec<String>, JsError> {
+    Ok(builtins()?.names().into_iter().map(String::from).collect())
+}
+
+fn builtins() -> Result<Registry> {
+    Registry::with_builtins(&AlgorithmConfig::default())
+}
+
+// Looks up `algorithm` and runs it, None if `n` is outside its supported range
+fn test_with(registry: &Registry, algorithm: &str, n: &BigUint) -> Result<Option<bool>> {
+    let test = registry.lookup(algorithm)?;
+
+    Ok(test.is_prime_big(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_builtins() {
+        let registry = builtins().unwrap();
+
+        assert_eq!(
+            test_with(&registry, "miller-rabin", &97u32.into()).unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            test_with(&registry, "aks", &91u32.into()).unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            test_with(
+                &registry,
+                "trial-division-sqrt",
+                &(BigUint::from(1u32) << 64)
+            )
+            .unwrap(),
+            None
+        );
+        assert!(test_with(&registry, "bogus", &97u32.into()).is_err());
+    }
+}